@@ -0,0 +1,175 @@
+//! Background fetch for Crux apps
+//!
+//! Mobile platforms periodically wake apps up in the background to let them refresh their
+//! data, giving them a limited amount of time to do so and asking them to report whether they
+//! found anything new. The shell passes the time granted to the core as a [`FetchBudget`] in an
+//! event, and the app runs its refresh work with [`BackgroundFetch::run`], which reports the
+//! [`FetchResult`] back to the shell - as [`FetchResult::Failed`] if the budget runs out first.
+
+use std::future::Future;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use crux_time::{Duration, Time};
+use futures::{future, pin_mut};
+use serde::{Deserialize, Serialize};
+
+/// The time the system granted the app for a background fetch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchBudget {
+    pub millis: u64,
+}
+
+/// The outcome of a background fetch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FetchResult {
+    NewData,
+    NoData,
+    Failed,
+}
+
+impl FetchResult {
+    /// Combine the outcomes of two refresh tasks: new data if either found any,
+    /// failed if both failed, and no data otherwise.
+    #[must_use]
+    pub fn merge(self, other: FetchResult) -> FetchResult {
+        match (self, other) {
+            (FetchResult::NewData, _) | (_, FetchResult::NewData) => FetchResult::NewData,
+            (FetchResult::Failed, FetchResult::Failed) => FetchResult::Failed,
+            _ => FetchResult::NoData,
+        }
+    }
+}
+
+/// Merges the outcomes of several refresh tasks, see [`FetchResult::merge`].
+/// No tasks at all is [`FetchResult::NoData`].
+impl FromIterator<FetchResult> for FetchResult {
+    fn from_iter<I: IntoIterator<Item = FetchResult>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+
+        match iter.next() {
+            Some(first) => iter.fold(first, FetchResult::merge),
+            None => FetchResult::NoData,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackgroundFetchOperation {
+    /// The background fetch has finished, the shell should report the result to the system
+    Complete(FetchResult),
+}
+
+impl Operation for BackgroundFetchOperation {
+    type Output = ();
+}
+
+/// The BackgroundFetch capability API
+///
+/// This capability runs the app's refresh work within the budget granted by the system,
+/// and reports the outcome to the shell.
+#[derive(crux_core::macros::Capability)]
+pub struct BackgroundFetch<Ev> {
+    context: CapabilityContext<BackgroundFetchOperation, Ev>,
+}
+
+impl<Ev> Clone for BackgroundFetch<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> BackgroundFetch<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<BackgroundFetchOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Run the `refresh` future, using the other capabilities' async APIs, and report its
+    /// result to the shell. If the `budget` runs out before `refresh` finishes, it is dropped
+    /// and the fetch is reported as failed. The `time` capability is used to track the budget.
+    pub fn run<Fut, TimeEv>(&self, budget: FetchBudget, time: &Time<TimeEv>, refresh: Fut)
+    where
+        Fut: Future<Output = FetchResult> + Send + 'static,
+        TimeEv: 'static,
+    {
+        self.context.spawn({
+            let this = self.clone();
+            let time = time.clone();
+
+            async move {
+                this.run_async(budget, &time, refresh).await;
+            }
+        });
+    }
+
+    /// Run the `refresh` future and report its result to the shell, see [`BackgroundFetch::run`].
+    /// Returns the reported result.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn run_async<Fut, TimeEv>(
+        &self,
+        budget: FetchBudget,
+        time: &Time<TimeEv>,
+        refresh: Fut,
+    ) -> FetchResult
+    where
+        Fut: Future<Output = FetchResult>,
+        TimeEv: 'static,
+    {
+        let budget = Duration::from_millis(budget.millis).unwrap_or(Duration::new(u64::MAX));
+        let deadline = time.notify_after_async(budget);
+        pin_mut!(refresh, deadline);
+
+        let result = match future::select(refresh, deadline).await {
+            future::Either::Left((result, _)) => result,
+            future::Either::Right(_) => FetchResult::Failed,
+        };
+
+        self.context
+            .notify_shell(BackgroundFetchOperation::Complete(result))
+            .await;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let serialized =
+            serde_json::to_string(&BackgroundFetchOperation::Complete(FetchResult::NewData))
+                .unwrap();
+        assert_eq!(&serialized, r#"{"complete":"newData"}"#);
+
+        let budget: FetchBudget = serde_json::from_str(r#"{"millis":25000}"#).unwrap();
+        assert_eq!(budget, FetchBudget { millis: 25_000 });
+    }
+
+    #[test]
+    fn merging_results() {
+        use FetchResult::{Failed, NewData, NoData};
+
+        assert_eq!(
+            [Failed, NewData].into_iter().collect::<FetchResult>(),
+            NewData
+        );
+        assert_eq!(
+            [Failed, NoData].into_iter().collect::<FetchResult>(),
+            NoData
+        );
+        assert_eq!(
+            [Failed, Failed].into_iter().collect::<FetchResult>(),
+            Failed
+        );
+        assert_eq!([].into_iter().collect::<FetchResult>(), NoData);
+    }
+}
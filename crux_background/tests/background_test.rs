@@ -0,0 +1,199 @@
+mod shared {
+    use crux_background::{BackgroundFetch, FetchBudget, FetchResult};
+    use crux_core::macros::Effect;
+    use crux_kv::KeyValue;
+    use crux_time::Time;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        BackgroundFetch(FetchBudget),
+    }
+
+    #[derive(Default)]
+    pub struct Model;
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, _model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::BackgroundFetch(budget) => {
+                    let key_value = caps.key_value.clone();
+
+                    // move downloaded messages into the inbox
+                    caps.background_fetch.run(budget, &caps.time, async move {
+                        let Ok(downloaded) = key_value.get_async("downloads".to_string()).await
+                        else {
+                            return FetchResult::Failed;
+                        };
+                        if downloaded.is_empty() {
+                            return FetchResult::NoData;
+                        }
+
+                        match key_value.set_async("inbox".to_string(), downloaded).await {
+                            Ok(_) => FetchResult::NewData,
+                            Err(_) => FetchResult::Failed,
+                        }
+                    });
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub background_fetch: BackgroundFetch<Event>,
+        pub key_value: KeyValue<Event>,
+        pub time: Time<Event>,
+    }
+}
+
+mod tests {
+    use crux_background::{BackgroundFetchOperation, FetchBudget, FetchResult};
+    use crux_core::testing::AppTester;
+    use crux_kv::{KeyValueOperation, KeyValueResponse, KeyValueResult, WriteMode};
+    use crux_time::{Duration, TimeRequest, TimeResponse};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn budget() -> Event {
+        Event::BackgroundFetch(FetchBudget { millis: 25_000 })
+    }
+
+    fn completed(effects: impl Iterator<Item = Effect>) -> Vec<FetchResult> {
+        effects
+            .filter_map(|effect| match effect {
+                Effect::BackgroundFetch(request) => {
+                    let BackgroundFetchOperation::Complete(result) = request.operation;
+                    Some(result)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_new_data() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model;
+
+        let mut update = app.update(budget(), &mut model);
+
+        let Some(Effect::Time(deadline)) = update.effects.pop() else {
+            panic!("expected a time effect");
+        };
+        let Some(Effect::KeyValue(mut get)) = update.effects.pop() else {
+            panic!("expected a key-value effect");
+        };
+        assert_eq!(
+            deadline.operation,
+            TimeRequest::NotifyAfter(Duration::from_secs(25).unwrap())
+        );
+
+        let mut update = app
+            .resolve(
+                &mut get,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Get {
+                        value: b"hello".to_vec(),
+                        version: 1,
+                    },
+                },
+            )
+            .unwrap();
+
+        let Some(Effect::KeyValue(mut set)) = update.effects.pop() else {
+            panic!("expected a key-value effect");
+        };
+        assert_eq!(
+            set.operation,
+            KeyValueOperation::Set {
+                key: "inbox".to_string(),
+                value: b"hello".to_vec(),
+                mode: WriteMode::Overwrite,
+                idempotency_key: set.operation.idempotency_key().cloned(),
+            }
+        );
+
+        let update = app
+            .resolve(
+                &mut set,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Set {
+                        previous: vec![],
+                        version: 1,
+                    },
+                },
+            )
+            .unwrap();
+
+        assert_eq!(completed(update.into_effects()), vec![FetchResult::NewData]);
+    }
+
+    #[test]
+    fn reports_no_data() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model;
+
+        let mut update = app.update(budget(), &mut model);
+        let Effect::KeyValue(mut get) = update.effects.remove(0) else {
+            panic!("expected a key-value effect");
+        };
+
+        let update = app
+            .resolve(
+                &mut get,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Get {
+                        value: vec![],
+                        version: 0,
+                    },
+                },
+            )
+            .unwrap();
+
+        assert_eq!(completed(update.into_effects()), vec![FetchResult::NoData]);
+    }
+
+    #[test]
+    fn fails_when_the_budget_runs_out() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model;
+
+        let mut update = app.update(budget(), &mut model);
+        let Some(Effect::Time(mut deadline)) = update.effects.pop() else {
+            panic!("expected a time effect");
+        };
+        let Some(Effect::KeyValue(mut get)) = update.effects.pop() else {
+            panic!("expected a key-value effect");
+        };
+
+        let update = app
+            .resolve(&mut deadline, TimeResponse::DurationElapsed)
+            .unwrap();
+        assert_eq!(completed(update.into_effects()), vec![FetchResult::Failed]);
+
+        // the refresh was abandoned, a late response doesn't report again
+        let update = app
+            .resolve(
+                &mut get,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Get {
+                        value: b"late".to_vec(),
+                        version: 1,
+                    },
+                },
+            )
+            .unwrap();
+        assert!(completed(update.into_effects()).is_empty());
+    }
+}
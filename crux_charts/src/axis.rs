@@ -0,0 +1,391 @@
+//! Axis ranges and ticks, see [`Axis`]
+
+use crate::Point;
+
+/// An interval of values, from `min` to `max`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Range {
+    pub fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest range including all the finite `values`, if there are any
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Option<Range> {
+        values.into_iter().filter(|value| value.is_finite()).fold(
+            None,
+            |range: Option<Range>, value| {
+                Some(range.map_or(Range::new(value, value), |range| range.include(value)))
+            },
+        )
+    }
+
+    /// The ranges of the `x` and `y` coordinates of the `points`
+    pub fn of_points(points: &[Point]) -> Option<(Range, Range)> {
+        let finite = points.iter().filter(|point| point.is_finite());
+
+        Range::of(finite.clone().map(|point| point.x)).zip(Range::of(finite.map(|point| point.y)))
+    }
+
+    /// The range extended to include the `value`
+    #[must_use]
+    pub fn include(self, value: f64) -> Range {
+        Range::new(self.min.min(value), self.max.max(value))
+    }
+
+    /// The range extended to include the `other` range
+    #[must_use]
+    pub fn union(self, other: Range) -> Range {
+        self.include(other.min).include(other.max)
+    }
+
+    pub fn span(&self) -> f64 {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.min <= value && value <= self.max
+    }
+
+    /// Where the `value` is in the range, from 0 at `min` to 1 at `max`
+    pub fn fraction(&self, value: f64) -> f64 {
+        if self.span() == 0.0 {
+            0.5
+        } else {
+            (value - self.min) / self.span()
+        }
+    }
+}
+
+/// What the values on an axis are
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scale {
+    /// Numbers, with the range extended to the nearest ticks
+    #[default]
+    Linear,
+    /// Times in milliseconds since the Unix epoch, with ticks at whole seconds, minutes, hours
+    /// or days (in UTC), labelled with the time or the date. The range isn't extended, so the
+    /// series start and end at the edges of the chart.
+    Time,
+}
+
+/// A labelled value on an axis
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tick {
+    pub value: f64,
+    pub label: String,
+}
+
+/// The range of an axis, and the ticks along it.
+///
+/// Ticks are spaced by 1, 2 or 5 times a power of ten for numbers, and by "round" durations for
+/// times, so that the labels are easy to read.
+///
+/// ```
+/// use crux_charts::{Axis, Range};
+///
+/// let axis = Axis::linear(Range::new(3.2, 97.0), 6);
+///
+/// assert_eq!(axis.range, Range::new(0.0, 100.0));
+/// let labels: Vec<_> = axis.ticks.iter().map(|tick| tick.label.as_str()).collect();
+/// assert_eq!(labels, ["0", "20", "40", "60", "80", "100"]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Axis {
+    pub range: Range,
+    pub ticks: Vec<Tick>,
+}
+
+const SECOND: f64 = 1000.0;
+const MINUTE: f64 = 60.0 * SECOND;
+const HOUR: f64 = 60.0 * MINUTE;
+const DAY: f64 = 24.0 * HOUR;
+
+/// The spacings of time ticks which read well
+const TIME_STEPS: [f64; 21] = [
+    SECOND,
+    2.0 * SECOND,
+    5.0 * SECOND,
+    10.0 * SECOND,
+    15.0 * SECOND,
+    30.0 * SECOND,
+    MINUTE,
+    2.0 * MINUTE,
+    5.0 * MINUTE,
+    10.0 * MINUTE,
+    15.0 * MINUTE,
+    30.0 * MINUTE,
+    HOUR,
+    2.0 * HOUR,
+    3.0 * HOUR,
+    6.0 * HOUR,
+    12.0 * HOUR,
+    DAY,
+    2.0 * DAY,
+    7.0 * DAY,
+    14.0 * DAY,
+];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+impl Axis {
+    /// An axis for the `range` on the `scale`, with at most `max_ticks` ticks
+    pub fn new(scale: Scale, range: Range, max_ticks: usize) -> Axis {
+        match scale {
+            Scale::Linear => Self::linear(range, max_ticks),
+            Scale::Time => Self::time(range, max_ticks),
+        }
+    }
+
+    /// A numeric axis extended from the `range` to the nearest ticks, with at most `max_ticks`
+    /// ticks, but at least 3 so that ranges across zero can be covered
+    pub fn linear(range: Range, max_ticks: usize) -> Axis {
+        let range = widen(range);
+        let intervals = max_ticks.max(3) - 1;
+
+        let mut step = nice(range.span() / intervals as f64);
+        let (first, last) = loop {
+            // allow for the imprecision of floating point division, e.g. 0.3 / 0.1
+            let first = (range.min / step + 1e-9).floor();
+            let last = (range.max / step - 1e-9).ceil();
+            if (last - first) as usize <= intervals {
+                break (first, last);
+            }
+
+            // rounding out to the ticks made too many of them
+            step = nice(step * 1.5);
+        };
+
+        let decimals = decimals(step);
+        let ticks: Vec<Tick> = (first as i64..=last as i64)
+            .map(|index| {
+                let value = round(index as f64 * step, decimals);
+                let label = format!("{value:.decimals$}");
+                // don't label zero as "-0"
+                let label = if value == 0.0 {
+                    format!("{:.decimals$}", 0.0)
+                } else {
+                    label
+                };
+
+                Tick { value, label }
+            })
+            .collect();
+
+        Axis {
+            range: Range::new(ticks[0].value, ticks[ticks.len() - 1].value),
+            ticks,
+        }
+    }
+
+    /// A time axis for the `range` of milliseconds since the Unix epoch, with at most
+    /// `max_ticks` ticks inside it
+    pub fn time(range: Range, max_ticks: usize) -> Axis {
+        let range = widen(range);
+        let intervals = (max_ticks.max(2) - 1) as f64;
+
+        let step = TIME_STEPS
+            .iter()
+            .copied()
+            .find(|step| range.span() / step <= intervals)
+            .unwrap_or_else(|| nice(range.span() / intervals / DAY).max(28.0) * DAY);
+
+        let first = (range.min / step).ceil() as i64;
+        let last = (range.max / step).floor() as i64;
+        let ticks = (first..=last)
+            .map(|index| {
+                let value = index as f64 * step;
+                Tick {
+                    value,
+                    label: time_label(value, step),
+                }
+            })
+            .collect();
+
+        Axis { range, ticks }
+    }
+}
+
+/// An empty range widened so that its values are in the middle of the axis
+fn widen(range: Range) -> Range {
+    if range.span() > 0.0 {
+        range
+    } else if range.min == 0.0 {
+        Range::new(-1.0, 1.0)
+    } else {
+        let margin = range.min.abs() / 10.0;
+        Range::new(range.min - margin, range.max + margin)
+    }
+}
+
+/// The smallest number at least `value` which is 1, 2 or 5 times a power of ten
+fn nice(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|nice| *nice >= value * (1.0 - 1e-9))
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// The number of decimals needed to label multiples of `step`
+fn decimals(step: f64) -> usize {
+    (-step.log10().floor()).max(0.0) as usize
+}
+
+fn round(value: f64, decimals: usize) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+
+    (value * factor).round() / factor
+}
+
+fn time_label(millis: f64, step: f64) -> String {
+    let millis = millis as i64;
+    let seconds_of_day = millis.rem_euclid(DAY as i64) / 1000;
+    let (hour, minute, second) = (
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+    );
+
+    if step < MINUTE {
+        format!("{hour:02}:{minute:02}:{second:02}")
+    } else if step < DAY {
+        format!("{hour:02}:{minute:02}")
+    } else {
+        let (year, month, day) = civil_date(millis.div_euclid(DAY as i64));
+        let month = MONTHS[month as usize - 1];
+
+        if step < 28.0 * DAY {
+            format!("{day} {month}")
+        } else {
+            format!("{day} {month} {year}")
+        }
+    }
+}
+
+/// The year, month and day of the `days` since the Unix epoch, in the proleptic Gregorian
+/// calendar, using Howard Hinnant's `civil_from_days`
+fn civil_date(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(axis: &Axis) -> Vec<&str> {
+        axis.ticks.iter().map(|tick| tick.label.as_str()).collect()
+    }
+
+    #[test]
+    fn computes_ranges() {
+        assert_eq!(
+            Range::of([3.0, f64::NAN, -1.0, 2.0]),
+            Some(Range::new(-1.0, 3.0))
+        );
+        assert_eq!(Range::of([]), None);
+        assert_eq!(
+            Range::of_points(&[Point::new(1.0, 5.0), Point::new(2.0, -5.0)]),
+            Some((Range::new(1.0, 2.0), Range::new(-5.0, 5.0)))
+        );
+        assert_eq!(Range::new(0.0, 10.0).fraction(2.5), 0.25);
+    }
+
+    #[test]
+    fn ticks_round_numbers() {
+        let axis = Axis::linear(Range::new(-0.23, 0.41), 10);
+        assert_eq!(axis.range, Range::new(-0.3, 0.5));
+        assert_eq!(
+            labels(&axis),
+            ["-0.3", "-0.2", "-0.1", "0.0", "0.1", "0.2", "0.3", "0.4", "0.5"]
+        );
+        assert_eq!(axis.ticks[6].value, 0.3);
+
+        let axis = Axis::linear(Range::new(1_234.0, 98_765.0), 5);
+        assert_eq!(labels(&axis), ["0", "50000", "100000"]);
+    }
+
+    #[test]
+    fn keeps_within_max_ticks() {
+        for (min, max) in [(0.0, 1.0), (-7.0, 13.0), (0.001, 0.0097), (3.0, 1e6)] {
+            for max_ticks in 2..12 {
+                let axis = Axis::linear(Range::new(min, max), max_ticks);
+
+                assert!(
+                    axis.ticks.len() <= max_ticks.max(3),
+                    "{min}..{max} {axis:?}"
+                );
+                assert!(
+                    axis.range.contains(min) && axis.range.contains(max),
+                    "{min}..{max} {axis:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn widens_empty_ranges() {
+        assert_eq!(
+            labels(&Axis::linear(Range::new(0.0, 0.0), 3)),
+            ["-1", "0", "1"]
+        );
+        assert_eq!(
+            labels(&Axis::linear(Range::new(50.0, 50.0), 3)),
+            ["45", "50", "55"]
+        );
+    }
+
+    #[test]
+    fn ticks_times() {
+        // 2024-02-28T22:50:00Z to 2024-02-29T03:10:00Z
+        let start = 1_709_160_600_000.0;
+        let axis = Axis::time(Range::new(start, start + 4.0 * HOUR + 20.0 * MINUTE), 6);
+
+        assert_eq!(axis.range.min, start);
+        assert_eq!(labels(&axis), ["23:00", "00:00", "01:00", "02:00", "03:00"]);
+
+        let axis = Axis::time(Range::new(start, start + 3.0 * DAY), 4);
+        assert_eq!(labels(&axis), ["29 Feb", "1 Mar", "2 Mar"]);
+
+        let axis = Axis::time(Range::new(start, start + 40.0 * SECOND), 5);
+        assert_eq!(
+            labels(&axis),
+            ["22:50:00", "22:50:10", "22:50:20", "22:50:30", "22:50:40"]
+        );
+
+        let axis = Axis::time(Range::new(0.0, 400.0 * DAY), 5);
+        assert_eq!(
+            labels(&axis),
+            [
+                "1 Jan 1970",
+                "11 Apr 1970",
+                "20 Jul 1970",
+                "28 Oct 1970",
+                "5 Feb 1971"
+            ]
+        );
+    }
+}
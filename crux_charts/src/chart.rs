@@ -0,0 +1,235 @@
+//! Chart view models, see [`Chart`]
+
+use serde::{Deserialize, Serialize};
+
+use crate::{downsample, Axis, Point, Range, Scale};
+
+/// A line chart of one or more series, which prepares a [`ChartView`] for the view model.
+///
+/// The series are downsampled to one point per unit of width by default, and the axes are
+/// shared by all the series.
+#[derive(Clone, Debug)]
+pub struct Chart {
+    width: f64,
+    height: f64,
+    x_scale: Scale,
+    x_ticks: usize,
+    y_ticks: usize,
+    y_range: Option<Range>,
+    max_points: usize,
+    series: Vec<(String, Vec<Point>)>,
+}
+
+impl Chart {
+    /// A chart drawn `width` by `height` in the shell's units, e.g. points or pixels
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            x_scale: Scale::Linear,
+            x_ticks: (width / 80.0) as usize,
+            y_ticks: (height / 40.0) as usize,
+            y_range: None,
+            max_points: width as usize,
+            series: Vec::new(),
+        }
+    }
+
+    /// Set the scale of the x axis, [`Scale::Linear`] by default
+    #[must_use]
+    pub fn x_scale(mut self, scale: Scale) -> Self {
+        self.x_scale = scale;
+        self
+    }
+
+    /// Set the maximum number of ticks on the x axis, one per 80 units of width by default
+    #[must_use]
+    pub fn x_ticks(mut self, max_ticks: usize) -> Self {
+        self.x_ticks = max_ticks;
+        self
+    }
+
+    /// Set the maximum number of ticks on the y axis, one per 40 units of height by default
+    #[must_use]
+    pub fn y_ticks(mut self, max_ticks: usize) -> Self {
+        self.y_ticks = max_ticks;
+        self
+    }
+
+    /// Include the `range` in the y axis, e.g. 0 to 100 for percentages, rather than only the
+    /// range of the series
+    #[must_use]
+    pub fn y_range(mut self, range: Range) -> Self {
+        self.y_range = Some(range);
+        self
+    }
+
+    /// Set the maximum number of points of each series, see [`downsample`]
+    #[must_use]
+    pub fn max_points(mut self, max_points: usize) -> Self {
+        self.max_points = max_points;
+        self
+    }
+
+    /// Add a series of `points`, sorted by `x`
+    #[must_use]
+    pub fn series(mut self, name: impl Into<String>, points: Vec<Point>) -> Self {
+        self.series.push((name.into(), points));
+        self
+    }
+
+    /// The view of the chart, with the axes and the series mapped onto its coordinates
+    pub fn view(&self) -> ChartView {
+        let series: Vec<(&str, Vec<Point>)> = self
+            .series
+            .iter()
+            .map(|(name, points)| (name.as_str(), downsample(points, self.max_points)))
+            .collect();
+
+        let ranges = series
+            .iter()
+            .filter_map(|(_, points)| Range::of_points(points))
+            .reduce(|(x, y), (other_x, other_y)| (x.union(other_x), y.union(other_y)));
+        let (x_range, y_range) = ranges.unwrap_or((Range::new(0.0, 1.0), Range::new(0.0, 1.0)));
+        let y_range = self.y_range.map_or(y_range, |range| range.union(y_range));
+
+        let x_axis = Axis::new(self.x_scale, x_range, self.x_ticks);
+        let y_axis = Axis::linear(y_range, self.y_ticks);
+
+        let x = |value: f64| x_axis.range.fraction(value) * self.width;
+        // the y axis points up, but the chart's coordinates go down from the top left corner
+        let y = |value: f64| (1.0 - y_axis.range.fraction(value)) * self.height;
+
+        ChartView {
+            width: self.width,
+            height: self.height,
+            x_axis: AxisView::new(&x_axis, x),
+            y_axis: AxisView::new(&y_axis, y),
+            series: series
+                .into_iter()
+                .map(|(name, points)| SeriesView {
+                    name: name.to_string(),
+                    points: points
+                        .iter()
+                        .map(|point| Point::new(x(point.x), y(point.y)))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A chart ready to be drawn, with all positions in the chart's coordinates: from the top
+/// left corner, with `x` to the right and `y` down
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartView {
+    pub width: f64,
+    pub height: f64,
+    pub x_axis: AxisView,
+    pub y_axis: AxisView,
+    pub series: Vec<SeriesView>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AxisView {
+    pub ticks: Vec<TickView>,
+}
+
+impl AxisView {
+    fn new(axis: &Axis, position: impl Fn(f64) -> f64) -> Self {
+        Self {
+            ticks: axis
+                .ticks
+                .iter()
+                .map(|tick| TickView {
+                    position: position(tick.value),
+                    label: tick.label.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A labelled tick, at `position` along its axis
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TickView {
+    pub position: f64,
+    pub label: String,
+}
+
+/// A series to draw as a line through its `points`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SeriesView {
+    pub name: String,
+    pub points: Vec<Point>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_series_onto_the_chart() {
+        let view = Chart::new(100.0, 50.0)
+            .x_ticks(3)
+            .y_ticks(3)
+            .series("a", vec![Point::new(0.0, 0.0), Point::new(10.0, 5.0)])
+            .series("b", vec![Point::new(5.0, 10.0)])
+            .view();
+
+        assert_eq!(
+            view.series[0].points,
+            [Point::new(0.0, 50.0), Point::new(100.0, 25.0)]
+        );
+        assert_eq!(view.series[1].points, [Point::new(50.0, 0.0)]);
+
+        let ticks: Vec<_> = view
+            .y_axis
+            .ticks
+            .iter()
+            .map(|tick| (tick.position, tick.label.as_str()))
+            .collect();
+        assert_eq!(ticks, [(50.0, "0"), (25.0, "5"), (0.0, "10")]);
+    }
+
+    #[test]
+    fn includes_the_y_range() {
+        let view = Chart::new(100.0, 100.0)
+            .y_ticks(3)
+            .y_range(Range::new(0.0, 100.0))
+            .series(
+                "percent",
+                vec![Point::new(0.0, 50.0), Point::new(1.0, 75.0)],
+            )
+            .view();
+
+        assert_eq!(view.series[0].points[0], Point::new(0.0, 50.0));
+        assert_eq!(view.y_axis.ticks.len(), 3);
+    }
+
+    #[test]
+    fn downsamples_series() {
+        let points = (0..1000).map(|x| Point::new(f64::from(x), 0.0)).collect();
+
+        let view = Chart::new(200.0, 100.0).series("flat", points).view();
+        assert_eq!(view.series[0].points.len(), 200);
+
+        let view = Chart::new(0.0, 0.0).view();
+        assert!(view.series.is_empty());
+    }
+
+    #[test]
+    fn serializes_for_the_shells() {
+        let view = Chart::new(10.0, 10.0)
+            .x_ticks(3)
+            .y_ticks(3)
+            .series("s", vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)])
+            .view();
+
+        assert_eq!(
+            serde_json::to_string(&view).unwrap(),
+            r#"{"width":10.0,"height":10.0,"xAxis":{"ticks":[{"position":0.0,"label":"0.0"},{"position":5.0,"label":"0.5"},{"position":10.0,"label":"1.0"}]},"yAxis":{"ticks":[{"position":10.0,"label":"0.0"},{"position":5.0,"label":"0.5"},{"position":0.0,"label":"1.0"}]},"series":[{"name":"s","points":[{"x":0.0,"y":10.0},{"x":10.0,"y":0.0}]}]}"#
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Chart data preparation for Crux view models
+//!
+//! Dashboards often show series of thousands of points on screens a few hundred points wide.
+//! Rather than sending all of them to the shells, and having each shell work out ranges, ticks
+//! and labels in its own way, the core can prepare a [`ChartView`] which only has what needs to
+//! be drawn:
+//!
+//! * [`downsample`] reduces a series to a number of points, keeping its peaks and troughs, with
+//!   the Largest-Triangle-Three-Buckets algorithm
+//! * [`Axis`] works out a range with evenly spaced, readable [ticks](Tick) for numbers or times
+//! * [`Chart`] puts these together, mapping the series and ticks onto the chart's coordinates
+//!
+//! ```
+//! use crux_charts::{Chart, Point, Scale};
+//!
+//! let temperatures: Vec<Point> = (0..10_000)
+//!     .map(|minute| Point::new(f64::from(minute) * 60_000.0, 20.0 + f64::from(minute % 60) / 10.0))
+//!     .collect();
+//!
+//! let view = Chart::new(300.0, 200.0)
+//!     .x_scale(Scale::Time)
+//!     .series("Temperature", temperatures)
+//!     .view();
+//!
+//! assert_eq!(view.series[0].points.len(), 300);
+//! assert_eq!(view.y_axis.ticks[0].label, "20");
+//! ```
+
+mod axis;
+mod chart;
+mod lttb;
+
+pub use axis::{Axis, Range, Scale, Tick};
+pub use chart::{AxisView, Chart, ChartView, SeriesView, TickView};
+pub use lttb::downsample;
+
+use serde::{Deserialize, Serialize};
+
+/// A point of a series, e.g. a measurement (`y`) at a time (`x`), in milliseconds since the
+/// Unix epoch for time series
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+}
@@ -0,0 +1,151 @@
+//! Largest-Triangle-Three-Buckets downsampling, see [`downsample`]
+
+use crate::Point;
+
+/// Reduce the `points`, sorted by `x`, to at most `threshold` points which keep the shape of
+/// the series when drawn as a line.
+///
+/// This is the Largest-Triangle-Three-Buckets algorithm from Sveinn Steinarsson's thesis
+/// "Downsampling Time Series for Visual Representation": the first and last points are kept,
+/// the others are split into buckets, and from each bucket the point is kept which forms the
+/// largest triangle with the point kept before it and the average of the next bucket.
+///
+/// Points with a coordinate which isn't finite (e.g. `NaN` for a missing measurement) are
+/// dropped. A `threshold` below 3 is treated as 3.
+///
+/// ```
+/// use crux_charts::{downsample, Point};
+///
+/// let points: Vec<Point> = (0..1000)
+///     .map(|x| Point::new(f64::from(x), if x == 500 { 100.0 } else { 0.0 }))
+///     .collect();
+///
+/// let downsampled = downsample(&points, 10);
+/// assert_eq!(downsampled.len(), 10);
+/// assert!(downsampled.contains(&Point::new(500.0, 100.0)));
+/// ```
+pub fn downsample(points: &[Point], threshold: usize) -> Vec<Point> {
+    let points: Vec<Point> = points.iter().copied().filter(Point::is_finite).collect();
+    let threshold = threshold.max(3);
+
+    if points.len() <= threshold {
+        return points;
+    }
+
+    // the first and last points are kept, the others are split into `threshold - 2` buckets
+    let count = points.len();
+    let bucket_size = (count - 2) as f64 / (threshold - 2) as f64;
+    let bucket_start = |bucket: usize| ((bucket as f64 * bucket_size) as usize + 1).min(count - 1);
+
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(points[0]);
+    let mut previous = points[0];
+
+    for bucket in 0..threshold - 2 {
+        let next = if bucket + 3 == threshold {
+            // the last bucket is followed by the last point only
+            &points[count - 1..]
+        } else {
+            &points[bucket_start(bucket + 1)..bucket_start(bucket + 2)]
+        };
+
+        let (sum_x, sum_y, length) = next.iter().fold((0.0, 0.0, 0.0), |(x, y, length), point| {
+            (x + point.x, y + point.y, length + 1.0)
+        });
+        let average = Point::new(sum_x / length, sum_y / length);
+
+        let candidates = &points[bucket_start(bucket)..bucket_start(bucket + 1)];
+        let area = |point: &Point| {
+            ((previous.x - average.x) * (point.y - previous.y)
+                - (previous.x - point.x) * (average.y - previous.y))
+                .abs()
+        };
+
+        let selected = candidates
+            .iter()
+            .copied()
+            .reduce(|best, point| {
+                if area(&point) > area(&best) {
+                    point
+                } else {
+                    best
+                }
+            })
+            .expect("buckets aren't empty");
+
+        sampled.push(selected);
+        previous = selected;
+    }
+
+    sampled.push(points[count - 1]);
+
+    sampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(ys: &[f64]) -> Vec<Point> {
+        ys.iter()
+            .enumerate()
+            .map(|(x, y)| Point::new(x as f64, *y))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_short_series() {
+        let points = series(&[1.0, 2.0, 3.0]);
+
+        assert_eq!(downsample(&points, 3), points);
+        assert_eq!(downsample(&points, 100), points);
+        assert_eq!(downsample(&[], 10), vec![]);
+    }
+
+    #[test]
+    fn keeps_the_extremes() {
+        let points = series(&[0.0, 1.0, 0.0, 0.0, -5.0, 0.0, 0.0, 9.0, 0.0, 0.0]);
+
+        let downsampled = downsample(&points, 4);
+
+        assert_eq!(
+            downsampled,
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(4.0, -5.0),
+                Point::new(7.0, 9.0),
+                Point::new(9.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_missing_values() {
+        let points = series(&[0.0, f64::NAN, 2.0, f64::INFINITY, 4.0]);
+
+        assert_eq!(
+            downsample(&points, 1),
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 2.0),
+                Point::new(4.0, 4.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_the_threshold_number_of_points() {
+        let points: Vec<Point> = (0..10_000)
+            .map(|x| Point::new(f64::from(x), (f64::from(x) / 100.0).sin()))
+            .collect();
+
+        for threshold in [3, 7, 100, 9_999] {
+            let downsampled = downsample(&points, threshold);
+
+            assert_eq!(downsampled.len(), threshold);
+            assert_eq!(downsampled.first(), points.first());
+            assert_eq!(downsampled.last(), points.last());
+            assert!(downsampled.windows(2).all(|pair| pair[0].x < pair[1].x));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Composing emails and text messages
+//!
+//! The [`Composer`] capability presents the platform's email or message composer, pre-filled
+//! with the recipients, subject, body and attachments assembled by the core, for the user to
+//! review and send. Bodies can be rendered from [`Template`]s with `{{name}}` placeholders,
+//! so the wording of messages like invitations or receipts is kept in the core too.
+//!
+//! The result says whether the user sent the message, saved it as a draft or cancelled.
+//! Some platforms hand the message over to another app which doesn't report back, in which
+//! case the outcome is [`ComposeOutcome::Unknown`].
+
+mod template;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use template::{Template, TemplateError};
+
+/// A file attached to a message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// The file name shown to the recipient, e.g. `receipt.pdf`
+    pub name: String,
+    /// The media type, e.g. `application/pdf`
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    pub fn new(name: impl Into<String>, media_type: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            media_type: media_type.into(),
+            data,
+        }
+    }
+}
+
+/// An email to pre-fill the composer with
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Email {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    /// Whether the body is HTML, rather than plain text
+    pub html: bool,
+    pub attachments: Vec<Attachment>,
+}
+
+impl Email {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn to(mut self, address: impl Into<String>) -> Self {
+        self.to.push(address.into());
+        self
+    }
+
+    #[must_use]
+    pub fn cc(mut self, address: impl Into<String>) -> Self {
+        self.cc.push(address.into());
+        self
+    }
+
+    #[must_use]
+    pub fn bcc(mut self, address: impl Into<String>) -> Self {
+        self.bcc.push(address.into());
+        self
+    }
+
+    #[must_use]
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = subject.into();
+        self
+    }
+
+    /// Set a plain text body
+    #[must_use]
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self.html = false;
+        self
+    }
+
+    /// Set an HTML body
+    #[must_use]
+    pub fn html_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self.html = true;
+        self
+    }
+
+    #[must_use]
+    pub fn attach(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+}
+
+/// A text message to pre-fill the composer with
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sms {
+    /// Phone numbers, or other addresses the platform's messaging app accepts
+    pub recipients: Vec<String>,
+    pub body: String,
+    /// Attachments, sent as MMS where the platform supports them
+    pub attachments: Vec<Attachment>,
+}
+
+impl Sms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn recipient(mut self, recipient: impl Into<String>) -> Self {
+        self.recipients.push(recipient.into());
+        self
+    }
+
+    #[must_use]
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    #[must_use]
+    pub fn attach(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComposerOperation {
+    Email(Email),
+    Sms(Sms),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComposerResponse {
+    Done(ComposeOutcome),
+    /// The device can't send this kind of message, e.g. no email account is set up
+    Unavailable,
+    Error {
+        message: String,
+    },
+}
+
+/// What the user did with the composed message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComposeOutcome {
+    Sent,
+    /// Saved as a draft, to be sent later
+    Saved,
+    Cancelled,
+    /// The message was handed over to another app, which doesn't report back
+    Unknown,
+}
+
+impl Operation for ComposerOperation {
+    type Output = ComposerResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ComposerError {
+    #[error("this device can't send the message")]
+    Unavailable,
+    #[error("composer failed: {message}")]
+    Shell { message: String },
+}
+
+impl ComposerResponse {
+    fn into_result(self) -> Result<ComposeOutcome, ComposerError> {
+        match self {
+            ComposerResponse::Done(outcome) => Ok(outcome),
+            ComposerResponse::Unavailable => Err(ComposerError::Unavailable),
+            ComposerResponse::Error { message } => Err(ComposerError::Shell { message }),
+        }
+    }
+}
+
+/// The Composer capability API
+///
+/// This capability lets the app present pre-filled email and text message composers.
+#[derive(crux_core::macros::Capability)]
+pub struct Composer<Ev> {
+    context: CapabilityContext<ComposerOperation, Ev>,
+}
+
+impl<Ev> Clone for Composer<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Composer<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<ComposerOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Present the email composer, then send the event returned by `callback` with what the
+    /// user did.
+    pub fn email<F>(&self, email: Email, callback: F)
+    where
+        F: FnOnce(Result<ComposeOutcome, ComposerError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.email_async(email).await));
+            }
+        });
+    }
+
+    /// Present the email composer.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn email_async(&self, email: Email) -> Result<ComposeOutcome, ComposerError> {
+        self.context
+            .request_from_shell(ComposerOperation::Email(email))
+            .await
+            .into_result()
+    }
+
+    /// Present the text message composer, then send the event returned by `callback` with
+    /// what the user did.
+    pub fn sms<F>(&self, sms: Sms, callback: F)
+    where
+        F: FnOnce(Result<ComposeOutcome, ComposerError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.sms_async(sms).await));
+            }
+        });
+    }
+
+    /// Present the text message composer.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn sms_async(&self, sms: Sms) -> Result<ComposeOutcome, ComposerError> {
+        self.context
+            .request_from_shell(ComposerOperation::Sms(sms))
+            .await
+            .into_result()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = ComposerOperation::Sms(Sms::new().recipient("+441234567890").body("Hi"));
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"sms":{"recipients":["+441234567890"],"body":"Hi","attachments":[]}}"#
+        );
+
+        let deserialized: ComposerOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let serialized =
+            serde_json::to_string(&ComposerResponse::Done(ComposeOutcome::Saved)).unwrap();
+        assert_eq!(&serialized, r#"{"done":"saved"}"#);
+    }
+}
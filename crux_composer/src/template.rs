@@ -0,0 +1,202 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+/// A message template, with `{{name}}` placeholders for the values it's rendered with
+///
+/// Placeholder names are made of letters, digits, `_` and `.`, and may be surrounded by
+/// spaces, e.g. `Hi {{ user.first_name }}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error("placeholder starting at byte {offset} is not closed")]
+    Unclosed { offset: usize },
+    #[error("invalid placeholder name {name:?}")]
+    InvalidName { name: String },
+    #[error("no value for placeholder {name}")]
+    Missing { name: String },
+}
+
+impl Template {
+    /// Parse a template.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a placeholder isn't closed, or its name isn't valid.
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                return Err(TemplateError::Unclosed {
+                    offset: source.len() - rest.len() + start,
+                });
+            };
+
+            let name = after[..end].trim();
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+            if !valid {
+                return Err(TemplateError::InvalidName {
+                    name: name.to_string(),
+                });
+            }
+
+            parts.push(Part::Variable(name.to_string()));
+            rest = &after[end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// The names of the template's placeholders, in order of appearance
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Variable(name) => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Render the template, replacing each placeholder with its value in `values`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first placeholder without a value.
+    pub fn render(&self, values: &BTreeMap<String, String>) -> Result<String, TemplateError> {
+        self.render_with(|name| values.get(name).cloned())
+    }
+
+    /// Render the template, replacing each placeholder with the value returned by `lookup`
+    /// for its name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first placeholder for which `lookup` returns `None`.
+    pub fn render_with<F>(&self, lookup: F) -> Result<String, TemplateError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut rendered = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => rendered.push_str(text),
+                Part::Variable(name) => {
+                    let value = lookup(name)
+                        .ok_or_else(|| TemplateError::Missing { name: name.clone() })?;
+                    rendered.push_str(&value);
+                }
+            }
+        }
+
+        Ok(rendered)
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => f.write_str(text)?,
+                Part::Variable(name) => write!(f, "{{{{{name}}}}}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let template =
+            Template::parse("Hi {{ name }}, your order {{order.id}} has shipped").unwrap();
+
+        assert_eq!(
+            template.variables().collect::<Vec<_>>(),
+            vec!["name", "order.id"]
+        );
+        assert_eq!(
+            template
+                .render(&values(&[("name", "Ana"), ("order.id", "#1042")]))
+                .unwrap(),
+            "Hi Ana, your order #1042 has shipped"
+        );
+        assert_eq!(
+            template.to_string(),
+            "Hi {{name}}, your order {{order.id}} has shipped"
+        );
+    }
+
+    #[test]
+    fn missing_values() {
+        let template = Template::parse("{{greeting}} {{name}}").unwrap();
+
+        assert_eq!(
+            template.render(&values(&[("greeting", "Hello")])),
+            Err(TemplateError::Missing {
+                name: "name".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_templates() {
+        assert_eq!(
+            Template::parse("Hi {{name"),
+            Err(TemplateError::Unclosed { offset: 3 })
+        );
+        assert_eq!(
+            "Hi {{}}".parse::<Template>(),
+            Err(TemplateError::InvalidName {
+                name: String::new()
+            })
+        );
+        assert_eq!(
+            Template::parse("{{first name}}"),
+            Err(TemplateError::InvalidName {
+                name: "first name".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,181 @@
+mod shared {
+    use std::collections::BTreeMap;
+
+    use crux_composer::{
+        Attachment, ComposeOutcome, Composer, ComposerError, Email, Sms, Template,
+    };
+    use crux_core::macros::Effect;
+    use serde::{Deserialize, Serialize};
+
+    const RECEIPT: &str =
+        "Hi {{name}},\n\nThanks for your order {{order}}. Your receipt is attached.";
+    const INVITE: &str = "{{name}} invited you to {{event}}: {{link}}";
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        SendReceipt,
+        Invite {
+            phone: String,
+        },
+
+        #[serde(skip)]
+        Composed(Result<ComposeOutcome, ComposerError>),
+    }
+
+    pub struct Model {
+        pub name: String,
+        pub email: String,
+        pub order: String,
+        pub outcome: Option<ComposeOutcome>,
+        pub error: Option<String>,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            Self {
+                name: "Ana".to_string(),
+                email: "ana@example.com".to_string(),
+                order: "#1042".to_string(),
+                outcome: None,
+                error: None,
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub composer: Composer<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::SendReceipt => {
+                    let values = BTreeMap::from([
+                        ("name".to_string(), model.name.clone()),
+                        ("order".to_string(), model.order.clone()),
+                    ]);
+                    let body = Template::parse(RECEIPT).unwrap().render(&values).unwrap();
+
+                    let email = Email::new()
+                        .to(model.email.clone())
+                        .subject(format!("Your order {}", model.order))
+                        .body(body)
+                        .attach(Attachment::new(
+                            "receipt.pdf",
+                            "application/pdf",
+                            b"%PDF".to_vec(),
+                        ));
+                    caps.composer.email(email, Event::Composed);
+                }
+                Event::Invite { phone } => {
+                    // the link isn't known yet, so the template can't be rendered
+                    let values = BTreeMap::from([
+                        ("name".to_string(), model.name.clone()),
+                        ("event".to_string(), "the picnic".to_string()),
+                    ]);
+                    match Template::parse(INVITE).unwrap().render(&values) {
+                        Ok(body) => caps
+                            .composer
+                            .sms(Sms::new().recipient(phone).body(body), Event::Composed),
+                        Err(error) => model.error = Some(error.to_string()),
+                    }
+                }
+                Event::Composed(Ok(outcome)) => model.outcome = Some(outcome),
+                Event::Composed(Err(error)) => model.error = Some(error.to_string()),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_composer::{ComposeOutcome, ComposerOperation, ComposerResponse};
+    use crux_core::testing::AppTester;
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    #[test]
+    fn composes_a_receipt_email() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut effects = app.update(Event::SendReceipt, &mut model).into_effects();
+        let Some(Effect::Composer(mut request)) = effects.next() else {
+            panic!("expected a composer effect");
+        };
+
+        let ComposerOperation::Email(email) = &request.operation else {
+            panic!("expected an email");
+        };
+        assert_eq!(email.to, vec!["ana@example.com".to_string()]);
+        assert_eq!(email.subject, "Your order #1042");
+        assert_eq!(
+            email.body,
+            "Hi Ana,\n\nThanks for your order #1042. Your receipt is attached."
+        );
+        assert!(!email.html);
+        assert_eq!(email.attachments[0].name, "receipt.pdf");
+
+        let update = app
+            .resolve(&mut request, ComposerResponse::Done(ComposeOutcome::Sent))
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(model.outcome, Some(ComposeOutcome::Sent));
+    }
+
+    #[test]
+    fn template_errors_stay_in_the_core() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::Invite {
+                phone: "+441234567890".to_string(),
+            },
+            &mut model,
+        );
+
+        assert!(update.effects.is_empty());
+        assert_eq!(
+            model.error.as_deref(),
+            Some("no value for placeholder link")
+        );
+    }
+
+    #[test]
+    fn unavailable_composer() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut effects = app.update(Event::SendReceipt, &mut model).into_effects();
+        let Some(Effect::Composer(mut request)) = effects.next() else {
+            panic!("expected a composer effect");
+        };
+
+        let update = app
+            .resolve(&mut request, ComposerResponse::Unavailable)
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(model.outcome, None);
+        assert_eq!(
+            model.error.as_deref(),
+            Some("this device can't send the message")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! The contract of the `crux_http` capability
+//!
+//! The fixtures make requests to a test server, which the shell's tests run, with these
+//! endpoints:
+//!
+//! * `/status/{code}` responds with the status `code` and an empty body, to any method
+//! * `/echo` responds with status 200 and the body of the request, to any method
+//! * `/header/{name}` responds with status 200 and the value of the request's header `name`
+//!   as the body
+
+use crux_http::protocol::HttpRequest;
+use serde_json::{json, Value};
+
+use crate::{Expect, Fixture, Step};
+
+fn fixture(name: &str, description: &str, steps: Vec<Step<HttpRequest>>) -> Fixture<HttpRequest> {
+    Fixture {
+        capability: "http".to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        steps,
+    }
+}
+
+fn response(status: u16, body: &[u8]) -> Value {
+    json!({ "Ok": { "status": status, "body": body } })
+}
+
+/// The fixtures of the HTTP contract, making requests to the test server at `base_url`,
+/// e.g. `http://localhost:8080`.
+pub fn fixtures(base_url: &str) -> Vec<Fixture<HttpRequest>> {
+    let base_url = base_url.trim_end_matches('/');
+    let url = |path: &str| format!("{base_url}{path}");
+    // bytes which aren't valid UTF-8, so a shell which decodes bodies as text fails
+    let binary = [0x00, 0x9f, 0xff, b'c', b'r', b'u', b'x'];
+
+    vec![
+        fixture(
+            "status",
+            "Responses with any status are responses, not errors",
+            vec![
+                Step::new(
+                    HttpRequest::get(url("/status/200")).build(),
+                    Expect::Includes(response(200, b"")),
+                ),
+                Step::new(
+                    HttpRequest::get(url("/status/404")).build(),
+                    Expect::Includes(response(404, b"")),
+                ),
+                Step::new(
+                    HttpRequest::get(url("/status/500")).build(),
+                    Expect::Includes(response(500, b"")),
+                ),
+            ],
+        ),
+        fixture(
+            "methodsAndBodies",
+            "Requests are sent with their method and body, and bodies are bytes",
+            vec![
+                Step::new(
+                    HttpRequest::post(url("/echo"))
+                        .body(binary.to_vec())
+                        .build(),
+                    Expect::Includes(response(200, &binary)),
+                ),
+                Step::new(
+                    HttpRequest::put(url("/echo")).body(b"put".to_vec()).build(),
+                    Expect::Includes(response(200, b"put")),
+                ),
+                Step::new(
+                    HttpRequest::delete(url("/status/204")).build(),
+                    Expect::Includes(response(204, b"")),
+                ),
+            ],
+        ),
+        fixture(
+            "headers",
+            "Requests are sent with their headers",
+            vec![Step::new(
+                HttpRequest::get(url("/header/x-crux-contract"))
+                    .header("x-crux-contract", "yes")
+                    .build(),
+                Expect::Includes(response(200, b"yes")),
+            )],
+        ),
+        fixture(
+            "connectionFailure",
+            "Requests which can't be sent are errors",
+            // the .invalid domain never resolves
+            vec![Step::new(
+                HttpRequest::get("http://crux-contract.invalid/").build(),
+                Expect::Variant("Err".to_string()),
+            )],
+        ),
+    ]
+}
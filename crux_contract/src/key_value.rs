@@ -0,0 +1,171 @@
+//! The contract of the `crux_kv` capability
+//!
+//! Each fixture expects an empty store. Versions are opaque to the core, so they're only
+//! checked where the contract fixes them: 0 for a key with no value.
+
+use crux_kv::{KeyValueOperation, KeyValueResponse, KeyValueResult, WriteMode};
+use serde_json::{json, Value};
+
+use crate::{Expect, Fixture, Step};
+
+type KvStep = Step<KeyValueOperation>;
+
+fn fixture(name: &str, description: &str, steps: Vec<KvStep>) -> Fixture<KeyValueOperation> {
+    Fixture {
+        capability: "keyValue".to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        steps,
+    }
+}
+
+fn get(key: &str) -> KeyValueOperation {
+    KeyValueOperation::Get {
+        key: key.to_string(),
+    }
+}
+
+fn set(key: &str, value: &[u8], mode: WriteMode) -> KeyValueOperation {
+    KeyValueOperation::Set {
+        key: key.to_string(),
+        value: value.to_vec(),
+        mode,
+        idempotency_key: None,
+    }
+}
+
+fn delete(key: &str) -> KeyValueOperation {
+    KeyValueOperation::Delete {
+        key: key.to_string(),
+    }
+}
+
+fn exists(key: &str) -> KeyValueOperation {
+    KeyValueOperation::Exists {
+        key: key.to_string(),
+    }
+}
+
+fn ok(response: KeyValueResponse) -> Value {
+    serde_json::to_value(KeyValueResult::Ok { response }).expect("responses serialize")
+}
+
+fn missing() -> Expect {
+    Expect::Equals(ok(KeyValueResponse::Get {
+        value: vec![],
+        version: 0,
+    }))
+}
+
+fn value(value: &[u8]) -> Value {
+    json!({ "Ok": { "response": { "Get": { "value": value } } } })
+}
+
+fn previous(operation: &str, previous: &[u8]) -> Value {
+    json!({ "Ok": { "response": { operation: { "previous": previous } } } })
+}
+
+fn conflict() -> Expect {
+    // at any current version
+    Expect::Includes(json!({ "Err": { "error": { "conflict": {} } } }))
+}
+
+/// The fixtures of the key-value contract
+pub fn fixtures() -> Vec<Fixture<KeyValueOperation>> {
+    let key = "contract/key";
+
+    vec![
+        fixture(
+            "missingKey",
+            "A key which was never written has an empty value at version 0",
+            vec![
+                Step::new(get(key), missing()),
+                Step::new(
+                    exists(key),
+                    Expect::Equals(ok(KeyValueResponse::Exists { is_present: false })),
+                ),
+                Step::new(
+                    delete(key),
+                    Expect::Equals(ok(KeyValueResponse::Delete { previous: vec![] })),
+                ),
+            ],
+        ),
+        fixture(
+            "setGetDelete",
+            "Values are stored until they're deleted, and writes return the previous value",
+            vec![
+                Step::new(
+                    set(key, b"one", WriteMode::Overwrite),
+                    Expect::Includes(previous("Set", b"")),
+                ),
+                Step::new(get(key), Expect::Includes(value(b"one"))),
+                Step::new(
+                    set(key, b"two", WriteMode::Overwrite),
+                    Expect::Includes(previous("Set", b"one")),
+                ),
+                Step::new(
+                    exists(key),
+                    Expect::Equals(ok(KeyValueResponse::Exists { is_present: true })),
+                ),
+                Step::new(
+                    delete(key),
+                    Expect::Equals(ok(KeyValueResponse::Delete {
+                        previous: b"two".to_vec(),
+                    })),
+                ),
+                Step::new(get(key), missing()),
+            ],
+        ),
+        fixture(
+            "writeModes",
+            "Writes which their mode doesn't allow fail with a conflict and leave the value",
+            vec![
+                Step::new(
+                    set(key, b"first", WriteMode::IfAbsent),
+                    Expect::Includes(previous("Set", b"")),
+                ),
+                Step::new(set(key, b"second", WriteMode::IfAbsent), conflict()),
+                Step::new(set(key, b"third", WriteMode::IfMatchVersion(0)), conflict()),
+                Step::new(get(key), Expect::Includes(value(b"first"))),
+            ],
+        ),
+        fixture(
+            "batch",
+            "The operations of a batch are performed in order, with a result for each",
+            vec![Step::new(
+                KeyValueOperation::Batch {
+                    operations: vec![
+                        set(key, b"value", WriteMode::Overwrite),
+                        get(key),
+                        delete(key),
+                    ],
+                },
+                Expect::Includes(json!({ "Ok": { "response": { "Batch": { "results": [
+                    previous("Set", b""),
+                    value(b"value"),
+                    previous("Delete", b"value"),
+                ] } } } })),
+            )],
+        ),
+        fixture(
+            "stats",
+            "Stats count the entries under a prefix, leaving how their size is measured to the store",
+            vec![
+                Step::new(
+                    set(key, b"value", WriteMode::Overwrite),
+                    Expect::Includes(previous("Set", b"")),
+                ),
+                Step::new(
+                    set("elsewhere/key", b"value", WriteMode::Overwrite),
+                    Expect::Includes(previous("Set", b"")),
+                ),
+                Step::new(
+                    KeyValueOperation::Stats {
+                        prefix: "contract/".to_string(),
+                    },
+                    Expect::Includes(json!({ "Ok": { "response": { "Stats": { "entries": 1 } } } })),
+                ),
+            ],
+        ),
+    ]
+}
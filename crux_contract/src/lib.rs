@@ -0,0 +1,314 @@
+//! Contract tests for shells' implementations of the built-in capabilities
+//!
+//! Each shell implements the capabilities' effects itself, in Swift, Kotlin or TypeScript,
+//! and a shell which doesn't do what the core expects, e.g. turning a `404` into an error
+//! rather than a response, or losing a key-value write, is only found out when the app runs.
+//! This crate describes what the core expects as fixtures, which shell developers run against
+//! their effect handlers in the shell's own tests, before integrating them with the core.
+//!
+//! A [`Fixture`] is a sequence of operations of one capability, each with the response it
+//! expects. Fixtures are exported as JSON (see [`export`]), with the operations serialized as
+//! they are for the shell, so a test adapter in the shell only needs to:
+//!
+//! 1. read a fixture file, and for each fixture, each operation in turn:
+//! 1. decode the operation, send it to the effect handler being tested, and encode the response
+//! 1. note the response, and how long it took, in milliseconds, as an [`Outcome`]
+//!
+//! and write out the outcomes for each fixture, by name, for [`Fixture::verify`] to check, e.g.
+//! in a Rust test which runs the shell's tests. Shells written in Rust can use
+//! [`Fixture::run`] directly instead.
+//!
+//! The fixtures for each capability are in [`http`], [`key_value`] and [`time`].
+
+pub mod http;
+pub mod key_value;
+pub mod time;
+
+use std::{fs, io, path::Path, time::Instant};
+
+use crux_core::capability::Operation;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// The response an operation expects, compared with the response as it's serialized to JSON
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Expect {
+    /// Exactly this response
+    Equals(Value),
+    /// A response which includes these fields, with these values, ignoring any other fields,
+    /// e.g. headers which differ between platforms. Lists must have the same items.
+    Includes(Value),
+    /// Any data in this variant of the response, e.g. `now` for any time
+    Variant(String),
+}
+
+impl Expect {
+    fn check(&self, response: &Value) -> Result<(), String> {
+        let matches = match self {
+            Expect::Equals(expected) => expected == response,
+            Expect::Includes(expected) => includes(response, expected),
+            Expect::Variant(variant) => match response {
+                Value::String(name) => name == variant,
+                Value::Object(map) => map.len() == 1 && map.contains_key(variant),
+                _ => false,
+            },
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(format!("expected {self:?}, got {response}"))
+        }
+    }
+}
+
+fn includes(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected.iter().all(|(key, value)| {
+            actual
+                .get(key)
+                .map_or(false, |actual| includes(actual, value))
+        }),
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len()
+                && actual.iter().zip(expected).all(|(a, e)| includes(a, e))
+        }
+        _ => actual == expected,
+    }
+}
+
+/// An operation for the shell to perform, and the response it expects
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Step<Op> {
+    pub operation: Op,
+    pub expect: Expect,
+    /// The shortest time the shell may take to respond, e.g. for a timer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_elapsed_ms: Option<u64>,
+}
+
+impl<Op> Step<Op> {
+    pub fn new(operation: Op, expect: Expect) -> Self {
+        Self {
+            operation,
+            expect,
+            min_elapsed_ms: None,
+        }
+    }
+
+    #[must_use]
+    pub fn taking_at_least(mut self, ms: u64) -> Self {
+        self.min_elapsed_ms = Some(ms);
+        self
+    }
+}
+
+/// How the shell responded to a [`Step`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outcome {
+    /// The response, serialized to JSON
+    pub response: Value,
+    /// How long the shell took to respond
+    #[serde(default)]
+    pub elapsed_ms: u64,
+}
+
+/// A shell's response which broke the contract
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{capability} fixture {fixture} failed at step {step}: {message}")]
+pub struct Violation {
+    pub capability: String,
+    pub fixture: String,
+    /// The index of the step which failed
+    pub step: usize,
+    pub message: String,
+}
+
+/// A scenario of a capability's contract: operations for the shell to perform in order, on a
+/// fresh instance of its effect handler, and the responses the core expects.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fixture<Op> {
+    pub capability: String,
+    pub name: String,
+    pub description: String,
+    pub steps: Vec<Step<Op>>,
+}
+
+impl<Op> Fixture<Op> {
+    fn violation(&self, step: usize, message: impl Into<String>) -> Violation {
+        Violation {
+            capability: self.capability.clone(),
+            fixture: self.name.clone(),
+            step,
+            message: message.into(),
+        }
+    }
+
+    /// Check the `outcomes` of the steps, as reported by a shell's test adapter.
+    pub fn verify(&self, outcomes: &[Outcome]) -> Result<(), Violation> {
+        for (index, step) in self.steps.iter().enumerate() {
+            let outcome = outcomes
+                .get(index)
+                .ok_or_else(|| self.violation(index, "the shell didn't respond"))?;
+
+            step.expect
+                .check(&outcome.response)
+                .map_err(|message| self.violation(index, message))?;
+
+            if let Some(min) = step.min_elapsed_ms {
+                if outcome.elapsed_ms < min {
+                    return Err(self.violation(
+                        index,
+                        format!(
+                            "responded after {}ms, expected at least {min}ms",
+                            outcome.elapsed_ms
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if outcomes.len() > self.steps.len() {
+            return Err(self.violation(self.steps.len(), "the shell responded to too many steps"));
+        }
+
+        Ok(())
+    }
+}
+
+impl<Op> Fixture<Op>
+where
+    Op: Operation,
+    Op::Output: Serialize,
+{
+    /// Run the steps through a shell's effect `handler` written in Rust, and check its
+    /// responses.
+    pub fn run<F>(&self, mut handler: F) -> Result<(), Violation>
+    where
+        F: FnMut(&Op) -> Op::Output,
+    {
+        let outcomes = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let started = Instant::now();
+                let output = handler(&step.operation);
+                let elapsed_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+                let response = serde_json::to_value(output)
+                    .map_err(|e| self.violation(index, format!("invalid response: {e}")))?;
+                Ok(Outcome {
+                    response,
+                    elapsed_ms,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.verify(&outcomes)
+    }
+}
+
+/// Write the fixtures of all the capabilities to `http.json`, `key_value.json` and `time.json`
+/// in the directory `dir`, for the shells' test adapters to read. The HTTP fixtures make
+/// requests to the test server at `http_base_url`, see [`http::fixtures`].
+pub fn export(dir: impl AsRef<Path>, http_base_url: &str) -> io::Result<()> {
+    fn write<T: Serialize>(path: &Path, fixtures: &T) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(fixtures)?;
+        fs::write(path, json)
+    }
+
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    write(&dir.join("http.json"), &http::fixtures(http_base_url))?;
+    write(&dir.join("key_value.json"), &key_value::fixtures())?;
+    write(&dir.join("time.json"), &time::fixtures())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn expectations_match_serialized_responses() {
+        let response =
+            json!({ "ok": { "status": 200, "headers": [{ "name": "a" }], "body": [1] } });
+
+        assert!(Expect::Equals(response.clone()).check(&response).is_ok());
+        assert!(Expect::Includes(json!({ "ok": { "status": 200 } }))
+            .check(&response)
+            .is_ok());
+        assert!(Expect::Includes(json!({ "ok": { "status": 404 } }))
+            .check(&response)
+            .is_err());
+        assert!(Expect::Includes(json!({ "ok": { "body": [] } }))
+            .check(&response)
+            .is_err());
+        assert!(Expect::Variant("ok".to_string()).check(&response).is_ok());
+        assert!(Expect::Variant("err".to_string()).check(&response).is_err());
+        assert!(Expect::Variant("now".to_string())
+            .check(&json!("now"))
+            .is_ok());
+    }
+
+    #[test]
+    fn outcomes_are_verified_in_order() {
+        let fixture = Fixture {
+            capability: "test".to_string(),
+            name: "timer".to_string(),
+            description: String::new(),
+            steps: vec![
+                Step::new((), Expect::Equals(json!("done"))).taking_at_least(50),
+                Step::new((), Expect::Variant("done".to_string())),
+            ],
+        };
+        let outcome = |response: &str, elapsed_ms| Outcome {
+            response: json!(response),
+            elapsed_ms,
+        };
+
+        assert!(fixture
+            .verify(&[outcome("done", 51), outcome("done", 0)])
+            .is_ok());
+        assert_eq!(
+            fixture.verify(&[outcome("done", 10)]).unwrap_err().message,
+            "responded after 10ms, expected at least 50ms"
+        );
+        assert_eq!(
+            fixture.verify(&[outcome("done", 50)]).unwrap_err(),
+            Violation {
+                capability: "test".to_string(),
+                fixture: "timer".to_string(),
+                step: 1,
+                message: "the shell didn't respond".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let step = Step::new(
+            json!({ "get": { "key": "a" } }),
+            Expect::Includes(json!({ "ok": {} })),
+        )
+        .taking_at_least(10);
+
+        let serialized = serde_json::to_string(&step).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"operation":{"get":{"key":"a"}},"expect":{"includes":{"ok":{}}},"minElapsedMs":10}"#
+        );
+
+        let deserialized: Step<Value> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, step);
+    }
+}
@@ -0,0 +1,61 @@
+//! The contract of the `crux_time` capability
+
+use crux_time::{Duration, Instant, TimeRequest, TimeResponse};
+
+use crate::{Expect, Fixture, Step};
+
+fn fixture(name: &str, description: &str, steps: Vec<Step<TimeRequest>>) -> Fixture<TimeRequest> {
+    Fixture {
+        capability: "time".to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        steps,
+    }
+}
+
+fn equals(response: TimeResponse) -> Expect {
+    Expect::Equals(serde_json::to_value(response).expect("responses serialize"))
+}
+
+/// The fixtures of the time contract
+pub fn fixtures() -> Vec<Fixture<TimeRequest>> {
+    let delay_ms = 100;
+
+    vec![
+        fixture(
+            "now",
+            "The current time is returned as an instant",
+            vec![Step::new(
+                TimeRequest::Now,
+                Expect::Variant("now".to_string()),
+            )],
+        ),
+        fixture(
+            "notifyAfter",
+            "A timer responds once its duration has elapsed, and not before",
+            vec![Step::new(
+                TimeRequest::NotifyAfter(
+                    Duration::from_millis(delay_ms).expect("the delay is a valid duration"),
+                ),
+                equals(TimeResponse::DurationElapsed),
+            )
+            .taking_at_least(delay_ms)],
+        ),
+        fixture(
+            "notifyAtPast",
+            "A timer for an instant which has passed responds straight away",
+            vec![Step::new(
+                TimeRequest::NotifyAt(Instant::new(0, 0).expect("the epoch is a valid instant")),
+                equals(TimeResponse::InstantArrived),
+            )],
+        ),
+        fixture(
+            "utcOffset",
+            "The offset of the local time zone is returned in seconds",
+            vec![Step::new(
+                TimeRequest::UtcOffset,
+                Expect::Variant("utcOffset".to_string()),
+            )],
+        ),
+    ]
+}
@@ -0,0 +1,239 @@
+mod shell {
+    use std::{collections::HashMap, thread, time::SystemTime};
+
+    use crux_http::{
+        protocol::{HttpRequest, HttpResponse, HttpResult},
+        HttpError,
+    };
+    use crux_kv::{
+        error::KeyValueError, KeyValueOperation, KeyValueResponse, KeyValueResult, WriteMode,
+    };
+    use crux_time::{Instant, TimeRequest, TimeResponse};
+
+    /// An in-memory store, as a shell might implement it
+    #[derive(Default)]
+    pub struct Store {
+        entries: HashMap<String, (Vec<u8>, u64)>,
+        last_version: u64,
+    }
+
+    impl Store {
+        pub fn handle(&mut self, operation: &KeyValueOperation) -> KeyValueResult {
+            let response = match operation {
+                KeyValueOperation::Get { key } => {
+                    let (value, version) = self.entries.get(key).cloned().unwrap_or_default();
+                    KeyValueResponse::Get { value, version }
+                }
+                KeyValueOperation::Set {
+                    key, value, mode, ..
+                } => {
+                    let current = self.entries.get(key).map_or(0, |(_, version)| *version);
+                    let allowed = match mode {
+                        WriteMode::Overwrite => true,
+                        WriteMode::IfAbsent => current == 0,
+                        WriteMode::IfMatchVersion(version) => current == *version,
+                    };
+                    if !allowed {
+                        return KeyValueResult::Err {
+                            error: KeyValueError::Conflict {
+                                current_version: current,
+                            },
+                        };
+                    }
+
+                    self.last_version += 1;
+                    let previous = self
+                        .entries
+                        .insert(key.clone(), (value.clone(), self.last_version))
+                        .map(|(value, _)| value)
+                        .unwrap_or_default();
+                    KeyValueResponse::Set {
+                        previous,
+                        version: self.last_version,
+                    }
+                }
+                KeyValueOperation::Delete { key } => KeyValueResponse::Delete {
+                    previous: self
+                        .entries
+                        .remove(key)
+                        .map(|(value, _)| value)
+                        .unwrap_or_default(),
+                },
+                KeyValueOperation::Exists { key } => KeyValueResponse::Exists {
+                    is_present: self.entries.contains_key(key),
+                },
+                KeyValueOperation::ListKeys { prefix, .. } => KeyValueResponse::ListKeys {
+                    keys: self
+                        .entries
+                        .keys()
+                        .filter(|key| key.starts_with(prefix.as_str()))
+                        .cloned()
+                        .collect(),
+                    next_cursor: 0,
+                },
+                KeyValueOperation::Stats { prefix } => {
+                    let (entries, bytes) = self
+                        .entries
+                        .iter()
+                        .filter(|(key, _)| key.starts_with(prefix.as_str()))
+                        .fold((0, 0), |(entries, bytes), (key, (value, _))| {
+                            (entries + 1, bytes + (key.len() + value.len()) as u64)
+                        });
+                    KeyValueResponse::Stats { entries, bytes }
+                }
+                KeyValueOperation::Batch { operations } => KeyValueResponse::Batch {
+                    results: operations.iter().map(|op| self.handle(op)).collect(),
+                },
+            };
+
+            KeyValueResult::Ok { response }
+        }
+    }
+
+    pub fn time(request: &TimeRequest) -> TimeResponse {
+        match request {
+            TimeRequest::Now => {
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap();
+                TimeResponse::Now(Instant::new(now.as_secs(), now.subsec_nanos()).unwrap())
+            }
+            TimeRequest::NotifyAt(instant) => {
+                let at = SystemTime::UNIX_EPOCH
+                    + std::time::Duration::new(instant.seconds, instant.nanos);
+                if let Ok(remaining) = at.duration_since(SystemTime::now()) {
+                    thread::sleep(remaining);
+                }
+                TimeResponse::InstantArrived
+            }
+            TimeRequest::NotifyAfter(duration) => {
+                thread::sleep((*duration).into());
+                TimeResponse::DurationElapsed
+            }
+            TimeRequest::UtcOffset => TimeResponse::UtcOffset(0),
+        }
+    }
+
+    /// The test server's endpoints, answered without a network
+    pub fn http(request: &HttpRequest) -> HttpResult {
+        let Some(path) = request.url.strip_prefix("http://localhost:8080") else {
+            return HttpResult::Err(HttpError::Io("host not found".to_string()));
+        };
+
+        let response = if let Some(code) = path.strip_prefix("/status/") {
+            HttpResponse::status(code.parse().unwrap()).build()
+        } else if let Some(name) = path.strip_prefix("/header/") {
+            let value = request
+                .headers
+                .iter()
+                .find(|header| header.name == name)
+                .map(|header| header.value.clone())
+                .unwrap_or_default();
+            HttpResponse::ok()
+                .header("content-type", "text/plain")
+                .body(value.into_bytes())
+                .build()
+        } else if path == "/echo" {
+            HttpResponse::ok().body(request.body.clone()).build()
+        } else {
+            HttpResponse::status(404).build()
+        };
+
+        HttpResult::Ok(response)
+    }
+}
+
+mod tests {
+    use crux_contract::{http, key_value, time, Outcome, Violation};
+    use crux_http::{
+        protocol::{HttpResponse, HttpResult},
+        HttpError,
+    };
+
+    use crate::shell;
+
+    #[test]
+    fn reference_handlers_keep_the_contract() {
+        for fixture in key_value::fixtures() {
+            let mut store = shell::Store::default();
+            fixture.run(|operation| store.handle(operation)).unwrap();
+        }
+
+        for fixture in time::fixtures() {
+            fixture.run(shell::time).unwrap();
+        }
+
+        for fixture in http::fixtures("http://localhost:8080/") {
+            fixture.run(shell::http).unwrap();
+        }
+    }
+
+    #[test]
+    fn handlers_which_treat_statuses_as_errors_break_the_contract() {
+        let fixture = http::fixtures("http://localhost:8080")
+            .into_iter()
+            .find(|fixture| fixture.name == "status")
+            .unwrap();
+
+        let violation = fixture
+            .run(|request| match shell::http(request) {
+                HttpResult::Ok(HttpResponse { status, .. }) if status >= 400 => {
+                    HttpResult::Err(HttpError::Io(format!("status {status}")))
+                }
+                result => result,
+            })
+            .unwrap_err();
+
+        assert_eq!(violation.fixture, "status");
+        assert_eq!(violation.step, 1);
+    }
+
+    #[test]
+    fn outcomes_reported_by_a_shell_are_verified() {
+        let fixture = key_value::fixtures()
+            .into_iter()
+            .find(|fixture| fixture.name == "missingKey")
+            .unwrap();
+
+        // as a test adapter in a shell would report them
+        let outcomes: Vec<Outcome> = serde_json::from_str(
+            r#"[
+                { "response": { "Ok": { "response": { "Get": { "value": [], "version": 0 } } } } },
+                { "response": { "Ok": { "response": { "Exists": { "is_present": false } } } } },
+                { "response": { "Err": { "error": { "Io": { "message": "not found" } } } } }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fixture.verify(&outcomes),
+            Err(Violation {
+                capability: "keyValue".to_string(),
+                fixture: "missingKey".to_string(),
+                step: 2,
+                message: r#"expected Equals(Object {"Ok": Object {"response": Object {"Delete": Object {"previous": Array []}}}}), got {"Err":{"error":{"Io":{"message":"not found"}}}}"#.to_string(),
+            })
+        );
+        assert!(fixture.verify(&outcomes[..2]).is_err());
+    }
+
+    #[test]
+    fn fixtures_are_exported_for_the_shells() {
+        let dir = std::env::temp_dir().join("crux_contract_export_test");
+        crux_contract::export(&dir, "http://localhost:8080").unwrap();
+
+        let exported: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.join("time.json")).unwrap()).unwrap();
+        assert_eq!(exported[1]["name"], "notifyAfter");
+        assert_eq!(
+            exported[1]["steps"][0]["operation"],
+            serde_json::json!({ "notifyAfter": { "nanos": 100_000_000 } })
+        );
+        assert_eq!(exported[1]["steps"][0]["minElapsedMs"], 100);
+
+        for file in ["http.json", "key_value.json"] {
+            assert!(dir.join(file).exists());
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
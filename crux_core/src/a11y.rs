@@ -0,0 +1,146 @@
+//! Accessibility metadata for the view model.
+//!
+//! Screens which are largely defined by the core's state should also carry the semantics
+//! screen readers need. The types in this module can be embedded in view model structs, and
+//! are picked up by the type generation like any other view model type, so that every shell
+//! can map them onto its platform's accessibility APIs in the same way. As with other enums
+//! nested in the view model, register [`A11yRole`] and [`LiveRegion`] with
+//! `TypeGen::register_type` so that all their variants are generated.
+//!
+//! ```rust
+//! use crux_core::a11y::{A11y, A11yRole, LiveRegion};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct ViewModel {
+//!     count: String,
+//!     count_a11y: A11y,
+//! }
+//!
+//! let view = ViewModel {
+//!     count: "3".to_string(),
+//!     count_a11y: A11y::label("3 items in your basket")
+//!         .with_role(A11yRole::Text)
+//!         .with_live_region(LiveRegion::Polite),
+//! };
+//!
+//! assert_eq!(view.count_a11y.label.as_str(), "3 items in your basket");
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// A short description of an element, read out by screen readers in place of
+/// (or in addition to) its visible content. An empty label means there is none.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct A11yLabel(pub String);
+
+/// A description of what happens when the user interacts with an element,
+/// e.g. "Opens the basket". An empty hint means there is none.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct A11yHint(pub String);
+
+macro_rules! text_impls {
+    ($name:ident) => {
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                Self(value.to_string())
+            }
+        }
+    };
+}
+
+text_impls!(A11yLabel);
+text_impls!(A11yHint);
+
+/// How urgently screen readers should announce changes to an element.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LiveRegion {
+    /// Changes are not announced
+    #[default]
+    Off,
+    /// Changes are announced when the user is idle
+    Polite,
+    /// Changes are announced immediately, interrupting the current announcement
+    Assertive,
+}
+
+/// The kind of element, for screen readers to describe and offer the right interactions.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum A11yRole {
+    /// No particular role, the shell's default for the element is used
+    #[default]
+    None,
+    Button,
+    Link,
+    Header,
+    Image,
+    Text,
+    TextField,
+    Toggle,
+    Slider,
+    List,
+    ListItem,
+    Alert,
+}
+
+/// Accessibility metadata for a single element of the view.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct A11y {
+    pub label: A11yLabel,
+    pub hint: A11yHint,
+    pub role: A11yRole,
+    pub live_region: LiveRegion,
+    /// Whether the element should be hidden from screen readers, e.g. because it's
+    /// purely decorative
+    pub hidden: bool,
+}
+
+impl A11y {
+    /// Metadata with the given label
+    pub fn label(label: impl Into<A11yLabel>) -> Self {
+        Self {
+            label: label.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Metadata for an element hidden from screen readers
+    pub fn hidden() -> Self {
+        Self {
+            hidden: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<A11yHint>) -> Self {
+        self.hint = hint.into();
+        self
+    }
+
+    pub fn with_role(mut self, role: A11yRole) -> Self {
+        self.role = role;
+        self
+    }
+
+    pub fn with_live_region(mut self, live_region: LiveRegion) -> Self {
+        self.live_region = live_region;
+        self
+    }
+}
@@ -2,11 +2,16 @@ mod registry;
 mod request_serde;
 
 use bincode::{DefaultOptions, Options};
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
 use erased_serde::Serialize as _;
 use serde::{Deserialize, Serialize};
 
+use crate::capability::ShellQuery;
+use crate::memory::MemoryPressure;
+use crate::preview::Preview;
 use crate::Effect;
-use crate::{App, Core};
+use crate::{App, Core, Queryable, ResolveError};
 use registry::ResolveRegistry;
 // ResolveByte is public to be accessible from crux_macros
 #[doc(hidden)]
@@ -69,6 +74,27 @@ where
         return_buffer
     }
 
+    /// Receive an event from the shell, passing each serialized effect request to the
+    /// `request_out` callback as soon as the core requests it, see [`Core::process_event_stream`].
+    pub fn process_event_with<F>(&self, event: &[u8], mut request_out: F)
+    where
+        A::Event: for<'a> Deserialize<'a>,
+        F: FnMut(Vec<u8>),
+    {
+        let options = Self::bincode_options();
+
+        let mut deser = bincode::Deserializer::from_slice(event, options);
+
+        self.inner.process_event_with(&mut deser, |request| {
+            let mut buffer = vec![];
+            request
+                .serialize(&mut bincode::Serializer::new(&mut buffer, options))
+                .expect("Request serialization failed.");
+
+            request_out(buffer);
+        });
+    }
+
     /// Receive a response to a capability request from the shell.
     ///
     /// The `output` is serialized capability output. It will be deserialized by the core.
@@ -87,7 +113,82 @@ where
         let mut return_buffer = vec![];
         let mut ser = bincode::Serializer::new(&mut return_buffer, options);
 
-        self.inner.handle_response(uuid, &mut deser, &mut ser);
+        self.inner.resume(uuid, &mut deser, Some(output), &mut ser);
+
+        return_buffer
+    }
+
+    /// The responses from the shell which could not be resolved, most recent last.
+    /// See [`BridgeWithSerializer::dead_letters`].
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.inner.dead_letters()
+    }
+
+    /// Take the responses from the shell which could not be resolved, emptying the queue.
+    pub fn take_dead_letters(&self) -> Vec<DeadLetter> {
+        self.inner.take_dead_letters()
+    }
+
+    /// Collect the effect requests made by [`App::init`](crate::App::init), see [`Core::start`].
+    /// Returns the serialized effect requests.
+    pub fn start(&self) -> Vec<u8> {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+        let mut ser = bincode::Serializer::new(&mut return_buffer, options);
+
+        self.inner.start(&mut ser);
+
+        return_buffer
+    }
+
+    /// Resolve the requests which timed out, see [`Core::check_timeouts`].
+    /// Returns the serialized effect requests.
+    pub fn check_timeouts(&self) -> Vec<u8> {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+        let mut ser = bincode::Serializer::new(&mut return_buffer, options);
+
+        self.inner.check_timeouts(&mut ser);
+
+        return_buffer
+    }
+
+    /// Start shutting down the app, see [`Core::shutdown`].
+    /// Returns the serialized effect requests.
+    pub fn shutdown(&self, grace: Duration) -> Vec<u8> {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+        let mut ser = bincode::Serializer::new(&mut return_buffer, options);
+
+        self.inner.shutdown(grace, &mut ser);
+
+        return_buffer
+    }
+
+    /// Get how far the core got shutting down (serialized), see [`Core::shutdown_status`].
+    pub fn shutdown_status(&self) -> Vec<u8> {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+
+        self.inner
+            .shutdown_status(&mut bincode::Serializer::new(&mut return_buffer, options));
+
+        return_buffer
+    }
+
+    /// Get the counters of the effect requests made by each capability (serialized),
+    /// see [`Core::metrics`].
+    pub fn metrics(&self) -> Vec<u8> {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+
+        self.inner
+            .metrics(&mut bincode::Serializer::new(&mut return_buffer, options));
 
         return_buffer
     }
@@ -104,13 +205,89 @@ where
         return_buffer
     }
 
+    /// Answer a serialized `query` about the current state of the app, see [`Queryable`].
+    /// Returns the serialized response.
+    pub fn query(&self, query: &[u8]) -> Vec<u8>
+    where
+        A: Queryable,
+        A::Query: for<'a> Deserialize<'a>,
+    {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+
+        self.inner.query(
+            &mut bincode::Deserializer::from_slice(query, options),
+            &mut bincode::Serializer::new(&mut return_buffer, options),
+        );
+
+        return_buffer
+    }
+
+    /// Answer a serialized `query` about the work the capabilities are tracking, see
+    /// [`ShellQuery`]. Returns the serialized response.
+    pub fn query_capabilities(&self, query: &[u8]) -> Vec<u8>
+    where
+        A::Capabilities: ShellQuery,
+        <A::Capabilities as ShellQuery>::Query: for<'a> Deserialize<'a>,
+    {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+
+        self.inner.query_capabilities(
+            &mut bincode::Deserializer::from_slice(query, options),
+            &mut bincode::Serializer::new(&mut return_buffer, options),
+        );
+
+        return_buffer
+    }
+
+    /// Trim the caches in the core when the platform sends a memory warning, given the
+    /// serialized [`MemoryPressure`]. Returns the serialized [`TrimReport`](crate::memory::TrimReport).
+    pub fn trim_memory(&self, pressure: &[u8]) -> Vec<u8> {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+
+        self.inner.trim_memory(
+            &mut bincode::Deserializer::from_slice(pressure, options),
+            &mut bincode::Serializer::new(&mut return_buffer, options),
+        );
+
+        return_buffer
+    }
+
+    /// Get the first `count` samples of the app's view model for UI previews, see
+    /// [`Preview`]. Returns the serialized samples.
+    pub fn previews(&self, count: usize) -> Vec<u8>
+    where
+        A::ViewModel: Preview,
+    {
+        let options = Self::bincode_options();
+
+        let mut return_buffer = vec![];
+
+        self.inner.previews(
+            count,
+            &mut bincode::Serializer::new(&mut return_buffer, options),
+        );
+
+        return_buffer
+    }
+
     fn bincode_options() -> impl bincode::Options + Copy {
-        DefaultOptions::new()
-            .with_fixint_encoding()
-            .allow_trailing_bytes()
+        bincode_options()
     }
 }
 
+/// The options of the bincode format the [`Bridge`] uses
+pub(crate) fn bincode_options() -> impl bincode::Options + Copy {
+    DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+}
+
 /// A bridge with a user supplied serializer
 ///
 /// This is exactly the same as [`Bridge`], except instead of using the default
@@ -129,9 +306,26 @@ where
 {
     core: Core<Eff, A>,
     registry: ResolveRegistry,
+    dead_letters: Mutex<VecDeque<DeadLetter>>,
 }
 // ANCHOR_END: bridge_with_serializer
 
+/// How many dead letters are kept, older ones are dropped first.
+const DEAD_LETTER_CAPACITY: usize = 100;
+
+/// A response from the shell which could not be resolved, e.g. because it didn't deserialize
+/// into the output the request expected, or because the request was already resolved.
+/// These point to a bug in the shell (or a mismatch between the shell and core versions), and
+/// are kept by the bridge for diagnosis, see [`Bridge::dead_letters`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// The `uuid` of the request the shell was resolving
+    pub uuid: Vec<u8>,
+    /// The serialized response, if it was passed to the bridge as bytes
+    pub response: Option<Vec<u8>>,
+    pub error: ResolveError,
+}
+
 impl<Eff, A> BridgeWithSerializer<Eff, A>
 where
     Eff: Effect,
@@ -141,6 +335,7 @@ where
         Self {
             core,
             registry: Default::default(),
+            dead_letters: Mutex::default(),
         }
     }
 
@@ -155,55 +350,137 @@ where
         S: ::serde::ser::Serializer,
     {
         let mut erased_de = <dyn erased_serde::Deserializer>::erase(event);
-        self.process(
-            None,
-            &mut erased_de,
+        let shell_event =
+            erased_serde::deserialize(&mut erased_de).expect("Message deserialization failed.");
+
+        self.serialize_requests(
+            self.core.process_event(shell_event),
             &mut <dyn erased_serde::Serializer>::erase(requests_out),
         );
     }
 
+    /// Receive an event from the shell, passing each effect request to the `request_out`
+    /// callback as soon as the core requests it, see [`Core::process_event_stream`].
+    pub fn process_event_with<'de, D, F>(&self, event: D, mut request_out: F)
+    where
+        for<'a> A::Event: Deserialize<'a>,
+        D: ::serde::de::Deserializer<'de> + 'de,
+        F: FnMut(Request<Eff::Ffi>),
+    {
+        let mut erased_de = <dyn erased_serde::Deserializer>::erase(event);
+        let shell_event =
+            erased_serde::deserialize(&mut erased_de).expect("Message deserialization failed.");
+
+        self.core.process_event_with(shell_event, |effect| {
+            request_out(self.registry.register(effect));
+        });
+    }
+
     /// Receive a response to a capability request from the shell.
     ///
     /// The `output` is serialized capability output. It will be deserialized by the core.
-    /// The `uuid` MUST match the `uuid` of the effect that triggered it, else the core will panic.
+    /// The `uuid` should match the `uuid` of the effect that triggered it. Responses which
+    /// can't be resolved are kept as [dead letters](BridgeWithSerializer::dead_letters).
     pub fn handle_response<'de, D, S>(&self, uuid: &[u8], response: D, requests_out: S)
     where
         for<'a> A::Event: Deserialize<'a>,
         D: ::serde::de::Deserializer<'de>,
         S: ::serde::ser::Serializer,
     {
-        let mut erased_response = <dyn erased_serde::Deserializer>::erase(response);
-        self.process(
-            Some(uuid),
-            &mut erased_response,
-            &mut <dyn erased_serde::Serializer>::erase(requests_out),
-        );
+        self.resume(uuid, response, None, requests_out);
     }
 
-    fn process(
+    // `raw_response` is the serialized response, if the caller has it, to keep in a dead letter
+    pub(crate) fn resume<'de, D, S>(
         &self,
-        uuid: Option<&[u8]>,
-        data: &mut dyn erased_serde::Deserializer,
-        requests_out: &mut dyn erased_serde::Serializer,
+        uuid: &[u8],
+        response: D,
+        raw_response: Option<&[u8]>,
+        requests_out: S,
     ) where
-        A::Event: for<'a> Deserialize<'a>,
+        D: ::serde::de::Deserializer<'de>,
+        S: ::serde::ser::Serializer,
     {
-        let effects = match uuid {
-            None => {
-                let shell_event =
-                    erased_serde::deserialize(data).expect("Message deserialization failed.");
+        let mut erased_response = <dyn erased_serde::Deserializer>::erase(response);
 
-                self.core.process_event(shell_event)
+        if let Err(error) = self.registry.resume(uuid, &mut erased_response) {
+            let mut dead_letters = self
+                .dead_letters
+                .lock()
+                .expect("Dead letters Mutex was poisoned.");
+            if dead_letters.len() == DEAD_LETTER_CAPACITY {
+                dead_letters.pop_front();
             }
-            Some(uuid) => {
-                self.registry.resume(uuid, data).expect(
-                    "Response could not be handled. The request did not expect a response.",
-                );
+            dead_letters.push_back(DeadLetter {
+                uuid: uuid.to_vec(),
+                response: raw_response.map(<[u8]>::to_vec),
+                error,
+            });
+        }
 
-                self.core.process()
-            }
-        };
+        self.serialize_requests(
+            self.core.process(),
+            &mut <dyn erased_serde::Serializer>::erase(requests_out),
+        );
+    }
+
+    /// The responses from the shell which could not be resolved, most recent last.
+    ///
+    /// Instead of panicking, the bridge keeps the last 100 of these for diagnosis, e.g. to
+    /// be included in a bug report.
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters
+            .lock()
+            .expect("Dead letters Mutex was poisoned.")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Take the responses from the shell which could not be resolved, emptying the queue.
+    pub fn take_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters
+            .lock()
+            .expect("Dead letters Mutex was poisoned.")
+            .drain(..)
+            .collect()
+    }
+
+    /// Collect the effect requests made by [`App::init`](crate::App::init), see [`Core::start`].
+    ///
+    /// The returned requests are serialized into `requests_out`.
+    pub fn start<S>(&self, requests_out: S)
+    where
+        S: ::serde::ser::Serializer,
+    {
+        let effects = self.core.start();
+
+        self.serialize_requests(
+            effects,
+            &mut <dyn erased_serde::Serializer>::erase(requests_out),
+        );
+    }
 
+    /// Resolve the requests which timed out, see [`Core::check_timeouts`].
+    ///
+    /// The returned requests are serialized into `requests_out`.
+    pub fn check_timeouts<S>(&self, requests_out: S)
+    where
+        S: ::serde::ser::Serializer,
+    {
+        let effects = self.core.check_timeouts();
+
+        self.serialize_requests(
+            effects,
+            &mut <dyn erased_serde::Serializer>::erase(requests_out),
+        );
+    }
+
+    fn serialize_requests(
+        &self,
+        effects: Vec<Eff>,
+        requests_out: &mut dyn erased_serde::Serializer,
+    ) {
         let requests: Vec<_> = effects
             .into_iter()
             .map(|eff| self.registry.register(eff))
@@ -214,6 +491,44 @@ where
             .expect("Request serialization failed.")
     }
 
+    /// Start shutting down the app, see [`Core::shutdown`].
+    ///
+    /// The returned requests are serialized into `requests_out`.
+    pub fn shutdown<S>(&self, grace: Duration, requests_out: S)
+    where
+        S: ::serde::ser::Serializer,
+    {
+        let effects = self.core.shutdown(grace);
+
+        self.serialize_requests(
+            effects,
+            &mut <dyn erased_serde::Serializer>::erase(requests_out),
+        );
+    }
+
+    /// Get how far the core got shutting down (serialized), see [`Core::shutdown_status`].
+    pub fn shutdown_status<S>(&self, ser: S)
+    where
+        S: ::serde::ser::Serializer,
+    {
+        self.core
+            .shutdown_status()
+            .serialize(ser)
+            .expect("Shutdown status should serialize");
+    }
+
+    /// Get the counters of the effect requests made by each capability (serialized),
+    /// see [`Core::metrics`].
+    pub fn metrics<S>(&self, ser: S)
+    where
+        S: ::serde::ser::Serializer,
+    {
+        self.core
+            .metrics()
+            .serialize(ser)
+            .expect("Metrics should serialize");
+    }
+
     /// Get the current state of the app's view model (serialized).
     pub fn view<S>(&self, ser: S)
     where
@@ -224,4 +539,65 @@ where
             .erased_serialize(&mut <dyn erased_serde::Serializer>::erase(ser))
             .expect("View should serialize")
     }
+
+    /// Answer a serialized `query` about the current state of the app, see [`Queryable`].
+    pub fn query<'de, D, S>(&self, query: D, response_out: S)
+    where
+        A: Queryable,
+        for<'a> A::Query: Deserialize<'a>,
+        D: ::serde::de::Deserializer<'de>,
+        S: ::serde::ser::Serializer,
+    {
+        let query = A::Query::deserialize(query).expect("Query deserialization failed.");
+
+        self.core
+            .query(query)
+            .erased_serialize(&mut <dyn erased_serde::Serializer>::erase(response_out))
+            .expect("Query response should serialize")
+    }
+
+    /// Answer a serialized `query` about the work the capabilities are tracking, see
+    /// [`ShellQuery`].
+    pub fn query_capabilities<'de, D, S>(&self, query: D, response_out: S)
+    where
+        A::Capabilities: ShellQuery,
+        for<'a> <A::Capabilities as ShellQuery>::Query: Deserialize<'a>,
+        D: ::serde::de::Deserializer<'de>,
+        S: ::serde::ser::Serializer,
+    {
+        let query = <A::Capabilities as ShellQuery>::Query::deserialize(query)
+            .expect("Capabilities query deserialization failed.");
+
+        self.core
+            .query_capabilities(query)
+            .erased_serialize(&mut <dyn erased_serde::Serializer>::erase(response_out))
+            .expect("Capabilities query response should serialize")
+    }
+
+    /// Trim the caches in the core, given the serialized [`MemoryPressure`].
+    pub fn trim_memory<'de, D, S>(&self, pressure: D, report_out: S)
+    where
+        D: ::serde::de::Deserializer<'de>,
+        S: ::serde::ser::Serializer,
+    {
+        let pressure =
+            MemoryPressure::deserialize(pressure).expect("Memory pressure deserialization failed.");
+
+        self.core
+            .trim_memory(pressure)
+            .serialize(report_out)
+            .expect("Trim report should serialize");
+    }
+
+    /// Get the first `count` samples of the app's view model for UI previews, see
+    /// [`Preview`].
+    pub fn previews<S>(&self, count: usize, previews_out: S)
+    where
+        A::ViewModel: Preview,
+        S: ::serde::ser::Serializer,
+    {
+        A::ViewModel::previews(count)
+            .erased_serialize(&mut <dyn erased_serde::Serializer>::erase(previews_out))
+            .expect("Previews should serialize")
+    }
 }
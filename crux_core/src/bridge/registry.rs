@@ -56,16 +56,13 @@ impl ResolveRegistry {
     ) -> Result<(), ResolveError> {
         let mut registry_lock = self.0.lock().expect("Registry Mutex poisoned");
 
-        let entry = {
-            let mut uuid_buf = [0; 16];
-            uuid_buf.copy_from_slice(uuid);
-
-            registry_lock.entry(uuid_buf)
+        let not_found = || ResolveError::NotFound {
+            uuid: uuid.to_vec(),
         };
+        let uuid_buf: [u8; 16] = uuid.try_into().map_err(|_| not_found())?;
 
-        let Entry::Occupied(mut entry) = entry else {
-            // FIXME return an Err instead of panicking here.
-            panic!("Request with UUID {uuid:?} not found.");
+        let Entry::Occupied(mut entry) = registry_lock.entry(uuid_buf) else {
+            return Err(not_found());
         };
 
         let resolve = entry.get_mut();
@@ -6,9 +6,11 @@ use crate::{
 
 // used in docs/internals/bridge.md
 // ANCHOR: resolve_serialized
-type ResolveOnceSerialized = Box<dyn FnOnce(&mut dyn erased_serde::Deserializer) + Send>;
+// A once resolve is only consumed when the response deserializes, so the shell can retry
+type ResolveOnceSerialized =
+    Box<dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), ResolveError> + Send>;
 type ResolveManySerialized =
-    Box<dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), ()> + Send>;
+    Box<dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), ResolveError> + Send>;
 
 /// A deserializing version of Resolve
 ///
@@ -30,14 +32,12 @@ impl ResolveSerialized {
     ) -> Result<(), ResolveError> {
         match self {
             ResolveSerialized::Never => Err(ResolveError::Never),
-            ResolveSerialized::Many(f) => f(bytes).map_err(|_| ResolveError::FinishedMany),
-            ResolveSerialized::Once(_) => {
+            ResolveSerialized::Many(f) => f(bytes),
+            ResolveSerialized::Once(f) => {
+                f(bytes)?;
+
                 // The resolve has been used, turn it into a Never
-                if let ResolveSerialized::Once(f) =
-                    std::mem::replace(self, ResolveSerialized::Never)
-                {
-                    f(bytes);
-                }
+                *self = ResolveSerialized::Never;
 
                 Ok(())
             }
@@ -63,7 +63,9 @@ where
         let (operation, resolve) = (self.operation, self.resolve);
 
         let resolve = resolve.deserializing(move |deserializer| {
-            erased_serde::deserialize(deserializer).expect("Deserialization failed")
+            erased_serde::deserialize(deserializer).map_err(|e| ResolveError::Deserialize {
+                message: e.to_string(),
+            })
         });
 
         (effect(operation), resolve)
@@ -75,18 +77,29 @@ impl<Out> Resolve<Out> {
     /// The `func` argument is a 'deserializer' converting from bytes into the `Out` type.
     fn deserializing<F>(self, mut func: F) -> ResolveSerialized
     where
-        F: (FnMut(&mut dyn erased_serde::Deserializer) -> Out) + Send + Sync + 'static,
+        F: (FnMut(&mut dyn erased_serde::Deserializer) -> Result<Out, ResolveError>)
+            + Send
+            + Sync
+            + 'static,
         Out: 'static,
     {
         match self {
             Resolve::Never => ResolveSerialized::Never,
-            Resolve::Once(resolve) => ResolveSerialized::Once(Box::new(move |deser| {
-                let out = func(deser);
-                resolve(out)
-            })),
+            Resolve::Once(resolve) => {
+                let mut resolve = Some(resolve);
+
+                ResolveSerialized::Once(Box::new(move |deser| {
+                    let out = func(deser)?;
+                    if let Some(resolve) = resolve.take() {
+                        resolve(out);
+                    }
+
+                    Ok(())
+                }))
+            }
             Resolve::Many(resolve) => ResolveSerialized::Many(Box::new(move |deser| {
-                let out = func(deser);
-                resolve(out)
+                let out = func(deser)?;
+                resolve(out).map_err(|()| ResolveError::FinishedMany)
             })),
         }
     }
@@ -0,0 +1,195 @@
+//! A capability for publishing messages between composed apps.
+//!
+//! When an app is composed from child apps, siblings often need to react to each other's domain
+//! events (e.g. "user logged out"), and routing each of those messages through the parent's
+//! `update` by hand quickly gets tedious. The [`Bus`] lets any app publish a message on a typed
+//! [`Topic`], and delivers it as an event to every app which subscribed to the topic.
+
+use std::{
+    any::{Any, TypeId},
+    sync::{Arc, Mutex},
+};
+
+use crate::capability::{CapabilityContext, Never};
+use crate::Capability;
+
+/// A type of message which can be published on the [`Bus`].
+pub trait Topic: Clone + Send + 'static {}
+
+type Handler = Arc<dyn Fn(&dyn Any) + Send + Sync>;
+
+struct Subscription {
+    topic: TypeId,
+    subscriber: TypeId,
+    handler: Handler,
+}
+
+/// The Bus capability delivers messages published by one app to the apps subscribed to the
+/// message's [`Topic`].
+///
+/// The capabilities of child apps are created from the parent's with
+/// [`map_event`](Capability::map_event), and all the `Bus` instances created this way share the
+/// same subscriptions. A child app subscribes to a topic with [`Bus::subscribe`], giving a
+/// function to turn messages into its own events, and other apps [`publish`](Bus::publish)
+/// messages without knowing who is listening:
+///
+/// ```rust
+/// # use crux_core::bus::{Bus, Topic};
+/// #[derive(Clone)]
+/// pub struct LoggedOut;
+///
+/// impl Topic for LoggedOut {}
+///
+/// # enum Event { LoggedOut }
+/// # fn example(bus: &Bus<Event>) {
+/// // in the settings app
+/// bus.publish(LoggedOut);
+///
+/// // in the inbox app
+/// bus.subscribe(|_: LoggedOut| Event::LoggedOut);
+/// # }
+/// ```
+///
+/// Each app (identified by its event type) has at most one subscription to each topic, so it's
+/// safe to subscribe on every update. Messages are delivered as events after the current update
+/// finishes, in the order they were published.
+pub struct Bus<Ev> {
+    context: CapabilityContext<Never, Ev>,
+    subscriptions: Arc<Mutex<Vec<Subscription>>>,
+}
+
+impl<Ev> Clone for Bus<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+            subscriptions: self.subscriptions.clone(),
+        }
+    }
+}
+
+impl<Ev> Bus<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<Never, Ev>) -> Self {
+        Self {
+            context,
+            subscriptions: Arc::default(),
+        }
+    }
+
+    /// Publish `message` to all the subscribers of its topic.
+    pub fn publish<T: Topic>(&self, message: T) {
+        let handlers: Vec<Handler> = self
+            .subscriptions
+            .lock()
+            .expect("Bus Mutex was poisoned.")
+            .iter()
+            .filter(|subscription| subscription.topic == TypeId::of::<T>())
+            .map(|subscription| subscription.handler.clone())
+            .collect();
+
+        for handler in handlers {
+            handler(&message);
+        }
+    }
+
+    /// Receive messages published on topic `T` as events, created with `make_event`.
+    /// This replaces any previous subscription of this app to the topic.
+    pub fn subscribe<T, F>(&self, make_event: F)
+    where
+        T: Topic,
+        F: Fn(T) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let handler: Handler = Arc::new(move |message: &dyn Any| {
+            if let Some(message) = message.downcast_ref::<T>() {
+                context.update_app(make_event(message.clone()));
+            }
+        });
+
+        self.insert(TypeId::of::<T>(), TypeId::of::<Ev>(), handler);
+    }
+
+    /// Stop receiving messages published on topic `T`.
+    pub fn unsubscribe<T: Topic>(&self) {
+        self.subscriptions
+            .lock()
+            .expect("Bus Mutex was poisoned.")
+            .retain(|subscription| {
+                subscription.topic != TypeId::of::<T>()
+                    || subscription.subscriber != TypeId::of::<Ev>()
+            });
+    }
+
+    /// Record the messages published on topic `T` from now on. This is mainly useful in tests,
+    /// together with [`AppTester`](crate::testing::AppTester), which gives access to the app's
+    /// capabilities with `as_ref()`.
+    pub fn record<T: Topic>(&self) -> Published<T> {
+        let published = Published {
+            messages: Arc::default(),
+        };
+
+        let messages = published.messages.clone();
+        let handler: Handler = Arc::new(move |message: &dyn Any| {
+            if let Some(message) = message.downcast_ref::<T>() {
+                messages
+                    .lock()
+                    .expect("Published Mutex was poisoned.")
+                    .push(message.clone());
+            }
+        });
+
+        self.insert(TypeId::of::<T>(), TypeId::of::<Published<T>>(), handler);
+
+        published
+    }
+
+    fn insert(&self, topic: TypeId, subscriber: TypeId, handler: Handler) {
+        let mut subscriptions = self.subscriptions.lock().expect("Bus Mutex was poisoned.");
+
+        subscriptions.retain(|s| s.topic != topic || s.subscriber != subscriber);
+        subscriptions.push(Subscription {
+            topic,
+            subscriber,
+            handler,
+        });
+    }
+}
+
+/// Messages published on topic `T`, see [`Bus::record`].
+pub struct Published<T> {
+    messages: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T: Clone> Published<T> {
+    /// The messages published so far
+    pub fn messages(&self) -> Vec<T> {
+        self.messages
+            .lock()
+            .expect("Published Mutex was poisoned.")
+            .clone()
+    }
+
+    /// Take the messages published so far, clearing the record
+    pub fn take(&self) -> Vec<T> {
+        std::mem::take(&mut *self.messages.lock().expect("Published Mutex was poisoned."))
+    }
+}
+
+impl<Ev> Capability<Ev> for Bus<Ev> {
+    type Operation = Never;
+    type MappedSelf<MappedEv> = Bus<MappedEv>;
+
+    fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+    where
+        F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+        NewEv: 'static,
+    {
+        Bus {
+            context: self.context.map_event(f),
+            subscriptions: self.subscriptions.clone(),
+        }
+    }
+}
@@ -1,9 +1,9 @@
 //! A capability which can spawn tasks which orchestrate across other capabilities. This
 //! is useful for orchestrating a number of different effects into a single transaction.
 
-use crate::capability::{CapabilityContext, Never};
+use crate::capability::{AbortHandle, CapabilityContext, Never};
 use crate::Capability;
-use futures::Future;
+use futures::{future, Future, FutureExt};
 
 /// Compose capability can be used to orchestrate effects into a single transaction.
 ///
@@ -110,6 +110,61 @@ impl<Ev> Compose<Ev> {
         let context = self.context.clone();
         self.context.spawn(effects_task(ComposeContext { context }));
     }
+
+    /// Spawn a task like [`spawn`](Compose::spawn), returning a handle to
+    /// [abort](AbortHandle::abort) it with. Aborting drops the task along with the effects it's
+    /// waiting for, and it sends no more events to the app.
+    pub fn spawn_abortable<F, Fut>(&self, effects_task: F) -> AbortHandle
+    where
+        F: FnOnce(ComposeContext<Ev>) -> Fut,
+        Fut: Future<Output = ()> + 'static + Send,
+        Ev: 'static,
+    {
+        let context = self.context.clone();
+        self.context
+            .spawn_abortable(effects_task(ComposeContext { context }))
+    }
+
+    /// Race the `contenders`, each a future resulting in an event, e.g. fetching from one of
+    /// several mirrors, or reading from a cache and the network. The first to finish sends its
+    /// event to the app, and the others are dropped, along with the effects they're waiting
+    /// for: the shell's late responses to them are ignored. If two finish at the same time,
+    /// the earlier one in `contenders` wins.
+    ///
+    /// To tell the shell to stop working on the requests of the contenders which lost, wrap
+    /// them with [`CapabilityContext::notifying_on_cancel`]. The returned handle aborts the
+    /// whole race.
+    ///
+    /// ```rust,ignore
+    /// let cache = caps.key_value.clone();
+    /// let http = caps.http.clone();
+    ///
+    /// caps.compose.select([
+    ///     async move { Event::Cached(cache.get_async(key).await) }.boxed(),
+    ///     async move { Event::Fetched(http.get(url).send_async().await) }.boxed(),
+    /// ]);
+    /// ```
+    pub fn select<I, Fut>(&self, contenders: I) -> AbortHandle
+    where
+        I: IntoIterator<Item = Fut>,
+        Fut: Future<Output = Ev> + Send + 'static,
+        Ev: 'static,
+    {
+        let contenders: Vec<_> = contenders.into_iter().map(FutureExt::boxed).collect();
+        let context = self.context.clone();
+
+        self.context.spawn_abortable(async move {
+            if contenders.is_empty() {
+                return;
+            }
+
+            let (event, _, losers) = future::select_all(contenders).await;
+            // cancel the losers before the app hears about the winner
+            drop(losers);
+
+            context.update_app(event);
+        })
+    }
 }
 
 impl<E> Clone for Compose<E> {
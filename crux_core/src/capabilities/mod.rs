@@ -1,2 +1,3 @@
+pub mod bus;
 pub mod compose;
 pub mod render;
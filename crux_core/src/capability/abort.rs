@@ -0,0 +1,169 @@
+//! Aborting tasks, see [`CapabilityContext::spawn_abortable`](super::CapabilityContext::spawn_abortable)
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use futures::{future, Future, FutureExt};
+
+type Notice = Box<dyn FnOnce() + Send>;
+
+/// A handle to a task spawned with
+/// [`spawn_abortable`](super::CapabilityContext::spawn_abortable), which abandons the task.
+///
+/// Apps typically keep the handle in the model, e.g. for a fetch started when a screen opens,
+/// and abort it when the user navigates away. Aborting drops the task's future the next time
+/// the core runs its tasks, along with the requests it is waiting for: the shell's late
+/// responses to them are ignored, and no more events are sent to the app from the task.
+/// Aborting a task which has already finished does nothing.
+///
+/// The handle can be cloned, and doesn't abort the task when dropped.
+#[derive(Clone)]
+pub struct AbortHandle {
+    handle: future::AbortHandle,
+    // taken when the task finishes, or by the first abort
+    notice: Arc<Mutex<Option<Notice>>>,
+}
+
+impl AbortHandle {
+    /// Abort the task. If it was spawned with
+    /// [`spawn_abortable_notifying`](super::CapabilityContext::spawn_abortable_notifying)
+    /// and hasn't finished, the shell is notified that its requests are no longer needed.
+    pub fn abort(&self) {
+        self.handle.abort();
+
+        let notice = self
+            .notice
+            .lock()
+            .expect("AbortHandle Mutex was poisoned.")
+            .take();
+        if let Some(notice) = notice {
+            notice();
+        }
+    }
+
+    /// Whether [`abort`](Self::abort) has been called
+    pub fn is_aborted(&self) -> bool {
+        self.handle.is_aborted()
+    }
+}
+
+impl fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortHandle")
+            .field("aborted", &self.is_aborted())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wrap the `task` so it can be aborted with the returned handle, calling `notice` if it's
+/// aborted before it finishes.
+pub(crate) fn abortable(
+    task: impl Future<Output = ()> + Send + 'static,
+    notice: Option<Notice>,
+) -> (impl Future<Output = ()> + Send + 'static, AbortHandle) {
+    let (task, handle) = future::abortable(task);
+    let notice = Arc::new(Mutex::new(notice));
+
+    let finished = notice.clone();
+    let task = task.map(move |_| {
+        finished
+            .lock()
+            .expect("AbortHandle Mutex was poisoned.")
+            .take();
+    });
+
+    (task, AbortHandle { handle, notice })
+}
+
+/// Wrap the `future` so that `notice` is called if it's dropped after it started and before
+/// it finished, e.g. because it lost a race, or its task was aborted.
+pub(crate) async fn notifying_on_cancel<T>(future: impl Future<Output = T>, notice: Notice) -> T {
+    let mut guard = CancelGuard(Some(notice));
+    let output = future.await;
+    guard.0 = None;
+
+    output
+}
+
+struct CancelGuard(Option<Notice>);
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if let Some(notice) = self.0.take() {
+            notice();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Context,
+    };
+
+    use futures::{executor::block_on, task::noop_waker_ref};
+
+    use super::*;
+
+    fn counter() -> (Arc<AtomicUsize>, Notice) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let notice = {
+            let count = count.clone();
+            Box::new(move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        (count, notice)
+    }
+
+    #[test]
+    fn aborting_notifies_once() {
+        let (notices, notice) = counter();
+        let (task, handle) = abortable(future::pending(), Some(notice));
+
+        handle.abort();
+        handle.clone().abort();
+        assert!(handle.is_aborted());
+        assert_eq!(notices.load(Ordering::SeqCst), 1);
+
+        // the task completes without running the future
+        block_on(task);
+    }
+
+    #[test]
+    fn dropping_a_started_future_notifies() {
+        let (notices, notice) = counter();
+        let mut future = notifying_on_cancel(future::pending::<()>(), notice).boxed();
+
+        let mut context = Context::from_waker(noop_waker_ref());
+        assert!(future.poll_unpin(&mut context).is_pending());
+        drop(future);
+        assert_eq!(notices.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn finished_futures_dont_notify() {
+        let (notices, notice) = counter();
+        assert_eq!(block_on(notifying_on_cancel(future::ready(1), notice)), 1);
+
+        let (unstarted, notice) = counter();
+        drop(notifying_on_cancel(future::pending::<()>(), notice));
+
+        assert_eq!(notices.load(Ordering::SeqCst), 0);
+        assert_eq!(unstarted.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn aborting_a_finished_task_does_nothing() {
+        let (notices, notice) = counter();
+        let (task, handle) = abortable(future::ready(()), Some(notice));
+
+        block_on(task);
+        handle.abort();
+        assert_eq!(notices.load(Ordering::SeqCst), 0);
+    }
+}
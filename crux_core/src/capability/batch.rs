@@ -0,0 +1,74 @@
+//! Sending several operations to the shell as one effect
+
+use super::{CapabilityContext, Operation, ShellTimeout};
+
+/// An operation which can carry a batch of operations of its own type, for example with a
+/// `Batch(Vec<Self>)` variant, so that a capability can send several of them to the shell as
+/// one effect with [`CapabilityContext::request_batch`].
+///
+/// The shell resolves the batch with one output holding the output of each operation, in
+/// order, which lets each operation fail on its own.
+pub trait BatchOperation: Operation + Sized {
+    /// The operation carrying the `operations`
+    fn batch(operations: Vec<Self>) -> Self;
+
+    /// The output of each of the `count` operations in a batch from the `output` the shell
+    /// resolved the batch with. Must return exactly `count` outputs, so if the whole batch
+    /// failed, or the shell returned the wrong number of outputs, the missing ones should be
+    /// errors.
+    fn unbatch(output: Self::Output, count: usize) -> Vec<Self::Output>;
+}
+
+impl<Op, Ev> CapabilityContext<Op, Ev>
+where
+    Op: BatchOperation,
+    Ev: 'static,
+{
+    /// Send the `operations` to the shell as one effect, see [`BatchOperation`], and return
+    /// the output of each, in order. An empty batch isn't sent at all.
+    ///
+    /// Like [`request_from_shell`](CapabilityContext::request_from_shell), this should only
+    /// be called inside an async task created with [`CapabilityContext::spawn`]. If the batch
+    /// times out, each operation gets its share of the batch's timed out output.
+    pub async fn request_batch(&self, operations: Vec<Op>) -> Vec<Op::Output> {
+        if operations.is_empty() {
+            return Vec::new();
+        }
+
+        let count = operations.len();
+        let output = self.request_from_shell(Op::batch(operations)).await;
+
+        unbatch::<Op>(output, count)
+    }
+
+    /// Send the `operations` to the shell as one effect, like
+    /// [`request_batch`](CapabilityContext::request_batch), failing the whole batch with a
+    /// [`ShellTimeout`] if the context has a timeout and the shell doesn't resolve it in time
+    /// (see [`request_from_shell_with_timeout`](CapabilityContext::request_from_shell_with_timeout)).
+    pub async fn request_batch_with_timeout(
+        &self,
+        operations: Vec<Op>,
+    ) -> Result<Vec<Op::Output>, ShellTimeout> {
+        if operations.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let count = operations.len();
+        let output = self
+            .request_from_shell_with_timeout(Op::batch(operations))
+            .await?;
+
+        Ok(unbatch::<Op>(output, count))
+    }
+}
+
+fn unbatch<Op: BatchOperation>(output: Op::Output, count: usize) -> Vec<Op::Output> {
+    let outputs = Op::unbatch(output, count);
+    assert_eq!(
+        outputs.len(),
+        count,
+        "BatchOperation::unbatch should return an output for each operation in the batch"
+    );
+
+    outputs
+}
@@ -50,7 +50,7 @@ impl<T> Receiver<T> {
         }
     }
 
-    pub fn drain(&self) -> Drain<T> {
+    pub fn drain(&self) -> Drain<'_, T> {
         Drain { receiver: self }
     }
 }
@@ -98,6 +98,19 @@ where
             }),
         }
     }
+
+    /// A sender which only sends the values `func` returns, e.g. to divert some of them
+    pub fn filter_input<F>(&self, func: F) -> Sender<T>
+    where
+        F: Fn(T) -> Option<T> + Send + Sync + 'static,
+    {
+        Sender {
+            inner: Arc::new(FilteredInner {
+                sender: Arc::clone(&self.inner),
+                func,
+            }),
+        }
+    }
 }
 
 trait SenderInner<T> {
@@ -124,6 +137,22 @@ where
     }
 }
 
+pub struct FilteredInner<T, F> {
+    sender: Arc<dyn SenderInner<T> + Send + Sync>,
+    func: F,
+}
+
+impl<F, T> SenderInner<T> for FilteredInner<T, F>
+where
+    F: Fn(T) -> Option<T>,
+{
+    fn send(&self, value: T) {
+        if let Some(value) = (self.func)(value) {
+            self.sender.send(value)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use static_assertions::assert_impl_all;
@@ -144,5 +173,12 @@ mod tests {
         assert_eq!(recv.receive(), Some(Some(1)));
 
         assert_eq!(recv.receive(), None);
+
+        let filtered_send =
+            send.filter_input(|value: Option<i32>| value.filter(|v| v % 2 == 0).map(Some));
+        filtered_send.send(Some(1));
+        filtered_send.send(Some(2));
+        assert_eq!(recv.receive(), Some(Some(2)));
+        assert_eq!(recv.receive(), None);
     }
 }
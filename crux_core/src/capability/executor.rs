@@ -1,5 +1,8 @@
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     task::Context,
 };
 
@@ -10,10 +13,14 @@ use futures::{
     Future, FutureExt,
 };
 
+use super::runtime::{RuntimeAdapter, RuntimeTask};
+
 // used in docs/internals/runtime.md
 // ANCHOR: executor
 pub(crate) struct QueuingExecutor {
     ready_queue: Receiver<Arc<Task>>,
+    pending: Arc<AtomicUsize>,
+    shutdown_tasks: Arc<ShutdownTasks>,
 }
 // ANCHOR_END: executor
 
@@ -22,6 +29,9 @@ pub(crate) struct QueuingExecutor {
 #[derive(Clone)]
 pub struct Spawner {
     task_sender: Sender<Arc<Task>>,
+    pending: Arc<AtomicUsize>,
+    runtime: Option<Arc<dyn RuntimeAdapter>>,
+    shutdown_tasks: Arc<ShutdownTasks>,
 }
 // ANCHOR_END: spawner
 
@@ -31,23 +41,88 @@ struct Task {
     future: Mutex<Option<future::BoxFuture<'static, ()>>>,
 
     task_sender: Sender<Arc<Task>>,
+    pending: Arc<AtomicUsize>,
 }
 // ANCHOR_END: task
 
+// A task dropped before its future completed is no longer pending
+impl Drop for Task {
+    fn drop(&mut self) {
+        if self
+            .future
+            .get_mut()
+            .map_or(false, |future| future.is_some())
+        {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+// Counts the tasks spawned since the core started shutting down, see `Core::shutdown`
+#[derive(Default)]
+struct ShutdownTasks {
+    tracking: AtomicBool,
+    outstanding: Arc<AtomicUsize>,
+}
+
+// Held by a task spawned while shutting down, until it completes or is dropped
+struct Outstanding(Arc<AtomicUsize>);
+
+impl Drop for Outstanding {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 pub(crate) fn executor_and_spawner() -> (QueuingExecutor, Spawner) {
     let (task_sender, ready_queue) = crossbeam_channel::unbounded();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let shutdown_tasks = Arc::<ShutdownTasks>::default();
 
-    (QueuingExecutor { ready_queue }, Spawner { task_sender })
+    (
+        QueuingExecutor {
+            ready_queue,
+            pending: pending.clone(),
+            shutdown_tasks: shutdown_tasks.clone(),
+        },
+        Spawner {
+            task_sender,
+            pending,
+            runtime: None,
+            shutdown_tasks,
+        },
+    )
 }
 
 // used in docs/internals/runtime.md
 // ANCHOR: spawning
 impl Spawner {
     pub fn spawn(&self, future: impl Future<Output = ()> + 'static + Send) {
-        let future = future.boxed();
+        let future = if self.shutdown_tasks.tracking.load(Ordering::SeqCst) {
+            let outstanding = self.shutdown_tasks.outstanding.clone();
+            outstanding.fetch_add(1, Ordering::SeqCst);
+            let outstanding = Outstanding(outstanding);
+
+            async move {
+                let _outstanding = outstanding;
+                future.await;
+            }
+            .boxed()
+        } else {
+            future.boxed()
+        };
+        self.pending.fetch_add(1, Ordering::SeqCst);
+
+        if let Some(runtime) = &self.runtime {
+            let task = RuntimeTask::new(future, runtime.clone(), self.pending.clone());
+            runtime.spawn(task.boxed());
+            return;
+        }
+
         let task = Arc::new(Task {
             future: Mutex::new(Some(future)),
             task_sender: self.task_sender.clone(),
+            pending: self.pending.clone(),
         });
 
         self.task_sender
@@ -57,6 +132,14 @@ impl Spawner {
 }
 // ANCHOR_END: spawning
 
+impl Spawner {
+    /// Spawn the tasks on the shell's `runtime` instead of the executor.
+    pub(crate) fn with_runtime(mut self, runtime: Arc<dyn RuntimeAdapter>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+}
+
 // used in docs/internals/runtime.md
 // ANCHOR: arc_wake
 impl ArcWake for Task {
@@ -88,9 +171,30 @@ impl QueuingExecutor {
                 if future.as_mut().poll(context).is_pending() {
                     // If it's still pending, put it back
                     *future_slot = Some(future)
+                } else {
+                    self.pending.fetch_sub(1, Ordering::SeqCst);
                 }
             }
         }
     }
 }
 // ANCHOR_END: run_all
+
+impl QueuingExecutor {
+    /// The number of spawned tasks whose futures haven't completed yet, including the ones
+    /// waiting for the shell.
+    pub fn pending_tasks(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Start counting the tasks spawned from now on, see [`Self::shutdown_tasks`].
+    pub fn track_shutdown_tasks(&self) {
+        self.shutdown_tasks.tracking.store(true, Ordering::SeqCst);
+    }
+
+    /// The number of tasks spawned since [`Self::track_shutdown_tasks`] whose futures
+    /// haven't completed yet.
+    pub fn shutdown_tasks(&self) -> usize {
+        self.shutdown_tasks.outstanding.load(Ordering::SeqCst)
+    }
+}
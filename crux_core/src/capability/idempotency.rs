@@ -0,0 +1,68 @@
+//! Keys to recognise retried operations, see [`IdempotencyKey`]
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A unique key identifying a mutating operation, like a payment or a write to storage,
+/// sent to the shell as part of the operation.
+///
+/// When an operation fails ambiguously, e.g. the connection drops before the response
+/// arrives, the shell (or the server behind it) can't tell whether it already took effect.
+/// Retrying it with the same key lets them recognise the retry and apply the operation once.
+/// A key is created when the operation is, so retries of the same request share it, and
+/// separate calls get different keys.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /// A new random key, different from any other.
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for IdempotencyKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Use a key the app chose, e.g. one stored with a pending operation so that it survives
+/// the app restarting.
+impl From<String> for IdempotencyKey {
+    fn from(key: String) -> Self {
+        Self(key)
+    }
+}
+
+impl From<&str> for IdempotencyKey {
+    fn from(key: &str) -> Self {
+        Self(key.to_string())
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_keys_are_unique() {
+        let key = IdempotencyKey::new();
+
+        assert_ne!(key, IdempotencyKey::new());
+        assert_eq!(key.clone(), key);
+        assert_eq!(serde_json::to_string(&key).unwrap(), format!("\"{key}\""));
+    }
+}
@@ -0,0 +1,110 @@
+//! Limits on the requests in flight, see [`CapabilityContext::with_max_in_flight`](super::CapabilityContext::with_max_in_flight)
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+type Queued = Box<dyn FnOnce(Permit) + Send>;
+
+/// The requests of a capability which the shell is resolving, and the ones waiting for
+/// a slot, shared by all the clones of the capability's context.
+pub(crate) struct InFlight {
+    max: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: usize,
+    queue: VecDeque<Queued>,
+}
+
+impl InFlight {
+    pub(crate) fn new(max: usize) -> Arc<Self> {
+        assert!(max > 0, "at least one request must be allowed in flight");
+
+        Arc::new(Self {
+            max,
+            state: Mutex::default(),
+        })
+    }
+
+    pub(crate) fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Call `send` with a [`Permit`] once there's a free slot, straight away if there is
+    /// one already, otherwise after the requests queued before it. Returns whether the
+    /// request was queued.
+    pub(crate) fn send(self: &Arc<Self>, send: impl FnOnce(Permit) + Send + 'static) -> bool {
+        let mut state = self.state.lock().expect("InFlight Mutex was poisoned.");
+        if state.in_flight >= self.max {
+            state.queue.push_back(Box::new(send));
+            return true;
+        }
+
+        state.in_flight += 1;
+        drop(state);
+
+        send(Permit(self.clone()));
+        false
+    }
+
+    // hand the slot of a finished request to the next one in the queue
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock().expect("InFlight Mutex was poisoned.");
+        let Some(next) = state.queue.pop_front() else {
+            state.in_flight -= 1;
+            return;
+        };
+        drop(state);
+
+        next(Permit(self.clone()));
+    }
+}
+
+/// A slot for a request in flight, freed for the next request when dropped.
+pub(crate) struct Permit(Arc<InFlight>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queues_requests_over_the_limit_in_order() {
+        let in_flight = InFlight::new(2);
+        let permits = Arc::new(Mutex::new(Vec::new()));
+
+        let queued: Vec<_> = (0..4)
+            .map(|n| {
+                let permits = permits.clone();
+                in_flight.send(move |permit| permits.lock().unwrap().push((n, permit)))
+            })
+            .collect();
+        assert_eq!(queued, vec![false, false, true, true]);
+
+        let sent = |permits: &Mutex<Vec<(i32, Permit)>>| -> Vec<_> {
+            permits.lock().unwrap().iter().map(|(n, _)| *n).collect()
+        };
+        assert_eq!(sent(&permits), vec![0, 1]);
+
+        // finishing a request sends the first one queued
+        let (_, permit) = permits.lock().unwrap().remove(0);
+        drop(permit);
+        assert_eq!(sent(&permits), vec![1, 2]);
+
+        let finished: Vec<_> = permits.lock().unwrap().drain(..).collect();
+        drop(finished);
+        assert_eq!(sent(&permits), vec![3]);
+
+        permits.lock().unwrap().clear();
+        assert_eq!(in_flight.state.lock().unwrap().in_flight, 0);
+    }
+}
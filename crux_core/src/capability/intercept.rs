@@ -0,0 +1,90 @@
+//! Diverting the requests of chosen capabilities away from the shell, used by the
+//! [`AppTester`](crate::testing::AppTester) to handle them itself, e.g. with a virtual clock
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Mutex,
+};
+
+use super::Operation;
+use crate::Request;
+
+type Handler = Box<dyn FnMut(Box<dyn Any + Send>) -> Option<Box<dyn Any + Send>> + Send>;
+
+/// Handlers for the requests of capabilities, keyed by their operation type.
+#[derive(Default)]
+pub(crate) struct Intercepts {
+    handlers: Mutex<HashMap<TypeId, Handler>>,
+}
+
+impl Intercepts {
+    /// Offer the requests with the operation `Op` to the `handler` before they're sent to the
+    /// shell. The handler returns the requests it doesn't take, replacing any previous handler.
+    pub(crate) fn register<Op, F>(&self, mut handler: F)
+    where
+        Op: Operation,
+        F: FnMut(Request<Op>) -> Option<Request<Op>> + Send + 'static,
+    {
+        let handler: Handler = Box::new(move |request| {
+            let request = request
+                .downcast::<Request<Op>>()
+                .expect("intercepted request has the registered operation type");
+
+            handler(*request).map(|request| Box::new(request) as Box<dyn Any + Send>)
+        });
+
+        self.handlers
+            .lock()
+            .expect("Intercepts Mutex was poisoned.")
+            .insert(TypeId::of::<Op>(), handler);
+    }
+
+    /// Offer the `request` to the handler registered for its operation, returning it if
+    /// there's none, or the handler didn't take it.
+    pub(crate) fn offer<Op: Operation>(&self, request: Request<Op>) -> Option<Request<Op>> {
+        let mut handlers = self
+            .handlers
+            .lock()
+            .expect("Intercepts Mutex was poisoned.");
+
+        let Some(handler) = handlers.get_mut(&TypeId::of::<Op>()) else {
+            return Some(request);
+        };
+
+        handler(Box::new(request)).map(|request| {
+            *request
+                .downcast::<Request<Op>>()
+                .expect("intercepted request has the registered operation type")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, serde::Serialize)]
+    struct Ping(u8);
+
+    impl Operation for Ping {
+        type Output = ();
+    }
+
+    #[derive(PartialEq, serde::Serialize)]
+    struct Pong;
+
+    impl Operation for Pong {
+        type Output = ();
+    }
+
+    #[test]
+    fn takes_requests_of_the_registered_operation() {
+        let intercepts = Intercepts::default();
+        intercepts.register(|request: Request<Ping>| (request.operation.0 > 1).then_some(request));
+
+        assert!(intercepts.offer(Request::resolves_never(Ping(1))).is_none());
+        assert!(intercepts.offer(Request::resolves_never(Ping(2))).is_some());
+        assert!(intercepts.offer(Request::resolves_never(Pong)).is_some());
+    }
+}
@@ -0,0 +1,212 @@
+//! Counters of the effect requests each capability makes, see [`Core::metrics`](crate::Core::metrics)
+
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::{timeout::Clock, Operation};
+use crate::core::{Request, Resolve};
+
+/// The effect requests made by each capability since the core started
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectMetrics {
+    /// The capabilities which made requests, ordered by name
+    pub capabilities: Vec<CapabilityMetrics>,
+}
+
+impl EffectMetrics {
+    /// The metrics of the capability with the operation type `name`, e.g. `HttpRequest`
+    pub fn capability(&self, name: &str) -> Option<&CapabilityMetrics> {
+        self.capabilities.iter().find(|c| c.capability == name)
+    }
+}
+
+/// The effect requests made by one capability
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityMetrics {
+    /// The name of the capability's operation type, e.g. `HttpRequest`
+    pub capability: String,
+    /// Requests sent to the shell, including notifications which expect no response
+    pub requests: u64,
+    /// Responses from the shell, each response to a streaming request counts once
+    pub resolved: u64,
+    /// Requests which timed out, and responses which were rejected because their
+    /// request had already concluded
+    pub failed: u64,
+    /// Requests which waited in a queue before being sent, because the capability had
+    /// reached its limit of requests in flight
+    pub queued: u64,
+    /// The mean time the shell took to resolve requests expecting a single response,
+    /// in milliseconds
+    pub mean_latency_ms: Option<f64>,
+}
+
+/// Collects the metrics of all the capabilities of an app.
+pub(crate) struct Metrics {
+    clock: Clock,
+    capabilities: Mutex<BTreeMap<&'static str, Counters>>,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: u64,
+    resolved: u64,
+    failed: u64,
+    queued: u64,
+    latency_total: Duration,
+    latency_count: u64,
+}
+
+impl Metrics {
+    /// Count the `request` as sent, and instrument it to count its resolutions.
+    pub(crate) fn track<Op: Operation>(self: &std::sync::Arc<Self>, request: &mut Request<Op>) {
+        let name = name_of::<Op>();
+        self.update(name, |counters| counters.requests += 1);
+
+        let resolve = std::mem::replace(&mut request.resolve, Resolve::Never);
+        request.resolve = match resolve {
+            Resolve::Never => Resolve::Never,
+            Resolve::Once(f) => {
+                let metrics = self.clone();
+                let sent = self.clock.elapsed();
+
+                Resolve::Once(Box::new(move |output| {
+                    let latency = metrics.clock.elapsed().saturating_sub(sent);
+                    metrics.update(name, |counters| {
+                        counters.resolved += 1;
+                        counters.latency_total += latency;
+                        counters.latency_count += 1;
+                    });
+
+                    f(output);
+                }))
+            }
+            Resolve::Many(f) => {
+                let metrics = self.clone();
+
+                Resolve::Many(Box::new(move |output| {
+                    let result = f(output);
+                    metrics.update(name, |counters| match result {
+                        Ok(()) => counters.resolved += 1,
+                        Err(()) => counters.failed += 1,
+                    });
+
+                    result
+                }))
+            }
+        };
+    }
+
+    /// Count a request of `Op` which timed out.
+    pub(crate) fn timed_out<Op: Operation>(&self) {
+        self.update(name_of::<Op>(), |counters| counters.failed += 1);
+    }
+
+    /// Count a request of `Op` which waited for a slot to be sent.
+    pub(crate) fn queued<Op: Operation>(&self) {
+        self.update(name_of::<Op>(), |counters| counters.queued += 1);
+    }
+
+    pub(crate) fn snapshot(&self) -> EffectMetrics {
+        let capabilities = self
+            .capabilities
+            .lock()
+            .expect("Metrics Mutex was poisoned.");
+
+        EffectMetrics {
+            capabilities: capabilities
+                .iter()
+                .map(|(name, counters)| CapabilityMetrics {
+                    capability: (*name).to_string(),
+                    requests: counters.requests,
+                    resolved: counters.resolved,
+                    failed: counters.failed,
+                    queued: counters.queued,
+                    mean_latency_ms: (counters.latency_count > 0).then(|| {
+                        counters.latency_total.as_secs_f64() * 1000.0
+                            / counters.latency_count as f64
+                    }),
+                })
+                .collect(),
+        }
+    }
+
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut Counters)) {
+        let mut capabilities = self
+            .capabilities
+            .lock()
+            .expect("Metrics Mutex was poisoned.");
+
+        f(capabilities.entry(name).or_default());
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            clock: Clock::new(),
+            capabilities: Mutex::default(),
+        }
+    }
+}
+
+/// The name of the operation type without its path or generic arguments,
+/// e.g. `HttpRequest` for `crux_http::protocol::HttpRequest`
+pub(crate) fn name_of<Op>() -> &'static str {
+    let name = std::any::type_name::<Op>();
+    let name = name.split('<').next().unwrap_or(name);
+
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(PartialEq, serde::Serialize)]
+    struct Ping;
+
+    impl Operation for Ping {
+        type Output = ();
+    }
+
+    #[test]
+    fn counts_requests_and_resolutions() {
+        let metrics = Arc::<Metrics>::default();
+
+        let mut once = Request::resolves_once(Ping, |()| {});
+        metrics.track(&mut once);
+        let mut never = Request::resolves_never(Ping);
+        metrics.track(&mut never);
+        let mut many = Request::resolves_many_times(Ping, |()| Ok(()));
+        metrics.track(&mut many);
+        let mut finished = Request::resolves_many_times(Ping, |()| Err(()));
+        metrics.track(&mut finished);
+
+        once.resolve(()).unwrap();
+        many.resolve(()).unwrap();
+        many.resolve(()).unwrap();
+        finished.resolve(()).unwrap_err();
+        metrics.timed_out::<Ping>();
+        metrics.queued::<Ping>();
+
+        let snapshot = metrics.snapshot();
+        let ping = snapshot.capability("Ping").unwrap();
+        assert_eq!(
+            (ping.requests, ping.resolved, ping.failed, ping.queued),
+            (4, 3, 2, 1)
+        );
+        assert!(ping.mean_latency_ms.is_some());
+    }
+
+    #[test]
+    fn operation_names() {
+        assert_eq!(name_of::<Ping>(), "Ping");
+        assert_eq!(name_of::<Option<Ping>>(), "Option");
+        assert_eq!(name_of::<u8>(), "u8");
+    }
+}
@@ -189,16 +189,39 @@
 
 pub(crate) mod channel;
 
+mod abort;
+mod batch;
 mod executor;
+mod idempotency;
+mod in_flight;
+mod intercept;
+mod metrics;
+mod ordered_stream;
+mod runtime;
+mod shell_query;
 mod shell_request;
 mod shell_stream;
+mod timeout;
 
 use futures::Future;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+pub use abort::AbortHandle;
+pub use batch::BatchOperation;
 pub(crate) use channel::channel;
 pub(crate) use executor::{executor_and_spawner, QueuingExecutor};
-
+pub use idempotency::IdempotencyKey;
+pub(crate) use in_flight::InFlight;
+pub(crate) use intercept::Intercepts;
+pub(crate) use metrics::{name_of, Metrics};
+pub use metrics::{CapabilityMetrics, EffectMetrics};
+pub use ordered_stream::{Sequenced, StreamGap};
+pub use runtime::RuntimeAdapter;
+pub use shell_query::ShellQuery;
+pub use timeout::{ShellTimeout, TimesOut};
+pub(crate) use timeout::{Timeout, Timeouts};
+
+use crate::memory::TrimRegistry;
 use crate::Request;
 use channel::Sender;
 
@@ -412,14 +435,45 @@ where
     shell_channel: Sender<Request<Op>>,
     app_channel: Sender<Event>,
     spawner: executor::Spawner,
+    timeout: Option<Timeout<Op::Output>>,
+    timeouts: Arc<Timeouts>,
+    in_flight: Option<Arc<InFlight>>,
+    metrics: Arc<Metrics>,
+    trim_registry: TrimRegistry,
 }
 // ANCHOR_END: capability_context
 
+impl<Op, Event> ContextInner<Op, Event>
+where
+    Op: Operation,
+{
+    // A copy of the context sending its events to `app_channel`
+    fn with_app_channel<NewEvent>(
+        &self,
+        app_channel: Sender<NewEvent>,
+    ) -> ContextInner<Op, NewEvent> {
+        ContextInner {
+            shell_channel: self.shell_channel.clone(),
+            app_channel,
+            spawner: self.spawner.clone(),
+            timeout: self.timeout,
+            timeouts: self.timeouts.clone(),
+            in_flight: self.in_flight.clone(),
+            metrics: self.metrics.clone(),
+            trim_registry: self.trim_registry.clone(),
+        }
+    }
+}
+
 /// Initial version of capability Context which has not yet been specialized to a chosen capability
 pub struct ProtoContext<Eff, Event> {
     shell_channel: Sender<Eff>,
     app_channel: Sender<Event>,
     spawner: executor::Spawner,
+    timeouts: Arc<Timeouts>,
+    metrics: Arc<Metrics>,
+    trim_registry: TrimRegistry,
+    intercepts: Option<Arc<Intercepts>>,
 }
 
 impl<Op, Ev> Clone for CapabilityContext<Op, Ev>
@@ -442,14 +496,27 @@ where
         shell_channel: Sender<Eff>,
         app_channel: Sender<Ev>,
         spawner: executor::Spawner,
+        timeouts: Arc<Timeouts>,
+        metrics: Arc<Metrics>,
+        trim_registry: TrimRegistry,
     ) -> Self {
         Self {
             shell_channel,
             app_channel,
             spawner,
+            timeouts,
+            metrics,
+            trim_registry,
+            intercepts: None,
         }
     }
 
+    /// Offer the requests of the capabilities to the `intercepts` before sending them to the shell.
+    pub(crate) fn with_intercepts(mut self, intercepts: Arc<Intercepts>) -> Self {
+        self.intercepts = Some(intercepts);
+        self
+    }
+
     /// Specialize the CapabilityContext to a specific capability, wrapping its operations into
     /// an Effect `Ef`. The `func` argument will typically be an Effect variant constructor, but
     /// can be any function taking the capability's operation type and returning
@@ -462,10 +529,19 @@ where
         F: Fn(Request<Op>) -> Eff + Sync + Send + Copy + 'static,
         Op: Operation,
     {
+        let mut shell_channel = self.shell_channel.map_input(func);
+        if let Some(intercepts) = &self.intercepts {
+            let intercepts = intercepts.clone();
+            shell_channel = shell_channel.filter_input(move |request| intercepts.offer(request));
+        }
+
         CapabilityContext::new(
-            self.shell_channel.map_input(func),
+            shell_channel,
             self.app_channel.clone(),
             self.spawner.clone(),
+            self.timeouts.clone(),
+            self.metrics.clone(),
+            self.trim_registry.clone(),
         )
     }
 }
@@ -479,22 +555,144 @@ where
         shell_channel: Sender<Request<Op>>,
         app_channel: Sender<Ev>,
         spawner: executor::Spawner,
+        timeouts: Arc<Timeouts>,
+        metrics: Arc<Metrics>,
+        trim_registry: TrimRegistry,
     ) -> Self {
         let inner = Arc::new(ContextInner {
             shell_channel,
             app_channel,
             spawner,
+            timeout: None,
+            timeouts,
+            in_flight: None,
+            metrics,
+            trim_registry,
         });
 
         CapabilityContext { inner }
     }
 
+    /// Create a copy of the context in which requests the shell doesn't resolve within
+    /// `timeout` resolve with the operation's [`timed_out`](TimesOut::timed_out) output
+    /// instead, as do streams the shell doesn't respond to in time. Only capabilities whose
+    /// operation implements [`TimesOut`] can time out.
+    ///
+    /// This is typically called when constructing the capability, for example with the
+    /// `#[effect(timeout_ms = 5000)]` attribute of the `Effect` derive macro. The core checks
+    /// for expired requests whenever it processes an event or a resolution, and when the shell
+    /// calls [`Core::check_timeouts`](crate::Core::check_timeouts).
+    #[must_use]
+    pub fn with_timeout(&self, timeout: Duration) -> Self
+    where
+        Op: TimesOut,
+    {
+        self.with(|inner| {
+            inner.timeout = Some(Timeout {
+                duration: timeout,
+                timed_out: Op::timed_out,
+            });
+        })
+    }
+
+    /// Create a copy of the context without the timeout set with
+    /// [`with_timeout`](CapabilityContext::with_timeout), e.g. for the requests of a
+    /// capability which the shell is expected to resolve much later, such as timers.
+    #[must_use]
+    pub fn without_timeout(&self) -> Self {
+        self.with(|inner| inner.timeout = None)
+    }
+
+    /// The timeout set with [`with_timeout`](CapabilityContext::with_timeout), if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout.map(|timeout| timeout.duration)
+    }
+
+    /// Create a copy of the context which sends at most `max` requests made with
+    /// [`request_from_shell`](CapabilityContext::request_from_shell) to the shell at a time.
+    /// Further requests wait in a queue, and are sent in the order they were made as the
+    /// shell resolves the ones in flight. The queued requests are counted in the
+    /// [`CapabilityMetrics`].
+    ///
+    /// This is typically called when constructing the capability, for example with the
+    /// `#[effect(max_in_flight = 4)]` attribute of the `Effect` derive macro, so that an update
+    /// fanning out many requests doesn't overwhelm the shell. Notifications and streaming
+    /// requests aren't limited.
+    ///
+    /// Panics if `max` is zero.
+    #[must_use]
+    pub fn with_max_in_flight(&self, max: usize) -> Self {
+        self.with(|inner| inner.in_flight = Some(InFlight::new(max)))
+    }
+
+    /// The limit set with [`with_max_in_flight`](CapabilityContext::with_max_in_flight), if any.
+    pub fn max_in_flight(&self) -> Option<usize> {
+        self.inner
+            .in_flight
+            .as_ref()
+            .map(|in_flight| in_flight.max())
+    }
+
+    /// The registry of the caches the core trims when memory runs low. Capabilities which
+    /// keep caches register them here, see [`crate::memory`].
+    pub fn trim_registry(&self) -> &TrimRegistry {
+        &self.inner.trim_registry
+    }
+
     /// Spawn a task to do the asynchronous work. Within the task, async code
     /// can be used to interact with the Shell and the App.
     pub fn spawn(&self, f: impl Future<Output = ()> + 'static + Send) {
         self.inner.spawner.spawn(f);
     }
 
+    /// Spawn a task like [`spawn`](CapabilityContext::spawn), returning a handle to
+    /// [abort](AbortHandle::abort) it with, e.g. to abandon a request the app no longer needs
+    /// the response to.
+    pub fn spawn_abortable(&self, f: impl Future<Output = ()> + 'static + Send) -> AbortHandle {
+        let (task, handle) = abort::abortable(f, None);
+        self.spawn(task);
+
+        handle
+    }
+
+    /// Spawn a task like [`spawn_abortable`](CapabilityContext::spawn_abortable), which sends
+    /// the `on_abort` operation to the shell (as a notification) if it's aborted before it
+    /// finishes, so that the shell can cancel the work it's doing for the task.
+    pub fn spawn_abortable_notifying(
+        &self,
+        f: impl Future<Output = ()> + 'static + Send,
+        on_abort: Op,
+    ) -> AbortHandle {
+        let context = self.clone();
+        let notice = Box::new(move || {
+            let notify = context.clone();
+            context.spawn(async move { notify.notify_shell(on_abort).await });
+        });
+
+        let (task, handle) = abort::abortable(f, Some(notice));
+        self.spawn(task);
+
+        handle
+    }
+
+    /// Wrap the future `f`, e.g. one requesting from the shell, so that if it's dropped before
+    /// it finishes, because it lost a [`Compose::select`](crate::compose::Compose::select)
+    /// race, or the task running it was aborted, the `on_cancel` operation is sent to the shell
+    /// (as a notification), so that the shell can cancel the work it's doing for it.
+    pub fn notifying_on_cancel<T: 'static>(
+        &self,
+        f: impl Future<Output = T> + Send + 'static,
+        on_cancel: Op,
+    ) -> impl Future<Output = T> + Send + 'static {
+        let context = self.clone();
+        let notice = Box::new(move || {
+            let notify = context.clone();
+            context.spawn(async move { notify.notify_shell(on_cancel).await });
+        });
+
+        abort::notifying_on_cancel(f, notice)
+    }
+
     /// Send an effect request to the shell in a fire and forget fashion. The
     /// provided `operation` does not expect anything to be returned back.
     pub async fn notify_shell(&self, operation: Op) {
@@ -502,9 +700,7 @@ where
         // it's important that it is.  It forces all capabilities to
         // spawn onto the executor which keeps the ordering of effects
         // consistent with their function calls.
-        self.inner
-            .shell_channel
-            .send(Request::resolves_never(operation));
+        self.send_request(Request::resolves_never(operation));
     }
 
     /// Send an event to the app. The event will be processed on the next
@@ -597,14 +793,27 @@ where
         F: Fn(NewEv) -> Ev + Sync + Send + 'static,
         NewEv: 'static,
     {
-        CapabilityContext::new(
-            self.inner.shell_channel.clone(),
-            self.inner.app_channel.map_input(func),
-            self.inner.spawner.clone(),
-        )
+        let inner = self
+            .inner
+            .with_app_channel(self.inner.app_channel.map_input(func));
+
+        CapabilityContext {
+            inner: Arc::new(inner),
+        }
+    }
+
+    // A copy of the context with the changes made by `change`
+    fn with(&self, change: impl FnOnce(&mut ContextInner<Op, Ev>)) -> Self {
+        let mut inner = self.inner.with_app_channel(self.inner.app_channel.clone());
+        change(&mut inner);
+
+        CapabilityContext {
+            inner: Arc::new(inner),
+        }
     }
 
-    pub(crate) fn send_request(&self, request: Request<Op>) {
+    pub(crate) fn send_request(&self, mut request: Request<Op>) {
+        self.inner.metrics.track(&mut request);
         self.inner.shell_channel.send(request);
     }
 }
@@ -0,0 +1,193 @@
+//! Ordered delivery for streams of shell responses, see
+//! [`CapabilityContext::stream_from_shell_ordered`](super::CapabilityContext::stream_from_shell_ordered)
+
+use std::{collections::BTreeMap, pin::Pin, task::Poll};
+
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::shell_stream::ShellStream;
+
+/// A response in a stream of responses, numbered by the shell.
+///
+/// The shell numbers the responses to a request from 0, in the order it produces them,
+/// so that the core can deliver them in that order even if they are resolved concurrently.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sequenced<T> {
+    pub seq: u64,
+    pub value: T,
+}
+
+/// Responses from `expected` up to (but excluding) `resumed` never arrived and were skipped.
+#[derive(Error, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[error("responses {expected} to {} of the stream were lost", resumed - 1)]
+pub struct StreamGap {
+    pub expected: u64,
+    pub resumed: u64,
+}
+
+pub struct OrderedShellStream<T> {
+    inner: ShellStream<Sequenced<T>>,
+    next_seq: u64,
+    // responses which arrived ahead of `next_seq`
+    pending: BTreeMap<u64, T>,
+    window: usize,
+    finished: bool,
+}
+
+impl<T> OrderedShellStream<T> {
+    /// Skip to the earliest pending response, reporting the responses skipped over
+    fn skip_gap(&mut self) -> Option<StreamGap> {
+        let (&resumed, _) = self.pending.iter().next()?;
+        let gap = StreamGap {
+            expected: self.next_seq,
+            resumed,
+        };
+        self.next_seq = resumed;
+
+        Some(gap)
+    }
+}
+
+impl<T> Stream for OrderedShellStream<T> {
+    type Item = Result<T, StreamGap>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        // none of the fields are structurally pinned
+        let this = self.get_mut();
+
+        loop {
+            if let Some(value) = this.pending.remove(&this.next_seq) {
+                this.next_seq += 1;
+                return Poll::Ready(Some(Ok(value)));
+            }
+
+            // too many responses are waiting, the missing ones are presumed lost
+            if this.finished || this.pending.len() > this.window {
+                return Poll::Ready(this.skip_gap().map(Err));
+            }
+
+            match this.inner.poll_next_unpin(cx) {
+                Poll::Ready(Some(Sequenced { seq, value })) => {
+                    // late duplicates of delivered (or skipped) responses are dropped
+                    if seq >= this.next_seq {
+                        this.pending.insert(seq, value);
+                    }
+                }
+                Poll::Ready(None) => this.finished = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<Op, Ev, T> crate::capability::CapabilityContext<Op, Ev>
+where
+    Op: crate::capability::Operation<Output = Sequenced<T>>,
+    Ev: 'static,
+    T: Send + 'static,
+{
+    /// Send an effect request to the shell, expecting a stream of [`Sequenced`] responses,
+    /// which are delivered in sequence order regardless of the order they are resolved in.
+    ///
+    /// Up to `window` responses are held back waiting for a missing one. When more arrive,
+    /// the missing responses are skipped and reported as a [`StreamGap`].
+    pub fn stream_from_shell_ordered(&self, operation: Op, window: usize) -> OrderedShellStream<T> {
+        OrderedShellStream {
+            inner: self.stream_from_shell(operation),
+            next_seq: 0,
+            pending: BTreeMap::new(),
+            window,
+            finished: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+
+    use super::{Sequenced, StreamGap};
+    use crate::capability::{channel, executor_and_spawner, CapabilityContext, Operation};
+
+    #[derive(serde::Serialize, PartialEq, Eq, Debug)]
+    struct Watch;
+
+    impl Operation for Watch {
+        type Output = Sequenced<u32>;
+    }
+
+    #[test]
+    fn test_ordered_stream() {
+        let (request_sender, requests) = channel();
+        let (event_sender, events) = channel::<Result<u32, StreamGap>>();
+        let (executor, spawner) = executor_and_spawner();
+        let context: CapabilityContext<Watch, _> = CapabilityContext::new(
+            request_sender,
+            event_sender,
+            spawner,
+            Arc::default(),
+            Arc::default(),
+            Default::default(),
+        );
+
+        let mut stream = context.stream_from_shell_ordered(Watch, 2);
+        context.spawn({
+            let context = context.clone();
+            async move {
+                while let Some(item) = stream.next().await {
+                    context.update_app(item);
+                }
+            }
+        });
+
+        executor.run_all();
+        let mut request = requests.receive().expect("we should have a request here");
+
+        let mut resolve = |seq, value| {
+            request.resolve(Sequenced { seq, value }).unwrap();
+            executor.run_all();
+            std::iter::from_fn(|| events.receive()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(resolve(1, 10), vec![]);
+        assert_eq!(resolve(0, 0), vec![Ok(0), Ok(10)]);
+        assert_eq!(resolve(1, 10), vec![]);
+
+        // 2 is lost, 3 and 4 are held back until the window is exceeded
+        assert_eq!(resolve(3, 30), vec![]);
+        assert_eq!(resolve(4, 40), vec![]);
+        assert_eq!(
+            resolve(5, 50),
+            vec![
+                Err(StreamGap {
+                    expected: 2,
+                    resumed: 3
+                }),
+                Ok(30),
+                Ok(40),
+                Ok(50)
+            ]
+        );
+        assert_eq!(resolve(2, 20), vec![]);
+        assert_eq!(resolve(6, 60), vec![Ok(60)]);
+    }
+
+    #[test]
+    fn test_gap_display() {
+        let gap = StreamGap {
+            expected: 2,
+            resumed: 5,
+        };
+
+        assert_eq!(gap.to_string(), "responses 2 to 4 of the stream were lost");
+    }
+}
@@ -0,0 +1,94 @@
+//! Running capability tasks on an async runtime provided by the shell, see [`RuntimeAdapter`]
+
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, Future};
+
+/// An async runtime provided by the shell to run the capabilities' tasks, instead of the
+/// core's built-in executor, e.g. a tokio handle on desktop or `wasm-bindgen-futures` on the web.
+/// Long running tasks in the core then integrate with the host's scheduling and instrumentation.
+///
+/// Create the core with [`Core::new_with_runtime`](crate::Core::new_with_runtime). For example:
+///
+/// ```rust,ignore
+/// struct Tokio(tokio::runtime::Handle);
+///
+/// impl RuntimeAdapter for Tokio {
+///     fn spawn(&self, future: BoxFuture<'static, ()>) {
+///         self.0.spawn(future);
+///     }
+///
+///     fn notify(&self) {
+///         // ask the UI thread to call `core.process_pending()`
+///     }
+/// }
+/// ```
+///
+/// The core no longer runs the tasks itself when it processes an event or a resolution, so the
+/// effects they request and the events they send to the app arrive in between. The runtime calls
+/// [`notify`](RuntimeAdapter::notify) when they may have, and the shell then calls
+/// [`Core::process_pending`](crate::Core::process_pending) to collect them.
+pub trait RuntimeAdapter: Send + Sync + 'static {
+    /// Spawn the `future` of a capability task, to run to completion.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+
+    /// Called after each time a task made progress, which may have requested effects or
+    /// sent events to the app. Does nothing by default.
+    fn notify(&self) {}
+}
+
+/// A task spawned on a [`RuntimeAdapter`], which notifies it of progress and counts
+/// as pending until it completes, or is dropped.
+pub(super) struct RuntimeTask {
+    future: Option<BoxFuture<'static, ()>>,
+    runtime: Arc<dyn RuntimeAdapter>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl RuntimeTask {
+    pub(super) fn new(
+        future: BoxFuture<'static, ()>,
+        runtime: Arc<dyn RuntimeAdapter>,
+        pending: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            future: Some(future),
+            runtime,
+            pending,
+        }
+    }
+}
+
+impl Future for RuntimeTask {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(future) = self.future.as_mut() else {
+            return Poll::Ready(());
+        };
+
+        let poll = future.as_mut().poll(cx);
+        if poll.is_ready() {
+            self.future = None;
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.runtime.notify();
+
+        poll
+    }
+}
+
+impl Drop for RuntimeTask {
+    fn drop(&mut self) {
+        if self.future.is_some() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Questions from the shell to the capabilities
+
+use serde::Serialize;
+
+/// Implement [`ShellQuery`] on your app's `Capabilities` to let the shell ask the capabilities
+/// about the work they're tracking with [`Core::query_capabilities`](crate::Core::query_capabilities),
+/// e.g. which downloads are still outstanding.
+///
+/// This is the reverse of an effect: the shell asks and the capabilities answer straight away.
+/// It lets a shell reconcile its own resources with the core after it restarts, for example
+/// cancelling downloads nobody is waiting for any more. Capabilities which can answer questions
+/// typically implement [`ShellQuery`] themselves, and the `Capabilities` delegate to them.
+///
+/// ```rust,ignore
+/// impl ShellQuery for Capabilities {
+///     type Query = CapabilitiesQuery;
+///     type QueryResponse = CapabilitiesQueryResponse;
+///
+///     fn query(&self, query: CapabilitiesQuery) -> CapabilitiesQueryResponse {
+///         match query {
+///             CapabilitiesQuery::Downloads(query) => {
+///                 CapabilitiesQueryResponse::Downloads(self.downloads.query(query))
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub trait ShellQuery {
+    /// Query, typically an `enum`, defines the questions the shell can ask
+    type Query;
+    /// QueryResponse, typically an `enum`, holds the answers to the queries
+    type QueryResponse: Serialize;
+
+    /// Answer the `query`
+    fn query(&self, query: Self::Query) -> Self::QueryResponse;
+}
@@ -1,12 +1,13 @@
 //! Async support for implementing capabilities
 //!
 use std::{
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, Weak},
     task::{Poll, Waker},
 };
 
 use futures::Future;
 
+use super::{in_flight::Permit, timeout::PendingTimeout, ShellTimeout};
 use crate::Request;
 
 pub struct ShellRequest<T> {
@@ -17,6 +18,12 @@ struct SharedState<T> {
     result: Option<T>,
     waker: Option<Waker>,
     send_request: Option<Box<dyn FnOnce() + Send + 'static>>,
+    // whether the shell or the timeout has resolved the request, whichever came first
+    settled: bool,
+    // the slot of the request, if the context limits the requests in flight
+    permit: Option<Permit>,
+    // the timeout of the request, if the context has one
+    timeout: Option<PendingTimeout>,
 }
 
 impl<T> Future for ShellRequest<T> {
@@ -30,7 +37,10 @@ impl<T> Future for ShellRequest<T> {
 
         // If there's still a request to send, take it and send it
         if let Some(send_request) = shared_state.send_request.take() {
+            // don't hold the lock while sending, the request may get a slot straight away
+            drop(shared_state);
             send_request();
+            shared_state = self.shared_state.lock().unwrap();
         }
 
         // If a result has been delivered, we're ready to continue
@@ -58,49 +68,159 @@ where
     /// `request_from_shell` is returns a future of the output, which can be
     /// `await`ed. You should only call this method inside an async task
     /// created with [`CapabilityContext::spawn`](crate::capability::CapabilityContext::spawn).
+    ///
+    /// If the context has a timeout set with
+    /// [`with_timeout`](crate::capability::CapabilityContext::with_timeout) and the shell
+    /// doesn't resolve the request in time, the future resolves with the operation's
+    /// [`timed_out`](crate::capability::TimesOut::timed_out) output instead, and the shell's
+    /// late resolution is ignored.
     pub fn request_from_shell(&self, operation: Op) -> ShellRequest<Op::Output> {
+        let timed_out = self.inner.timeout.map(|timeout| timeout.timed_out);
+
+        self.request(operation, |output| output, timed_out)
+    }
+
+    /// Send an effect request to the shell, expecting an output, like
+    /// [`request_from_shell`](crate::capability::CapabilityContext::request_from_shell),
+    /// but resolving with a [`ShellTimeout`] error rather than the operation's
+    /// [`timed_out`](crate::capability::TimesOut::timed_out) output if the shell doesn't
+    /// resolve the request within the context's timeout. Without a timeout, this never fails.
+    pub fn request_from_shell_with_timeout(
+        &self,
+        operation: Op,
+    ) -> ShellRequest<Result<Op::Output, ShellTimeout>> {
+        self.request(operation, Ok, Some(Err))
+    }
+
+    // Send a request resolving with `resolved(output)` when the shell resolves it, or
+    // `timed_out(timeout)` if the context has a timeout and the shell doesn't resolve it in time
+    fn request<T>(
+        &self,
+        operation: Op,
+        resolved: fn(Op::Output) -> T,
+        timed_out: Option<fn(ShellTimeout) -> T>,
+    ) -> ShellRequest<T>
+    where
+        T: Send + 'static,
+    {
         let shared_state = Arc::new(Mutex::new(SharedState {
             result: None,
             waker: None,
             send_request: None,
+            settled: false,
+            permit: None,
+            timeout: None,
         }));
 
         // Our callback holds a weak pointer to avoid circular references
         // from shared_state -> send_request -> request -> shared_state
         let callback_shared_state = Arc::downgrade(&shared_state);
 
-        // used in docs/internals/runtime.md
-        // ANCHOR: resolve
-        let request = Request::resolves_once(operation, move |result| {
-            let Some(shared_state) = callback_shared_state.upgrade() else {
-                // The ShellRequest was dropped before we were called, so just
-                // do nothing.
-                return;
-            };
-
-            let mut shared_state = shared_state.lock().unwrap();
-
-            // Attach the result to the shared state of the future
-            shared_state.result = Some(result);
-            // Signal the executor to wake the task holding this future
-            if let Some(waker) = shared_state.waker.take() {
-                waker.wake()
-            }
+        let request = Request::resolves_once(operation, move |output| {
+            // Attach the result to the shared state of the future, unless the
+            // ShellRequest was dropped or has timed out already
+            settle(&callback_shared_state, resolved(output));
         });
-        // ANCHOR_END: resolve
 
         // Send the request on the next poll of the ShellRequest future
         let send_req_context = self.clone();
-        let send_request = move || send_req_context.send_request(request);
+        let timeout_shared_state = Arc::downgrade(&shared_state);
+        let send_request = move || {
+            if let (Some(timeout), Some(timed_out)) = (send_req_context.inner.timeout, timed_out) {
+                let duration = timeout.duration;
+                let metrics = send_req_context.inner.metrics.clone();
+                let weak_shared_state = timeout_shared_state.clone();
+                let pending = send_req_context.inner.timeouts.register(duration, move || {
+                    let timeout = ShellTimeout { timeout: duration };
+                    if settle(&weak_shared_state, timed_out(timeout)) {
+                        metrics.timed_out::<Op>();
+                    }
+                });
+
+                if let Some(shared_state) = timeout_shared_state.upgrade() {
+                    shared_state.lock().unwrap().timeout = Some(pending);
+                }
+            }
 
-        shared_state.lock().unwrap().send_request = Some(Box::new(send_request));
+            send_req_context.send_request(request);
+        };
+
+        shared_state.lock().unwrap().send_request =
+            Some(self.send_when_allowed(&shared_state, send_request));
 
         ShellRequest { shared_state }
     }
+
+    // Wrap `send` to wait for a slot if the context limits the requests in flight, keeping
+    // the slot until the request settles or is dropped
+    fn send_when_allowed<T>(
+        &self,
+        shared_state: &Arc<Mutex<SharedState<T>>>,
+        send: impl FnOnce() + Send + 'static,
+    ) -> Box<dyn FnOnce() + Send + 'static>
+    where
+        T: Send + 'static,
+    {
+        let Some(in_flight) = self.inner.in_flight.clone() else {
+            return Box::new(send);
+        };
+
+        let shared_state = Arc::downgrade(shared_state);
+        let metrics = self.inner.metrics.clone();
+
+        Box::new(move || {
+            let queued = in_flight.send(move |permit| {
+                let Some(shared_state) = shared_state.upgrade() else {
+                    // The ShellRequest was dropped while queued, the permit goes to the next one
+                    return;
+                };
+                shared_state.lock().unwrap().permit = Some(permit);
+
+                send();
+            });
+
+            if queued {
+                metrics.queued::<Op>();
+            }
+        })
+    }
+}
+
+// used in docs/internals/runtime.md
+// ANCHOR: resolve
+/// Settle the request with the `result`, unless it's already settled or was dropped,
+/// freeing its slot and cancelling its timeout. Returns whether this call settled it.
+fn settle<T>(shared_state: &Weak<Mutex<SharedState<T>>>, result: T) -> bool {
+    let Some(shared_state) = shared_state.upgrade() else {
+        // The ShellRequest was dropped before we were called, so just
+        // do nothing.
+        return false;
+    };
+    let mut shared_state = shared_state.lock().unwrap();
+    if shared_state.settled {
+        return false;
+    }
+    shared_state.settled = true;
+
+    shared_state.result = Some(result);
+    // Signal the executor to wake the task holding this future
+    if let Some(waker) = shared_state.waker.take() {
+        waker.wake();
+    }
+    // Free the slot for the next request, and stop waiting for the timeout
+    shared_state.permit = None;
+    let timeout = shared_state.timeout.take();
+    drop(shared_state);
+    drop(timeout);
+
+    true
 }
+// ANCHOR_END: resolve
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use assert_matches::assert_matches;
 
     use crate::capability::{channel, executor_and_spawner, CapabilityContext, Operation};
@@ -117,8 +237,14 @@ mod tests {
         let (request_sender, requests) = channel();
         let (event_sender, events) = channel::<()>();
         let (executor, spawner) = executor_and_spawner();
-        let capability_context =
-            CapabilityContext::new(request_sender, event_sender.clone(), spawner.clone());
+        let capability_context = CapabilityContext::new(
+            request_sender,
+            event_sender.clone(),
+            spawner.clone(),
+            Arc::default(),
+            Arc::default(),
+            Default::default(),
+        );
 
         let future = capability_context.request_from_shell(TestOperation);
 
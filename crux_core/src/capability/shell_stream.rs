@@ -1,11 +1,16 @@
 use std::{
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, Weak},
     task::{Poll, Waker},
 };
 
 use futures::Stream;
 
-use super::{channel, channel::Receiver};
+use super::{
+    channel,
+    channel::{Receiver, Sender},
+    timeout::PendingTimeout,
+    ShellTimeout,
+};
 use crate::core::Request;
 
 pub struct ShellStream<T> {
@@ -14,8 +19,14 @@ pub struct ShellStream<T> {
 
 struct SharedState<T> {
     receiver: Receiver<T>,
+    // dropped to end the stream if it times out
+    sender: Option<Sender<T>>,
     waker: Option<Waker>,
     send_request: Option<Box<dyn FnOnce() + Send + 'static>>,
+    // whether the shell has responded or the stream has timed out, whichever came first
+    settled: bool,
+    // the timeout for the first response, if the context has one
+    timeout: Option<PendingTimeout>,
 }
 
 impl<T> Stream for ShellStream<T> {
@@ -28,7 +39,10 @@ impl<T> Stream for ShellStream<T> {
         let mut shared_state = self.shared_state.lock().unwrap();
 
         if let Some(send_request) = shared_state.send_request.take() {
+            // don't hold the lock while sending, which registers the timeout in the shared state
+            drop(shared_state);
             send_request();
+            shared_state = self.shared_state.lock().unwrap();
         }
 
         match shared_state.receiver.try_receive() {
@@ -48,12 +62,21 @@ where
     Ev: 'static,
 {
     /// Send an effect request to the shell, expecting a stream of responses
+    ///
+    /// If the context has a timeout set with
+    /// [`with_timeout`](crate::capability::CapabilityContext::with_timeout) and the shell
+    /// doesn't send the first response in time, the stream yields the operation's
+    /// [`timed_out`](crate::capability::TimesOut::timed_out) output and ends, ignoring any
+    /// later responses. Once the shell has responded, the stream doesn't time out.
     pub fn stream_from_shell(&self, operation: Op) -> ShellStream<Op::Output> {
         let (sender, receiver) = channel();
         let shared_state = Arc::new(Mutex::new(SharedState {
             receiver,
+            sender: Some(sender),
             waker: None,
             send_request: None,
+            settled: false,
+            timeout: None,
         }));
 
         // Our callback holds a weak pointer so the channel can be freed
@@ -67,30 +90,92 @@ where
             };
 
             let mut shared_state = shared_state.lock().unwrap();
+            let Some(sender) = &shared_state.sender else {
+                // The stream has timed out
+                return Err(());
+            };
 
             sender.send(result);
             if let Some(waker) = shared_state.waker.take() {
                 waker.wake();
             }
 
+            // Stop waiting for the timeout
+            shared_state.settled = true;
+            let timeout = shared_state.timeout.take();
+            drop(shared_state);
+            drop(timeout);
+
             Ok(())
         });
 
         // Put a callback into our shared_state so that we only send
         // our request to the shell when the stream is first polled.
         let send_req_context = self.clone();
-        let send_request = move || send_req_context.send_request(request);
+        let timeout_shared_state = Arc::downgrade(&shared_state);
+        let send_request = move || {
+            if let Some(timeout) = send_req_context.inner.timeout {
+                let metrics = send_req_context.inner.metrics.clone();
+                let weak_shared_state = timeout_shared_state.clone();
+                let pending =
+                    send_req_context
+                        .inner
+                        .timeouts
+                        .register(timeout.duration, move || {
+                            let output = (timeout.timed_out)(ShellTimeout {
+                                timeout: timeout.duration,
+                            });
+                            if time_out(&weak_shared_state, output) {
+                                metrics.timed_out::<Op>();
+                            }
+                        });
+
+                if let Some(shared_state) = timeout_shared_state.upgrade() {
+                    shared_state.lock().unwrap().timeout = Some(pending);
+                }
+            }
+
+            send_req_context.send_request(request);
+        };
         shared_state.lock().unwrap().send_request = Some(Box::new(send_request));
 
         ShellStream { shared_state }
     }
 }
 
+/// End the stream with the timed out `output`, unless the shell has responded already or the
+/// stream was dropped. Returns whether the stream timed out.
+fn time_out<T: Send + 'static>(shared_state: &Weak<Mutex<SharedState<T>>>, output: T) -> bool {
+    let Some(shared_state) = shared_state.upgrade() else {
+        return false;
+    };
+    let mut shared_state = shared_state.lock().unwrap();
+    if shared_state.settled {
+        return false;
+    }
+    shared_state.settled = true;
+
+    // the stream ends once the output is received and the sender is gone
+    if let Some(sender) = shared_state.sender.take() {
+        sender.send(output);
+    }
+    if let Some(waker) = shared_state.waker.take() {
+        waker.wake();
+    }
+
+    true
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use assert_matches::assert_matches;
 
-    use crate::capability::{channel, executor_and_spawner, CapabilityContext, Operation};
+    use crate::capability::{
+        channel, executor_and_spawner, CapabilityContext, Operation, ShellTimeout, Timeouts,
+        TimesOut,
+    };
 
     #[derive(serde::Serialize, PartialEq, Eq, Debug)]
     struct TestOperation;
@@ -99,6 +184,12 @@ mod tests {
         type Output = Option<Done>;
     }
 
+    impl TimesOut for TestOperation {
+        fn timed_out(_timeout: ShellTimeout) -> Self::Output {
+            None
+        }
+    }
+
     #[derive(serde::Deserialize, PartialEq, Eq, Debug)]
     struct Done;
 
@@ -107,8 +198,14 @@ mod tests {
         let (request_sender, requests) = channel();
         let (event_sender, events) = channel::<()>();
         let (executor, spawner) = executor_and_spawner();
-        let capability_context =
-            CapabilityContext::new(request_sender, event_sender.clone(), spawner.clone());
+        let capability_context = CapabilityContext::new(
+            request_sender,
+            event_sender.clone(),
+            spawner.clone(),
+            Arc::default(),
+            Arc::default(),
+            Default::default(),
+        );
 
         let mut stream = capability_context.stream_from_shell(TestOperation);
 
@@ -168,4 +265,44 @@ mod tests {
             .resolve(None)
             .expect_err("resolving a finished task should error");
     }
+
+    #[test]
+    fn test_shell_stream_timeout() {
+        let (request_sender, requests) = channel();
+        let (event_sender, events) = channel();
+        let (executor, spawner) = executor_and_spawner();
+        let timeouts = Arc::new(Timeouts::default());
+        let capability_context = CapabilityContext::new(
+            request_sender,
+            event_sender.clone(),
+            spawner.clone(),
+            timeouts.clone(),
+            Arc::default(),
+            Default::default(),
+        )
+        .with_timeout(std::time::Duration::ZERO);
+
+        let mut stream = capability_context.stream_from_shell(TestOperation);
+        spawner.spawn(async move {
+            use futures::StreamExt;
+            while let Some(maybe_done) = stream.next().await {
+                event_sender.send(maybe_done);
+            }
+        });
+
+        executor.run_all();
+        let mut request = requests.receive().expect("we should have a request here");
+
+        timeouts.expire_all();
+        executor.run_all();
+
+        // The stream yields the timed out output and ends
+        assert_matches!(events.receive(), Some(None));
+        assert_matches!(events.receive(), None);
+        request
+            .resolve(Some(Done))
+            .expect_err("resolving a timed out stream should error");
+        executor.run_all();
+        assert_matches!(events.receive(), None);
+    }
 }
@@ -0,0 +1,241 @@
+//! Timeouts for shell requests, see [`CapabilityContext::with_timeout`](super::CapabilityContext::with_timeout)
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::Operation;
+
+/// The error a request resolves with when the shell doesn't resolve it within the
+/// capability's timeout, see [`TimesOut`] and
+/// [`request_from_shell_with_timeout`](super::CapabilityContext::request_from_shell_with_timeout).
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[error("the shell did not resolve the request within {timeout:?}")]
+pub struct ShellTimeout {
+    pub timeout: Duration,
+}
+
+/// An operation whose requests can time out, i.e. which has an output to resolve them
+/// with when the shell doesn't. Only capabilities whose operation implements it can be given
+/// a timeout with [`with_timeout`](super::CapabilityContext::with_timeout), or the
+/// `#[effect(timeout_ms = 5000)]` attribute of the `Effect` derive macro.
+pub trait TimesOut: Operation {
+    /// The output of a request the shell didn't resolve within the `timeout`, typically an error
+    fn timed_out(timeout: ShellTimeout) -> Self::Output;
+}
+
+/// The timeout of a capability context, and how its requests resolve when they time out
+pub(crate) struct Timeout<Output> {
+    pub(crate) duration: Duration,
+    pub(crate) timed_out: fn(ShellTimeout) -> Output,
+}
+
+// not derived, which would require `Output: Copy`
+impl<Output> Clone for Timeout<Output> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Output> Copy for Timeout<Output> {}
+
+/// Requests waiting to time out, shared by all the capabilities of an app, in the order
+/// of their deadlines. Expired requests are resolved when the core next processes anything.
+pub(crate) struct Timeouts {
+    clock: Clock,
+    pending: Mutex<Pending>,
+}
+
+#[derive(Default)]
+struct Pending {
+    // keyed by the deadline, and an id to tell apart the requests with the same deadline
+    expiries: BTreeMap<(Duration, u64), Box<dyn FnOnce() + Send>>,
+    next_id: u64,
+}
+
+/// A request's registered timeout, which is cancelled when this is dropped, typically
+/// because the request settled first.
+pub(crate) struct PendingTimeout {
+    timeouts: Arc<Timeouts>,
+    key: (Duration, u64),
+}
+
+impl Drop for PendingTimeout {
+    fn drop(&mut self) {
+        // dropped without holding the lock
+        let _expire = self.timeouts.lock().expiries.remove(&self.key);
+    }
+}
+
+impl Timeouts {
+    /// Call `expire` once `timeout` has elapsed, unless the returned [`PendingTimeout`] is
+    /// dropped first.
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        timeout: Duration,
+        expire: impl FnOnce() + Send + 'static,
+    ) -> PendingTimeout {
+        let deadline = self.clock.elapsed().saturating_add(timeout);
+
+        let mut pending = self.lock();
+        let key = (deadline, pending.next_id);
+        pending.next_id += 1;
+        pending.expiries.insert(key, Box::new(expire));
+
+        PendingTimeout {
+            timeouts: self.clone(),
+            key,
+        }
+    }
+
+    /// The time elapsed since the timeouts were created, which deadlines are measured in.
+    pub(crate) fn now(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    /// Expire the requests whose timeout has elapsed.
+    pub(crate) fn expire_due(&self) {
+        let mut pending = self.lock();
+        let Some((&(first, _), _)) = pending.expiries.first_key_value() else {
+            return;
+        };
+
+        let now = self.clock.elapsed();
+        if first > now {
+            return;
+        }
+
+        // the deadlines up to and including `now` are due
+        let waiting = match now.checked_add(Duration::from_nanos(1)) {
+            Some(after) => pending.expiries.split_off(&(after, 0)),
+            None => BTreeMap::new(),
+        };
+        let due = std::mem::replace(&mut pending.expiries, waiting);
+        drop(pending);
+
+        Self::expire(due);
+    }
+
+    /// Expire all the requests, regardless of their timeout.
+    pub(crate) fn expire_all(&self) {
+        let due = std::mem::take(&mut self.lock().expiries);
+
+        Self::expire(due);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.pending.lock().expect("Timeouts Mutex was poisoned.")
+    }
+
+    // the callbacks wake tasks and cancel timeouts, so they run without holding the lock
+    fn expire(due: BTreeMap<(Duration, u64), Box<dyn FnOnce() + Send>>) {
+        for expire in due.into_values() {
+            expire();
+        }
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            clock: Clock::new(),
+            pending: Mutex::default(),
+        }
+    }
+}
+
+// std::time::Instant is not available in the browser
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(super) struct Clock {
+    origin: std::time::Instant,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Clock {
+    pub(super) fn new() -> Self {
+        Self {
+            origin: std::time::Instant::now(),
+        }
+    }
+
+    pub(super) fn elapsed(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(super) struct Clock {
+    origin_millis: f64,
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Clock {
+    pub(super) fn new() -> Self {
+        Self {
+            origin_millis: js_sys::Date::now(),
+        }
+    }
+
+    pub(super) fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((js_sys::Date::now() - self.origin_millis).max(0.0) / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn register(
+        timeouts: &Arc<Timeouts>,
+        timeout: Duration,
+        expired: &Arc<AtomicUsize>,
+    ) -> PendingTimeout {
+        let expired = expired.clone();
+        timeouts.register(timeout, move || {
+            expired.fetch_add(1, Ordering::SeqCst);
+        })
+    }
+
+    #[test]
+    fn expires_only_due_requests() {
+        let timeouts = Arc::new(Timeouts::default());
+        let expired = Arc::new(AtomicUsize::new(0));
+
+        let _due = register(&timeouts, Duration::ZERO, &expired);
+        let _waiting = register(&timeouts, Duration::from_secs(3600), &expired);
+
+        timeouts.expire_due();
+        assert_eq!(expired.load(Ordering::SeqCst), 1);
+
+        timeouts.expire_due();
+        assert_eq!(expired.load(Ordering::SeqCst), 1);
+
+        timeouts.expire_all();
+        assert_eq!(expired.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn settled_requests_are_removed() {
+        let timeouts = Arc::new(Timeouts::default());
+        let expired = Arc::new(AtomicUsize::new(0));
+
+        let settled = register(&timeouts, Duration::ZERO, &expired);
+        let _waiting = register(&timeouts, Duration::ZERO, &expired);
+        assert_eq!(timeouts.lock().expiries.len(), 2);
+
+        drop(settled);
+        assert_eq!(timeouts.lock().expiries.len(), 1);
+
+        timeouts.expire_due();
+        assert_eq!(expired.load(Ordering::SeqCst), 1);
+        assert!(timeouts.lock().expiries.is_empty());
+    }
+}
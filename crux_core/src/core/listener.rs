@@ -0,0 +1,118 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use super::{Core, Effect};
+use crate::App;
+
+type Listener<Ef> = Arc<dyn Fn(Ef) + Send + Sync>;
+
+/// Hands the effects requested while the core isn't processing anything to the listener
+/// set with [`Core::set_effect_listener`], instead of leaving them for the next call.
+pub(crate) struct EffectListener<Ef> {
+    processing: AtomicUsize,
+    listener: Mutex<Option<Listener<Ef>>>,
+}
+
+impl<Ef> EffectListener<Ef> {
+    pub(crate) fn set(&self, listener: Listener<Ef>) {
+        *self.listener.lock().expect("Listener Mutex was poisoned.") = Some(listener);
+    }
+
+    /// Take the `effect` if the core isn't processing and there's a listener,
+    /// otherwise it's returned to go to the core's requests.
+    pub(crate) fn offer(&self, effect: Ef) -> Option<Ef> {
+        if self.processing.load(Ordering::SeqCst) > 0 {
+            return Some(effect);
+        }
+
+        match self.listener() {
+            Some(listener) => {
+                listener(effect);
+                None
+            }
+            None => Some(effect),
+        }
+    }
+
+    // cloned out of the Mutex, so that the listener can call the core
+    fn listener(&self) -> Option<Listener<Ef>> {
+        self.listener
+            .lock()
+            .expect("Listener Mutex was poisoned.")
+            .clone()
+    }
+}
+
+impl<Ef> Default for EffectListener<Ef> {
+    fn default() -> Self {
+        Self {
+            processing: AtomicUsize::new(0),
+            listener: Mutex::new(None),
+        }
+    }
+}
+
+/// Marks the core as processing while alive. When the last one is dropped, any effects
+/// left over, e.g. requested by a task on the shell's runtime right after the core collected
+/// its effects, go to the listener.
+pub(crate) struct Processing<'a, Ef, A>
+where
+    A: App,
+{
+    core: &'a Core<Ef, A>,
+}
+
+impl<'a, Ef, A> Processing<'a, Ef, A>
+where
+    A: App,
+{
+    pub(crate) fn new(core: &'a Core<Ef, A>) -> Self {
+        core.listener.processing.fetch_add(1, Ordering::SeqCst);
+
+        Self { core }
+    }
+}
+
+impl<'a, Ef, A> Drop for Processing<'a, Ef, A>
+where
+    A: App,
+{
+    fn drop(&mut self) {
+        let listener = &self.core.listener;
+        if listener.processing.fetch_sub(1, Ordering::SeqCst) > 1 {
+            return;
+        }
+
+        if let Some(listener) = listener.listener() {
+            for effect in self.core.requests.drain() {
+                listener(effect);
+            }
+        }
+    }
+}
+
+impl<Ef, A> Core<Ef, A>
+where
+    Ef: Effect,
+    A: App,
+{
+    /// Set a `listener` for the effects requested while the core isn't processing an event
+    /// or a resolution, e.g. by a task on the shell's runtime (see
+    /// [`Core::new_with_runtime`]) finishing a timer or a watch. Without a listener, the
+    /// shell gets them the next time it calls the core, e.g. with [`Core::process_pending`].
+    ///
+    /// The listener is called with each effect as soon as it's requested, on the thread
+    /// which requested it. Effects requested while the core is processing are returned
+    /// from the call as usual.
+    pub fn set_effect_listener<F>(&self, listener: F)
+    where
+        F: Fn(Ef) + Send + Sync + 'static,
+    {
+        self.listener.set(Arc::new(listener));
+
+        // anything requested before the listener was set
+        drop(Processing::new(self));
+    }
+}
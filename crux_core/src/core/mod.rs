@@ -1,17 +1,34 @@
 mod effect;
+mod listener;
 mod request;
 mod resolve;
+mod shutdown;
+mod stream;
 
-use std::sync::RwLock;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use futures::Stream;
 
 pub use effect::Effect;
 pub use request::Request;
 pub use resolve::ResolveError;
+pub use shutdown::ShutdownStatus;
 
 pub(crate) use resolve::Resolve;
 
-use crate::capability::{self, channel::Receiver, Operation, ProtoContext, QueuingExecutor};
-use crate::{App, WithContext};
+use listener::{EffectListener, Processing};
+use shutdown::Shutdown;
+use stream::Settle;
+
+use crate::capability::{
+    self, channel::Receiver, EffectMetrics, Metrics, Operation, ProtoContext, QueuingExecutor,
+    RuntimeAdapter, ShellQuery, Timeouts,
+};
+use crate::memory::{self, MemoryPressure, TrimRegistry, TrimReport};
+use crate::{App, Queryable, WithContext};
 
 /// The Crux core. Create an instance of this type with your effect type, and your app type as type parameters
 ///
@@ -32,6 +49,11 @@ where
     capabilities: A::Capabilities,
     requests: Receiver<Ef>,
     capability_events: Receiver<A::Event>,
+    timeouts: Arc<Timeouts>,
+    metrics: Arc<Metrics>,
+    trim_registry: TrimRegistry,
+    shutdown: Shutdown,
+    listener: Arc<EffectListener<Ef>>,
     app: A,
 }
 // ANCHOR_END: core
@@ -48,22 +70,78 @@ where
     /// ```
     ///
     pub fn new<Capabilities>() -> Self
+    where
+        Capabilities: WithContext<A, Ef>,
+    {
+        Self::build::<Capabilities>(None)
+    }
+
+    /// Create an instance of the Crux core which runs the capabilities' tasks on the shell's
+    /// async `runtime`, see [`RuntimeAdapter`]. The shell calls [`Core::process_pending`]
+    /// when the runtime notifies it that the tasks have made progress.
+    pub fn new_with_runtime<Capabilities>(runtime: impl RuntimeAdapter) -> Self
+    where
+        Capabilities: WithContext<A, Ef>,
+    {
+        Self::build::<Capabilities>(Some(Arc::new(runtime)))
+    }
+
+    fn build<Capabilities>(runtime: Option<Arc<dyn RuntimeAdapter>>) -> Self
     where
         Capabilities: WithContext<A, Ef>,
     {
         let (request_sender, request_receiver) = capability::channel();
+        let listener = Arc::<EffectListener<Ef>>::default();
+        let request_sender = {
+            let listener = listener.clone();
+            request_sender.filter_input(move |effect| listener.offer(effect))
+        };
         let (event_sender, event_receiver) = capability::channel();
-        let (executor, spawner) = capability::executor_and_spawner();
-        let capability_context = ProtoContext::new(request_sender, event_sender, spawner);
+        let (executor, mut spawner) = capability::executor_and_spawner();
+        if let Some(runtime) = runtime {
+            spawner = spawner.with_runtime(runtime);
+        }
+        let timeouts = Arc::<Timeouts>::default();
+        let metrics = Arc::<Metrics>::default();
+        let trim_registry = TrimRegistry::default();
+        let capability_context = ProtoContext::new(
+            request_sender,
+            event_sender,
+            spawner,
+            timeouts.clone(),
+            metrics.clone(),
+            trim_registry.clone(),
+        );
 
-        Self {
+        let core = Self {
             model: Default::default(),
             executor,
             app: Default::default(),
             capabilities: Capabilities::new_with_context(capability_context),
             requests: request_receiver,
             capability_events: event_receiver,
-        }
+            timeouts,
+            metrics,
+            trim_registry,
+            shutdown: Shutdown::default(),
+            listener,
+        };
+
+        let mut model = core.model.write().expect("Model RwLock was poisoned.");
+        core.app.init(&mut model, &core.capabilities);
+        drop(model);
+
+        core
+    }
+
+    /// Collect the effect requests made by [`App::init`] when the core was created,
+    /// returning a vector of effect requests. The shell should call this once, straight
+    /// after creating the core.
+    ///
+    /// The effects are also returned by the first call processing events or resolutions,
+    /// so they can't get lost if the shell doesn't call this.
+    pub fn start(&self) -> Vec<Ef> {
+        self.process()
     }
 
     /// Run the app's `update` function with a given `event`, returning a vector of
@@ -71,14 +149,50 @@ where
     // used in docs/internals/runtime.md
     // ANCHOR: process_event
     pub fn process_event(&self, event: A::Event) -> Vec<Ef> {
+        let _processing = Processing::new(self);
         let mut model = self.model.write().expect("Model RwLock was poisoned.");
 
         self.app.update(event, &mut model, &self.capabilities);
 
+        // don't hold the lock while processing, capabilities may send events to the app
+        drop(model);
+
         self.process()
     }
     // ANCHOR_END: process_event
 
+    /// Run the app's `update` function with a given `event`, returning a stream of the
+    /// effect requests, which yields each of them as soon as it's requested, rather than
+    /// after the update settles like [`Core::process_event`] does.
+    ///
+    /// This gets the first effects, typically a render, to the shell sooner when the update
+    /// also starts a long chain of work in capabilities. The update itself runs straight
+    /// away, the rest of the work as the stream is polled. The stream is ready whenever it's
+    /// polled, as all the work is done by the core.
+    pub fn process_event_stream(&self, event: A::Event) -> impl Stream<Item = Ef> + '_ {
+        let processing = Processing::new(self);
+        let mut model = self.model.write().expect("Model RwLock was poisoned.");
+        self.app.update(event, &mut model, &self.capabilities);
+        drop(model);
+
+        futures::stream::iter(Settle::new(self, processing))
+    }
+
+    /// Run the app's `update` function with a given `event`, passing each effect request
+    /// to the `effect_out` callback as soon as it's requested, see [`Core::process_event_stream`].
+    /// This suits shells which can't consume a Rust stream, e.g. across an FFI boundary.
+    pub fn process_event_with<F>(&self, event: A::Event, effect_out: F)
+    where
+        F: FnMut(Ef),
+    {
+        let processing = Processing::new(self);
+        let mut model = self.model.write().expect("Model RwLock was poisoned.");
+        self.app.update(event, &mut model, &self.capabilities);
+        drop(model);
+
+        Settle::new(self, processing).for_each(effect_out);
+    }
+
     /// Resolve an effect `request` for operation `Op` with the corresponding result.
     ///
     /// Note that the `request` is borrowed mutably. When a request that is expected to
@@ -92,6 +206,7 @@ where
         Op: Operation,
         // ANCHOR_END: resolve_sig
     {
+        let _processing = Processing::new(self);
         let resolve_result = request.resolve(result);
         debug_assert!(resolve_result.is_ok());
 
@@ -102,6 +217,8 @@ where
     // used in docs/internals/runtime.md
     // ANCHOR: process
     pub(crate) fn process(&self) -> Vec<Ef> {
+        let _processing = Processing::new(self);
+        self.timeouts.expire_due();
         self.executor.run_all();
 
         while let Some(capability_event) = self.capability_events.receive() {
@@ -116,12 +233,98 @@ where
     }
     // ANCHOR_END: process
 
+    /// Start shutting down the app when the platform is about to terminate it, running
+    /// [`App::will_terminate`] and returning a vector of effect requests.
+    ///
+    /// The shell should resolve the effects as usual, and check [`Core::shutdown_status`]
+    /// after each resolution, until it's done, to give critical work like persisting the
+    /// state or flushing an outbox a chance to finish. The shutdown waits for the work
+    /// started from `will_terminate` for at most the `grace` period. Calling this again
+    /// once the app is shutting down only processes pending work.
+    pub fn shutdown(&self, grace: Duration) -> Vec<Ef> {
+        let _processing = Processing::new(self);
+
+        let deadline = self.timeouts.now().saturating_add(grace);
+        if self.shutdown.start(deadline) {
+            self.executor.track_shutdown_tasks();
+
+            let mut model = self.model.write().expect("Model RwLock was poisoned.");
+            self.app.will_terminate(&mut model, &self.capabilities);
+            drop(model);
+        }
+
+        self.process()
+    }
+
+    /// How far the core got shutting down, see [`Core::shutdown`].
+    pub fn shutdown_status(&self) -> ShutdownStatus {
+        self.shutdown
+            .status(self.timeouts.now(), self.executor.shutdown_tasks())
+    }
+
+    /// Resolve the requests which the shell didn't resolve within their capability's timeout
+    /// (see [`CapabilityContext::with_timeout`](crate::capability::CapabilityContext::with_timeout)),
+    /// returning a vector of effect requests.
+    ///
+    /// Timeouts are checked every time the core processes an event or a resolution, but the
+    /// shell should also call this periodically (e.g. every second) while it has unresolved
+    /// requests, so that the app isn't stuck waiting when nothing else happens.
+    pub fn check_timeouts(&self) -> Vec<Ef> {
+        self.process()
+    }
+
+    /// Process the events sent to the app and collect the effects requested by capability
+    /// tasks since the last call, returning a vector of effect requests.
+    ///
+    /// This is only needed when the tasks run on the shell's runtime (see
+    /// [`Core::new_with_runtime`]), where they make progress outside of the core's other calls.
+    pub fn process_pending(&self) -> Vec<Ef> {
+        self.process()
+    }
+
+    /// Counters of the effect requests made by each capability since the core started,
+    /// e.g. for a diagnostics screen in the shell. See [`EffectMetrics`].
+    pub fn metrics(&self) -> EffectMetrics {
+        self.metrics.snapshot()
+    }
+
+    /// Trim the caches kept in the core when the platform warns that memory is running low,
+    /// first the ones in the model, with [`App::trim_memory`], then the ones registered by
+    /// the capabilities. Returns how many bytes were freed, see [`crate::memory`].
+    pub fn trim_memory(&self, pressure: MemoryPressure) -> TrimReport {
+        let mut model = self.model.write().expect("Model RwLock was poisoned.");
+
+        memory::trim_app(&self.app, &mut model, &self.trim_registry, pressure)
+    }
+
     /// Get the current state of the app's view model.
     pub fn view(&self) -> A::ViewModel {
         let model = self.model.read().expect("Model RwLock was poisoned.");
 
         self.app.view(&model)
     }
+
+    /// Answer a `query` about the current state of the app, see [`Queryable`].
+    pub fn query(&self, query: A::Query) -> A::QueryResponse
+    where
+        A: Queryable,
+    {
+        let model = self.model.read().expect("Model RwLock was poisoned.");
+
+        self.app.query(query, &model)
+    }
+
+    /// Answer a `query` from the shell about the work the capabilities are tracking,
+    /// see [`ShellQuery`].
+    pub fn query_capabilities(
+        &self,
+        query: <A::Capabilities as ShellQuery>::Query,
+    ) -> <A::Capabilities as ShellQuery>::QueryResponse
+    where
+        A::Capabilities: ShellQuery,
+    {
+        self.capabilities.query(query)
+    }
 }
 
 impl<Ef, A> Default for Core<Ef, A>
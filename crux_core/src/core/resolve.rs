@@ -31,10 +31,14 @@ impl<Out> Resolve<Out> {
     }
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum ResolveError {
     #[error("Attempted to resolve a request that is not expected to be resolved.")]
     Never,
     #[error("Attempted to resolve a request that has concluded.")]
     FinishedMany,
+    #[error("Request with UUID {uuid:?} not found.")]
+    NotFound { uuid: Vec<u8> },
+    #[error("Response could not be deserialized: {message}")]
+    Deserialize { message: String },
 }
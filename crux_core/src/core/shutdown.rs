@@ -0,0 +1,59 @@
+use std::{sync::Mutex, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// How far the core got shutting down, see [`Core::shutdown`](crate::Core::shutdown).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownStatus {
+    /// The core isn't shutting down
+    Running,
+    /// The work started by [`App::will_terminate`](crate::App::will_terminate) is waiting for
+    /// the shell to resolve its effects
+    Draining { outstanding: usize },
+    /// The work started by [`App::will_terminate`](crate::App::will_terminate) has finished,
+    /// the app can terminate
+    Complete,
+    /// The grace period ran out before the work started by
+    /// [`App::will_terminate`](crate::App::will_terminate) finished
+    TimedOut { outstanding: usize },
+}
+
+impl ShutdownStatus {
+    /// Whether the shell can let the app terminate, because the work has finished or the
+    /// grace period ran out.
+    pub fn is_done(&self) -> bool {
+        matches!(self, Self::Complete | Self::TimedOut { .. })
+    }
+}
+
+/// The deadline of the shutdown, once it started.
+#[derive(Default)]
+pub(crate) struct Shutdown {
+    deadline: Mutex<Option<Duration>>,
+}
+
+impl Shutdown {
+    /// Start shutting down with the `deadline`, returning `false` if the shutdown had
+    /// already started.
+    pub(crate) fn start(&self, deadline: Duration) -> bool {
+        let mut current = self.deadline.lock().expect("Shutdown Mutex was poisoned.");
+        if current.is_some() {
+            return false;
+        }
+
+        *current = Some(deadline);
+        true
+    }
+
+    pub(crate) fn status(&self, now: Duration, outstanding: usize) -> ShutdownStatus {
+        let deadline = *self.deadline.lock().expect("Shutdown Mutex was poisoned.");
+
+        match deadline {
+            None => ShutdownStatus::Running,
+            Some(_) if outstanding == 0 => ShutdownStatus::Complete,
+            Some(deadline) if now >= deadline => ShutdownStatus::TimedOut { outstanding },
+            Some(_) => ShutdownStatus::Draining { outstanding },
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::{Core, Effect, Processing};
+use crate::App;
+
+/// Settles the core after an update, one step at a time, yielding the effects
+/// each step requests as soon as it's taken. The steps are the same as in
+/// [`Core::process`], which settles the core in one go.
+pub(crate) struct Settle<'a, Ef, A>
+where
+    A: App,
+{
+    core: &'a Core<Ef, A>,
+    _processing: Processing<'a, Ef, A>,
+    ran: bool,
+    done: bool,
+}
+
+impl<'a, Ef, A> Settle<'a, Ef, A>
+where
+    Ef: Effect,
+    A: App,
+{
+    pub(crate) fn new(core: &'a Core<Ef, A>, processing: Processing<'a, Ef, A>) -> Self {
+        Self {
+            core,
+            _processing: processing,
+            ran: false,
+            done: false,
+        }
+    }
+
+    fn step(&mut self) {
+        if !self.ran {
+            self.core.timeouts.expire_due();
+            self.core.executor.run_all();
+            self.ran = true;
+            return;
+        }
+
+        let Some(capability_event) = self.core.capability_events.receive() else {
+            self.done = true;
+            return;
+        };
+
+        let mut model = self.core.model.write().expect("Model RwLock was poisoned.");
+        self.core
+            .app
+            .update(capability_event, &mut model, &self.core.capabilities);
+        drop(model);
+        self.core.executor.run_all();
+    }
+}
+
+impl<'a, Ef, A> Iterator for Settle<'a, Ef, A>
+where
+    Ef: Effect,
+    A: App,
+{
+    type Item = Ef;
+
+    fn next(&mut self) -> Option<Ef> {
+        loop {
+            if let Some(effect) = self.core.requests.receive() {
+                return Some(effect);
+            }
+            if self.done {
+                return None;
+            }
+
+            self.step();
+        }
+    }
+}
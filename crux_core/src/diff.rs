@@ -0,0 +1,488 @@
+//! Keyed diffing of lists in the view model.
+//!
+//! Shells rendering long lists can animate changes and avoid re-rendering the whole list if they
+//! know which items were inserted, removed and moved since the last render. A [`VecDiffer`] kept in
+//! the model records each version of a list in `update`, and produces a [`DiffedVec`] in `view`
+//! carrying the items along with the [`ListChanges`] from the version the shell last
+//! acknowledged. The view is computed without changing the differ, so it can be asked for any
+//! number of times.
+//!
+//! ```rust
+//! use crux_core::diff::{Identifiable, VecDiffer};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, Clone)]
+//! struct Todo {
+//!     id: u32,
+//!     title: String,
+//! }
+//!
+//! impl Identifiable for Todo {
+//!     type Id = u32;
+//!
+//!     fn id(&self) -> u32 {
+//!         self.id
+//!     }
+//! }
+//!
+//! let mut differ = VecDiffer::default();
+//! let todo = |id| Todo { id, title: format!("todo {id}") };
+//!
+//! // in `update`
+//! let todos = vec![todo(1), todo(2), todo(3)];
+//! differ.update(&todos);
+//!
+//! // in `view`
+//! let first = differ.diff(todos);
+//! assert_eq!(first.changes.inserted, vec![0, 1, 2]);
+//!
+//! // in `update`, when the shell has applied the first version
+//! differ.acknowledge(first.revision);
+//! let todos = vec![todo(3), todo(1), todo(4)];
+//! differ.update(&todos);
+//!
+//! // in `view`
+//! let second = differ.diff(todos);
+//! assert_eq!(second.base_revision, first.revision);
+//! assert_eq!(second.changes.removed, vec![1]);
+//! assert_eq!(second.changes.inserted, vec![2]);
+//! assert_eq!(second.changes.moved.len(), 1);
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Implemented by list items which have a stable identity, used to match items between
+/// the previous and the next version of a list.
+pub trait Identifiable {
+    type Id: Eq + Hash + Clone;
+
+    /// The identity of the item. Ids must be unique within a list, the changes to lists with
+    /// duplicate ids are reported as replacing the whole list.
+    fn id(&self) -> Self::Id;
+}
+
+/// A list of items for the view model, along with the changes from the version of the list
+/// the shell last acknowledged.
+///
+/// Shells holding the list at `base_revision` apply the `changes`, others reload the whole
+/// list from `items`. Either way, they then acknowledge `revision`, see
+/// [`VecDiffer::acknowledge`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffedVec<T> {
+    /// The full, current list of items
+    pub items: Vec<T>,
+    /// The revision of the current list
+    pub revision: u64,
+    /// The revision the changes are from. Revision 0 is the empty list.
+    pub base_revision: u64,
+    /// The changes from the list at `base_revision`
+    pub changes: ListChanges,
+}
+
+/// Changes between two versions of a list, in the same form as the batch updates of
+/// platform list views:
+///
+/// * `removed` indices refer to positions in the previous list
+/// * `inserted` indices refer to positions in the new list
+/// * `moved` items go from a position in the previous list to a position in the new list
+///
+/// Items which aren't mentioned kept their relative order.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListChanges {
+    pub removed: Vec<usize>,
+    pub inserted: Vec<usize>,
+    pub moved: Vec<ListMove>,
+}
+
+/// A single item moved from one position to another.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListMove {
+    pub from: usize,
+    pub to: usize,
+}
+
+impl ListChanges {
+    /// Whether the list is unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.inserted.is_empty() && self.moved.is_empty()
+    }
+
+    /// Compute the changes from the `previous` list of ids to the `next` one. If either list has
+    /// duplicate ids, all the previous items are removed and all the next ones inserted.
+    pub fn between<Id>(previous: &[Id], next: &[Id]) -> Self
+    where
+        Id: Eq + Hash,
+    {
+        let previous_positions: HashMap<&Id, usize> =
+            previous.iter().enumerate().map(|(i, id)| (id, i)).collect();
+        let next_positions: HashMap<&Id, usize> =
+            next.iter().enumerate().map(|(i, id)| (id, i)).collect();
+
+        if previous_positions.len() < previous.len() || next_positions.len() < next.len() {
+            return Self {
+                removed: (0..previous.len()).collect(),
+                inserted: (0..next.len()).collect(),
+                moved: Vec::new(),
+            };
+        }
+
+        let removed = previous
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| !next_positions.contains_key(id))
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut inserted = Vec::new();
+        // (previous index, next index) of items present in both lists, in the new order
+        let mut kept = Vec::new();
+        for (to, id) in next.iter().enumerate() {
+            match previous_positions.get(id) {
+                Some(&from) => kept.push((from, to)),
+                None => inserted.push(to),
+            }
+        }
+
+        // Items along the longest run which is still in the previous order stay put,
+        // everything else kept is moved.
+        let stable = longest_increasing_subsequence(&kept);
+        let moved = kept
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !stable.contains(i))
+            .map(|(_, &(from, to))| ListMove { from, to })
+            .collect();
+
+        Self {
+            removed,
+            inserted,
+            moved,
+        }
+    }
+}
+
+/// Returns the positions in `pairs` of the longest subsequence increasing in the first element.
+fn longest_increasing_subsequence(pairs: &[(usize, usize)]) -> HashSet<usize> {
+    // tails[k] is the position of the smallest tail of an increasing subsequence of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut predecessors: Vec<Option<usize>> = vec![None; pairs.len()];
+
+    for (i, &(value, _)) in pairs.iter().enumerate() {
+        let k = tails.partition_point(|&t| pairs[t].0 < value);
+        predecessors[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut stable = HashSet::new();
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        stable.insert(i);
+        next = predecessors[i];
+    }
+
+    stable
+}
+
+/// Keeps the ids of the versions of a list, to compute the changes from the version the
+/// shell last acknowledged. Keep a `VecDiffer` in the model for each diffed list.
+///
+/// Call [`VecDiffer::update`] in `update` whenever the list may have changed, and
+/// [`VecDiffer::diff`] in `view`. When the shell has applied a [`DiffedVec`], it sends its
+/// `revision` back to the app, which passes it to [`VecDiffer::acknowledge`]. Until then, views
+/// keep reporting the changes from the previously acknowledged version.
+///
+/// `VecDiffer` is not serializable, mark it with `#[serde(skip)]` in models which are.
+pub struct VecDiffer<Id> {
+    acknowledged: Version<Id>,
+    /// Versions after the acknowledged one, oldest first
+    pending: VecDeque<Version<Id>>,
+}
+
+struct Version<Id> {
+    revision: u64,
+    ids: Vec<Id>,
+}
+
+impl<Id> Version<Id> {
+    fn empty() -> Self {
+        Self {
+            revision: 0,
+            ids: Vec::new(),
+        }
+    }
+}
+
+impl<Id> VecDiffer<Id>
+where
+    Id: Eq + Hash + Clone,
+{
+    /// The number of unacknowledged versions kept. Shells acknowledging older versions get the
+    /// whole list to reload instead.
+    pub const MAX_PENDING: usize = 16;
+
+    /// Record `items` as the current version of the list, if their ids changed.
+    pub fn update<T>(&mut self, items: &[T])
+    where
+        T: Identifiable<Id = Id>,
+    {
+        let ids: Vec<Id> = items.iter().map(Identifiable::id).collect();
+
+        let current = self.current();
+        if ids == current.ids {
+            return;
+        }
+
+        let revision = current.revision + 1;
+        self.pending.push_back(Version { revision, ids });
+        if self.pending.len() > Self::MAX_PENDING {
+            self.pending.pop_front();
+        }
+    }
+
+    /// Produce the view model representation of `items`, which should be the list last passed
+    /// to [`update`](VecDiffer::update), including the changes from the acknowledged version.
+    pub fn diff<T>(&self, items: Vec<T>) -> DiffedVec<T>
+    where
+        T: Identifiable<Id = Id>,
+    {
+        let ids: Vec<Id> = items.iter().map(Identifiable::id).collect();
+        let current = self.current();
+        debug_assert!(
+            ids == current.ids,
+            "VecDiffer::diff called with a list which wasn't passed to VecDiffer::update"
+        );
+
+        DiffedVec {
+            items,
+            revision: current.revision,
+            base_revision: self.acknowledged.revision,
+            changes: ListChanges::between(&self.acknowledged.ids, &ids),
+        }
+    }
+
+    /// Note that the shell has applied the list at `revision`, so that the following diffs are
+    /// from it. Revisions older than the acknowledged one, or no longer kept, are ignored.
+    pub fn acknowledge(&mut self, revision: u64) {
+        if let Some(index) = self.pending.iter().position(|v| v.revision == revision) {
+            self.pending.drain(..index);
+            self.acknowledged = self.pending.pop_front().expect("position is in pending");
+        }
+    }
+
+    /// Forget the acknowledged version of the list, so the next diff reports all items
+    /// as inserted. Use this when the shell has lost its copy of the list.
+    pub fn reset(&mut self) {
+        let acknowledged = std::mem::replace(&mut self.acknowledged, Version::empty());
+        if self.pending.is_empty() && acknowledged.revision > 0 {
+            self.pending.push_back(acknowledged);
+        }
+    }
+
+    fn current(&self) -> &Version<Id> {
+        self.pending.back().unwrap_or(&self.acknowledged)
+    }
+}
+
+impl<Id> Default for VecDiffer<Id> {
+    fn default() -> Self {
+        Self {
+            acknowledged: Version::empty(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<Id> std::fmt::Debug for VecDiffer<Id>
+where
+    Id: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let current = self.pending.back().unwrap_or(&self.acknowledged);
+
+        f.debug_struct("VecDiffer")
+            .field("revision", &current.revision)
+            .field("ids", &current.ids)
+            .field("acknowledged", &self.acknowledged.revision)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply the changes the way a shell would, and return the resulting list.
+    fn apply(previous: &[char], next: &[char], changes: &ListChanges) -> Vec<char> {
+        let mut slots: Vec<Option<char>> = vec![None; next.len()];
+        for (i, item) in previous.iter().enumerate() {
+            if changes.removed.contains(&i) || changes.moved.iter().any(|m| m.from == i) {
+                continue;
+            }
+            // unchanged items keep their relative order, fill the first free slot which
+            // is neither an insertion nor a move target
+            let slot = (0..next.len())
+                .find(|&s| {
+                    slots[s].is_none()
+                        && !changes.inserted.contains(&s)
+                        && !changes.moved.iter().any(|m| m.to == s)
+                })
+                .unwrap();
+            slots[slot] = Some(*item);
+        }
+        for m in &changes.moved {
+            slots[m.to] = Some(previous[m.from]);
+        }
+        for &i in &changes.inserted {
+            slots[i] = Some(next[i]);
+        }
+
+        slots.into_iter().map(Option::unwrap).collect()
+    }
+
+    fn check(previous: &str, next: &str) -> ListChanges {
+        let previous: Vec<char> = previous.chars().collect();
+        let next: Vec<char> = next.chars().collect();
+        let changes = ListChanges::between(&previous, &next);
+
+        assert_eq!(apply(&previous, &next, &changes), next);
+
+        changes
+    }
+
+    #[test]
+    fn unchanged_list_has_no_changes() {
+        assert!(check("abcd", "abcd").is_empty());
+    }
+
+    #[test]
+    fn insertions_and_removals() {
+        let changes = check("abcd", "xacdy");
+
+        assert_eq!(changes.removed, vec![1]);
+        assert_eq!(changes.inserted, vec![0, 4]);
+        assert!(changes.moved.is_empty());
+    }
+
+    #[test]
+    fn moving_one_item_is_a_single_move() {
+        let changes = check("abcde", "bcdea");
+
+        assert_eq!(changes.moved, vec![ListMove { from: 0, to: 4 }]);
+        assert!(changes.removed.is_empty());
+        assert!(changes.inserted.is_empty());
+    }
+
+    #[test]
+    fn reversal() {
+        let changes = check("abcd", "dcba");
+
+        assert_eq!(changes.moved.len(), 3);
+    }
+
+    #[test]
+    fn mixed_changes() {
+        check("abcdefgh", "hxbgcyda");
+        check("", "abc");
+        check("abc", "");
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Item(u8);
+
+    impl Identifiable for Item {
+        type Id = u8;
+
+        fn id(&self) -> u8 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn duplicate_ids_replace_the_whole_list() {
+        let changes = check("abca", "ab");
+
+        assert_eq!(changes.removed, vec![0, 1, 2, 3]);
+        assert_eq!(changes.inserted, vec![0, 1]);
+        assert!(changes.moved.is_empty());
+    }
+
+    #[test]
+    fn differ_diffs_from_the_acknowledged_version() {
+        let mut differ = VecDiffer::default();
+
+        differ.update(&[Item(1), Item(2)]);
+        let first = differ.diff(vec![Item(1), Item(2)]);
+        assert_eq!(first.changes.inserted, vec![0, 1]);
+        assert_eq!((first.base_revision, first.revision), (0, 1));
+
+        differ.acknowledge(first.revision);
+        differ.update(&[Item(2), Item(1)]);
+        let second = differ.diff(vec![Item(2), Item(1)]);
+        assert_eq!(second.changes.moved.len(), 1);
+        assert_eq!(second.items, vec![Item(2), Item(1)]);
+        assert_eq!((second.base_revision, second.revision), (1, 2));
+
+        // unchanged ids don't make a new revision
+        differ.update(&[Item(2), Item(1)]);
+        assert_eq!(differ.diff(vec![Item(2), Item(1)]), second);
+
+        differ.acknowledge(second.revision);
+        let third = differ.diff(vec![Item(2), Item(1)]);
+        assert!(third.changes.is_empty());
+
+        differ.reset();
+        let fourth = differ.diff(vec![Item(2), Item(1)]);
+        assert_eq!(fourth.changes.inserted, vec![0, 1]);
+    }
+
+    #[test]
+    fn viewing_twice_reports_the_same_changes() {
+        let mut differ = VecDiffer::default();
+        differ.update(&[Item(1), Item(2)]);
+        differ.acknowledge(1);
+
+        differ.update(&[Item(2), Item(3)]);
+        let first = differ.diff(vec![Item(2), Item(3)]);
+        let second = differ.diff(vec![Item(2), Item(3)]);
+
+        assert_eq!(first, second);
+        assert_eq!(first.changes.removed, vec![0]);
+        assert_eq!(first.changes.inserted, vec![1]);
+    }
+
+    #[test]
+    fn changes_accumulate_until_acknowledged() {
+        let mut differ = VecDiffer::default();
+        differ.update(&[Item(1)]);
+        differ.acknowledge(1);
+
+        differ.update(&[Item(1), Item(2)]);
+        differ.update(&[Item(1), Item(2), Item(3)]);
+        let diffed = differ.diff(vec![Item(1), Item(2), Item(3)]);
+        assert_eq!((diffed.base_revision, diffed.revision), (1, 3));
+        assert_eq!(diffed.changes.inserted, vec![1, 2]);
+
+        // the shell applied revision 2 in the meantime
+        differ.acknowledge(2);
+        let diffed = differ.diff(vec![Item(1), Item(2), Item(3)]);
+        assert_eq!(diffed.base_revision, 2);
+        assert_eq!(diffed.changes.inserted, vec![2]);
+
+        // stale acknowledgements are ignored
+        differ.acknowledge(1);
+        assert_eq!(
+            differ.diff(vec![Item(1), Item(2), Item(3)]).base_revision,
+            2
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! A common error type for capabilities.
+//!
+//! Each capability has its own error type, describing its failures in detail
+//! (e.g. `crux_http::HttpError` or `crux_kv::KeyValueError`). Those errors can be
+//! converted into a [`CapabilityError`], which classifies them the same way
+//! across all capabilities, so that an app can share error handling and retry
+//! policy between them:
+//!
+//! ```rust
+//! use crux_core::error::CapabilityError;
+//!
+//! fn should_retry(error: impl Into<CapabilityError>, attempt: u32) -> bool {
+//!     error.into().is_transient() && attempt < 3
+//! }
+//!
+//! assert!(should_retry(CapabilityError::Timeout, 1));
+//! assert!(!should_retry(CapabilityError::PermissionDenied, 1));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::capability::ShellTimeout;
+
+/// The kind of failure a capability encountered, independent of the capability.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CapabilityError {
+    /// The shell can't currently perform the request, e.g. because the device is offline,
+    /// or the service is overloaded
+    #[error("unavailable")]
+    Unavailable,
+    /// The user or the platform did not allow the request
+    #[error("permission denied")]
+    PermissionDenied,
+    /// The request did not complete in time
+    #[error("timeout")]
+    Timeout,
+    /// The requested resource does not exist
+    #[error("not found")]
+    NotFound,
+    /// The request or its response exceeded a size limit
+    #[error("too large")]
+    TooLarge,
+    /// The data given to, or received by, the capability was not valid
+    #[error("invalid data: {0}")]
+    InvalidData(String),
+    /// The shell failed to perform the request for another reason
+    #[error("shell failure: {0}")]
+    ShellFailure(String),
+    /// The request conflicted with the current state, e.g. a compare-and-swap write to a
+    /// value which had changed since it was read, so it should be retried after reading the
+    /// state again, rather than as it is
+    #[error("conflict: {0}")]
+    Conflict(String),
+}
+
+impl CapabilityError {
+    /// Whether the failure is likely to be temporary, such that the same request
+    /// might succeed if retried later.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Unavailable | Self::Timeout)
+    }
+}
+
+impl From<ShellTimeout> for CapabilityError {
+    fn from(_: ShellTimeout) -> Self {
+        Self::Timeout
+    }
+}
+
+/// Make an event from a capability's result with `ok` if it succeeded, or with `err` if it
+/// failed, instead of matching on a `Result` in the event. The error is converted into the
+/// type `err` takes, so that the failures of several capabilities can be sent to one event,
+/// e.g. with a [`CapabilityError`]:
+///
+/// ```rust,ignore
+/// caps.http
+///     .get(url)
+///     .expect_json()
+///     .send(route(Event::Fetched, Event::Failed));
+/// caps.key_value
+///     .get(key, route(Event::Loaded, Event::Failed));
+///
+/// // ...
+/// Event::Failed(error) if error.is_transient() => { /* retry */ }
+/// ```
+pub fn route<T, E, Error, Ev>(
+    ok: impl FnOnce(T) -> Ev + Send + 'static,
+    err: impl FnOnce(Error) -> Ev + Send + 'static,
+) -> impl FnOnce(Result<T, E>) -> Ev + Send + 'static
+where
+    E: Into<Error>,
+{
+    move |result| match result {
+        Ok(value) => ok(value),
+        Err(error) => err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Loaded(Vec<u8>),
+        Failed(CapabilityError),
+    }
+
+    #[test]
+    fn results_are_routed_to_events() {
+        let loaded = route(Event::Loaded, Event::Failed);
+        assert_eq!(
+            loaded(Ok::<_, ShellTimeout>(vec![1])),
+            Event::Loaded(vec![1])
+        );
+
+        let failed = route(Event::Loaded, Event::Failed);
+        assert_eq!(
+            failed(Err(ShellTimeout {
+                timeout: Duration::from_secs(1),
+            })),
+            Event::Failed(CapabilityError::Timeout)
+        );
+    }
+
+    #[test]
+    fn shell_timeouts_are_transient() {
+        let error = CapabilityError::from(ShellTimeout {
+            timeout: Duration::from_secs(1),
+        });
+
+        assert_eq!(error, CapabilityError::Timeout);
+        assert!(error.is_transient());
+        assert!(!CapabilityError::ShellFailure("broken".to_string()).is_transient());
+    }
+}
@@ -150,9 +150,18 @@
 //! See [typegen] for details.
 //!
 
+pub mod a11y;
 pub mod bridge;
 pub mod capability;
+pub mod diff;
+pub mod error;
+pub mod memo;
+pub mod memory;
+pub mod migrations;
+pub mod preview;
+pub mod shared;
 pub mod testing;
+pub mod text_field;
 #[cfg(feature = "typegen")]
 pub mod typegen;
 
@@ -164,7 +173,7 @@ use serde::Serialize;
 pub use self::{
     capabilities::*,
     capability::{Capability, WithContext},
-    core::{Core, Effect, Request},
+    core::{Core, Effect, Request, ResolveError, ShutdownStatus},
 };
 pub use crux_macros as macros;
 
@@ -195,4 +204,46 @@ pub trait App: Default {
 
     /// View method is used by the Shell to request the current state of the user interface
     fn view(&self, model: &Self::Model) -> Self::ViewModel;
+
+    /// Init method runs once when the [`Core`] is created, after the `model` is set to its
+    /// default, to start the work the app needs before its first event, e.g. restoring the
+    /// state from storage, refreshing the session or fetching the configuration.
+    ///
+    /// Like `update`, it can mutate the `model` and use the capabilities in `caps`. Later
+    /// stages of the startup follow from the events the capabilities send back to `update`.
+    /// The shell collects the effects requested during `init` with [`Core::start`].
+    /// Does nothing by default.
+    fn init(&self, _model: &mut Self::Model, _caps: &Self::Capabilities) {}
+
+    /// Will terminate method runs when the shell calls [`Core::shutdown`], because the platform
+    /// is about to terminate the app, to start the work which must finish before it does, e.g.
+    /// persisting the state or flushing an outbox.
+    ///
+    /// Like `update`, it can mutate the `model` and use the capabilities in `caps`. The shell
+    /// waits a bounded time for this work, so only the critical work should be started.
+    /// Does nothing by default.
+    fn will_terminate(&self, _model: &mut Self::Model, _caps: &Self::Capabilities) {}
+
+    /// Trim the caches kept in the `model`, e.g. [`Memo`](crate::memo::Memo) cells or a search
+    /// index, when memory is running low, returning roughly how many bytes were freed.
+    /// See [`Core::trim_memory`]. Keeps everything by default.
+    fn trim_memory(&self, _model: &mut Self::Model, _pressure: memory::MemoryPressure) -> usize {
+        0
+    }
+}
+
+/// Implement [`Queryable`] on your app to let the shell read small slices of the app's state
+/// with [`Core::query`], without going through [`App::view`] or sending an event.
+///
+/// This is useful for shell extensions like widgets or watch complications, which only need
+/// a tiny part of the state and shouldn't pay for building the whole view model. Queries only
+/// have read access to the model, and can't request any effects.
+pub trait Queryable: App {
+    /// Query, typically an `enum`, defines the questions the shell can ask about the state
+    type Query;
+    /// QueryResponse, typically an `enum`, holds the answers to the queries
+    type QueryResponse: Serialize;
+
+    /// Answer the `query` from the current `model`
+    fn query(&self, query: Self::Query, model: &Self::Model) -> Self::QueryResponse;
 }
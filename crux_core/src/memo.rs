@@ -0,0 +1,195 @@
+//! Memoization of expensive derived values.
+//!
+//! The [`App::view`](crate::App::view) function is called every time the shell renders, and
+//! only has read access to the model. Derivations which are expensive to compute (sorting or
+//! filtering a large list, for example) can be cached in a [`Memo`] cell kept in the model. The
+//! cell remembers the inputs the value was computed from, and only recomputes it when they change.
+//!
+//! ```rust
+//! use crux_core::memo::Memo;
+//!
+//! #[derive(Default)]
+//! struct Model {
+//!     items: Vec<String>,
+//!     // bumped every time `items` changes, so the memo doesn't have to compare whole lists
+//!     items_revision: usize,
+//!     filter: String,
+//!     visible: Memo<(usize, String), Vec<String>>,
+//! }
+//!
+//! fn visible_items(model: &Model) -> Vec<String> {
+//!     model.visible.get((model.items_revision, model.filter.clone()), |(_, filter)| {
+//!         let mut items: Vec<_> = model
+//!             .items
+//!             .iter()
+//!             .filter(|item| item.contains(filter.as_str()))
+//!             .cloned()
+//!             .collect();
+//!         items.sort();
+//!         items
+//!     })
+//! }
+//!
+//! let mut model = Model::default();
+//! model.items = vec!["pear".to_string(), "apple".to_string(), "plum".to_string()];
+//! model.items_revision += 1;
+//! model.filter = "p".to_string();
+//!
+//! assert_eq!(visible_items(&model), vec!["apple", "pear", "plum"]);
+//! ```
+
+use std::{fmt, sync::Mutex};
+
+/// A cell caching a value of type `T`, computed from inputs of type `Inputs`.
+///
+/// The value is recomputed when [`Memo::get`] is called with inputs which are not equal
+/// to the inputs used the last time the value was computed. Inputs should be cheap to compare,
+/// for large collections prefer a revision counter the app increments whenever the collection
+/// changes.
+///
+/// `Memo` is not serializable, mark it with `#[serde(skip)]` in models which are. It will
+/// be empty after deserialization and recompute the value on first use.
+pub struct Memo<Inputs, T> {
+    cache: Mutex<Option<(Inputs, T)>>,
+}
+
+impl<Inputs, T> Memo<Inputs, T>
+where
+    Inputs: PartialEq,
+    T: Clone,
+{
+    /// Create an empty memo cell.
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Get the value computed from `inputs`, calling `compute` to produce it if it
+    /// hasn't been computed from equal inputs before.
+    ///
+    /// The cell isn't locked while `compute` runs, so it can read the memo itself, e.g. to
+    /// derive the value from the one for other inputs.
+    pub fn get<F>(&self, inputs: Inputs, compute: F) -> T
+    where
+        F: FnOnce(&Inputs) -> T,
+    {
+        {
+            let cache = self.cache.lock().expect("Memo Mutex was poisoned.");
+            if let Some((cached_inputs, value)) = &*cache {
+                if *cached_inputs == inputs {
+                    return value.clone();
+                }
+            }
+        }
+
+        let value = compute(&inputs);
+        *self.cache.lock().expect("Memo Mutex was poisoned.") = Some((inputs, value.clone()));
+
+        value
+    }
+
+    /// Whether the memo currently holds a value computed from `inputs`.
+    pub fn is_fresh(&self, inputs: &Inputs) -> bool {
+        let cache = self.cache.lock().expect("Memo Mutex was poisoned.");
+
+        matches!(&*cache, Some((cached_inputs, _)) if cached_inputs == inputs)
+    }
+
+    /// Drop the cached value, forcing the next call to [`Memo::get`] to recompute it.
+    pub fn invalidate(&self) {
+        *self.cache.lock().expect("Memo Mutex was poisoned.") = None;
+    }
+}
+
+impl<Inputs, T> Default for Memo<Inputs, T>
+where
+    Inputs: PartialEq,
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Inputs, T> Clone for Memo<Inputs, T>
+where
+    Inputs: Clone,
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        let cache = self.cache.lock().expect("Memo Mutex was poisoned.");
+
+        Self {
+            cache: Mutex::new(cache.clone()),
+        }
+    }
+}
+
+impl<Inputs, T> fmt::Debug for Memo<Inputs, T>
+where
+    Inputs: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cache = self.cache.lock().expect("Memo Mutex was poisoned.");
+
+        f.debug_struct("Memo")
+            .field("inputs", &cache.as_ref().map(|(inputs, _)| inputs))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(Memo<usize, Vec<String>>: Send, Sync);
+
+    #[test]
+    fn computes_once_for_equal_inputs() {
+        let memo = Memo::<u32, u32>::new();
+        let calls = Cell::new(0);
+        let square = |n: &u32| {
+            calls.set(calls.get() + 1);
+            n * n
+        };
+
+        assert_eq!(memo.get(3, square), 9);
+        assert_eq!(memo.get(3, square), 9);
+        assert_eq!(calls.get(), 1);
+
+        assert_eq!(memo.get(4, square), 16);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn invalidate_forces_recompute() {
+        let memo = Memo::<(), String>::default();
+
+        memo.get((), |_| "first".to_string());
+        assert!(memo.is_fresh(&()));
+
+        memo.invalidate();
+        assert!(!memo.is_fresh(&()));
+
+        assert_eq!(memo.get((), |_| "second".to_string()), "second");
+    }
+
+    #[test]
+    fn compute_can_read_the_memo() {
+        let memo = Memo::<u32, u32>::new();
+
+        let value = memo.get(2, |n| {
+            assert!(!memo.is_fresh(n));
+            memo.get(n - 1, |m| m * 10) + 1
+        });
+
+        assert_eq!(value, 11);
+        assert!(memo.is_fresh(&2));
+        assert_eq!(memo.get(2, |_| unreachable!()), 11);
+    }
+}
@@ -0,0 +1,230 @@
+//! Trimming caches when the device runs low on memory.
+//!
+//! Caches kept in the core, like decoded images, memoized view data or a search index, make
+//! the app faster, but on low-end devices they can get the app killed by the platform when
+//! memory runs short. When the shell gets a memory warning from the platform, it calls
+//! [`Core::trim_memory`](crate::Core::trim_memory) with the [`MemoryPressure`], and the core
+//! trims the caches, reporting how many bytes they freed.
+//!
+//! Capabilities register the caches they keep with the [`TrimRegistry`] of their context
+//! (see [`CapabilityContext::trim_registry`](crate::capability::CapabilityContext::trim_registry)),
+//! and caches kept in the model are trimmed by [`App::trim_memory`](crate::App::trim_memory).
+//!
+//! ```rust
+//! use std::sync::{Arc, Mutex};
+//!
+//! use crux_core::memory::{MemoryPressure, Trim, TrimRegistry};
+//!
+//! #[derive(Default)]
+//! struct ImageCache {
+//!     images: Mutex<Vec<Vec<u8>>>,
+//! }
+//!
+//! impl Trim for ImageCache {
+//!     fn trim(&self, pressure: MemoryPressure) -> usize {
+//!         let mut images = self.images.lock().unwrap();
+//!         // keep the most recent half, unless memory is critically low
+//!         let keep = match pressure {
+//!             MemoryPressure::Moderate => images.len() / 2,
+//!             MemoryPressure::Critical => 0,
+//!         };
+//!         let trimmed = images.len() - keep;
+//!
+//!         images.drain(..trimmed).map(|image| image.len()).sum()
+//!     }
+//! }
+//!
+//! let registry = TrimRegistry::default();
+//! let cache = Arc::new(ImageCache::default());
+//! registry.register("images", &cache);
+//!
+//! cache.images.lock().unwrap().extend([vec![0; 100], vec![0; 200]]);
+//!
+//! let report = registry.trim(MemoryPressure::Moderate);
+//! assert_eq!(report.freed_bytes, 100);
+//! ```
+
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+
+use crate::App;
+
+/// How urgently the platform needs memory back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryPressure {
+    /// Memory is running low (e.g. Android's `TRIM_MEMORY_RUNNING_LOW`), caches should give
+    /// up what's cheap to rebuild
+    Moderate,
+    /// The app is about to be killed (e.g. an iOS memory warning, or Android's
+    /// `TRIM_MEMORY_RUNNING_CRITICAL`), caches should free everything they can
+    Critical,
+}
+
+/// A cache which can give memory back, see the [module docs](self).
+pub trait Trim: Send + Sync {
+    /// Free memory according to the `pressure`, returning roughly how many bytes were freed.
+    fn trim(&self, pressure: MemoryPressure) -> usize;
+}
+
+/// How much memory a cache freed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimmedCache {
+    pub name: String,
+    pub freed_bytes: usize,
+}
+
+/// How much memory the caches freed in total, and each of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimReport {
+    pub freed_bytes: usize,
+    pub caches: Vec<TrimmedCache>,
+}
+
+impl TrimReport {
+    /// Add the bytes freed by the cache called `name` to the report.
+    pub fn add(&mut self, name: impl Into<String>, freed_bytes: usize) {
+        self.freed_bytes += freed_bytes;
+        self.caches.push(TrimmedCache {
+            name: name.into(),
+            freed_bytes,
+        });
+    }
+}
+
+type Caches = Vec<(String, Weak<dyn Trim>)>;
+
+/// The caches to trim when memory runs low, shared by all its clones.
+///
+/// The registry only holds on to the caches weakly, so a cache which is dropped is also
+/// forgotten by the registry.
+#[derive(Clone, Default)]
+pub struct TrimRegistry {
+    caches: Arc<Mutex<Caches>>,
+}
+
+impl TrimRegistry {
+    /// Trim the `cache` when memory runs low, reporting it as `name`. Caches are trimmed in
+    /// the order they were registered.
+    pub fn register<T>(&self, name: impl Into<String>, cache: &Arc<T>)
+    where
+        T: Trim + 'static,
+    {
+        let cache: Arc<dyn Trim> = cache.clone();
+
+        self.caches
+            .lock()
+            .expect("TrimRegistry Mutex was poisoned.")
+            .push((name.into(), Arc::downgrade(&cache)));
+    }
+
+    /// Trim all the caches which are still alive according to the `pressure`.
+    pub fn trim(&self, pressure: MemoryPressure) -> TrimReport {
+        // don't hold the lock while trimming, caches may register other caches
+        let caches: Vec<_> = {
+            let mut caches = self
+                .caches
+                .lock()
+                .expect("TrimRegistry Mutex was poisoned.");
+            caches.retain(|(_, cache)| cache.strong_count() > 0);
+            caches.clone()
+        };
+
+        let mut report = TrimReport::default();
+        for (name, cache) in caches {
+            if let Some(cache) = cache.upgrade() {
+                report.add(name, cache.trim(pressure));
+            }
+        }
+
+        report
+    }
+}
+
+/// Trim the caches in the `model`, then the ones in the `registry`.
+pub(crate) fn trim_app<A: App>(
+    app: &A,
+    model: &mut A::Model,
+    registry: &TrimRegistry,
+    pressure: MemoryPressure,
+) -> TrimReport {
+    let mut report = TrimReport::default();
+
+    let freed_bytes = app.trim_memory(model, pressure);
+    if freed_bytes > 0 {
+        report.add("model", freed_bytes);
+    }
+
+    let registered = registry.trim(pressure);
+    report.freed_bytes += registered.freed_bytes;
+    report.caches.extend(registered.caches);
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    struct Cache(AtomicUsize);
+
+    impl Trim for Cache {
+        fn trim(&self, pressure: MemoryPressure) -> usize {
+            let size = self.0.load(Ordering::SeqCst);
+            let freed = match pressure {
+                MemoryPressure::Moderate => size / 2,
+                MemoryPressure::Critical => size,
+            };
+            self.0.fetch_sub(freed, Ordering::SeqCst);
+
+            freed
+        }
+    }
+
+    #[test]
+    fn trims_progressively() {
+        let registry = TrimRegistry::default();
+        let a = Arc::new(Cache(AtomicUsize::new(100)));
+        let b = Arc::new(Cache(AtomicUsize::new(10)));
+        registry.register("a", &a);
+        registry.register("b", &b);
+
+        let report = registry.trim(MemoryPressure::Moderate);
+        assert_eq!(report.freed_bytes, 55);
+        assert_eq!(
+            report.caches,
+            vec![
+                TrimmedCache {
+                    name: "a".to_string(),
+                    freed_bytes: 50
+                },
+                TrimmedCache {
+                    name: "b".to_string(),
+                    freed_bytes: 5
+                },
+            ]
+        );
+
+        assert_eq!(registry.trim(MemoryPressure::Critical).freed_bytes, 55);
+        assert_eq!(registry.trim(MemoryPressure::Critical).freed_bytes, 0);
+    }
+
+    #[test]
+    fn forgets_dropped_caches() {
+        let registry = TrimRegistry::default();
+        let cache = Arc::new(Cache(AtomicUsize::new(100)));
+        registry.register("cache", &cache);
+        drop(cache);
+
+        assert_eq!(
+            registry.trim(MemoryPressure::Critical),
+            TrimReport::default()
+        );
+        assert!(registry.caches.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! Versioned persistence for the app's model.
+//!
+//! Apps which persist their model (for example using a key-value store) will eventually change the
+//! shape of it. Rather than failing to deserialize the old data and silently starting from scratch,
+//! an app can declare a [`Migrations`] registry with the current version of the model and a step for
+//! each version it used to be at. Persisted data is wrapped in a small envelope carrying the version
+//! it was written with, and the steps are run in order when the data is restored.
+//!
+//! ```rust
+//! use crux_core::migrations::Migrations;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct ModelV1 {
+//!     name: String,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Model {
+//!     first_name: String,
+//!     last_name: String,
+//! }
+//!
+//! let migrations = Migrations::<Model>::new(2)
+//!     // data written before versioning was introduced is treated as version 0
+//!     .register(0, |v0: ModelV1| v0)
+//!     .register(1, |v1: ModelV1| {
+//!         let (first, last) = v1.name.split_once(' ').unwrap_or((&v1.name, ""));
+//!         Model {
+//!             first_name: first.to_string(),
+//!             last_name: last.to_string(),
+//!         }
+//!     });
+//!
+//! let restored = migrations.restore(br#"{"name":"Ada Lovelace"}"#).unwrap();
+//!
+//! assert_eq!(restored.applied, vec![0, 1]);
+//! assert_eq!(restored.model.last_name, "Lovelace");
+//! ```
+
+use std::{collections::BTreeMap, fmt, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+type Step = Box<dyn Fn(Value) -> Result<Value, MigrationError> + Send + Sync>;
+
+/// A registry of migration steps for the `Model` type, keyed by the version they migrate from.
+///
+/// The step registered for version `n` migrates the data to version `n + 1`. The registry
+/// must have a step for every version between the oldest version it expects to see and
+/// the current version.
+pub struct Migrations<Model> {
+    version: u32,
+    steps: BTreeMap<u32, Step>,
+    model: PhantomData<fn() -> Model>,
+}
+
+/// The envelope persisted data is stored in. The version is under a reserved key, and no
+/// other fields are allowed, so that unversioned data with `version` and `data` fields isn't
+/// mistaken for an envelope.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Versioned<T> {
+    #[serde(rename = "$crux_version")]
+    version: u32,
+    data: T,
+}
+
+/// A model restored from persisted data, along with a report of what it took to restore it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migrated<Model> {
+    /// The restored model, at the current version
+    pub model: Model,
+    /// The version the data was persisted with
+    pub from_version: u32,
+    /// The versions which were migrated from, in the order the migrations ran.
+    /// Empty if the data was already at the current version.
+    pub applied: Vec<u32>,
+}
+
+impl<Model> Migrated<Model> {
+    /// Whether any migrations ran while restoring the model. If they did, the app will
+    /// typically want to persist the model again, in its current version.
+    pub fn was_migrated(&self) -> bool {
+        !self.applied.is_empty()
+    }
+}
+
+/// Error type for model migrations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum MigrationError {
+    #[error("could not decode data at version {version}: {message}")]
+    Decode { version: u32, message: String },
+    #[error("could not encode data at version {version}: {message}")]
+    Encode { version: u32, message: String },
+    #[error("data version {stored} is newer than the current version {current}")]
+    UnknownVersion { stored: u32, current: u32 },
+    #[error("no migration registered from version {version}")]
+    MissingStep { version: u32 },
+}
+
+impl<Model> Migrations<Model>
+where
+    Model: Serialize + DeserializeOwned,
+{
+    /// Create a registry for a model whose current version is `version`.
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            steps: BTreeMap::new(),
+            model: PhantomData,
+        }
+    }
+
+    /// The current version of the model
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Register a step migrating data persisted at `from_version` to `from_version + 1`.
+    ///
+    /// `Old` is the shape of the data at `from_version` and `New` the shape at the next version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a step for `from_version` is already registered, or if `from_version` is not
+    /// older than the current version.
+    pub fn register<Old, New, F>(mut self, from_version: u32, step: F) -> Self
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+        F: Fn(Old) -> New + Send + Sync + 'static,
+    {
+        assert!(
+            from_version < self.version,
+            "migration from version {from_version} is not older than the current version {}",
+            self.version
+        );
+
+        let step: Step = Box::new(move |value| {
+            let old: Old = serde_json::from_value(value).map_err(|e| MigrationError::Decode {
+                version: from_version,
+                message: e.to_string(),
+            })?;
+
+            serde_json::to_value(step(old)).map_err(|e| MigrationError::Encode {
+                version: from_version + 1,
+                message: e.to_string(),
+            })
+        });
+
+        let previous = self.steps.insert(from_version, step);
+        assert!(
+            previous.is_none(),
+            "migration from version {from_version} registered twice"
+        );
+
+        self
+    }
+
+    /// Serialize the `model` at the current version, ready to be persisted.
+    pub fn persist(&self, model: &Model) -> Result<Vec<u8>, MigrationError> {
+        serde_json::to_vec(&Versioned {
+            version: self.version,
+            data: model,
+        })
+        .map_err(|e| MigrationError::Encode {
+            version: self.version,
+            message: e.to_string(),
+        })
+    }
+
+    /// Restore the model from persisted `bytes`, running any migrations necessary to bring
+    /// the data to the current version.
+    ///
+    /// Data which was not persisted with [`Migrations::persist`] (i.e. isn't an envelope with
+    /// exactly a `$crux_version` and a `data` field) is assumed to be at version 0.
+    pub fn restore(&self, bytes: &[u8]) -> Result<Migrated<Model>, MigrationError> {
+        let Versioned { version, data } = match serde_json::from_slice(bytes) {
+            Ok(versioned) => versioned,
+            Err(_) => Versioned {
+                version: 0,
+                data: serde_json::from_slice(bytes).map_err(|e| MigrationError::Decode {
+                    version: 0,
+                    message: e.to_string(),
+                })?,
+            },
+        };
+
+        if version > self.version {
+            return Err(MigrationError::UnknownVersion {
+                stored: version,
+                current: self.version,
+            });
+        }
+
+        let mut data = data;
+        let mut applied = Vec::new();
+        for from in version..self.version {
+            let step = self
+                .steps
+                .get(&from)
+                .ok_or(MigrationError::MissingStep { version: from })?;
+
+            data = step(data)?;
+            applied.push(from);
+        }
+
+        let model = serde_json::from_value(data).map_err(|e| MigrationError::Decode {
+            version: self.version,
+            message: e.to_string(),
+        })?;
+
+        Ok(Migrated {
+            model,
+            from_version: version,
+            applied,
+        })
+    }
+}
+
+impl<Model> fmt::Debug for Migrations<Model> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("version", &self.version)
+            .field("steps", &self.steps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct V0 {
+        count: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct V1 {
+        count: u64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Model {
+        count: u64,
+        label: String,
+    }
+
+    fn migrations() -> Migrations<Model> {
+        Migrations::new(2)
+            .register(0, |v0: V0| V1 {
+                count: v0.count.into(),
+            })
+            .register(1, |v1: V1| Model {
+                count: v1.count,
+                label: format!("{} items", v1.count),
+            })
+    }
+
+    #[test]
+    fn restores_current_version_without_migrating() {
+        let migrations = migrations();
+        let model = Model {
+            count: 3,
+            label: "three".to_string(),
+        };
+
+        let bytes = migrations.persist(&model).unwrap();
+        let restored = migrations.restore(&bytes).unwrap();
+
+        assert_eq!(restored.model, model);
+        assert_eq!(restored.from_version, 2);
+        assert!(!restored.was_migrated());
+    }
+
+    #[test]
+    fn migrates_versioned_data() {
+        let bytes = br#"{"$crux_version":1,"data":{"count":7}}"#;
+
+        let restored = migrations().restore(bytes).unwrap();
+
+        assert_eq!(restored.from_version, 1);
+        assert_eq!(restored.applied, vec![1]);
+        assert_eq!(
+            restored.model,
+            Model {
+                count: 7,
+                label: "7 items".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn treats_unversioned_data_as_version_zero() {
+        let restored = migrations().restore(br#"{"count":2}"#).unwrap();
+
+        assert_eq!(restored.from_version, 0);
+        assert_eq!(restored.applied, vec![0, 1]);
+        assert_eq!(restored.model.label, "2 items");
+    }
+
+    #[test]
+    fn unversioned_data_can_look_like_an_envelope() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Release {
+            version: u32,
+            data: String,
+        }
+
+        let migrations = Migrations::<Release>::new(1).register(0, |v0: Release| v0);
+        let restored = migrations
+            .restore(br#"{"version":3,"data":"notes"}"#)
+            .unwrap();
+
+        assert_eq!(restored.from_version, 0);
+        assert_eq!(
+            restored.model,
+            Release {
+                version: 3,
+                data: "notes".to_string()
+            }
+        );
+
+        let bytes = migrations.persist(&restored.model).unwrap();
+        assert_eq!(
+            bytes,
+            br#"{"$crux_version":1,"data":{"version":3,"data":"notes"}}"#
+        );
+        assert_eq!(migrations.restore(&bytes).unwrap().model, restored.model);
+    }
+
+    #[test]
+    fn reports_decode_errors_with_version() {
+        let error = migrations()
+            .restore(br#"{"$crux_version":1,"data":{"count":"seven"}}"#)
+            .unwrap_err();
+
+        assert!(matches!(error, MigrationError::Decode { version: 1, .. }));
+    }
+
+    #[test]
+    fn rejects_data_from_the_future() {
+        let error = migrations()
+            .restore(br#"{"$crux_version":3,"data":{}}"#)
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            MigrationError::UnknownVersion {
+                stored: 3,
+                current: 2
+            }
+        );
+    }
+
+    #[test]
+    fn reports_missing_steps() {
+        let migrations = Migrations::<Model>::new(2).register(1, |v1: V1| Model {
+            count: v1.count,
+            label: String::new(),
+        });
+
+        let error = migrations.restore(br#"{"count":1}"#).unwrap_err();
+
+        assert_eq!(error, MigrationError::MissingStep { version: 0 });
+    }
+
+    #[test]
+    #[should_panic(expected = "registered twice")]
+    fn panics_on_duplicate_steps() {
+        let _ = Migrations::<Model>::new(2)
+            .register(0, |v0: V0| v0)
+            .register(0, |v0: V0| v0);
+    }
+}
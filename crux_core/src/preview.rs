@@ -0,0 +1,230 @@
+//! Sample view models for previews.
+//!
+//! UI previews (SwiftUI previews, Compose previews, Storybook stories) need view models to
+//! render, and preview data written by hand in each shell soon drifts from what the core
+//! really produces. Implement [`Preview`] on the view model, typically with
+//! `#[derive(crux_core::macros::Preview)]`, and the shells can get the samples from the core
+//! with [`Bridge::previews`](crate::bridge::Bridge::previews) instead.
+//!
+//! The samples are deterministic, so previews and snapshot tests built on them are stable.
+//! After a typical first sample, they include the awkward cases, like empty and long
+//! strings, empty lists and missing optional values, so a sequence of samples exercises
+//! more of a screen's layout than the first one alone.
+//!
+//! ```rust
+//! use crux_core::{macros::Preview, preview::Preview as _};
+//!
+//! #[derive(Preview)]
+//! struct ViewModel {
+//!     title: String,
+//!     #[preview(value = "\"Basket\".to_string()")]
+//!     screen: String,
+//!     items: Vec<String>,
+//!     total: Option<u32>,
+//! }
+//!
+//! let view = ViewModel::preview();
+//! assert_eq!(view.screen, "Basket");
+//! assert_eq!(view.items.len(), 3);
+//!
+//! // the third sample is empty
+//! let views = ViewModel::previews(4);
+//! assert!(views[2].items.is_empty());
+//! assert_eq!(views[2].total, None);
+//! ```
+
+/// A type with deterministic sample values for previews, see the [module docs](self).
+///
+/// The samples come in cycles of four: two typical values, an empty one (empty strings and
+/// lists, zero, `None`) and a large one (long strings and lists, big numbers). The derive
+/// macro builds every field of a sample from the same point in the cycle, with a different
+/// typical value for each field, and picks the variant of an enum with the index. Use
+/// `#[preview(value = "...")]` on a field to give it a fixed value, and `#[preview(skip)]`
+/// on an enum variant to leave it out of the samples.
+pub trait Preview: Sized {
+    /// The sample at `index`. The same index always gives the same sample.
+    fn preview_at(index: usize) -> Self;
+
+    /// A representative sample
+    fn preview() -> Self {
+        Self::preview_at(0)
+    }
+
+    /// The first `count` samples
+    fn previews(count: usize) -> Vec<Self> {
+        (0..count).map(Self::preview_at).collect()
+    }
+}
+
+const CYCLE: usize = 4;
+
+/// The index of the sample for the field at `position`, or the item at `position` in a
+/// list, in the sample at `index`: at the same point in the cycle, with another typical
+/// value. Used by the derive macro.
+#[doc(hidden)]
+pub fn field_index(index: usize, position: usize) -> usize {
+    index + CYCLE * position
+}
+
+enum Sample {
+    Typical(usize),
+    Empty,
+    Large,
+}
+
+fn sample(index: usize) -> Sample {
+    match index % CYCLE {
+        2 => Sample::Empty,
+        3 => Sample::Large,
+        n => Sample::Typical(index / CYCLE * 2 + n),
+    }
+}
+
+const WORDS: [&str; 6] = [
+    "Lorem ipsum",
+    "Dolor sit amet",
+    "Consectetur",
+    "Adipiscing elit",
+    "Sed do eiusmod",
+    "Tempor",
+];
+
+const LONG_TEXT: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+                         eiusmod tempor incididunt ut labore et dolore magna aliqua";
+
+// all fit the smallest integer types
+const NUMBERS: [u8; 5] = [42, 7, 12, 3, 1];
+const LARGE_NUMBER: u8 = 100;
+
+const FLOATS: [f64; 4] = [4.5, 0.25, 12.75, 1.0];
+const LARGE_FLOAT: f64 = 1999.99;
+
+const TYPICAL_LENGTH: usize = 3;
+const LARGE_LENGTH: usize = 12;
+
+impl Preview for String {
+    fn preview_at(index: usize) -> Self {
+        match sample(index) {
+            Sample::Typical(n) => WORDS[n % WORDS.len()].to_string(),
+            Sample::Empty => String::new(),
+            Sample::Large => LONG_TEXT.to_string(),
+        }
+    }
+}
+
+impl Preview for bool {
+    fn preview_at(index: usize) -> Self {
+        match sample(index) {
+            Sample::Typical(n) => n % 2 == 0,
+            Sample::Empty => false,
+            Sample::Large => true,
+        }
+    }
+}
+
+macro_rules! number_impls {
+    ($($ty:ty),*) => {
+        $(
+            impl Preview for $ty {
+                fn preview_at(index: usize) -> Self {
+                    match sample(index) {
+                        Sample::Typical(n) => NUMBERS[n % NUMBERS.len()] as $ty,
+                        Sample::Empty => 0,
+                        Sample::Large => LARGE_NUMBER as $ty,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+number_impls!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Preview for f64 {
+    fn preview_at(index: usize) -> Self {
+        match sample(index) {
+            Sample::Typical(n) => FLOATS[n % FLOATS.len()],
+            Sample::Empty => 0.0,
+            Sample::Large => LARGE_FLOAT,
+        }
+    }
+}
+
+impl Preview for f32 {
+    fn preview_at(index: usize) -> Self {
+        f64::preview_at(index) as f32
+    }
+}
+
+impl Preview for () {
+    fn preview_at(_index: usize) -> Self {}
+}
+
+/// `None` in the empty samples
+impl<T: Preview> Preview for Option<T> {
+    fn preview_at(index: usize) -> Self {
+        match sample(index) {
+            Sample::Empty => None,
+            _ => Some(T::preview_at(index)),
+        }
+    }
+}
+
+/// Empty in the empty samples, and long in the large ones
+impl<T: Preview> Preview for Vec<T> {
+    fn preview_at(index: usize) -> Self {
+        let length = match sample(index) {
+            Sample::Typical(_) => TYPICAL_LENGTH,
+            Sample::Empty => 0,
+            Sample::Large => LARGE_LENGTH,
+        };
+
+        (0..length)
+            .map(|position| T::preview_at(field_index(index, position)))
+            .collect()
+    }
+}
+
+impl<T: Preview> Preview for Box<T> {
+    fn preview_at(index: usize) -> Self {
+        Box::new(T::preview_at(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_deterministic() {
+        assert_eq!(String::previews(8), String::previews(8));
+        assert_eq!(u32::preview(), 42);
+        assert_eq!(f32::preview_at(1), 0.25);
+    }
+
+    #[test]
+    fn samples_cycle_through_typical_empty_and_large_values() {
+        let strings = String::previews(8);
+        assert_eq!(strings[0], "Lorem ipsum");
+        assert_eq!(strings[1], "Dolor sit amet");
+        assert!(strings[2].is_empty());
+        assert!(strings[3].len() > 80);
+        assert_eq!(strings[4], "Consectetur");
+
+        assert_eq!(
+            Option::<u8>::previews(4),
+            vec![Some(42), Some(7), None, Some(100)]
+        );
+
+        let lengths: Vec<_> = Vec::<bool>::previews(4).iter().map(Vec::len).collect();
+        assert_eq!(lengths, vec![3, 3, 0, 12]);
+    }
+
+    #[test]
+    fn list_items_differ() {
+        assert_eq!(
+            Vec::<String>::preview(),
+            vec!["Lorem ipsum", "Consectetur", "Sed do eiusmod"]
+        );
+    }
+}
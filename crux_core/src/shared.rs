@@ -0,0 +1,239 @@
+//! Sharing a slice of the model between a parent app and its children.
+//!
+//! Child apps composed into a parent each own their own model, but some state (e.g. the
+//! current user) is often needed by several of them. Copying it into each child's model
+//! means keeping the copies in sync by hand. Instead, the parent can keep the state in a
+//! [`Shared`] cell and hand the children a handle to it - either a [`Shared`] clone, which
+//! can also write, or a read-only [`SharedView`].
+//!
+//! ```rust
+//! use crux_core::shared::{Shared, SharedView};
+//!
+//! #[derive(Default)]
+//! struct ParentModel {
+//!     user: Shared<Option<String>>,
+//!     inbox: InboxModel,
+//! }
+//!
+//! #[derive(Default)]
+//! struct InboxModel {
+//!     user: SharedView<Option<String>>,
+//! }
+//!
+//! impl ParentModel {
+//!     fn new() -> Self {
+//!         let user = Shared::default();
+//!         let inbox = InboxModel {
+//!             user: user.view(),
+//!         };
+//!
+//!         Self { user, inbox }
+//!     }
+//! }
+//!
+//! let model = ParentModel::new();
+//! model.user.set(Some("ada".to_string()));
+//!
+//! assert_eq!(model.inbox.user.get(), Some("ada".to_string()));
+//! ```
+//!
+//! Both types compare and debug-print by value, so models holding them work with
+//! [`AppTester`](crate::testing::AppTester) assertions as usual.
+
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A value of type `T` shared between several models. Cloning a `Shared` creates another
+/// handle to the same value, and changes made through any of the handles are visible
+/// through all of them.
+///
+/// `Shared` serializes as the value it holds. Deserializing creates a new, unshared value,
+/// so only the owning model should serialize it and child models should get their handles
+/// again after deserialization (mark their fields with `#[serde(skip)]`).
+pub struct Shared<T> {
+    value: Arc<RwLock<T>>,
+}
+
+impl<T> Shared<T> {
+    /// Create a new shared value.
+    pub fn new(value: T) -> Self {
+        Self {
+            value: Arc::new(RwLock::new(value)),
+        }
+    }
+
+    /// A read-only handle to the value.
+    pub fn view(&self) -> SharedView<T> {
+        SharedView {
+            value: self.value.clone(),
+        }
+    }
+
+    /// Call `f` with a reference to the value.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.value.read().expect("Shared RwLock was poisoned."))
+    }
+
+    /// Call `f` with a mutable reference to the value.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.value.write().expect("Shared RwLock was poisoned."))
+    }
+
+    /// Replace the value, returning the previous one.
+    pub fn set(&self, value: T) -> T {
+        self.update(|current| std::mem::replace(current, value))
+    }
+
+    /// Whether `other` is a handle to the same value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl<T: Clone> Shared<T> {
+    /// A copy of the value.
+    pub fn get(&self) -> T {
+        self.with(T::clone)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T: Default> Default for Shared<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || other.with(|other| self.with(|value| value == other))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with(|value| f.debug_tuple("Shared").field(value).finish())
+    }
+}
+
+impl<T: Serialize> Serialize for Shared<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.with(|value| value.serialize(serializer))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Shared<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// A read-only handle to a [`Shared`] value, created with [`Shared::view`].
+///
+/// A default `SharedView` holds its own default value, not shared with anything, and is
+/// mainly useful as a placeholder until the parent hands out a real view.
+pub struct SharedView<T> {
+    value: Arc<RwLock<T>>,
+}
+
+impl<T> SharedView<T> {
+    /// Call `f` with a reference to the value.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.value.read().expect("Shared RwLock was poisoned."))
+    }
+
+    /// Whether this is a view of `shared`.
+    pub fn views(&self, shared: &Shared<T>) -> bool {
+        Arc::ptr_eq(&self.value, &shared.value)
+    }
+}
+
+impl<T: Clone> SharedView<T> {
+    /// A copy of the value.
+    pub fn get(&self) -> T {
+        self.with(T::clone)
+    }
+}
+
+impl<T> Clone for SharedView<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T: Default> Default for SharedView<T> {
+    fn default() -> Self {
+        Shared::default().view()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SharedView<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.value, &other.value)
+            || other.with(|other| self.with(|value| value == other))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedView<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with(|value| f.debug_tuple("SharedView").field(value).finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(Shared<String>: Send, Sync);
+    assert_impl_all!(SharedView<String>: Send, Sync);
+
+    #[test]
+    fn changes_are_visible_through_all_handles() {
+        let shared = Shared::new(1);
+        let other = shared.clone();
+        let view = shared.view();
+
+        other.update(|value| *value += 1);
+        assert_eq!(shared.get(), 2);
+        assert_eq!(view.get(), 2);
+
+        assert_eq!(shared.set(5), 2);
+        assert_eq!(view.get(), 5);
+        assert!(view.views(&other));
+        assert!(!view.views(&Shared::new(5)));
+    }
+
+    #[test]
+    fn compares_by_value() {
+        assert_eq!(Shared::new("a"), Shared::new("a"));
+        assert_ne!(Shared::new("a").view(), Shared::new("b").view());
+        assert_eq!(format!("{:?}", Shared::new(1).view()), "SharedView(1)");
+    }
+
+    #[test]
+    fn serializes_as_the_value() {
+        let shared = Shared::new(vec![1, 2]);
+
+        let json = serde_json::to_string(&shared).unwrap();
+        assert_eq!(json, "[1,2]");
+
+        let restored: Shared<Vec<i32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, shared);
+        assert!(!restored.ptr_eq(&shared));
+    }
+}
@@ -0,0 +1,472 @@
+//! Fuzzing the bridge from several threads at once, see [`BridgeFuzz`]
+
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::SoakRng;
+use crate::{
+    bridge::{bincode_options, Bridge, Request},
+    capability::EffectMetrics,
+    Effect, ResolveError,
+};
+
+/// How many times the outstanding requests are resolved while winding down.
+const IDLE_ROUNDS: usize = 100;
+
+/// How many messages of broken checks a report keeps.
+const MAX_VIOLATIONS: usize = 10;
+
+type ViewCheck<ViewModel> = Box<dyn Fn(&ViewModel) -> Result<(), String> + Sync>;
+
+/// A fuzz test of the [`Bridge`] driven by several threads at once, as a shell which
+/// sends events and resolves requests from different threads would.
+///
+/// Each thread sends random events and resolves random outstanding requests, in whatever
+/// interleaving the threads end up in, and sometimes sends a response again, as a buggy
+/// shell might. Once they're done, the outstanding requests are resolved until the app is
+/// idle, and the [`BridgeFuzzReport`] has what went wrong:
+///
+/// * effects the app requested which never reached the shell
+/// * requests sent to the shell twice, with the same `uuid`
+/// * responses the app received twice, or which were rejected the first time
+/// * views which broke the checks given to [`check_view`](BridgeFuzz::check_view), e.g. a
+///   request seen to complete before it started
+///
+/// The choices each thread makes are generated from the seed, but the interleaving of the
+/// threads isn't, so a failure may need a few runs to show up again.
+///
+/// ```rust,ignore
+/// let bridge = Bridge::new(Core::<Effect, App>::new::<Capabilities>());
+///
+/// BridgeFuzz::<App>::new(42)
+///     .threads(8)
+///     .check_view(|view: &ViewModel| {
+///         if view.completed <= view.started {
+///             Ok(())
+///         } else {
+///             Err(format!("{} completed of {} started", view.completed, view.started))
+///         }
+///     })
+///     .run(&bridge, |rng| Event::Fetch(rng.below(10)), |effect, shell| match effect {
+///         EffectFfi::Http(_) => shell.respond(&HttpResult::Ok(HttpResponse::ok().build())),
+///         EffectFfi::Render(_) => {}
+///     })
+///     .assert_clean();
+/// ```
+pub struct BridgeFuzz<App>
+where
+    App: crate::App,
+{
+    seed: u64,
+    threads: usize,
+    steps: usize,
+    duplicate_rate: f64,
+    check_view: Option<ViewCheck<App::ViewModel>>,
+}
+
+impl<App> BridgeFuzz<App>
+where
+    App: crate::App,
+{
+    /// A fuzz test with 4 threads of 250 steps each, generated from the `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            threads: 4,
+            steps: 250,
+            duplicate_rate: 0.05,
+            check_view: None,
+        }
+    }
+
+    /// Drive the bridge from the given number of threads
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Run the given number of steps on each thread, each either sending an event or
+    /// resolving a request
+    #[must_use]
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Send a response again after a step with the `rate`, from 0.0 to 1.0. The bridge
+    /// should reject all of them.
+    #[must_use]
+    pub fn duplicate_rate(mut self, rate: f64) -> Self {
+        self.duplicate_rate = rate;
+        self
+    }
+
+    /// Check the view after every step, returning a description of what's wrong, if
+    /// anything is
+    #[must_use]
+    pub fn check_view<F>(mut self, check: F) -> Self
+    where
+        F: Fn(&App::ViewModel) -> Result<(), String> + Sync + 'static,
+    {
+        self.check_view = Some(Box::new(check));
+        self
+    }
+
+    /// Run the fuzz test against the `bridge`.
+    ///
+    /// The `event` function generates the next event to send, and the `shell` function plays
+    /// the part of the shell, responding to each request it's given with
+    /// [`BridgeFuzzShell::respond`]. Requests it doesn't respond to are notifications, which
+    /// aren't resolved.
+    pub fn run<Ef, E, S>(self, bridge: &Bridge<Ef, App>, event: E, shell: S) -> BridgeFuzzReport
+    where
+        Ef: Effect + Send + 'static,
+        Ef::Ffi: DeserializeOwned,
+        App::Event: Serialize + DeserializeOwned,
+        App::ViewModel: DeserializeOwned,
+        Bridge<Ef, App>: Sync,
+        E: Fn(&mut SoakRng) -> App::Event + Sync,
+        S: Fn(&Ef::Ffi, &mut BridgeFuzzShell<'_>) + Sync,
+    {
+        let run = Run {
+            fuzz: &self,
+            bridge,
+            shell: &shell,
+            state: Mutex::default(),
+            events: AtomicUsize::new(0),
+        };
+
+        thread::scope(|scope| {
+            for index in 0..self.threads {
+                let run = &run;
+                let event = &event;
+                let mut rng = SoakRng::new(self.seed.wrapping_add(index as u64));
+
+                scope.spawn(move || {
+                    for _ in 0..self.steps {
+                        run.step(&mut rng, event);
+                    }
+                });
+            }
+        });
+
+        let mut rng = SoakRng::new(self.seed);
+        for _ in 0..IDLE_ROUNDS {
+            let outstanding = std::mem::take(&mut run.state().outstanding);
+            if outstanding.is_empty() {
+                break;
+            }
+
+            for response in outstanding {
+                run.resolve(response, &mut rng);
+            }
+        }
+
+        let metrics: EffectMetrics = bincode_options()
+            .deserialize(&bridge.metrics())
+            .expect("metrics deserialize");
+        let requested = metrics
+            .capabilities
+            .iter()
+            .map(|capability| capability.requests)
+            .sum::<u64>();
+
+        let state = run
+            .state
+            .into_inner()
+            .expect("BridgeFuzz Mutex was poisoned.");
+        let received = state.seen.len() + state.duplicate_uuids;
+
+        BridgeFuzzReport {
+            seed: self.seed,
+            threads: self.threads,
+            events: run.events.into_inner(),
+            requests: received,
+            lost_effects: usize::try_from(requested)
+                .unwrap_or(usize::MAX)
+                .saturating_sub(received),
+            duplicate_uuids: state.duplicate_uuids,
+            double_resolutions: state
+                .duplicates_sent
+                .saturating_sub(state.duplicates_rejected),
+            rejected_responses: state.rejected_responses,
+            unresolved_requests: state.outstanding.len(),
+            violations: state.violations,
+        }
+    }
+}
+
+/// A response the shell gave, waiting to be sent to the bridge
+struct Response {
+    uuid: Vec<u8>,
+    output: Vec<u8>,
+}
+
+#[derive(Default)]
+struct State {
+    outstanding: Vec<Response>,
+    resolved: Vec<Response>,
+    duplicated: HashSet<Vec<u8>>,
+    seen: HashSet<Vec<u8>>,
+    duplicate_uuids: usize,
+    duplicates_sent: usize,
+    duplicates_rejected: usize,
+    rejected_responses: usize,
+    violations: Vec<String>,
+}
+
+struct Run<'a, Ef, App, S>
+where
+    Ef: Effect,
+    App: crate::App,
+{
+    fuzz: &'a BridgeFuzz<App>,
+    bridge: &'a Bridge<Ef, App>,
+    shell: &'a S,
+    state: Mutex<State>,
+    events: AtomicUsize,
+}
+
+impl<'a, Ef, App, S> Run<'a, Ef, App, S>
+where
+    Ef: Effect + Send + 'static,
+    Ef::Ffi: DeserializeOwned,
+    App: crate::App,
+    App::Event: Serialize + DeserializeOwned,
+    App::ViewModel: DeserializeOwned,
+    S: Fn(&Ef::Ffi, &mut BridgeFuzzShell<'_>),
+{
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("BridgeFuzz Mutex was poisoned.")
+    }
+
+    fn step<E>(&self, rng: &mut SoakRng, event: &E)
+    where
+        E: Fn(&mut SoakRng) -> App::Event,
+    {
+        let response = {
+            let mut state = self.state();
+            if state.outstanding.is_empty() || rng.chance(0.5) {
+                None
+            } else {
+                let index = rng.below(state.outstanding.len());
+                Some(state.outstanding.swap_remove(index))
+            }
+        };
+
+        match response {
+            Some(response) => self.resolve(response, rng),
+            None => {
+                let event = bincode_options()
+                    .serialize(&event(rng))
+                    .expect("events serialize");
+                self.events.fetch_add(1, Ordering::SeqCst);
+
+                let requests = self.bridge.process_event(&event);
+                self.receive(&requests, rng);
+            }
+        }
+
+        if rng.chance(self.fuzz.duplicate_rate) {
+            let duplicate = {
+                let mut state = self.state();
+                let duplicate = rng.pick(&state.resolved).map(|response| Response {
+                    uuid: response.uuid.clone(),
+                    output: response.output.clone(),
+                });
+                if let Some(duplicate) = &duplicate {
+                    state.duplicates_sent += 1;
+                    state.duplicated.insert(duplicate.uuid.clone());
+                }
+
+                duplicate
+            };
+
+            if let Some(duplicate) = duplicate {
+                let requests = self
+                    .bridge
+                    .handle_response(&duplicate.uuid, &duplicate.output);
+                self.collect_dead_letters();
+                self.receive(&requests, rng);
+            }
+        }
+
+        self.check_view();
+    }
+
+    fn resolve(&self, response: Response, rng: &mut SoakRng) {
+        let requests = self
+            .bridge
+            .handle_response(&response.uuid, &response.output);
+        self.collect_dead_letters();
+        self.state().resolved.push(response);
+
+        self.receive(&requests, rng);
+    }
+
+    fn receive(&self, requests: &[u8], rng: &mut SoakRng) {
+        let requests: Vec<Request<Ef::Ffi>> = bincode_options()
+            .deserialize(requests)
+            .expect("requests deserialize");
+
+        for request in requests {
+            let mut shell = BridgeFuzzShell { rng, output: None };
+            (self.shell)(&request.effect, &mut shell);
+            let output = shell.output;
+
+            let mut state = self.state();
+            if !state.seen.insert(request.uuid.clone()) {
+                state.duplicate_uuids += 1;
+            }
+            if let Some(output) = output {
+                state.outstanding.push(Response {
+                    uuid: request.uuid,
+                    output,
+                });
+            }
+        }
+    }
+
+    // the dead letters are shared by the threads, so each one is counted by whichever thread
+    // takes it, by whether its response was sent again on purpose
+    fn collect_dead_letters(&self) {
+        let dead_letters = self.bridge.take_dead_letters();
+        if dead_letters.is_empty() {
+            return;
+        }
+
+        let mut state = self.state();
+        for dead_letter in dead_letters {
+            let duplicate = matches!(
+                dead_letter.error,
+                ResolveError::Never | ResolveError::FinishedMany
+            ) && state.duplicated.contains(&dead_letter.uuid);
+
+            if duplicate {
+                state.duplicates_rejected += 1;
+            } else {
+                state.rejected_responses += 1;
+            }
+        }
+    }
+
+    fn check_view(&self) {
+        let Some(check) = &self.fuzz.check_view else {
+            return;
+        };
+
+        let view = bincode_options()
+            .deserialize(&self.bridge.view())
+            .expect("the view deserializes");
+
+        if let Err(message) = check(&view) {
+            let mut state = self.state();
+            if state.violations.len() < MAX_VIOLATIONS {
+                state.violations.push(message);
+            }
+        }
+    }
+}
+
+/// The shell's side of a bridge fuzz test, passed to the `shell` function of
+/// [`BridgeFuzz::run`]
+pub struct BridgeFuzzShell<'a> {
+    rng: &'a mut SoakRng,
+    output: Option<Vec<u8>>,
+}
+
+impl<'a> BridgeFuzzShell<'a> {
+    /// Respond to the request with the `output`, which is sent to the bridge at a random
+    /// point later on, from any of the threads
+    pub fn respond<T: Serialize>(&mut self, output: &T) {
+        let output = bincode_options()
+            .serialize(output)
+            .expect("outputs serialize");
+
+        self.output = Some(output);
+    }
+
+    /// The thread's random number generator, e.g. to pick a response
+    pub fn rng(&mut self) -> &mut SoakRng {
+        self.rng
+    }
+}
+
+/// What went wrong in a bridge fuzz test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeFuzzReport {
+    /// The seed the threads' choices were generated from
+    pub seed: u64,
+    pub threads: usize,
+    /// The number of random events sent
+    pub events: usize,
+    /// The number of requests the shell received
+    pub requests: usize,
+    /// Requests the capabilities made which never reached the shell
+    pub lost_effects: usize,
+    /// Requests the shell received with the `uuid` of an earlier request
+    pub duplicate_uuids: usize,
+    /// Responses sent again which the bridge didn't reject
+    pub double_resolutions: usize,
+    /// First responses to requests which the bridge rejected
+    pub rejected_responses: usize,
+    /// Requests the shell responded to which were still outstanding after winding down
+    pub unresolved_requests: usize,
+    /// What the checks of the view found wrong, at most the first 10
+    pub violations: Vec<String>,
+}
+
+impl BridgeFuzzReport {
+    /// Whether nothing went wrong
+    pub fn is_clean(&self) -> bool {
+        self.lost_effects == 0
+            && self.duplicate_uuids == 0
+            && self.double_resolutions == 0
+            && self.rejected_responses == 0
+            && self.unresolved_requests == 0
+            && self.violations.is_empty()
+    }
+
+    /// Panics if anything went wrong
+    #[track_caller]
+    pub fn assert_clean(&self) {
+        assert!(self.is_clean(), "{self}");
+    }
+}
+
+impl fmt::Display for BridgeFuzzReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bridge fuzz test with seed {} ({} threads, {} events, {} requests) found {} lost \
+             effects, {} duplicate uuids, {} double resolutions, {} rejected responses and {} \
+             unresolved requests",
+            self.seed,
+            self.threads,
+            self.events,
+            self.requests,
+            self.lost_effects,
+            self.duplicate_uuids,
+            self.double_resolutions,
+            self.rejected_responses,
+            self.unresolved_requests
+        )?;
+
+        for violation in &self.violations {
+            write!(f, "\n  {violation}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! A virtual clock resolving timer requests for the [`AppTester`](super::AppTester),
+//! see [`AppTester::advance_time`](super::AppTester::advance_time)
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{capability::Operation, Request};
+
+/// The operation of a timer capability, like `crux_time`'s `TimeRequest`, which the
+/// [`AppTester`](super::AppTester) can resolve with a virtual clock instead of returning
+/// it as an effect. See [`AppTester::with_virtual_clock`](super::AppTester::with_virtual_clock).
+pub trait Timer: Operation {
+    /// When to resolve the request, and with what output, given the virtual time `now`
+    /// (since the Unix epoch). A deadline of `now` or earlier resolves on the next
+    /// [`advance_time`](super::AppTester::advance_time).
+    ///
+    /// Returns `None` for requests which aren't about time, which are returned as effects.
+    fn schedule(&self, now: Duration) -> Option<(Duration, Self::Output)>;
+}
+
+type Fire = Box<dyn FnOnce() + Send>;
+
+pub(super) struct VirtualClock {
+    state: Mutex<ClockState>,
+}
+
+struct ClockState {
+    now: Duration,
+    // in the order the requests were made, so that timers with the same deadline
+    // fire in that order
+    timers: Vec<(Duration, Fire)>,
+}
+
+impl VirtualClock {
+    pub(super) fn new(start: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(ClockState {
+                now: start,
+                timers: Vec::new(),
+            }),
+        })
+    }
+
+    pub(super) fn now(&self) -> Duration {
+        self.state().now
+    }
+
+    /// Take the `request` if it's a timer, to resolve it once its deadline has passed.
+    pub(super) fn take<Op: Timer>(&self, request: Request<Op>) -> Option<Request<Op>> {
+        self.take_with(request, Op::schedule)
+    }
+
+    /// Take the `request` if `schedule` gives it a deadline and an output, to resolve it
+    /// once the deadline has passed.
+    pub(super) fn take_with<Op, F>(&self, request: Request<Op>, schedule: F) -> Option<Request<Op>>
+    where
+        Op: Operation,
+        F: FnOnce(&Op, Duration) -> Option<(Duration, Op::Output)>,
+    {
+        let mut state = self.state();
+
+        let Some((deadline, output)) = schedule(&request.operation, state.now) else {
+            return Some(request);
+        };
+
+        let mut request = request;
+        state.timers.push((
+            deadline,
+            Box::new(move || {
+                // the task waiting for it may be gone, which is fine
+                let _ = request.resolve(output);
+            }),
+        ));
+
+        None
+    }
+
+    /// Remove the timer with the earliest deadline, if it's due by `until`, and move
+    /// the clock forward to its deadline. The timer is fired by the caller, so that
+    /// it can make new timer requests.
+    pub(super) fn next_due(&self, until: Duration) -> Option<Fire> {
+        let mut state = self.state();
+
+        let (index, deadline) = state
+            .timers
+            .iter()
+            .enumerate()
+            .min_by_key(|(index, (deadline, _))| (*deadline, *index))
+            .map(|(index, (deadline, _))| (index, *deadline))?;
+        if deadline > until {
+            return None;
+        }
+
+        state.now = state.now.max(deadline);
+        Some(state.timers.remove(index).1)
+    }
+
+    pub(super) fn advance_to(&self, until: Duration) {
+        let mut state = self.state();
+        state.now = state.now.max(until);
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        self.state.lock().expect("VirtualClock Mutex was poisoned.")
+    }
+}
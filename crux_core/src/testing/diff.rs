@@ -0,0 +1,137 @@
+//! Which fields of the model an update changed, see [`assert_model_changed!`](crate::assert_model_changed)
+
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// A snapshot of the model before an update, to find which of its fields the update changed.
+///
+/// Fields are compared by their serialized values and named as they're serialized,
+/// so fields skipped by serde are never reported as changed.
+pub struct ModelSnapshot {
+    before: Value,
+}
+
+/// A field of the model which changed, with its serialized value before and after the change
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+impl ModelSnapshot {
+    /// Panics if the model can't be serialized to JSON.
+    pub fn of<Model: Serialize>(model: &Model) -> Self {
+        Self {
+            before: to_value(model),
+        }
+    }
+
+    /// The fields which differ in `model` from the snapshot, ordered by name. A model which
+    /// doesn't serialize as a struct has a single field, called `self`.
+    pub fn changes<Model: Serialize>(&self, model: &Model) -> Vec<FieldChange> {
+        let after = to_value(model);
+
+        match (&self.before, &after) {
+            (Value::Object(before), Value::Object(after)) => {
+                let mut fields: Vec<_> = before.keys().chain(after.keys()).collect();
+                fields.sort();
+                fields.dedup();
+
+                fields
+                    .into_iter()
+                    .filter_map(|field| {
+                        let before = before.get(field).cloned().unwrap_or(Value::Null);
+                        let after = after.get(field).cloned().unwrap_or(Value::Null);
+
+                        (before != after).then(|| FieldChange {
+                            field: field.clone(),
+                            before,
+                            after,
+                        })
+                    })
+                    .collect()
+            }
+            (before, after) if before != after => vec![FieldChange {
+                field: "self".to_string(),
+                before: before.clone(),
+                after: after.clone(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Panics unless exactly the `expected` fields differ in `model` from the snapshot,
+    /// showing the unexpected changes.
+    #[track_caller]
+    pub fn assert_changed<Model: Serialize>(&self, model: &Model, expected: &[&str]) {
+        if let Value::Object(fields) = &self.before {
+            let unknown: Vec<_> = expected
+                .iter()
+                .filter(|field| !fields.contains_key(**field))
+                .collect();
+            assert!(unknown.is_empty(), "the model has no fields {unknown:?}");
+        }
+
+        let changes = self.changes(model);
+
+        let unexpected: Vec<_> = changes
+            .iter()
+            .filter(|change| !expected.contains(&change.field.as_str()))
+            .collect();
+        let unchanged: Vec<_> = expected
+            .iter()
+            .filter(|field| !changes.iter().any(|change| change.field == **field))
+            .collect();
+        if unexpected.is_empty() && unchanged.is_empty() {
+            return;
+        }
+
+        let mut message = String::from("the update didn't change the expected fields of the model");
+        for change in unexpected {
+            let _ = write!(
+                message,
+                "\n  unexpected change of {}: {} -> {}",
+                change.field, change.before, change.after
+            );
+        }
+        for field in unchanged {
+            let _ = write!(message, "\n  {field} didn't change");
+        }
+
+        panic!("{message}");
+    }
+}
+
+#[track_caller]
+fn to_value<Model: Serialize>(model: &Model) -> Value {
+    serde_json::to_value(model).expect("the model should serialize to JSON")
+}
+
+/// Runs an update and panics unless it changed exactly the listed fields of the model,
+/// catching unintended side effects in big update functions. It evaluates to the result
+/// of the update. The model must implement [`Serialize`](serde::Serialize).
+///
+/// The fields are named as they're serialized, so this doesn't work with models using
+/// `#[serde(rename_all = "..")]`, and ignores fields skipped by serde.
+/// See [`ModelSnapshot`](crate::testing::ModelSnapshot) for more control.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let update = assert_model_changed!(app.update(Event::Fetch, &mut model), model, loading);
+/// // no field changes
+/// assert_model_changed!(app.update(Event::Noop, &mut model), model);
+/// ```
+#[macro_export]
+macro_rules! assert_model_changed {
+    ($update:expr, $model:expr $(, $field:ident)* $(,)?) => {{
+        let snapshot = $crate::testing::ModelSnapshot::of(&$model);
+        let update = $update;
+        snapshot.assert_changed(&$model, &[$(stringify!($field)),*]);
+
+        update
+    }};
+}
@@ -0,0 +1,73 @@
+//! Model invariants checked by the [`AppTester`](super::AppTester) after every update
+
+use std::fmt::{Debug, Write as _};
+
+use super::Update;
+
+type Check<Model> = Box<dyn Fn(&Model) -> anyhow::Result<()>>;
+
+/// The invariants registered with [`AppTester::with_invariant`](super::AppTester::with_invariant),
+/// and how to describe the events and effects of a failing update.
+pub(super) struct Invariants<Model, Ev, Ef> {
+    checks: Vec<Check<Model>>,
+    describe_event: fn(&Ev) -> String,
+    describe_effect: fn(&Ef) -> String,
+}
+
+impl<Model, Ev, Ef> Invariants<Model, Ev, Ef>
+where
+    Ev: Debug,
+    Ef: Debug,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            checks: Vec::new(),
+            describe_event: describe,
+            describe_effect: describe,
+        }
+    }
+}
+
+impl<Model, Ev, Ef> Invariants<Model, Ev, Ef> {
+    pub(super) fn push(&mut self, check: impl Fn(&Model) -> anyhow::Result<()> + 'static) {
+        self.checks.push(Box::new(check));
+    }
+
+    /// Describe the `event` before it's consumed by the update, in case an invariant fails.
+    pub(super) fn describe_event(&self, event: &Ev) -> String {
+        (self.describe_event)(event)
+    }
+
+    /// Check the invariants against the `model` after the update processing `event`
+    /// (as described by [`describe_event`](Self::describe_event)), panicking if any fail.
+    #[track_caller]
+    pub(super) fn check(&self, model: &Model, event: &str, update: &Update<Ef, Ev>) {
+        let failures: Vec<_> = self
+            .checks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, check)| check(model).err().map(|error| (index, error)))
+            .collect();
+        if failures.is_empty() {
+            return;
+        }
+
+        let mut message = String::new();
+        for (index, error) in failures {
+            let _ = writeln!(message, "invariant #{index} failed: {error:#}");
+        }
+        let _ = writeln!(message, "after the update with event: {event}");
+
+        let effects: Vec<_> = update.effects.iter().map(self.describe_effect).collect();
+        let _ = writeln!(message, "which requested effects: [{}]", effects.join(", "));
+
+        let events: Vec<_> = update.events.iter().map(self.describe_event).collect();
+        let _ = write!(message, "and dispatched events: [{}]", events.join(", "));
+
+        panic!("{message}");
+    }
+}
+
+fn describe<T: Debug>(value: &T) -> String {
+    format!("{value:?}")
+}
@@ -0,0 +1,487 @@
+//! Testing support for unit testing Crux apps.
+
+mod bridge_fuzz;
+mod clock;
+mod diff;
+mod invariants;
+mod scenario;
+mod soak;
+mod state_chart;
+
+use std::{collections::VecDeque, fmt::Debug, rc::Rc, sync::Arc, time::Duration};
+
+use anyhow::Result;
+
+use crate::{
+    capability::{
+        channel::Receiver, executor_and_spawner, Intercepts, Operation, ProtoContext,
+        QueuingExecutor, Timeouts,
+    },
+    memory::{self, MemoryPressure, TrimRegistry, TrimReport},
+    Request, WithContext,
+};
+use clock::VirtualClock;
+use invariants::Invariants;
+use state_chart::Recorder;
+
+pub use bridge_fuzz::{BridgeFuzz, BridgeFuzzReport, BridgeFuzzShell};
+pub use clock::Timer;
+pub use diff::{FieldChange, ModelSnapshot};
+pub use scenario::Scenario;
+pub use soak::{Soak, SoakReport, SoakRng, SoakShell};
+pub use state_chart::{StateChart, Transition};
+
+/// AppTester is a simplified execution environment for Crux apps for use in
+/// tests.
+///
+/// Create an instance of `AppTester` with your `App` and an `Effect` type
+/// using [`AppTester::default`].
+///
+/// for example:
+///
+/// ```rust,ignore
+/// let app = AppTester::<ExampleApp, ExampleEffect>::default();
+/// ```
+pub struct AppTester<App, Ef>
+where
+    App: crate::App,
+{
+    app: App,
+    capabilities: App::Capabilities,
+    context: Rc<AppContext<Ef, App::Event>>,
+    invariants: Option<Invariants<App::Model, App::Event, Ef>>,
+    state_chart: Option<Recorder<App::Model, App::Event>>,
+    clock: Option<Arc<VirtualClock>>,
+}
+
+struct AppContext<Ef, Ev> {
+    commands: Receiver<Ef>,
+    events: Receiver<Ev>,
+    executor: QueuingExecutor,
+    timeouts: Arc<Timeouts>,
+    intercepts: Arc<Intercepts>,
+    trim_registry: TrimRegistry,
+}
+
+impl<App, Ef> AppTester<App, Ef>
+where
+    App: crate::App,
+{
+    /// Run the app's `update` function with an event and a model state
+    ///
+    /// You can use the resulting [`Update`] to inspect the effects which were requested
+    /// and potential further events dispatched by capabilities.
+    ///
+    /// Panics if the updated model breaks an invariant, see [`AppTester::with_invariant`].
+    #[track_caller]
+    pub fn update(&self, event: App::Event, model: &mut App::Model) -> Update<Ef, App::Event> {
+        let described = self
+            .invariants
+            .as_ref()
+            .map(|invariants| invariants.describe_event(&event));
+        let transition = self
+            .state_chart
+            .as_ref()
+            .map(|recorder| recorder.before(&event, model));
+
+        self.app.update(event, model, &self.capabilities);
+        let update = self.context.updates();
+
+        if let (Some(recorder), Some(transition)) = (&self.state_chart, transition) {
+            recorder.after(transition, model);
+        }
+        if let (Some(invariants), Some(described)) = (&self.invariants, described) {
+            invariants.check(model, &described, &update);
+        }
+
+        update
+    }
+
+    /// Run the app's `init` function with a model state, like the [`Core`](crate::Core)
+    /// does when it's created, see [`App::init`](crate::App::init).
+    ///
+    /// Panics if the model breaks an invariant, see [`AppTester::with_invariant`].
+    #[track_caller]
+    pub fn init(&self, model: &mut App::Model) -> Update<Ef, App::Event> {
+        self.app.init(model, &self.capabilities);
+        let update = self.context.updates();
+        if let Some(invariants) = &self.invariants {
+            invariants.check(model, "init", &update);
+        }
+
+        update
+    }
+
+    /// Run the app's `will_terminate` function with a model state, like the
+    /// [`Core`](crate::Core) does when it's shutting down, see
+    /// [`App::will_terminate`](crate::App::will_terminate).
+    ///
+    /// Panics if the model breaks an invariant, see [`AppTester::with_invariant`].
+    #[track_caller]
+    pub fn will_terminate(&self, model: &mut App::Model) -> Update<Ef, App::Event> {
+        self.app.will_terminate(model, &self.capabilities);
+        let update = self.context.updates();
+        if let Some(invariants) = &self.invariants {
+            invariants.check(model, "will_terminate", &update);
+        }
+
+        update
+    }
+
+    /// Resolve an effect `request` from previous update with an operation output.
+    ///
+    /// This potentially runs the app's `update` function if the effect is completed, and
+    /// produce another `Update`.
+    pub fn resolve<Op: Operation>(
+        &self,
+        request: &mut Request<Op>,
+        value: Op::Output,
+    ) -> Result<Update<Ef, App::Event>> {
+        request.resolve(value)?;
+
+        Ok(self.context.updates())
+    }
+
+    /// Time out all the pending requests made with a timeout (see
+    /// [`CapabilityContext::with_timeout`](crate::capability::CapabilityContext::with_timeout)),
+    /// as if the shell never resolved them, regardless of how long the timeout is.
+    ///
+    /// The tester doesn't time out requests otherwise, so that tests don't depend on the
+    /// time they take to run.
+    pub fn time_out_requests(&self) -> Update<Ef, App::Event> {
+        self.context.timeouts.expire_all();
+        self.context.updates()
+    }
+
+    /// Change the capabilities before the test, typically to replace a local capability,
+    /// i.e. a service in the core annotated with `#[effect(local)]`, with a fake:
+    ///
+    /// ```rust,ignore
+    /// let app = AppTester::<App, Effect>::default()
+    ///     .with_capabilities(|caps| caps.search = SearchIndex::with_documents(fixtures()));
+    /// ```
+    #[must_use]
+    pub fn with_capabilities<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut App::Capabilities),
+    {
+        f(&mut self.capabilities);
+        self
+    }
+
+    /// Resolve the requests of the timer capability with the operation `Op` (e.g. `crux_time`'s
+    /// `TimeRequest`) with a virtual clock starting at `start` (since the Unix epoch), instead
+    /// of returning them as effects. Time then only passes when the test calls
+    /// [`advance_time`](AppTester::advance_time).
+    ///
+    /// ```rust,ignore
+    /// let app = AppTester::<App, Effect>::default()
+    ///     .with_virtual_clock::<TimeRequest>(Duration::ZERO);
+    ///
+    /// app.update(Event::Search("cr".to_string()), &mut model);
+    /// app.update(Event::Search("crux".to_string()), &mut model);
+    ///
+    /// // the debounce timers fire, and the app searches once
+    /// let update = app.advance_time(Duration::from_millis(300), &mut model);
+    /// assert_effect!(update, Effect::Http(_));
+    /// ```
+    #[must_use]
+    pub fn with_virtual_clock<Op: Timer>(mut self, start: Duration) -> Self {
+        let clock = VirtualClock::new(start);
+
+        let intercepted = clock.clone();
+        self.context
+            .intercepts
+            .register(move |request: Request<Op>| intercepted.take(request));
+        self.clock = Some(clock);
+
+        self
+    }
+
+    /// Resolve the requests of the capability with the operation `Op` on the virtual clock,
+    /// with the output `simulate` gives them and after the delay it gives, instead of returning
+    /// them as effects, e.g. to simulate a server and the network to it. `simulate` is called
+    /// with each request as it's made, and the virtual time, and returns the time to resolve
+    /// it at, or `None` to return it as an effect.
+    ///
+    /// Panics if the tester has no virtual clock yet, see
+    /// [`with_virtual_clock`](AppTester::with_virtual_clock).
+    #[must_use]
+    #[track_caller]
+    pub fn with_simulation<Op, F>(self, mut simulate: F) -> Self
+    where
+        Op: Operation,
+        F: FnMut(&Op, Duration) -> Option<(Duration, Op::Output)> + Send + 'static,
+    {
+        let clock = self
+            .clock
+            .clone()
+            .expect("with_simulation needs a virtual clock, see AppTester::with_virtual_clock");
+
+        self.context
+            .intercepts
+            .register(move |request: Request<Op>| clock.take_with(request, &mut simulate));
+
+        self
+    }
+
+    /// The virtual time (since the Unix epoch), see [`with_virtual_clock`](AppTester::with_virtual_clock)
+    pub fn now(&self) -> Option<Duration> {
+        self.clock.as_ref().map(|clock| clock.now())
+    }
+
+    /// Move the virtual clock forward `by` the duration, resolving the timer requests whose
+    /// deadlines pass in the order of their deadlines, and sending the events they result in
+    /// back to the app, as the shell would. Timers requested along the way fire too, if they're
+    /// due in time.
+    ///
+    /// The returned [`Update`] has the other effects requested meanwhile. Its events have
+    /// already been processed.
+    ///
+    /// Panics if the tester has no virtual clock, see [`with_virtual_clock`](AppTester::with_virtual_clock).
+    #[track_caller]
+    pub fn advance_time(&self, by: Duration, model: &mut App::Model) -> Update<Ef, App::Event> {
+        let clock = self
+            .clock
+            .as_ref()
+            .expect("advance_time needs a virtual clock, see AppTester::with_virtual_clock");
+        let until = clock.now() + by;
+
+        let mut effects = Vec::new();
+        let mut events = VecDeque::new();
+        loop {
+            // process the events first, they may request earlier timers
+            while let Some(event) = events.pop_front() {
+                let update = self.update(event, model);
+                effects.extend(update.effects);
+                events.extend(update.events);
+            }
+
+            let Some(fire) = clock.next_due(until) else {
+                break;
+            };
+            fire();
+
+            let update = self.context.updates();
+            effects.extend(update.effects);
+            events.extend(update.events);
+        }
+        clock.advance_to(until);
+
+        Update {
+            effects,
+            events: Vec::new(),
+        }
+    }
+
+    /// The number of capability tasks which haven't completed yet, e.g. because they're
+    /// waiting for the shell to resolve a request.
+    pub fn pending_tasks(&self) -> usize {
+        self.context.executor.pending_tasks()
+    }
+
+    /// The transitions the app made in the updates so far, see
+    /// [`with_state_chart`](AppTester::with_state_chart)
+    pub fn state_chart(&self) -> Option<StateChart> {
+        self.state_chart.as_ref().map(Recorder::chart)
+    }
+
+    /// Run the app's `view` function with a model state
+    pub fn view(&self, model: &App::Model) -> App::ViewModel {
+        self.app.view(model)
+    }
+
+    /// Trim the caches in the `model` and the ones registered by the capabilities,
+    /// like [`Core::trim_memory`](crate::Core::trim_memory)
+    pub fn trim_memory(&self, pressure: MemoryPressure, model: &mut App::Model) -> TrimReport {
+        memory::trim_app(&self.app, model, &self.context.trim_registry, pressure)
+    }
+
+    /// Run the app's `query` function with a model state
+    pub fn query(&self, query: App::Query, model: &App::Model) -> App::QueryResponse
+    where
+        App: crate::Queryable,
+    {
+        self.app.query(query, model)
+    }
+
+    /// Answer a shell's `query` about the work the capabilities are tracking,
+    /// see [`ShellQuery`](crate::capability::ShellQuery)
+    pub fn query_capabilities(
+        &self,
+        query: <App::Capabilities as crate::capability::ShellQuery>::Query,
+    ) -> <App::Capabilities as crate::capability::ShellQuery>::QueryResponse
+    where
+        App::Capabilities: crate::capability::ShellQuery,
+    {
+        crate::capability::ShellQuery::query(&self.capabilities, query)
+    }
+}
+
+impl<App, Ef> AppTester<App, Ef>
+where
+    App: crate::App,
+    App::Event: Debug,
+    Ef: Debug,
+{
+    /// Check the `invariant` against the model after every [`update`](AppTester::update),
+    /// instead of asserting it in every test. The model only changes in `update`, so this
+    /// covers the events sent back by capabilities after a [`resolve`](AppTester::resolve).
+    ///
+    /// When the invariant returns an error, the update panics with the error, the event it
+    /// was processing, and the effects and events it produced. For example:
+    ///
+    /// ```rust,ignore
+    /// let app = AppTester::<Counter, Effect>::default()
+    ///     .with_invariant(|model| {
+    ///         anyhow::ensure!(model.count >= 0, "count is negative: {}", model.count);
+    ///         Ok(())
+    ///     });
+    /// ```
+    #[must_use]
+    pub fn with_invariant<F>(mut self, invariant: F) -> Self
+    where
+        F: Fn(&App::Model) -> Result<()> + 'static,
+    {
+        self.invariants
+            .get_or_insert_with(Invariants::new)
+            .push(invariant);
+        self
+    }
+}
+
+impl<App, Ef> AppTester<App, Ef>
+where
+    App: crate::App,
+    App::Event: Debug,
+{
+    /// Record the transitions the app makes between the states of its model, as named by
+    /// `state`, in a [`StateChart`], which can be exported as a diagram for review after the
+    /// tests, see [`state_chart`](AppTester::state_chart). Each [`update`](AppTester::update)
+    /// is a transition, on the event named by its variant.
+    ///
+    /// ```rust,ignore
+    /// let app = AppTester::<App, Effect>::default()
+    ///     .with_state_chart(|model: &Model| format!("{:?}", model.page));
+    ///
+    /// // ... run through the app's flows
+    ///
+    /// std::fs::write("checkout.mmd", app.state_chart().unwrap().to_mermaid())?;
+    /// ```
+    #[must_use]
+    pub fn with_state_chart<F>(mut self, state: F) -> Self
+    where
+        F: Fn(&App::Model) -> String + 'static,
+    {
+        self.state_chart = Some(Recorder::new(state));
+        self
+    }
+}
+
+impl<App, Ef> Default for AppTester<App, Ef>
+where
+    App: crate::App,
+    App::Capabilities: WithContext<App, Ef>,
+    App::Event: Send,
+    Ef: Send + 'static,
+{
+    fn default() -> Self {
+        let (command_sender, commands) = crate::capability::channel();
+        let (event_sender, events) = crate::capability::channel();
+        let (executor, spawner) = executor_and_spawner();
+        let timeouts = Arc::<Timeouts>::default();
+        let intercepts = Arc::<Intercepts>::default();
+        let trim_registry = TrimRegistry::default();
+        let capability_context = ProtoContext::new(
+            command_sender,
+            event_sender,
+            spawner,
+            timeouts.clone(),
+            Arc::default(),
+            trim_registry.clone(),
+        )
+        .with_intercepts(intercepts.clone());
+
+        Self {
+            app: App::default(),
+            capabilities: App::Capabilities::new_with_context(capability_context),
+            context: Rc::new(AppContext {
+                commands,
+                events,
+                executor,
+                timeouts,
+                intercepts,
+                trim_registry,
+            }),
+            invariants: None,
+            state_chart: None,
+            clock: None,
+        }
+    }
+}
+
+impl<App, Ef> AsRef<App::Capabilities> for AppTester<App, Ef>
+where
+    App: crate::App,
+{
+    fn as_ref(&self) -> &App::Capabilities {
+        &self.capabilities
+    }
+}
+
+impl<Ef, Ev> AppContext<Ef, Ev> {
+    pub fn updates(self: &Rc<Self>) -> Update<Ef, Ev> {
+        self.executor.run_all();
+        let effects = self.commands.drain().collect();
+        let events = self.events.drain().collect();
+
+        Update { effects, events }
+    }
+}
+
+/// Update test helper holds the result of running an app update using [`AppTester::update`]
+/// or resolving a request with [`AppTester::resolve`].
+#[derive(Debug)]
+pub struct Update<Ef, Ev> {
+    /// Effects requested from the update run
+    pub effects: Vec<Ef>,
+    /// Events dispatched from the update run
+    pub events: Vec<Ev>,
+}
+
+impl<Ef, Ev> Update<Ef, Ev> {
+    pub fn into_effects(self) -> impl Iterator<Item = Ef> {
+        self.effects.into_iter()
+    }
+
+    pub fn effects(&self) -> impl Iterator<Item = &Ef> {
+        self.effects.iter()
+    }
+
+    pub fn effects_mut(&mut self) -> impl Iterator<Item = &mut Ef> {
+        self.effects.iter_mut()
+    }
+}
+
+/// Panics if the pattern doesn't match an `Effect` from the specified `Update`
+///
+/// Like in a `match` expression, the pattern can be optionally followed by `if`
+/// and a guard expression that has access to names bound by the pattern.
+///
+/// # Example
+///
+/// ```
+/// # use crux_core::testing::Update;
+/// # enum Effect { Render(String) };
+/// # enum Event { None };
+/// # let effects = vec![Effect::Render("test".to_string())].into_iter().collect();
+/// # let mut update = Update { effects, events: vec!(Event::None) };
+/// use crux_core::assert_effect;
+/// assert_effect!(update, Effect::Render(_));
+/// ```
+#[macro_export]
+macro_rules! assert_effect {
+    ($expression:expr, $(|)? $( $pattern:pat_param )|+ $( if $guard: expr )? $(,)?) => {
+        assert!($expression.effects().any(|e| matches!(e, $( $pattern )|+ $( if $guard )?)));
+    };
+}
@@ -0,0 +1,352 @@
+//! End-to-end tests of an app's user flows, see [`Scenario`]
+
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+    fmt::{self, Debug},
+    sync::{Arc, Mutex},
+};
+
+use super::{AppTester, Update};
+use crate::{
+    capability::{name_of, Operation},
+    core::Resolve,
+    Request, WithContext,
+};
+
+type Action<App, Ef> =
+    Box<dyn FnOnce(&mut Run<'_, App, Ef>, &mut <App as crate::App>::Model) -> Result<(), String>>;
+type Prepare<App, Ef> = Box<dyn FnOnce(&mut Run<'_, App, Ef>)>;
+type Pending<Op> = Arc<Mutex<Vec<Request<Op>>>>;
+
+/// A scenario is a user flow through an app, told as a sequence of steps: the events the
+/// user sends, the responses the shell gives to the app's requests, and what the user sees.
+///
+/// The scenario plays the part of the shell. It takes the requests of the capabilities it
+/// responds to, instead of returning them as effects, and sends the events they result in
+/// back to the app, so that a test reads like the flow it checks:
+///
+/// ```rust,ignore
+/// Scenario::<App, Effect>::new()
+///     .titled("Checking the weather")
+///     .event(Event::Fetch)
+///     .expect_view(|view| assert!(view.loading))
+///     .respond_http(|request| request.url.ends_with("/weather"), sunny)
+///     .expect_view(|view| assert_eq!(view.forecast, "Sunny"))
+///     .run();
+/// ```
+///
+/// A scenario displays as a numbered list of its steps, to document the flow.
+/// Capability crates extend scenarios with their own steps, like `respond_http` from
+/// `crux_http::testing::HttpScenario`, built on [`respond`](Scenario::respond).
+pub struct Scenario<App, Ef>
+where
+    App: crate::App,
+{
+    title: Option<String>,
+    steps: Vec<Step<App, Ef>>,
+}
+
+struct Step<App, Ef>
+where
+    App: crate::App,
+{
+    description: String,
+    prepare: Option<Prepare<App, Ef>>,
+    action: Action<App, Ef>,
+}
+
+impl<App, Ef> Scenario<App, Ef>
+where
+    App: crate::App,
+    Ef: 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            title: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Name the flow, for the description of the scenario
+    #[must_use]
+    pub fn titled(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Send the `event` to the app, then the events it results in, as the shell would.
+    #[must_use]
+    pub fn event(self, event: App::Event) -> Self
+    where
+        App::Event: Debug,
+    {
+        let description = format!("send the event {event:?}");
+
+        self.step(description, None, move |run, model| {
+            let update = run.tester.update(event, model);
+            run.absorb(update, model);
+            Ok(())
+        })
+    }
+
+    /// Resolve the first outstanding request of the capability with the operation `Op`
+    /// which the `matcher` accepts with the `output`, then send the events it results in
+    /// to the app. The step fails if there's no such request.
+    ///
+    /// Requests expecting more than one response stay outstanding, so they can be
+    /// responded to again.
+    #[must_use]
+    pub fn respond<Op, M>(self, matcher: M, output: Op::Output) -> Self
+    where
+        Op: Operation,
+        M: Fn(&Op) -> bool + 'static,
+    {
+        let description = format!("respond to a {} request", name_of::<Op>());
+
+        self.respond_described(description, matcher, output)
+    }
+
+    /// Like [`respond`](Scenario::respond), with a `description` of the step for the
+    /// description of the scenario, e.g. by capability crates extending scenarios.
+    #[must_use]
+    pub fn respond_described<Op, M>(
+        self,
+        description: impl Into<String>,
+        matcher: M,
+        output: Op::Output,
+    ) -> Self
+    where
+        Op: Operation,
+        M: Fn(&Op) -> bool + 'static,
+    {
+        let prepare: Prepare<App, Ef> = Box::new(|run| {
+            run.pending::<Op>();
+        });
+
+        self.step(description.into(), Some(prepare), move |run, model| {
+            let pending = run.pending::<Op>();
+            let mut request = {
+                let mut pending = pending.lock().expect("Scenario Mutex was poisoned.");
+                let index = pending
+                    .iter()
+                    .position(|request| matcher(&request.operation))
+                    .ok_or_else(|| {
+                        format!(
+                            "no outstanding request matches, out of {} {} requests",
+                            pending.len(),
+                            name_of::<Op>()
+                        )
+                    })?;
+
+                pending.remove(index)
+            };
+
+            request
+                .resolve(output)
+                .map_err(|error| format!("the request could not be resolved: {error}"))?;
+            if matches!(request.resolve, Resolve::Many(_)) {
+                pending
+                    .lock()
+                    .expect("Scenario Mutex was poisoned.")
+                    .push(request);
+            }
+
+            let update = run.tester.context.updates();
+            run.absorb(update, model);
+            Ok(())
+        })
+    }
+
+    /// Check the view of the model, panicking if it's not as expected.
+    #[must_use]
+    pub fn expect_view<F>(self, expectation: F) -> Self
+    where
+        F: FnOnce(&App::ViewModel) + 'static,
+    {
+        self.step("check the view".to_string(), None, move |run, model| {
+            expectation(&run.tester.view(model));
+            Ok(())
+        })
+    }
+
+    /// Check the model, panicking if it's not as expected.
+    #[must_use]
+    pub fn expect_model<F>(self, expectation: F) -> Self
+    where
+        F: FnOnce(&App::Model) + 'static,
+    {
+        self.step("check the model".to_string(), None, move |_, model| {
+            expectation(model);
+            Ok(())
+        })
+    }
+
+    /// Check that one of the effects requested since the previous `expect_effect`
+    /// step matches the `predicate`, e.g. `|effect| matches!(effect, Effect::Render(_))`.
+    /// The requests the scenario responds to aren't effects.
+    #[must_use]
+    pub fn expect_effect<F>(self, predicate: F) -> Self
+    where
+        F: Fn(&Ef) -> bool + 'static,
+    {
+        self.step("check the effects".to_string(), None, move |run, _| {
+            let effects = std::mem::take(&mut run.effects);
+
+            if effects.iter().any(predicate) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "none of the {} effects requested matches",
+                    effects.len()
+                ))
+            }
+        })
+    }
+
+    /// Run the scenario with a new [`AppTester`] and model, returning the final model.
+    ///
+    /// Panics if a step fails.
+    #[track_caller]
+    pub fn run(self) -> App::Model
+    where
+        App::Capabilities: WithContext<App, Ef>,
+        App::Event: Send,
+        Ef: Send,
+    {
+        let tester = AppTester::default();
+        let mut model = App::Model::default();
+        self.run_with(&tester, &mut model);
+
+        model
+    }
+
+    /// Run the scenario with the `tester`, starting from the `model`. The scenario keeps
+    /// taking the requests of the capabilities it responds to from the tester afterwards.
+    ///
+    /// Panics if a step fails.
+    #[track_caller]
+    pub fn run_with(self, tester: &AppTester<App, Ef>, model: &mut App::Model) {
+        let mut run = Run {
+            tester,
+            pending: HashMap::new(),
+            effects: Vec::new(),
+        };
+
+        let mut actions = Vec::with_capacity(self.steps.len());
+        let mut descriptions = Vec::with_capacity(self.steps.len());
+        for step in self.steps {
+            if let Some(prepare) = step.prepare {
+                prepare(&mut run);
+            }
+            actions.push(step.action);
+            descriptions.push(step.description);
+        }
+
+        for (index, action) in actions.into_iter().enumerate() {
+            if let Err(error) = action(&mut run, model) {
+                let title = self.title.as_deref().unwrap_or("scenario");
+                panic!(
+                    "{title} failed at step {}, {}: {error}",
+                    index + 1,
+                    descriptions[index]
+                );
+            }
+        }
+    }
+
+    fn step<F>(mut self, description: String, prepare: Option<Prepare<App, Ef>>, action: F) -> Self
+    where
+        F: FnOnce(&mut Run<'_, App, Ef>, &mut App::Model) -> Result<(), String> + 'static,
+    {
+        self.steps.push(Step {
+            description,
+            prepare,
+            action: Box::new(action),
+        });
+        self
+    }
+}
+
+impl<App, Ef> Default for Scenario<App, Ef>
+where
+    App: crate::App,
+    Ef: 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<App, Ef> fmt::Display for Scenario<App, Ef>
+where
+    App: crate::App,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "{title}")?;
+        }
+
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{}. {}", index + 1, step.description)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Run<'a, App, Ef>
+where
+    App: crate::App,
+{
+    tester: &'a AppTester<App, Ef>,
+    // the outstanding requests taken from the capabilities, a `Pending<Op>` for each `Op`
+    pending: HashMap<TypeId, Box<dyn Any>>,
+    effects: Vec<Ef>,
+}
+
+impl<'a, App, Ef> Run<'a, App, Ef>
+where
+    App: crate::App,
+{
+    /// The outstanding requests with the operation `Op`, taking them from the capability
+    /// from the first call on.
+    fn pending<Op: Operation>(&mut self) -> Pending<Op> {
+        let tester = self.tester;
+        let pending = self.pending.entry(TypeId::of::<Op>()).or_insert_with(|| {
+            let pending = Pending::<Op>::default();
+
+            let taken = pending.clone();
+            tester
+                .context
+                .intercepts
+                .register(move |request: Request<Op>| {
+                    taken
+                        .lock()
+                        .expect("Scenario Mutex was poisoned.")
+                        .push(request);
+                    None
+                });
+
+            Box::new(pending)
+        });
+
+        pending
+            .downcast_ref::<Pending<Op>>()
+            .expect("pending requests have the operation type they're keyed by")
+            .clone()
+    }
+
+    /// Keep the effects of the `update` and send its events back to the app, until
+    /// there are no more.
+    fn absorb(&mut self, update: Update<Ef, App::Event>, model: &mut App::Model) {
+        let mut events = VecDeque::from(update.events);
+        self.effects.extend(update.effects);
+
+        while let Some(event) = events.pop_front() {
+            let update = self.tester.update(event, model);
+            self.effects.extend(update.effects);
+            events.extend(update.events);
+        }
+    }
+}
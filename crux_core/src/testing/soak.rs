@@ -0,0 +1,335 @@
+//! Soak testing Crux apps for leaked effects, see [`Soak`]
+
+use std::{collections::VecDeque, fmt};
+
+use super::{AppTester, Update};
+use crate::{capability::Operation, Request};
+
+/// How many events the app may dispatch to itself in a single step before the soak
+/// test gives up on them, e.g. because of an event loop.
+const MAX_EVENTS_PER_STEP: usize = 10_000;
+
+/// How many times the outstanding requests are handed to the shell while winding down.
+const IDLE_ROUNDS: usize = 100;
+
+/// A soak test runs a long, random sequence of events through an app, with the shell
+/// resolving its requests in a random order, then lets the app wind down and checks that
+/// nothing is left behind: no unresolved requests, no pending tasks and no queued events.
+///
+/// This catches leaked subscriptions and capability calls which are never resolved,
+/// which are hard to spot in example-based tests. The sequence is generated from a seed,
+/// so a failing run can be replayed.
+///
+/// ```rust,ignore
+/// let app = AppTester::<App, Effect>::default();
+/// let mut model = Model::default();
+///
+/// Soak::<App>::new(42)
+///     .steps(1000)
+///     .wind_down(vec![Event::SignOut])
+///     .run(&app, &mut model, random_event, shell)
+///     .assert_idle();
+/// ```
+pub struct Soak<App>
+where
+    App: crate::App,
+{
+    seed: u64,
+    steps: usize,
+    wind_down: Vec<App::Event>,
+}
+
+impl<App> Soak<App>
+where
+    App: crate::App,
+{
+    /// A soak test of 1000 steps, generated from the `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            steps: 1000,
+            wind_down: Vec::new(),
+        }
+    }
+
+    /// Run the given number of steps, each either sending an event or handing a request
+    /// to the shell.
+    #[must_use]
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Send the `events` after the random steps, to stop whatever a well behaved app
+    /// would legitimately still be doing, e.g. a subscription it keeps open while signed in.
+    #[must_use]
+    pub fn wind_down(mut self, events: Vec<App::Event>) -> Self {
+        self.wind_down = events;
+        self
+    }
+
+    /// Run the soak test with the app in the `tester`, starting from the `model`.
+    ///
+    /// The `event` function generates the next event to send, and the `shell` function
+    /// plays the part of the shell, resolving requests with [`SoakShell::resolve`]. It returns
+    /// whether it's done with the effect, or wants it back later, e.g. to send more responses
+    /// to a streaming request. Once the app is winding down, [`SoakShell::is_idle`] is `true`
+    /// and the shell should conclude everything it can.
+    pub fn run<Ef, E, S>(
+        self,
+        tester: &AppTester<App, Ef>,
+        model: &mut App::Model,
+        mut event: E,
+        mut shell: S,
+    ) -> SoakReport
+    where
+        E: FnMut(&mut SoakRng, &App::Model) -> App::Event,
+        S: FnMut(&mut SoakShell<'_, App, Ef>, &mut Ef) -> bool,
+    {
+        let mut run = Run {
+            tester,
+            rng: SoakRng::new(self.seed),
+            outstanding: Vec::new(),
+            queue: VecDeque::new(),
+        };
+        let mut events = 0;
+
+        for _ in 0..self.steps {
+            if run.outstanding.is_empty() || run.rng.chance(0.5) {
+                let event = event(&mut run.rng, model);
+                run.queue.push_back(event);
+                events += 1;
+            } else {
+                let index = run.rng.below(run.outstanding.len());
+                let effect = run.outstanding.swap_remove(index);
+                run.handle(effect, false, &mut shell);
+            }
+
+            run.deliver(model);
+        }
+
+        for event in self.wind_down {
+            run.queue.push_back(event);
+            run.deliver(model);
+        }
+
+        for _ in 0..IDLE_ROUNDS {
+            if run.outstanding.is_empty() {
+                break;
+            }
+
+            for effect in std::mem::take(&mut run.outstanding) {
+                run.handle(effect, true, &mut shell);
+                run.deliver(model);
+            }
+        }
+
+        SoakReport {
+            seed: self.seed,
+            events,
+            unresolved_requests: run.outstanding.len(),
+            pending_tasks: tester.pending_tasks(),
+            queued_events: run.queue.len(),
+        }
+    }
+}
+
+struct Run<'a, App, Ef>
+where
+    App: crate::App,
+{
+    tester: &'a AppTester<App, Ef>,
+    rng: SoakRng,
+    outstanding: Vec<Ef>,
+    queue: VecDeque<App::Event>,
+}
+
+impl<'a, App, Ef> Run<'a, App, Ef>
+where
+    App: crate::App,
+{
+    fn handle<S>(&mut self, mut effect: Ef, idle: bool, shell: &mut S)
+    where
+        S: FnMut(&mut SoakShell<'_, App, Ef>, &mut Ef) -> bool,
+    {
+        let mut soak_shell = SoakShell {
+            tester: self.tester,
+            rng: &mut self.rng,
+            idle,
+            updates: Vec::new(),
+        };
+        let done = shell(&mut soak_shell, &mut effect);
+
+        for update in soak_shell.updates {
+            self.absorb(update);
+        }
+        if !done {
+            self.outstanding.push(effect);
+        }
+    }
+
+    fn deliver(&mut self, model: &mut App::Model) {
+        for _ in 0..MAX_EVENTS_PER_STEP {
+            let Some(event) = self.queue.pop_front() else {
+                return;
+            };
+
+            let update = self.tester.update(event, model);
+            self.absorb(update);
+        }
+    }
+
+    fn absorb(&mut self, update: Update<Ef, App::Event>) {
+        self.outstanding.extend(update.effects);
+        self.queue.extend(update.events);
+    }
+}
+
+/// The shell's side of a soak test, passed to the `shell` function of [`Soak::run`]
+pub struct SoakShell<'a, App, Ef>
+where
+    App: crate::App,
+{
+    tester: &'a AppTester<App, Ef>,
+    rng: &'a mut SoakRng,
+    idle: bool,
+    updates: Vec<Update<Ef, App::Event>>,
+}
+
+impl<'a, App, Ef> SoakShell<'a, App, Ef>
+where
+    App: crate::App,
+{
+    /// Resolve the `request` with the `output`, returning whether it could be resolved
+    /// (it can't if it's a notification, or a stream which has ended).
+    pub fn resolve<Op: Operation>(
+        &mut self,
+        request: &mut Request<Op>,
+        output: Op::Output,
+    ) -> bool {
+        match self.tester.resolve(request, output) {
+            Ok(update) => {
+                self.updates.push(update);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Whether the app is winding down, and the shell should conclude the requests it can
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// The soak test's random number generator, e.g. to pick a response
+    pub fn rng(&mut self) -> &mut SoakRng {
+        self.rng
+    }
+}
+
+/// What was left behind at the end of a soak test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoakReport {
+    /// The seed the test was generated from, to replay it
+    pub seed: u64,
+    /// The number of random events sent
+    pub events: usize,
+    /// Requests the shell still wanted back after winding down
+    pub unresolved_requests: usize,
+    /// Capability tasks which never completed, e.g. waiting on a request the shell dropped
+    pub pending_tasks: usize,
+    /// Events the app dispatched to itself which were never processed
+    pub queued_events: usize,
+}
+
+impl SoakReport {
+    /// Whether nothing was left behind
+    pub fn is_idle(&self) -> bool {
+        self.unresolved_requests == 0 && self.pending_tasks == 0 && self.queued_events == 0
+    }
+
+    /// Panics if anything was left behind
+    #[track_caller]
+    pub fn assert_idle(&self) {
+        assert!(self.is_idle(), "{self}");
+    }
+}
+
+impl fmt::Display for SoakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "soak test with seed {} ({} events) left {} unresolved requests, {} pending tasks \
+             and {} queued events",
+            self.seed,
+            self.events,
+            self.unresolved_requests,
+            self.pending_tasks,
+            self.queued_events
+        )
+    }
+}
+
+/// A small random number generator (SplitMix64), so that soak tests are reproducible
+/// from their seed without depending on a particular version of a random number crate.
+pub struct SoakRng {
+    state: u64,
+}
+
+impl SoakRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to, but not including, `n`. Panics if `n` is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "below(0) has no numbers to choose from");
+
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// `true` with the given `probability`, from 0.0 to 1.0
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// One of the `items`, or `None` if there are none
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.below(items.len()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = SoakRng::new(seed);
+            (0..8).map(|_| rng.below(100)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(numbers(7), numbers(8));
+
+        let mut rng = SoakRng::new(1);
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+        assert_eq!(rng.pick::<u8>(&[]), None);
+    }
+}
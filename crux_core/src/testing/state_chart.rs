@@ -0,0 +1,248 @@
+//! The transitions between states an app was seen to make, see
+//! [`AppTester::with_state_chart`](super::AppTester::with_state_chart)
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Debug, Write as _},
+};
+
+/// A transition between two states, and how many times it was made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub from: String,
+    pub event: String,
+    pub to: String,
+    pub count: usize,
+}
+
+/// A graph of the states an app was in and the events which moved it between them, collected
+/// while it ran, to be drawn with [`to_dot`](StateChart::to_dot) (Graphviz) or
+/// [`to_mermaid`](StateChart::to_mermaid), so that the actual behaviour of a large `update`
+/// can be reviewed.
+///
+/// Charts are usually collected by an [`AppTester`](super::AppTester), but can be built from
+/// any record of events and states, e.g. a replayed session, with [`record`](StateChart::record).
+/// The output is sorted, so that charts from the same transitions are the same, and can be
+/// kept in snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateChart {
+    initial: Option<String>,
+    transitions: BTreeMap<(String, String, String), usize>,
+}
+
+impl StateChart {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a transition `from` a state `to` another one on the `event`. The state of the
+    /// first transition recorded is the initial state.
+    pub fn record(&mut self, from: &str, event: &str, to: &str) {
+        self.initial.get_or_insert_with(|| from.to_string());
+        *self
+            .transitions
+            .entry((from.to_string(), event.to_string(), to.to_string()))
+            .or_default() += 1;
+    }
+
+    /// The state the app was in when the first transition was recorded
+    pub fn initial(&self) -> Option<&str> {
+        self.initial.as_deref()
+    }
+
+    /// The states seen, sorted
+    pub fn states(&self) -> BTreeSet<&str> {
+        self.transitions
+            .keys()
+            .flat_map(|(from, _, to)| [from.as_str(), to.as_str()])
+            .collect()
+    }
+
+    /// The transitions seen, ordered by state and event
+    pub fn transitions(&self) -> impl Iterator<Item = Transition> + '_ {
+        self.transitions
+            .iter()
+            .map(|((from, event, to), count)| Transition {
+                from: from.clone(),
+                event: event.clone(),
+                to: to.clone(),
+                count: *count,
+            })
+    }
+
+    /// The chart in the Graphviz DOT language, with the number of times each transition was
+    /// made, e.g. to render with `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        if let Some(initial) = &self.initial {
+            let _ = writeln!(dot, "    __start [shape=point];");
+            let _ = writeln!(dot, "    __start -> {};", dot_id(initial));
+        }
+        for state in self.states() {
+            let _ = writeln!(dot, "    {};", dot_id(state));
+        }
+        for transition in self.transitions() {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label={}];",
+                dot_id(&transition.from),
+                dot_id(&transition.to),
+                dot_id(&format!("{} ({})", transition.event, transition.count)),
+            );
+        }
+        dot.push('}');
+
+        dot
+    }
+
+    /// The chart as a Mermaid state diagram, e.g. to paste into Markdown which renders it.
+    pub fn to_mermaid(&self) -> String {
+        // Mermaid identifies states by a plain word, so the states are numbered and labelled
+        let ids: BTreeMap<&str, String> = self
+            .states()
+            .into_iter()
+            .enumerate()
+            .map(|(index, state)| (state, format!("s{index}")))
+            .collect();
+
+        let mut mermaid = String::from("stateDiagram-v2\n");
+        for (state, id) in &ids {
+            let _ = writeln!(mermaid, "    {id} : {}", mermaid_label(state));
+        }
+        if let Some(initial) = &self.initial {
+            let _ = writeln!(mermaid, "    [*] --> {}", ids[initial.as_str()]);
+        }
+        for transition in self.transitions() {
+            let _ = writeln!(
+                mermaid,
+                "    {} --> {} : {} ({})",
+                ids[transition.from.as_str()],
+                ids[transition.to.as_str()],
+                mermaid_label(&transition.event),
+                transition.count,
+            );
+        }
+        mermaid.pop();
+
+        mermaid
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_label(name: &str) -> String {
+    // colons separate the label in Mermaid, and line breaks end the statement
+    name.replace(':', "#colon;").replace('\n', " ")
+}
+
+/// The name of an event's variant, from its `Debug` representation, e.g. `Search` for
+/// `Search("crux")`, so that events with different data are the same transition.
+pub(super) fn variant_name<Ev: Debug>(event: &Ev) -> String {
+    let described = format!("{event:?}");
+    let end = described.find(['(', ' ', '{']).unwrap_or(described.len());
+
+    described[..end].to_string()
+}
+
+/// Records the transitions of an [`AppTester`](super::AppTester) in a [`StateChart`].
+pub(super) struct Recorder<Model, Ev> {
+    state: Box<dyn Fn(&Model) -> String>,
+    describe_event: fn(&Ev) -> String,
+    chart: RefCell<StateChart>,
+}
+
+impl<Model, Ev: Debug> Recorder<Model, Ev> {
+    pub(super) fn new(state: impl Fn(&Model) -> String + 'static) -> Self {
+        Self {
+            state: Box::new(state),
+            describe_event: variant_name,
+            chart: RefCell::default(),
+        }
+    }
+}
+
+impl<Model, Ev> Recorder<Model, Ev> {
+    /// The state and the event's name, before the update consumes it
+    pub(super) fn before(&self, event: &Ev, model: &Model) -> (String, String) {
+        ((self.state)(model), (self.describe_event)(event))
+    }
+
+    /// Record the transition to the state of the `model` after the update
+    pub(super) fn after(&self, (from, event): (String, String), model: &Model) {
+        let to = (self.state)(model);
+        self.chart.borrow_mut().record(&from, &event, &to);
+    }
+
+    pub(super) fn chart(&self) -> StateChart {
+        self.chart.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum Event {
+        Search(String),
+        Clear,
+        Filter { tag: String },
+    }
+
+    fn chart() -> StateChart {
+        let mut chart = StateChart::new();
+        chart.record("Idle", "Search", "Searching");
+        chart.record("Searching", "Results", "Showing \"results\"");
+        chart.record("Showing \"results\"", "Search", "Searching");
+        chart.record("Searching", "Results", "Showing \"results\"");
+        chart
+    }
+
+    #[test]
+    fn events_are_named_by_variant() {
+        assert_eq!(variant_name(&Event::Search("crux".to_string())), "Search");
+        assert_eq!(variant_name(&Event::Clear), "Clear");
+        assert_eq!(
+            variant_name(&Event::Filter {
+                tag: "a".to_string()
+            }),
+            "Filter"
+        );
+    }
+
+    #[test]
+    fn exports_dot() {
+        assert_eq!(
+            chart().to_dot(),
+            r#"digraph {
+    __start [shape=point];
+    __start -> "Idle";
+    "Idle";
+    "Searching";
+    "Showing \"results\"";
+    "Idle" -> "Searching" [label="Search (1)"];
+    "Searching" -> "Showing \"results\"" [label="Results (2)"];
+    "Showing \"results\"" -> "Searching" [label="Search (1)"];
+}"#
+        );
+    }
+
+    #[test]
+    fn exports_mermaid() {
+        assert_eq!(
+            chart().to_mermaid(),
+            r#"stateDiagram-v2
+    s0 : Idle
+    s1 : Searching
+    s2 : Showing "results"
+    [*] --> s0
+    s0 --> s1 : Search (1)
+    s1 --> s2 : Results (2)
+    s2 --> s1 : Search (1)"#
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Text input state kept in the core.
+//!
+//! A [`TextField`] in the model tracks the value of a text input, along with the selection and
+//! the IME composition (the "marked" text an input method is still working on). The shell reports
+//! edits with [`TextEdit`] events, and renders the field from the [`TextFieldView`] in the view
+//! model.
+//!
+//! A field can be given a transform, for example to format a phone number. Transforms are never
+//! applied while the input method is composing, and the selection is carried over to the
+//! transformed value, so formatting doesn't fight the platform text system. The shell should only
+//! write the value and selection back into the native text input when the view's `revision`
+//! changes, i.e. when the core changed the value itself rather than accepting the shell's edit.
+//!
+//! All offsets are in UTF-16 code units, which is what the text systems on iOS, Android and the
+//! web use.
+//!
+//! ```rust
+//! use crux_core::text_field::{TextEdit, TextField, TextRange};
+//!
+//! let mut field = TextField::new().with_transform(|value| {
+//!     value.chars().filter(char::is_ascii_digit).take(4).collect()
+//! });
+//!
+//! field.edit(TextEdit::Changed {
+//!     value: "12a3".to_string(),
+//!     selection: TextRange::cursor(4),
+//!     composition: TextRange::default(),
+//! });
+//!
+//! let view = field.view();
+//! assert_eq!(view.value, "123");
+//! assert_eq!(view.selection, TextRange::cursor(3));
+//! assert_eq!(view.revision, 1);
+//! ```
+
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+type Transform = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A range of text, in UTF-16 code units. An empty range is a cursor position.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextRange {
+    /// A range from `start` to `end`
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// An empty range at `offset`
+    pub fn cursor(offset: usize) -> Self {
+        Self {
+            start: offset,
+            end: offset,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Edits to a text field, reported by the shell.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TextEdit {
+    /// The text changed. `composition` is the range of text the input method is still
+    /// composing, empty if there is none.
+    Changed {
+        value: String,
+        selection: TextRange,
+        composition: TextRange,
+    },
+    /// The selection (or cursor position) changed, without changing the text.
+    SelectionChanged { selection: TextRange },
+}
+
+/// The state of a text field for the view model.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextFieldView {
+    pub value: String,
+    pub selection: TextRange,
+    /// The range of text the input method is composing, empty if there is none
+    pub composition: TextRange,
+    /// Incremented every time the core changes the value or selection on its own account.
+    /// The shell should only update the native text input when this changes.
+    pub revision: u64,
+}
+
+/// Text input state kept in the model. See the [module documentation](self) for details.
+#[derive(Clone, Default)]
+pub struct TextField {
+    view: TextFieldView,
+    transform: Option<Transform>,
+}
+
+impl TextField {
+    /// Create an empty text field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a transform applied to the value after every edit which isn't part of an
+    /// input method composition, e.g. to apply a mask or formatting.
+    ///
+    /// The selection is carried over to the transformed value by matching the characters
+    /// before it, in order, against the transformed value. The transform should therefore
+    /// keep the characters it doesn't remove in their original order.
+    pub fn with_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// The current value
+    pub fn value(&self) -> &str {
+        &self.view.value
+    }
+
+    /// The current selection
+    pub fn selection(&self) -> TextRange {
+        self.view.selection
+    }
+
+    /// Whether the input method is composing text
+    pub fn is_composing(&self) -> bool {
+        !self.view.composition.is_empty()
+    }
+
+    /// Replace the value programmatically, placing the cursor at the end. Any ongoing
+    /// composition is abandoned.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        let value = self.transformed(value.into());
+        let end = utf16_len(&value);
+
+        self.view.value = value;
+        self.view.selection = TextRange::cursor(end);
+        self.view.composition = TextRange::default();
+        self.view.revision += 1;
+    }
+
+    /// Apply an edit reported by the shell. Returns `true` if the value changed.
+    pub fn edit(&mut self, edit: TextEdit) -> bool {
+        match edit {
+            TextEdit::Changed {
+                value,
+                selection,
+                composition,
+            } => {
+                let changed = value != self.view.value;
+                let selection = clamp(selection, &value);
+                let composition = clamp(composition, &value);
+
+                if !composition.is_empty() || self.transform.is_none() {
+                    self.view.value = value;
+                    self.view.selection = selection;
+                    self.view.composition = composition;
+
+                    return changed;
+                }
+
+                let transformed = self.transformed(value.clone());
+                if transformed != value {
+                    self.view.selection = TextRange::new(
+                        map_offset(&value, &transformed, selection.start),
+                        map_offset(&value, &transformed, selection.end),
+                    );
+                    self.view.revision += 1;
+                } else {
+                    self.view.selection = selection;
+                }
+
+                let changed = transformed != self.view.value;
+                self.view.value = transformed;
+                self.view.composition = TextRange::default();
+
+                changed
+            }
+            TextEdit::SelectionChanged { selection } => {
+                self.view.selection = clamp(selection, &self.view.value);
+
+                false
+            }
+        }
+    }
+
+    /// The view model representation of the field
+    pub fn view(&self) -> TextFieldView {
+        self.view.clone()
+    }
+
+    fn transformed(&self, value: String) -> String {
+        match &self.transform {
+            Some(transform) => transform(&value),
+            None => value,
+        }
+    }
+}
+
+impl fmt::Debug for TextField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextField")
+            .field("view", &self.view)
+            .field("transform", &self.transform.is_some())
+            .finish()
+    }
+}
+
+fn utf16_len(value: &str) -> usize {
+    value.chars().map(char::len_utf16).sum()
+}
+
+fn clamp(range: TextRange, value: &str) -> TextRange {
+    let len = utf16_len(value);
+
+    TextRange::new(range.start.min(len), range.end.min(len))
+}
+
+/// Map a UTF-16 `offset` in `from` to the corresponding offset in `to`, by matching
+/// the characters before the offset, in order, against the characters of `to`.
+fn map_offset(from: &str, to: &str, offset: usize) -> usize {
+    let to: Vec<char> = to.chars().collect();
+    let mut position = 0;
+    let mut consumed = 0;
+
+    for c in from.chars() {
+        if consumed >= offset {
+            break;
+        }
+        consumed += c.len_utf16();
+
+        if let Some(found) = to[position..].iter().position(|&t| t == c) {
+            position += found + 1;
+        }
+    }
+
+    to[..position].iter().map(|c| c.len_utf16()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phone_number(value: &str) -> String {
+        let digits: Vec<char> = value
+            .chars()
+            .filter(char::is_ascii_digit)
+            .take(10)
+            .collect();
+
+        let mut formatted = String::new();
+        for (i, digit) in digits.iter().enumerate() {
+            match i {
+                0 => formatted.push('('),
+                3 => formatted.push_str(") "),
+                6 => formatted.push('-'),
+                _ => {}
+            }
+            formatted.push(*digit);
+        }
+        formatted
+    }
+
+    fn changed(value: &str, cursor: usize) -> TextEdit {
+        TextEdit::Changed {
+            value: value.to_string(),
+            selection: TextRange::cursor(cursor),
+            composition: TextRange::default(),
+        }
+    }
+
+    #[test]
+    fn accepts_edits_without_transform() {
+        let mut field = TextField::new();
+
+        assert!(field.edit(changed("hello", 5)));
+        assert!(!field.edit(changed("hello", 2)));
+
+        let view = field.view();
+        assert_eq!(view.value, "hello");
+        assert_eq!(view.selection, TextRange::cursor(2));
+        assert_eq!(view.revision, 0);
+    }
+
+    #[test]
+    fn formats_and_keeps_cursor_after_typed_digit() {
+        let mut field = TextField::new().with_transform(phone_number);
+
+        field.edit(changed("5551", 4));
+        assert_eq!(field.value(), "(555) 1");
+        assert_eq!(field.selection(), TextRange::cursor(7));
+
+        // insert a digit in the middle of the formatted number
+        field.edit(changed("(5595) 1", 4));
+        assert_eq!(field.value(), "(559) 51");
+        assert_eq!(field.selection(), TextRange::cursor(4));
+        assert_eq!(field.view().revision, 2);
+    }
+
+    #[test]
+    fn does_not_transform_while_composing() {
+        let mut field = TextField::new().with_transform(|value| value.replace(' ', ""));
+
+        field.edit(TextEdit::Changed {
+            value: "に ほ".to_string(),
+            selection: TextRange::cursor(3),
+            composition: TextRange::new(0, 3),
+        });
+        assert!(field.is_composing());
+        assert_eq!(field.value(), "に ほ");
+        assert_eq!(field.view().revision, 0);
+
+        field.edit(changed("日本 ", 3));
+        assert!(!field.is_composing());
+        assert_eq!(field.value(), "日本");
+        assert_eq!(field.selection(), TextRange::cursor(2));
+        assert_eq!(field.view().revision, 1);
+    }
+
+    #[test]
+    fn offsets_are_utf16() {
+        let mut field = TextField::new().with_transform(|value| value.replace(' ', ""));
+
+        // 🦀 is two UTF-16 code units
+        field.edit(changed("🦀 a", 4));
+        assert_eq!(field.value(), "🦀a");
+        assert_eq!(field.selection(), TextRange::cursor(3));
+    }
+
+    #[test]
+    fn set_value_bumps_revision_and_clamps_selection() {
+        let mut field = TextField::new();
+
+        field.set_value("abc");
+        assert_eq!(field.view().revision, 1);
+        assert_eq!(field.selection(), TextRange::cursor(3));
+
+        field.edit(TextEdit::SelectionChanged {
+            selection: TextRange::new(1, 10),
+        });
+        assert_eq!(field.selection(), TextRange::new(1, 3));
+    }
+}
@@ -0,0 +1,228 @@
+mod capability {
+    use crux_core::capability::{AbortHandle, CapabilityContext, Operation};
+    use crux_core::macros::Capability;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum DownloadOperation {
+        Start { url: String },
+        Cancel { url: String },
+    }
+
+    impl Operation for DownloadOperation {
+        type Output = usize;
+    }
+
+    #[derive(Capability)]
+    pub struct Downloader<Ev> {
+        context: CapabilityContext<DownloadOperation, Ev>,
+    }
+
+    impl<Ev> Downloader<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<DownloadOperation, Ev>) -> Self {
+            Self { context }
+        }
+
+        /// Download the url, asking the shell to cancel the download if it's aborted
+        pub fn download<F>(&self, url: &str, callback: F) -> AbortHandle
+        where
+            F: FnOnce(usize) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            let start = DownloadOperation::Start {
+                url: url.to_string(),
+            };
+
+            self.context.spawn_abortable_notifying(
+                async move {
+                    let size = context.request_from_shell(start).await;
+                    context.update_app(callback(size));
+                },
+                DownloadOperation::Cancel {
+                    url: url.to_string(),
+                },
+            )
+        }
+    }
+}
+
+mod app {
+    use crux_core::capability::AbortHandle;
+    use crux_core::macros::Effect;
+    use crux_http::{Http, Response};
+
+    use crate::capability::Downloader;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        OpenProfile(String),
+        CloseProfile,
+        ProfileFetched(crux_http::Result<Response<String>>),
+        DownloadVideo,
+        StopDownload,
+        Downloaded(usize),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub fetch: Option<AbortHandle>,
+        pub profiles: Vec<String>,
+        pub download: Option<AbortHandle>,
+        pub downloaded: Option<usize>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        #[effect(max_in_flight = 1)]
+        pub http: Http<Event>,
+        pub downloader: Downloader<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::OpenProfile(name) => {
+                    let fetch = caps
+                        .http
+                        .get(format!("http://example.com/profiles/{name}"))
+                        .expect_string()
+                        .send_abortable(Event::ProfileFetched);
+                    model.fetch = Some(fetch);
+                }
+                Event::CloseProfile => {
+                    if let Some(fetch) = model.fetch.take() {
+                        fetch.abort();
+                    }
+                }
+                Event::ProfileFetched(response) => {
+                    model.fetch = None;
+                    model
+                        .profiles
+                        .push(response.unwrap().take_body().unwrap_or_default());
+                }
+                Event::DownloadVideo => {
+                    let download = caps
+                        .downloader
+                        .download("http://example.com/video", Event::Downloaded);
+                    model.download = Some(download);
+                }
+                Event::StopDownload => {
+                    if let Some(download) = model.download.take() {
+                        download.abort();
+                    }
+                }
+                Event::Downloaded(size) => {
+                    model.download = None;
+                    model.downloaded = Some(size);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpResponse, HttpResult};
+
+    use crate::{
+        app::{App, Effect, Event, Model},
+        capability::DownloadOperation,
+    };
+
+    fn ok(body: &str) -> HttpResult {
+        HttpResult::Ok(HttpResponse::ok().body(body).build())
+    }
+
+    #[test]
+    fn an_aborted_request_is_ignored_and_frees_its_slot() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let mut first = app
+            .update(Event::OpenProfile("ada".to_string()), &mut model)
+            .into_effects()
+            .find_map(Effect::into_http)
+            .unwrap();
+        app.update(Event::CloseProfile, &mut model);
+
+        // the next request is sent straight away, although the first wasn't resolved
+        let mut second = app
+            .update(Event::OpenProfile("grace".to_string()), &mut model)
+            .into_effects()
+            .find_map(Effect::into_http)
+            .unwrap();
+        assert_eq!(
+            second.operation.url,
+            "http://example.com/profiles/grace".to_string()
+        );
+
+        let update = app.resolve(&mut first, ok("ada")).unwrap();
+        assert!(update.events.is_empty());
+
+        let update = app.resolve(&mut second, ok("grace")).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(model.profiles, vec!["grace"]);
+    }
+
+    #[test]
+    fn aborting_notifies_the_shell_until_the_task_finishes() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let mut download = app
+            .update(Event::DownloadVideo, &mut model)
+            .into_effects()
+            .find_map(Effect::into_downloader)
+            .unwrap();
+
+        let cancel = app
+            .update(Event::StopDownload, &mut model)
+            .into_effects()
+            .find_map(Effect::into_downloader)
+            .unwrap();
+        assert_eq!(
+            cancel.operation,
+            DownloadOperation::Cancel {
+                url: "http://example.com/video".to_string()
+            }
+        );
+
+        assert!(app.resolve(&mut download, 1024).unwrap().events.is_empty());
+        assert_eq!(model.downloaded, None);
+
+        // a download which finished isn't cancelled
+        let mut download = app
+            .update(Event::DownloadVideo, &mut model)
+            .into_effects()
+            .find_map(Effect::into_downloader)
+            .unwrap();
+        let handle = model.download.clone().unwrap();
+
+        for event in app.resolve(&mut download, 2048).unwrap().events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(model.downloaded, Some(2048));
+
+        handle.abort();
+        assert!(handle.is_aborted());
+        assert!(app
+            .update(Event::StopDownload, &mut model)
+            .effects
+            .is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_time::{Time, TimeResponse};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Fetch,
+        Render,
+
+        #[serde(skip)]
+        Fetched(TimeResponse),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub started: usize,
+        pub completed: usize,
+        pub latest: Option<TimeResponse>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct ViewModel {
+        pub started: usize,
+        pub completed: usize,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Fetch => {
+                    model.started += 1;
+                    caps.time.now(Event::Fetched);
+                }
+                Event::Fetched(response) => {
+                    model.completed += 1;
+                    model.latest = Some(response);
+                    caps.render.render();
+                }
+                Event::Render => caps.render.render(),
+            }
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            ViewModel {
+                started: model.started,
+                completed: model.completed,
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+        pub time: Time<Event>,
+    }
+}
+
+mod tests {
+    use bincode::Options;
+    use crux_core::{
+        bridge::Bridge,
+        testing::{BridgeFuzz, BridgeFuzzShell},
+        Core,
+    };
+    use crux_time::{Instant, TimeResponse};
+
+    use crate::app::{App, Capabilities, Effect, EffectFfi, Event, ViewModel};
+
+    fn bridge() -> Bridge<Effect, App> {
+        Bridge::new(Core::new::<Capabilities>())
+    }
+
+    fn event(rng: &mut crux_core::testing::SoakRng) -> Event {
+        if rng.chance(0.8) {
+            Event::Fetch
+        } else {
+            Event::Render
+        }
+    }
+
+    fn shell(effect: &EffectFfi, shell: &mut BridgeFuzzShell<'_>) {
+        match effect {
+            EffectFfi::Time(_) => {
+                let seconds = shell.rng().below(1_000) as u64;
+                shell.respond(&TimeResponse::Now(Instant::new(seconds, 0).unwrap()));
+            }
+            EffectFfi::Render(_) => {}
+        }
+    }
+
+    fn view(bridge: &Bridge<Effect, App>) -> ViewModel {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .deserialize(&bridge.view())
+            .unwrap()
+    }
+
+    #[test]
+    fn concurrent_events_and_responses_lose_nothing() {
+        let bridge = bridge();
+
+        let report = BridgeFuzz::<App>::new(7)
+            .threads(8)
+            .steps(200)
+            .duplicate_rate(0.1)
+            .check_view(|view: &ViewModel| {
+                if view.completed <= view.started {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} requests completed of {} started",
+                        view.completed, view.started
+                    ))
+                }
+            })
+            .run(&bridge, event, shell);
+
+        report.assert_clean();
+        assert!(report.requests > 0);
+
+        let view = view(&bridge);
+        assert!(view.started > 0);
+        assert_eq!(view.completed, view.started);
+    }
+
+    #[test]
+    fn reports_broken_checks() {
+        let report = BridgeFuzz::<App>::new(1)
+            .threads(1)
+            .steps(20)
+            .check_view(|view: &ViewModel| {
+                if view.completed == view.started {
+                    Ok(())
+                } else {
+                    Err("a request is outstanding".to_string())
+                }
+            })
+            .run(&bridge(), |_| Event::Fetch, shell);
+
+        assert!(!report.is_clean());
+        assert_eq!(report.violations[0], "a request is outstanding");
+        assert_eq!(report.lost_effects, 0);
+        assert!(report
+            .to_string()
+            .starts_with("bridge fuzz test with seed 1 (1 threads, "));
+    }
+}
@@ -0,0 +1,159 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_time::{Time, TimeResponse};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Render,
+        GetTime,
+
+        #[serde(skip)]
+        Time(TimeResponse),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub time: Option<TimeResponse>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ViewModel {
+        pub has_time: bool,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Render => caps.render.render(),
+                Event::GetTime => caps.time.now(Event::Time),
+                Event::Time(time) => model.time = Some(time),
+            }
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            ViewModel {
+                has_time: model.time.is_some(),
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+        pub time: Time<Event>,
+    }
+}
+
+mod tests {
+    use bincode::{DefaultOptions, Options};
+    use crux_core::{
+        bridge::{Bridge, Request},
+        Core, ResolveError,
+    };
+    use crux_time::{Instant, TimeResponse};
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use crate::app::{App, EffectFfi, Event, ViewModel};
+
+    fn options() -> impl Options + Copy {
+        DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        options().serialize(value).unwrap()
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> T {
+        options().deserialize(bytes).unwrap()
+    }
+
+    fn single_request(bytes: &[u8]) -> Request<EffectFfi> {
+        let mut requests: Vec<Request<EffectFfi>> = deserialize(bytes);
+        assert_eq!(requests.len(), 1);
+
+        requests.remove(0)
+    }
+
+    #[test]
+    fn resolving_a_request_which_expects_no_response() {
+        let bridge = Bridge::<crate::app::Effect, App>::new(Core::default());
+
+        let request = single_request(&bridge.process_event(&serialize(&Event::Render)));
+        let response = serialize(&());
+
+        let requests: Vec<Request<EffectFfi>> =
+            deserialize(&bridge.handle_response(&request.uuid, &response));
+        assert!(requests.is_empty());
+
+        let dead_letters = bridge.dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].uuid, request.uuid);
+        assert_eq!(dead_letters[0].response, Some(response));
+        assert_eq!(dead_letters[0].error, ResolveError::Never);
+    }
+
+    #[test]
+    fn resolving_an_unknown_request() {
+        let bridge = Bridge::<crate::app::Effect, App>::new(Core::default());
+
+        bridge.handle_response(&[0; 16], &serialize(&()));
+        bridge.handle_response(&[1, 2, 3], &serialize(&()));
+
+        let errors: Vec<_> = bridge
+            .take_dead_letters()
+            .into_iter()
+            .map(|letter| letter.error)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ResolveError::NotFound { uuid: vec![0; 16] },
+                ResolveError::NotFound {
+                    uuid: vec![1, 2, 3]
+                },
+            ]
+        );
+        assert!(bridge.dead_letters().is_empty());
+    }
+
+    #[test]
+    fn a_response_which_does_not_deserialize_can_be_retried() {
+        let bridge = Bridge::<crate::app::Effect, App>::new(Core::default());
+
+        let request = single_request(&bridge.process_event(&serialize(&Event::GetTime)));
+        let EffectFfi::Time(_) = request.effect else {
+            panic!("expected a Time effect");
+        };
+
+        bridge.handle_response(&request.uuid, &[]);
+
+        let dead_letters = bridge.take_dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        assert!(matches!(
+            dead_letters[0].error,
+            ResolveError::Deserialize { .. }
+        ));
+
+        let view: ViewModel = deserialize(&bridge.view());
+        assert!(!view.has_time);
+
+        let response = TimeResponse::Now(Instant::new(1, 0).unwrap());
+        bridge.handle_response(&request.uuid, &serialize(&response));
+
+        let view: ViewModel = deserialize(&bridge.view());
+        assert!(view.has_time);
+        assert!(bridge.dead_letters().is_empty());
+    }
+}
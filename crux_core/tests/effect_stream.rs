@@ -0,0 +1,101 @@
+mod app {
+    use crux_core::{compose::Compose, macros::Effect, render::Render};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Import,
+        // sent by the background work, one batch at a time
+        Imported,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub batches: usize,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = usize;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Import => {}
+                Event::Imported => model.batches += 1,
+            }
+
+            caps.render.render();
+            if model.batches < 3 {
+                caps.compose
+                    .spawn(|context| async move { context.update_app(Event::Imported) });
+            }
+        }
+
+        fn view(&self, model: &Model) -> usize {
+            model.batches
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{bridge::Bridge, Core};
+    use futures::{executor::block_on_stream, StreamExt};
+
+    use crate::app::{App, Effect, Event};
+
+    #[test]
+    fn yields_effects_before_the_update_settles() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = block_on_stream(core.process_event_stream(Event::Import));
+
+        assert!(matches!(effects.next(), Some(Effect::Render(_))));
+        // the background work hasn't run yet
+        assert_eq!(core.view(), 0);
+
+        assert_eq!(effects.count(), 3);
+        assert_eq!(core.view(), 3);
+    }
+
+    #[test]
+    fn a_dropped_stream_settles_later() {
+        let core: Core<Effect, App> = Core::default();
+
+        let stream = core.process_event_stream(Event::Import);
+        assert_eq!(block_on_stream(stream.take(1)).count(), 1);
+        assert_eq!(core.view(), 0);
+
+        // the next call picks up where the stream stopped, running the work of both imports
+        core.process_event(Event::Import);
+        assert_eq!(core.view(), 4);
+    }
+
+    #[test]
+    fn callbacks_receive_the_same_effects() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = Vec::new();
+        core.process_event_with(Event::Import, |effect| effects.push(effect));
+        assert_eq!(effects.len(), 4);
+        assert!(effects.iter().all(Effect::is_render));
+
+        let bridge = Bridge::new(Core::<Effect, App>::default());
+        let event = bincode::serialize(&Event::Import).unwrap();
+
+        let mut requests = Vec::new();
+        bridge.process_event_with(&event, |request| requests.push(request));
+        assert_eq!(requests.len(), 4);
+    }
+}
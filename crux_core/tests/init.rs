@@ -0,0 +1,151 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_http::{Http, Response};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        #[serde(skip)]
+        SessionRefreshed(crux_http::Result<Response<String>>),
+        #[serde(skip)]
+        ConfigFetched(crux_http::Result<Response<String>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub stage: Stage,
+        pub session: Option<String>,
+        pub config: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum Stage {
+        #[default]
+        Created,
+        RefreshingSession,
+        FetchingConfig,
+        Ready,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct ViewModel {
+        pub stage: Stage,
+        pub config: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub render: Render<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn init(&self, model: &mut Model, caps: &Capabilities) {
+            model.stage = Stage::RefreshingSession;
+            caps.http
+                .post("http://example.com/session")
+                .expect_string()
+                .send(Event::SessionRefreshed);
+        }
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::SessionRefreshed(Ok(mut response)) => {
+                    model.session = response.take_body();
+                    model.stage = Stage::FetchingConfig;
+                    caps.http
+                        .get("http://example.com/config")
+                        .expect_string()
+                        .send(Event::ConfigFetched);
+                }
+                Event::ConfigFetched(Ok(mut response)) => {
+                    model.config = response.take_body();
+                    model.stage = Stage::Ready;
+                    caps.render.render();
+                }
+                Event::SessionRefreshed(Err(_)) | Event::ConfigFetched(Err(_)) => {
+                    model.stage = Stage::Ready;
+                    caps.render.render();
+                }
+            }
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            ViewModel {
+                stage: model.stage,
+                config: model.config.clone(),
+            }
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{bridge::Bridge, testing::AppTester, Core};
+    use crux_http::protocol::{HttpResponse, HttpResult};
+
+    use crate::app::{App, Effect, Model, Stage, ViewModel};
+
+    fn ok(body: &str) -> HttpResult {
+        HttpResult::Ok(HttpResponse::ok().body(body).build())
+    }
+
+    #[test]
+    fn core_runs_init_when_created() {
+        let core: Core<Effect, App> = Core::default();
+        assert_eq!(core.view().stage, Stage::RefreshingSession);
+
+        let Some(Effect::Http(mut request)) = core.start().pop() else {
+            panic!("expected the session to be refreshed");
+        };
+        assert_eq!(request.operation.url, "http://example.com/session");
+
+        let Some(Effect::Http(mut request)) = core.resolve(&mut request, ok("token")).pop() else {
+            panic!("expected the config to be fetched");
+        };
+        assert_eq!(request.operation.url, "http://example.com/config");
+        assert_eq!(core.view().stage, Stage::FetchingConfig);
+
+        let effects = core.resolve(&mut request, ok("dark mode"));
+        assert!(matches!(effects[..], [Effect::Render(_)]));
+        assert_eq!(
+            core.view(),
+            ViewModel {
+                stage: Stage::Ready,
+                config: Some("dark mode".to_string())
+            }
+        );
+
+        assert!(core.start().is_empty());
+    }
+
+    #[test]
+    fn bridge_starts_the_core() {
+        let bridge = Bridge::<Effect, App>::new(Core::default());
+
+        let requests: Vec<crux_core::bridge::Request<crate::app::EffectFfi>> =
+            bincode::deserialize(&bridge.start()).unwrap();
+
+        assert_eq!(requests.len(), 1);
+    }
+
+    #[test]
+    fn app_tester_runs_init() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let update = app.init(&mut model);
+
+        assert_eq!(model.stage, Stage::RefreshingSession);
+        let request = update.into_effects().find_map(Effect::into_http).unwrap();
+        assert_eq!(request.operation.url, "http://example.com/session");
+    }
+}
@@ -0,0 +1,92 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        Deposit(i64),
+        // doesn't check the balance
+        Withdraw(i64),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub balance: i64,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = i64;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Deposit(amount) => model.balance += amount,
+                Event::Withdraw(amount) => model.balance -= amount,
+            }
+
+            caps.render.render();
+        }
+
+        fn view(&self, model: &Model) -> i64 {
+            model.balance
+        }
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+
+    use crate::app::{App, Effect, Event, Model};
+
+    fn app() -> AppTester<App, Effect> {
+        AppTester::default()
+            .with_invariant(|model: &Model| {
+                anyhow::ensure!(model.balance >= 0, "balance is negative: {}", model.balance);
+                Ok(())
+            })
+            .with_invariant(|model: &Model| {
+                anyhow::ensure!(model.balance <= 100, "balance is over the limit");
+                Ok(())
+            })
+    }
+
+    #[test]
+    fn updates_keeping_the_invariants_pass() {
+        let app = app();
+        let mut model = Model::default();
+
+        app.update(Event::Deposit(10), &mut model);
+        app.update(Event::Withdraw(10), &mut model);
+
+        assert_eq!(model.balance, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "invariant #0 failed: balance is negative: -5\n\
+                               after the update with event: Withdraw(15)\n\
+                               which requested effects: [Render(Request(RenderOperation))]\n\
+                               and dispatched events: []")]
+    fn breaking_an_invariant_panics_with_the_update() {
+        let app = app();
+        let mut model = Model::default();
+
+        app.update(Event::Deposit(10), &mut model);
+        app.update(Event::Withdraw(15), &mut model);
+    }
+
+    #[test]
+    #[should_panic(expected = "invariant #1 failed: balance is over the limit")]
+    fn every_invariant_is_checked() {
+        app().update(Event::Deposit(101), &mut Model::default());
+    }
+}
@@ -69,21 +69,21 @@ mod tests {
         };
 
         let Value::Array(uuid) = &request["uuid"] else {
-            panic!("Expected uuid to be an array, got: {:?}", &request["uuid"])
+            panic!("Expected uuid to be an array, got: {:?}", request["uuid"])
         };
         assert_eq!(uuid.len(), 16);
 
         let Value::Object(effect) = &request["effect"] else {
             panic!(
                 "Expected effect to be an object, got: {:?}",
-                &request["effect"]
+                request["effect"]
             )
         };
 
         let Value::Null = &effect["Render"] else {
             panic!(
                 "Expected effect to be a 'Render' variant, got: {:?}",
-                &effect
+                effect
             )
         };
     }
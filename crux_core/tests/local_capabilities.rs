@@ -0,0 +1,119 @@
+mod search {
+    use std::sync::{Arc, Mutex};
+
+    /// A service in the core, which never talks to the shell
+    #[derive(Clone, Default)]
+    pub struct SearchIndex {
+        documents: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SearchIndex {
+        pub fn with_documents(documents: &[&str]) -> Self {
+            let documents = documents.iter().map(ToString::to_string).collect();
+
+            Self {
+                documents: Arc::new(Mutex::new(documents)),
+            }
+        }
+
+        pub fn index(&self, document: &str) {
+            self.documents.lock().unwrap().push(document.to_string());
+        }
+
+        pub fn search(&self, query: &str) -> Vec<String> {
+            let documents = self.documents.lock().unwrap();
+
+            documents
+                .iter()
+                .filter(|document| document.contains(query))
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+mod app {
+    use crux_core::{macros::Effect, render::Render};
+
+    use crate::search::SearchIndex;
+
+    #[derive(Default)]
+    pub struct App;
+
+    pub enum Event {
+        Save(String),
+        Search(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub results: Vec<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+        #[effect(local)]
+        pub search: SearchIndex,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Vec<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Save(note) => caps.search.index(&note),
+                Event::Search(query) => {
+                    model.results = caps.search.search(&query);
+                    caps.render.render();
+                }
+            }
+        }
+
+        fn view(&self, model: &Model) -> Vec<String> {
+            model.results.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Core};
+
+    use crate::{
+        app::{App, Effect, Event, Model},
+        search::SearchIndex,
+    };
+
+    #[test]
+    fn local_capabilities_run_in_the_core() {
+        let core: Core<Effect, App> = Core::default();
+
+        assert!(core
+            .process_event(Event::Save("buy milk".to_string()))
+            .is_empty());
+        core.process_event(Event::Save("walk the dog".to_string()));
+
+        for effect in core.process_event(Event::Search("milk".to_string())) {
+            // the search index has no effect variant
+            match effect {
+                Effect::Render(_) => {}
+            }
+        }
+        assert_eq!(core.view(), vec!["buy milk"]);
+    }
+
+    #[test]
+    fn local_capabilities_can_be_swapped_in_tests() {
+        let app = AppTester::<App, Effect>::default().with_capabilities(|caps| {
+            caps.search = SearchIndex::with_documents(&["crux core", "crux shell"]);
+        });
+        let mut model = Model::default();
+
+        app.update(Event::Search("crux".to_string()), &mut model);
+
+        assert_eq!(app.view(&model), vec!["crux core", "crux shell"]);
+    }
+}
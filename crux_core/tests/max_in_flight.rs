@@ -0,0 +1,119 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_http::{Http, Response};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        FetchImages(usize),
+        #[serde(skip)]
+        ImageFetched(crux_http::Result<Response<Vec<u8>>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub fetched: usize,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        #[effect(max_in_flight = 2)]
+        pub http: Http<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = usize;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::FetchImages(count) => {
+                    for image in 0..count {
+                        caps.http
+                            .get(format!("http://example.com/images/{image}"))
+                            .send(Event::ImageFetched);
+                    }
+                }
+                Event::ImageFetched(response) => {
+                    if response.is_ok() {
+                        model.fetched += 1;
+                    }
+                }
+            }
+        }
+
+        fn view(&self, model: &Model) -> usize {
+            model.fetched
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{Core, Request};
+    use crux_http::protocol::{HttpRequest, HttpResponse, HttpResult};
+
+    use crate::app::{App, Effect, Event};
+
+    fn http(effects: Vec<Effect>) -> Vec<Request<HttpRequest>> {
+        effects.into_iter().filter_map(Effect::into_http).collect()
+    }
+
+    fn urls(requests: &[Request<HttpRequest>]) -> Vec<&str> {
+        requests
+            .iter()
+            .map(|request| request.operation.url.as_str())
+            .collect()
+    }
+
+    fn ok() -> HttpResult {
+        HttpResult::Ok(HttpResponse::ok().build())
+    }
+
+    #[test]
+    fn requests_over_the_limit_are_sent_in_order_as_others_resolve() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut in_flight = http(core.process_event(Event::FetchImages(5)));
+        assert_eq!(
+            urls(&in_flight),
+            vec!["http://example.com/images/0", "http://example.com/images/1"]
+        );
+
+        let mut next = http(core.resolve(&mut in_flight[1], ok()));
+        assert_eq!(urls(&next), vec!["http://example.com/images/2"]);
+        in_flight.append(&mut next);
+
+        let mut next = http(core.resolve(&mut in_flight[0], ok()));
+        assert_eq!(urls(&next), vec!["http://example.com/images/3"]);
+        in_flight.append(&mut next);
+
+        let mut next = http(core.resolve(&mut in_flight[2], ok()));
+        assert_eq!(urls(&next), vec!["http://example.com/images/4"]);
+        in_flight.append(&mut next);
+
+        assert!(core.resolve(&mut in_flight[3], ok()).is_empty());
+        assert!(core.resolve(&mut in_flight[4], ok()).is_empty());
+        assert_eq!(core.view(), 5);
+
+        let metrics = core.metrics();
+        let http = metrics.capability("HttpRequest").unwrap();
+        assert_eq!((http.requests, http.resolved, http.queued), (5, 5, 3));
+    }
+
+    #[test]
+    fn the_limit_is_shared_by_all_updates() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut first = http(core.process_event(Event::FetchImages(1)));
+        assert_eq!(first.len(), 1);
+        assert_eq!(http(core.process_event(Event::FetchImages(3))).len(), 1);
+
+        let next = http(core.resolve(&mut first[0], ok()));
+        assert_eq!(urls(&next), vec!["http://example.com/images/1"]);
+    }
+}
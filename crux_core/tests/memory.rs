@@ -0,0 +1,255 @@
+mod capability {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use crux_core::capability::{Capability, CapabilityContext, Operation};
+    use crux_core::memory::{MemoryPressure, Trim};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct FetchThumbnail {
+        pub url: String,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Thumbnail(pub Vec<u8>);
+
+    impl Operation for FetchThumbnail {
+        type Output = Thumbnail;
+    }
+
+    #[derive(Default)]
+    pub struct ThumbnailCache {
+        thumbnails: Mutex<BTreeMap<String, Vec<u8>>>,
+    }
+
+    impl Trim for ThumbnailCache {
+        fn trim(&self, pressure: MemoryPressure) -> usize {
+            let mut thumbnails = self.thumbnails.lock().unwrap();
+            let keep = match pressure {
+                MemoryPressure::Moderate => 1,
+                MemoryPressure::Critical => 0,
+            };
+
+            let mut freed = 0;
+            while thumbnails.len() > keep {
+                let (_, thumbnail) = thumbnails.pop_first().unwrap();
+                freed += thumbnail.len();
+            }
+
+            freed
+        }
+    }
+
+    pub struct Thumbnails<Ev> {
+        context: CapabilityContext<FetchThumbnail, Ev>,
+        cache: Arc<ThumbnailCache>,
+    }
+
+    impl<Ev> Thumbnails<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<FetchThumbnail, Ev>) -> Self {
+            let cache = Arc::default();
+            context.trim_registry().register("thumbnails", &cache);
+
+            Self { context, cache }
+        }
+
+        pub fn fetch<F>(&self, url: String, callback: F)
+        where
+            F: FnOnce(Vec<u8>) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            let cache = self.cache.clone();
+            self.context.spawn(async move {
+                let cached = cache.thumbnails.lock().unwrap().get(&url).cloned();
+                let thumbnail = match cached {
+                    Some(thumbnail) => thumbnail,
+                    None => {
+                        let Thumbnail(thumbnail) = context
+                            .request_from_shell(FetchThumbnail { url: url.clone() })
+                            .await;
+                        cache
+                            .thumbnails
+                            .lock()
+                            .unwrap()
+                            .insert(url, thumbnail.clone());
+
+                        thumbnail
+                    }
+                };
+
+                context.update_app(callback(thumbnail));
+            });
+        }
+    }
+
+    impl<Ev> Capability<Ev> for Thumbnails<Ev> {
+        type Operation = FetchThumbnail;
+        type MappedSelf<MappedEv> = Thumbnails<MappedEv>;
+
+        fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+        where
+            F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+            Ev: 'static,
+            NewEv: 'static,
+        {
+            Thumbnails {
+                context: self.context.map_event(f),
+                cache: self.cache.clone(),
+            }
+        }
+    }
+}
+
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::memory::MemoryPressure;
+    use serde::{Deserialize, Serialize};
+
+    use crate::capability::Thumbnails;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Fetch(String),
+        Fetched(Vec<u8>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub thumbnails: Vec<Vec<u8>>,
+        // memoized from the thumbnails, cheap to rebuild
+        pub sizes: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub thumbnails: Thumbnails<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = String;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Fetch(url) => caps.thumbnails.fetch(url, Event::Fetched),
+                Event::Fetched(thumbnail) => {
+                    model.thumbnails.push(thumbnail);
+                    let sizes: Vec<_> = model.thumbnails.iter().map(|t| t.len()).collect();
+                    model.sizes = Some(format!("{sizes:?}"));
+                }
+            }
+        }
+
+        fn view(&self, model: &Model) -> String {
+            model.sizes.clone().unwrap_or_default()
+        }
+
+        fn trim_memory(&self, model: &mut Model, pressure: MemoryPressure) -> usize {
+            if pressure < MemoryPressure::Critical {
+                return 0;
+            }
+
+            model.sizes.take().map(|sizes| sizes.len()).unwrap_or(0)
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{
+        bridge::Bridge,
+        memory::{MemoryPressure, TrimReport, TrimmedCache},
+        testing::AppTester,
+        Core,
+    };
+
+    use crate::{
+        app::{App, Effect, Event, Model},
+        capability::Thumbnail,
+    };
+
+    fn fetch(core: &Core<Effect, App>, url: &str, size: usize) {
+        for effect in core.process_event(Event::Fetch(url.to_string())) {
+            let Effect::Thumbnails(mut request) = effect;
+            core.resolve(&mut request, Thumbnail(vec![0; size]));
+        }
+    }
+
+    fn cache(name: &str, freed_bytes: usize) -> TrimmedCache {
+        TrimmedCache {
+            name: name.to_string(),
+            freed_bytes,
+        }
+    }
+
+    #[test]
+    fn core_trims_caches_progressively() {
+        let core: Core<Effect, App> = Core::default();
+        fetch(&core, "a", 100);
+        fetch(&core, "b", 200);
+        fetch(&core, "c", 300);
+        assert_eq!(core.view(), "[100, 200, 300]");
+
+        let report = core.trim_memory(MemoryPressure::Moderate);
+        assert_eq!(
+            report,
+            TrimReport {
+                freed_bytes: 300,
+                caches: vec![cache("thumbnails", 300)],
+            }
+        );
+
+        let report = core.trim_memory(MemoryPressure::Critical);
+        assert_eq!(
+            report,
+            TrimReport {
+                freed_bytes: 315,
+                caches: vec![cache("model", 15), cache("thumbnails", 300)],
+            }
+        );
+        assert_eq!(core.view(), "");
+    }
+
+    #[test]
+    fn bridge_trims_on_a_serialized_memory_warning() {
+        let bridge = Bridge::<Effect, App>::new(Core::default());
+
+        let pressure = bincode::serialize(&MemoryPressure::Critical).unwrap();
+        let report: TrimReport = bincode::deserialize(&bridge.trim_memory(&pressure)).unwrap();
+
+        assert_eq!(
+            report,
+            TrimReport {
+                freed_bytes: 0,
+                caches: vec![cache("thumbnails", 0)],
+            }
+        );
+    }
+
+    #[test]
+    fn app_tester_trims_the_model() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model {
+            sizes: Some("[1]".to_string()),
+            ..Default::default()
+        };
+
+        let report = app.trim_memory(MemoryPressure::Critical, &mut model);
+
+        assert_eq!(
+            report.caches,
+            vec![cache("model", 3), cache("thumbnails", 0)]
+        );
+        assert_eq!(model.sizes, None);
+    }
+}
@@ -0,0 +1,153 @@
+mod capability {
+    use crux_core::capability::{CapabilityContext, Operation, ShellTimeout, TimesOut};
+    use crux_core::macros::Capability;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Ping;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Pong;
+
+    impl Operation for Ping {
+        type Output = Result<Pong, ShellTimeout>;
+    }
+
+    impl TimesOut for Ping {
+        fn timed_out(timeout: ShellTimeout) -> Self::Output {
+            Err(timeout)
+        }
+    }
+
+    #[derive(Capability)]
+    pub struct Pinger<Ev> {
+        context: CapabilityContext<Ping, Ev>,
+    }
+
+    impl<Ev> Pinger<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<Ping, Ev>) -> Self {
+            Self { context }
+        }
+
+        pub fn ping<F>(&self, callback: F)
+        where
+            F: FnOnce(Result<Pong, ShellTimeout>) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            self.context.spawn(async move {
+                let pong = context.request_from_shell(Ping).await;
+
+                context.update_app(callback(pong));
+            });
+        }
+    }
+}
+
+mod app {
+    use crux_core::capability::ShellTimeout;
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+
+    use crate::capability::{Pinger, Pong};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Ping,
+        Pong(Result<Pong, ShellTimeout>),
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        // times out on the next check
+        #[effect(timeout_ms = 0)]
+        pub pinger: Pinger<Event>,
+        pub render: Render<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = ();
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, _model: &mut (), caps: &Capabilities) {
+            match event {
+                Event::Ping => caps.pinger.ping(Event::Pong),
+                Event::Pong(_) => caps.render.render(),
+            }
+        }
+
+        fn view(&self, _model: &()) {}
+    }
+}
+
+mod tests {
+    use crux_core::{bridge::Bridge, capability::EffectMetrics, Core};
+
+    use crate::{
+        app::{App, Effect, Event},
+        capability::Pong,
+    };
+
+    #[test]
+    fn counts_requests_per_capability() {
+        let core: Core<Effect, App> = Core::default();
+        assert_eq!(core.metrics(), EffectMetrics::default());
+
+        // resolved in time
+        let Some(Effect::Pinger(mut request)) = core.process_event(Event::Ping).pop() else {
+            panic!("expected a Pinger effect");
+        };
+        core.resolve(&mut request, Ok(Pong));
+
+        // timed out
+        core.process_event(Event::Ping);
+        core.check_timeouts();
+
+        let metrics = core.metrics();
+        let names: Vec<_> = metrics
+            .capabilities
+            .iter()
+            .map(|c| c.capability.as_str())
+            .collect();
+        assert_eq!(names, ["Ping", "RenderOperation"]);
+
+        let ping = metrics.capability("Ping").unwrap();
+        assert_eq!((ping.requests, ping.resolved, ping.failed), (2, 1, 1));
+        assert!(ping.mean_latency_ms.is_some());
+
+        let render = metrics.capability("RenderOperation").unwrap();
+        assert_eq!((render.requests, render.resolved, render.failed), (2, 0, 0));
+        assert_eq!(render.mean_latency_ms, None);
+    }
+
+    #[test]
+    fn serializes_the_metrics_for_the_shell() {
+        let core: Core<Effect, App> = Core::default();
+        core.process_event(Event::Ping);
+
+        let json = serde_json::to_value(core.metrics()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "capabilities": [{
+                    "capability": "Ping",
+                    "requests": 1,
+                    "resolved": 0,
+                    "failed": 0,
+                    "queued": 0,
+                    "meanLatencyMs": null
+                }]
+            })
+        );
+
+        let bridge = Bridge::new(core);
+        assert!(!bridge.metrics().is_empty());
+    }
+}
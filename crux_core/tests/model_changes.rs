@@ -0,0 +1,105 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use serde::Serialize;
+
+    #[derive(Default)]
+    pub struct App;
+
+    pub enum Event {
+        Rename(String),
+        // also touches the history by mistake
+        Fetch,
+        Nothing,
+    }
+
+    #[derive(Default, Serialize)]
+    pub struct Model {
+        pub name: String,
+        pub loading: bool,
+        pub history: Vec<String>,
+        #[serde(skip)]
+        pub renders: usize,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = String;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Rename(name) => {
+                    model.history.push(std::mem::replace(&mut model.name, name));
+                }
+                Event::Fetch => {
+                    model.loading = true;
+                    model.history.clear();
+                }
+                Event::Nothing => {}
+            }
+
+            model.renders += 1;
+            caps.render.render();
+        }
+
+        fn view(&self, model: &Model) -> String {
+            model.name.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{assert_effect, assert_model_changed, testing::AppTester};
+
+    use crate::app::{App, Effect, Event, Model};
+
+    #[test]
+    fn passes_when_exactly_the_fields_change() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let update = assert_model_changed!(
+            app.update(Event::Rename("Crux".to_string()), &mut model),
+            model,
+            name,
+            history,
+        );
+        assert_effect!(update, Effect::Render(_));
+
+        // fields skipped by serde aren't compared
+        assert_model_changed!(app.update(Event::Nothing, &mut model), model);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "the update didn't change the expected fields of the model\n  \
+                               unexpected change of history: [\"\"] -> []"
+    )]
+    fn reports_unexpected_changes() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+        app.update(Event::Rename("Crux".to_string()), &mut model);
+
+        assert_model_changed!(app.update(Event::Fetch, &mut model), model, loading);
+    }
+
+    #[test]
+    #[should_panic(expected = "the model has no fields [\"nmae\"]")]
+    fn rejects_unknown_fields() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        assert_model_changed!(
+            app.update(Event::Rename("Crux".to_string()), &mut model),
+            model,
+            nmae
+        );
+    }
+}
@@ -0,0 +1,110 @@
+mod app {
+    use crux_core::macros::{Effect, Preview};
+    use crux_core::render::Render;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Preview)]
+    pub struct ViewModel {
+        pub title: String,
+        #[preview(value = "Screen::Basket")]
+        pub screen: Screen,
+        pub items: Vec<Item>,
+        pub status: Status,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Preview)]
+    pub enum Screen {
+        Home,
+        Basket,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Preview)]
+    pub struct Item(pub String, pub u32);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Preview)]
+    pub enum Status {
+        Idle,
+        Saving {
+            progress: f32,
+        },
+        #[preview(skip)]
+        Crashed,
+    }
+
+    impl crux_core::App for App {
+        type Event = ();
+        type Model = ();
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, _event: (), _model: &mut (), caps: &Capabilities) {
+            caps.render.render();
+        }
+
+        fn view(&self, _model: &()) -> ViewModel {
+            unimplemented!()
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<()>,
+    }
+}
+
+mod tests {
+    use crux_core::{bridge::Bridge, preview::Preview, Core};
+
+    use crate::app::{App, Effect, Item, Screen, Status, ViewModel};
+
+    #[test]
+    fn derived_previews_build_each_field() {
+        assert_eq!(
+            ViewModel::preview(),
+            ViewModel {
+                title: "Lorem ipsum".to_string(),
+                screen: Screen::Basket,
+                items: vec![
+                    Item("Sed do eiusmod".to_string(), 7),
+                    Item("Lorem ipsum".to_string(), 3),
+                    Item("Consectetur".to_string(), 42),
+                ],
+                status: Status::Idle,
+            }
+        );
+    }
+
+    #[test]
+    fn derived_previews_cycle_through_variants() {
+        let statuses = Status::previews(4);
+        assert_eq!(
+            statuses,
+            vec![
+                Status::Idle,
+                Status::Saving { progress: 0.25 },
+                Status::Idle,
+                Status::Saving { progress: 1999.99 },
+            ]
+        );
+
+        assert_eq!(ViewModel::previews(5), ViewModel::previews(5));
+
+        // the third sample is empty, the fourth large
+        let views = ViewModel::previews(4);
+        assert!(views[2].title.is_empty());
+        assert!(views[2].items.is_empty());
+        assert_eq!(views[3].items.len(), 12);
+    }
+
+    #[test]
+    fn bridge_serializes_previews() {
+        let bridge = Bridge::<Effect, App>::new(Core::default());
+
+        let previews: Vec<ViewModel> = bincode::deserialize(&bridge.previews(3)).unwrap();
+
+        assert_eq!(previews, ViewModel::previews(3));
+    }
+}
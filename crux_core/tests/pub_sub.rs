@@ -0,0 +1,254 @@
+mod topics {
+    use crux_core::bus::Topic;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct LoggedOut {
+        pub user: String,
+    }
+
+    impl Topic for LoggedOut {}
+}
+
+mod session {
+    use crux_core::{bus::Bus, App};
+
+    use crate::topics::LoggedOut;
+
+    #[derive(Default)]
+    pub struct Session;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        LogIn(String),
+        LogOut,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub user: Option<String>,
+    }
+
+    pub struct Capabilities {
+        pub bus: Bus<Event>,
+    }
+
+    impl App for Session {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::LogIn(user) => model.user = Some(user),
+                Event::LogOut => {
+                    if let Some(user) = model.user.take() {
+                        caps.bus.publish(LoggedOut { user });
+                    }
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod inbox {
+    use crux_core::{bus::Bus, App};
+
+    use crate::topics::LoggedOut;
+
+    #[derive(Default)]
+    pub struct Inbox;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Receive(String),
+        Clear(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub messages: Vec<String>,
+        pub cleared_for: Option<String>,
+    }
+
+    pub struct Capabilities {
+        pub bus: Bus<Event>,
+    }
+
+    impl App for Inbox {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            caps.bus.subscribe(|LoggedOut { user }| Event::Clear(user));
+
+            match event {
+                Event::Receive(message) => model.messages.push(message),
+                Event::Clear(user) => {
+                    model.messages.clear();
+                    model.cleared_for = Some(user);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod app {
+    use crux_core::{bus::Bus, macros::Effect, render::Render, App, Capability};
+
+    use crate::{inbox, session};
+
+    #[derive(Default)]
+    pub struct Parent {
+        session: session::Session,
+        inbox: inbox::Inbox,
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Session(session::Event),
+        Inbox(inbox::Event),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub session: session::Model,
+        pub inbox: inbox::Model,
+    }
+
+    #[derive(Effect)]
+    #[effect(app = "Parent")]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+        #[effect(skip)]
+        pub bus: Bus<Event>,
+    }
+
+    impl From<&Capabilities> for session::Capabilities {
+        fn from(incoming: &Capabilities) -> Self {
+            Self {
+                bus: incoming.bus.map_event(Event::Session),
+            }
+        }
+    }
+
+    impl From<&Capabilities> for inbox::Capabilities {
+        fn from(incoming: &Capabilities) -> Self {
+            Self {
+                bus: incoming.bus.map_event(Event::Inbox),
+            }
+        }
+    }
+
+    impl App for Parent {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        // the parent only forwards events to the children, it doesn't route messages
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Session(event) => {
+                    self.session.update(event, &mut model.session, &caps.into());
+                }
+                Event::Inbox(event) => {
+                    self.inbox.update(event, &mut model.inbox, &caps.into());
+                }
+            }
+
+            caps.render.render();
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Capability};
+
+    use crate::{
+        app::{Event, Model, Parent},
+        inbox, session,
+        topics::LoggedOut,
+    };
+
+    #[test]
+    fn delivers_published_messages_to_subscribers() {
+        let app = AppTester::<Parent, _>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(
+            Event::Inbox(inbox::Event::Receive("hi".to_string())),
+            &mut model,
+        );
+        let _ = app.update(
+            Event::Session(session::Event::LogIn("ada".to_string())),
+            &mut model,
+        );
+
+        let update = app.update(Event::Session(session::Event::LogOut), &mut model);
+        assert_eq!(
+            update.events,
+            vec![Event::Inbox(inbox::Event::Clear("ada".to_string()))]
+        );
+
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+        assert!(model.inbox.messages.is_empty());
+        assert_eq!(model.inbox.cleared_for.as_deref(), Some("ada"));
+    }
+
+    #[test]
+    fn records_published_messages() {
+        let app = AppTester::<Parent, _>::default();
+        let mut model = Model::default();
+        let published = app.as_ref().bus.record::<LoggedOut>();
+
+        let _ = app.update(
+            Event::Session(session::Event::LogIn("ada".to_string())),
+            &mut model,
+        );
+        // nobody is subscribed yet, but the message is still published
+        let update = app.update(Event::Session(session::Event::LogOut), &mut model);
+        assert!(update.events.is_empty());
+
+        assert_eq!(
+            published.take(),
+            vec![LoggedOut {
+                user: "ada".to_string()
+            }]
+        );
+        assert!(published.messages().is_empty());
+    }
+
+    #[test]
+    fn unsubscribing_stops_delivery() {
+        let app = AppTester::<Parent, _>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(
+            Event::Inbox(inbox::Event::Receive("hi".to_string())),
+            &mut model,
+        );
+        app.as_ref()
+            .bus
+            .map_event(Event::Inbox)
+            .unsubscribe::<LoggedOut>();
+
+        let _ = app.update(
+            Event::Session(session::Event::LogIn("ada".to_string())),
+            &mut model,
+        );
+        let update = app.update(Event::Session(session::Event::LogOut), &mut model);
+
+        assert!(update.events.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Add(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub items: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub struct ViewModel {
+        pub items: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Query {
+        Count,
+        Latest,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub enum QueryResponse {
+        Count(usize),
+        Latest(Option<String>),
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Add(item) => model.items.push(item),
+            }
+
+            caps.render.render();
+        }
+
+        fn view(&self, _model: &Model) -> ViewModel {
+            panic!("queries should not build the view model");
+        }
+    }
+
+    impl crux_core::Queryable for App {
+        type Query = Query;
+        type QueryResponse = QueryResponse;
+
+        fn query(&self, query: Query, model: &Model) -> QueryResponse {
+            match query {
+                Query::Count => QueryResponse::Count(model.items.len()),
+                Query::Latest => QueryResponse::Latest(model.items.last().cloned()),
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::{
+        bridge::{Bridge, BridgeWithSerializer},
+        testing::AppTester,
+        Core,
+    };
+    use serde_json::json;
+
+    use crate::app::{App, Effect, Event, Model, Query, QueryResponse};
+
+    #[test]
+    fn core_answers_queries() {
+        let core: Core<Effect, App> = Core::default();
+        assert_eq!(core.query(Query::Latest), QueryResponse::Latest(None));
+
+        core.process_event(Event::Add("milk".to_string()));
+        core.process_event(Event::Add("eggs".to_string()));
+
+        assert_eq!(core.query(Query::Count), QueryResponse::Count(2));
+        assert_eq!(
+            core.query(Query::Latest),
+            QueryResponse::Latest(Some("eggs".to_string()))
+        );
+    }
+
+    #[test]
+    fn bridge_answers_serialized_queries() {
+        let bridge = Bridge::<Effect, App>::new(Core::default());
+
+        let event = bincode::serialize(&Event::Add("milk".to_string())).unwrap();
+        bridge.process_event(&event);
+
+        let query = bincode::serialize(&Query::Count).unwrap();
+        let response: QueryResponse = bincode::deserialize(&bridge.query(&query)).unwrap();
+
+        assert_eq!(response, QueryResponse::Count(1));
+    }
+
+    #[test]
+    fn bridge_with_serializer_answers_queries() {
+        let bridge = BridgeWithSerializer::<Effect, App>::new(Core::default());
+
+        let mut response = vec![];
+        bridge.query(
+            json!("Latest"),
+            &mut serde_json::Serializer::new(&mut response),
+        );
+
+        let response: serde_json::Value = serde_json::from_slice(&response).unwrap();
+        assert_eq!(response, json!({ "Latest": null }));
+    }
+
+    #[test]
+    fn app_tester_answers_queries() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(Event::Add("milk".to_string()), &mut model);
+
+        assert_eq!(app.query(Query::Count, &model), QueryResponse::Count(1));
+    }
+}
@@ -0,0 +1,165 @@
+mod capability {
+    use crux_core::capability::{BatchOperation, CapabilityContext, Operation};
+    use crux_core::macros::Capability;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Lookup {
+        Name { id: u32 },
+        Batch { lookups: Vec<Lookup> },
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Found {
+        Name(Option<String>),
+        Batch(Vec<Found>),
+    }
+
+    impl Operation for Lookup {
+        type Output = Found;
+    }
+
+    impl BatchOperation for Lookup {
+        fn batch(lookups: Vec<Self>) -> Self {
+            Lookup::Batch { lookups }
+        }
+
+        fn unbatch(output: Found, count: usize) -> Vec<Found> {
+            let mut found = match output {
+                Found::Batch(found) => found,
+                Found::Name(_) => vec![],
+            };
+            found.resize(count, Found::Name(None));
+
+            found
+        }
+    }
+
+    #[derive(Capability)]
+    pub struct Directory<Ev> {
+        context: CapabilityContext<Lookup, Ev>,
+    }
+
+    impl<Ev> Directory<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<Lookup, Ev>) -> Self {
+            Self { context }
+        }
+
+        pub fn names<F>(&self, ids: Vec<u32>, callback: F)
+        where
+            F: FnOnce(Vec<Option<String>>) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            self.context.spawn(async move {
+                let lookups = ids.into_iter().map(|id| Lookup::Name { id }).collect();
+                let names = context
+                    .request_batch(lookups)
+                    .await
+                    .into_iter()
+                    .map(|found| match found {
+                        Found::Name(name) => name,
+                        Found::Batch(_) => None,
+                    })
+                    .collect();
+
+                context.update_app(callback(names));
+            });
+        }
+    }
+}
+
+mod app {
+    use crux_core::macros::Effect;
+
+    use crate::capability::Directory;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Lookup(Vec<u32>),
+        Found(Vec<Option<String>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub names: Option<Vec<Option<String>>>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub directory: Directory<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<Vec<Option<String>>>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Lookup(ids) => caps.directory.names(ids, Event::Found),
+                Event::Found(names) => model.names = Some(names),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Self::ViewModel {
+            model.names.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::Core;
+
+    use crate::{
+        app::{App, Effect, Event},
+        capability::{Found, Lookup},
+    };
+
+    #[test]
+    fn operations_are_sent_as_one_effect() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Lookup(vec![1, 2, 3]));
+        assert_eq!(effects.len(), 1);
+        let Some(Effect::Directory(mut request)) = effects.pop() else {
+            panic!("expected a Directory effect");
+        };
+        assert_eq!(
+            request.operation,
+            Lookup::Batch {
+                lookups: vec![
+                    Lookup::Name { id: 1 },
+                    Lookup::Name { id: 2 },
+                    Lookup::Name { id: 3 },
+                ]
+            }
+        );
+
+        // the shell only found two of them
+        let found = Found::Batch(vec![
+            Found::Name(Some("ferris".to_string())),
+            Found::Name(None),
+        ]);
+        assert!(core.resolve(&mut request, found).is_empty());
+
+        assert_eq!(
+            core.view(),
+            Some(vec![Some("ferris".to_string()), None, None])
+        );
+    }
+
+    #[test]
+    fn empty_batches_are_not_sent() {
+        let core: Core<Effect, App> = Core::default();
+
+        assert!(core.process_event(Event::Lookup(vec![])).is_empty());
+        assert_eq!(core.view(), Some(vec![]));
+    }
+}
@@ -0,0 +1,125 @@
+mod app {
+    use crux_core::error::CapabilityError;
+    use crux_core::macros::Effect;
+    use crux_http::{Http, HttpError, Response};
+    use crux_time::{Time, TimeResponse};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[crux_core::macros::responses(
+        fetch: Result<Response<String>, HttpError>,
+        now: TimeResponse,
+    )]
+    #[derive(Debug)]
+    pub enum Event {
+        Fetch,
+        Now,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub body: Option<String>,
+        pub now: Option<TimeResponse>,
+        pub failure: Option<(String, CapabilityError)>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub time: Time<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Fetch => caps
+                    .http
+                    .get("http://example.com")
+                    .expect_string()
+                    .send(Event::fetch_response),
+                Event::Now => caps.time.now(Event::now_response),
+
+                Event::FetchResponse(mut response) => model.body = response.take_body(),
+                Event::NowResponse(now) => model.now = Some(now),
+                Event::ResponseFailed { call, error } => model.failure = Some((call, error)),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{error::CapabilityError, testing::AppTester};
+    use crux_http::protocol::{HttpResponse, HttpResult};
+    use crux_time::{Instant, TimeResponse};
+
+    use crate::app::{App, Effect, Event, Model};
+
+    #[test]
+    fn successful_calls_send_their_output() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut request = app
+            .update(Event::Fetch, &mut model)
+            .into_effects()
+            .find_map(Effect::into_http)
+            .unwrap();
+        let update = app
+            .resolve(
+                &mut request,
+                HttpResult::Ok(HttpResponse::ok().body("hello").build()),
+            )
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(model.body.as_deref(), Some("hello"));
+
+        let mut request = app
+            .update(Event::Now, &mut model)
+            .into_effects()
+            .find_map(Effect::into_time)
+            .unwrap();
+        let now = TimeResponse::Now(Instant::new(1, 0).unwrap());
+        let update = app.resolve(&mut request, now).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(model.now, Some(now));
+    }
+
+    #[test]
+    fn failed_calls_send_one_event() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut request = app
+            .update(Event::Fetch, &mut model)
+            .into_effects()
+            .find_map(Effect::into_http)
+            .unwrap();
+        let update = app
+            .resolve(
+                &mut request,
+                HttpResult::Ok(HttpResponse::status(404).build()),
+            )
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(
+            model.failure,
+            Some(("fetch".to_string(), CapabilityError::NotFound))
+        );
+        assert_eq!(model.body, None);
+    }
+}
@@ -0,0 +1,209 @@
+mod capability {
+    use crux_core::capability::{CapabilityContext, Operation};
+    use crux_core::macros::Capability;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Lookup(pub String);
+
+    impl Operation for Lookup {
+        type Output = String;
+    }
+
+    #[derive(Capability)]
+    pub struct Directory<Ev> {
+        context: CapabilityContext<Lookup, Ev>,
+    }
+
+    impl<Ev> Directory<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<Lookup, Ev>) -> Self {
+            Self { context }
+        }
+
+        pub fn lookup<F>(&self, name: &str, callback: F)
+        where
+            F: FnOnce(String) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            let name = name.to_string();
+            self.context.spawn(async move {
+                let number = context.request_from_shell(Lookup(name)).await;
+
+                context.update_app(callback(number));
+            });
+        }
+    }
+}
+
+mod app {
+    use crux_core::macros::Effect;
+
+    use crate::capability::Directory;
+
+    #[derive(Default)]
+    pub struct App;
+
+    pub enum Event {
+        Call(String),
+        Found(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub number: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub directory: Directory<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Call(name) => caps.directory.lookup(&name, Event::Found),
+                Event::Found(number) => model.number = Some(number),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Option<String> {
+            model.number.clone()
+        }
+    }
+}
+
+mod runtime {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        task::{Context, Poll},
+    };
+
+    use crux_core::capability::RuntimeAdapter;
+    use futures::{future::BoxFuture, task::noop_waker_ref};
+
+    /// A runtime the test drives by hand, polling every task whenever it runs
+    #[derive(Clone, Default)]
+    pub struct ManualRuntime {
+        tasks: Arc<Mutex<Vec<BoxFuture<'static, ()>>>>,
+        pub spawned: Arc<AtomicUsize>,
+        pub notified: Arc<AtomicUsize>,
+    }
+
+    impl ManualRuntime {
+        pub fn run(&self) {
+            let mut context = Context::from_waker(noop_waker_ref());
+            let mut tasks = self.tasks.lock().unwrap();
+
+            tasks.retain_mut(|task| task.as_mut().poll(&mut context) == Poll::Pending);
+        }
+
+        pub fn tasks(&self) -> usize {
+            self.tasks.lock().unwrap().len()
+        }
+    }
+
+    impl RuntimeAdapter for ManualRuntime {
+        fn spawn(&self, future: BoxFuture<'static, ()>) {
+            self.spawned.fetch_add(1, Ordering::SeqCst);
+            self.tasks.lock().unwrap().push(future);
+        }
+
+        fn notify(&self) {
+            self.notified.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use crux_core::Core;
+
+    use crate::{
+        app::{App, Capabilities, Effect, Event},
+        runtime::ManualRuntime,
+    };
+
+    #[test]
+    fn tasks_run_on_the_shell_runtime() {
+        let runtime = ManualRuntime::default();
+        let core: Core<Effect, App> = Core::new_with_runtime::<Capabilities>(runtime.clone());
+
+        // the task is spawned, but only runs when the runtime runs it
+        let effects = core.process_event(Event::Call("Alice".to_string()));
+        assert!(effects.is_empty());
+        assert_eq!(runtime.spawned.load(Ordering::SeqCst), 1);
+
+        runtime.run();
+        assert_eq!(runtime.notified.load(Ordering::SeqCst), 1);
+
+        let mut effects = core.process_pending();
+        let Some(Effect::Directory(mut request)) = effects.pop() else {
+            panic!("expected a Directory effect");
+        };
+        assert_eq!(request.operation.0, "Alice");
+
+        assert!(core
+            .resolve(&mut request, "555-0100".to_string())
+            .is_empty());
+        assert_eq!(core.view(), None);
+
+        runtime.run();
+        assert_eq!(runtime.tasks(), 0);
+        assert_eq!(runtime.notified.load(Ordering::SeqCst), 2);
+
+        assert!(core.process_pending().is_empty());
+        assert_eq!(core.view(), Some("555-0100".to_string()));
+    }
+
+    #[test]
+    fn effects_requested_in_between_go_to_the_listener() {
+        let runtime = ManualRuntime::default();
+        let core: Core<Effect, App> = Core::new_with_runtime::<Capabilities>(runtime.clone());
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        core.set_effect_listener(move |effect| sender.send(effect).unwrap());
+
+        assert!(core
+            .process_event(Event::Call("Carol".to_string()))
+            .is_empty());
+        assert!(receiver.try_recv().is_err());
+
+        // the task requests the lookup outside of any call to the core
+        runtime.run();
+        let Ok(Effect::Directory(request)) = receiver.try_recv() else {
+            panic!("expected a Directory effect");
+        };
+        assert_eq!(request.operation.0, "Carol");
+        assert!(core.process_pending().is_empty());
+    }
+
+    #[test]
+    fn the_built_in_executor_runs_tasks_otherwise() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Call("Bob".to_string()));
+        let Some(Effect::Directory(mut request)) = effects.pop() else {
+            panic!("expected a Directory effect");
+        };
+
+        // effects requested while the core is processing are returned as usual
+        core.set_effect_listener(|_| panic!("unexpected effect"));
+
+        core.resolve(&mut request, "555-0199".to_string());
+        assert_eq!(core.view(), Some("555-0199".to_string()));
+
+        assert_eq!(core.process_event(Event::Call("Dan".to_string())).len(), 1);
+    }
+}
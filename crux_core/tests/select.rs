@@ -0,0 +1,211 @@
+mod capability {
+    use crux_core::capability::{CapabilityContext, Operation};
+    use crux_core::macros::Capability;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum MirrorOperation {
+        Fetch { mirror: usize },
+        Cancel { mirror: usize },
+    }
+
+    impl Operation for MirrorOperation {
+        type Output = String;
+    }
+
+    #[derive(Capability)]
+    pub struct Mirrors<Ev> {
+        context: CapabilityContext<MirrorOperation, Ev>,
+    }
+
+    impl<Ev> Clone for Mirrors<Ev> {
+        fn clone(&self) -> Self {
+            Self {
+                context: self.context.clone(),
+            }
+        }
+    }
+
+    impl<Ev> Mirrors<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<MirrorOperation, Ev>) -> Self {
+            Self { context }
+        }
+
+        /// Fetch from the mirror, asking the shell to cancel the fetch if it's no longer needed
+        pub async fn fetch_async(&self, mirror: usize) -> String {
+            let context = self.context.clone();
+
+            self.context
+                .notifying_on_cancel(
+                    async move {
+                        context
+                            .request_from_shell(MirrorOperation::Fetch { mirror })
+                            .await
+                    },
+                    MirrorOperation::Cancel { mirror },
+                )
+                .await
+        }
+    }
+}
+
+mod app {
+    use crux_core::capability::AbortHandle;
+    use crux_core::compose::Compose;
+    use crux_core::macros::Effect;
+
+    use crate::capability::Mirrors;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Fetch(Vec<usize>),
+        GiveUp,
+        Fetched { mirror: usize, body: String },
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub race: Option<AbortHandle>,
+        pub fetched: Vec<(usize, String)>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub mirrors: Mirrors<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Fetch(mirrors) => {
+                    let race = caps.compose.select(mirrors.into_iter().map(|mirror| {
+                        let mirrors = caps.mirrors.clone();
+                        async move {
+                            let body = mirrors.fetch_async(mirror).await;
+                            Event::Fetched { mirror, body }
+                        }
+                    }));
+                    model.race = Some(race);
+                }
+                Event::GiveUp => {
+                    if let Some(race) = model.race.take() {
+                        race.abort();
+                    }
+                }
+                Event::Fetched { mirror, body } => {
+                    model.race = None;
+                    model.fetched.push((mirror, body));
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+
+    use crate::{
+        app::{App, Effect, Event, Model},
+        capability::MirrorOperation,
+    };
+
+    fn operations(effects: impl Iterator<Item = Effect>) -> Vec<MirrorOperation> {
+        effects
+            .map(|effect| match effect {
+                Effect::Mirrors(request) => request.operation,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_fastest_mirror_wins_and_the_others_are_cancelled() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Fetch(vec![1, 2, 3]), &mut model);
+        let mut requests: Vec<_> = update
+            .into_effects()
+            .map(|effect| match effect {
+                Effect::Mirrors(request) => request,
+            })
+            .collect();
+        assert_eq!(
+            requests
+                .iter()
+                .map(|request| request.operation.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                MirrorOperation::Fetch { mirror: 1 },
+                MirrorOperation::Fetch { mirror: 2 },
+                MirrorOperation::Fetch { mirror: 3 },
+            ]
+        );
+
+        let update = app.resolve(&mut requests[1], "from 2".to_string()).unwrap();
+        assert_eq!(
+            update.events,
+            vec![Event::Fetched {
+                mirror: 2,
+                body: "from 2".to_string()
+            }]
+        );
+        assert_eq!(
+            operations(update.into_effects()),
+            vec![
+                MirrorOperation::Cancel { mirror: 1 },
+                MirrorOperation::Cancel { mirror: 3 },
+            ]
+        );
+
+        // the losers' late responses are ignored
+        let update = app.resolve(&mut requests[0], "from 1".to_string()).unwrap();
+        assert!(update.events.is_empty());
+        assert_eq!(update.effects().count(), 0);
+        assert_eq!(app.pending_tasks(), 0);
+    }
+
+    #[test]
+    fn aborting_the_race_cancels_every_contender() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        app.update(Event::Fetch(vec![1, 2]), &mut model);
+        let update = app.update(Event::GiveUp, &mut model);
+
+        assert_eq!(
+            operations(update.into_effects()),
+            vec![
+                MirrorOperation::Cancel { mirror: 1 },
+                MirrorOperation::Cancel { mirror: 2 },
+            ]
+        );
+        assert_eq!(app.pending_tasks(), 0);
+    }
+
+    #[test]
+    fn a_race_without_contenders_does_nothing() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Fetch(vec![]), &mut model);
+
+        assert_eq!(update.effects().count(), 0);
+        assert!(update.events.is_empty());
+        assert_eq!(app.pending_tasks(), 0);
+    }
+}
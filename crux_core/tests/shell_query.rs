@@ -0,0 +1,223 @@
+mod capability {
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, Mutex},
+    };
+
+    use crux_core::capability::{Capability, CapabilityContext, Operation, ShellQuery};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Download {
+        pub url: String,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Downloaded;
+
+    impl Operation for Download {
+        type Output = Downloaded;
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum DownloadsQuery {
+        Outstanding,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub enum DownloadsQueryResponse {
+        Outstanding(Vec<String>),
+    }
+
+    pub struct Downloads<Ev> {
+        context: CapabilityContext<Download, Ev>,
+        outstanding: Arc<Mutex<BTreeSet<String>>>,
+    }
+
+    impl<Ev> Downloads<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<Download, Ev>) -> Self {
+            Self {
+                context,
+                outstanding: Arc::default(),
+            }
+        }
+
+        pub fn download<F>(&self, url: String, callback: F)
+        where
+            F: FnOnce(String) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            let outstanding = self.outstanding.clone();
+            self.context.spawn(async move {
+                outstanding.lock().unwrap().insert(url.clone());
+                context
+                    .request_from_shell(Download { url: url.clone() })
+                    .await;
+                outstanding.lock().unwrap().remove(&url);
+
+                context.update_app(callback(url));
+            });
+        }
+    }
+
+    impl<Ev> Capability<Ev> for Downloads<Ev> {
+        type Operation = Download;
+        type MappedSelf<MappedEv> = Downloads<MappedEv>;
+
+        fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+        where
+            F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+            Ev: 'static,
+            NewEv: 'static,
+        {
+            Downloads {
+                context: self.context.map_event(f),
+                outstanding: self.outstanding.clone(),
+            }
+        }
+    }
+
+    impl<Ev> ShellQuery for Downloads<Ev> {
+        type Query = DownloadsQuery;
+        type QueryResponse = DownloadsQueryResponse;
+
+        fn query(&self, query: DownloadsQuery) -> DownloadsQueryResponse {
+            match query {
+                DownloadsQuery::Outstanding => {
+                    let outstanding = self.outstanding.lock().unwrap();
+                    DownloadsQueryResponse::Outstanding(outstanding.iter().cloned().collect())
+                }
+            }
+        }
+    }
+}
+
+mod app {
+    use crux_core::capability::ShellQuery;
+    use crux_core::macros::Effect;
+    use serde::{Deserialize, Serialize};
+
+    use crate::capability::{Downloads, DownloadsQuery, DownloadsQueryResponse};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Download(String),
+        Downloaded(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub downloaded: Vec<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub downloads: Downloads<Event>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum CapabilitiesQuery {
+        Downloads(DownloadsQuery),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub enum CapabilitiesQueryResponse {
+        Downloads(DownloadsQueryResponse),
+    }
+
+    impl ShellQuery for Capabilities {
+        type Query = CapabilitiesQuery;
+        type QueryResponse = CapabilitiesQueryResponse;
+
+        fn query(&self, query: CapabilitiesQuery) -> CapabilitiesQueryResponse {
+            match query {
+                CapabilitiesQuery::Downloads(query) => {
+                    CapabilitiesQueryResponse::Downloads(self.downloads.query(query))
+                }
+            }
+        }
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Vec<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Download(url) => caps.downloads.download(url, Event::Downloaded),
+                Event::Downloaded(url) => model.downloaded.push(url),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Vec<String> {
+            model.downloaded.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::{bridge::Bridge, testing::AppTester, Core};
+
+    use crate::{
+        app::{App, CapabilitiesQuery, CapabilitiesQueryResponse, Effect, Event, Model},
+        capability::{Downloaded, DownloadsQuery, DownloadsQueryResponse},
+    };
+
+    fn outstanding(urls: &[&str]) -> CapabilitiesQueryResponse {
+        CapabilitiesQueryResponse::Downloads(DownloadsQueryResponse::Outstanding(
+            urls.iter().map(|url| url.to_string()).collect(),
+        ))
+    }
+
+    const QUERY: CapabilitiesQuery = CapabilitiesQuery::Downloads(DownloadsQuery::Outstanding);
+
+    #[test]
+    fn core_answers_capability_queries() {
+        let core: Core<Effect, App> = Core::default();
+        assert_eq!(core.query_capabilities(QUERY), outstanding(&[]));
+
+        let mut a = core.process_event(Event::Download("a".to_string()));
+        core.process_event(Event::Download("b".to_string()));
+        assert_eq!(core.query_capabilities(QUERY), outstanding(&["a", "b"]));
+
+        let Some(Effect::Downloads(mut request)) = a.pop() else {
+            panic!("expected a Downloads effect");
+        };
+        core.resolve(&mut request, Downloaded);
+
+        assert_eq!(core.query_capabilities(QUERY), outstanding(&["b"]));
+        assert_eq!(core.view(), vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn bridge_answers_serialized_capability_queries() {
+        let bridge = Bridge::<Effect, App>::new(Core::default());
+
+        let event = bincode::serialize(&Event::Download("a".to_string())).unwrap();
+        bridge.process_event(&event);
+
+        let query = bincode::serialize(&QUERY).unwrap();
+        let response: CapabilitiesQueryResponse =
+            bincode::deserialize(&bridge.query_capabilities(&query)).unwrap();
+
+        assert_eq!(response, outstanding(&["a"]));
+    }
+
+    #[test]
+    fn app_tester_answers_capability_queries() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(Event::Download("a".to_string()), &mut model);
+
+        assert_eq!(app.query_capabilities(QUERY), outstanding(&["a"]));
+    }
+}
@@ -0,0 +1,156 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_http::{Http, Response};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Send(String),
+        Refresh,
+        #[serde(skip)]
+        Sent(crux_http::Result<Response<Vec<u8>>>),
+        #[serde(skip)]
+        Refreshed(crux_http::Result<Response<Vec<u8>>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub outbox: Vec<String>,
+        pub flushed: bool,
+        pub refreshed: bool,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Send(message) => model.outbox.push(message),
+                Event::Refresh => caps
+                    .http
+                    .get("http://example.com/inbox")
+                    .send(Event::Refreshed),
+                Event::Sent(Ok(_)) => {
+                    model.outbox.clear();
+                    model.flushed = true;
+                }
+                Event::Refreshed(response) => model.refreshed = response.is_ok(),
+                Event::Sent(Err(_)) => {}
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+
+        fn will_terminate(&self, model: &mut Model, caps: &Capabilities) {
+            if model.outbox.is_empty() {
+                return;
+            }
+
+            caps.http
+                .post("http://example.com/outbox")
+                .body_json(&model.outbox)
+                .expect("outbox should serialize")
+                .send(Event::Sent);
+        }
+    }
+}
+
+mod tests {
+    use std::time::Duration;
+
+    use crux_core::{bridge::Bridge, testing::AppTester, Core, ShutdownStatus};
+    use crux_http::protocol::{HttpResponse, HttpResult};
+
+    use crate::app::{App, Effect, Event, Model};
+
+    const GRACE: Duration = Duration::from_secs(60);
+
+    fn ok() -> HttpResult {
+        HttpResult::Ok(HttpResponse::ok().build())
+    }
+
+    #[test]
+    fn shutdown_waits_for_the_work_started_while_terminating() {
+        let core: Core<Effect, App> = Core::default();
+        core.process_event(Event::Send("hello".to_string()));
+        // a request started before shutting down isn't waited for
+        let refresh = core.process_event(Event::Refresh);
+        assert_eq!(refresh.len(), 1);
+        assert_eq!(core.shutdown_status(), ShutdownStatus::Running);
+
+        let Some(Effect::Http(mut request)) = core.shutdown(GRACE).pop() else {
+            panic!("expected the outbox to be flushed");
+        };
+        assert_eq!(request.operation.url, "http://example.com/outbox");
+        assert_eq!(
+            core.shutdown_status(),
+            ShutdownStatus::Draining { outstanding: 1 }
+        );
+
+        // shutting down again doesn't terminate the app twice
+        assert!(core.shutdown(GRACE).is_empty());
+
+        core.resolve(&mut request, ok());
+        assert_eq!(core.shutdown_status(), ShutdownStatus::Complete);
+        assert!(core.shutdown_status().is_done());
+    }
+
+    #[test]
+    fn shutdown_is_bounded_by_the_grace_period() {
+        let core: Core<Effect, App> = Core::default();
+        core.process_event(Event::Send("hello".to_string()));
+
+        let effects = core.shutdown(Duration::ZERO);
+        assert_eq!(effects.len(), 1);
+
+        assert_eq!(
+            core.shutdown_status(),
+            ShutdownStatus::TimedOut { outstanding: 1 }
+        );
+    }
+
+    #[test]
+    fn shutdown_without_work_completes_straight_away() {
+        let bridge = Bridge::<Effect, App>::new(Core::default());
+
+        let requests: Vec<crux_core::bridge::Request<crate::app::EffectFfi>> =
+            bincode::deserialize(&bridge.shutdown(GRACE)).unwrap();
+        assert!(requests.is_empty());
+
+        let status: ShutdownStatus = bincode::deserialize(&bridge.shutdown_status()).unwrap();
+        assert_eq!(status, ShutdownStatus::Complete);
+    }
+
+    #[test]
+    fn app_tester_runs_will_terminate() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model {
+            outbox: vec!["hello".to_string()],
+            ..Default::default()
+        };
+
+        let mut request = app
+            .will_terminate(&mut model)
+            .into_effects()
+            .find_map(Effect::into_http)
+            .unwrap();
+        let update = app.resolve(&mut request, ok()).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert!(model.flushed);
+        assert!(model.outbox.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+mod capability {
+    use crux_core::capability::{CapabilityContext, Operation};
+    use crux_core::macros::Capability;
+    use futures::StreamExt;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum TickOperation {
+        Start { id: u32 },
+        Stop { id: u32 },
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Tick {
+        Tick,
+        Stopped,
+    }
+
+    impl Operation for TickOperation {
+        type Output = Tick;
+    }
+
+    #[derive(Capability)]
+    pub struct Ticker<Ev> {
+        context: CapabilityContext<TickOperation, Ev>,
+    }
+
+    impl<Ev> Ticker<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<TickOperation, Ev>) -> Self {
+            Self { context }
+        }
+
+        pub fn start<F>(&self, id: u32, callback: F)
+        where
+            F: Fn() -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            self.context.spawn(async move {
+                let mut ticks = context.stream_from_shell(TickOperation::Start { id });
+
+                while let Some(Tick::Tick) = ticks.next().await {
+                    context.update_app(callback());
+                }
+            });
+        }
+
+        pub fn stop(&self, id: u32) {
+            let context = self.context.clone();
+            self.context.spawn(async move {
+                context.notify_shell(TickOperation::Stop { id }).await;
+            });
+        }
+    }
+}
+
+mod app {
+    use crux_core::macros::Effect;
+
+    use crate::capability::Ticker;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Event {
+        Start,
+        Stop,
+        Ticked,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        /// whether starting again while running is ignored, rather than leaking
+        /// the running subscription
+        pub careful: bool,
+        pub running: Option<u32>,
+        pub next_id: u32,
+        pub ticks: usize,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub ticker: Ticker<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Start => {
+                    if model.careful && model.running.is_some() {
+                        return;
+                    }
+
+                    model.next_id += 1;
+                    model.running = Some(model.next_id);
+                    caps.ticker.start(model.next_id, || Event::Ticked);
+                }
+                Event::Stop => {
+                    if let Some(id) = model.running.take() {
+                        caps.ticker.stop(id);
+                    }
+                }
+                Event::Ticked => model.ticks += 1,
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use std::collections::HashSet;
+
+    use crux_core::testing::{AppTester, Soak, SoakReport, SoakRng, SoakShell};
+
+    use crate::{
+        app::{App, Effect, Event, Model},
+        capability::{Tick, TickOperation},
+    };
+
+    fn soak(careful: bool, seed: u64) -> (SoakReport, Model) {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model {
+            careful,
+            ..Model::default()
+        };
+
+        let events = [Event::Start, Event::Stop];
+        let random_event = |rng: &mut SoakRng, _: &Model| *rng.pick(&events).unwrap();
+
+        let mut stopped = HashSet::new();
+        let shell = |shell: &mut SoakShell<'_, App, Effect>, effect: &mut Effect| {
+            let Effect::Ticker(request) = effect;
+
+            match request.operation {
+                TickOperation::Stop { id } => {
+                    stopped.insert(id);
+                    true
+                }
+                TickOperation::Start { id } if stopped.contains(&id) => {
+                    shell.resolve(request, Tick::Stopped);
+                    true
+                }
+                // only the app can stop the ticks
+                TickOperation::Start { .. } if shell.is_idle() => false,
+                TickOperation::Start { .. } => {
+                    shell.resolve(request, Tick::Tick);
+                    false
+                }
+            }
+        };
+
+        let report = Soak::<App>::new(seed)
+            .steps(500)
+            .wind_down(vec![Event::Stop])
+            .run(&app, &mut model, random_event, shell);
+
+        (report, model)
+    }
+
+    #[test]
+    fn a_well_behaved_app_winds_down() {
+        for seed in 0..10 {
+            let (report, model) = soak(true, seed);
+
+            report.assert_idle();
+            assert!(report.events > 0);
+            assert!(model.ticks > 0);
+        }
+    }
+
+    #[test]
+    fn leaked_subscriptions_are_reported() {
+        let (report, _) = soak(false, 1);
+
+        assert!(!report.is_idle());
+        assert!(report.unresolved_requests > 0);
+        assert_eq!(report.pending_tasks, report.unresolved_requests);
+        assert_eq!(report.queued_events, 0);
+        assert!(report.to_string().starts_with("soak test with seed 1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "unresolved requests")]
+    fn asserting_on_a_leak_panics() {
+        soak(false, 2).0.assert_idle();
+    }
+}
@@ -0,0 +1,132 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        AddItem(String),
+        RemoveItem(String),
+        Checkout,
+        Paid { reference: String },
+    }
+
+    #[derive(Debug, Default)]
+    pub enum Page {
+        #[default]
+        Basket,
+        Payment,
+        Confirmation,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub page: Page,
+        pub items: Vec<String>,
+        pub reference: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub render: Render<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::AddItem(item) => model.items.push(item),
+                Event::RemoveItem(item) => model.items.retain(|i| *i != item),
+                Event::Checkout if !model.items.is_empty() => model.page = Page::Payment,
+                Event::Checkout => {}
+                Event::Paid { reference } => {
+                    model.page = Page::Confirmation;
+                    model.reference = Some(reference);
+                }
+            }
+
+            caps.render.render();
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::{AppTester, Transition};
+
+    use crate::app::{App, Effect, Event, Model};
+
+    fn transition(from: &str, event: &str, to: &str, count: usize) -> Transition {
+        Transition {
+            from: from.to_string(),
+            event: event.to_string(),
+            to: to.to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn records_the_transitions_between_pages() {
+        let app = AppTester::<App, Effect>::default()
+            .with_state_chart(|model: &Model| format!("{:?}", model.page));
+        let mut model = Model::default();
+
+        app.update(Event::Checkout, &mut model);
+        app.update(Event::AddItem("tea".to_string()), &mut model);
+        app.update(Event::AddItem("milk".to_string()), &mut model);
+        app.update(Event::RemoveItem("milk".to_string()), &mut model);
+        app.update(Event::Checkout, &mut model);
+        app.update(
+            Event::Paid {
+                reference: "A1".to_string(),
+            },
+            &mut model,
+        );
+
+        let chart = app.state_chart().unwrap();
+        assert_eq!(chart.initial(), Some("Basket"));
+        assert_eq!(
+            chart.transitions().collect::<Vec<_>>(),
+            vec![
+                transition("Basket", "AddItem", "Basket", 2),
+                transition("Basket", "Checkout", "Basket", 1),
+                transition("Basket", "Checkout", "Payment", 1),
+                transition("Basket", "RemoveItem", "Basket", 1),
+                transition("Payment", "Paid", "Confirmation", 1),
+            ]
+        );
+
+        assert_eq!(
+            chart.to_mermaid(),
+            [
+                "stateDiagram-v2",
+                "    s0 : Basket",
+                "    s1 : Confirmation",
+                "    s2 : Payment",
+                "    [*] --> s0",
+                "    s0 --> s0 : AddItem (2)",
+                "    s0 --> s0 : Checkout (1)",
+                "    s0 --> s2 : Checkout (1)",
+                "    s0 --> s0 : RemoveItem (1)",
+                "    s2 --> s1 : Paid (1)",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn no_chart_is_recorded_unless_asked_for() {
+        let app = AppTester::<App, Effect>::default();
+        let mut model = Model::default();
+
+        app.update(Event::Checkout, &mut model);
+        assert!(app.state_chart().is_none());
+    }
+}
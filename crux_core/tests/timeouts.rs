@@ -0,0 +1,196 @@
+mod capability {
+    use crux_core::capability::{CapabilityContext, Operation, ShellTimeout, TimesOut};
+    use crux_core::macros::Capability;
+    use futures::StreamExt;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Ping;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Pong;
+
+    impl Operation for Ping {
+        type Output = Result<Pong, ShellTimeout>;
+    }
+
+    impl TimesOut for Ping {
+        fn timed_out(timeout: ShellTimeout) -> Self::Output {
+            Err(timeout)
+        }
+    }
+
+    #[derive(Capability)]
+    pub struct Pinger<Ev> {
+        context: CapabilityContext<Ping, Ev>,
+    }
+
+    impl<Ev> Pinger<Ev>
+    where
+        Ev: 'static,
+    {
+        pub fn new(context: CapabilityContext<Ping, Ev>) -> Self {
+            Self { context }
+        }
+
+        pub fn ping<F>(&self, callback: F)
+        where
+            F: FnOnce(Result<Pong, ShellTimeout>) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            self.context.spawn(async move {
+                let pong = context.request_from_shell(Ping).await;
+
+                context.update_app(callback(pong));
+            });
+        }
+
+        pub fn listen<F>(&self, callback: F)
+        where
+            F: Fn(Result<Pong, ShellTimeout>) -> Ev + Send + 'static,
+        {
+            let context = self.context.clone();
+            self.context.spawn(async move {
+                let mut pongs = context.stream_from_shell(Ping);
+
+                while let Some(pong) = pongs.next().await {
+                    context.update_app(callback(pong));
+                }
+            });
+        }
+    }
+}
+
+mod app {
+    use crux_core::capability::ShellTimeout;
+    use crux_core::macros::Effect;
+
+    use crate::capability::{Pinger, Pong};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Ping,
+        Listen,
+        Pong(Result<Pong, ShellTimeout>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub outcome: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        // times out on the next check
+        #[effect(timeout_ms = 0)]
+        pub pinger: Pinger<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Ping => caps.pinger.ping(Event::Pong),
+                Event::Listen => caps.pinger.listen(Event::Pong),
+                Event::Pong(Ok(Pong)) => model.outcome = Some("pong".to_string()),
+                Event::Pong(Err(error)) => model.outcome = Some(error.to_string()),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Option<String> {
+            model.outcome.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::Core;
+
+    use crate::{
+        app::{App, Effect, Event},
+        capability::Pong,
+    };
+
+    #[test]
+    fn unresolved_requests_time_out() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Ping);
+        let Some(Effect::Pinger(mut request)) = effects.pop() else {
+            panic!("expected a Pinger effect");
+        };
+        assert_eq!(core.view(), None);
+
+        assert!(core.check_timeouts().is_empty());
+        assert_eq!(
+            core.view().as_deref(),
+            Some("the shell did not resolve the request within 0ns")
+        );
+
+        // resolving after the timeout has no effect
+        assert!(core.resolve(&mut request, Ok(Pong)).is_empty());
+        assert_eq!(
+            core.view().as_deref(),
+            Some("the shell did not resolve the request within 0ns")
+        );
+    }
+
+    #[test]
+    fn requests_resolved_in_time_succeed() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Ping);
+        let Some(Effect::Pinger(mut request)) = effects.pop() else {
+            panic!("expected a Pinger effect");
+        };
+
+        core.resolve(&mut request, Ok(Pong));
+        assert_eq!(core.view().as_deref(), Some("pong"));
+
+        core.check_timeouts();
+        assert_eq!(core.view().as_deref(), Some("pong"));
+    }
+
+    #[test]
+    fn unanswered_streams_time_out() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Listen);
+        let Some(Effect::Pinger(_request)) = effects.pop() else {
+            panic!("expected a Pinger effect");
+        };
+
+        assert!(core.check_timeouts().is_empty());
+        assert_eq!(
+            core.view().as_deref(),
+            Some("the shell did not resolve the request within 0ns")
+        );
+    }
+
+    #[test]
+    fn answered_streams_do_not_time_out() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Listen);
+        let Some(Effect::Pinger(mut request)) = effects.pop() else {
+            panic!("expected a Pinger effect");
+        };
+
+        core.resolve(&mut request, Ok(Pong));
+        assert_eq!(core.view().as_deref(), Some("pong"));
+
+        core.check_timeouts();
+        assert_eq!(core.view().as_deref(), Some("pong"));
+
+        // the stream is still open
+        core.resolve(&mut request, Ok(Pong));
+        assert_eq!(core.view().as_deref(), Some("pong"));
+    }
+}
@@ -90,4 +90,35 @@ mod test {
         assert!(registry.contains_key("Effect"));
         assert!(registry.contains_key("RenderOperation"));
     }
+
+    #[test]
+    fn test_a11y_types() {
+        use crux_core::a11y::{A11y, A11yRole, LiveRegion};
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Row {
+            title: String,
+            a11y: A11y,
+        }
+
+        let mut gen = TypeGen::new();
+        gen.register_type::<A11yRole>().unwrap();
+        gen.register_type::<LiveRegion>().unwrap();
+        gen.register_type::<Row>().unwrap();
+
+        let registry = match gen.state {
+            crux_core::typegen::State::Registering(tracer, _) => {
+                tracer.registry().expect("Should get registry")
+            }
+            crux_core::typegen::State::Generating(_) => {
+                panic!("Expected to still be in registering stage")
+            }
+        };
+
+        for name in ["A11y", "A11yLabel", "A11yHint", "A11yRole", "LiveRegion"] {
+            assert!(registry.contains_key(name), "{name} not registered");
+        }
+    }
 }
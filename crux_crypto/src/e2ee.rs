@@ -0,0 +1,249 @@
+//! End-to-end encryption primitives.
+//!
+//! Keys are X25519 key pairs, and messages are encrypted with XChaCha20-Poly1305, using a key
+//! derived (with BLAKE3) from the X25519 shared secret of the sender and the recipient.
+//!
+//! * [`seal`] encrypts a message for a recipient anonymously, using a fresh ephemeral key pair,
+//!   so that only the recipient can [`open`] it, but the recipient can't tell who sent it.
+//! * [`encrypt`] and [`decrypt`] authenticate the sender as well, using the sender's key pair.
+//!
+//! Secret keys can be exported with [`SecretKey::to_bytes`] to be persisted. They should only be
+//! stored in the platform's secure storage (e.g. the iOS Keychain or the Android Keystore),
+//! never in plain app storage.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+const KEY_DERIVATION_CONTEXT: &str = "crux_crypto e2ee 2024-06 message key";
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Error type for encryption operations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum CryptoError {
+    #[error("message is too short to be a ciphertext")]
+    Truncated,
+    #[error("message could not be decrypted")]
+    Decryption,
+    #[error("public key is a low order point")]
+    WeakKey,
+    #[error("invalid key length {length}, expected 32")]
+    InvalidKey { length: usize },
+}
+
+/// An X25519 public key, which can be shared with anyone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PublicKey(pub [u8; KEY_LEN]);
+
+/// An X25519 secret key. The key material is zeroed when dropped.
+#[derive(Clone)]
+pub struct SecretKey(x25519_dalek::StaticSecret);
+
+impl SecretKey {
+    /// Generate a new secret key from the operating system's random number generator
+    pub fn generate() -> Self {
+        Self(x25519_dalek::StaticSecret::random_from_rng(OsRng))
+    }
+
+    /// Restore a secret key exported with [`SecretKey::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let bytes: [u8; KEY_LEN] = bytes.try_into().map_err(|_| CryptoError::InvalidKey {
+            length: bytes.len(),
+        })?;
+
+        Ok(Self(bytes.into()))
+    }
+
+    /// Export the secret key, to be kept in secure storage
+    pub fn to_bytes(&self) -> Zeroizing<[u8; KEY_LEN]> {
+        Zeroizing::new(self.0.to_bytes())
+    }
+
+    /// The public key corresponding to this secret key
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(x25519_dalek::PublicKey::from(&self.0).to_bytes())
+    }
+
+    fn message_key(
+        &self,
+        public: &PublicKey,
+        context: &[&PublicKey],
+    ) -> Result<Zeroizing<[u8; KEY_LEN]>, CryptoError> {
+        let shared = self
+            .0
+            .diffie_hellman(&x25519_dalek::PublicKey::from(public.0));
+        if !shared.was_contributory() {
+            return Err(CryptoError::WeakKey);
+        }
+
+        let mut material = Zeroizing::new(shared.as_bytes().to_vec());
+        for key in context {
+            material.extend_from_slice(&key.0);
+        }
+
+        Ok(Zeroizing::new(blake3::derive_key(
+            KEY_DERIVATION_CONTEXT,
+            &material,
+        )))
+    }
+}
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SecretKey")
+            .field(&self.public_key())
+            .finish()
+    }
+}
+
+/// Encrypt `plaintext` anonymously for `recipient`. The result can only be opened with the
+/// recipient's secret key.
+pub fn seal(recipient: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let ephemeral = SecretKey::generate();
+    let ephemeral_public = ephemeral.public_key();
+    let key = ephemeral.message_key(recipient, &[&ephemeral_public, recipient])?;
+
+    let mut sealed = ephemeral_public.0.to_vec();
+    sealed.extend(encrypt_with_key(&key, plaintext));
+
+    Ok(sealed)
+}
+
+/// Open a message [`seal`]ed for the owner of `secret`.
+pub fn open(secret: &SecretKey, sealed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if sealed.len() < KEY_LEN {
+        return Err(CryptoError::Truncated);
+    }
+    let (ephemeral_public, ciphertext) = sealed.split_at(KEY_LEN);
+    let ephemeral_public = PublicKey(ephemeral_public.try_into().expect("split at 32 bytes"));
+
+    let key = secret.message_key(
+        &ephemeral_public,
+        &[&ephemeral_public, &secret.public_key()],
+    )?;
+
+    decrypt_with_key(&key, ciphertext)
+}
+
+/// Encrypt `plaintext` from the owner of `sender` to `recipient`. Only the recipient can
+/// decrypt it, and only with the sender's public key.
+pub fn encrypt(
+    sender: &SecretKey,
+    recipient: &PublicKey,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let key = sender.message_key(recipient, &[&sender.public_key(), recipient])?;
+
+    Ok(encrypt_with_key(&key, plaintext))
+}
+
+/// Decrypt a message [`encrypt`]ed by the owner of `sender` for the owner of `recipient`.
+pub fn decrypt(
+    recipient: &SecretKey,
+    sender: &PublicKey,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let key = recipient.message_key(sender, &[sender, &recipient.public_key()])?;
+
+    decrypt_with_key(&key, ciphertext)
+}
+
+/// Encrypt with XChaCha20-Poly1305, prepending a random nonce.
+fn encrypt_with_key(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Vec<u8> {
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .expect("encryption with a valid key and nonce does not fail");
+
+    let mut message = nonce.to_vec();
+    message.extend(ciphertext);
+    message
+}
+
+fn decrypt_with_key(key: &[u8; KEY_LEN], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if message.len() < NONCE_LEN {
+        return Err(CryptoError::Truncated);
+    }
+    let (nonce, ciphertext) = message.split_at(NONCE_LEN);
+
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CryptoError::Decryption)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_box_round_trip() {
+        let recipient = SecretKey::generate();
+
+        let sealed = seal(&recipient.public_key(), b"hello").unwrap();
+        assert_eq!(open(&recipient, &sealed).unwrap(), b"hello");
+
+        let someone_else = SecretKey::generate();
+        assert_eq!(open(&someone_else, &sealed), Err(CryptoError::Decryption));
+    }
+
+    #[test]
+    fn authenticated_round_trip() {
+        let alice = SecretKey::generate();
+        let bob = SecretKey::generate();
+
+        let ciphertext = encrypt(&alice, &bob.public_key(), b"hi bob").unwrap();
+        assert_eq!(
+            decrypt(&bob, &alice.public_key(), &ciphertext).unwrap(),
+            b"hi bob"
+        );
+
+        let mallory = SecretKey::generate();
+        assert_eq!(
+            decrypt(&bob, &mallory.public_key(), &ciphertext),
+            Err(CryptoError::Decryption)
+        );
+    }
+
+    #[test]
+    fn detects_tampering_and_truncation() {
+        let recipient = SecretKey::generate();
+        let mut sealed = seal(&recipient.public_key(), b"hello").unwrap();
+
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert_eq!(open(&recipient, &sealed), Err(CryptoError::Decryption));
+
+        assert_eq!(open(&recipient, &sealed[..40]), Err(CryptoError::Truncated));
+    }
+
+    #[test]
+    fn rejects_low_order_public_keys() {
+        let secret = SecretKey::generate();
+
+        assert_eq!(
+            encrypt(&secret, &PublicKey([0; KEY_LEN]), b"hello"),
+            Err(CryptoError::WeakKey)
+        );
+    }
+
+    #[test]
+    fn secret_key_export_round_trip() {
+        let secret = SecretKey::generate();
+        let restored = SecretKey::from_bytes(&*secret.to_bytes()).unwrap();
+
+        assert_eq!(restored.public_key(), secret.public_key());
+        assert!(matches!(
+            SecretKey::from_bytes(&[0; 16]),
+            Err(CryptoError::InvalidKey { length: 16 })
+        ));
+    }
+}
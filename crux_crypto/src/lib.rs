@@ -0,0 +1,335 @@
+//! Hashing and message authentication for Crux apps
+//!
+//! The [`Crypto`] capability computes content hashes (SHA-256 and BLAKE3) and HMACs. The work is
+//! done in the core, not the shell, so all platforms share the same implementation, but it is
+//! modelled as a capability so that its results can be delivered as events and used in effect
+//! chains orchestrated with [`crux_core::compose::Compose`].
+//!
+//! The [`e2ee`] module adds end-to-end encryption (X25519 key agreement and XChaCha20-Poly1305),
+//! also available through the capability.
+//!
+//! `Crypto` never requests anything from the shell, so it should be marked `#[effect(skip)]`
+//! in the app's `Capabilities`.
+
+pub mod e2ee;
+
+use std::fmt;
+
+use crux_core::capability::{CapabilityContext, Never};
+use crux_core::Capability;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use subtle::ConstantTimeEq;
+
+use e2ee::{CryptoError, PublicKey, SecretKey};
+
+/// Supported hash algorithms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+/// A 256 bit hash or message authentication code
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    /// The digest as a lowercase hex string
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Compare with `other` in constant time
+    pub fn verify(&self, other: &[u8]) -> bool {
+        constant_time_eq(&self.0, other)
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({})", self.to_hex())
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Compare two byte strings in time which depends only on their lengths, not their contents,
+/// e.g. to check a message authentication code without leaking how much of it was correct.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Hash `data` with the given algorithm
+pub fn hash(algorithm: HashAlgorithm, data: &[u8]) -> Digest {
+    match algorithm {
+        HashAlgorithm::Sha256 => Digest(Sha256::digest(data).into()),
+        HashAlgorithm::Blake3 => Digest(*blake3::hash(data).as_bytes()),
+    }
+}
+
+/// Compute HMAC-SHA256 of `data` with `key`
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Digest {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+
+    Digest(mac.finalize().into_bytes().into())
+}
+
+/// The Crypto capability API
+pub struct Crypto<Ev> {
+    context: CapabilityContext<Never, Ev>,
+}
+
+impl<Ev> Clone for Crypto<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Crypto<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<Never, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Hash `data` with `algorithm`, passing the [`Digest`] to the app wrapped in the event
+    /// produced by `make_event`.
+    pub fn hash<F>(&self, algorithm: HashAlgorithm, data: Vec<u8>, make_event: F)
+    where
+        F: FnOnce(Digest) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            context.update_app(make_event(this.hash_async(algorithm, data).await));
+        });
+    }
+
+    /// Hash `data` with `algorithm`, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn hash_async(&self, algorithm: HashAlgorithm, data: Vec<u8>) -> Digest {
+        hash(algorithm, &data)
+    }
+
+    /// Compute HMAC-SHA256 of `data` with `key`, passing the [`Digest`] to the app wrapped
+    /// in the event produced by `make_event`.
+    pub fn hmac<F>(&self, key: Vec<u8>, data: Vec<u8>, make_event: F)
+    where
+        F: FnOnce(Digest) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            context.update_app(make_event(this.hmac_async(key, data).await));
+        });
+    }
+
+    /// Compute HMAC-SHA256 of `data` with `key`, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn hmac_async(&self, key: Vec<u8>, data: Vec<u8>) -> Digest {
+        hmac_sha256(&key, &data)
+    }
+
+    /// Check that `tag` is the HMAC-SHA256 of `data` with `key`, comparing in constant time.
+    /// The result is passed to the app wrapped in the event produced by `make_event`.
+    pub fn verify_hmac<F>(&self, key: Vec<u8>, data: Vec<u8>, tag: Vec<u8>, make_event: F)
+    where
+        F: FnOnce(bool) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            context.update_app(make_event(this.verify_hmac_async(key, data, tag).await));
+        });
+    }
+
+    /// Check that `tag` is the HMAC-SHA256 of `data` with `key`, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn verify_hmac_async(&self, key: Vec<u8>, data: Vec<u8>, tag: Vec<u8>) -> bool {
+        hmac_sha256(&key, &data).verify(&tag)
+    }
+
+    /// Encrypt `plaintext` anonymously for `recipient` (see [`e2ee::seal`]), passing the
+    /// result to the app wrapped in the event produced by `make_event`.
+    pub fn seal<F>(&self, recipient: PublicKey, plaintext: Vec<u8>, make_event: F)
+    where
+        F: FnOnce(Result<Vec<u8>, CryptoError>) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            context.update_app(make_event(this.seal_async(recipient, plaintext).await));
+        });
+    }
+
+    /// Encrypt `plaintext` anonymously for `recipient`, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn seal_async(
+        &self,
+        recipient: PublicKey,
+        plaintext: Vec<u8>,
+    ) -> Result<Vec<u8>, CryptoError> {
+        e2ee::seal(&recipient, &plaintext)
+    }
+
+    /// Open a message sealed for the owner of `secret` (see [`e2ee::open`]), passing the
+    /// result to the app wrapped in the event produced by `make_event`.
+    pub fn open<F>(&self, secret: SecretKey, sealed: Vec<u8>, make_event: F)
+    where
+        F: FnOnce(Result<Vec<u8>, CryptoError>) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            context.update_app(make_event(this.open_async(secret, sealed).await));
+        });
+    }
+
+    /// Open a message sealed for the owner of `secret`, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn open_async(
+        &self,
+        secret: SecretKey,
+        sealed: Vec<u8>,
+    ) -> Result<Vec<u8>, CryptoError> {
+        e2ee::open(&secret, &sealed)
+    }
+
+    /// Encrypt `plaintext` from the owner of `sender` to `recipient` (see [`e2ee::encrypt`]),
+    /// passing the result to the app wrapped in the event produced by `make_event`.
+    pub fn encrypt<F>(
+        &self,
+        sender: SecretKey,
+        recipient: PublicKey,
+        plaintext: Vec<u8>,
+        make_event: F,
+    ) where
+        F: FnOnce(Result<Vec<u8>, CryptoError>) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            let result = this.encrypt_async(sender, recipient, plaintext).await;
+            context.update_app(make_event(result));
+        });
+    }
+
+    /// Encrypt `plaintext` from the owner of `sender` to `recipient`, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn encrypt_async(
+        &self,
+        sender: SecretKey,
+        recipient: PublicKey,
+        plaintext: Vec<u8>,
+    ) -> Result<Vec<u8>, CryptoError> {
+        e2ee::encrypt(&sender, &recipient, &plaintext)
+    }
+
+    /// Decrypt a message encrypted by the owner of `sender` for the owner of `recipient`
+    /// (see [`e2ee::decrypt`]), passing the result to the app wrapped in the event produced
+    /// by `make_event`.
+    pub fn decrypt<F>(
+        &self,
+        recipient: SecretKey,
+        sender: PublicKey,
+        ciphertext: Vec<u8>,
+        make_event: F,
+    ) where
+        F: FnOnce(Result<Vec<u8>, CryptoError>) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            let result = this.decrypt_async(recipient, sender, ciphertext).await;
+            context.update_app(make_event(result));
+        });
+    }
+
+    /// Decrypt a message encrypted by the owner of `sender` for the owner of `recipient`,
+    /// while in an async context. This is used together with [`crux_core::compose::Compose`].
+    pub async fn decrypt_async(
+        &self,
+        recipient: SecretKey,
+        sender: PublicKey,
+        ciphertext: Vec<u8>,
+    ) -> Result<Vec<u8>, CryptoError> {
+        e2ee::decrypt(&recipient, &sender, &ciphertext)
+    }
+}
+
+impl<Ev> Capability<Ev> for Crypto<Ev> {
+    type Operation = Never;
+    type MappedSelf<MappedEv> = Crypto<MappedEv>;
+
+    fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+    where
+        F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+        NewEv: 'static,
+    {
+        Crypto::new(self.context.map_event(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_known_answer() {
+        assert_eq!(
+            hash(HashAlgorithm::Sha256, b"abc").to_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn blake3_known_answer() {
+        assert_eq!(
+            hash(HashAlgorithm::Blake3, b"").to_hex(),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn hmac_sha256_known_answer() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?").to_hex(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret!"));
+    }
+
+    #[test]
+    fn digest_serializes_as_bytes() {
+        let digest = hash(HashAlgorithm::Blake3, b"crux");
+        let json = serde_json::to_string(&digest).unwrap();
+
+        assert_eq!(serde_json::from_str::<Digest>(&json).unwrap(), digest);
+    }
+}
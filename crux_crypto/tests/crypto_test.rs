@@ -0,0 +1,105 @@
+mod shared {
+    use crux_core::compose::Compose;
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_crypto::{Crypto, Digest, HashAlgorithm};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Hash(String),
+        Sign(String),
+        Hashed(Digest),
+        Verified(bool),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub hash: String,
+        pub verified: Option<bool>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Hash(text) => {
+                    caps.crypto
+                        .hash(HashAlgorithm::Sha256, text.into_bytes(), Event::Hashed);
+                }
+                Event::Sign(text) => caps.compose.spawn(|ctx| {
+                    let crypto = caps.crypto.clone();
+
+                    async move {
+                        let key = b"key".to_vec();
+                        let data = text.into_bytes();
+                        let tag = crypto.hmac_async(key.clone(), data.clone()).await;
+                        let verified = crypto.verify_hmac_async(key, data, tag.0.to_vec()).await;
+
+                        ctx.update_app(Event::Verified(verified));
+                    }
+                }),
+                Event::Hashed(digest) => {
+                    model.hash = digest.to_hex();
+                    caps.render.render();
+                }
+                Event::Verified(verified) => model.verified = Some(verified),
+            }
+        }
+
+        fn view(&self, _model: &Self::Model) -> Self::ViewModel {}
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        #[effect(skip)]
+        pub crypto: Crypto<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+
+    use crate::shared::{App, Event, Model};
+
+    #[test]
+    fn hashes_in_the_core() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Hash("abc".to_string()), &mut model);
+        assert_eq!(update.effects.len(), 0);
+
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(
+            model.hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn chains_with_compose() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Sign("hello".to_string()), &mut model);
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(model.verified, Some(true));
+    }
+}
@@ -0,0 +1,357 @@
+//! Reporting errors to the shell's crash reporter
+//!
+//! The [`ErrorReport`] capability sends handled errors and panics caught by the core, with
+//! context and the latest log messages as breadcrumbs, to the shell, which passes them on to
+//! the platform's crash reporting SDK. Building the reports in the core keeps the telemetry
+//! the same on every platform.
+//!
+//! Reports are sampled and limited in the core (see [`ReportPolicy`]), so that an error in
+//! a loop can't flood the crash reporter. Reporting is fire and forget: the shell doesn't
+//! respond, and the app isn't sent an event.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use crux_core::capability::{Capability, CapabilityContext, Operation};
+use crux_log::{Breadcrumbs, LogOperation};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportKind {
+    /// An error the app handled, e.g. a failed request it recovered from
+    Error,
+    /// A panic which was caught, e.g. with `std::panic::catch_unwind`
+    Panic,
+}
+
+/// A report for the shell to send to its crash reporter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorReportOperation {
+    pub kind: ReportKind,
+    pub message: String,
+    /// Key-value pairs describing what the app was doing, e.g. the current screen
+    pub context: BTreeMap<String, String>,
+    /// The latest messages written to the log, oldest first
+    pub breadcrumbs: Vec<LogOperation>,
+}
+
+impl Operation for ErrorReportOperation {
+    type Output = ();
+}
+
+/// A report of an error or a panic, built up before sending it with [`ErrorReport::report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    kind: ReportKind,
+    message: String,
+    context: BTreeMap<String, String>,
+}
+
+impl Report {
+    /// A report of a handled error, described by `message`.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            kind: ReportKind::Error,
+            message: message.into(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// A report of a handled `error`, described by it and its sources.
+    pub fn from_error(error: &dyn std::error::Error) -> Self {
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(error) = source {
+            message.push_str(&format!(": {error}"));
+            source = error.source();
+        }
+
+        Self::error(message)
+    }
+
+    /// A report of a caught panic, described by its `payload`.
+    pub fn panic(payload: &(dyn std::any::Any + Send)) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "panic with a non-string payload".to_string()
+        };
+
+        Self {
+            kind: ReportKind::Panic,
+            message,
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Add context to the report, e.g. the current screen.
+    #[must_use]
+    pub fn with_context(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// How many reports to send to the shell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReportPolicy {
+    /// The fraction of error reports to send, from 0.0 to 1.0. The sample is spread evenly
+    /// over the reports rather than random, starting with the first. Panics are always sent.
+    pub sample_rate: f64,
+    /// The most reports with the same message to send
+    pub max_per_message: u32,
+    /// The most reports to send in total
+    pub max_reports: u32,
+}
+
+impl Default for ReportPolicy {
+    fn default() -> Self {
+        Self {
+            sample_rate: 1.0,
+            max_per_message: 10,
+            max_reports: 100,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Limiter {
+    policy: ReportPolicy,
+    sampled: u64,
+    sent: u32,
+    sent_per_message: HashMap<String, u32>,
+}
+
+impl Limiter {
+    fn allow(&mut self, report: &Report) -> bool {
+        let sent_for_message = self
+            .sent_per_message
+            .get(&report.message)
+            .copied()
+            .unwrap_or_default();
+        if self.sent >= self.policy.max_reports || sent_for_message >= self.policy.max_per_message {
+            return false;
+        }
+
+        if report.kind == ReportKind::Error {
+            // keep a report whenever the running total of the sample rate reaches a new whole number
+            let rate = self.policy.sample_rate.clamp(0.0, 1.0);
+            let before = (self.sampled as f64 * rate).ceil();
+            self.sampled += 1;
+            if (self.sampled as f64 * rate).ceil() <= before {
+                return false;
+            }
+        }
+
+        self.sent += 1;
+        self.sent_per_message
+            .insert(report.message.clone(), sent_for_message + 1);
+
+        true
+    }
+}
+
+/// The ErrorReport capability API
+///
+/// This capability lets the core report errors and panics to the shell's crash reporter.
+pub struct ErrorReport<Ev> {
+    context: CapabilityContext<ErrorReportOperation, Ev>,
+    limiter: Arc<Mutex<Limiter>>,
+    breadcrumbs: Option<Breadcrumbs>,
+}
+
+impl<Ev> Clone for ErrorReport<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+            limiter: self.limiter.clone(),
+            breadcrumbs: self.breadcrumbs.clone(),
+        }
+    }
+}
+
+impl<Ev> Capability<Ev> for ErrorReport<Ev> {
+    type Operation = ErrorReportOperation;
+    type MappedSelf<MappedEv> = ErrorReport<MappedEv>;
+
+    fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+    where
+        F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+        NewEv: 'static,
+    {
+        ErrorReport {
+            context: self.context.map_event(f),
+            limiter: self.limiter.clone(),
+            breadcrumbs: self.breadcrumbs.clone(),
+        }
+    }
+}
+
+impl<Ev> ErrorReport<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<ErrorReportOperation, Ev>) -> Self {
+        Self {
+            context,
+            limiter: Arc::default(),
+            breadcrumbs: None,
+        }
+    }
+
+    /// Attach the latest messages written with a [`Log`](crux_log::Log) to the reports,
+    /// e.g. `caps.error_report.with_breadcrumbs(caps.log.breadcrumbs())`.
+    #[must_use]
+    pub fn with_breadcrumbs(&self, breadcrumbs: Breadcrumbs) -> Self {
+        Self {
+            breadcrumbs: Some(breadcrumbs),
+            ..self.clone()
+        }
+    }
+
+    /// Change how many reports are sent from now on, see [`ReportPolicy`]. The policy is
+    /// shared by all the clones of the capability.
+    pub fn set_policy(&self, policy: ReportPolicy) {
+        self.limiter.lock().unwrap().policy = policy;
+    }
+
+    /// Start counting the reports towards the limits of the [`ReportPolicy`] again, e.g.
+    /// when the app comes back to the foreground.
+    pub fn reset_limits(&self) {
+        let mut limiter = self.limiter.lock().unwrap();
+        limiter.sent = 0;
+        limiter.sent_per_message.clear();
+    }
+
+    /// Send the `report` to the shell, unless the [`ReportPolicy`] leaves it out.
+    pub fn report(&self, report: Report) {
+        self.context.spawn({
+            let this = self.clone();
+
+            async move { this.report_async(report).await }
+        });
+    }
+
+    /// Send the `report` to the shell, unless the [`ReportPolicy`] leaves it out.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn report_async(&self, report: Report) {
+        if !self.limiter.lock().unwrap().allow(&report) {
+            return;
+        }
+
+        let operation = ErrorReportOperation {
+            kind: report.kind,
+            message: report.message,
+            context: report.context,
+            breadcrumbs: self
+                .breadcrumbs
+                .as_ref()
+                .map(Breadcrumbs::recent)
+                .unwrap_or_default(),
+        };
+
+        self.context.notify_shell(operation).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn allowed(limiter: &mut Limiter, report: &Report, times: usize) -> usize {
+        (0..times).filter(|_| limiter.allow(report)).count()
+    }
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = ErrorReportOperation {
+            kind: ReportKind::Error,
+            message: "sync failed".to_string(),
+            context: [("screen".to_string(), "notes".to_string())].into(),
+            breadcrumbs: vec![LogOperation {
+                level: crux_log::LogLevel::Info,
+                target: "sync".to_string(),
+                message: "started".to_string(),
+            }],
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"kind":"error","message":"sync failed","context":{"screen":"notes"},"breadcrumbs":[{"level":"info","target":"sync","message":"started"}]}"#
+        );
+
+        let deserialized: ErrorReportOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+    }
+
+    #[test]
+    fn test_sampling_is_spread_evenly() {
+        let mut limiter = Limiter {
+            policy: ReportPolicy {
+                sample_rate: 0.25,
+                max_per_message: 100,
+                max_reports: 100,
+            },
+            ..Default::default()
+        };
+        let report = Report::error("boom");
+
+        let sent: Vec<_> = (0..8).map(|_| limiter.allow(&report)).collect();
+        assert_eq!(
+            sent,
+            vec![true, false, false, false, true, false, false, false]
+        );
+
+        // panics aren't sampled
+        let panic = Report::panic(&"oh no");
+        assert_eq!(allowed(&mut limiter, &panic, 3), 3);
+    }
+
+    #[test]
+    fn test_reports_are_limited() {
+        let mut limiter = Limiter {
+            policy: ReportPolicy {
+                sample_rate: 1.0,
+                max_per_message: 2,
+                max_reports: 3,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(allowed(&mut limiter, &Report::error("a"), 5), 2);
+        assert_eq!(allowed(&mut limiter, &Report::error("b"), 5), 1);
+        assert_eq!(allowed(&mut limiter, &Report::error("c"), 5), 0);
+    }
+
+    #[derive(Debug)]
+    struct SyncFailed(crux_core::error::CapabilityError);
+
+    impl std::fmt::Display for SyncFailed {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("sync failed")
+        }
+    }
+
+    impl std::error::Error for SyncFailed {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_reports_describe_errors_and_panics() {
+        let error = SyncFailed(crux_core::error::CapabilityError::Timeout);
+        assert_eq!(Report::from_error(&error).message, "sync failed: timeout");
+
+        let payload = std::panic::catch_unwind(|| panic!("at {}", 42)).unwrap_err();
+        assert_eq!(Report::panic(payload.as_ref()).message, "at 42");
+    }
+}
@@ -0,0 +1,115 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_error_report::{ErrorReport, Report, ReportPolicy};
+    use crux_log::Log;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Configure(u32),
+        Sync,
+        SyncFailed(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub failures: u32,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = u32;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Configure(max_per_message) => caps.error_report.set_policy(ReportPolicy {
+                    max_per_message,
+                    ..Default::default()
+                }),
+                Event::Sync => caps.log.info("sync", "syncing notes"),
+                Event::SyncFailed(reason) => {
+                    model.failures += 1;
+                    caps.log.warn("sync", "sync failed");
+                    caps.error_report
+                        .with_breadcrumbs(caps.log.breadcrumbs())
+                        .report(Report::error(reason).with_context("screen", "notes"));
+                }
+            }
+        }
+
+        fn view(&self, model: &Model) -> u32 {
+            model.failures
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub error_report: ErrorReport<Event>,
+        pub log: Log<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_error_report::{ErrorReportOperation, ReportKind};
+    use crux_log::{LogLevel, LogOperation};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn reports(effects: impl Iterator<Item = Effect>) -> Vec<ErrorReportOperation> {
+        effects
+            .filter_map(Effect::into_error_report)
+            .map(|request| request.operation)
+            .collect()
+    }
+
+    #[test]
+    fn errors_are_reported_with_context_and_breadcrumbs() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(Event::Sync, &mut model);
+        let update = app.update(Event::SyncFailed("offline".to_string()), &mut model);
+
+        let log = |level, message: &str| LogOperation {
+            level,
+            target: "sync".to_string(),
+            message: message.to_string(),
+        };
+        assert_eq!(
+            reports(update.into_effects()),
+            vec![ErrorReportOperation {
+                kind: ReportKind::Error,
+                message: "offline".to_string(),
+                context: [("screen".to_string(), "notes".to_string())].into(),
+                breadcrumbs: vec![
+                    log(LogLevel::Info, "syncing notes"),
+                    log(LogLevel::Warn, "sync failed"),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn repeated_errors_are_limited() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(Event::Configure(2), &mut model);
+
+        let sent: Vec<_> = (0..4)
+            .map(|_| {
+                let update = app.update(Event::SyncFailed("offline".to_string()), &mut model);
+                reports(update.into_effects()).len()
+            })
+            .collect();
+
+        assert_eq!(sent, vec![1, 1, 0, 0]);
+        assert_eq!(app.view(&model), 4);
+    }
+}
@@ -0,0 +1,340 @@
+//! A/B experiments assigned in the core
+//!
+//! Experiments are usually bucketed in each shell, with the logic which depends on the variant
+//! repeated on every platform. This crate assigns users to the variants of experiments in the
+//! core instead, from experiments delivered as data, e.g. with the rest of the app's
+//! configuration, so that `update` and `view` can read the variant as one of the app's own
+//! types.
+//!
+//! Assignment is deterministic: a user is bucketed by hashing a stable id, such as an account
+//! or install id, with the experiment (see [`crux_crypto::hash`]), so they get the same variant
+//! on every platform and every launch, with no state to keep in sync. The variants are
+//! weighted, and changing the weights only moves the users whose bucket changes hands.
+//!
+//! A user is only counted in an experiment's results once they have been shown its variant,
+//! which the app marks with [`Assignments::expose`]. The [`Exposure`]s are collected for the
+//! app to send to its analytics, e.g. with `crux_http`.
+//!
+//! ```
+//! use crux_experiments::{Experiments, Exposure};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! #[serde(rename_all = "camelCase")]
+//! enum Checkout {
+//!     OnePage,
+//!     Steps,
+//! }
+//!
+//! let experiments: Experiments = serde_json::from_str(r#"[
+//!     { "name": "checkout", "variants": [
+//!         { "name": "onePage", "weight": 1 },
+//!         { "name": "steps", "weight": 1 }
+//!     ] }
+//! ]"#)?;
+//!
+//! // e.g. kept in the model when the configuration arrives
+//! let mut assignments = experiments.assign("user-42");
+//!
+//! // in `view`
+//! let checkout: Option<Checkout> = assignments.variant("checkout");
+//!
+//! // in `update`, when the user starts checking out
+//! let shown: Option<Checkout> = assignments.expose("checkout");
+//! assert_eq!(shown, checkout);
+//! assert_eq!(
+//!     assignments.take_exposures(),
+//!     vec![Exposure {
+//!         experiment: "checkout".to_string(),
+//!         variant: assignments.variant_name("checkout").unwrap().to_string(),
+//!     }]
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crux_crypto::HashAlgorithm;
+use serde::{
+    de::{
+        value::{Error as ValueError, StrDeserializer},
+        DeserializeOwned,
+    },
+    Deserialize, Serialize,
+};
+
+/// A variant of an experiment, and its share of the users
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variant {
+    pub name: String,
+    /// The variant's share of the users, relative to the weights of the other variants
+    pub weight: u32,
+}
+
+/// An experiment, with the variants users are assigned to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Experiment {
+    pub name: String,
+    /// Hashed with the stable id instead of the name, if set, e.g. to assign users afresh
+    /// when an experiment is run again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    pub variants: Vec<Variant>,
+}
+
+impl Experiment {
+    /// The variant the user with the `stable_id` is assigned to. Experiments with no
+    /// weighted variants assign nobody.
+    pub fn assign(&self, stable_id: &str) -> Option<&Variant> {
+        let total: u64 = self.variants.iter().map(|v| u64::from(v.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut bucket = bucket(self.salt.as_ref().unwrap_or(&self.name), stable_id) % total;
+        self.variants.iter().find(|variant| {
+            let weight = u64::from(variant.weight);
+            if bucket < weight {
+                true
+            } else {
+                bucket -= weight;
+                false
+            }
+        })
+    }
+}
+
+fn bucket(salt: &str, stable_id: &str) -> u64 {
+    // the salt and the id are separated by a byte neither contains, so they can't run together
+    let digest = crux_crypto::hash(
+        HashAlgorithm::Sha256,
+        format!("{salt}\0{stable_id}").as_bytes(),
+    );
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest.0[..8]);
+
+    u64::from_be_bytes(bytes)
+}
+
+/// Experiments looked up by name, which deserialize from a list.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Experiments {
+    experiments: Vec<Experiment>,
+}
+
+impl Experiments {
+    pub fn new(experiments: impl IntoIterator<Item = Experiment>) -> Self {
+        Self {
+            experiments: experiments.into_iter().collect(),
+        }
+    }
+
+    /// The experiment called `name`. If there's more than one, the last one wins, as with
+    /// `crux_rules`.
+    pub fn get(&self, name: &str) -> Option<&Experiment> {
+        self.experiments.iter().rev().find(|e| e.name == name)
+    }
+
+    pub fn experiments(&self) -> impl Iterator<Item = &Experiment> {
+        self.experiments.iter()
+    }
+
+    /// Assign the user with the `stable_id` to a variant of each experiment.
+    pub fn assign(&self, stable_id: &str) -> Assignments {
+        let variants = self
+            .experiments
+            .iter()
+            .filter_map(|experiment| {
+                let variant = self.get(&experiment.name)?.assign(stable_id)?;
+                Some((experiment.name.clone(), variant.name.clone()))
+            })
+            .collect();
+
+        Assignments {
+            variants,
+            ..Assignments::default()
+        }
+    }
+}
+
+/// A user was shown the variant of an experiment, see [`Assignments::expose`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exposure {
+    pub experiment: String,
+    pub variant: String,
+}
+
+/// The variants one user is assigned to, and the ones they have been shown, typically kept
+/// in the model.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Assignments {
+    variants: BTreeMap<String, String>,
+    #[serde(default)]
+    exposed: BTreeSet<String>,
+    #[serde(default)]
+    exposures: Vec<Exposure>,
+}
+
+impl Assignments {
+    /// The name of the variant of the `experiment` the user is assigned to
+    pub fn variant_name(&self, experiment: &str) -> Option<&str> {
+        self.variants.get(experiment).map(String::as_str)
+    }
+
+    /// The variant of the `experiment` the user is assigned to, as one of the app's types,
+    /// e.g. an enum with a unit variant for each of the experiment's variants, deserialized
+    /// from the variant's name. Variants the app doesn't know, e.g. added by a newer
+    /// configuration, are `None`, so that the app falls back to its default.
+    pub fn variant<V: DeserializeOwned>(&self, experiment: &str) -> Option<V> {
+        let name = self.variant_name(experiment)?;
+
+        V::deserialize(StrDeserializer::<ValueError>::new(name)).ok()
+    }
+
+    /// The variant of the `experiment`, like [`variant`](Self::variant), noting that the user
+    /// was shown it. Only the first exposure to each experiment is collected.
+    pub fn expose<V: DeserializeOwned>(&mut self, experiment: &str) -> Option<V> {
+        let variant = self.variant(experiment)?;
+
+        if self.exposed.insert(experiment.to_string()) {
+            self.exposures.push(Exposure {
+                experiment: experiment.to_string(),
+                variant: self.variants[experiment].clone(),
+            });
+        }
+
+        Some(variant)
+    }
+
+    /// The exposures collected since they were last taken, for the app to send to its
+    /// analytics.
+    pub fn take_exposures(&mut self) -> Vec<Exposure> {
+        std::mem::take(&mut self.exposures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    enum Banner {
+        Control,
+        Bold,
+    }
+
+    fn experiment(name: &str, weights: &[(&str, u32)]) -> Experiment {
+        Experiment {
+            name: name.to_string(),
+            salt: None,
+            variants: weights
+                .iter()
+                .map(|(name, weight)| Variant {
+                    name: (*name).to_string(),
+                    weight: *weight,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn assignment_is_deterministic_and_weighted() {
+        let banner = experiment("banner", &[("control", 1), ("bold", 3)]);
+
+        let bold = (0..4000)
+            .filter(|id| {
+                let id = format!("user-{id}");
+                let variant = banner.assign(&id).unwrap();
+                assert_eq!(banner.assign(&id), Some(variant));
+
+                variant.name == "bold"
+            })
+            .count();
+        assert!((2800..3200).contains(&bold), "{bold} of 4000 users in bold");
+
+        assert_eq!(experiment("empty", &[("a", 0)]).assign("user-1"), None);
+        assert_eq!(experiment("empty", &[]).assign("user-1"), None);
+    }
+
+    #[test]
+    fn experiments_are_bucketed_independently() {
+        let a = experiment("a", &[("x", 1), ("y", 1)]);
+        let b = experiment("b", &[("x", 1), ("y", 1)]);
+        let mut salted = a.clone();
+        salted.salt = Some("a, again".to_string());
+
+        let same = |other: &Experiment| {
+            (0..1000)
+                .filter(|id| {
+                    let id = id.to_string();
+                    a.assign(&id) == other.assign(&id)
+                })
+                .count()
+        };
+        assert_eq!(same(&a), 1000);
+        assert!((400..600).contains(&same(&b)));
+        assert!((400..600).contains(&same(&salted)));
+    }
+
+    #[test]
+    fn variants_are_read_as_the_apps_types() {
+        let experiments: Experiments = serde_json::from_value(json!([
+            { "name": "banner", "variants": [{ "name": "bold", "weight": 1 }] },
+            { "name": "layout", "variants": [{ "name": "grid", "weight": 1 }] },
+        ]))
+        .unwrap();
+        let assignments = experiments.assign("user-1");
+
+        assert_eq!(assignments.variant("banner"), Some(Banner::Bold));
+        assert_eq!(assignments.variant::<Banner>("layout"), None);
+        assert_eq!(assignments.variant::<Banner>("missing"), None);
+        assert_eq!(
+            assignments.variant::<String>("layout"),
+            Some("grid".to_string())
+        );
+    }
+
+    #[test]
+    fn only_the_first_exposure_is_collected() {
+        let experiments = Experiments::new([
+            experiment("banner", &[("control", 1)]),
+            experiment("layout", &[("grid", 1)]),
+        ]);
+        let mut assignments = experiments.assign("user-1");
+
+        assert_eq!(assignments.expose("banner"), Some(Banner::Control));
+        assert_eq!(assignments.expose("banner"), Some(Banner::Control));
+        assert_eq!(assignments.expose::<Banner>("layout"), None);
+        assert_eq!(
+            assignments.take_exposures(),
+            vec![Exposure {
+                experiment: "banner".to_string(),
+                variant: "control".to_string(),
+            }]
+        );
+
+        assert_eq!(assignments.expose("banner"), Some(Banner::Control));
+        assert!(assignments.take_exposures().is_empty());
+    }
+
+    #[test]
+    fn later_experiments_override_earlier_ones() {
+        let experiments = Experiments::new([
+            experiment("banner", &[("control", 1)]),
+            experiment("banner", &[("bold", 1)]),
+        ]);
+
+        assert_eq!(
+            experiments.assign("user-1").variant("banner"),
+            Some(Banner::Bold)
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Picking, reading and writing user documents
+//!
+//! The [`FilePicker`] capability presents the platform's document picker, to open existing
+//! documents or choose where to save a new one, optionally filtered by [`FileType`]. The picked
+//! documents are returned as [`FileHandle`]s, which wrap the platform's persistent reference to
+//! the document (a security-scoped bookmark on Apple platforms, a persisted Storage Access
+//! Framework URI on Android) and can be stored by the app, e.g. with `crux_kv`, to read and
+//! write the document again later, including in future sessions.
+//!
+//! References can go stale, for example when the document is moved, in which case the shell
+//! may return a refreshed handle with the contents it read, which the app should store in
+//! place of the old one. Documents the shell can no longer access at all fail with
+//! [`FileError::AccessLost`], and need to be picked again.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A persistent reference to a document picked by the user
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHandle {
+    /// The shell's serialized reference to the document, opaque to the core
+    pub reference: String,
+    /// The document's display name, e.g. `notes.md`
+    pub name: String,
+    pub media_type: Option<String>,
+}
+
+/// A type of document to offer in the picker
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileType {
+    /// A media type, e.g. `application/pdf` or `image/*`
+    MediaType(String),
+    /// A file extension, without the dot, e.g. `md`
+    Extension(String),
+}
+
+impl FileType {
+    pub fn media_type(media_type: impl Into<String>) -> Self {
+        Self::MediaType(media_type.into())
+    }
+
+    pub fn extension(extension: impl Into<String>) -> Self {
+        Self::Extension(extension.into().trim_start_matches('.').to_string())
+    }
+}
+
+/// Options for picking documents to open
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOptions {
+    /// The types of documents which can be picked, or any if empty
+    pub types: Vec<FileType>,
+    /// Whether more than one document can be picked
+    pub multiple: bool,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.types.push(file_type);
+        self
+    }
+
+    #[must_use]
+    pub fn multiple(mut self) -> Self {
+        self.multiple = true;
+        self
+    }
+}
+
+/// Options for picking where to save a new document
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveOptions {
+    /// The name suggested to the user, e.g. `Untitled.md`
+    pub suggested_name: String,
+    pub types: Vec<FileType>,
+}
+
+impl SaveOptions {
+    pub fn new(suggested_name: impl Into<String>) -> Self {
+        Self {
+            suggested_name: suggested_name.into(),
+            types: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.types.push(file_type);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilePickerOperation {
+    Open(OpenOptions),
+    Save(SaveOptions),
+    Read {
+        handle: FileHandle,
+    },
+    /// Replace the contents of the document
+    Write {
+        handle: FileHandle,
+        data: Vec<u8>,
+    },
+    /// Give up the persistent access to the document
+    Release {
+        handle: FileHandle,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilePickerResponse {
+    /// The documents picked, none if the user cancelled the picker
+    Picked {
+        files: Vec<FileHandle>,
+    },
+    Read(FileContents),
+    Written,
+    /// The document no longer exists, or the app no longer has access to it
+    AccessLost,
+    Error {
+        message: String,
+    },
+}
+
+/// The contents of a document
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContents {
+    pub data: Vec<u8>,
+    /// A new handle for the document, if the one it was read with went stale. The app should
+    /// store it in place of the old one.
+    pub refreshed: Option<FileHandle>,
+}
+
+impl Operation for FilePickerOperation {
+    type Output = FilePickerResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FileError {
+    #[error("lost access to {name}, it needs to be picked again")]
+    AccessLost { name: String },
+    #[error("file operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<FilePickerResponse> },
+}
+
+impl FileError {
+    fn from_response(response: FilePickerResponse, handle: Option<&FileHandle>) -> Self {
+        match response {
+            FilePickerResponse::AccessLost => FileError::AccessLost {
+                name: handle.map(|h| h.name.clone()).unwrap_or_default(),
+            },
+            FilePickerResponse::Error { message } => FileError::Shell { message },
+            response => FileError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// The FilePicker capability API
+///
+/// This capability lets the app ask the user to pick documents to open or a place to save one,
+/// and read and write the picked documents, now or later.
+#[derive(crux_core::macros::Capability)]
+pub struct FilePicker<Ev> {
+    context: CapabilityContext<FilePickerOperation, Ev>,
+}
+
+impl<Ev> Clone for FilePicker<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> FilePicker<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<FilePickerOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Present the picker to open documents, then send the event returned by `callback` with
+    /// the documents picked, which are none if the user cancelled.
+    pub fn open<F>(&self, options: OpenOptions, callback: F)
+    where
+        F: FnOnce(Result<Vec<FileHandle>, FileError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.open_async(options).await));
+            }
+        });
+    }
+
+    /// Present the picker to open documents, returning the documents picked.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn open_async(&self, options: OpenOptions) -> Result<Vec<FileHandle>, FileError> {
+        match self
+            .context
+            .request_from_shell(FilePickerOperation::Open(options))
+            .await
+        {
+            FilePickerResponse::Picked { files } => Ok(files),
+            response => Err(FileError::from_response(response, None)),
+        }
+    }
+
+    /// Present the picker to choose where to save a new document, then send the event returned
+    /// by `callback` with the handle to write to, or `None` if the user cancelled.
+    pub fn save<F>(&self, options: SaveOptions, callback: F)
+    where
+        F: FnOnce(Result<Option<FileHandle>, FileError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.save_async(options).await));
+            }
+        });
+    }
+
+    /// Present the picker to choose where to save a new document.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn save_async(&self, options: SaveOptions) -> Result<Option<FileHandle>, FileError> {
+        match self
+            .context
+            .request_from_shell(FilePickerOperation::Save(options))
+            .await
+        {
+            FilePickerResponse::Picked { files } => Ok(files.into_iter().next()),
+            response => Err(FileError::from_response(response, None)),
+        }
+    }
+
+    /// Read the document, then send the event returned by `callback` with its contents.
+    pub fn read<F>(&self, handle: FileHandle, callback: F)
+    where
+        F: FnOnce(Result<FileContents, FileError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.read_async(handle).await));
+            }
+        });
+    }
+
+    /// Read the document.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn read_async(&self, handle: FileHandle) -> Result<FileContents, FileError> {
+        match self
+            .context
+            .request_from_shell(FilePickerOperation::Read {
+                handle: handle.clone(),
+            })
+            .await
+        {
+            FilePickerResponse::Read(contents) => Ok(contents),
+            response => Err(FileError::from_response(response, Some(&handle))),
+        }
+    }
+
+    /// Replace the contents of the document with `data`, then send the event returned by
+    /// `callback` with the result.
+    pub fn write<F>(&self, handle: FileHandle, data: Vec<u8>, callback: F)
+    where
+        F: FnOnce(Result<(), FileError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.write_async(handle, data).await));
+            }
+        });
+    }
+
+    /// Replace the contents of the document with `data`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn write_async(&self, handle: FileHandle, data: Vec<u8>) -> Result<(), FileError> {
+        match self
+            .context
+            .request_from_shell(FilePickerOperation::Write {
+                handle: handle.clone(),
+                data,
+            })
+            .await
+        {
+            FilePickerResponse::Written => Ok(()),
+            response => Err(FileError::from_response(response, Some(&handle))),
+        }
+    }
+
+    /// Give up the persistent access to the document, when the app no longer needs it
+    pub fn release(&self, handle: FileHandle) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context
+                    .notify_shell(FilePickerOperation::Release { handle })
+                    .await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = FilePickerOperation::Open(
+            OpenOptions::new()
+                .file_type(FileType::extension(".md"))
+                .file_type(FileType::media_type("text/plain")),
+        );
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"open":{"types":[{"extension":"md"},{"mediaType":"text/plain"}],"multiple":false}}"#
+        );
+
+        let deserialized: FilePickerOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let serialized = serde_json::to_string(&FilePickerResponse::AccessLost).unwrap();
+        assert_eq!(&serialized, r#""accessLost""#);
+    }
+}
@@ -0,0 +1,237 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_files::{
+        FileContents, FileError, FileHandle, FilePicker, FileType, OpenOptions, SaveOptions,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Open,
+        SaveAs,
+        Reopen,
+        Save(String),
+
+        #[serde(skip)]
+        Picked(Result<Vec<FileHandle>, FileError>),
+        #[serde(skip)]
+        SaveLocation(Result<Option<FileHandle>, FileError>),
+        #[serde(skip)]
+        Loaded(Result<FileContents, FileError>),
+        #[serde(skip)]
+        Saved(Result<(), FileError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        /// the handle is kept, to reopen the document in later sessions
+        pub document: Option<FileHandle>,
+        pub text: String,
+        pub error: Option<FileError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub files: FilePicker<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Open => caps.files.open(
+                    OpenOptions::new().file_type(FileType::extension("md")),
+                    Event::Picked,
+                ),
+                Event::SaveAs => caps.files.save(
+                    SaveOptions::new("Untitled.md").file_type(FileType::extension("md")),
+                    Event::SaveLocation,
+                ),
+                Event::Reopen => {
+                    if let Some(handle) = model.document.clone() {
+                        caps.files.read(handle, Event::Loaded);
+                    }
+                }
+                Event::Save(text) => {
+                    if let Some(handle) = model.document.clone() {
+                        caps.files.write(handle, text.into_bytes(), Event::Saved);
+                    }
+                }
+                Event::Picked(Ok(mut files)) => {
+                    if let Some(handle) = files.pop() {
+                        model.document = Some(handle.clone());
+                        caps.files.read(handle, Event::Loaded);
+                    }
+                }
+                Event::SaveLocation(Ok(handle)) => model.document = handle,
+                Event::Loaded(Ok(contents)) => {
+                    if let Some(refreshed) = contents.refreshed {
+                        model.document = Some(refreshed);
+                    }
+                    model.text = String::from_utf8(contents.data).unwrap();
+                }
+                Event::Saved(Ok(())) => {}
+                Event::Picked(Err(error))
+                | Event::SaveLocation(Err(error))
+                | Event::Loaded(Err(error))
+                | Event::Saved(Err(error)) => {
+                    if matches!(error, FileError::AccessLost { .. }) {
+                        model.document = None;
+                    }
+                    model.error = Some(error);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_files::{
+        FileContents, FileError, FileHandle, FilePickerOperation, FilePickerResponse, FileType,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn handle(reference: &str) -> FileHandle {
+        FileHandle {
+            reference: reference.to_string(),
+            name: "notes.md".to_string(),
+            media_type: Some("text/markdown".to_string()),
+        }
+    }
+
+    fn request(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Request<FilePickerOperation> {
+        let Some(Effect::FilePicker(request)) = app.update(event, model).into_effects().next()
+        else {
+            panic!("expected a file picker effect");
+        };
+
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<FilePickerOperation>,
+        response: FilePickerResponse,
+    ) -> Vec<Effect> {
+        let update = app.resolve(request, response).unwrap();
+        let mut effects = update.effects;
+        for event in update.events {
+            effects.extend(app.update(event, model).effects);
+        }
+
+        effects
+    }
+
+    #[test]
+    fn opens_and_reopens_a_document() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut open = request(&app, &mut model, Event::Open);
+        let FilePickerOperation::Open(options) = &open.operation else {
+            panic!("expected the open picker");
+        };
+        assert_eq!(options.types, vec![FileType::extension("md")]);
+
+        // picking reads the document
+        let effects = respond(
+            &app,
+            &mut model,
+            &mut open,
+            FilePickerResponse::Picked {
+                files: vec![handle("bookmark-1")],
+            },
+        );
+        let Some(Effect::FilePicker(mut read)) = effects.into_iter().next() else {
+            panic!("expected to read the document");
+        };
+        assert_eq!(
+            read.operation,
+            FilePickerOperation::Read {
+                handle: handle("bookmark-1")
+            }
+        );
+
+        // the bookmark went stale, and was refreshed when reading
+        respond(
+            &app,
+            &mut model,
+            &mut read,
+            FilePickerResponse::Read(FileContents {
+                data: b"# Notes".to_vec(),
+                refreshed: Some(handle("bookmark-2")),
+            }),
+        );
+        assert_eq!(model.text, "# Notes");
+        assert_eq!(model.document, Some(handle("bookmark-2")));
+
+        let mut write = request(&app, &mut model, Event::Save("# Notes\n".to_string()));
+        assert_eq!(
+            write.operation,
+            FilePickerOperation::Write {
+                handle: handle("bookmark-2"),
+                data: b"# Notes\n".to_vec()
+            }
+        );
+        respond(&app, &mut model, &mut write, FilePickerResponse::Written);
+        assert_eq!(model.error, None);
+
+        // later, the document was deleted
+        let mut read = request(&app, &mut model, Event::Reopen);
+        respond(&app, &mut model, &mut read, FilePickerResponse::AccessLost);
+
+        assert_eq!(
+            model.error,
+            Some(FileError::AccessLost {
+                name: "notes.md".to_string()
+            })
+        );
+        assert_eq!(model.document, None);
+    }
+
+    #[test]
+    fn cancelling_the_pickers() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut open = request(&app, &mut model, Event::Open);
+        let effects = respond(
+            &app,
+            &mut model,
+            &mut open,
+            FilePickerResponse::Picked { files: vec![] },
+        );
+        assert!(effects.is_empty());
+
+        let mut save = request(&app, &mut model, Event::SaveAs);
+        let FilePickerOperation::Save(options) = &save.operation else {
+            panic!("expected the save picker");
+        };
+        assert_eq!(options.suggested_name, "Untitled.md");
+
+        respond(
+            &app,
+            &mut model,
+            &mut save,
+            FilePickerResponse::Picked { files: vec![] },
+        );
+        assert_eq!(model.document, None);
+        assert_eq!(model.error, None);
+    }
+}
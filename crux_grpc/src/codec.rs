@@ -0,0 +1,166 @@
+//! gRPC-web framing of messages and trailers
+//!
+//! Every message in a gRPC-web body is prefixed with a flag byte and the message length as a
+//! big-endian `u32`. The trailers are sent as the last frame, flagged with the most significant
+//! bit, and contain HTTP/1 style `name: value` lines.
+
+use crate::GrpcError;
+
+const HEADER_LEN: usize = 5;
+const TRAILER_FLAG: u8 = 0x80;
+const COMPRESSED_FLAG: u8 = 0x01;
+
+/// The decoded frames of a gRPC-web response body
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Frames {
+    /// The encoded messages, in the order they were sent
+    pub messages: Vec<Vec<u8>>,
+    /// The trailers, with lowercase names, if a trailer frame was sent
+    pub trailers: Option<Vec<(String, String)>>,
+}
+
+/// Frame an encoded message
+pub fn encode_frame(message: &[u8]) -> Vec<u8> {
+    frame(0, message)
+}
+
+/// Frame trailers, as sent by a gRPC-web server
+pub fn encode_trailers<'a>(trailers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<u8> {
+    let block: String = trailers
+        .into_iter()
+        .map(|(name, value)| format!("{name}:{value}\r\n"))
+        .collect();
+
+    frame(TRAILER_FLAG, block.as_bytes())
+}
+
+fn frame(flag: u8, payload: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(payload.len()).expect("gRPC messages are limited to 4GB");
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.push(flag);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+
+    frame
+}
+
+/// Split a gRPC-web response body into its messages and trailers
+///
+/// # Errors
+///
+/// Returns [`GrpcError::Protocol`] if the body is truncated, a message is compressed,
+/// or frames follow the trailers.
+pub fn decode_frames(mut body: &[u8]) -> Result<Frames, GrpcError> {
+    let protocol = |message: &str| GrpcError::Protocol {
+        message: message.to_string(),
+    };
+    let mut frames = Frames::default();
+
+    while !body.is_empty() {
+        if frames.trailers.is_some() {
+            return Err(protocol("unexpected frame after the trailers"));
+        }
+        if body.len() < HEADER_LEN {
+            return Err(protocol("truncated frame header"));
+        }
+
+        let flag = body[0];
+        let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        let payload = body
+            .get(HEADER_LEN..HEADER_LEN + len)
+            .ok_or_else(|| protocol("truncated frame"))?;
+        body = &body[HEADER_LEN + len..];
+
+        if flag & TRAILER_FLAG != 0 {
+            frames.trailers = Some(parse_trailers(payload));
+        } else if flag & COMPRESSED_FLAG != 0 {
+            return Err(protocol("compressed messages are not supported"));
+        } else {
+            frames.messages.push(payload.to_vec());
+        }
+    }
+
+    Ok(frames)
+}
+
+fn parse_trailers(block: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8_lossy(block)
+        .split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect()
+}
+
+/// Decode a percent-encoded `grpc-message`
+pub(crate) fn decode_message(message: &str) -> String {
+    let bytes = message.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut body = encode_frame(b"first");
+        body.extend(encode_frame(b""));
+        body.extend(encode_trailers([
+            ("Grpc-Status", "0"),
+            ("grpc-message", ""),
+        ]));
+
+        let frames = decode_frames(&body).unwrap();
+
+        assert_eq!(frames.messages, vec![b"first".to_vec(), Vec::new()]);
+        assert_eq!(
+            frames.trailers,
+            Some(vec![
+                ("grpc-status".to_string(), "0".to_string()),
+                ("grpc-message".to_string(), String::new())
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_bodies() {
+        let truncated = &encode_frame(b"message")[..8];
+        assert!(decode_frames(truncated).is_err());
+
+        let mut after_trailers = encode_trailers([("grpc-status", "0")]);
+        after_trailers.extend(encode_frame(b"late"));
+        assert!(decode_frames(&after_trailers).is_err());
+
+        let mut compressed = encode_frame(b"gzip");
+        compressed[0] = COMPRESSED_FLAG;
+        assert!(decode_frames(&compressed).is_err());
+    }
+
+    #[test]
+    fn percent_decoded_messages() {
+        assert_eq!(decode_message("no%20such%20user"), "no such user");
+        assert_eq!(decode_message("caf%C3%A9 100%"), "café 100%");
+    }
+}
@@ -0,0 +1,228 @@
+//! gRPC-web calls over crux_http
+//!
+//! For backends which only speak gRPC, a [`GrpcClient`] makes
+//! [gRPC-web](https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md) calls using the
+//! `crux_http` capability, so no additional support is needed in the shell. Requests and
+//! responses are [`prost`] messages, typically generated from the service's `.proto` files with
+//! `prost-build`. The client has to be used in an async context, together with
+//! [`crux_core::compose::Compose`].
+//!
+//! Messages are framed as `application/grpc-web+proto` (see the [`codec`] module), and the
+//! outcome of each call is read from its trailers, or from the response headers for
+//! trailers-only responses. Calls which the server completes with a status other than
+//! [`Code::Ok`] fail with [`GrpcError::Status`], holding the typed status code and message.
+//! Server streaming calls are supported, with the whole stream delivered once the call
+//! completes.
+//!
+//! Compressed messages and the base64 `grpc-web-text` encoding are not supported.
+
+pub mod codec;
+mod status;
+
+use crux_core::error::CapabilityError;
+use crux_http::{Http, HttpError, ResponseAsync};
+use thiserror::Error;
+
+pub use crate::status::{Code, Status};
+
+const CONTENT_TYPE: &str = "application/grpc-web+proto";
+
+/// Makes gRPC-web calls to the services served at a base URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcClient {
+    base_url: String,
+    metadata: Vec<(String, String)>,
+}
+
+impl GrpcClient {
+    /// A client for the gRPC-web endpoint at `base_url`, e.g. `https://api.example.com`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Send the metadata `name: value` with every call, e.g. an `authorization` header
+    #[must_use]
+    pub fn with_metadata(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((name.into(), value.into()));
+        self
+    }
+
+    /// Make a unary call to `method`, given as `package.Service/Method`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, the server completes the call with an error
+    /// status, or the response is not a single valid message.
+    pub async fn unary<Ev, Req, Res>(
+        &self,
+        http: &Http<Ev>,
+        method: &str,
+        request: &Req,
+    ) -> Result<Res, GrpcError>
+    where
+        Ev: 'static,
+        Req: prost::Message,
+        Res: prost::Message + Default,
+    {
+        let mut messages = self.call(http, method, request).await?;
+
+        if messages.len() != 1 {
+            return Err(GrpcError::Protocol {
+                message: format!("expected one response message, got {}", messages.len()),
+            });
+        }
+
+        decode(&messages.remove(0))
+    }
+
+    /// Make a server streaming call to `method`, given as `package.Service/Method`,
+    /// returning all the messages streamed by the server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, the server completes the call with an error
+    /// status, or a message is invalid.
+    pub async fn server_streaming<Ev, Req, Res>(
+        &self,
+        http: &Http<Ev>,
+        method: &str,
+        request: &Req,
+    ) -> Result<Vec<Res>, GrpcError>
+    where
+        Ev: 'static,
+        Req: prost::Message,
+        Res: prost::Message + Default,
+    {
+        self.call(http, method, request)
+            .await?
+            .iter()
+            .map(|message| decode(message))
+            .collect()
+    }
+
+    async fn call<Ev, Req>(
+        &self,
+        http: &Http<Ev>,
+        method: &str,
+        request: &Req,
+    ) -> Result<Vec<Vec<u8>>, GrpcError>
+    where
+        Ev: 'static,
+        Req: prost::Message,
+    {
+        let url = format!("{}/{}", self.base_url, method.trim_start_matches('/'));
+        let mut builder = http
+            .post(url)
+            .body_bytes(codec::encode_frame(&request.encode_to_vec()))
+            .header("content-type", CONTENT_TYPE)
+            .header("accept", CONTENT_TYPE)
+            .header("x-grpc-web", "1");
+        for (name, value) in &self.metadata {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        let mut response = builder
+            .send_async()
+            .await
+            .map_err(|error| GrpcError::Http { error })?;
+
+        // trailers-only responses carry the status in the headers
+        let header_status = status_from(|name| header(&response, name))?;
+
+        if !response.status().is_success() {
+            return Err(GrpcError::Status {
+                status: header_status.unwrap_or_else(|| {
+                    let code = response.status();
+                    Status::new(Code::from_http(code), format!("HTTP status {code}"))
+                }),
+            });
+        }
+
+        let body = response
+            .body_bytes()
+            .await
+            .map_err(|error| GrpcError::Http { error })?;
+        let frames = codec::decode_frames(&body)?;
+
+        let trailer_status = match &frames.trailers {
+            Some(trailers) => status_from(|name| {
+                trailers
+                    .iter()
+                    .find(|(trailer, _)| trailer == name)
+                    .map(|(_, value)| value.clone())
+            })?,
+            None => None,
+        };
+
+        let status = trailer_status
+            .or(header_status)
+            .ok_or_else(|| GrpcError::Protocol {
+                message: "the response has no grpc-status".to_string(),
+            })?;
+
+        if status.code == Code::Ok {
+            Ok(frames.messages)
+        } else {
+            Err(GrpcError::Status { status })
+        }
+    }
+}
+
+fn header(response: &ResponseAsync, name: &str) -> Option<String> {
+    response
+        .header(name)
+        .map(|values| values.last().as_str().to_string())
+}
+
+fn status_from(get: impl Fn(&str) -> Option<String>) -> Result<Option<Status>, GrpcError> {
+    let Some(code) = get("grpc-status") else {
+        return Ok(None);
+    };
+    let code = code.parse().map_err(|_| GrpcError::Protocol {
+        message: format!("invalid grpc-status {code}"),
+    })?;
+    let message = get("grpc-message")
+        .map(|message| codec::decode_message(&message))
+        .unwrap_or_default();
+
+    Ok(Some(Status::new(Code::from_i32(code), message)))
+}
+
+fn decode<Res: prost::Message + Default>(message: &[u8]) -> Result<Res, GrpcError> {
+    Res::decode(message).map_err(|e| GrpcError::Decode {
+        message: e.to_string(),
+    })
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GrpcError {
+    #[error("HTTP error: {error}")]
+    Http { error: HttpError },
+    #[error("gRPC error: {status}")]
+    Status { status: Status },
+    #[error("invalid gRPC-web response: {message}")]
+    Protocol { message: String },
+    #[error("failed to decode the response message: {message}")]
+    Decode { message: String },
+}
+
+impl From<GrpcError> for CapabilityError {
+    fn from(e: GrpcError) -> Self {
+        match e {
+            GrpcError::Http { error } => error.into(),
+            GrpcError::Status { status } => match status.code {
+                Code::Unavailable | Code::ResourceExhausted => Self::Unavailable,
+                Code::PermissionDenied | Code::Unauthenticated => Self::PermissionDenied,
+                Code::DeadlineExceeded => Self::Timeout,
+                Code::NotFound => Self::NotFound,
+                _ => Self::ShellFailure(status.to_string()),
+            },
+            GrpcError::Protocol { message } | GrpcError::Decode { message } => {
+                Self::InvalidData(message)
+            }
+        }
+    }
+}
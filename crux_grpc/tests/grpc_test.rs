@@ -0,0 +1,249 @@
+mod shared {
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_grpc::{GrpcClient, GrpcError};
+    use crux_http::Http;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetUserRequest {
+        #[prost(string, tag = "1")]
+        pub id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct User {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub name: String,
+    }
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        GetUser(String),
+        ListUsers,
+
+        #[serde(skip)]
+        Users(Result<Vec<User>, GrpcError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub users: Vec<User>,
+        pub error: Option<GrpcError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            let client = GrpcClient::new("https://api.example.com/")
+                .with_metadata("authorization", "Bearer token");
+            let http = caps.http.clone();
+
+            match event {
+                Event::GetUser(id) => caps.compose.spawn(|ctx| async move {
+                    let request = GetUserRequest { id };
+                    let user: Result<User, _> = client
+                        .unary(&http, "users.v1.Users/GetUser", &request)
+                        .await;
+                    ctx.update_app(Event::Users(user.map(|user| vec![user])));
+                }),
+                Event::ListUsers => caps.compose.spawn(|ctx| async move {
+                    let users = client
+                        .server_streaming(&http, "users.v1.Users/ListUsers", &())
+                        .await;
+                    ctx.update_app(Event::Users(users));
+                }),
+                Event::Users(Ok(users)) => model.users = users,
+                Event::Users(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{error::CapabilityError, testing::AppTester};
+    use crux_grpc::{
+        codec::{encode_frame, encode_trailers},
+        Code, GrpcError, Status,
+    };
+    use crux_http::protocol::{HttpRequest, HttpResponse, HttpResult};
+    use prost::Message;
+
+    use crate::shared::{App, Effect, Event, GetUserRequest, Model, User};
+
+    /// Sends the event and answers its only HTTP request with `response`
+    fn run(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+        response: HttpResponse,
+    ) -> HttpRequest {
+        let update = app.update(event, model);
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("expected an HTTP request");
+        };
+
+        let update = app.resolve(&mut request, HttpResult::Ok(response)).unwrap();
+        for event in update.events {
+            app.update(event, model);
+        }
+
+        request.operation
+    }
+
+    fn header<'a>(request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+
+    fn user(id: &str, name: &str) -> User {
+        User {
+            id: id.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn unary_call() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut body = encode_frame(&user("1", "Ada").encode_to_vec());
+        body.extend(encode_trailers([("grpc-status", "0")]));
+
+        let request = run(
+            &app,
+            &mut model,
+            Event::GetUser("1".to_string()),
+            HttpResponse::ok().body(body).build(),
+        );
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            request.url,
+            "https://api.example.com/users.v1.Users/GetUser"
+        );
+        assert_eq!(
+            header(&request, "content-type"),
+            Some("application/grpc-web+proto")
+        );
+        assert_eq!(header(&request, "x-grpc-web"), Some("1"));
+        assert_eq!(header(&request, "authorization"), Some("Bearer token"));
+
+        // a single framed message
+        assert_eq!(request.body[0], 0);
+        assert_eq!(
+            GetUserRequest::decode(&request.body[5..]).unwrap(),
+            GetUserRequest {
+                id: "1".to_string()
+            }
+        );
+
+        assert_eq!(model.users, vec![user("1", "Ada")]);
+    }
+
+    #[test]
+    fn server_streaming_call() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut body = encode_frame(&user("1", "Ada").encode_to_vec());
+        body.extend(encode_frame(&user("2", "Grace").encode_to_vec()));
+        body.extend(encode_trailers([("grpc-status", "0")]));
+
+        run(
+            &app,
+            &mut model,
+            Event::ListUsers,
+            HttpResponse::ok().body(body).build(),
+        );
+
+        assert_eq!(model.users, vec![user("1", "Ada"), user("2", "Grace")]);
+    }
+
+    #[test]
+    fn error_status_in_trailers() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let body = encode_trailers([("grpc-status", "5"), ("grpc-message", "no%20such%20user")]);
+        run(
+            &app,
+            &mut model,
+            Event::GetUser("2".to_string()),
+            HttpResponse::ok().body(body).build(),
+        );
+
+        let error = model.error.unwrap();
+        assert_eq!(
+            error,
+            GrpcError::Status {
+                status: Status::new(Code::NotFound, "no such user")
+            }
+        );
+        assert_eq!(CapabilityError::from(error), CapabilityError::NotFound);
+    }
+
+    #[test]
+    fn trailers_only_and_http_errors() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        run(
+            &app,
+            &mut model,
+            Event::GetUser("1".to_string()),
+            HttpResponse::ok()
+                .header("grpc-status", "16")
+                .header("grpc-message", "expired")
+                .build(),
+        );
+        assert_eq!(
+            model.error.take(),
+            Some(GrpcError::Status {
+                status: Status::new(Code::Unauthenticated, "expired")
+            })
+        );
+
+        // the request didn't reach a gRPC server
+        run(
+            &app,
+            &mut model,
+            Event::GetUser("1".to_string()),
+            HttpResponse::status(503).build(),
+        );
+        assert!(matches!(
+            model.error.take(),
+            Some(GrpcError::Status { status }) if status.code == Code::Unavailable
+        ));
+
+        // no status at all
+        run(
+            &app,
+            &mut model,
+            Event::GetUser("1".to_string()),
+            HttpResponse::ok().build(),
+        );
+        assert!(matches!(model.error, Some(GrpcError::Protocol { .. })));
+    }
+}
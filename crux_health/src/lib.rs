@@ -0,0 +1,476 @@
+//! Health and fitness data
+//!
+//! The [`Health`] capability reads and writes samples in the device's health store (HealthKit
+//! on iOS, Health Connect on Android) as typed structs: [step counts](StepCount),
+//! [heart rate readings](HeartRate) and [workouts](Workout). Access is asked for per
+//! [`HealthDataType`] with [`Health::request_authorization`], and every operation fails with
+//! [`HealthError::PermissionDenied`] if it wasn't given.
+//!
+//! Besides one-off [queries](Health::query), the core can [observe](Health::observe) a data
+//! type with an anchored query: the shell sends the samples added and deleted since an
+//! [`Anchor`], then keeps sending changes as they happen, each with a new anchor. Apps keep the
+//! latest anchor (e.g. with the KV capability) to continue incrementally after a restart,
+//! instead of reading the whole history again.
+
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthDataType {
+    Steps,
+    HeartRate,
+    Workouts,
+}
+
+/// The health store's identifier for a sample
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SampleId(pub String);
+
+/// The health store's position in the history of a data type, to observe changes from
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Anchor(pub String);
+
+/// The core's identifier for an observer, see [`Health::observe`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ObserverId(pub u64);
+
+/// The number of steps taken between `start` and `end`, in milliseconds since the UNIX epoch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepCount {
+    pub count: u32,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// A heart rate reading at a time, in milliseconds since the UNIX epoch
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartRate {
+    pub beats_per_minute: f64,
+    pub at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WorkoutActivity {
+    Running,
+    Walking,
+    Cycling,
+    Swimming,
+    Hiking,
+    StrengthTraining,
+    Yoga,
+    /// An activity the health store doesn't have a more specific type for
+    Other,
+}
+
+/// A workout between `start` and `end`, in milliseconds since the UNIX epoch
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workout {
+    pub activity: WorkoutActivity,
+    pub start: u64,
+    pub end: u64,
+    /// The active energy burned, in kilocalories
+    pub energy_kcal: Option<f64>,
+    /// The distance covered, in metres
+    pub distance_metres: Option<f64>,
+}
+
+/// The data of a sample, to write to the health store
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SampleData {
+    Steps(StepCount),
+    HeartRate(HeartRate),
+    Workout(Workout),
+}
+
+impl SampleData {
+    pub fn data_type(&self) -> HealthDataType {
+        match self {
+            SampleData::Steps(_) => HealthDataType::Steps,
+            SampleData::HeartRate(_) => HealthDataType::HeartRate,
+            SampleData::Workout(_) => HealthDataType::Workouts,
+        }
+    }
+}
+
+/// A sample read from the health store
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthSample {
+    pub id: SampleId,
+    /// The app or device which recorded the sample, e.g. `com.apple.health`
+    pub source: String,
+    pub data: SampleData,
+}
+
+/// A query for the samples of a data type which overlap a time range
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleQuery {
+    pub data_type: HealthDataType,
+    /// The start of the range, in milliseconds since the UNIX epoch
+    pub start: u64,
+    /// The end of the range, in milliseconds since the UNIX epoch
+    pub end: u64,
+    /// The most samples to return, newest first, or all if `None`
+    pub limit: Option<u32>,
+}
+
+impl SampleQuery {
+    /// All the samples of the `data_type` between `start` and `end`
+    pub fn new(data_type: HealthDataType, start: u64, end: u64) -> Self {
+        Self {
+            data_type,
+            start,
+            end: end.max(start),
+            limit: None,
+        }
+    }
+
+    /// Only the newest `limit` samples
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit.max(1));
+        self
+    }
+}
+
+/// The samples of a data type added and deleted since an anchor
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthChanges {
+    pub added: Vec<HealthSample>,
+    pub deleted: Vec<SampleId>,
+    /// Where to continue observing from after these changes
+    pub anchor: Anchor,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum HealthOperation {
+    /// Ask the user for access to read and write data types. Shells only show the permission
+    /// prompt for the types which haven't been asked about before.
+    RequestAuthorization {
+        read: Vec<HealthDataType>,
+        write: Vec<HealthDataType>,
+    },
+    Query(SampleQuery),
+    Save(Vec<SampleData>),
+    /// Send the changes to a data type since the anchor (or all its samples if `None`), then
+    /// keep sending changes until told to stop
+    Observe {
+        id: ObserverId,
+        data_type: HealthDataType,
+        anchor: Option<Anchor>,
+    },
+    StopObserving {
+        id: ObserverId,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthResponse {
+    Authorized,
+    Samples(Vec<HealthSample>),
+    /// The ids of the saved samples, in the order they were saved in
+    Saved(Vec<SampleId>),
+    Changes(HealthChanges),
+    /// The device has no health store
+    Unavailable,
+    /// The user hasn't given the app access to the data type
+    PermissionDenied,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for HealthOperation {
+    type Output = HealthResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum HealthError {
+    #[error("health data is not available on this device")]
+    Unavailable,
+    #[error("access to health data was denied")]
+    PermissionDenied,
+    #[error("health data operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<HealthResponse> },
+}
+
+impl From<HealthResponse> for HealthError {
+    fn from(response: HealthResponse) -> Self {
+        match response {
+            HealthResponse::Unavailable => HealthError::Unavailable,
+            HealthResponse::PermissionDenied => HealthError::PermissionDenied,
+            HealthResponse::Error { message } => HealthError::Shell { message },
+            response => HealthError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// Shared by all the instances of the capability, including the ones mapped for composed apps
+static STOPPED: Mutex<BTreeSet<ObserverId>> = Mutex::new(BTreeSet::new());
+
+/// The Health capability API
+///
+/// This capability lets the app read and write health and fitness samples, and observe changes
+/// to them.
+#[derive(crux_core::macros::Capability)]
+pub struct Health<Ev> {
+    context: CapabilityContext<HealthOperation, Ev>,
+}
+
+impl<Ev> Clone for Health<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Health<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<HealthOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Ask for access to `read` and `write` the data types, then send the event returned by
+    /// `callback` with the outcome.
+    ///
+    /// Health stores don't tell apps whether they were given read access, so that they can't
+    /// infer anything from it. Reading data the user didn't give access to succeeds, with no
+    /// samples.
+    pub fn request_authorization<F>(
+        &self,
+        read: Vec<HealthDataType>,
+        write: Vec<HealthDataType>,
+        callback: F,
+    ) where
+        F: FnOnce(Result<(), HealthError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let result = this.request_authorization_async(read, write).await;
+                context.update_app(callback(result));
+            }
+        });
+    }
+
+    /// Ask for access to `read` and `write` the data types.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn request_authorization_async(
+        &self,
+        read: Vec<HealthDataType>,
+        write: Vec<HealthDataType>,
+    ) -> Result<(), HealthError> {
+        match self
+            .context
+            .request_from_shell(HealthOperation::RequestAuthorization { read, write })
+            .await
+        {
+            HealthResponse::Authorized => Ok(()),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Query samples, then send the event returned by `callback` with them.
+    pub fn query<F>(&self, query: SampleQuery, callback: F)
+    where
+        F: FnOnce(Result<Vec<HealthSample>, HealthError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.query_async(query).await));
+            }
+        });
+    }
+
+    /// Query samples.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn query_async(&self, query: SampleQuery) -> Result<Vec<HealthSample>, HealthError> {
+        match self
+            .context
+            .request_from_shell(HealthOperation::Query(query))
+            .await
+        {
+            HealthResponse::Samples(samples) => Ok(samples),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Write samples to the health store, then send the event returned by `callback` with
+    /// their ids.
+    pub fn save<F>(&self, samples: Vec<SampleData>, callback: F)
+    where
+        F: FnOnce(Result<Vec<SampleId>, HealthError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.save_async(samples).await));
+            }
+        });
+    }
+
+    /// Write samples to the health store.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn save_async(&self, samples: Vec<SampleData>) -> Result<Vec<SampleId>, HealthError> {
+        match self
+            .context
+            .request_from_shell(HealthOperation::Save(samples))
+            .await
+        {
+            HealthResponse::Saved(ids) => Ok(ids),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Observe the changes to a data type since the `anchor`, or all of its samples if `None`,
+    /// passing each set of changes to the app, wrapped in the event produced by the
+    /// `callback`. If the data type can't be observed, the callback is called with the error,
+    /// and no more changes follow.
+    ///
+    /// Returns the id to [stop observing](Self::stop_observing) with.
+    pub fn observe<F>(
+        &self,
+        data_type: HealthDataType,
+        anchor: Option<Anchor>,
+        callback: F,
+    ) -> ObserverId
+    where
+        F: Fn(Result<HealthChanges, HealthError>) -> Ev + Send + Sync + 'static,
+    {
+        let id = ObserverId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(HealthOperation::Observe {
+                    id,
+                    data_type,
+                    anchor,
+                });
+
+                while let Some(response) = stream.next().await {
+                    if STOPPED.lock().unwrap().remove(&id) {
+                        break;
+                    }
+
+                    let changes = match response {
+                        HealthResponse::Changes(changes) => Ok(changes),
+                        response => Err(HealthError::from(response)),
+                    };
+                    let failed = changes.is_err();
+
+                    context.update_app(callback(changes));
+
+                    if failed {
+                        break;
+                    }
+                }
+            }
+        });
+
+        id
+    }
+
+    /// Stop observing. No more of the observer's changes are passed to the app.
+    pub fn stop_observing(&self, id: ObserverId) {
+        STOPPED.lock().unwrap().insert(id);
+
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context
+                    .notify_shell(HealthOperation::StopObserving { id })
+                    .await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = HealthOperation::Save(vec![
+            SampleData::Steps(StepCount {
+                count: 120,
+                start: 1_000,
+                end: 61_000,
+            }),
+            SampleData::Workout(Workout {
+                activity: WorkoutActivity::StrengthTraining,
+                start: 0,
+                end: 1_800_000,
+                energy_kcal: Some(250.0),
+                distance_metres: None,
+            }),
+        ]);
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"save":[{"steps":{"count":120,"start":1000,"end":61000}},{"workout":{"activity":"strengthTraining","start":0,"end":1800000,"energyKcal":250.0,"distanceMetres":null}}]}"#
+        );
+
+        let deserialized: HealthOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let operation = HealthOperation::Observe {
+            id: ObserverId(1),
+            data_type: HealthDataType::HeartRate,
+            anchor: Some(Anchor("42".to_string())),
+        };
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"observe":{"id":1,"dataType":"heartRate","anchor":"42"}}"#
+        );
+    }
+
+    #[test]
+    fn test_queries_have_a_valid_range() {
+        let query = SampleQuery::new(HealthDataType::Steps, 2_000, 1_000).limit(0);
+
+        assert_eq!((query.start, query.end), (2_000, 2_000));
+        assert_eq!(query.limit, Some(1));
+    }
+}
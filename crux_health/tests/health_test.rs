@@ -0,0 +1,309 @@
+mod shared {
+    use std::collections::BTreeMap;
+
+    use crux_core::macros::Effect;
+    use crux_health::{
+        Anchor, Health, HealthChanges, HealthDataType, HealthError, ObserverId, SampleData,
+        SampleId, Workout, WorkoutActivity,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Connect,
+        Disconnect,
+        LogRun {
+            start: u64,
+            end: u64,
+        },
+
+        #[serde(skip)]
+        Authorized(Result<(), HealthError>),
+        #[serde(skip)]
+        StepsChanged(Result<HealthChanges, HealthError>),
+        #[serde(skip)]
+        Saved(Result<Vec<SampleId>, HealthError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub observer: Option<ObserverId>,
+        /// the last anchor seen, which would be persisted to continue from after a restart
+        pub anchor: Option<Anchor>,
+        pub steps: BTreeMap<SampleId, u32>,
+        pub saved: Vec<SampleId>,
+        pub error: Option<HealthError>,
+    }
+
+    impl Model {
+        pub fn total_steps(&self) -> u32 {
+            self.steps.values().sum()
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub health: Health<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Connect => caps.health.request_authorization(
+                    vec![HealthDataType::Steps],
+                    vec![HealthDataType::Workouts],
+                    Event::Authorized,
+                ),
+                Event::Authorized(Ok(())) => {
+                    let id = caps.health.observe(
+                        HealthDataType::Steps,
+                        model.anchor.clone(),
+                        Event::StepsChanged,
+                    );
+                    model.observer = Some(id);
+                }
+                Event::Disconnect => {
+                    if let Some(id) = model.observer.take() {
+                        caps.health.stop_observing(id);
+                    }
+                }
+                Event::StepsChanged(Ok(changes)) => {
+                    for id in changes.deleted {
+                        model.steps.remove(&id);
+                    }
+                    for sample in changes.added {
+                        if let SampleData::Steps(steps) = sample.data {
+                            model.steps.insert(sample.id, steps.count);
+                        }
+                    }
+                    model.anchor = Some(changes.anchor);
+                }
+                Event::LogRun { start, end } => caps.health.save(
+                    vec![SampleData::Workout(Workout {
+                        activity: WorkoutActivity::Running,
+                        start,
+                        end,
+                        energy_kcal: None,
+                        distance_metres: Some(5_000.0),
+                    })],
+                    Event::Saved,
+                ),
+                Event::Saved(Ok(ids)) => model.saved.extend(ids),
+                Event::StepsChanged(Err(error)) => {
+                    model.observer = None;
+                    model.error = Some(error);
+                }
+                Event::Authorized(Err(error)) | Event::Saved(Err(error)) => {
+                    model.error = Some(error);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_health::{
+        Anchor, HealthChanges, HealthDataType, HealthError, HealthOperation, HealthResponse,
+        HealthSample, SampleData, SampleId, StepCount,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn request(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Request<HealthOperation> {
+        let Some(Effect::Health(request)) = app.update(event, model).into_effects().next() else {
+            panic!("expected a health effect");
+        };
+
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<HealthOperation>,
+        response: HealthResponse,
+    ) -> Vec<Effect> {
+        let update = app.resolve(request, response).unwrap();
+        let mut effects = update.effects;
+        for event in update.events {
+            effects.extend(app.update(event, model).effects);
+        }
+
+        effects
+    }
+
+    fn steps(id: &str, count: u32) -> HealthSample {
+        HealthSample {
+            id: SampleId(id.to_string()),
+            source: "com.example.watch".to_string(),
+            data: SampleData::Steps(StepCount {
+                count,
+                start: 0,
+                end: 60_000,
+            }),
+        }
+    }
+
+    fn changes(added: Vec<HealthSample>, deleted: &[&str], anchor: &str) -> HealthResponse {
+        HealthResponse::Changes(HealthChanges {
+            added,
+            deleted: deleted.iter().map(|id| SampleId(id.to_string())).collect(),
+            anchor: Anchor(anchor.to_string()),
+        })
+    }
+
+    #[test]
+    fn observes_changes_from_the_anchor_until_stopped() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model {
+            anchor: Some(Anchor("7".to_string())),
+            ..Model::default()
+        };
+
+        let mut authorization = request(&app, &mut model, Event::Connect);
+        assert_eq!(
+            authorization.operation,
+            HealthOperation::RequestAuthorization {
+                read: vec![HealthDataType::Steps],
+                write: vec![HealthDataType::Workouts],
+            }
+        );
+
+        let mut effects = respond(
+            &app,
+            &mut model,
+            &mut authorization,
+            HealthResponse::Authorized,
+        );
+        let Some(Effect::Health(mut observer)) = effects.pop() else {
+            panic!("expected to observe");
+        };
+        let HealthOperation::Observe {
+            id,
+            data_type,
+            anchor,
+        } = observer.operation.clone()
+        else {
+            panic!("expected to observe");
+        };
+        assert_eq!(data_type, HealthDataType::Steps);
+        assert_eq!(anchor, Some(Anchor("7".to_string())));
+        assert_eq!(model.observer, Some(id));
+
+        respond(
+            &app,
+            &mut model,
+            &mut observer,
+            changes(vec![steps("a", 100), steps("b", 250)], &[], "8"),
+        );
+        assert_eq!(model.total_steps(), 350);
+
+        // a sample was edited in the health app: it's deleted and added again
+        respond(
+            &app,
+            &mut model,
+            &mut observer,
+            changes(vec![steps("c", 200)], &["b"], "9"),
+        );
+        assert_eq!(model.total_steps(), 300);
+        assert_eq!(model.anchor, Some(Anchor("9".to_string())));
+
+        let stop = request(&app, &mut model, Event::Disconnect);
+        assert_eq!(stop.operation, HealthOperation::StopObserving { id });
+
+        // changes already on their way are dropped
+        respond(
+            &app,
+            &mut model,
+            &mut observer,
+            changes(vec![steps("d", 1_000)], &[], "10"),
+        );
+        assert_eq!(model.total_steps(), 300);
+        assert_eq!(model.anchor, Some(Anchor("9".to_string())));
+    }
+
+    #[test]
+    fn denied_access_ends_the_observer() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut authorization = request(&app, &mut model, Event::Connect);
+        let Some(Effect::Health(mut observer)) = respond(
+            &app,
+            &mut model,
+            &mut authorization,
+            HealthResponse::Authorized,
+        )
+        .pop() else {
+            panic!("expected to observe");
+        };
+
+        respond(
+            &app,
+            &mut model,
+            &mut observer,
+            HealthResponse::PermissionDenied,
+        );
+        assert_eq!(model.error, Some(HealthError::PermissionDenied));
+        assert_eq!(model.observer, None);
+
+        // the core is no longer listening
+        assert!(app
+            .resolve(&mut observer, changes(vec![steps("a", 1)], &[], "1"))
+            .is_err());
+    }
+
+    #[test]
+    fn saves_workouts() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut save = request(
+            &app,
+            &mut model,
+            Event::LogRun {
+                start: 1_000,
+                end: 1_801_000,
+            },
+        );
+        let HealthOperation::Save(samples) = &save.operation else {
+            panic!("expected to save");
+        };
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].data_type(), HealthDataType::Workouts);
+
+        respond(
+            &app,
+            &mut model,
+            &mut save,
+            HealthResponse::Saved(vec![SampleId("run".to_string())]),
+        );
+        assert_eq!(model.saved, vec![SampleId("run".to_string())]);
+
+        let mut save = request(
+            &app,
+            &mut model,
+            Event::LogRun {
+                start: 1_000,
+                end: 1_801_000,
+            },
+        );
+        respond(&app, &mut model, &mut save, HealthResponse::Unavailable);
+        assert_eq!(model.error, Some(HealthError::Unavailable));
+    }
+}
@@ -0,0 +1,269 @@
+//! The app's badge and shortcuts
+//!
+//! The [`HomeScreen`] capability sets the badge count shown on the app's icon, and registers
+//! the dynamic shortcuts (quick actions) offered when the icon is long-pressed, so the logic
+//! deciding them, like which notifications count as unread, stays in the core.
+//!
+//! Each [`Shortcut`] carries a deep link. When the user picks a shortcut, the shell opens the
+//! app with its link, exactly as it would any other deep link into the app, so the core
+//! handles shortcuts with the same routing as links from notifications or the web.
+
+use std::collections::HashSet;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The most shortcuts which can be registered. Platforms show at most four,
+/// or fewer depending on the launcher.
+pub const MAX_SHORTCUTS: usize = 4;
+
+/// A dynamic shortcut to an activity in the app
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shortcut {
+    /// Identifies the shortcut, so the platform can tell updated shortcuts from new ones
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// The name of an icon bundled with the shell
+    pub icon: Option<String>,
+    /// The deep link the app is opened with when the shortcut is picked,
+    /// e.g. `myapp://chats/42`
+    pub link: String,
+}
+
+impl Shortcut {
+    pub fn new(id: impl Into<String>, title: impl Into<String>, link: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            subtitle: None,
+            icon: None,
+            link: link.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    #[must_use]
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HomeScreenOperation {
+    /// Show `count` on the app's badge, or hide the badge when it's zero
+    SetBadge { count: u32 },
+    /// Replace all the app's dynamic shortcuts
+    SetShortcuts { shortcuts: Vec<Shortcut> },
+}
+
+impl Operation for HomeScreenOperation {
+    type Output = ();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ShortcutError {
+    #[error("{count} shortcuts given, but at most {max} can be registered")]
+    TooMany { count: usize, max: usize },
+    #[error("more than one shortcut has the id {id}")]
+    DuplicateId { id: String },
+    #[error("shortcut link {link} is not a URL")]
+    InvalidLink { link: String },
+}
+
+fn validate(shortcuts: &[Shortcut]) -> Result<(), ShortcutError> {
+    if shortcuts.len() > MAX_SHORTCUTS {
+        return Err(ShortcutError::TooMany {
+            count: shortcuts.len(),
+            max: MAX_SHORTCUTS,
+        });
+    }
+
+    let mut ids = HashSet::new();
+    for shortcut in shortcuts {
+        if !ids.insert(shortcut.id.as_str()) {
+            return Err(ShortcutError::DuplicateId {
+                id: shortcut.id.clone(),
+            });
+        }
+
+        if !has_scheme(&shortcut.link) {
+            return Err(ShortcutError::InvalidLink {
+                link: shortcut.link.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// A URL starts with a scheme: a letter, followed by letters, digits, `+`, `-` or `.`, then `:`
+fn has_scheme(link: &str) -> bool {
+    let Some((scheme, rest)) = link.split_once(':') else {
+        return false;
+    };
+
+    let mut chars = scheme.chars();
+    let starts_with_letter = match chars.next() {
+        Some(c) => c.is_ascii_alphabetic(),
+        None => false,
+    };
+
+    starts_with_letter
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.is_empty()
+}
+
+/// The HomeScreen capability API
+///
+/// This capability lets the app set its icon's badge count and its dynamic shortcuts.
+#[derive(crux_core::macros::Capability)]
+pub struct HomeScreen<Ev> {
+    context: CapabilityContext<HomeScreenOperation, Ev>,
+}
+
+impl<Ev> Clone for HomeScreen<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> HomeScreen<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<HomeScreenOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Show `count` on the app's badge, or hide the badge when it's zero.
+    pub fn set_badge(&self, count: u32) {
+        self.context.spawn({
+            let this = self.clone();
+
+            async move {
+                this.set_badge_async(count).await;
+            }
+        });
+    }
+
+    /// Show `count` on the app's badge, or hide the badge when it's zero.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn set_badge_async(&self, count: u32) {
+        self.context
+            .notify_shell(HomeScreenOperation::SetBadge { count })
+            .await;
+    }
+
+    /// Hide the app's badge.
+    pub fn clear_badge(&self) {
+        self.set_badge(0);
+    }
+
+    /// Replace the app's dynamic shortcuts, in the order they should be shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are more than [`MAX_SHORTCUTS`], if two share an id, or if a
+    /// link isn't a URL, in which case the shortcuts are left unchanged.
+    pub fn set_shortcuts(&self, shortcuts: Vec<Shortcut>) -> Result<(), ShortcutError> {
+        validate(&shortcuts)?;
+
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context
+                    .notify_shell(HomeScreenOperation::SetShortcuts { shortcuts })
+                    .await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Replace the app's dynamic shortcuts, in the order they should be shown.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shortcuts aren't valid, as for [`Self::set_shortcuts`].
+    pub async fn set_shortcuts_async(&self, shortcuts: Vec<Shortcut>) -> Result<(), ShortcutError> {
+        validate(&shortcuts)?;
+
+        self.context
+            .notify_shell(HomeScreenOperation::SetShortcuts { shortcuts })
+            .await;
+
+        Ok(())
+    }
+
+    /// Remove all the app's dynamic shortcuts, e.g. when the user logs out.
+    pub fn clear_shortcuts(&self) {
+        self.set_shortcuts(Vec::new())
+            .expect("no shortcuts are always valid");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = HomeScreenOperation::SetShortcuts {
+            shortcuts: vec![Shortcut::new("new", "New note", "notes://new").icon("compose")],
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"setShortcuts":{"shortcuts":[{"id":"new","title":"New note","subtitle":null,"icon":"compose","link":"notes://new"}]}}"#
+        );
+
+        let deserialized: HomeScreenOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+    }
+
+    #[test]
+    fn test_validating_shortcuts() {
+        let shortcut = |id: &str| Shortcut::new(id, id, format!("notes://{id}"));
+
+        assert_eq!(validate(&[shortcut("a"), shortcut("b")]), Ok(()));
+        assert_eq!(
+            validate(&[shortcut("a"), shortcut("a")]),
+            Err(ShortcutError::DuplicateId {
+                id: "a".to_string()
+            })
+        );
+        assert_eq!(
+            validate(&vec![shortcut("a"); MAX_SHORTCUTS + 1]),
+            Err(ShortcutError::TooMany {
+                count: 5,
+                max: MAX_SHORTCUTS
+            })
+        );
+
+        for link in ["chats/42", ":42", "1app://chats", "notes:"] {
+            assert_eq!(
+                validate(&[Shortcut::new("a", "A", link)]),
+                Err(ShortcutError::InvalidLink {
+                    link: link.to_string()
+                })
+            );
+        }
+        assert!(has_scheme("https://example.com/notes/1"));
+        assert!(has_scheme("x-notes+v2://new"));
+    }
+}
@@ -0,0 +1,161 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_home_screen::{HomeScreen, Shortcut, ShortcutError};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        MessageReceived {
+            chat: u32,
+            from: String,
+        },
+        ChatRead(u32),
+        /// The app was opened with a deep link, e.g. from a shortcut
+        OpenLink(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        /// Chats with unread messages, most recent first
+        pub unread: Vec<(u32, String)>,
+        pub open_chat: Option<u32>,
+        pub error: Option<ShortcutError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub home_screen: HomeScreen<Event>,
+    }
+
+    impl App {
+        fn update_home_screen(model: &mut Model, caps: &Capabilities) {
+            caps.home_screen.set_badge(model.unread.len() as u32);
+
+            let shortcuts = model
+                .unread
+                .iter()
+                .take(3)
+                .map(|(chat, from)| {
+                    Shortcut::new(
+                        format!("chat-{chat}"),
+                        from.clone(),
+                        format!("chats://chat/{chat}"),
+                    )
+                })
+                .collect();
+            if let Err(error) = caps.home_screen.set_shortcuts(shortcuts) {
+                model.error = Some(error);
+            }
+        }
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::MessageReceived { chat, from } => {
+                    model.unread.retain(|(c, _)| *c != chat);
+                    model.unread.insert(0, (chat, from));
+                    Self::update_home_screen(model, caps);
+                }
+                Event::ChatRead(chat) => {
+                    model.unread.retain(|(c, _)| *c != chat);
+                    Self::update_home_screen(model, caps);
+                }
+                Event::OpenLink(link) => {
+                    if let Some(chat) = link
+                        .strip_prefix("chats://chat/")
+                        .and_then(|chat| chat.parse().ok())
+                    {
+                        model.open_chat = Some(chat);
+                        self.update(Event::ChatRead(chat), model, caps);
+                    }
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_home_screen::{HomeScreenOperation, Shortcut};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn operations(effects: impl Iterator<Item = Effect>) -> Vec<HomeScreenOperation> {
+        effects
+            .map(|effect| {
+                let Effect::HomeScreen(request) = effect;
+                request.operation
+            })
+            .collect()
+    }
+
+    fn receive(app: &AppTester<App, Effect>, model: &mut Model, chat: u32, from: &str) {
+        app.update(
+            Event::MessageReceived {
+                chat,
+                from: from.to_string(),
+            },
+            model,
+        );
+    }
+
+    #[test]
+    fn badge_and_shortcuts_follow_unread_chats() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        receive(&app, &mut model, 1, "Alice");
+        receive(&app, &mut model, 2, "Bob");
+        let update = app.update(
+            Event::MessageReceived {
+                chat: 1,
+                from: "Alice".to_string(),
+            },
+            &mut model,
+        );
+
+        assert_eq!(
+            operations(update.into_effects()),
+            vec![
+                HomeScreenOperation::SetBadge { count: 2 },
+                HomeScreenOperation::SetShortcuts {
+                    shortcuts: vec![
+                        Shortcut::new("chat-1", "Alice", "chats://chat/1"),
+                        Shortcut::new("chat-2", "Bob", "chats://chat/2"),
+                    ]
+                }
+            ]
+        );
+        assert_eq!(model.error, None);
+    }
+
+    #[test]
+    fn activating_a_shortcut_opens_the_chat() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        receive(&app, &mut model, 1, "Alice");
+
+        let update = app.update(Event::OpenLink("chats://chat/1".to_string()), &mut model);
+
+        assert_eq!(model.open_chat, Some(1));
+        assert_eq!(
+            operations(update.into_effects()),
+            vec![
+                HomeScreenOperation::SetBadge { count: 0 },
+                HomeScreenOperation::SetShortcuts { shortcuts: vec![] }
+            ]
+        );
+    }
+}
@@ -1,12 +1,15 @@
+use crux_core::error::CapabilityError;
 use serde::{Deserialize, Serialize};
 use thiserror::Error as ThisError;
 
+use crate::http::StatusCode;
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, ThisError, Debug)]
 pub enum HttpError {
     #[error("HTTP error {code}: {message}")]
     #[serde(skip)]
     Http {
-        code: crate::http::StatusCode,
+        code: StatusCode,
         message: String,
         body: Option<Vec<u8>>,
     },
@@ -43,6 +46,24 @@ impl From<url::ParseError> for HttpError {
     }
 }
 
+impl From<HttpError> for CapabilityError {
+    fn from(e: HttpError) -> Self {
+        match e {
+            HttpError::Http { code, message, .. } => match code {
+                StatusCode::Unauthorized | StatusCode::Forbidden => Self::PermissionDenied,
+                StatusCode::NotFound | StatusCode::Gone => Self::NotFound,
+                StatusCode::PayloadTooLarge => Self::TooLarge,
+                StatusCode::RequestTimeout | StatusCode::GatewayTimeout => Self::Timeout,
+                StatusCode::TooManyRequests | StatusCode::ServiceUnavailable => Self::Unavailable,
+                _ => Self::ShellFailure(format!("HTTP error {code}: {message}")),
+            },
+            HttpError::Json(message) | HttpError::Url(message) => Self::InvalidData(message),
+            HttpError::Io(message) => Self::ShellFailure(message),
+            HttpError::Timeout => Self::Timeout,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,10 +71,36 @@ mod tests {
     #[test]
     fn test_error_display() {
         let error = HttpError::Http {
-            code: crate::http::StatusCode::BadRequest,
+            code: StatusCode::BadRequest,
             message: "Bad Request".to_string(),
             body: None,
         };
         assert_eq!(error.to_string(), "HTTP error 400: Bad Request");
     }
+
+    #[test]
+    fn test_into_capability_error() {
+        let error = |code| HttpError::Http {
+            code,
+            message: "message".to_string(),
+            body: None,
+        };
+
+        assert_eq!(
+            CapabilityError::from(error(StatusCode::Forbidden)),
+            CapabilityError::PermissionDenied
+        );
+        assert_eq!(
+            CapabilityError::from(error(StatusCode::ServiceUnavailable)),
+            CapabilityError::Unavailable
+        );
+        assert_eq!(
+            CapabilityError::from(error(StatusCode::InternalServerError)),
+            CapabilityError::ShellFailure("HTTP error 500: message".to_string())
+        );
+        assert_eq!(
+            CapabilityError::from(HttpError::Timeout),
+            CapabilityError::Timeout
+        );
+    }
 }
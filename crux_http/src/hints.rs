@@ -0,0 +1,69 @@
+//! Hints to the shell about the requests an app is about to make, see [`HttpHints`]
+
+use crux_core::capability::CapabilityContext;
+use crux_core::macros::Capability;
+use url::Url;
+
+use crate::protocol::HttpHint;
+
+/// The capability to hint to the Shell which requests are coming soon, so that it can
+/// prepare for them, e.g. right before a checkout flow.
+///
+/// Hints are separate from the [`Http`](crate::Http) capability so that they are never
+/// mistaken for requests: they are notifications the Shell may ignore, which don't take up
+/// a slot of the requests in flight, don't time out, and are counted on their own in
+/// [`Core::metrics`](crux_core::Core::metrics).
+#[derive(Capability)]
+pub struct HttpHints<Ev> {
+    context: CapabilityContext<HttpHint, Ev>,
+}
+
+impl<Ev> Clone for HttpHints<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> HttpHints<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<HttpHint, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Hint that requests to the origin of `url` are coming soon, so that the Shell can
+    /// resolve the name and open a connection ahead of them.
+    ///
+    /// Hints are best effort, so a `url` which is malformed, or has no origin to connect
+    /// to (e.g. a `data:` URL), is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # enum Event {}
+    /// # struct Capabilities { hints: crux_http::HttpHints<Event> }
+    /// # fn update(caps: &Capabilities) {
+    /// caps.hints.preconnect("https://payments.example.com/checkout")
+    /// # }
+    /// ```
+    pub fn preconnect(&self, url: impl AsRef<str>) {
+        let Ok(url) = Url::parse(url.as_ref()) else {
+            return;
+        };
+        let origin = url.origin();
+        if !origin.is_tuple() {
+            return;
+        }
+
+        let hint = HttpHint::Preconnect {
+            origin: origin.ascii_serialization(),
+        };
+        let context = self.context.clone();
+        self.context.spawn(async move {
+            context.notify_shell(hint).await;
+        });
+    }
+}
@@ -0,0 +1,740 @@
+//! Decoding and verification of JSON Web Tokens.
+//!
+//! Available with the `jwt` feature. Tokens signed with HS256, RS256 or ES256 can be verified
+//! against a [`JwkSet`], and their claims decoded into the app's own types. A [`JwksCache`]
+//! fetches the key set from the issuer's JWKS endpoint with the [`Http`](crate::Http)
+//! capability, and keeps it for a configurable amount of time.
+//!
+//! The core has no clock of its own, so the current time (e.g. from `crux_time`) is passed in
+//! with the [`Validation`] to check the `exp` and `nbf` claims.
+//!
+//! ```rust
+//! use crux_http::jwt::{self, Jwk, JwkSet, Validation};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Profile {
+//!     name: String,
+//! }
+//!
+//! // {"alg":"HS256"}.{"sub":"1234","name":"Ada","exp":2000}, signed with "secret"
+//! let token = "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxMjM0IiwibmFtZSI6IkFkYSIsImV4cCI6MjAwMH0.\
+//!              RDzRlmXRGcSSLyLx6Q_slx0TrUnyCFczB-jphxNHDc0";
+//! let keys = JwkSet { keys: vec![Jwk::hmac(b"secret")] };
+//!
+//! let verified = jwt::verify::<Profile>(token, &keys, &Validation::new(1000)).unwrap();
+//! assert_eq!(verified.claims.custom.name, "Ada");
+//! assert_eq!(verified.claims.registered.sub.as_deref(), Some("1234"));
+//!
+//! let expired = jwt::verify::<Profile>(token, &keys, &Validation::new(3000));
+//! assert_eq!(expired.err(), Some(jwt::JwtError::Expired));
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use p256::ecdsa::signature::Verifier as _;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::{Http, HttpError};
+
+/// Error type for JWT decoding and verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum JwtError {
+    #[error("malformed token: {message}")]
+    Malformed { message: String },
+    #[error("unsupported signing algorithm")]
+    UnsupportedAlgorithm,
+    #[error("no key found to verify the token (key id {kid:?})")]
+    KeyNotFound { kid: Option<String> },
+    #[error("invalid key: {message}")]
+    InvalidKey { message: String },
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("token has expired")]
+    Expired,
+    #[error("token is not valid yet")]
+    NotYetValid,
+    #[error("token was not issued for the expected audience")]
+    InvalidAudience,
+    #[error("token was not issued by the expected issuer")]
+    InvalidIssuer,
+    #[error("token has no expiry")]
+    MissingExpiry,
+    #[error("could not fetch keys: {error}")]
+    Http { error: HttpError },
+}
+
+/// Signing algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Algorithm {
+    HS256,
+    RS256,
+    ES256,
+    /// Any other algorithm, including `none`. Tokens with unsupported algorithms never verify.
+    #[serde(other)]
+    Unsupported,
+}
+
+/// The JOSE header of a token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub alg: Algorithm,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+}
+
+/// The `aud` claim, which can be a single audience or a list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    pub fn contains(&self, audience: &str) -> bool {
+        match self {
+            Audience::One(one) => one == audience,
+            Audience::Many(many) => many.iter().any(|a| a == audience),
+        }
+    }
+}
+
+/// The registered claims of RFC 7519. Times are in seconds since the Unix epoch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredClaims {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+/// The claims of a token: the registered claims, and the app's own claims of type `C`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims<C> {
+    #[serde(flatten)]
+    pub registered: RegisteredClaims,
+    #[serde(flatten)]
+    pub custom: C,
+}
+
+/// A decoded token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token<C> {
+    pub header: Header,
+    pub claims: Claims<C>,
+}
+
+/// A JSON Web Key. Only the parameters needed for verification are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    // RSA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    // EC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    // symmetric
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k: Option<String>,
+}
+
+impl Jwk {
+    /// A symmetric key, for tokens signed with HS256
+    pub fn hmac(secret: &[u8]) -> Self {
+        Self {
+            kty: "oct".to_string(),
+            kid: None,
+            alg: Some("HS256".to_string()),
+            n: None,
+            e: None,
+            crv: None,
+            x: None,
+            y: None,
+            k: Some(URL_SAFE_NO_PAD.encode(secret)),
+        }
+    }
+
+    fn supports(&self, alg: Algorithm) -> bool {
+        let kty = match alg {
+            Algorithm::HS256 => "oct",
+            Algorithm::RS256 => "RSA",
+            Algorithm::ES256 => "EC",
+            Algorithm::Unsupported => return false,
+        };
+        let alg_matches = self.alg.as_deref().map_or(true, |a| {
+            matches!(
+                serde_json::from_value::<Algorithm>(serde_json::Value::String(a.to_string())),
+                Ok(a) if a == alg
+            )
+        });
+
+        self.kty == kty && alg_matches
+    }
+}
+
+/// A JSON Web Key Set, as served from a JWKS endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    fn find(&self, header: &Header) -> Option<&Jwk> {
+        let mut candidates = self.keys.iter().filter(|key| key.supports(header.alg));
+
+        match &header.kid {
+            Some(kid) => candidates.find(|key| key.kid.as_ref() == Some(kid)),
+            None => candidates.next(),
+        }
+    }
+}
+
+/// The checks made on the claims of a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validation {
+    now: u64,
+    leeway: u64,
+    audience: Option<String>,
+    issuer: Option<String>,
+    require_expiry: bool,
+}
+
+impl Validation {
+    /// Validate tokens at `now`, in seconds since the Unix epoch. Tokens without an expiry
+    /// are rejected, unless [`Validation::allow_missing_expiry`] is used.
+    pub fn new(now: u64) -> Self {
+        Self {
+            now,
+            leeway: 0,
+            audience: None,
+            issuer: None,
+            require_expiry: true,
+        }
+    }
+
+    /// Allow for clock skew of up to `seconds` when checking `exp` and `nbf`
+    pub fn with_leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// Require the `aud` claim to include `audience`
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Require the `iss` claim to be `issuer`
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Accept tokens which don't have an `exp` claim
+    pub fn allow_missing_expiry(mut self) -> Self {
+        self.require_expiry = false;
+        self
+    }
+
+    fn check(&self, claims: &RegisteredClaims) -> Result<(), JwtError> {
+        match claims.exp {
+            Some(exp) if self.now >= exp.saturating_add(self.leeway) => {
+                return Err(JwtError::Expired)
+            }
+            None if self.require_expiry => return Err(JwtError::MissingExpiry),
+            _ => {}
+        }
+
+        if let Some(nbf) = claims.nbf {
+            if self.now.saturating_add(self.leeway) < nbf {
+                return Err(JwtError::NotYetValid);
+            }
+        }
+
+        if let Some(audience) = &self.audience {
+            if !claims
+                .aud
+                .as_ref()
+                .map_or(false, |aud| aud.contains(audience))
+            {
+                return Err(JwtError::InvalidAudience);
+            }
+        }
+
+        if let Some(issuer) = &self.issuer {
+            if claims.iss.as_ref() != Some(issuer) {
+                return Err(JwtError::InvalidIssuer);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode a token without verifying its signature or claims. Only use this to read tokens
+/// which are verified elsewhere (e.g. by the server they are sent to).
+pub fn decode_unverified<C>(token: &str) -> Result<Token<C>, JwtError>
+where
+    C: DeserializeOwned,
+{
+    let parts = split(token)?;
+
+    Ok(Token {
+        header: decode_part(parts.header)?,
+        claims: decode_part(parts.claims)?,
+    })
+}
+
+/// Decode a token, verifying its signature with a key from `keys` and its claims with
+/// `validation`.
+pub fn verify<C>(token: &str, keys: &JwkSet, validation: &Validation) -> Result<Token<C>, JwtError>
+where
+    C: DeserializeOwned,
+{
+    let parts = split(token)?;
+    let header: Header = decode_part(parts.header)?;
+
+    if header.alg == Algorithm::Unsupported {
+        return Err(JwtError::UnsupportedAlgorithm);
+    }
+    let key = keys.find(&header).ok_or_else(|| JwtError::KeyNotFound {
+        kid: header.kid.clone(),
+    })?;
+
+    let signature = decode_base64(parts.signature)?;
+    verify_signature(header.alg, key, parts.signed.as_bytes(), &signature)?;
+
+    let claims: Claims<C> = decode_part(parts.claims)?;
+    validation.check(&claims.registered)?;
+
+    Ok(Token { header, claims })
+}
+
+/// A cache of the keys served from a JWKS endpoint.
+///
+/// Keys are fetched on first use, and again when they are older than the maximum age, or when
+/// a token refers to a key id which isn't in the cached set (e.g. because the issuer rotated its
+/// keys). Keys are refetched for unknown key ids at most once per refetch interval, so that
+/// tokens with made-up key ids can't make the app hammer the endpoint. Clones of the cache share
+/// the cached keys.
+#[derive(Clone, Debug)]
+pub struct JwksCache {
+    url: String,
+    max_age: u64,
+    refetch_interval: u64,
+    cached: Arc<Mutex<Option<(JwkSet, u64)>>>,
+}
+
+impl JwksCache {
+    /// Cache the keys served at `url` for up to `max_age` seconds, refetching them for
+    /// unknown key ids at most once a minute
+    pub fn new(url: impl Into<String>, max_age: u64) -> Self {
+        Self {
+            url: url.into(),
+            max_age,
+            refetch_interval: 60,
+            cached: Arc::default(),
+        }
+    }
+
+    /// Refetch the keys for unknown key ids at most once every `seconds`
+    #[must_use]
+    pub fn with_refetch_interval(mut self, seconds: u64) -> Self {
+        self.refetch_interval = seconds;
+        self
+    }
+
+    /// Verify `token` with the cached keys, fetching them with `http` if necessary.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn verify<C, Ev>(
+        &self,
+        http: &Http<Ev>,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<Token<C>, JwtError>
+    where
+        C: DeserializeOwned,
+        Ev: 'static,
+    {
+        let header: Header = decode_part(split(token)?.header)?;
+
+        let cached = self
+            .cached
+            .lock()
+            .expect("JwksCache Mutex was poisoned.")
+            .clone()
+            .filter(|(_, fetched_at)| validation.now.saturating_sub(*fetched_at) < self.max_age);
+
+        let keys = match cached {
+            Some((keys, _)) if keys.find(&header).is_some() => keys,
+            // too soon to refetch, so the key is not found
+            Some((keys, fetched_at))
+                if validation.now.saturating_sub(fetched_at) < self.refetch_interval =>
+            {
+                keys
+            }
+            _ => {
+                let keys = self.fetch(http).await?;
+                *self.cached.lock().expect("JwksCache Mutex was poisoned.") =
+                    Some((keys.clone(), validation.now));
+                keys
+            }
+        };
+
+        verify(token, &keys, validation)
+    }
+
+    /// Forget the cached keys
+    pub fn clear(&self) {
+        *self.cached.lock().expect("JwksCache Mutex was poisoned.") = None;
+    }
+
+    async fn fetch<Ev>(&self, http: &Http<Ev>) -> Result<JwkSet, JwtError>
+    where
+        Ev: 'static,
+    {
+        let error = |error| JwtError::Http { error };
+
+        let mut response = http.get(&self.url).send_async().await.map_err(error)?;
+        if !response.status().is_success() {
+            return Err(error(HttpError::Http {
+                code: response.status(),
+                message: format!("fetching {} failed", self.url),
+                body: None,
+            }));
+        }
+
+        response.body_json().await.map_err(error)
+    }
+}
+
+struct Parts<'a> {
+    header: &'a str,
+    claims: &'a str,
+    signature: &'a str,
+    /// header and claims, the part of the token which is signed
+    signed: &'a str,
+}
+
+fn split(token: &str) -> Result<Parts<'_>, JwtError> {
+    let malformed = || JwtError::Malformed {
+        message: "expected three dot separated parts".to_string(),
+    };
+
+    let (signed, signature) = token.rsplit_once('.').ok_or_else(malformed)?;
+    let (header, claims) = signed.split_once('.').ok_or_else(malformed)?;
+    if claims.contains('.') {
+        return Err(malformed());
+    }
+
+    Ok(Parts {
+        header,
+        claims,
+        signature,
+        signed,
+    })
+}
+
+fn decode_base64(part: &str) -> Result<Vec<u8>, JwtError> {
+    URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| JwtError::Malformed {
+            message: e.to_string(),
+        })
+}
+
+fn decode_part<T: DeserializeOwned>(part: &str) -> Result<T, JwtError> {
+    serde_json::from_slice(&decode_base64(part)?).map_err(|e| JwtError::Malformed {
+        message: e.to_string(),
+    })
+}
+
+fn key_parameter<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, JwtError> {
+    value.as_deref().ok_or_else(|| JwtError::InvalidKey {
+        message: format!("missing parameter {name}"),
+    })
+}
+
+fn invalid_key(error: impl std::fmt::Display) -> JwtError {
+    JwtError::InvalidKey {
+        message: error.to_string(),
+    }
+}
+
+fn verify_signature(
+    alg: Algorithm,
+    key: &Jwk,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), JwtError> {
+    match alg {
+        Algorithm::HS256 => {
+            let secret = decode_base64(key_parameter(&key.k, "k")?)?;
+            let mut mac = Hmac::<Sha256>::new_from_slice(&secret).map_err(invalid_key)?;
+            mac.update(message);
+
+            mac.verify_slice(signature)
+                .map_err(|_| JwtError::InvalidSignature)
+        }
+        Algorithm::RS256 => {
+            use rsa::{pkcs1v15, BigUint, RsaPublicKey};
+
+            let n = BigUint::from_bytes_be(&decode_base64(key_parameter(&key.n, "n")?)?);
+            let e = BigUint::from_bytes_be(&decode_base64(key_parameter(&key.e, "e")?)?);
+            let public_key = RsaPublicKey::new(n, e).map_err(invalid_key)?;
+
+            let signature =
+                pkcs1v15::Signature::try_from(signature).map_err(|_| JwtError::InvalidSignature)?;
+
+            pkcs1v15::VerifyingKey::<Sha256>::new(public_key)
+                .verify(message, &signature)
+                .map_err(|_| JwtError::InvalidSignature)
+        }
+        Algorithm::ES256 => {
+            use p256::{ecdsa, EncodedPoint};
+
+            if key.crv.as_deref() != Some("P-256") {
+                return Err(invalid_key("expected curve P-256"));
+            }
+            let x = decode_base64(key_parameter(&key.x, "x")?)?;
+            let y = decode_base64(key_parameter(&key.y, "y")?)?;
+            if x.len() != 32 || y.len() != 32 {
+                return Err(invalid_key("expected 32 byte coordinates"));
+            }
+
+            let point = EncodedPoint::from_affine_coordinates(
+                x.as_slice().into(),
+                y.as_slice().into(),
+                false,
+            );
+            let verifying_key =
+                ecdsa::VerifyingKey::from_encoded_point(&point).map_err(invalid_key)?;
+
+            let signature =
+                ecdsa::Signature::from_slice(signature).map_err(|_| JwtError::InvalidSignature)?;
+
+            verifying_key
+                .verify(message, &signature)
+                .map_err(|_| JwtError::InvalidSignature)
+        }
+        Algorithm::Unsupported => Err(JwtError::UnsupportedAlgorithm),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+    use serde_json::json;
+
+    use super::*;
+
+    fn encode(value: &serde_json::Value) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).unwrap())
+    }
+
+    fn hs256(claims: serde_json::Value, secret: &[u8]) -> String {
+        let signed = format!("{}.{}", encode(&json!({"alg": "HS256"})), encode(&claims));
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(signed.as_bytes());
+
+        format!(
+            "{signed}.{}",
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    fn es256_key() -> (SigningKey, Jwk) {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let point = signing_key.verifying_key().to_encoded_point(false);
+
+        let jwk = Jwk {
+            kty: "EC".to_string(),
+            kid: Some("ec-1".to_string()),
+            alg: None,
+            n: None,
+            e: None,
+            crv: Some("P-256".to_string()),
+            x: Some(URL_SAFE_NO_PAD.encode(point.x().unwrap())),
+            y: Some(URL_SAFE_NO_PAD.encode(point.y().unwrap())),
+            k: None,
+        };
+
+        (signing_key, jwk)
+    }
+
+    fn es256(claims: serde_json::Value, key: &SigningKey, kid: &str) -> String {
+        let signed = format!(
+            "{}.{}",
+            encode(&json!({"alg": "ES256", "kid": kid})),
+            encode(&claims)
+        );
+        let signature: Signature = key.sign(signed.as_bytes());
+
+        format!("{signed}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Custom {
+        role: String,
+    }
+
+    #[test]
+    fn verifies_hs256_and_decodes_claims() {
+        let token = hs256(
+            json!({"sub": "ada", "aud": ["app", "web"], "iss": "auth", "exp": 100, "role": "admin"}),
+            b"secret",
+        );
+        let keys = JwkSet {
+            keys: vec![Jwk::hmac(b"secret")],
+        };
+        let validation = Validation::new(50).with_audience("app").with_issuer("auth");
+
+        let token = verify::<Custom>(&token, &keys, &validation).unwrap();
+
+        assert_eq!(token.header.alg, Algorithm::HS256);
+        assert_eq!(token.claims.registered.sub.as_deref(), Some("ada"));
+        assert_eq!(token.claims.custom.role, "admin");
+    }
+
+    #[test]
+    fn rejects_bad_signatures() {
+        let token = hs256(json!({"exp": 100, "role": "admin"}), b"secret");
+        let keys = JwkSet {
+            keys: vec![Jwk::hmac(b"wrong")],
+        };
+
+        assert_eq!(
+            verify::<Custom>(&token, &keys, &Validation::new(0)),
+            Err(JwtError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn checks_registered_claims() {
+        let keys = JwkSet {
+            keys: vec![Jwk::hmac(b"secret")],
+        };
+        let check = |claims, validation: Validation| {
+            verify::<serde_json::Value>(&hs256(claims, b"secret"), &keys, &validation).err()
+        };
+
+        assert_eq!(
+            check(json!({"exp": 100}), Validation::new(100)),
+            Some(JwtError::Expired)
+        );
+        assert_eq!(
+            check(json!({"exp": 100}), Validation::new(100).with_leeway(5)),
+            None
+        );
+        assert_eq!(
+            check(json!({"exp": 100, "nbf": 60}), Validation::new(50)),
+            Some(JwtError::NotYetValid)
+        );
+        assert_eq!(
+            check(json!({}), Validation::new(50)),
+            Some(JwtError::MissingExpiry)
+        );
+        assert_eq!(
+            check(json!({}), Validation::new(50).allow_missing_expiry()),
+            None
+        );
+        assert_eq!(
+            check(
+                json!({"exp": 100, "aud": "web"}),
+                Validation::new(50).with_audience("app")
+            ),
+            Some(JwtError::InvalidAudience)
+        );
+        assert_eq!(
+            check(
+                json!({"exp": 100, "iss": "evil"}),
+                Validation::new(50).with_issuer("auth")
+            ),
+            Some(JwtError::InvalidIssuer)
+        );
+    }
+
+    #[test]
+    fn verifies_es256_by_key_id() {
+        let (signing_key, jwk) = es256_key();
+        let keys = JwkSet {
+            keys: vec![Jwk::hmac(b"other"), jwk],
+        };
+
+        let token = es256(json!({"exp": 100, "role": "user"}), &signing_key, "ec-1");
+        let verified = verify::<Custom>(&token, &keys, &Validation::new(0)).unwrap();
+        assert_eq!(verified.claims.custom.role, "user");
+
+        let token = es256(json!({"exp": 100, "role": "user"}), &signing_key, "ec-2");
+        assert_eq!(
+            verify::<Custom>(&token, &keys, &Validation::new(0)),
+            Err(JwtError::KeyNotFound {
+                kid: Some("ec-2".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_algorithm_confusion() {
+        // an "HS256" token signed with the public parameters of an EC key must not verify
+        let (_, jwk) = es256_key();
+        let keys = JwkSet { keys: vec![jwk] };
+        let token = hs256(json!({"exp": 100}), b"anything");
+
+        assert_eq!(
+            verify::<serde_json::Value>(&token, &keys, &Validation::new(0)),
+            Err(JwtError::KeyNotFound { kid: None })
+        );
+
+        let unsigned = format!(
+            "{}.{}.",
+            encode(&json!({"alg": "none"})),
+            encode(&json!({"exp": 100}))
+        );
+        assert_eq!(
+            verify::<serde_json::Value>(&unsigned, &keys, &Validation::new(0)),
+            Err(JwtError::UnsupportedAlgorithm)
+        );
+    }
+
+    #[test]
+    fn decodes_without_verifying() {
+        let token = hs256(json!({"sub": "ada", "role": "admin"}), b"secret");
+        let decoded = decode_unverified::<Custom>(&token).unwrap();
+
+        assert_eq!(decoded.claims.custom.role, "admin");
+        assert!(matches!(
+            decode_unverified::<Custom>("not-a-token"),
+            Err(JwtError::Malformed { .. })
+        ));
+    }
+}
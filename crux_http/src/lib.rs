@@ -13,13 +13,19 @@ use url::Url;
 mod config;
 mod error;
 mod expect;
+mod hints;
+mod range;
 mod request;
 mod request_builder;
 mod response;
 
 pub mod client;
+#[cfg(feature = "jwt")]
+pub mod jwt;
 pub mod middleware;
 pub mod protocol;
+#[cfg(feature = "resumable")]
+pub mod resumable;
 pub mod testing;
 
 pub use http_types::{self as http};
@@ -27,8 +33,10 @@ pub use http_types::{self as http};
 pub use self::{
     config::Config,
     error::HttpError,
+    hints::HttpHints,
+    range::{ByteRange, ContentRange, InvalidContentRange},
     request::Request,
-    request_builder::RequestBuilder,
+    request_builder::{RequestBuilder, IDEMPOTENCY_KEY},
     response::{Response, ResponseAsync},
 };
 
@@ -63,6 +71,17 @@ where
         }
     }
 
+    /// A copy of this capability which logs each request it sends, and the response,
+    /// with the `logger` (see [`middleware::WireLogger`]).
+    #[cfg(feature = "log")]
+    #[must_use]
+    pub fn with_wire_logger(&self, logger: middleware::WireLogger<Ev>) -> Self {
+        Self {
+            context: self.context.clone(),
+            client: self.client.clone().with(logger),
+        }
+    }
+
     /// Instruct the Shell to perform a HTTP GET request to the provided `url`.
     ///
     /// The request can be configured via associated functions on `RequestBuilder`
@@ -53,8 +53,12 @@ use std::sync::Arc;
 use crate::{Client, Request, ResponseAsync, Result};
 
 mod redirect;
+#[cfg(feature = "log")]
+mod wire_log;
 
 pub use redirect::Redirect;
+#[cfg(feature = "log")]
+pub use wire_log::WireLogger;
 
 use async_trait::async_trait;
 use futures_util::future::BoxFuture;
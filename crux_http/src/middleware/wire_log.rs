@@ -0,0 +1,177 @@
+//! HTTP wire logging middleware.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # enum Event { ReceiveResponse(crux_http::Result<crux_http::Response<Vec<u8>>>) }
+//! # struct Capabilities { http: crux_http::Http<Event>, log: crux_log::Log<Event> }
+//! # fn update(caps: &Capabilities) {
+//! use crux_http::middleware::WireLogger;
+//!
+//! let logger = WireLogger::new(caps.log.clone())
+//!     .with_bodies(1024)
+//!     .redact_header("x-api-key");
+//!
+//! caps.http
+//!     .with_wire_logger(logger)
+//!     .get("https://httpbin.org/get")
+//!     .send(Event::ReceiveResponse)
+//! # }
+//! ```
+
+use std::time::Instant;
+
+use crux_log::{Log, LogLevel};
+
+use crate::http::headers;
+use crate::middleware::{Middleware, Next, Request};
+use crate::{Client, ResponseAsync, Result};
+
+const TARGET: &str = "crux_http";
+const REDACTED: &str = "[redacted]";
+
+/// A middleware which writes each request and its response to the [`Log`] capability:
+/// the method, URL, status and how long the request took, with the headers and,
+/// optionally, the start of the bodies.
+///
+/// The values of headers carrying credentials (`Authorization`, `Proxy-Authorization`,
+/// `Cookie` and `Set-Cookie`) are redacted, as are any added with
+/// [`WireLogger::redact_header`].
+pub struct WireLogger<Ev> {
+    log: Log<Ev>,
+    body_limit: Option<usize>,
+    redacted_headers: Vec<String>,
+}
+
+impl<Ev> WireLogger<Ev>
+where
+    Ev: 'static,
+{
+    /// Log to `log`, without the bodies.
+    pub fn new(log: Log<Ev>) -> Self {
+        let redacted_headers = [
+            "authorization",
+            "proxy-authorization",
+            "cookie",
+            "set-cookie",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        Self {
+            log,
+            body_limit: None,
+            redacted_headers,
+        }
+    }
+
+    /// Also log the request and response bodies, truncated to `limit` bytes.
+    #[must_use]
+    pub fn with_bodies(mut self, limit: usize) -> Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    /// Redact the value of the header `name` (case insensitive), e.g. an API key.
+    #[must_use]
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redacted_headers.push(name.into().to_lowercase());
+        self
+    }
+
+    fn headers(&self, headers: headers::Iter<'_>) -> String {
+        // sorted, as the order of the headers isn't stable
+        let mut headers: Vec<_> = headers.collect();
+        headers.sort_by_key(|(name, _)| name.as_str());
+
+        let mut lines = String::new();
+        for (name, values) in headers {
+            let name = name.as_str();
+            let redacted = self.redacted_headers.iter().any(|r| r == name);
+
+            for value in values.iter() {
+                let value = if redacted { REDACTED } else { value.as_str() };
+                lines.push_str(&format!("\n  {name}: {value}"));
+            }
+        }
+
+        lines
+    }
+
+    fn body(&self, body: &[u8]) -> String {
+        let Some(limit) = self.body_limit else {
+            return String::new();
+        };
+        if body.is_empty() {
+            return String::new();
+        }
+
+        let shown = &body[..body.len().min(limit)];
+        let mut line = format!("\n  body: {}", String::from_utf8_lossy(shown));
+        if body.len() > limit {
+            line.push_str(&format!("... ({} bytes)", body.len()));
+        }
+
+        line
+    }
+}
+
+#[async_trait::async_trait]
+impl<Ev> Middleware for WireLogger<Ev>
+where
+    Ev: 'static,
+{
+    async fn handle(
+        &self,
+        mut req: Request,
+        client: Client,
+        next: Next<'_>,
+    ) -> Result<ResponseAsync> {
+        let method = req.method();
+        let url = req.url().clone();
+
+        let mut message = format!("--> {method} {url}{}", self.headers(req.iter()));
+        if self.body_limit.is_some() {
+            let body = req.take_body().into_bytes().await?;
+            message.push_str(&self.body(&body));
+            req.set_body(body);
+        }
+        self.log.debug(TARGET, message);
+
+        let started = Instant::now();
+        let result = next.run(req, client).await;
+        let elapsed = started.elapsed().as_millis();
+
+        let mut res = match result {
+            Ok(res) => res,
+            Err(e) => {
+                let message = format!("<-- {method} {url} failed ({elapsed}ms): {e}");
+                self.log.error(TARGET, message);
+
+                return Err(e);
+            }
+        };
+
+        let status = res.status();
+        let mut message = format!(
+            "<-- {} {method} {url} ({elapsed}ms){}",
+            u16::from(status),
+            self.headers(res.iter())
+        );
+        if self.body_limit.is_some() {
+            let body = res.body_bytes().await?;
+            message.push_str(&self.body(&body));
+            res.set_body(body);
+        }
+
+        let level = if status.is_client_error() || status.is_server_error() {
+            LogLevel::Warn
+        } else {
+            LogLevel::Info
+        };
+        self.log.log(level, TARGET, message);
+
+        Ok(res)
+    }
+}
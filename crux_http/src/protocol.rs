@@ -138,6 +138,26 @@ impl crux_core::capability::Operation for HttpRequest {
     type Output = HttpResult;
 }
 
+impl crux_core::capability::TimesOut for HttpRequest {
+    fn timed_out(_timeout: crux_core::capability::ShellTimeout) -> HttpResult {
+        HttpResult::Err(HttpError::Timeout)
+    }
+}
+
+/// A hint about requests the app is about to make, sent by [`HttpHints`](crate::HttpHints).
+/// The shell may act on it however suits the platform, or ignore it, and never resolves it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HttpHint {
+    /// Resolve the name of the `origin`, e.g. `https://example.com`, and open a connection
+    /// to it
+    Preconnect { origin: String },
+}
+
+impl crux_core::capability::Operation for HttpHint {
+    type Output = ();
+}
+
 #[async_trait]
 pub(crate) trait EffectSender {
     async fn send(&self, effect: HttpRequest) -> HttpResult;
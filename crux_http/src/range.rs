@@ -0,0 +1,164 @@
+//! Byte ranges, for requesting part of a resource with [`RequestBuilder::range`](crate::RequestBuilder::range)
+
+use std::{
+    fmt,
+    ops::{RangeFrom, RangeInclusive},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The bytes to request with a `Range` header, from `first` to `last` inclusive, or to the
+/// end of the resource if `last` is `None`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteRange {
+    pub first: u64,
+    pub last: Option<u64>,
+}
+
+impl ByteRange {
+    /// The bytes from `first` to the end of the resource.
+    pub fn starting_at(first: u64) -> Self {
+        Self { first, last: None }
+    }
+
+    /// The bytes from `first` to `last` inclusive.
+    pub fn new(first: u64, last: u64) -> Self {
+        Self {
+            first,
+            last: Some(last),
+        }
+    }
+}
+
+impl From<RangeFrom<u64>> for ByteRange {
+    fn from(range: RangeFrom<u64>) -> Self {
+        Self::starting_at(range.start)
+    }
+}
+
+impl From<RangeInclusive<u64>> for ByteRange {
+    fn from(range: RangeInclusive<u64>) -> Self {
+        Self::new(*range.start(), *range.end())
+    }
+}
+
+/// Formats the value of the `Range` header, e.g. `bytes=100-199`
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.last {
+            Some(last) => write!(f, "bytes={}-{}", self.first, last),
+            None => write!(f, "bytes={}-", self.first),
+        }
+    }
+}
+
+/// The bytes a partial response holds, from its `Content-Range` header, e.g.
+/// `bytes 100-199/1000`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRange {
+    /// The first byte in the response
+    pub first: u64,
+    /// The last byte in the response, inclusive
+    pub last: u64,
+    /// The length of the whole resource, if the server knows it
+    pub complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    /// The number of bytes in the response, a content range always has at least one
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.last - self.first + 1
+    }
+
+    /// Whether the response holds the end of the resource, as far as the server knows
+    pub fn is_last(&self) -> bool {
+        self.complete_length
+            .map_or(false, |length| self.last + 1 >= length)
+    }
+}
+
+/// The `Content-Range` header couldn't be parsed, or described an unsatisfiable range
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("invalid content range: {0}")]
+pub struct InvalidContentRange(String);
+
+impl FromStr for ContentRange {
+    type Err = InvalidContentRange;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidContentRange(value.to_string());
+
+        let (range, complete_length) = value
+            .trim()
+            .strip_prefix("bytes ")
+            .and_then(|rest| rest.split_once('/'))
+            .ok_or_else(invalid)?;
+        let (first, last) = range.split_once('-').ok_or_else(invalid)?;
+
+        let first: u64 = first.parse().map_err(|_| invalid())?;
+        let last: u64 = last.parse().map_err(|_| invalid())?;
+        let complete_length = match complete_length {
+            "*" => None,
+            length => Some(length.parse().map_err(|_| invalid())?),
+        };
+
+        if last < first || complete_length.map_or(false, |length| last >= length) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            first,
+            last,
+            complete_length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_range_headers() {
+        assert_eq!(ByteRange::from(100..).to_string(), "bytes=100-");
+        assert_eq!(ByteRange::from(0..=499).to_string(), "bytes=0-499");
+    }
+
+    #[test]
+    fn parses_content_ranges() {
+        let range: ContentRange = "bytes 0-499/1234".parse().unwrap();
+        assert_eq!(
+            range,
+            ContentRange {
+                first: 0,
+                last: 499,
+                complete_length: Some(1234)
+            }
+        );
+        assert_eq!(range.len(), 500);
+        assert!(!range.is_last());
+
+        let range: ContentRange = "bytes 500-1233/1234".parse().unwrap();
+        assert!(range.is_last());
+
+        let range: ContentRange = "bytes 500-999/*".parse().unwrap();
+        assert_eq!(range.complete_length, None);
+        assert!(!range.is_last());
+    }
+
+    #[test]
+    fn rejects_invalid_content_ranges() {
+        for value in [
+            "bytes */1234",
+            "bytes 10-5/100",
+            "bytes 0-100/100",
+            "items 0-5/10",
+            "",
+        ] {
+            assert!(value.parse::<ContentRange>().is_err(), "{value}");
+        }
+    }
+}
@@ -7,14 +7,20 @@ use crate::{
         Body, Method, Mime, Url,
     },
 };
-use crate::{Client, HttpError, Request, Response, ResponseAsync, Result};
+use crate::{
+    protocol::HttpRequest, ByteRange, Client, HttpError, Request, Response, ResponseAsync, Result,
+};
 
-use futures_util::future::BoxFuture;
+use crux_core::capability::{AbortHandle, CapabilityContext, IdempotencyKey};
+use futures_util::{future::BoxFuture, Future};
 use http_types::convert::DeserializeOwned;
 use serde::Serialize;
 
 use std::{fmt, marker::PhantomData};
 
+/// The name of the header carrying the key set with [`RequestBuilder::idempotent`]
+pub const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
 /// Request Builder
 ///
 /// Provides an ergonomic way to chain the creation of a request.
@@ -102,6 +108,57 @@ where
         self
     }
 
+    /// Sets the `Idempotency-Key` header on the request to a new unique key, so that the
+    /// shell and the server can recognise retries of this request after an ambiguous
+    /// failure and apply it only once, see [`IdempotencyKey`]. Typically used with
+    /// requests which change state on the server, e.g. a `POST` or a `PUT`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # enum Event { ReceiveResponse(crux_http::Result<crux_http::Response<Vec<u8>>>) }
+    /// # struct Capabilities { http: crux_http::Http<Event> }
+    /// # fn update(caps: &Capabilities) {
+    /// caps.http
+    ///     .post("https://httpbin.org/post")
+    ///     .body_string("order".to_string())
+    ///     .idempotent()
+    ///     .send(Event::ReceiveResponse)
+    /// # }
+    /// ```
+    pub fn idempotent(self) -> Self {
+        self.idempotency_key(IdempotencyKey::new())
+    }
+
+    /// Sets the `Idempotency-Key` header on the request to `key`, like
+    /// [`idempotent`](RequestBuilder::idempotent), e.g. to reuse a key stored with an
+    /// operation the app retries after restarting.
+    pub fn idempotency_key(self, key: impl Into<IdempotencyKey>) -> Self {
+        let key = key.into();
+
+        self.header(IDEMPOTENCY_KEY, key.as_str())
+    }
+
+    /// Sets the `Range` header on the request, to get only the bytes in the `range` of the
+    /// resource. The server responds with `206 Partial Content` and the bytes it sent in
+    /// [`Response::content_range`], or with the whole resource if it doesn't support ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # enum Event { ReceiveResponse(crux_http::Result<crux_http::Response<Vec<u8>>>) }
+    /// # struct Capabilities { http: crux_http::Http<Event> }
+    /// # fn update(caps: &Capabilities) {
+    /// caps.http
+    ///     .get("https://httpbin.org/range/1024")
+    ///     .range(0..=511)
+    ///     .send(Event::ReceiveResponse)
+    /// # }
+    /// ```
+    pub fn range(self, range: impl Into<ByteRange>) -> Self {
+        self.header("Range", range.into().to_string())
+    }
+
     /// Sets the Content-Type header on the request.
     ///
     /// # Examples
@@ -365,6 +422,31 @@ where
     /// When finished, the response will wrapped in an event using `make_event` and
     /// dispatched to the app's `update function.
     pub fn send<F>(self, make_event: F)
+    where
+        F: FnOnce(crate::Result<Response<ExpectBody>>) -> Event + Send + 'static,
+    {
+        let (context, task) = self.into_task(make_event);
+        context.spawn(task);
+    }
+
+    /// Sends the constructed `Request` like [`send`](Self::send), returning a handle to abort
+    /// it with. Once aborted, the response is ignored and `make_event` is never called, e.g.
+    /// for a fetch the user navigated away from.
+    pub fn send_abortable<F>(self, make_event: F) -> AbortHandle
+    where
+        F: FnOnce(crate::Result<Response<ExpectBody>>) -> Event + Send + 'static,
+    {
+        let (context, task) = self.into_task(make_event);
+        context.spawn_abortable(task)
+    }
+
+    fn into_task<F>(
+        self,
+        make_event: F,
+    ) -> (
+        CapabilityContext<HttpRequest, Event>,
+        impl Future<Output = ()> + Send + 'static,
+    )
     where
         F: FnOnce(crate::Result<Response<ExpectBody>>) -> Event + Send + 'static,
     {
@@ -374,7 +456,7 @@ where
         let request = self.req;
 
         let ctx = capability.context.clone();
-        ctx.spawn(async move {
+        let task = async move {
             let result = capability.client.send(request.unwrap()).await;
 
             let resp = match result {
@@ -390,7 +472,9 @@ where
                 .and_then(|r| self.expectation.decode(r));
 
             capability.context.update_app(make_event(resp));
-        });
+        };
+
+        (ctx, task)
     }
 
     /// Sends the constructed `Request` and returns a future that resolves to [`ResponseAsync`].
@@ -4,8 +4,12 @@ use crate::http::{
     headers::{self, HeaderName, HeaderValues, ToHeaderValues},
     Mime, StatusCode, Version,
 };
+use crate::ContentRange;
 
-use http::{headers::CONTENT_TYPE, Headers};
+use http::{
+    headers::{CONTENT_RANGE, CONTENT_TYPE},
+    Headers,
+};
 use serde::de::DeserializeOwned;
 
 use std::fmt;
@@ -153,6 +157,23 @@ impl<Body> Response<Body> {
         self.header(CONTENT_TYPE)?.last().as_str().parse().ok()
     }
 
+    /// Get the bytes of the resource in a partial response to a request with a
+    /// [`range`](crate::RequestBuilder::range), from the `Content-Range` header. Returns `None`
+    /// if the header is missing or invalid, e.g. if the server sent the whole resource.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let res = crux_http::testing::ResponseBuilder::ok()
+    /// #   .header("Content-Range", "bytes 0-499/1234")
+    /// #   .build();
+    /// let range = res.content_range().unwrap();
+    /// assert_eq!((range.first, range.last, range.complete_length), (0, 499, Some(1234)));
+    /// ```
+    pub fn content_range(&self) -> Option<ContentRange> {
+        self.header(CONTENT_RANGE)?.last().as_str().parse().ok()
+    }
+
     pub fn body(&self) -> Option<&Body> {
         self.body.as_ref()
     }
@@ -14,6 +14,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use super::decode::decode_body;
+use crate::ContentRange;
 
 pin_project_lite::pin_project! {
     /// An HTTP response that exposes async methods. This is to support async
@@ -157,6 +158,16 @@ impl ResponseAsync {
         self.res.content_type()
     }
 
+    /// Get the bytes of the resource in a partial response, from the `Content-Range` header,
+    /// see [`Response::content_range`](crate::Response::content_range).
+    pub fn content_range(&self) -> Option<ContentRange> {
+        self.header(headers::CONTENT_RANGE)?
+            .last()
+            .as_str()
+            .parse()
+            .ok()
+    }
+
     /// Get the length of the body stream, if it has been set.
     ///
     /// This value is set when passing a fixed-size object into as the body.
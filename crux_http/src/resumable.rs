@@ -0,0 +1,223 @@
+//! Downloads which resume where they stopped, see [`ResumableDownload`]
+//!
+//! Large downloads, like podcast episodes or videos, are likely to fail part of the way
+//! through on a mobile connection. A [`ResumableDownload`] fetches the resource in chunks
+//! with range requests, and persists how far it got with the `crux_kv` capability after each
+//! chunk, so that after a failure, or the app restarting, it carries on from the last chunk
+//! rather than starting over.
+//!
+//! The chunks are passed to the app, which stores them, e.g. appending them to a file.
+
+use crux_kv::{error::KeyValueError, KeyValue};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{http::StatusCode, ByteRange, Http, HttpError};
+
+/// How far a download got, persisted after every chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    /// The number of bytes downloaded so far
+    pub downloaded: u64,
+    /// The length of the whole resource, once known
+    pub total: Option<u64>,
+    /// The `ETag` of the resource, so the download only resumes if the resource hasn't changed
+    pub etag: Option<String>,
+}
+
+impl DownloadProgress {
+    pub fn is_complete(&self) -> bool {
+        self.total.map_or(false, |total| self.downloaded >= total)
+    }
+}
+
+/// A chunk of the resource, to be stored by the app.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// Where the chunk starts in the resource. A chunk at offset 0 after other chunks
+    /// means the download started over, e.g. because the resource changed, and the chunks
+    /// stored before should be discarded.
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+    /// The progress of the download including this chunk
+    pub progress: DownloadProgress,
+}
+
+#[derive(Error, Debug)]
+pub enum DownloadError {
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    #[error(transparent)]
+    KeyValue(#[from] KeyValueError),
+    #[error("the download progress stored under {key} is invalid: {message}")]
+    InvalidProgress { key: String, message: String },
+}
+
+/// A download of the resource at a URL in chunks, which resumes where it stopped, see the
+/// [module docs](self).
+///
+/// # Examples
+///
+/// ```no_run
+/// # use crux_core::compose::Compose;
+/// # use crux_http::{resumable::{Chunk, ResumableDownload}, Http};
+/// # use crux_kv::KeyValue;
+/// # enum Event { Chunk(Chunk), Done, Failed(String) }
+/// # struct Capabilities { http: Http<Event>, key_value: KeyValue<Event>, compose: Compose<Event> }
+/// # fn update(caps: &Capabilities) {
+/// let download = ResumableDownload::new(
+///     &caps.http,
+///     &caps.key_value,
+///     "https://example.com/episode.mp3",
+///     "downloads/episode",
+/// );
+///
+/// caps.compose.spawn(|context| async move {
+///     loop {
+///         match download.next_chunk().await {
+///             Ok(Some(chunk)) => context.update_app(Event::Chunk(chunk)),
+///             Ok(None) => break context.update_app(Event::Done),
+///             // calling `next_chunk` again later resumes from the last chunk
+///             Err(error) => break context.update_app(Event::Failed(error.to_string())),
+///         }
+///     }
+/// });
+/// # }
+/// ```
+pub struct ResumableDownload<Ev> {
+    http: Http<Ev>,
+    key_value: KeyValue<Ev>,
+    url: String,
+    key: String,
+    chunk_size: u64,
+}
+
+impl<Ev> ResumableDownload<Ev>
+where
+    Ev: 'static,
+{
+    /// The default number of bytes to request at a time
+    pub const DEFAULT_CHUNK_SIZE: u64 = 1024 * 1024;
+
+    /// A download of the resource at `url`, persisting its progress under `key`.
+    pub fn new(
+        http: &Http<Ev>,
+        key_value: &KeyValue<Ev>,
+        url: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Self {
+        Self {
+            http: http.clone(),
+            key_value: key_value.clone(),
+            url: url.into(),
+            key: key.into(),
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Request `bytes` at a time. Panics if `bytes` is zero.
+    #[must_use]
+    pub fn with_chunk_size(mut self, bytes: u64) -> Self {
+        assert!(bytes > 0, "chunks must not be empty");
+
+        self.chunk_size = bytes;
+        self
+    }
+
+    /// How far the download got, from the progress persisted after the last chunk.
+    pub async fn progress(&self) -> Result<DownloadProgress, DownloadError> {
+        let stored = self.key_value.get_async(self.key.clone()).await?;
+        if stored.is_empty() {
+            return Ok(DownloadProgress::default());
+        }
+
+        serde_json::from_slice(&stored).map_err(|error| DownloadError::InvalidProgress {
+            key: self.key.clone(),
+            message: error.to_string(),
+        })
+    }
+
+    /// Download the chunk after the last one, persisting the progress. Returns `None` once
+    /// the whole resource was downloaded.
+    pub async fn next_chunk(&self) -> Result<Option<Chunk>, DownloadError> {
+        let progress = self.progress().await?;
+        if progress.is_complete() {
+            return Ok(None);
+        }
+
+        let first = progress.downloaded;
+        let mut request = self
+            .http
+            .get(&self.url)
+            .range(ByteRange::new(first, first + self.chunk_size - 1));
+        if let (true, Some(etag)) = (first > 0, &progress.etag) {
+            // the server sends the whole resource instead if it changed
+            request = request.header("If-Range", etag.as_str());
+        }
+
+        let mut response = request.await?;
+        let status = response.status();
+        if status == StatusCode::RequestedRangeNotSatisfiable {
+            // the last chunk ended exactly at the end of a resource of unknown length
+            let progress = DownloadProgress {
+                total: Some(first),
+                ..progress
+            };
+            self.save(&progress).await?;
+
+            return Ok(None);
+        }
+        if status.is_client_error() || status.is_server_error() {
+            return Err(HttpError::Http {
+                code: status,
+                message: status.to_string(),
+                body: response.body_bytes().await.ok(),
+            }
+            .into());
+        }
+
+        let bytes = response.body_bytes().await?;
+        let etag = response
+            .header("ETag")
+            .map(|etag| etag.last().as_str().to_string());
+        let len = bytes.len() as u64;
+
+        let (offset, mut total) = match response.content_range() {
+            Some(range) if status == StatusCode::PartialContent => {
+                (range.first, range.complete_length)
+            }
+            // the server sent the whole resource
+            _ => (0, Some(len)),
+        };
+        if total.is_none() && len < self.chunk_size {
+            total = Some(offset + len);
+        }
+
+        let progress = DownloadProgress {
+            downloaded: offset + len,
+            total,
+            etag: etag.or(progress.etag),
+        };
+        self.save(&progress).await?;
+
+        Ok(Some(Chunk {
+            offset,
+            bytes,
+            progress,
+        }))
+    }
+
+    /// Forget the progress, so that the next chunk is the first one again.
+    pub async fn reset(&self) -> Result<(), DownloadError> {
+        self.key_value.delete_async(self.key.clone()).await?;
+
+        Ok(())
+    }
+
+    async fn save(&self, progress: &DownloadProgress) -> Result<(), DownloadError> {
+        let value = serde_json::to_vec(progress).expect("progress should serialize");
+        self.key_value.set_async(self.key.clone(), value).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use crate::protocol::HttpRequest;
+
+/// Whether the `actual` request matches the `expected` one. Unlike `==`, bodies which are
+/// both JSON are compared by their values, ignoring key order and whitespace, so this
+/// can be used as a matcher, e.g. with [`HttpScenario::respond_http`](super::HttpScenario).
+pub fn requests_match(actual: &HttpRequest, expected: &HttpRequest) -> bool {
+    differences(actual, expected).is_empty()
+}
+
+/// Assert that the `actual` request matches the `expected` one, comparing JSON bodies by
+/// their values (see [`requests_match`]). On a mismatch, panics listing each difference,
+/// with the path into the body for JSON, e.g. `body at $.items[1].name: "a" != "b"`.
+#[track_caller]
+pub fn assert_request_eq(actual: &HttpRequest, expected: &HttpRequest) {
+    let differences = differences(actual, expected);
+
+    if !differences.is_empty() {
+        panic!(
+            "the requests don't match (actual != expected)\n  {}",
+            differences.join("\n  ")
+        );
+    }
+}
+
+fn differences(actual: &HttpRequest, expected: &HttpRequest) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    if actual.method != expected.method {
+        differences.push(format!(
+            "method: {:?} != {:?}",
+            actual.method, expected.method
+        ));
+    }
+    if actual.url != expected.url {
+        differences.push(format!("url: {:?} != {:?}", actual.url, expected.url));
+    }
+    if actual.headers != expected.headers {
+        differences.push(format!(
+            "headers: {:?} != {:?}",
+            actual.headers, expected.headers
+        ));
+    }
+
+    let json = (
+        serde_json::from_slice::<Value>(&actual.body),
+        serde_json::from_slice::<Value>(&expected.body),
+    );
+    match json {
+        (Ok(actual), Ok(expected)) => diff_json("$", &actual, &expected, &mut differences),
+        _ if actual.body != expected.body => differences.push(format!(
+            "body: {:?} != {:?}",
+            String::from_utf8_lossy(&actual.body),
+            String::from_utf8_lossy(&expected.body)
+        )),
+        _ => {}
+    }
+
+    differences
+}
+
+fn diff_json(path: &str, actual: &Value, expected: &Value, differences: &mut Vec<String>) {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => {
+            let keys: BTreeSet<_> = actual.keys().chain(expected.keys()).collect();
+
+            for key in keys {
+                let path = format!("{path}.{key}");
+                match (actual.get(key), expected.get(key)) {
+                    (Some(actual), Some(expected)) => {
+                        diff_json(&path, actual, expected, differences);
+                    }
+                    (Some(actual), None) => {
+                        differences.push(format!("body at {path}: unexpected {actual}"));
+                    }
+                    (None, Some(expected)) => {
+                        differences.push(format!("body at {path}: missing {expected}"));
+                    }
+                    (None, None) => unreachable!("the key comes from one of the objects"),
+                }
+            }
+        }
+        (Value::Array(actual), Value::Array(expected)) => {
+            if actual.len() != expected.len() {
+                differences.push(format!(
+                    "body at {path}: {} items != {} items",
+                    actual.len(),
+                    expected.len()
+                ));
+            }
+
+            for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+                diff_json(&format!("{path}[{index}]"), actual, expected, differences);
+            }
+        }
+        (actual, expected) if actual != expected => {
+            differences.push(format!("body at {path}: {actual} != {expected}"));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{differences, requests_match};
+    use crate::protocol::HttpRequest;
+
+    fn post(body: &str) -> HttpRequest {
+        HttpRequest::post("http://example.com/items")
+            .body(body.as_bytes().to_vec())
+            .build()
+    }
+
+    #[test]
+    fn json_bodies_match_regardless_of_key_order_and_whitespace() {
+        let actual = post(r#"{"name":"crux","tags":["a","b"],"count":1}"#);
+        let expected = post(
+            r#"{
+                "count": 1,
+                "name": "crux",
+                "tags": ["a", "b"]
+            }"#,
+        );
+
+        assert!(requests_match(&actual, &expected));
+    }
+
+    #[test]
+    fn differences_point_into_the_json_body() {
+        let actual = HttpRequest::post("http://example.com/items")
+            .json(json!({ "items": [{ "name": "a" }, { "name": "b", "extra": true }] }))
+            .build();
+        let expected = HttpRequest::put("http://example.com/items")
+            .json(json!({ "items": [{ "name": "a" }, { "name": "c" }], "page": 1 }))
+            .build();
+
+        assert_eq!(
+            differences(&actual, &expected),
+            vec![
+                r#"method: "POST" != "PUT""#,
+                "body at $.items[1].extra: unexpected true",
+                r#"body at $.items[1].name: "b" != "c""#,
+                "body at $.page: missing 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn other_bodies_are_compared_as_bytes() {
+        assert!(requests_match(&post("hello"), &post("hello")));
+        assert_eq!(
+            differences(&post("hello"), &post("hello ")),
+            vec![r#"body: "hello" != "hello ""#]
+        );
+    }
+}
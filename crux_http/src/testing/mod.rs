@@ -1,9 +1,15 @@
+mod json;
+mod network;
 mod response_builder;
+mod scenario;
 
 #[cfg(test)]
 mod fake_shell;
 
+pub use json::{assert_request_eq, requests_match};
+pub use network::{HttpSimulation, SimulatedNetwork};
 pub use response_builder::ResponseBuilder;
+pub use scenario::HttpScenario;
 
 #[cfg(test)]
 pub(crate) use fake_shell::FakeShell;
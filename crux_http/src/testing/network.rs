@@ -0,0 +1,155 @@
+use std::{ops::Range, time::Duration};
+
+use crux_core::testing::{AppTester, SoakRng};
+
+use crate::{
+    protocol::{HttpRequest, HttpResponse, HttpResult},
+    HttpError,
+};
+
+/// A server, and the network between it and the app, for testing how an app copes with slow,
+/// flaky or missing connections on the [`AppTester`]'s virtual clock, see [`HttpSimulation`].
+///
+/// Requests reach the `server` as they're made, and its responses arrive after the latency,
+/// plus the time to transfer the request and response bodies. Failures are random, but
+/// drawn from a seeded generator, so a test with the same seed sees the same failures.
+///
+/// ```rust,ignore
+/// let network = SimulatedNetwork::new(|_request| HttpResponse::ok().body("[]").build())
+///     .latency(Duration::from_millis(200))
+///     .failure_rate(0.3)
+///     .offline(Duration::from_secs(10)..Duration::from_secs(20));
+///
+/// let app = AppTester::<App, Effect>::default()
+///     .with_virtual_clock::<TimeRequest>(Duration::ZERO)
+///     .with_network(network);
+/// ```
+pub struct SimulatedNetwork {
+    server: Box<dyn FnMut(&HttpRequest) -> HttpResponse + Send>,
+    latency: Duration,
+    jitter: Duration,
+    bytes_per_second: Option<u64>,
+    failure_rate: f64,
+    offline: Vec<Range<Duration>>,
+    rng: SoakRng,
+}
+
+impl SimulatedNetwork {
+    /// A network with no latency and no failures, to the `server`
+    pub fn new<S>(server: S) -> Self
+    where
+        S: FnMut(&HttpRequest) -> HttpResponse + Send + 'static,
+    {
+        Self {
+            server: Box::new(server),
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bytes_per_second: None,
+            failure_rate: 0.0,
+            offline: Vec::new(),
+            rng: SoakRng::new(0),
+        }
+    }
+
+    /// Respond after the `latency`
+    #[must_use]
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Add up to `jitter` to the latency of each request, at random
+    #[must_use]
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Transfer the request and response bodies at `bytes_per_second`
+    #[must_use]
+    pub fn bandwidth(mut self, bytes_per_second: u64) -> Self {
+        self.bytes_per_second = Some(bytes_per_second);
+        self
+    }
+
+    /// Fail requests at random with the `rate`, from 0.0 to 1.0, after the latency
+    #[must_use]
+    pub fn failure_rate(mut self, rate: f64) -> Self {
+        self.failure_rate = rate;
+        self
+    }
+
+    /// The seed for the jitter and failures
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = SoakRng::new(seed);
+        self
+    }
+
+    /// Go offline during the `window` of virtual time (since the Unix epoch). Requests made
+    /// while offline fail straight away, and requests still waiting for their response when
+    /// the network goes offline fail then.
+    #[must_use]
+    pub fn offline(mut self, window: Range<Duration>) -> Self {
+        self.offline.push(window);
+        self
+    }
+
+    /// The result of the `request` made at the virtual time `now`, and when it arrives
+    pub fn simulate(&mut self, request: &HttpRequest, now: Duration) -> (Duration, HttpResult) {
+        if self.offline.iter().any(|window| window.contains(&now)) {
+            return (now, failure("the network is offline"));
+        }
+
+        let jitter = self.jitter.mul_f64(fraction(&mut self.rng));
+        let failed = self.rng.chance(self.failure_rate);
+        let response = (self.server)(request);
+
+        let transfer = self.bytes_per_second.map_or(Duration::ZERO, |rate| {
+            let bytes = (request.body.len() + response.body.len()) as f64;
+            Duration::from_secs_f64(bytes / rate.max(1) as f64)
+        });
+        let arrives = now + self.latency + jitter + transfer;
+
+        let lost = self
+            .offline
+            .iter()
+            .map(|window| window.start)
+            .filter(|start| *start > now && *start < arrives)
+            .min();
+
+        match (lost, failed) {
+            (Some(lost), _) => (lost, failure("the connection was lost")),
+            (None, true) => (arrives, failure("the request failed")),
+            (None, false) => (arrives, HttpResult::Ok(response)),
+        }
+    }
+}
+
+/// A number from 0.0 up to, but not including, 1.0
+fn fraction(rng: &mut SoakRng) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn failure(message: &str) -> HttpResult {
+    HttpResult::Err(HttpError::Io(format!("simulated network: {message}")))
+}
+
+/// Resolving HTTP requests with a [`SimulatedNetwork`] on the [`AppTester`]'s virtual clock
+pub trait HttpSimulation: Sized {
+    /// Resolve the app's HTTP requests with the `network`, as time passes on the virtual
+    /// clock, see [`AppTester::advance_time`]. Panics if the tester has no virtual clock yet,
+    /// see [`AppTester::with_virtual_clock`].
+    #[must_use]
+    fn with_network(self, network: SimulatedNetwork) -> Self;
+}
+
+impl<App, Ef> HttpSimulation for AppTester<App, Ef>
+where
+    App: crux_core::App,
+{
+    #[track_caller]
+    fn with_network(self, mut network: SimulatedNetwork) -> Self {
+        self.with_simulation(move |request: &HttpRequest, now| Some(network.simulate(request, now)))
+    }
+}
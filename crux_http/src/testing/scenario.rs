@@ -0,0 +1,28 @@
+use crux_core::testing::Scenario;
+
+use crate::protocol::{HttpRequest, HttpResponse, HttpResult};
+
+/// HTTP steps for a [`Scenario`]
+pub trait HttpScenario: Sized {
+    /// Respond to the first outstanding HTTP request the `matcher` accepts with the
+    /// `response`, e.g. `|request| request.method == "GET" && request.url.ends_with("/items")`
+    #[must_use]
+    fn respond_http<M>(self, matcher: M, response: HttpResponse) -> Self
+    where
+        M: Fn(&HttpRequest) -> bool + 'static;
+}
+
+impl<App, Ef> HttpScenario for Scenario<App, Ef>
+where
+    App: crux_core::App,
+    Ef: 'static,
+{
+    fn respond_http<M>(self, matcher: M, response: HttpResponse) -> Self
+    where
+        M: Fn(&HttpRequest) -> bool + 'static,
+    {
+        let description = format!("respond to an HTTP request with status {}", response.status);
+
+        self.respond_described(description, matcher, HttpResult::Ok(response))
+    }
+}
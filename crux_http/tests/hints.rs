@@ -0,0 +1,96 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_http::{Http, HttpHints};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        Checkout(&'static str),
+        Pay,
+        Paid,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        // hints don't take up the only slot
+        #[effect(max_in_flight = 1)]
+        pub http: Http<Event>,
+        pub hints: HttpHints<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = ();
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, _model: &mut (), caps: &Capabilities) {
+            match event {
+                Event::Checkout(url) => caps.hints.preconnect(url),
+                Event::Pay => caps
+                    .http
+                    .post("https://pay.example.com/card")
+                    .send(|_| Event::Paid),
+                Event::Paid => {}
+            }
+        }
+
+        fn view(&self, _model: &()) {}
+    }
+}
+
+mod tests {
+    use crux_core::Core;
+    use crux_http::protocol::HttpHint;
+
+    use crate::app::{App, Effect, Event};
+
+    #[test]
+    fn preconnect_hints_at_the_origin() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects =
+            core.process_event(Event::Checkout("https://pay.example.com/card?amount=1"));
+        let Some(Effect::HttpHints(request)) = effects.pop() else {
+            panic!("expected an HttpHints effect");
+        };
+        assert_eq!(
+            request.operation,
+            HttpHint::Preconnect {
+                origin: "https://pay.example.com".to_string()
+            }
+        );
+
+        let effects = core.process_event(Event::Pay);
+        assert!(matches!(effects[..], [Effect::Http(_)]));
+
+        let metrics = core.metrics();
+        let hints = metrics.capability("HttpHint").unwrap();
+        assert_eq!((hints.requests, hints.resolved, hints.failed), (1, 0, 0));
+        assert_eq!(metrics.capability("HttpRequest").unwrap().requests, 1);
+    }
+
+    #[test]
+    fn urls_without_an_origin_are_ignored() {
+        let core: Core<Effect, App> = Core::default();
+
+        for url in ["not a url", "data:text/plain,hello", "/card"] {
+            assert!(core.process_event(Event::Checkout(url)).is_empty());
+        }
+        assert_eq!(core.metrics().capability("HttpHint"), None);
+    }
+
+    #[test]
+    fn serializes_the_hint_for_the_shell() {
+        let hint = HttpHint::Preconnect {
+            origin: "https://pay.example.com".to_string(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(hint).unwrap(),
+            serde_json::json!({ "preconnect": { "origin": "https://pay.example.com" } })
+        );
+    }
+}
@@ -0,0 +1,165 @@
+#![cfg(feature = "jwt")]
+
+mod shared {
+    use crux_core::compose::Compose;
+    use crux_core::macros::Effect;
+    use crux_http::jwt::{JwksCache, JwtError, Token, Validation};
+    use crux_http::Http;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Verify { token: String, now: u64 },
+        Verified(Box<Result<Token<serde_json::Value>, JwtError>>),
+    }
+
+    pub struct Model {
+        pub jwks: JwksCache,
+        pub subject: Option<String>,
+        pub error: Option<JwtError>,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            Self {
+                jwks: JwksCache::new("https://auth.example.com/.well-known/jwks.json", 600),
+                subject: None,
+                error: None,
+            }
+        }
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Verify { token, now } => caps.compose.spawn(|ctx| {
+                    let http = caps.http.clone();
+                    let jwks = model.jwks.clone();
+
+                    async move {
+                        let result = jwks.verify(&http, &token, &Validation::new(now)).await;
+                        ctx.update_app(Event::Verified(Box::new(result)));
+                    }
+                }),
+                Event::Verified(result) => match *result {
+                    Ok(token) => model.subject = token.claims.registered.sub,
+                    Err(error) => model.error = Some(error),
+                },
+            }
+        }
+
+        fn view(&self, _model: &Self::Model) -> Self::ViewModel {}
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_http::jwt::JwtError;
+    use crux_http::protocol::{HttpRequest, HttpResponse, HttpResult};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    // {"alg":"HS256","kid":"k1"}.{"sub":"ada","exp":2000}, signed with "secret"
+    const TOKEN: &str = "eyJhbGciOiJIUzI1NiIsImtpZCI6ImsxIn0.eyJzdWIiOiJhZGEiLCJleHAiOjIwMDB9.\
+                         CFKln5ZeODLd38YREwfE3n2N7Nti7oLTKftarKPPCXg";
+
+    // the same token, but for key "k2", which the endpoint doesn't serve
+    const UNKNOWN_KEY_TOKEN: &str = "eyJhbGciOiJIUzI1NiIsImtpZCI6ImsyIn0.\
+                                     eyJzdWIiOiJhZGEiLCJleHAiOjIwMDB9.\
+                                     CFKln5ZeODLd38YREwfE3n2N7Nti7oLTKftarKPPCXg";
+
+    fn verify(app: &AppTester<App, Effect>, model: &mut Model, now: u64) -> usize {
+        verify_token(app, model, TOKEN, now)
+    }
+
+    fn verify_token(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        token: &str,
+        now: u64,
+    ) -> usize {
+        let event = Event::Verify {
+            token: token.to_string(),
+            now,
+        };
+        let mut update = app.update(event, model);
+        let mut fetches = 0;
+
+        while let Some(Effect::Http(mut request)) = update.effects.pop() {
+            assert_eq!(
+                request.operation,
+                HttpRequest::get("https://auth.example.com/.well-known/jwks.json").build()
+            );
+            fetches += 1;
+
+            let jwks = serde_json::json!({"keys": [{"kty": "oct", "kid": "k1", "k": "c2VjcmV0"}]});
+            update = app
+                .resolve(
+                    &mut request,
+                    HttpResult::Ok(HttpResponse::ok().json(jwks).build()),
+                )
+                .unwrap();
+        }
+
+        for event in update.events {
+            app.update(event, model);
+        }
+
+        fetches
+    }
+
+    #[test]
+    fn fetches_and_caches_keys() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        assert_eq!(verify(&app, &mut model, 1000), 1);
+        assert_eq!(model.subject.as_deref(), Some("ada"));
+
+        // cached
+        assert_eq!(verify(&app, &mut model, 1200), 0);
+
+        // stale
+        assert_eq!(verify(&app, &mut model, 1700), 1);
+    }
+
+    #[test]
+    fn refetches_for_unknown_keys_at_most_once_a_minute() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let not_found = JwtError::KeyNotFound {
+            kid: Some("k2".to_string()),
+        };
+
+        assert_eq!(verify(&app, &mut model, 1000), 1);
+
+        // just fetched
+        assert_eq!(verify_token(&app, &mut model, UNKNOWN_KEY_TOKEN, 1010), 0);
+        assert_eq!(model.error.take(), Some(not_found.clone()));
+
+        assert_eq!(verify_token(&app, &mut model, UNKNOWN_KEY_TOKEN, 1060), 1);
+        assert_eq!(model.error.take(), Some(not_found.clone()));
+
+        assert_eq!(verify_token(&app, &mut model, UNKNOWN_KEY_TOKEN, 1100), 0);
+        assert_eq!(model.error.take(), Some(not_found));
+
+        // known keys are still verified from the cache
+        assert_eq!(verify(&app, &mut model, 1100), 0);
+        assert_eq!(model.error, None);
+    }
+}
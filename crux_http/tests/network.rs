@@ -0,0 +1,197 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_http::Http;
+    use crux_time::{Duration, Time};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Fetch,
+
+        #[serde(skip)]
+        Fetched(crux_http::Result<crux_http::Response<String>>),
+        #[serde(skip)]
+        Retry,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub attempts: u32,
+        pub forecast: Option<String>,
+        pub gave_up: bool,
+    }
+
+    pub const MAX_ATTEMPTS: u32 = 5;
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Fetch | Event::Retry => {
+                    model.attempts += 1;
+                    caps.http
+                        .get("https://weather.example.com/today")
+                        .body_bytes(vec![0; 500])
+                        .expect_string()
+                        .send(Event::Fetched);
+                }
+                Event::Fetched(Ok(mut response)) => model.forecast = response.take_body(),
+                Event::Fetched(Err(_)) if model.attempts < MAX_ATTEMPTS => {
+                    // back off exponentially, from a second
+                    let delay = 1000 * 2u64.pow(model.attempts - 1);
+                    caps.time.notify_after(
+                        Duration::from_millis(delay).expect("valid duration"),
+                        |_| Event::Retry,
+                    );
+                }
+                Event::Fetched(Err(_)) => model.gave_up = true,
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub time: Time<Event>,
+    }
+}
+
+mod tests {
+    use std::time::Duration;
+
+    use crux_core::testing::AppTester;
+    use crux_http::{
+        protocol::HttpResponse,
+        testing::{HttpSimulation, SimulatedNetwork},
+    };
+    use crux_time::TimeRequest;
+
+    use crate::shared::{App, Effect, Event, Model, MAX_ATTEMPTS};
+
+    fn server() -> SimulatedNetwork {
+        SimulatedNetwork::new(|_request| HttpResponse::ok().body("Sunny").build())
+    }
+
+    fn app(network: SimulatedNetwork) -> AppTester<App, Effect> {
+        AppTester::default()
+            .with_virtual_clock::<TimeRequest>(Duration::ZERO)
+            .with_network(network)
+    }
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn responses_arrive_after_the_latency() {
+        let app = app(server().latency(millis(200)));
+        let mut model = Model::default();
+
+        let update = app.update(Event::Fetch, &mut model);
+        assert_eq!(update.effects().count(), 0);
+
+        app.advance_time(millis(199), &mut model);
+        assert_eq!(model.forecast, None);
+
+        app.advance_time(millis(1), &mut model);
+        assert_eq!(model.forecast.as_deref(), Some("Sunny"));
+        assert_eq!(model.attempts, 1);
+    }
+
+    #[test]
+    fn bodies_take_time_to_transfer() {
+        // the request's 500 bytes and the response's 5
+        let app = app(server().latency(millis(100)).bandwidth(101));
+        let mut model = Model::default();
+
+        app.update(Event::Fetch, &mut model);
+        app.advance_time(millis(5099), &mut model);
+        assert_eq!(model.forecast, None);
+
+        app.advance_time(millis(1), &mut model);
+        assert_eq!(model.forecast.as_deref(), Some("Sunny"));
+    }
+
+    #[test]
+    fn requests_fail_while_offline() {
+        let app = app(server()
+            .latency(millis(100))
+            .offline(millis(0)..millis(2500)));
+        let mut model = Model::default();
+
+        // fails straight away, then at 1s, and succeeds after the retry at 3s
+        app.update(Event::Fetch, &mut model);
+        app.advance_time(millis(3000), &mut model);
+        assert_eq!(model.attempts, 3);
+        assert_eq!(model.forecast, None);
+
+        app.advance_time(millis(100), &mut model);
+        assert_eq!(model.forecast.as_deref(), Some("Sunny"));
+    }
+
+    #[test]
+    fn requests_in_flight_fail_when_the_network_goes_offline() {
+        let app = app(server()
+            .latency(millis(500))
+            .offline(millis(300)..millis(600)));
+        let mut model = Model::default();
+
+        app.update(Event::Fetch, &mut model);
+        app.advance_time(millis(300), &mut model);
+        assert_eq!(model.forecast, None);
+
+        // retried after a second, once back online
+        app.advance_time(millis(1500), &mut model);
+        assert_eq!(model.attempts, 2);
+        assert_eq!(model.forecast.as_deref(), Some("Sunny"));
+    }
+
+    #[test]
+    fn failures_are_the_same_for_the_same_seed() {
+        let attempts = |seed| {
+            let app = app(server().latency(millis(50)).failure_rate(0.5).seed(seed));
+            let mut model = Model::default();
+
+            app.update(Event::Fetch, &mut model);
+            app.advance_time(Duration::from_secs(60), &mut model);
+            assert!(model.forecast.is_some() || model.gave_up);
+
+            model.attempts
+        };
+
+        let seeds = 0..20;
+        let first: Vec<_> = seeds.clone().map(attempts).collect();
+        let second: Vec<_> = seeds.map(attempts).collect();
+
+        assert_eq!(first, second);
+        assert!(first.iter().any(|attempts| *attempts > 1));
+        assert!(first.iter().all(|attempts| *attempts <= MAX_ATTEMPTS));
+    }
+
+    #[test]
+    fn all_requests_fail_at_a_failure_rate_of_one() {
+        let app = app(server().failure_rate(1.0));
+        let mut model = Model::default();
+
+        app.update(Event::Fetch, &mut model);
+        app.advance_time(Duration::from_secs(60), &mut model);
+
+        assert_eq!(model.attempts, MAX_ATTEMPTS);
+        assert!(model.gave_up);
+    }
+
+    #[test]
+    #[should_panic(expected = "with_simulation needs a virtual clock")]
+    fn needs_a_virtual_clock() {
+        let _ = AppTester::<App, Effect>::default().with_network(server());
+    }
+}
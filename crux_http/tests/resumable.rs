@@ -0,0 +1,260 @@
+#![cfg(feature = "resumable")]
+
+mod app {
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_http::{
+        resumable::{Chunk, ResumableDownload},
+        Http,
+    };
+    use crux_kv::KeyValue;
+    use serde::{Deserialize, Serialize};
+
+    pub const URL: &str = "http://example.com/episode.mp3";
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Download,
+        #[serde(skip)]
+        Chunk(Chunk),
+        Done,
+        Failed(String),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub file: Vec<u8>,
+        pub done: bool,
+        pub error: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub key_value: KeyValue<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = usize;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Download => {
+                    let download =
+                        ResumableDownload::new(&caps.http, &caps.key_value, URL, "episode")
+                            .with_chunk_size(4);
+
+                    caps.compose.spawn(|context| async move {
+                        loop {
+                            match download.next_chunk().await {
+                                Ok(Some(chunk)) => context.update_app(Event::Chunk(chunk)),
+                                Ok(None) => break context.update_app(Event::Done),
+                                Err(error) => {
+                                    break context.update_app(Event::Failed(error.to_string()))
+                                }
+                            }
+                        }
+                    });
+                }
+                Event::Chunk(chunk) => {
+                    model.file.truncate(chunk.offset as usize);
+                    model.file.extend(chunk.bytes);
+                }
+                Event::Done => model.done = true,
+                Event::Failed(error) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, model: &Model) -> usize {
+            model.file.len()
+        }
+    }
+}
+
+mod tests {
+    use std::collections::HashMap;
+
+    use crux_core::Core;
+    use crux_http::{
+        protocol::{HttpRequest, HttpResponse, HttpResult},
+        ByteRange,
+    };
+    use crux_kv::{KeyValueOperation, KeyValueResponse, KeyValueResult};
+
+    use crate::app::{App, Effect, Event};
+
+    const EPISODE: &[u8] = b"0123456789";
+
+    struct Shell {
+        core: Core<Effect, App>,
+        store: HashMap<String, Vec<u8>>,
+        requests: Vec<String>,
+    }
+
+    impl Shell {
+        fn new() -> Self {
+            Self {
+                core: Core::default(),
+                store: HashMap::new(),
+                requests: Vec::new(),
+            }
+        }
+
+        // resolve the effects, serving HTTP requests with `serve` until it returns `None`
+        fn run(&mut self, event: Event, mut serve: impl FnMut(&HttpRequest) -> Option<HttpResult>) {
+            let mut effects = self.core.process_event(event);
+
+            while let Some(effect) = effects.pop() {
+                match effect {
+                    Effect::KeyValue(mut request) => {
+                        let response = self.key_value(&request.operation);
+                        effects.extend(self.core.resolve(&mut request, response));
+                    }
+                    Effect::Http(mut request) => {
+                        let header = |name: &str| {
+                            request
+                                .operation
+                                .headers
+                                .iter()
+                                .find(|header| header.name.eq_ignore_ascii_case(name))
+                                .map(|header| header.value.clone())
+                                .unwrap_or_default()
+                        };
+                        self.requests
+                            .push(format!("{} {}", header("Range"), header("If-Range")));
+
+                        let Some(result) = serve(&request.operation) else {
+                            return;
+                        };
+                        effects.extend(self.core.resolve(&mut request, result));
+                    }
+                }
+            }
+        }
+
+        fn key_value(&mut self, operation: &KeyValueOperation) -> KeyValueResult {
+            let response = match operation {
+                KeyValueOperation::Get { key } => KeyValueResponse::Get {
+                    value: self.store.get(key).cloned().unwrap_or_default(),
+                    version: 0,
+                },
+                KeyValueOperation::Set { key, value, .. } => KeyValueResponse::Set {
+                    previous: self
+                        .store
+                        .insert(key.clone(), value.clone())
+                        .unwrap_or_default(),
+                    version: 0,
+                },
+                KeyValueOperation::Delete { key } => KeyValueResponse::Delete {
+                    previous: self.store.remove(key).unwrap_or_default(),
+                },
+                operation => panic!("unexpected operation {operation:?}"),
+            };
+
+            KeyValueResult::Ok { response }
+        }
+    }
+
+    // serve the requested range of the episode, like a server supporting range requests
+    fn partial(request: &HttpRequest, etag: &str) -> HttpResult {
+        let range = request
+            .headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case("Range"))
+            .and_then(|header| header.value.strip_prefix("bytes="))
+            .and_then(|range| range.split_once('-'))
+            .unwrap();
+        let first: usize = range.0.parse().unwrap();
+        let last = range.1.parse::<usize>().unwrap().min(EPISODE.len() - 1);
+
+        HttpResult::Ok(
+            HttpResponse::status(206)
+                .header(
+                    "Content-Range",
+                    format!("bytes {first}-{last}/{}", EPISODE.len()),
+                )
+                .header("ETag", etag)
+                .body(EPISODE[first..=last].to_vec())
+                .build(),
+        )
+    }
+
+    #[test]
+    fn downloads_in_chunks() {
+        let mut shell = Shell::new();
+
+        shell.run(Event::Download, |request| Some(partial(request, "\"v1\"")));
+
+        assert_eq!(shell.core.view(), EPISODE.len());
+        assert_eq!(
+            shell.requests,
+            vec![
+                "bytes=0-3 ".to_string(),
+                "bytes=4-7 \"v1\"".to_string(),
+                "bytes=8-11 \"v1\"".to_string(),
+            ]
+        );
+        assert_eq!(ByteRange::new(0, 3).to_string(), "bytes=0-3");
+    }
+
+    #[test]
+    fn resumes_after_a_failure() {
+        let mut shell = Shell::new();
+
+        let mut served = 0;
+        shell.run(Event::Download, |request| {
+            served += 1;
+            if served > 1 {
+                return Some(HttpResult::Err(crux_http::HttpError::Io(
+                    "connection lost".to_string(),
+                )));
+            }
+
+            Some(partial(request, "\"v1\""))
+        });
+        assert_eq!(shell.core.view(), 4);
+
+        shell.requests.clear();
+        shell.run(Event::Download, |request| Some(partial(request, "\"v1\"")));
+
+        assert_eq!(shell.core.view(), EPISODE.len());
+        assert_eq!(
+            shell.requests,
+            vec![
+                "bytes=4-7 \"v1\"".to_string(),
+                "bytes=8-11 \"v1\"".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn starts_over_when_the_resource_changed() {
+        let mut shell = Shell::new();
+
+        let mut served = 0;
+        shell.run(Event::Download, |request| {
+            served += 1;
+            (served == 1).then(|| partial(request, "\"v1\""))
+        });
+        assert_eq!(shell.core.view(), 4);
+
+        // the server ignores the range of a changed resource and sends all of it
+        shell.run(Event::Download, |_| {
+            Some(HttpResult::Ok(
+                HttpResponse::ok()
+                    .header("ETag", "\"v2\"")
+                    .body(b"abcdef".to_vec())
+                    .build(),
+            ))
+        });
+
+        assert_eq!(shell.core.view(), 6);
+    }
+}
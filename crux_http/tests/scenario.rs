@@ -0,0 +1,145 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_http::Http;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Fetch,
+        Retry,
+
+        #[serde(skip)]
+        Fetched(crux_http::Result<crux_http::Response<String>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub loading: bool,
+        pub forecast: Option<String>,
+        pub failed: bool,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct ViewModel {
+        pub text: String,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Fetch | Event::Retry => {
+                    model.loading = true;
+                    model.failed = false;
+                    caps.http
+                        .get("https://weather.example.com/today")
+                        .expect_string()
+                        .send(Event::Fetched);
+                }
+                Event::Fetched(Ok(mut response)) => {
+                    model.loading = false;
+                    model.forecast = response.take_body();
+                }
+                Event::Fetched(Err(_)) => {
+                    model.loading = false;
+                    model.failed = true;
+                }
+            }
+
+            caps.render.render();
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            let text = match (&model.forecast, model.loading, model.failed) {
+                (_, true, _) => "Loading…".to_string(),
+                (_, false, true) => "Couldn't get the forecast".to_string(),
+                (Some(forecast), false, false) => forecast.clone(),
+                (None, false, false) => String::new(),
+            };
+
+            ViewModel { text }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::Scenario;
+    use crux_http::{protocol::HttpResponse, testing::HttpScenario};
+
+    use crate::shared::{App, Effect, Event};
+
+    fn weather() -> Scenario<App, Effect> {
+        Scenario::<App, Effect>::new()
+            .titled("Retrying the forecast")
+            .event(Event::Fetch)
+            .expect_view(|view| assert_eq!(view.text, "Loading…"))
+            .respond_http(
+                |request| request.url.ends_with("/today"),
+                HttpResponse::status(503).build(),
+            )
+            .expect_view(|view| assert_eq!(view.text, "Couldn't get the forecast"))
+            .event(Event::Retry)
+            .respond_http(
+                |request| request.method == "GET",
+                HttpResponse::ok().body("Sunny").build(),
+            )
+            .expect_effect(|effect| matches!(effect, Effect::Render(_)))
+            .expect_view(|view| assert_eq!(view.text, "Sunny"))
+    }
+
+    #[test]
+    fn runs_the_flow_end_to_end() {
+        let model = weather().run();
+
+        assert_eq!(model.forecast.as_deref(), Some("Sunny"));
+        assert!(!model.loading);
+    }
+
+    #[test]
+    fn describes_the_flow() {
+        assert_eq!(
+            weather().to_string(),
+            "Retrying the forecast\n\
+             1. send the event Fetch\n\
+             2. check the view\n\
+             3. respond to an HTTP request with status 503\n\
+             4. check the view\n\
+             5. send the event Retry\n\
+             6. respond to an HTTP request with status 200\n\
+             7. check the effects\n\
+             8. check the view\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Retrying the forecast failed at step 3, respond to an HTTP request \
+                               with status 200: no outstanding request matches, out of 1 \
+                               HttpRequest requests"
+    )]
+    fn fails_when_no_request_matches() {
+        Scenario::<App, Effect>::new()
+            .titled("Retrying the forecast")
+            .event(Event::Fetch)
+            .expect_view(|view| assert_eq!(view.text, "Loading…"))
+            .respond_http(
+                |request| request.method == "POST",
+                HttpResponse::ok().build(),
+            )
+            .run();
+    }
+}
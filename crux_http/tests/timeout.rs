@@ -0,0 +1,71 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_http::Http;
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        Get,
+        Got(crux_http::Result<crux_http::Response<Vec<u8>>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub outcome: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        // times out on the next check
+        #[effect(timeout_ms = 0)]
+        pub http: Http<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Get => caps.http.get("http://example.com").send(Event::Got),
+                Event::Got(Ok(response)) => model.outcome = Some(response.status().to_string()),
+                Event::Got(Err(error)) => model.outcome = Some(error.to_string()),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Option<String> {
+            model.outcome.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::Core;
+    use crux_http::protocol::{HttpResponse, HttpResult};
+
+    use crate::app::{App, Effect, Event};
+
+    #[test]
+    fn unresolved_requests_time_out() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Get);
+        let Some(Effect::Http(mut request)) = effects.pop() else {
+            panic!("expected an Http effect");
+        };
+        assert_eq!(core.view(), None);
+
+        assert!(core.check_timeouts().is_empty());
+        assert_eq!(core.view().as_deref(), Some("Timeout"));
+
+        // resolving after the timeout has no effect
+        assert!(core
+            .resolve(&mut request, HttpResult::Ok(HttpResponse::ok().build()))
+            .is_empty());
+        assert_eq!(core.view().as_deref(), Some("Timeout"));
+    }
+}
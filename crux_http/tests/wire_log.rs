@@ -0,0 +1,148 @@
+#![cfg(feature = "log")]
+
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_http::middleware::WireLogger;
+    use crux_http::{Http, HttpError};
+    use crux_log::Log;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum Event {
+        Get,
+        Post,
+        #[serde(skip)]
+        Set(crux_http::Result<crux_http::Response<Vec<u8>>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub body: Vec<u8>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            let http = caps.http.with_wire_logger(
+                WireLogger::new(caps.log.clone())
+                    .with_bodies(16)
+                    .redact_header("X-Api-Key"),
+            );
+
+            match event {
+                Event::Get => http
+                    .get("http://example.com/items")
+                    .header("Authorization", "Bearer secret")
+                    .header("X-Api-Key", "key")
+                    .send(Event::Set),
+                Event::Post => http
+                    .post("http://example.com/items")
+                    .body_string("a body which is longer than the limit".to_string())
+                    .send(Event::Set),
+                Event::Set(response) => {
+                    model.body = match response {
+                        Ok(mut response) => response.take_body().unwrap(),
+                        Err(HttpError::Http { body, .. }) => body.unwrap_or_default(),
+                        Err(e) => panic!("{e}"),
+                    }
+                }
+            }
+        }
+
+        fn view(&self, _model: &Self::Model) -> Self::ViewModel {}
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub log: Log<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpResponse, HttpResult};
+    use crux_log::LogLevel;
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn logs(effects: impl Iterator<Item = Effect>) -> Vec<(LogLevel, String)> {
+        effects
+            .filter_map(|effect| effect.into_log())
+            .map(|request| (request.operation.level, request.operation.message))
+            .collect()
+    }
+
+    #[test]
+    fn requests_and_responses_are_logged_with_redacted_headers() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Get, &mut model);
+        let (logs_, mut http): (Vec<_>, Vec<_>) =
+            update.effects.into_iter().partition(Effect::is_log);
+
+        let lines = logs(logs_.into_iter());
+        assert_eq!(
+            lines,
+            vec![(
+                LogLevel::Debug,
+                "--> GET http://example.com/items\n  authorization: [redacted]\n  x-api-key: [redacted]"
+                    .to_string()
+            )]
+        );
+
+        let mut request = http.remove(0).into_http().unwrap();
+        let response = HttpResponse::status(404)
+            .header("set-cookie", "session=abc")
+            .body("not found")
+            .build();
+        let update = app.resolve(&mut request, HttpResult::Ok(response)).unwrap();
+
+        let lines = logs(update.effects.into_iter());
+        assert_eq!(lines.len(), 1);
+        let (level, line) = &lines[0];
+        assert_eq!(*level, LogLevel::Warn);
+        assert!(line.starts_with("<-- 404 GET http://example.com/items ("));
+        assert!(line.ends_with(
+            "ms)\n  content-type: application/octet-stream\n  set-cookie: [redacted]\n  \
+             body: not found"
+        ));
+
+        // the body is still there for the app
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(model.body, b"not found");
+    }
+
+    #[test]
+    fn bodies_are_truncated() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Post, &mut model);
+        let (logs_, http): (Vec<_>, Vec<_>) = update.effects.into_iter().partition(Effect::is_log);
+
+        let lines = logs(logs_.into_iter());
+        assert_eq!(
+            lines[0].1,
+            "--> POST http://example.com/items\n  content-type: text/plain;charset=utf-8\n  \
+             body: a body which is ... (37 bytes)"
+        );
+
+        // the shell gets the whole body
+        let request = http.into_iter().next().unwrap().into_http().unwrap();
+        assert_eq!(
+            request.operation.body,
+            b"a body which is longer than the limit"
+        );
+    }
+}
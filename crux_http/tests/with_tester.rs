@@ -16,6 +16,7 @@ mod shared {
     pub enum Event {
         Get,
         Post,
+        PlaceOrder(Option<String>),
         GetPostChain,
         ConcurrentGets,
         ComposeComplete(StatusCode),
@@ -58,6 +59,15 @@ mod shared {
                         .expect_string()
                         .send(Event::Set);
                 }
+                Event::PlaceOrder(key) => {
+                    let request = caps.http.put("http://example.com/order");
+                    let request = match key {
+                        Some(key) => request.idempotency_key(key),
+                        None => request.idempotent(),
+                    };
+
+                    request.expect_string().send(Event::Set);
+                }
                 Event::GetPostChain => caps.compose.spawn(|context| {
                     let http = caps.http.clone();
 
@@ -213,6 +223,36 @@ mod tests {
         });
     }
 
+    fn idempotency_key(request: &HttpRequest) -> Option<&str> {
+        request
+            .headers
+            .iter()
+            .find(|header| header.name == "idempotency-key")
+            .map(|header| header.value.as_str())
+    }
+
+    #[test]
+    fn idempotent_requests_carry_a_key() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut keys = Vec::new();
+        for _ in 0..2 {
+            let update = app.update(Event::PlaceOrder(None), &mut model);
+            let Effect::Http(request) = update.effects().next().unwrap();
+            keys.push(idempotency_key(&request.operation).unwrap().to_string());
+        }
+        assert_ne!(keys[0], keys[1]);
+
+        let update = app.update(Event::PlaceOrder(Some("order-1".to_string())), &mut model);
+        let Effect::Http(request) = update.effects().next().unwrap();
+        assert_eq!(idempotency_key(&request.operation), Some("order-1"));
+
+        let update = app.update(Event::Post, &mut model);
+        let Effect::Http(request) = update.effects().next().unwrap();
+        assert_eq!(idempotency_key(&request.operation), None);
+    }
+
     #[test]
     fn get_post_chain() {
         let app = AppTester::<App, _>::default();
@@ -0,0 +1,530 @@
+//! Background jobs for Crux apps
+//!
+//! A [`JobQueue`] lives in the app's model, and holds jobs of an app-defined type `J`, typically
+//! an enum describing each kind of work (e.g. uploading a photo) with the data it needs. Jobs are
+//! [enqueued](JobQueue::enqueue) from `update` and [started](JobQueue::start) as async tasks with
+//! [`Compose`], highest priority first, with at most
+//! [`max_concurrency`](JobConfig::max_concurrency) running at the same time.
+//!
+//! When a job finishes, its task sends a [`JobEvent`] back to the app, which passes it to
+//! [`JobQueue::handle`]. Failed jobs are retried with exponential [`Backoff`], using the [`Time`]
+//! capability to wait, until they run out of attempts. Both `enqueue` and `handle` return a
+//! [`JobStatusUpdate`], so the app can track the progress of its jobs.
+//!
+//! Queued jobs don't need to die with the process: the queue can be persisted with
+//! [`JobQueue::save_async`] and restored with [`JobQueue::load_async`], which re-queues any
+//! jobs which were running when the queue was saved.
+
+use std::{collections::BTreeMap, future::Future};
+
+use crux_core::compose::Compose;
+use crux_kv::{error::KeyValueError, KeyValue};
+use crux_time::{Duration, Time};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Error type for job queue persistence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum JobQueueError {
+    #[error("storage error: {error}")]
+    Storage { error: KeyValueError },
+    #[error("stored job queue could not be read: {message}")]
+    Corrupt { message: String },
+}
+
+/// Identifies a job in a queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct JobId(pub u64);
+
+/// Jobs with a higher priority are started first. Jobs with the same priority are started in the
+/// order they were enqueued.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum JobStatus {
+    /// Waiting to be started
+    Queued,
+    /// Running, for the given attempt (starting at 1)
+    Running { attempt: u32 },
+    /// The given attempt failed, and the job will be queued again after the delay
+    Retrying {
+        attempt: u32,
+        delay_ms: u64,
+        error: String,
+    },
+    /// The job completed, and was removed from the queue
+    Succeeded,
+    /// The job failed on its last attempt, and was removed from the queue
+    Failed { error: String },
+}
+
+/// A change of a job's status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatusUpdate {
+    pub id: JobId,
+    pub status: JobStatus,
+}
+
+/// Sent to the app by the queue's tasks, to be passed to [`JobQueue::handle`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum JobEvent {
+    /// A job's task finished
+    Finished {
+        id: JobId,
+        result: Result<(), String>,
+    },
+    /// A job's backoff delay has elapsed
+    RetryDue { id: JobId },
+}
+
+/// Exponential backoff between attempts of a failed job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backoff {
+    /// Delay after the first failed attempt
+    pub initial_ms: u64,
+    /// Each following delay is this many times longer than the previous one
+    pub factor: u32,
+    /// The longest delay
+    pub max_ms: u64,
+}
+
+impl Backoff {
+    /// The delay after the given failed attempt (starting at 1)
+    pub fn delay_ms(&self, attempt: u32) -> u64 {
+        let factor = u64::from(self.factor).saturating_pow(attempt.saturating_sub(1));
+
+        self.initial_ms.saturating_mul(factor).min(self.max_ms)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_ms: 1_000,
+            factor: 2,
+            max_ms: 60_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobConfig {
+    /// How many jobs can run at the same time
+    pub max_concurrency: usize,
+    /// How many times a job is attempted before it fails
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 2,
+            max_attempts: 5,
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+/// A job in the queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job<J> {
+    pub id: JobId,
+    pub job: J,
+    pub priority: Priority,
+    pub status: JobStatus,
+    /// The number of attempts started so far
+    pub attempts: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Stored<Jobs> {
+    version: u32,
+    next_id: u64,
+    jobs: Jobs,
+}
+
+/// A queue of jobs of type `J`, see the [crate documentation](crate) for an overview.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobQueue<J> {
+    config: JobConfig,
+    next_id: u64,
+    jobs: BTreeMap<JobId, Job<J>>,
+}
+
+impl<J> Default for JobQueue<J> {
+    fn default() -> Self {
+        Self::new(JobConfig::default())
+    }
+}
+
+impl<J> JobQueue<J> {
+    pub fn new(config: JobConfig) -> Self {
+        Self {
+            config,
+            next_id: 0,
+            jobs: BTreeMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &JobConfig {
+        &self.config
+    }
+
+    /// Add a job to the queue. It will be run by the next call to [`JobQueue::start`].
+    pub fn enqueue(&mut self, job: J, priority: Priority) -> JobStatusUpdate {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+
+        self.jobs.insert(
+            id,
+            Job {
+                id,
+                job,
+                priority,
+                status: JobStatus::Queued,
+                attempts: 0,
+            },
+        );
+
+        JobStatusUpdate {
+            id,
+            status: JobStatus::Queued,
+        }
+    }
+
+    /// Remove a job which isn't running from the queue. Returns whether the job was removed.
+    pub fn cancel(&mut self, id: JobId) -> bool {
+        match self.jobs.get(&id) {
+            Some(job) if !matches!(job.status, JobStatus::Running { .. }) => {
+                self.jobs.remove(&id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The status of a job still in the queue
+    pub fn status(&self, id: JobId) -> Option<&JobStatus> {
+        self.jobs.get(&id).map(|job| &job.status)
+    }
+
+    /// The jobs in the queue, in the order they were enqueued
+    pub fn jobs(&self) -> impl Iterator<Item = &Job<J>> {
+        self.jobs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// The number of jobs currently running
+    pub fn running(&self) -> usize {
+        self.jobs
+            .values()
+            .filter(|job| matches!(job.status, JobStatus::Running { .. }))
+            .count()
+    }
+
+    /// Start queued jobs, highest priority first, up to the concurrency limit. Each job is run
+    /// with `execute`, and when it finishes, `make_event` is used to send a [`JobEvent`] to the
+    /// app, which should be passed to [`JobQueue::handle`].
+    ///
+    /// Call this after enqueuing jobs and after handling job events.
+    pub fn start<Ev, F, Fut, E>(
+        &mut self,
+        compose: &Compose<Ev>,
+        mut execute: F,
+        make_event: E,
+    ) -> Vec<JobStatusUpdate>
+    where
+        J: Clone,
+        F: FnMut(J) -> Fut,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+        E: Fn(JobEvent) -> Ev + Clone + Send + 'static,
+        Ev: 'static,
+    {
+        let available = self.config.max_concurrency.saturating_sub(self.running());
+
+        let mut queued: Vec<&mut Job<J>> = self
+            .jobs
+            .values_mut()
+            .filter(|job| job.status == JobStatus::Queued)
+            .collect();
+        // stable, so jobs with the same priority stay in order
+        queued.sort_by_key(|job| std::cmp::Reverse(job.priority));
+
+        queued
+            .into_iter()
+            .take(available)
+            .map(|job| {
+                job.attempts += 1;
+                job.status = JobStatus::Running {
+                    attempt: job.attempts,
+                };
+
+                let id = job.id;
+                let task = execute(job.job.clone());
+                let make_event = make_event.clone();
+
+                compose.spawn(|ctx| async move {
+                    let result = task.await;
+                    ctx.update_app(make_event(JobEvent::Finished { id, result }));
+                });
+
+                JobStatusUpdate {
+                    id,
+                    status: job.status.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Update the queue with an event sent by one of its tasks. Failed jobs with attempts left
+    /// are retried after a backoff delay, waited for with `time`.
+    ///
+    /// Returns the job's new status, or `None` if the event is stale (e.g. the job was cancelled).
+    pub fn handle<Ev, E>(
+        &mut self,
+        event: JobEvent,
+        time: &Time<Ev>,
+        make_event: E,
+    ) -> Option<JobStatusUpdate>
+    where
+        E: Fn(JobEvent) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+    {
+        match event {
+            JobEvent::Finished { id, result } => {
+                let job = self.jobs.get_mut(&id)?;
+                let JobStatus::Running { attempt } = job.status else {
+                    return None;
+                };
+
+                let status = match result {
+                    Ok(()) => {
+                        self.jobs.remove(&id);
+                        JobStatus::Succeeded
+                    }
+                    Err(error) if attempt >= self.config.max_attempts => {
+                        self.jobs.remove(&id);
+                        JobStatus::Failed { error }
+                    }
+                    Err(error) => {
+                        let delay_ms = self.config.backoff.delay_ms(attempt);
+                        job.status = JobStatus::Retrying {
+                            attempt,
+                            delay_ms,
+                            error,
+                        };
+
+                        let delay = Duration::new(delay_ms.saturating_mul(1_000_000));
+                        time.notify_after(delay, move |_| make_event(JobEvent::RetryDue { id }));
+
+                        job.status.clone()
+                    }
+                };
+
+                Some(JobStatusUpdate { id, status })
+            }
+            JobEvent::RetryDue { id } => {
+                let job = self.jobs.get_mut(&id)?;
+                if !matches!(job.status, JobStatus::Retrying { .. }) {
+                    return None;
+                }
+                job.status = JobStatus::Queued;
+
+                Some(JobStatusUpdate {
+                    id,
+                    status: JobStatus::Queued,
+                })
+            }
+        }
+    }
+}
+
+impl<J> JobQueue<J>
+where
+    J: Serialize + DeserializeOwned,
+{
+    /// Serialize the jobs in the queue to be persisted
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let stored = Stored {
+            version: FORMAT_VERSION,
+            next_id: self.next_id,
+            jobs: self.jobs.values().collect::<Vec<_>>(),
+        };
+
+        serde_json::to_vec(&stored).expect("job queue serializes to JSON")
+    }
+
+    /// Restore a queue serialized with [`JobQueue::to_bytes`]. Jobs which were running or
+    /// waiting to be retried are queued again.
+    pub fn from_bytes(bytes: &[u8], config: JobConfig) -> Result<Self, JobQueueError> {
+        let stored: Stored<Vec<Job<J>>> =
+            serde_json::from_slice(bytes).map_err(|e| JobQueueError::Corrupt {
+                message: e.to_string(),
+            })?;
+
+        if stored.version != FORMAT_VERSION {
+            return Err(JobQueueError::Corrupt {
+                message: format!("unknown job queue format version {}", stored.version),
+            });
+        }
+
+        let jobs = stored
+            .jobs
+            .into_iter()
+            .map(|mut job| {
+                job.status = JobStatus::Queued;
+                (job.id, job)
+            })
+            .collect();
+
+        Ok(Self {
+            config,
+            next_id: stored.next_id,
+            jobs,
+        })
+    }
+
+    /// Persist the queue in the key-value store under `key`.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn save_async<Ev>(
+        &self,
+        key_value: &KeyValue<Ev>,
+        key: String,
+    ) -> Result<(), JobQueueError>
+    where
+        Ev: 'static,
+    {
+        key_value
+            .set_async(key, self.to_bytes())
+            .await
+            .map(|_| ())
+            .map_err(|error| JobQueueError::Storage { error })
+    }
+
+    /// Load a queue persisted with [`JobQueue::save_async`]. If there is no queue stored under
+    /// `key`, an empty queue is returned.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn load_async<Ev>(
+        key_value: &KeyValue<Ev>,
+        key: String,
+        config: JobConfig,
+    ) -> Result<Self, JobQueueError>
+    where
+        Ev: 'static,
+    {
+        let bytes = key_value
+            .get_async(key)
+            .await
+            .map_err(|error| JobQueueError::Storage { error })?;
+
+        if bytes.is_empty() {
+            return Ok(Self::new(config));
+        }
+
+        Self::from_bytes(&bytes, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_up_to_the_maximum() {
+        let backoff = Backoff {
+            initial_ms: 100,
+            factor: 3,
+            max_ms: 1_000,
+        };
+
+        let delays: Vec<_> = (1..=4).map(|attempt| backoff.delay_ms(attempt)).collect();
+        assert_eq!(delays, vec![100, 300, 900, 1_000]);
+
+        assert_eq!(backoff.delay_ms(u32::MAX), 1_000);
+    }
+
+    #[test]
+    fn cancels_jobs_which_are_not_running() {
+        let mut queue = JobQueue::default();
+        let update = queue.enqueue("upload", Priority::Normal);
+
+        assert!(queue.cancel(update.id));
+        assert!(!queue.cancel(update.id));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn restoring_requeues_interrupted_jobs() {
+        let mut queue = JobQueue::new(JobConfig::default());
+        let first = queue.enqueue("first".to_string(), Priority::Normal).id;
+        let second = queue.enqueue("second".to_string(), Priority::High).id;
+        queue.jobs.get_mut(&first).unwrap().status = JobStatus::Running { attempt: 1 };
+        queue.jobs.get_mut(&first).unwrap().attempts = 1;
+
+        let restored =
+            JobQueue::<String>::from_bytes(&queue.to_bytes(), JobConfig::default()).unwrap();
+
+        assert_eq!(restored.status(first), Some(&JobStatus::Queued));
+        assert_eq!(restored.jobs[&first].attempts, 1);
+        assert_eq!(restored.status(second), Some(&JobStatus::Queued));
+        assert_eq!(restored.next_id, 2);
+
+        assert!(matches!(
+            JobQueue::<String>::from_bytes(b"[]", JobConfig::default()),
+            Err(JobQueueError::Corrupt { .. })
+        ));
+    }
+
+    #[test]
+    fn serializes_in_camel_case() {
+        let status = JobStatus::Retrying {
+            attempt: 2,
+            delay_ms: 300,
+            error: "offline".to_string(),
+        };
+
+        let serialized = serde_json::to_string(&status).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"retrying":{"attempt":2,"delayMs":300,"error":"offline"}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<JobStatus>(&serialized).unwrap(),
+            status
+        );
+
+        assert_eq!(
+            serde_json::to_string(&JobConfig::default()).unwrap(),
+            r#"{"maxConcurrency":2,"maxAttempts":5,"backoff":{"initialMs":1000,"factor":2,"maxMs":60000}}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&JobEvent::RetryDue { id: JobId(1) }).unwrap(),
+            r#"{"retryDue":{"id":1}}"#
+        );
+    }
+}
@@ -0,0 +1,236 @@
+mod shared {
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_jobs::{Backoff, JobConfig, JobEvent, JobQueue, JobStatusUpdate, Priority};
+    use crux_kv::KeyValue;
+    use crux_time::Time;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum Event {
+        Enqueue(Vec<(String, Priority)>),
+        Job(JobEvent),
+    }
+
+    pub struct Model {
+        pub jobs: JobQueue<String>,
+        pub updates: Vec<JobStatusUpdate>,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            Self {
+                jobs: JobQueue::new(JobConfig {
+                    max_concurrency: 2,
+                    max_attempts: 2,
+                    backoff: Backoff {
+                        initial_ms: 100,
+                        factor: 2,
+                        max_ms: 1_000,
+                    },
+                }),
+                updates: Vec::new(),
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub key_value: KeyValue<Event>,
+        pub time: Time<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Enqueue(jobs) => {
+                    for (name, priority) in jobs {
+                        model.updates.push(model.jobs.enqueue(name, priority));
+                    }
+                }
+                Event::Job(event) => {
+                    model
+                        .updates
+                        .extend(model.jobs.handle(event, &caps.time, Event::Job));
+                }
+            }
+
+            // each job marks itself done in the key-value store
+            let key_value = caps.key_value.clone();
+            let started = model.jobs.start(
+                &caps.compose,
+                move |name| {
+                    let key_value = key_value.clone();
+                    async move {
+                        key_value
+                            .set_async(format!("done/{name}"), vec![])
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| e.to_string())
+                    }
+                },
+                Event::Job,
+            );
+            model.updates.extend(started);
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::{AppTester, Update};
+    use crux_jobs::{JobId, JobStatus, JobStatusUpdate, Priority};
+    use crux_kv::{error::KeyValueError, KeyValueOperation, KeyValueResponse, KeyValueResult};
+    use crux_time::{Duration, TimeRequest, TimeResponse};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn enqueue(jobs: &[(&str, Priority)]) -> Event {
+        Event::Enqueue(
+            jobs.iter()
+                .map(|(name, priority)| (name.to_string(), *priority))
+                .collect(),
+        )
+    }
+
+    /// The keys being written by running jobs
+    fn running(effects: &[Effect]) -> Vec<String> {
+        effects
+            .iter()
+            .filter_map(|effect| match effect {
+                Effect::KeyValue(request) => match &request.operation {
+                    KeyValueOperation::Set { key, .. } => Some(key.clone()),
+                    _ => None,
+                },
+                Effect::Time(_) => None,
+            })
+            .collect()
+    }
+
+    fn process(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        update: Update<Effect, Event>,
+    ) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        for event in update.events {
+            effects.extend(app.update(event, model).effects);
+        }
+        effects
+    }
+
+    fn finish_job(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        effect: Effect,
+        result: KeyValueResult,
+    ) -> Vec<Effect> {
+        let Effect::KeyValue(mut request) = effect else {
+            panic!("expected a key-value effect");
+        };
+        let update = app.resolve(&mut request, result).unwrap();
+
+        process(app, model, update)
+    }
+
+    fn ok() -> KeyValueResult {
+        KeyValueResult::Ok {
+            response: KeyValueResponse::Set {
+                previous: vec![],
+                version: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn runs_by_priority_within_concurrency_limit() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let jobs = [
+            ("low", Priority::Low),
+            ("normal", Priority::Normal),
+            ("high", Priority::High),
+        ];
+        let mut effects = app.update(enqueue(&jobs), &mut model).effects;
+        assert_eq!(running(&effects), vec!["done/high", "done/normal"]);
+        assert_eq!(model.jobs.running(), 2);
+
+        let mut started = finish_job(&app, &mut model, effects.remove(0), ok());
+        assert_eq!(running(&started), vec!["done/low"]);
+
+        finish_job(&app, &mut model, effects.remove(0), ok());
+        finish_job(&app, &mut model, started.remove(0), ok());
+
+        assert!(model.jobs.is_empty());
+        assert_eq!(
+            model.updates.last(),
+            Some(&JobStatusUpdate {
+                id: JobId(0),
+                status: JobStatus::Succeeded
+            })
+        );
+    }
+
+    #[test]
+    fn retries_failed_jobs_with_backoff() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut effects = app
+            .update(enqueue(&[("sync", Priority::Normal)]), &mut model)
+            .effects;
+
+        let failure = || KeyValueResult::Err {
+            error: KeyValueError::Io {
+                message: "disk full".to_string(),
+            },
+        };
+        let mut effects = finish_job(&app, &mut model, effects.remove(0), failure());
+
+        let Some(Effect::Time(mut request)) = effects.pop() else {
+            panic!("expected a time effect");
+        };
+        assert_eq!(
+            request.operation,
+            TimeRequest::NotifyAfter(Duration::from_millis(100).unwrap())
+        );
+        assert!(matches!(
+            model.jobs.status(JobId(0)),
+            Some(JobStatus::Retrying { attempt: 1, .. })
+        ));
+
+        let update = app
+            .resolve(&mut request, TimeResponse::DurationElapsed)
+            .unwrap();
+        let mut effects = process(&app, &mut model, update);
+        assert_eq!(running(&effects), vec!["done/sync"]);
+
+        // out of attempts
+        finish_job(&app, &mut model, effects.remove(0), failure());
+
+        assert!(model.jobs.is_empty());
+        let statuses: Vec<_> = model.updates.iter().map(|u| &u.status).collect();
+        assert!(matches!(
+            statuses[..],
+            [
+                JobStatus::Queued,
+                JobStatus::Running { attempt: 1 },
+                JobStatus::Retrying { delay_ms: 100, .. },
+                JobStatus::Queued,
+                JobStatus::Running { attempt: 2 },
+                JobStatus::Failed { .. },
+            ]
+        ));
+    }
+}
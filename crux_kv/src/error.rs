@@ -1,9 +1,10 @@
+use crux_core::{error::CapabilityError, migrations::MigrationError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Error type for KeyValue operations
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum KeyValueError {
     #[error("IO error: {message}")]
     Io { message: String },
@@ -11,6 +12,29 @@ pub enum KeyValueError {
     Timeout,
     #[error("cursor not found")]
     CursorNotFound,
+    /// The write mode of a `Set` didn't allow the write, because the key already has a value,
+    /// or the value has changed since it was read
+    #[error("write conflict, the stored value is at version {current_version}")]
+    Conflict { current_version: u64 },
     #[error("other error: {message}")]
     Other { message: String },
+    /// The stored value could not be migrated to the current version
+    /// (only produced in the core, see [`KeyValue::restore`](crate::KeyValue::restore))
+    #[error("migration error: {error}")]
+    #[serde(skip)]
+    Migration { error: MigrationError },
+}
+
+impl From<KeyValueError> for CapabilityError {
+    fn from(e: KeyValueError) -> Self {
+        match e {
+            KeyValueError::Io { message } | KeyValueError::Other { message } => {
+                Self::ShellFailure(message)
+            }
+            KeyValueError::Timeout => Self::Timeout,
+            KeyValueError::CursorNotFound => Self::NotFound,
+            e @ KeyValueError::Conflict { .. } => Self::Conflict(e.to_string()),
+            KeyValueError::Migration { error } => Self::InvalidData(error.to_string()),
+        }
+    }
 }
@@ -4,19 +4,33 @@
 //! persist the data using platform native capabilities (e.g. disk or web localStorage)
 
 pub mod error;
+pub mod wal;
 
-use crux_core::capability::{CapabilityContext, Operation};
+use std::sync::Arc;
+
+use crux_core::capability::{
+    BatchOperation, CapabilityContext, IdempotencyKey, Operation, ShellTimeout, TimesOut,
+};
 use crux_core::macros::Capability;
+use crux_core::migrations::{Migrated, Migrations};
 use error::KeyValueError;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// Supported operations
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum KeyValueOperation {
     /// Read bytes stored under a key
     Get { key: String },
-    /// Write bytes under a key
-    Set { key: String, value: Vec<u8> },
+    /// Write bytes under a key, if the `mode` allows it
+    Set {
+        key: String,
+        value: Vec<u8>,
+        #[serde(default)]
+        mode: WriteMode,
+        /// A unique key for this write, so that the shell can apply a retry of it only once
+        #[serde(default)]
+        idempotency_key: Option<IdempotencyKey>,
+    },
     /// Remove a key and its value
     Delete { key: String },
     /// Test if a key exists
@@ -34,6 +48,49 @@ pub enum KeyValueOperation {
         /// a `KeyValueError::CursorNotFound` error.
         cursor: u64,
     },
+    /// Perform several operations, which aren't batches themselves, in order,
+    /// responding with the result of each
+    Batch { operations: Vec<KeyValueOperation> },
+    /// Count the entries whose keys start with a prefix, and the space they take up
+    Stats {
+        /// The prefix to count the entries of, or an empty string to count all entries
+        prefix: String,
+    },
+}
+
+/// How a `Set` treats a value already stored under the key.
+///
+/// The shell keeps a version for each key, which changes every time the key is written,
+/// and returns it from reads and writes, so that the core can write back a value only if
+/// nobody else changed it in the meantime.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Replace any stored value, the last write wins
+    #[default]
+    Overwrite,
+    /// Only write if the key has no value, otherwise fail with `KeyValueError::Conflict`
+    IfAbsent,
+    /// Only write if the stored value is at the given version, as returned by a read,
+    /// otherwise fail with `KeyValueError::Conflict`
+    IfMatchVersion(u64),
+}
+
+/// A value read from the store, with its version (see [`WriteMode`])
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Versioned {
+    /// The value stored under the key, which may be empty
+    pub value: Vec<u8>,
+    /// The version of the value, or 0 if there is no value
+    pub version: u64,
+}
+
+/// How many entries are stored under a prefix, and how much space they take up, e.g. to
+/// evict the least recently used entries of a cache once it grows too big
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    pub entries: u64,
+    /// The size of the entries' keys and values in bytes, which may be approximate
+    pub bytes: u64,
 }
 
 /// The result of an operation on the store.
@@ -49,11 +106,21 @@ pub enum KeyValueResult {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KeyValueResponse {
     /// Response to a `KeyValueOperation::Get`,
-    /// returning the value stored under the key, which may be empty
-    Get { value: Vec<u8> },
+    /// returning the value stored under the key, which may be empty,
+    /// and its version, or 0 if there is no value
+    Get {
+        value: Vec<u8>,
+        #[serde(default)]
+        version: u64,
+    },
     /// Response to a `KeyValueOperation::Set`,
-    /// returning the value that was previously stored under the key, may be empty
-    Set { previous: Vec<u8> },
+    /// returning the value that was previously stored under the key, may be empty,
+    /// and the version of the value just written
+    Set {
+        previous: Vec<u8>,
+        #[serde(default)]
+        version: u64,
+    },
     /// Response to a `KeyValueOperation::Delete`,
     /// returning the value that was previously stored under the key, may be empty
     Delete { previous: Vec<u8> },
@@ -72,12 +139,69 @@ pub enum KeyValueResponse {
         /// include a `KeyValueError::CursorNotFound` error.
         next_cursor: u64,
     },
+    /// Response to a `KeyValueOperation::Batch`,
+    /// returning the result of each operation in the batch, in order
+    Batch { results: Vec<KeyValueResult> },
+    /// Response to a `KeyValueOperation::Stats`,
+    /// returning the number of entries under the prefix, and their size
+    Stats {
+        entries: u64,
+        /// The size of the entries' keys and values in bytes, which may be approximate,
+        /// e.g. if the store compresses them
+        bytes: u64,
+    },
+}
+
+impl KeyValueOperation {
+    /// The key identifying a write, for the shell to recognise a retry of it
+    pub fn idempotency_key(&self) -> Option<&IdempotencyKey> {
+        match self {
+            KeyValueOperation::Set {
+                idempotency_key, ..
+            } => idempotency_key.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 impl Operation for KeyValueOperation {
     type Output = KeyValueResult;
 }
 
+impl TimesOut for KeyValueOperation {
+    fn timed_out(_timeout: ShellTimeout) -> KeyValueResult {
+        KeyValueResult::Err {
+            error: KeyValueError::Timeout,
+        }
+    }
+}
+
+impl BatchOperation for KeyValueOperation {
+    fn batch(operations: Vec<Self>) -> Self {
+        KeyValueOperation::Batch { operations }
+    }
+
+    fn unbatch(output: KeyValueResult, count: usize) -> Vec<KeyValueResult> {
+        let mut results = match output {
+            KeyValueResult::Ok { response } => match response {
+                KeyValueResponse::Batch { results } => results,
+                _ => panic!("attempt to convert KeyValueResponse other than Batch to results"),
+            },
+            KeyValueResult::Err { error } => vec![KeyValueResult::Err { error }; count],
+        };
+
+        results.resize(
+            count,
+            KeyValueResult::Err {
+                error: KeyValueError::Other {
+                    message: "the shell returned no result for the operation".to_string(),
+                },
+            },
+        );
+        results
+    }
+}
+
 #[derive(Capability)]
 pub struct KeyValue<Ev> {
     context: CapabilityContext<KeyValueOperation, Ev>,
@@ -119,12 +243,115 @@ where
     /// Read a value under `key`, while in an async context. This is used together with
     /// [`crux_core::compose::Compose`].
     pub async fn get_async(&self, key: String) -> Result<Vec<u8>, KeyValueError> {
+        self.get_versioned_async(key)
+            .await
+            .map(|versioned| versioned.value)
+    }
+
+    /// Read the values under each of the `keys` in one request to the shell, will dispatch
+    /// the event with the result of reading each key, in order, as payload
+    pub fn get_many<F>(&self, keys: Vec<String>, make_event: F)
+    where
+        F: FnOnce(Vec<Result<Vec<u8>, KeyValueError>>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this.get_many_async(keys).await;
+                context.update_app(make_event(response));
+            }
+        });
+    }
+
+    /// Read the values under each of the `keys` in one request to the shell, while in an
+    /// async context. This is used together with [`crux_core::compose::Compose`].
+    pub async fn get_many_async(&self, keys: Vec<String>) -> Vec<Result<Vec<u8>, KeyValueError>> {
+        let operations = keys
+            .into_iter()
+            .map(|key| KeyValueOperation::Get { key })
+            .collect();
+
         self.context
-            .request_from_shell(KeyValueOperation::Get { key })
+            .request_batch(operations)
+            .await
+            .into_iter()
+            .map(|result| result.unwrap_get().map(|versioned| versioned.value))
+            .collect()
+    }
+
+    /// Read a value under `key` with its version, to write it back with
+    /// [`WriteMode::IfMatchVersion`]. Will dispatch the event with the [`Versioned`] value
+    /// as payload.
+    pub fn get_versioned<F>(&self, key: String, make_event: F)
+    where
+        F: FnOnce(Result<Versioned, KeyValueError>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this.get_versioned_async(key).await;
+                context.update_app(make_event(response));
+            }
+        });
+    }
+
+    /// Read a value under `key` with its version, while in an async context. This is used
+    /// together with [`crux_core::compose::Compose`].
+    pub async fn get_versioned_async(&self, key: String) -> Result<Versioned, KeyValueError> {
+        self.request(KeyValueOperation::Get { key })
             .await
             .unwrap_get()
     }
 
+    /// Read a model persisted under `key` with [`Migrations::persist`], running any migrations
+    /// required to bring it up to the current version. Will dispatch the event with the restored
+    /// model and a report of the migrations which ran, or `None` if there is no value stored
+    /// under the key.
+    ///
+    /// If the stored value can't be migrated, the event is dispatched with a
+    /// `KeyValueError::Migration` error, rather than the data being silently dropped.
+    pub fn restore<T, F>(&self, key: String, migrations: Arc<Migrations<T>>, make_event: F)
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: FnOnce(Result<Option<Migrated<T>>, KeyValueError>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this.restore_async(key, &migrations).await;
+                context.update_app(make_event(response));
+            }
+        });
+    }
+
+    /// Read a model persisted under `key` with [`Migrations::persist`], running any migrations
+    /// required, while in an async context. This is used together with
+    /// [`crux_core::compose::Compose`].
+    pub async fn restore_async<T>(
+        &self,
+        key: String,
+        migrations: &Migrations<T>,
+    ) -> Result<Option<Migrated<T>>, KeyValueError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let value = self.get_async(key).await?;
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        migrations
+            .restore(&value)
+            .map(Some)
+            .map_err(|error| KeyValueError::Migration { error })
+    }
+
     /// Set `key` to be the provided `value`. Typically the bytes would be
     /// a value serialized/deserialized by the app.
     ///
@@ -147,10 +374,98 @@ where
     /// Set `key` to be the provided `value`, while in an async context. This is used together with
     /// [`crux_core::compose::Compose`].
     pub async fn set_async(&self, key: String, value: Vec<u8>) -> Result<Vec<u8>, KeyValueError> {
-        self.context
-            .request_from_shell(KeyValueOperation::Set { key, value })
+        let operation = KeyValueOperation::Set {
+            key,
+            value,
+            mode: WriteMode::Overwrite,
+            idempotency_key: Some(IdempotencyKey::new()),
+        };
+
+        self.request(operation)
             .await
             .unwrap_set()
+            .map(|(previous, _)| previous)
+    }
+
+    /// Set `key` to be the provided `value` if the `mode` allows it, e.g. only if the stored
+    /// value is still at the version it was read at.
+    ///
+    /// Will dispatch the event with the version of the written value as payload, or a
+    /// `KeyValueError::Conflict` if the `mode` didn't allow the write.
+    pub fn set_with_mode<F>(&self, key: String, value: Vec<u8>, mode: WriteMode, make_event: F)
+    where
+        F: FnOnce(Result<u64, KeyValueError>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this.set_with_mode_async(key, value, mode).await;
+                context.update_app(make_event(response))
+            }
+        });
+    }
+
+    /// Set `key` to be the provided `value` if the `mode` allows it, while in an async context.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn set_with_mode_async(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        mode: WriteMode,
+    ) -> Result<u64, KeyValueError> {
+        self.set_with_key_async(key, value, mode, IdempotencyKey::new())
+            .await
+    }
+
+    /// Set `key` to be the provided `value` if the `mode` allows it, like
+    /// [`set_with_mode`](KeyValue::set_with_mode), with the given `idempotency_key` rather
+    /// than a new one, e.g. to reuse a key stored with a write the app retries after
+    /// restarting, so that the shell applies it only once.
+    pub fn set_with_key<F>(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        mode: WriteMode,
+        idempotency_key: impl Into<IdempotencyKey>,
+        make_event: F,
+    ) where
+        F: FnOnce(Result<u64, KeyValueError>) -> Ev + Send + Sync + 'static,
+    {
+        let idempotency_key = idempotency_key.into();
+
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this
+                    .set_with_key_async(key, value, mode, idempotency_key)
+                    .await;
+                context.update_app(make_event(response))
+            }
+        });
+    }
+
+    /// Set `key` to be the provided `value` with the given `idempotency_key`, while in an
+    /// async context. This is used together with [`crux_core::compose::Compose`].
+    pub async fn set_with_key_async(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        mode: WriteMode,
+        idempotency_key: impl Into<IdempotencyKey>,
+    ) -> Result<u64, KeyValueError> {
+        self.request(KeyValueOperation::Set {
+            key,
+            value,
+            mode,
+            idempotency_key: Some(idempotency_key.into()),
+        })
+        .await
+        .unwrap_set()
+        .map(|(_, version)| version)
     }
 
     /// Remove a `key` and its value, will dispatch the event with a
@@ -171,8 +486,7 @@ where
     /// Remove a `key` and its value, while in an async context. This is used together with
     /// [`crux_core::compose::Compose`].
     pub async fn delete_async(&self, key: String) -> Result<Vec<u8>, KeyValueError> {
-        self.context
-            .request_from_shell(KeyValueOperation::Delete { key })
+        self.request(KeyValueOperation::Delete { key })
             .await
             .unwrap_delete()
     }
@@ -195,8 +509,7 @@ where
     /// Check to see if a `key` exists, while in an async context. This is used together with
     /// [`crux_core::compose::Compose`].
     pub async fn exists_async(&self, key: String) -> Result<bool, KeyValueError> {
-        self.context
-            .request_from_shell(KeyValueOperation::Exists { key })
+        self.request(KeyValueOperation::Exists { key })
             .await
             .unwrap_exists()
     }
@@ -240,29 +553,55 @@ where
         prefix: String,
         cursor: u64,
     ) -> Result<(Vec<String>, u64), KeyValueError> {
-        self.context
-            .request_from_shell(KeyValueOperation::ListKeys { prefix, cursor })
+        self.request(KeyValueOperation::ListKeys { prefix, cursor })
             .await
             .unwrap_list_keys()
     }
+
+    /// Count the entries whose keys start with the provided `prefix`, and their size, will
+    /// dispatch the event with the [`StoreStats`] as payload
+    pub fn stats<F>(&self, prefix: String, make_event: F)
+    where
+        F: FnOnce(Result<StoreStats, KeyValueError>) -> Ev + Send + Sync + 'static,
+    {
+        let context = self.context.clone();
+        let this = self.clone();
+
+        self.context.spawn(async move {
+            let response = this.stats_async(prefix).await;
+            context.update_app(make_event(response))
+        });
+    }
+
+    /// Count the entries whose keys start with the provided `prefix`, and their size, while
+    /// in an async context. This is used together with [`crux_core::compose::Compose`].
+    pub async fn stats_async(&self, prefix: String) -> Result<StoreStats, KeyValueError> {
+        self.request(KeyValueOperation::Stats { prefix })
+            .await
+            .unwrap_stats()
+    }
+
+    async fn request(&self, operation: KeyValueOperation) -> KeyValueResult {
+        self.context.request_from_shell(operation).await
+    }
 }
 
 impl KeyValueResult {
-    fn unwrap_get(self) -> Result<Vec<u8>, KeyValueError> {
+    fn unwrap_get(self) -> Result<Versioned, KeyValueError> {
         match self {
             KeyValueResult::Ok { response } => match response {
-                KeyValueResponse::Get { value } => Ok(value),
-                _ => panic!("attempt to convert KeyValueResponse other than Get to Vec<u8>"),
+                KeyValueResponse::Get { value, version } => Ok(Versioned { value, version }),
+                _ => panic!("attempt to convert KeyValueResponse other than Get to Versioned"),
             },
             KeyValueResult::Err { error } => Err(error.clone()),
         }
     }
 
-    fn unwrap_set(self) -> Result<Vec<u8>, KeyValueError> {
+    fn unwrap_set(self) -> Result<(Vec<u8>, u64), KeyValueError> {
         match self {
             KeyValueResult::Ok { response } => match response {
-                KeyValueResponse::Set { previous } => Ok(previous),
-                _ => panic!("attempt to convert KeyValueResponse other than Set to Vec<u8>"),
+                KeyValueResponse::Set { previous, version } => Ok((previous, version)),
+                _ => panic!("attempt to convert KeyValueResponse other than Set to (Vec<u8>, u64)"),
             },
             KeyValueResult::Err { error } => Err(error.clone()),
         }
@@ -302,6 +641,16 @@ impl KeyValueResult {
             KeyValueResult::Err { error } => Err(error.clone()),
         }
     }
+
+    fn unwrap_stats(self) -> Result<StoreStats, KeyValueError> {
+        match self {
+            KeyValueResult::Ok { response } => match response {
+                KeyValueResponse::Stats { entries, bytes } => Ok(StoreStats { entries, bytes }),
+                _ => panic!("attempt to convert KeyValueResponse other than Stats to StoreStats"),
+            },
+            KeyValueResult::Err { error } => Err(error.clone()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,8 +1,28 @@
+use std::sync::Arc;
+
 use anyhow::Result;
-use crux_core::{macros::Effect, render::Render, testing::AppTester};
+use crux_core::{
+    capability::IdempotencyKey,
+    error::CapabilityError,
+    macros::Effect,
+    migrations::{Migrated, Migrations},
+    render::Render,
+    testing::AppTester,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::{error::KeyValueError, KeyValue, KeyValueOperation, KeyValueResponse, KeyValueResult};
+use crate::{
+    error::KeyValueError, KeyValue, KeyValueOperation, KeyValueResponse, KeyValueResult,
+    StoreStats, WriteMode,
+};
+
+// writes get a random key, so take it from the `operation` to compare the rest of it
+fn idempotency_key(operation: &KeyValueOperation) -> Option<IdempotencyKey> {
+    let key = operation.idempotency_key().cloned();
+    assert!(key.is_some());
+
+    key
+}
 
 #[derive(Default)]
 pub struct App;
@@ -15,11 +35,37 @@ pub enum Event {
     Exists,
     ListKeys,
     GetThenSet,
+    Restore,
+    Claim,
+    Retry,
+    Increment,
+    GetMany,
+    Stats,
 
     GetResponse(Result<Vec<u8>, KeyValueError>),
     SetResponse(Result<Vec<u8>, KeyValueError>),
     ExistsResponse(Result<bool, KeyValueError>),
     ListKeysResponse(Result<(Vec<String>, u64), KeyValueError>),
+    WriteResponse(Result<u64, KeyValueError>),
+    GetManyResponse(Vec<Result<Vec<u8>, KeyValueError>>),
+    #[serde(skip)]
+    RestoreResponse(Result<Option<Migrated<Stored>>, KeyValueError>),
+    #[serde(skip)]
+    StatsResponse(Result<StoreStats, KeyValueError>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredV0 {
+    pub number: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Stored {
+    pub value: i32,
+}
+
+fn migrations() -> Migrations<Stored> {
+    Migrations::new(1).register(0, |v0: StoredV0| Stored { value: v0.number })
 }
 
 #[derive(Debug, Default)]
@@ -28,6 +74,11 @@ pub struct Model {
     pub keys: Vec<String>,
     pub cursor: u64,
     pub successful: bool,
+    pub migrated_from: Vec<u32>,
+    pub version: u64,
+    pub conflict: Option<u64>,
+    pub many: Vec<Result<Vec<u8>, KeyValueError>>,
+    pub stats: Option<StoreStats>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -57,6 +108,11 @@ impl crux_core::App for App {
                     .list_keys("test:".to_string(), 0, Event::ListKeysResponse)
             }
 
+            Event::Restore => {
+                caps.key_value
+                    .restore(key, Arc::new(migrations()), Event::RestoreResponse)
+            }
+
             Event::GetThenSet => caps.compose.spawn(|ctx| {
                 let kv = caps.key_value.clone();
 
@@ -74,6 +130,56 @@ impl crux_core::App for App {
                 }
             }),
 
+            Event::Claim => caps.key_value.set_with_mode(
+                key,
+                b"mine".to_vec(),
+                WriteMode::IfAbsent,
+                Event::WriteResponse,
+            ),
+
+            Event::Retry => caps.key_value.set_with_key(
+                key,
+                b"order".to_vec(),
+                WriteMode::Overwrite,
+                "order-1",
+                Event::WriteResponse,
+            ),
+
+            // optimistic concurrency: only write back if nobody else has written in between
+            Event::Increment => caps.compose.spawn(|ctx| {
+                let kv = caps.key_value.clone();
+
+                async move {
+                    let Ok(read) = kv.get_versioned_async("counter".to_string()).await else {
+                        panic!("expected get response");
+                    };
+
+                    let num = read.value.first().copied().unwrap_or_default();
+                    let mode = WriteMode::IfMatchVersion(read.version);
+                    let result = kv
+                        .set_with_mode_async("counter".to_string(), vec![num + 1], mode)
+                        .await;
+
+                    ctx.update_app(Event::WriteResponse(result))
+                }
+            }),
+
+            Event::GetMany => caps.key_value.get_many(
+                vec!["a".to_string(), "b".to_string()],
+                Event::GetManyResponse,
+            ),
+            Event::GetManyResponse(results) => model.many = results,
+
+            Event::Stats => caps
+                .key_value
+                .stats("cache:".to_string(), Event::StatsResponse),
+            Event::StatsResponse(result) => model.stats = Some(result.unwrap()),
+
+            Event::WriteResponse(Ok(version)) => model.version = version,
+            Event::WriteResponse(Err(KeyValueError::Conflict { current_version })) => {
+                model.conflict = Some(current_version);
+            }
+
             Event::GetResponse(Ok(value)) => {
                 let (int_bytes, _rest) = value.split_at(std::mem::size_of::<i32>());
                 model.value = i32::from_ne_bytes(int_bytes.try_into().unwrap());
@@ -95,6 +201,13 @@ impl crux_core::App for App {
                 caps.render.render()
             }
 
+            Event::RestoreResponse(Ok(Some(restored))) => {
+                model.value = restored.model.value;
+                model.migrated_from = restored.applied;
+            }
+
+            Event::RestoreResponse(Ok(None)) => {}
+
             Event::GetResponse(Err(error)) => {
                 panic!("error: {:?}", error);
             }
@@ -107,6 +220,9 @@ impl crux_core::App for App {
             Event::ListKeysResponse(Err(error)) => {
                 panic!("Error: {:?}", error);
             }
+            Event::RestoreResponse(Err(error)) | Event::WriteResponse(Err(error)) => {
+                panic!("Error: {:?}", error);
+            }
         }
     }
 
@@ -119,6 +235,7 @@ impl crux_core::App for App {
 
 #[derive(Effect)]
 pub struct Capabilities {
+    #[effect(timeout_ms = 5000)]
     pub key_value: KeyValue<Event>,
     pub render: Render<Event>,
     #[effect(skip)]
@@ -149,6 +266,7 @@ fn test_get() {
             KeyValueResult::Ok {
                 response: KeyValueResponse::Get {
                     value: 42i32.to_ne_bytes().to_vec(),
+                    version: 1,
                 },
             },
         )
@@ -160,6 +278,37 @@ fn test_get() {
     assert_eq!(model.value, 42);
 }
 
+#[test]
+fn test_get_timeout() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let updated = app.update(Event::Get, &mut model);
+    let Effect::KeyValue(mut request) = updated.into_effects().next().unwrap() else {
+        panic!("Expected KeyValue effect");
+    };
+
+    let updated = app.time_out_requests();
+    assert!(matches!(
+        &updated.events[..],
+        [Event::GetResponse(Err(KeyValueError::Timeout))]
+    ));
+
+    // the shell's late response is ignored
+    let updated = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Get {
+                    value: vec![],
+                    version: 0,
+                },
+            },
+        )
+        .unwrap();
+    assert!(updated.events.is_empty());
+}
+
 #[test]
 fn test_set() {
     let app = AppTester::<App, _>::default();
@@ -172,7 +321,7 @@ fn test_set() {
         panic!("Expected KeyValue effect");
     };
 
-    let KeyValueOperation::Set { key, value } = request.operation.clone() else {
+    let KeyValueOperation::Set { key, value, .. } = request.operation.clone() else {
         panic!("Expected set operation");
     };
 
@@ -183,7 +332,10 @@ fn test_set() {
         .resolve(
             &mut request,
             KeyValueResult::Ok {
-                response: KeyValueResponse::Set { previous: vec![] },
+                response: KeyValueResponse::Set {
+                    previous: vec![],
+                    version: 1,
+                },
             },
         )
         .unwrap();
@@ -298,6 +450,123 @@ fn test_list_keys() {
     assert_eq!(model.cursor, 2);
 }
 
+#[test]
+fn test_stats() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let updated = app.update(Event::Stats, &mut model);
+
+    let Effect::KeyValue(mut request) = updated.into_effects().next().unwrap() else {
+        panic!("Expected KeyValue effect");
+    };
+    assert_eq!(
+        request.operation,
+        KeyValueOperation::Stats {
+            prefix: "cache:".to_string()
+        }
+    );
+
+    let updated = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Stats {
+                    entries: 3,
+                    bytes: 2048,
+                },
+            },
+        )
+        .unwrap();
+    for event in updated.events {
+        app.update(event, &mut model);
+    }
+
+    assert_eq!(
+        model.stats,
+        Some(StoreStats {
+            entries: 3,
+            bytes: 2048
+        })
+    );
+}
+
+#[test]
+fn test_restore_runs_migrations() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let updated = app.update(Event::Restore, &mut model);
+
+    let effect = updated.into_effects().next().unwrap();
+    let Effect::KeyValue(mut request) = effect else {
+        panic!("Expected KeyValue effect");
+    };
+
+    let KeyValueOperation::Get { key } = request.operation.clone() else {
+        panic!("Expected get operation");
+    };
+
+    assert_eq!(key, "test");
+
+    let updated = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Get {
+                    value: br#"{"number":42}"#.to_vec(),
+                    version: 1,
+                },
+            },
+        )
+        .unwrap();
+
+    let event = updated.events.into_iter().next().unwrap();
+    app.update(event, &mut model);
+
+    assert_eq!(model.value, 42);
+    assert_eq!(model.migrated_from, vec![0]);
+}
+
+#[test]
+fn test_restore_reports_migration_errors() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let updated = app.update(Event::Restore, &mut model);
+
+    let effect = updated.into_effects().next().unwrap();
+    let Effect::KeyValue(mut request) = effect else {
+        panic!("Expected KeyValue effect");
+    };
+
+    let updated = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Get {
+                    version: 1,
+                    value: br#"{"$crux_version":7,"data":{"value":1}}"#.to_vec(),
+                },
+            },
+        )
+        .unwrap();
+
+    let Some(Event::RestoreResponse(Err(KeyValueError::Migration { error }))) =
+        updated.events.into_iter().next()
+    else {
+        panic!("Expected a migration error");
+    };
+
+    assert_eq!(
+        error,
+        crux_core::migrations::MigrationError::UnknownVersion {
+            stored: 7,
+            current: 1
+        }
+    );
+}
+
 #[test]
 pub fn test_kv_async() -> Result<()> {
     let app = AppTester::<App, _>::default();
@@ -322,6 +591,7 @@ pub fn test_kv_async() -> Result<()> {
             KeyValueResult::Ok {
                 response: KeyValueResponse::Get {
                     value: 17u32.to_ne_bytes().to_vec(),
+                    version: 1,
                 },
             },
         )
@@ -332,7 +602,7 @@ pub fn test_kv_async() -> Result<()> {
         panic!("Expected KeyValue effect");
     };
 
-    let KeyValueOperation::Set { key, value } = request.operation.clone() else {
+    let KeyValueOperation::Set { key, value, .. } = request.operation.clone() else {
         panic!("Expected get operation");
     };
 
@@ -343,7 +613,10 @@ pub fn test_kv_async() -> Result<()> {
         .resolve(
             &mut request,
             KeyValueResult::Ok {
-                response: KeyValueResponse::Set { previous: vec![] },
+                response: KeyValueResponse::Set {
+                    previous: vec![],
+                    version: 1,
+                },
             },
         )
         .unwrap();
@@ -355,3 +628,268 @@ pub fn test_kv_async() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_set_if_absent() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let mut request = app
+        .update(Event::Claim, &mut model)
+        .into_effects()
+        .find_map(Effect::into_key_value)
+        .unwrap();
+
+    assert_eq!(
+        request.operation,
+        KeyValueOperation::Set {
+            key: "test".to_string(),
+            value: b"mine".to_vec(),
+            mode: WriteMode::IfAbsent,
+            idempotency_key: idempotency_key(&request.operation),
+        }
+    );
+
+    // somebody else got there first
+    let update = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Err {
+                error: KeyValueError::Conflict { current_version: 3 },
+            },
+        )
+        .unwrap();
+
+    for event in update.events {
+        app.update(event, &mut model);
+    }
+    assert_eq!(model.conflict, Some(3));
+}
+
+#[test]
+fn test_conflicts() {
+    let error = KeyValueError::Conflict { current_version: 3 };
+
+    let serialized = serde_json::to_string(&error).unwrap();
+    assert_eq!(&serialized, r#"{"conflict":{"currentVersion":3}}"#);
+    assert_eq!(
+        serde_json::from_str::<KeyValueError>(&serialized).unwrap(),
+        error
+    );
+
+    assert!(matches!(
+        CapabilityError::from(error),
+        CapabilityError::Conflict(_)
+    ));
+}
+
+#[test]
+fn test_set_if_match_version() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let mut request = app
+        .update(Event::Increment, &mut model)
+        .into_effects()
+        .find_map(Effect::into_key_value)
+        .unwrap();
+
+    let update = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Get {
+                    value: vec![41],
+                    version: 7,
+                },
+            },
+        )
+        .unwrap();
+
+    let mut request = update
+        .into_effects()
+        .find_map(Effect::into_key_value)
+        .unwrap();
+
+    assert_eq!(
+        request.operation,
+        KeyValueOperation::Set {
+            key: "counter".to_string(),
+            value: vec![42],
+            mode: WriteMode::IfMatchVersion(7),
+            idempotency_key: idempotency_key(&request.operation),
+        }
+    );
+
+    let update = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Set {
+                    previous: vec![41],
+                    version: 8,
+                },
+            },
+        )
+        .unwrap();
+
+    for event in update.events {
+        app.update(event, &mut model);
+    }
+    assert_eq!(model.version, 8);
+    assert_eq!(model.conflict, None);
+}
+
+#[test]
+fn test_get_many() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let mut request = app
+        .update(Event::GetMany, &mut model)
+        .into_effects()
+        .find_map(Effect::into_key_value)
+        .unwrap();
+
+    assert_eq!(
+        request.operation,
+        KeyValueOperation::Batch {
+            operations: vec![
+                KeyValueOperation::Get {
+                    key: "a".to_string()
+                },
+                KeyValueOperation::Get {
+                    key: "b".to_string()
+                },
+            ]
+        }
+    );
+
+    // each read succeeds or fails on its own
+    let update = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Ok {
+                response: KeyValueResponse::Batch {
+                    results: vec![
+                        KeyValueResult::Ok {
+                            response: KeyValueResponse::Get {
+                                value: b"one".to_vec(),
+                                version: 1,
+                            },
+                        },
+                        KeyValueResult::Err {
+                            error: KeyValueError::Other {
+                                message: "corrupt".to_string(),
+                            },
+                        },
+                    ],
+                },
+            },
+        )
+        .unwrap();
+
+    for event in update.events {
+        app.update(event, &mut model);
+    }
+    assert_eq!(
+        model.many,
+        vec![
+            Ok(b"one".to_vec()),
+            Err(KeyValueError::Other {
+                message: "corrupt".to_string()
+            })
+        ]
+    );
+}
+
+#[test]
+fn test_get_many_fails_every_read_with_the_batch() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let mut request = app
+        .update(Event::GetMany, &mut model)
+        .into_effects()
+        .find_map(Effect::into_key_value)
+        .unwrap();
+
+    let update = app
+        .resolve(
+            &mut request,
+            KeyValueResult::Err {
+                error: KeyValueError::Io {
+                    message: "disk full".to_string(),
+                },
+            },
+        )
+        .unwrap();
+
+    for event in update.events {
+        app.update(event, &mut model);
+    }
+    let error = KeyValueError::Io {
+        message: "disk full".to_string(),
+    };
+    assert_eq!(model.many, vec![Err(error.clone()), Err(error)]);
+}
+
+#[test]
+fn test_write_modes_serialize_with_a_default() {
+    let operation: KeyValueOperation =
+        serde_json::from_str(r#"{"Set":{"key":"test","value":[1]}}"#).unwrap();
+    assert_eq!(
+        operation,
+        KeyValueOperation::Set {
+            key: "test".to_string(),
+            value: vec![1],
+            mode: WriteMode::Overwrite,
+            idempotency_key: None,
+        }
+    );
+
+    let mode = serde_json::to_string(&WriteMode::IfMatchVersion(7)).unwrap();
+    assert_eq!(mode, r#"{"IfMatchVersion":7}"#);
+}
+
+#[test]
+fn test_writes_get_a_new_idempotency_key() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let keys: Vec<_> = (0..2)
+        .map(|_| {
+            let request = app
+                .update(Event::Set, &mut model)
+                .into_effects()
+                .find_map(Effect::into_key_value)
+                .unwrap();
+
+            idempotency_key(&request.operation)
+        })
+        .collect();
+
+    assert_ne!(keys[0], keys[1]);
+}
+
+#[test]
+fn test_writes_can_reuse_an_idempotency_key() {
+    let app = AppTester::<App, _>::default();
+    let mut model = Model::default();
+
+    let request = app
+        .update(Event::Retry, &mut model)
+        .into_effects()
+        .find_map(Effect::into_key_value)
+        .unwrap();
+
+    assert_eq!(
+        request.operation,
+        KeyValueOperation::Set {
+            key: "test".to_string(),
+            value: b"order".to_vec(),
+            mode: WriteMode::Overwrite,
+            idempotency_key: Some("order-1".into()),
+        }
+    );
+}
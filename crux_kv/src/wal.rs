@@ -0,0 +1,523 @@
+//! A write-ahead log stored in the key-value store.
+//!
+//! Capabilities which need to make sure work isn't lost if the app is killed (an outbox of
+//! requests waiting to be sent, or local changes waiting to be synced) can append a record to a
+//! [`Wal`] before acting on it, and remove it with a checkpoint once the work is done. When the app
+//! starts again, [`Wal::open`] recovers the records which were written but never checkpointed.
+//!
+//! Each record is stored under its own key, named after the log and the record's sequence number,
+//! and carries a checksum. A record which fails the checksum (for example because the write was
+//! torn by a crash) ends the recovery: it and any records after it are discarded.
+//!
+//! All the methods are async, and are used together with [`crux_core::compose::Compose`].
+
+use std::sync::{Arc, Mutex};
+
+use crate::{error::KeyValueError, KeyValue};
+
+/// When appended records are written to the store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Every record is written as it is appended
+    EveryRecord,
+    /// Records are buffered, and written in batches of the given size, or when
+    /// [`Wal::sync`] is called. Records not yet written are lost if the app is killed.
+    Batch(usize),
+}
+
+/// A record recovered from the log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WalRecord {
+    pub sequence: u64,
+    pub payload: Vec<u8>,
+}
+
+/// The result of recovering a log
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recovery {
+    /// The intact records after the last checkpoint, in the order they were appended
+    pub records: Vec<WalRecord>,
+    /// The number of records discarded because they (or a record before them) were corrupt
+    pub discarded: usize,
+}
+
+/// A write-ahead log, see the [module documentation](self) for details.
+pub struct Wal<Ev> {
+    key_value: KeyValue<Ev>,
+    name: String,
+    policy: SyncPolicy,
+    state: Arc<Mutex<WalState>>,
+}
+
+#[derive(Default)]
+struct WalState {
+    next_sequence: u64,
+    pending: Vec<(u64, Vec<u8>)>,
+}
+
+impl<Ev> Clone for Wal<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            key_value: self.key_value.clone(),
+            name: self.name.clone(),
+            policy: self.policy,
+            state: self.state.clone(),
+        }
+    }
+}
+
+const HEADER_LEN: usize = 4 + 8;
+
+impl<Ev> Wal<Ev>
+where
+    Ev: 'static,
+{
+    /// Open the log called `name`, recovering the records appended since the last checkpoint.
+    ///
+    /// Only one `Wal` should be open for a given name at a time. Clones of the returned `Wal`
+    /// share its state.
+    pub async fn open(
+        key_value: KeyValue<Ev>,
+        name: impl Into<String>,
+        policy: SyncPolicy,
+    ) -> Result<(Self, Recovery), KeyValueError> {
+        let wal = Self {
+            key_value,
+            name: name.into(),
+            policy,
+            state: Arc::default(),
+        };
+
+        let checkpoint = wal.read_checkpoint().await?;
+
+        let mut sequences = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (keys, next) = wal
+                .key_value
+                .list_keys_async(wal.record_prefix(), cursor)
+                .await?;
+            sequences.extend(keys.iter().filter_map(|key| wal.parse_record_key(key)));
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        sequences.sort_unstable();
+
+        let mut recovery = Recovery::default();
+        let mut next_sequence = checkpoint;
+        let mut expected = checkpoint;
+        for sequence in sequences {
+            let intact = if sequence < checkpoint {
+                // left over from a checkpoint interrupted before it removed all its records
+                wal.key_value.delete_async(wal.record_key(sequence)).await?;
+                continue;
+            } else if recovery.discarded > 0 || sequence != expected {
+                None
+            } else {
+                let bytes = wal.key_value.get_async(wal.record_key(sequence)).await?;
+                decode(sequence, &bytes)
+            };
+
+            match intact {
+                Some(payload) => {
+                    recovery.records.push(WalRecord { sequence, payload });
+                    expected = sequence + 1;
+                }
+                None => {
+                    wal.key_value.delete_async(wal.record_key(sequence)).await?;
+                    recovery.discarded += 1;
+                }
+            }
+            next_sequence = next_sequence.max(sequence + 1);
+        }
+
+        wal.state
+            .lock()
+            .expect("Wal Mutex was poisoned.")
+            .next_sequence = next_sequence;
+
+        Ok((wal, recovery))
+    }
+
+    /// Append a record to the log, returning its sequence number. Depending on the
+    /// [`SyncPolicy`], the record is written immediately or buffered.
+    pub async fn append(&self, payload: Vec<u8>) -> Result<u64, KeyValueError> {
+        let (sequence, should_sync) = {
+            let mut state = self.state.lock().expect("Wal Mutex was poisoned.");
+            let sequence = state.next_sequence;
+            state.next_sequence += 1;
+            state.pending.push((sequence, payload));
+
+            let should_sync = match self.policy {
+                SyncPolicy::EveryRecord => true,
+                SyncPolicy::Batch(size) => state.pending.len() >= size,
+            };
+
+            (sequence, should_sync)
+        };
+
+        if should_sync {
+            self.sync().await?;
+        }
+
+        Ok(sequence)
+    }
+
+    /// Write all buffered records to the store.
+    ///
+    /// If writing fails, the records which weren't written are buffered again.
+    pub async fn sync(&self) -> Result<(), KeyValueError> {
+        let pending =
+            std::mem::take(&mut self.state.lock().expect("Wal Mutex was poisoned.").pending);
+
+        let mut records = pending.into_iter();
+        while let Some((sequence, payload)) = records.next() {
+            let result = self
+                .key_value
+                .set_async(self.record_key(sequence), encode(sequence, &payload))
+                .await;
+
+            if let Err(error) = result {
+                let mut state = self.state.lock().expect("Wal Mutex was poisoned.");
+                let mut unwritten: Vec<_> = std::iter::once((sequence, payload))
+                    .chain(records)
+                    .collect();
+                unwritten.append(&mut state.pending);
+                state.pending = unwritten;
+
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mark all records up to and including `sequence` as done. They are removed from the log
+    /// and will not be recovered.
+    pub async fn checkpoint(&self, sequence: u64) -> Result<(), KeyValueError> {
+        let previous = self.read_checkpoint().await?;
+        let checkpoint = sequence + 1;
+
+        if checkpoint <= previous {
+            return Ok(());
+        }
+
+        self.state
+            .lock()
+            .expect("Wal Mutex was poisoned.")
+            .pending
+            .retain(|(s, _)| *s >= checkpoint);
+
+        // the checkpoint is written first, so that records it covers are never recovered,
+        // even if removing them is interrupted
+        self.key_value
+            .set_async(self.checkpoint_key(), encode(checkpoint, &[]))
+            .await?;
+
+        for sequence in previous..checkpoint {
+            self.key_value
+                .delete_async(self.record_key(sequence))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn read_checkpoint(&self) -> Result<u64, KeyValueError> {
+        let bytes = self.key_value.get_async(self.checkpoint_key()).await?;
+        if bytes.is_empty() {
+            return Ok(0);
+        }
+
+        let sequence = bytes
+            .get(4..HEADER_LEN)
+            .map(|s| u64::from_le_bytes(s.try_into().expect("slice is 8 bytes")))
+            .filter(|sequence| decode(*sequence, &bytes).is_some());
+
+        sequence.ok_or_else(|| KeyValueError::Other {
+            message: format!("checkpoint of write-ahead log {} is corrupt", self.name),
+        })
+    }
+
+    fn record_prefix(&self) -> String {
+        format!("{}/wal/records/", self.name)
+    }
+
+    fn record_key(&self, sequence: u64) -> String {
+        // zero padded, so that keys sort in sequence order
+        format!("{}{sequence:020}", self.record_prefix())
+    }
+
+    fn parse_record_key(&self, key: &str) -> Option<u64> {
+        key.strip_prefix(&self.record_prefix())?.parse().ok()
+    }
+
+    fn checkpoint_key(&self) -> String {
+        format!("{}/wal/checkpoint", self.name)
+    }
+}
+
+/// Frame a record as `[checksum: u32][sequence: u64][payload]`, little endian. The checksum
+/// covers the sequence number and the payload.
+fn encode(sequence: u64, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&sequence.to_le_bytes());
+    bytes.extend_from_slice(payload);
+
+    let checksum = crc32(&bytes[4..]);
+    bytes[..4].copy_from_slice(&checksum.to_le_bytes());
+
+    bytes
+}
+
+/// Check the framing of a record stored for `sequence`, returning its payload if intact.
+fn decode(sequence: u64, bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < HEADER_LEN {
+        return None;
+    }
+
+    let checksum = u32::from_le_bytes(bytes[..4].try_into().ok()?);
+    let stored_sequence = u64::from_le_bytes(bytes[4..HEADER_LEN].try_into().ok()?);
+
+    (checksum == crc32(&bytes[4..]) && stored_sequence == sequence)
+        .then(|| bytes[HEADER_LEN..].to_vec())
+}
+
+/// CRC-32 (IEEE)
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, VecDeque};
+
+    use crux_core::{compose::Compose, macros::Effect, testing::AppTester};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{KeyValueOperation, KeyValueResponse, KeyValueResult};
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn framing_round_trips_and_detects_corruption() {
+        let bytes = encode(7, b"hello");
+        assert_eq!(decode(7, &bytes), Some(b"hello".to_vec()));
+
+        // stored under the wrong sequence number
+        assert_eq!(decode(8, &bytes), None);
+
+        let mut flipped = bytes.clone();
+        flipped[HEADER_LEN] ^= 1;
+        assert_eq!(decode(7, &flipped), None);
+
+        assert_eq!(decode(7, &bytes[..bytes.len() - 1]), None);
+    }
+
+    #[derive(Default)]
+    struct App;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    enum Event {
+        Append(Vec<String>, Option<u64>),
+        Recover,
+
+        #[serde(skip)]
+        Appended(Result<Vec<u64>, KeyValueError>),
+        #[serde(skip)]
+        Recovered(Result<Recovery, KeyValueError>),
+    }
+
+    #[derive(Default)]
+    struct Model {
+        appended: Vec<u64>,
+        recovered: Vec<String>,
+        discarded: usize,
+    }
+
+    #[derive(Effect)]
+    struct Capabilities {
+        key_value: KeyValue<Event>,
+        #[effect(skip)]
+        compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            let key_value = caps.key_value.clone();
+
+            match event {
+                Event::Append(payloads, checkpoint) => caps.compose.spawn(|ctx| async move {
+                    let result = async {
+                        let (wal, _) = Wal::open(key_value, "outbox", SyncPolicy::Batch(2)).await?;
+
+                        let mut sequences = Vec::new();
+                        for payload in payloads {
+                            sequences.push(wal.append(payload.into_bytes()).await?);
+                        }
+                        wal.sync().await?;
+
+                        if let Some(sequence) = checkpoint {
+                            wal.checkpoint(sequence).await?;
+                        }
+
+                        Ok(sequences)
+                    };
+
+                    ctx.update_app(Event::Appended(result.await));
+                }),
+                Event::Recover => caps.compose.spawn(|ctx| async move {
+                    let result = Wal::open(key_value, "outbox", SyncPolicy::EveryRecord)
+                        .await
+                        .map(|(_, recovery)| recovery);
+
+                    ctx.update_app(Event::Recovered(result));
+                }),
+                Event::Appended(sequences) => model.appended = sequences.unwrap(),
+                Event::Recovered(recovery) => {
+                    let recovery = recovery.unwrap();
+                    model.recovered = recovery
+                        .records
+                        .into_iter()
+                        .map(|r| String::from_utf8(r.payload).unwrap())
+                        .collect();
+                    model.discarded = recovery.discarded;
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+
+    /// Run `event` to completion, resolving key-value requests against `store`.
+    fn run(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        store: &mut BTreeMap<String, Vec<u8>>,
+        event: Event,
+    ) {
+        let mut events = VecDeque::from([event]);
+
+        while let Some(event) = events.pop_front() {
+            let mut effects: VecDeque<_> = app.update(event, model).into_effects().collect();
+
+            while let Some(Effect::KeyValue(mut request)) = effects.pop_front() {
+                let response = match request.operation.clone() {
+                    KeyValueOperation::Get { key } => KeyValueResponse::Get {
+                        value: store.get(&key).cloned().unwrap_or_default(),
+                        version: 1,
+                    },
+                    KeyValueOperation::Set { key, value, .. } => KeyValueResponse::Set {
+                        previous: store.insert(key, value).unwrap_or_default(),
+                        version: 1,
+                    },
+                    KeyValueOperation::Delete { key } => KeyValueResponse::Delete {
+                        previous: store.remove(&key).unwrap_or_default(),
+                    },
+                    KeyValueOperation::Exists { key } => KeyValueResponse::Exists {
+                        is_present: store.contains_key(&key),
+                    },
+                    KeyValueOperation::ListKeys { prefix, .. } => KeyValueResponse::ListKeys {
+                        keys: store
+                            .keys()
+                            .filter(|k| k.starts_with(&prefix))
+                            .cloned()
+                            .collect(),
+                        next_cursor: 0,
+                    },
+                    KeyValueOperation::Batch { .. } | KeyValueOperation::Stats { .. } => {
+                        panic!("the log only reads, writes and lists keys")
+                    }
+                };
+
+                let update = app
+                    .resolve(&mut request, KeyValueResult::Ok { response })
+                    .unwrap();
+                effects.extend(update.effects);
+                events.extend(update.events);
+            }
+        }
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn recovers_records_after_checkpoint() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut store = BTreeMap::new();
+
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Append(strings(&["a", "b", "c"]), Some(0)),
+        );
+        assert_eq!(model.appended, vec![0, 1, 2]);
+
+        run(&app, &mut model, &mut store, Event::Recover);
+        assert_eq!(model.recovered, strings(&["b", "c"]));
+        assert_eq!(model.discarded, 0);
+
+        // new records continue the sequence
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Append(strings(&["d"]), Some(2)),
+        );
+        assert_eq!(model.appended, vec![3]);
+
+        run(&app, &mut model, &mut store, Event::Recover);
+        assert_eq!(model.recovered, strings(&["d"]));
+        assert_eq!(store.keys().filter(|k| k.contains("/records/")).count(), 1);
+    }
+
+    #[test]
+    fn discards_corrupt_tail() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut store = BTreeMap::new();
+
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Append(strings(&["a", "b", "c"]), None),
+        );
+
+        // tear the write of the second record
+        let torn = store
+            .get_mut("outbox/wal/records/00000000000000000001")
+            .unwrap();
+        torn.truncate(torn.len() - 1);
+
+        run(&app, &mut model, &mut store, Event::Recover);
+        assert_eq!(model.recovered, strings(&["a"]));
+        assert_eq!(model.discarded, 2);
+        assert_eq!(store.keys().filter(|k| k.contains("/records/")).count(), 1);
+    }
+}
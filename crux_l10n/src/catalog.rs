@@ -0,0 +1,160 @@
+//! Parsed translations of one locale, see [`Catalog`]
+
+use std::collections::HashMap;
+
+use crate::{fluent, gettext, Arg, L10nError};
+
+/// The format of a catalog's source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A Fluent resource (`.ftl`)
+    Fluent,
+    /// A gettext portable object (`.po`)
+    Gettext,
+}
+
+impl Format {
+    /// The format of the catalog at `path`, from its extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.split(['?', '#']).next().unwrap_or(path);
+
+        match path.rsplit_once('.')?.1 {
+            "ftl" => Some(Format::Fluent),
+            "po" => Some(Format::Gettext),
+            _ => None,
+        }
+    }
+}
+
+/// A message, made of text and placeables filled in from the arguments
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Pattern(pub(crate) Vec<Element>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Element {
+    Text(String),
+    Variable(String),
+    Select {
+        variable: String,
+        variants: Vec<(String, Pattern)>,
+        default: usize,
+    },
+}
+
+impl Pattern {
+    fn format(&self, args: &[(&str, Arg)], out: &mut String) {
+        for element in &self.0 {
+            match element {
+                Element::Text(text) => out.push_str(text),
+                Element::Variable(name) => match arg(args, name) {
+                    Some(value) => out.push_str(&value.to_string()),
+                    None => {
+                        out.push_str("{$");
+                        out.push_str(name);
+                        out.push('}');
+                    }
+                },
+                Element::Select {
+                    variable,
+                    variants,
+                    default,
+                } => {
+                    let value = arg(args, variable);
+                    let (_, pattern) = variants
+                        .iter()
+                        .find(|(key, _)| value.map_or(false, |value| value.matches(key)))
+                        .unwrap_or(&variants[*default]);
+
+                    pattern.format(args, out);
+                }
+            }
+        }
+    }
+}
+
+fn arg<'a>(args: &'a [(&str, Arg)], name: &str) -> Option<&'a Arg> {
+    args.iter()
+        .find(|(arg_name, _)| *arg_name == name)
+        .map(|(_, value)| value)
+}
+
+/// The translated messages of one locale, keyed by message id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Catalog {
+    messages: HashMap<String, Pattern>,
+}
+
+impl Catalog {
+    /// Parse a catalog from `source` in the given `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`L10nError::Parse`] with the line of the first syntax error in `source`.
+    pub fn parse(source: &str, format: Format) -> Result<Self, L10nError> {
+        let messages = match format {
+            Format::Fluent => fluent::parse(source)?,
+            Format::Gettext => gettext::parse(source)?,
+        };
+
+        Ok(Self {
+            messages: messages.into_iter().collect(),
+        })
+    }
+
+    /// Whether the catalog has a message with the `id`
+    pub fn contains(&self, id: &str) -> bool {
+        self.messages.contains_key(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Format the message with the `id`, filling in its placeables from `args`. Placeables
+    /// without an argument are left in the text as `{$name}`.
+    pub fn format(&self, id: &str, args: &[(&str, Arg)]) -> Option<String> {
+        let pattern = self.messages.get(id)?;
+
+        let mut out = String::new();
+        pattern.format(args, &mut out);
+
+        Some(out)
+    }
+
+    /// Add the messages of `other`, replacing messages with the same ids
+    pub fn merge(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats_from_paths() {
+        assert_eq!(Format::from_path("l10n/en.ftl"), Some(Format::Fluent));
+        assert_eq!(
+            Format::from_path("https://example.com/fr.po?v=2"),
+            Some(Format::Gettext)
+        );
+        assert_eq!(Format::from_path("messages.json"), None);
+        assert_eq!(Format::from_path("messages"), None);
+    }
+
+    #[test]
+    fn leaves_missing_arguments_in_the_text() {
+        let catalog = Catalog::parse("hello = Hello, { $name }!", Format::Fluent).unwrap();
+
+        assert_eq!(catalog.format("hello", &[]).unwrap(), "Hello, {$name}!");
+        assert_eq!(
+            catalog.format("hello", &[("name", "Ana".into())]).unwrap(),
+            "Hello, Ana!"
+        );
+        assert_eq!(catalog.format("goodbye", &[]), None);
+    }
+}
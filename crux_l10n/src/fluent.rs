@@ -0,0 +1,409 @@
+//! A parser for the subset of [Fluent](https://projectfluent.org/) syntax the catalogs support:
+//!
+//! * messages (`id = value`), with values continuing on indented lines
+//! * attributes (`.title = value` on an indented line), stored as `id.title`
+//! * terms (`-brand = value`), inlined where they're referenced with `{ -brand }`
+//! * variables (`{ $name }`) and string literals (`{ "{" }`)
+//! * select expressions on variables, with numeric and plural category keys
+//! * comments (`#`, `##` and `###`)
+//!
+//! Functions, message references and term arguments are not supported.
+
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+use crate::{
+    catalog::{Element, Pattern},
+    L10nError,
+};
+
+struct Entry {
+    line: usize,
+    id: String,
+    value: String,
+}
+
+pub(crate) fn parse(source: &str) -> Result<Vec<(String, Pattern)>, L10nError> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut attribute_of: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: &str| L10nError::Parse {
+            line: line_number,
+            message: message.to_string(),
+        };
+
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // a select expression's closing brace may be at the start of a line
+        if line.starts_with([' ', '\t', '}']) {
+            let trimmed = line.trim();
+
+            if let Some(attribute) = trimmed.strip_prefix('.') {
+                let parent = attribute_of
+                    .as_ref()
+                    .ok_or_else(|| error("unexpected attribute"))?;
+                let (name, value) =
+                    definition(attribute).ok_or_else(|| error("invalid attribute"))?;
+
+                entries.push(Entry {
+                    line: line_number,
+                    id: format!("{parent}.{name}"),
+                    value: value.to_string(),
+                });
+                continue;
+            }
+
+            let entry = entries
+                .last_mut()
+                .ok_or_else(|| error("unexpected indentation"))?;
+            if !entry.value.is_empty() {
+                entry.value.push('\n');
+            }
+            entry.value.push_str(trimmed);
+            continue;
+        }
+
+        let (id, value) = definition(line).ok_or_else(|| error("expected a message"))?;
+        attribute_of = Some(id.to_string());
+        entries.push(Entry {
+            line: line_number,
+            id: id.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    let mut terms = HashMap::new();
+    let mut messages = Vec::new();
+
+    for entry in entries {
+        let pattern = Parser::new(&entry.value, entry.line).pattern(false)?;
+
+        match entry.id.strip_prefix('-') {
+            Some(term) => {
+                terms.insert(term.to_string(), pattern);
+            }
+            None => messages.push((entry.id, pattern, entry.line)),
+        }
+    }
+
+    messages
+        .into_iter()
+        .map(|(id, pattern, line)| Ok((id, inline_terms(pattern, &terms, line)?)))
+        .collect()
+}
+
+/// Split `id = value` into the id and the value
+fn definition(line: &str) -> Option<(&str, &str)> {
+    let (id, value) = line.split_once('=')?;
+    let id = id.trim();
+
+    let name = id.strip_prefix('-').unwrap_or(id);
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    valid.then(|| (id, value.trim()))
+}
+
+fn inline_terms(
+    pattern: Pattern,
+    terms: &HashMap<String, Pattern>,
+    line: usize,
+) -> Result<Pattern, L10nError> {
+    let mut elements = Vec::new();
+
+    for element in pattern.0 {
+        match element {
+            Element::Variable(name) if name.starts_with('-') => {
+                let term = terms.get(&name[1..]).ok_or_else(|| L10nError::Parse {
+                    line,
+                    message: format!("unknown term {name}"),
+                })?;
+                elements.extend(term.0.iter().cloned());
+            }
+            Element::Select {
+                variable,
+                variants,
+                default,
+            } => {
+                let variants = variants
+                    .into_iter()
+                    .map(|(key, pattern)| Ok((key, inline_terms(pattern, terms, line)?)))
+                    .collect::<Result<_, L10nError>>()?;
+
+                elements.push(Element::Select {
+                    variable,
+                    variants,
+                    default,
+                });
+            }
+            element => elements.push(element),
+        }
+    }
+
+    Ok(Pattern(elements))
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(value: &'a str, line: usize) -> Self {
+        Self {
+            chars: value.chars().peekable(),
+            line,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> L10nError {
+        L10nError::Parse {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    /// Parse text and placeables, up to the end of the value, or the end of the line in a
+    /// select variant. Term references are parsed as variables with a leading `-`, to be
+    /// inlined once all terms are known.
+    fn pattern(&mut self, in_variant: bool) -> Result<Pattern, L10nError> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if in_variant && (c == '\n' || c == '}') {
+                break;
+            }
+            self.chars.next();
+
+            if c == '{' {
+                let element = self.placeable()?;
+                match element {
+                    Element::Text(literal) => text.push_str(&literal),
+                    element => {
+                        if !text.is_empty() {
+                            elements.push(Element::Text(std::mem::take(&mut text)));
+                        }
+                        elements.push(element);
+                    }
+                }
+            } else if c == '}' {
+                return Err(self.error("unbalanced '}'"));
+            } else {
+                text.push(c);
+            }
+        }
+
+        let text = text.trim_end();
+        if !text.is_empty() {
+            elements.push(Element::Text(text.to_string()));
+        }
+
+        Ok(Pattern(elements))
+    }
+
+    /// Parse a placeable, after its opening `{`
+    fn placeable(&mut self) -> Result<Element, L10nError> {
+        self.skip_whitespace();
+
+        let element = match self.chars.next() {
+            Some('"') => Element::Text(self.string_literal()?),
+            Some('$') => {
+                let name = self.identifier()?;
+                self.skip_whitespace();
+
+                if self.chars.peek() == Some(&'-') {
+                    self.chars.next();
+                    if self.chars.next() != Some('>') {
+                        return Err(self.error("expected '->'"));
+                    }
+                    self.select(name)?
+                } else {
+                    Element::Variable(name)
+                }
+            }
+            Some('-') => Element::Variable(format!("-{}", self.identifier()?)),
+            _ => return Err(self.error("expected a variable, term or string literal")),
+        };
+
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some('}') => Ok(element),
+            _ => Err(self.error("expected '}'")),
+        }
+    }
+
+    /// Parse the variants of a select expression on `variable`, after the `->`
+    fn select(&mut self, variable: String) -> Result<Element, L10nError> {
+        let mut variants = Vec::new();
+        let mut default = None;
+
+        loop {
+            self.skip_whitespace();
+
+            match self.chars.peek() {
+                Some('}') => break,
+                Some('*') => {
+                    self.chars.next();
+                    if default.replace(variants.len()).is_some() {
+                        return Err(self.error("more than one default variant"));
+                    }
+                }
+                _ => {}
+            }
+
+            if self.chars.next() != Some('[') {
+                return Err(self.error("expected a variant"));
+            }
+            let key: String = self.chars.by_ref().take_while(|&c| c != ']').collect();
+            while self.chars.peek() == Some(&' ') {
+                self.chars.next();
+            }
+
+            variants.push((key.trim().to_string(), self.pattern(true)?));
+        }
+
+        let default = default.ok_or_else(|| self.error("missing default variant"))?;
+
+        Ok(Element::Select {
+            variable,
+            variants,
+            default,
+        })
+    }
+
+    fn string_literal(&mut self) -> Result<String, L10nError> {
+        let mut literal = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(literal),
+                Some('\\') => match self.chars.next() {
+                    Some(c @ ('"' | '\\')) => literal.push(c),
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some('\n') | None => return Err(self.error("unterminated string literal")),
+                Some(c) => literal.push(c),
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, L10nError> {
+        let mut identifier = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                break;
+            }
+            // `->` follows a selector, it isn't part of its name
+            if c == '-' && self.chars.clone().nth(1) == Some('>') {
+                break;
+            }
+            identifier.push(c);
+            self.chars.next();
+        }
+
+        if identifier.is_empty() {
+            return Err(self.error("expected an identifier"));
+        }
+
+        Ok(identifier)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().map_or(false, |c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Catalog, Format};
+
+    const SOURCE: &str = r#"
+### Inbox
+
+-brand = Postbox
+
+# $count (Number) - the number of unread messages
+unread = { $count ->
+    [0] No unread messages
+    [one] One unread message
+   *[other] { $count } unread messages
+}
+welcome = Welcome to { -brand }, { $name }!
+    .title = { -brand }
+about =
+    { -brand } keeps your mail.
+    It works offline.
+braces = Use {"{"} and {"}"} in templates
+"#;
+
+    fn format(id: &str, args: &[(&str, crate::Arg)]) -> String {
+        let catalog = Catalog::parse(SOURCE, Format::Fluent).unwrap();
+        catalog.format(id, args).unwrap()
+    }
+
+    #[test]
+    fn formats_messages() {
+        assert_eq!(
+            format("welcome", &[("name", "Ana".into())]),
+            "Welcome to Postbox, Ana!"
+        );
+        assert_eq!(format("welcome.title", &[]), "Postbox");
+        assert_eq!(
+            format("about", &[]),
+            "Postbox keeps your mail.\nIt works offline."
+        );
+        assert_eq!(format("braces", &[]), "Use { and } in templates");
+    }
+
+    #[test]
+    fn selects_variants() {
+        assert_eq!(
+            format("unread", &[("count", 0.into())]),
+            "No unread messages"
+        );
+        assert_eq!(
+            format("unread", &[("count", 1.into())]),
+            "One unread message"
+        );
+        assert_eq!(
+            format("unread", &[("count", 7.into())]),
+            "7 unread messages"
+        );
+        assert_eq!(format("unread", &[]), "{$count} unread messages");
+    }
+
+    #[test]
+    fn skips_terms() {
+        let catalog = Catalog::parse(SOURCE, Format::Fluent).unwrap();
+
+        assert!(!catalog.contains("-brand"));
+        assert_eq!(catalog.len(), 5);
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        for (source, line) in [
+            ("hello = Hello\n  .title = { $name", 2),
+            ("hello = { $n ->\n  [one] One\n}", 1),
+            ("hello = Hi {-missing}", 1),
+            ("hello = Hello\nnot a message", 2),
+            ("  indented = Hello", 1),
+        ] {
+            match Catalog::parse(source, Format::Fluent) {
+                Err(crate::L10nError::Parse { line: actual, .. }) => {
+                    assert_eq!(actual, line, "{source}");
+                }
+                result => panic!("expected a syntax error in {source:?}, got {result:?}"),
+            }
+        }
+    }
+}
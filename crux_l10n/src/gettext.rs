@@ -0,0 +1,298 @@
+//! A parser for gettext portable object (`.po`) files.
+//!
+//! Entries are keyed by their `msgid`, or `msgctxt.msgid` if they have a context. Named
+//! placeholders in the translations (`{name}`) are filled in from the arguments. Plural entries
+//! select the first form when the `count` argument is 1, and the last form otherwise.
+//!
+//! Fuzzy and untranslated entries are skipped, so that the fallback locale is used instead.
+
+use crate::{
+    catalog::{Element, Pattern},
+    L10nError,
+};
+
+#[derive(Default)]
+struct Entry {
+    fuzzy: bool,
+    context: Option<String>,
+    id: Option<String>,
+    translations: Vec<String>,
+}
+
+impl Entry {
+    fn into_message(self) -> Option<(String, Pattern)> {
+        let id = self.id?;
+        if self.fuzzy || id.is_empty() || self.translations.iter().all(String::is_empty) {
+            return None;
+        }
+
+        let id = match self.context {
+            Some(context) => format!("{context}.{id}"),
+            None => id,
+        };
+
+        let mut forms = self.translations;
+        let pattern = if forms.len() > 1 {
+            let other = placeholders(&forms.pop().unwrap_or_default());
+            let one = placeholders(&forms.swap_remove(0));
+
+            Pattern(vec![Element::Select {
+                variable: "count".to_string(),
+                variants: vec![("one".to_string(), one), ("other".to_string(), other)],
+                default: 1,
+            }])
+        } else {
+            placeholders(&forms[0])
+        };
+
+        Some((id, pattern))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    Plural,
+    Translation(usize),
+}
+
+pub(crate) fn parse(source: &str) -> Result<Vec<(String, Pattern)>, L10nError> {
+    let mut messages = Vec::new();
+    let mut entry = Entry::default();
+    let mut field = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: &str| L10nError::Parse {
+            line: line_number,
+            message: message.to_string(),
+        };
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            if entry.id.is_some() {
+                messages.extend(std::mem::take(&mut entry).into_message());
+            }
+            entry.fuzzy = flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('"') {
+            let text = string(line).ok_or_else(|| error("invalid string"))?;
+            match field {
+                Some(Field::Context) => entry
+                    .context
+                    .get_or_insert_with(String::new)
+                    .push_str(&text),
+                Some(Field::Id) => entry.id.get_or_insert_with(String::new).push_str(&text),
+                Some(Field::Plural) => {}
+                Some(Field::Translation(index)) => entry.translations[index].push_str(&text),
+                None => return Err(error("unexpected string")),
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| error("expected a keyword"))?;
+        let text = string(rest.trim()).ok_or_else(|| error("invalid string"))?;
+
+        field = Some(match keyword {
+            "msgctxt" | "msgid" if entry.id.is_some() && !entry.translations.is_empty() => {
+                messages.extend(std::mem::take(&mut entry).into_message());
+                start(&mut entry, keyword, text)
+            }
+            "msgctxt" | "msgid" => start(&mut entry, keyword, text),
+            // the forms are selected by the `count` argument instead
+            "msgid_plural" => Field::Plural,
+            "msgstr" => {
+                entry.translations.push(text);
+                Field::Translation(entry.translations.len() - 1)
+            }
+            keyword => {
+                let form = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|form| form.strip_suffix(']'))
+                    .and_then(|form| form.parse::<usize>().ok())
+                    .ok_or_else(|| error("unknown keyword"))?;
+                if form != entry.translations.len() {
+                    return Err(error("plural forms out of order"));
+                }
+
+                entry.translations.push(text);
+                Field::Translation(form)
+            }
+        });
+    }
+
+    messages.extend(entry.into_message());
+
+    Ok(messages)
+}
+
+fn start(entry: &mut Entry, keyword: &str, text: String) -> Field {
+    if keyword == "msgctxt" {
+        entry.context = Some(text);
+        Field::Context
+    } else {
+        entry.id = Some(text);
+        Field::Id
+    }
+}
+
+/// Unquote and unescape a string
+fn string(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        text.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            c @ ('"' | '\\') => c,
+            _ => return None,
+        });
+    }
+
+    Some(text)
+}
+
+/// Split a translation into text and `{name}` placeholders
+fn placeholders(translation: &str) -> Pattern {
+    let mut elements = Vec::new();
+    let mut rest = translation;
+
+    while let Some(start) = rest.find('{') {
+        let name = rest[start + 1..]
+            .split_once('}')
+            .map(|(name, _)| name)
+            .filter(|name| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+
+        let Some(name) = name else {
+            push_text(&mut elements, &rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        };
+
+        push_text(&mut elements, &rest[..start]);
+        elements.push(Element::Variable(name.to_string()));
+        rest = &rest[start + name.len() + 2..];
+    }
+    push_text(&mut elements, rest);
+
+    Pattern(elements)
+}
+
+fn push_text(elements: &mut Vec<Element>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+
+    match elements.last_mut() {
+        Some(Element::Text(last)) => last.push_str(text),
+        _ => elements.push(Element::Text(text.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Catalog, Format, L10nError};
+
+    const SOURCE: &str = r#"
+# French translations
+msgid ""
+msgstr ""
+"Language: fr\n"
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+msgid "welcome"
+msgstr "Bienvenue, {name} !"
+
+#, fuzzy
+msgid "about"
+msgstr "À propos"
+
+msgid "untranslated"
+msgstr ""
+
+msgctxt "menu"
+msgid "open"
+msgstr "Ouvrir"
+
+msgid "unread"
+msgid_plural "unread"
+msgstr[0] "{count} message non lu"
+msgstr[1] "{count} messages non lus"
+
+msgid "multiline"
+msgstr ""
+"Première ligne\n"
+"{not a placeholder} \"cité\""
+"#;
+
+    #[test]
+    fn parses_entries() {
+        let catalog = Catalog::parse(SOURCE, Format::Gettext).unwrap();
+
+        assert_eq!(catalog.len(), 4);
+        assert_eq!(
+            catalog
+                .format("welcome", &[("name", "Ana".into())])
+                .unwrap(),
+            "Bienvenue, Ana !"
+        );
+        assert_eq!(catalog.format("menu.open", &[]).unwrap(), "Ouvrir");
+        assert_eq!(
+            catalog.format("multiline", &[]).unwrap(),
+            "Première ligne\n{not a placeholder} \"cité\""
+        );
+        assert!(!catalog.contains("about"));
+        assert!(!catalog.contains("untranslated"));
+    }
+
+    #[test]
+    fn selects_plural_forms() {
+        let catalog = Catalog::parse(SOURCE, Format::Gettext).unwrap();
+
+        assert_eq!(
+            catalog.format("unread", &[("count", 1.into())]).unwrap(),
+            "1 message non lu"
+        );
+        assert_eq!(
+            catalog.format("unread", &[("count", 3.into())]).unwrap(),
+            "3 messages non lus"
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        for (source, line) in [
+            ("msgid \"hello\"\nmsgstr \"Hello", 2),
+            ("\"orphan\"", 1),
+            ("msgid \"a\"\nmsgstr[1] \"b\"", 2),
+            ("msgid \"a\"\nmsgfoo \"b\"", 2),
+        ] {
+            match Catalog::parse(source, Format::Gettext) {
+                Err(L10nError::Parse { line: actual, .. }) => assert_eq!(actual, line, "{source}"),
+                result => panic!("expected a syntax error in {source:?}, got {result:?}"),
+            }
+        }
+    }
+}
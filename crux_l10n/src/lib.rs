@@ -0,0 +1,329 @@
+//! Localization for Crux apps
+//!
+//! Translations live with the core rather than in each shell's resource system: a
+//! [`Localization`] holds a [`Catalog`] of messages for each locale, parsed from Fluent (`.ftl`)
+//! or gettext (`.po`) sources, and formats them for the current locale with the [`t!`] macro,
+//! typically in `view()`:
+//!
+//! ```
+//! # use crux_l10n::{t, Catalog, Format, Localization};
+//! let mut l10n = Localization::new("en");
+//! l10n.insert("en", Catalog::parse("greeting = Hello, { $name }!", Format::Fluent).unwrap());
+//! l10n.insert("fr", Catalog::parse("greeting = Bonjour, { $name } !", Format::Fluent).unwrap());
+//!
+//! assert_eq!(t!(l10n, "greeting", name = "Ana"), "Hello, Ana!");
+//!
+//! l10n.set_locale("fr-CA");
+//! assert_eq!(t!(l10n, "greeting", name = "Ana"), "Bonjour, Ana !");
+//! ```
+//!
+//! Catalogs can be bundled with the core (e.g. with `include_str!`), or downloaded with
+//! [`fetch`] using the HTTP capability. Switching the locale at runtime with
+//! [`Localization::switch_locale`] asks the shell to render, so the new translations show
+//! straight away.
+
+mod catalog;
+mod fluent;
+mod gettext;
+
+pub use catalog::{Catalog, Format};
+
+use std::{collections::HashMap, fmt};
+
+use crux_core::render::Render;
+use crux_http::{Http, HttpError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Error type for loading catalogs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum L10nError {
+    #[error("http error: {error}")]
+    Http { error: HttpError },
+    #[error("syntax error on line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("unknown catalog format of {url}")]
+    UnknownFormat { url: String },
+}
+
+/// An argument to a message, see [`t!`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    String(String),
+    Number(f64),
+}
+
+impl Arg {
+    /// Whether the argument selects the variant with the `key`. Numbers match numeric keys
+    /// with the same value, and plural categories following the English rules: `one` for 1,
+    /// and `other` for anything else.
+    pub fn matches(&self, key: &str) -> bool {
+        match self {
+            Arg::String(value) => value == key,
+            #[allow(clippy::float_cmp)]
+            Arg::Number(value) => match key.parse::<f64>() {
+                Ok(number) => *value == number,
+                Err(_) if key == "one" => *value == 1.0,
+                Err(_) => key == "other",
+            },
+        }
+    }
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::String(value) => f.write_str(value),
+            Arg::Number(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        Arg::String(value.to_string())
+    }
+}
+
+impl From<&String> for Arg {
+    fn from(value: &String) -> Self {
+        Arg::String(value.clone())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(value: String) -> Self {
+        Arg::String(value)
+    }
+}
+
+macro_rules! number_args {
+    ($($number:ty),*) => {
+        $(
+            impl From<$number> for Arg {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn from(value: $number) -> Self {
+                    Arg::Number(value as f64)
+                }
+            }
+        )*
+    };
+}
+
+number_args!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+/// Format the message with an id in the current locale of a [`Localization`], with named
+/// arguments.
+///
+/// ```
+/// # use crux_l10n::{t, Catalog, Format, Localization};
+/// # let mut l10n = Localization::new("en");
+/// # l10n.insert("en", Catalog::parse(
+/// #     "unread = { $count ->\n [one] One unread message\n *[other] { $count } unread messages\n}",
+/// #     Format::Fluent,
+/// # ).unwrap());
+/// let count = 3;
+/// assert_eq!(t!(l10n, "unread", count = count), "3 unread messages");
+/// assert_eq!(t!(l10n, "missing"), "missing");
+/// ```
+#[macro_export]
+macro_rules! t {
+    ($l10n:expr, $id:expr $(,)?) => {
+        $l10n.format($id, &[])
+    };
+    ($l10n:expr, $id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $l10n.format($id, &[$((stringify!($name), $crate::Arg::from($value))),+])
+    };
+}
+
+/// The catalogs of the locales an app supports, and the current locale.
+///
+/// Messages are looked up in the catalog of the current locale (e.g. `fr-CA`), then of its
+/// language (`fr`), then of the fallback locale. Messages missing from all of them are formatted
+/// as their id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Localization {
+    catalogs: HashMap<String, Catalog>,
+    locale: String,
+    fallback: String,
+}
+
+impl Localization {
+    /// A localization with the `fallback` locale as the current locale.
+    pub fn new(fallback: impl Into<String>) -> Self {
+        let fallback = fallback.into();
+
+        Self {
+            catalogs: HashMap::new(),
+            locale: fallback.clone(),
+            fallback,
+        }
+    }
+
+    /// The current locale
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn fallback(&self) -> &str {
+        &self.fallback
+    }
+
+    /// Add the `catalog` of the `locale`, merging it into the catalog already added, if any.
+    pub fn insert(&mut self, locale: impl Into<String>, catalog: Catalog) {
+        self.catalogs
+            .entry(locale.into())
+            .or_default()
+            .merge(catalog);
+    }
+
+    /// Whether a catalog has been added for the `locale`, or its language.
+    pub fn has_catalog(&self, locale: &str) -> bool {
+        self.catalogs.contains_key(locale) || self.catalogs.contains_key(language(locale))
+    }
+
+    /// The locales with a catalog
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.catalogs.keys().map(String::as_str)
+    }
+
+    /// Change the current locale. Returns whether it changed.
+    pub fn set_locale(&mut self, locale: impl Into<String>) -> bool {
+        let locale = locale.into();
+        if locale == self.locale {
+            return false;
+        }
+
+        self.locale = locale;
+        true
+    }
+
+    /// Change the current locale, and if it changed, ask the shell to render the new translations.
+    /// Returns whether it changed.
+    ///
+    /// If there isn't a catalog for the `locale` yet, messages fall back to the fallback locale
+    /// until it's [fetched](fetch) and [inserted](Localization::insert), after which the app
+    /// should render again.
+    pub fn switch_locale<Ev>(&mut self, locale: impl Into<String>, render: &Render<Ev>) -> bool
+    where
+        Ev: 'static,
+    {
+        let changed = self.set_locale(locale);
+        if changed {
+            render.render();
+        }
+
+        changed
+    }
+
+    /// Format the message with the `id` in the current locale, see [`t!`].
+    pub fn format(&self, id: &str, args: &[(&str, Arg)]) -> String {
+        [
+            self.locale.as_str(),
+            language(&self.locale),
+            self.fallback.as_str(),
+        ]
+        .into_iter()
+        .filter_map(|locale| self.catalogs.get(locale))
+        .find_map(|catalog| catalog.format(id, args))
+        .unwrap_or_else(|| id.to_string())
+    }
+}
+
+/// The language subtag of a locale, e.g. `pt` of `pt-BR`
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// Download and parse the catalog at `url`, in the given `format`, or the format matching the
+/// extension of the `url` if `None`.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the format is unknown, or the catalog is invalid.
+pub async fn fetch<Ev>(
+    http: &Http<Ev>,
+    url: &str,
+    format: Option<Format>,
+) -> Result<Catalog, L10nError>
+where
+    Ev: 'static,
+{
+    let error = |error| L10nError::Http { error };
+
+    let format =
+        format
+            .or_else(|| Format::from_path(url))
+            .ok_or_else(|| L10nError::UnknownFormat {
+                url: url.to_string(),
+            })?;
+
+    let mut response = http.get(url).send_async().await.map_err(error)?;
+
+    if !response.status().is_success() {
+        return Err(error(HttpError::Http {
+            code: response.status(),
+            message: format!("fetching catalog {url} failed"),
+            body: response.body_bytes().await.ok(),
+        }));
+    }
+
+    let source = response.body_string().await.map_err(error)?;
+
+    Catalog::parse(&source, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(source: &str) -> Catalog {
+        Catalog::parse(source, Format::Fluent).unwrap()
+    }
+
+    #[test]
+    fn falls_back_to_language_then_fallback_locale() {
+        let mut l10n = Localization::new("en");
+        l10n.insert("en", catalog("yes = Yes\nno = No\ncolor = Color"));
+        l10n.insert("en-GB", catalog("color = Colour"));
+        l10n.insert("pt", catalog("yes = Sim"));
+
+        l10n.set_locale("en-GB");
+        assert_eq!(t!(l10n, "color"), "Colour");
+        assert_eq!(t!(l10n, "yes"), "Yes");
+
+        l10n.set_locale("pt-BR");
+        assert_eq!(t!(l10n, "yes"), "Sim");
+        assert_eq!(t!(l10n, "no"), "No");
+        assert_eq!(t!(l10n, "maybe"), "maybe");
+        assert!(l10n.has_catalog("pt-BR"));
+        assert!(!l10n.has_catalog("de"));
+    }
+
+    #[test]
+    fn merges_catalogs_of_a_locale() {
+        let mut l10n = Localization::new("en");
+        l10n.insert("en", catalog("yes = Yes\nno = No"));
+        l10n.insert("en", catalog("no = Nope"));
+
+        assert_eq!(t!(l10n, "yes"), "Yes");
+        assert_eq!(t!(l10n, "no"), "Nope");
+    }
+
+    #[test]
+    fn matches_variant_keys() {
+        assert!(Arg::from(1).matches("one"));
+        assert!(Arg::from(1).matches("1"));
+        assert!(!Arg::from(2).matches("one"));
+        assert!(Arg::from(2.5).matches("other"));
+        assert!(Arg::from("female").matches("female"));
+        assert!(!Arg::from("female").matches("other"));
+    }
+
+    #[test]
+    fn formats_number_arguments() {
+        assert_eq!(Arg::from(3_u64).to_string(), "3");
+        assert_eq!(Arg::from(2.5).to_string(), "2.5");
+    }
+}
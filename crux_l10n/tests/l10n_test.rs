@@ -0,0 +1,180 @@
+mod shared {
+    use crux_core::compose::Compose;
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_http::Http;
+    use crux_l10n::{fetch, t, Catalog, Format, L10nError, Localization};
+    use serde::{Deserialize, Serialize};
+
+    const EN: &str = r"
+greeting = Hello, { $name }!
+inbox = { $count ->
+    [0] Your inbox is empty
+    [one] One message
+   *[other] { $count } messages
+}
+";
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        SwitchLanguage(String),
+
+        #[serde(skip)]
+        CatalogFetched(String, Result<Catalog, L10nError>),
+    }
+
+    pub struct Model {
+        pub l10n: Localization,
+        pub name: String,
+        pub messages: usize,
+        pub error: Option<L10nError>,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            let mut l10n = Localization::new("en");
+            l10n.insert("en", Catalog::parse(EN, Format::Fluent).unwrap());
+
+            Self {
+                l10n,
+                name: "Ana".to_string(),
+                messages: 2,
+                error: None,
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub struct ViewModel {
+        pub greeting: String,
+        pub inbox: String,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::SwitchLanguage(locale) => {
+                    if !model.l10n.has_catalog(&locale) {
+                        let http = caps.http.clone();
+                        let url = format!("https://example.com/l10n/{locale}.po");
+
+                        caps.compose.spawn(|context| async move {
+                            let result = fetch(&http, &url, None).await;
+                            context.update_app(Event::CatalogFetched(locale, result));
+                        });
+                        return;
+                    }
+
+                    model.l10n.switch_locale(locale, &caps.render);
+                }
+                Event::CatalogFetched(locale, Ok(catalog)) => {
+                    model.l10n.insert(locale.clone(), catalog);
+                    model.l10n.switch_locale(locale, &caps.render);
+                }
+                Event::CatalogFetched(_, Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            ViewModel {
+                greeting: t!(model.l10n, "greeting", name = &model.name),
+                inbox: t!(model.l10n, "inbox", count = model.messages),
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub render: Render<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpResponse, HttpResult};
+    use crux_l10n::L10nError;
+
+    use crate::shared::{App, Effect, Event, Model, ViewModel};
+
+    const FR: &str = r#"
+msgid "greeting"
+msgstr "Bonjour, {name} !"
+"#;
+
+    #[test]
+    fn fetches_a_catalog_and_switches_language() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        assert_eq!(
+            app.view(&model),
+            ViewModel {
+                greeting: "Hello, Ana!".to_string(),
+                inbox: "2 messages".to_string(),
+            }
+        );
+
+        let update = app.update(Event::SwitchLanguage("fr".to_string()), &mut model);
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("Expected Http effect");
+        };
+        assert_eq!(request.operation.url, "https://example.com/l10n/fr.po");
+
+        let response = HttpResponse::ok().body(FR).build();
+        let update = app.resolve(&mut request, HttpResult::Ok(response)).unwrap();
+        for event in update.events {
+            let update = app.update(event, &mut model);
+            assert!(matches!(update.effects().next(), Some(Effect::Render(_))));
+        }
+
+        assert_eq!(model.l10n.locale(), "fr");
+        assert_eq!(
+            app.view(&model),
+            ViewModel {
+                greeting: "Bonjour, Ana !".to_string(),
+                // missing from the French catalog
+                inbox: "2 messages".to_string(),
+            }
+        );
+
+        let update = app.update(Event::SwitchLanguage("en".to_string()), &mut model);
+        assert!(matches!(update.effects().next(), Some(Effect::Render(_))));
+        assert_eq!(app.view(&model).greeting, "Hello, Ana!");
+    }
+
+    #[test]
+    fn reports_invalid_catalogs() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::SwitchLanguage("de".to_string()), &mut model);
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("Expected Http effect");
+        };
+
+        let response = HttpResponse::ok()
+            .body("msgid \"greeting\"\nmsgstr \"Hallo")
+            .build();
+        let update = app.resolve(&mut request, HttpResult::Ok(response)).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert!(matches!(
+            model.error,
+            Some(L10nError::Parse { line: 2, .. })
+        ));
+        assert_eq!(model.l10n.locale(), "en");
+    }
+}
@@ -0,0 +1,225 @@
+//! Logging to the shell
+//!
+//! The [`Log`] capability sends log messages from the core to the shell, which writes them
+//! to the platform's log (e.g. `os_log`, Logcat or the browser console) alongside its own,
+//! so that what the core did can be diagnosed from the same logs in production.
+//!
+//! Logging is fire and forget: the shell doesn't respond, and the app isn't sent an event.
+//!
+//! The capability also keeps the most recent messages as [`Breadcrumbs`], e.g. to attach
+//! to error reports.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crux_core::capability::{Capability, CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        };
+
+        f.write_str(level)
+    }
+}
+
+/// A message to write to the shell's log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogOperation {
+    pub level: LogLevel,
+    /// Where in the core the message comes from, e.g. `crux_http` or a module of the app,
+    /// for the shell to filter by
+    pub target: String,
+    pub message: String,
+}
+
+impl Operation for LogOperation {
+    type Output = ();
+}
+
+/// The number of messages kept as [`Breadcrumbs`]
+pub const BREADCRUMBS: usize = 50;
+
+/// The most recent messages written with a [`Log`], oldest first, shared by all its clones.
+#[derive(Clone, Debug, Default)]
+pub struct Breadcrumbs {
+    messages: Arc<Mutex<VecDeque<LogOperation>>>,
+}
+
+impl Breadcrumbs {
+    /// The last (up to [`BREADCRUMBS`]) messages, oldest first
+    pub fn recent(&self) -> Vec<LogOperation> {
+        self.messages.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn push(&self, message: LogOperation) {
+        let mut messages = self.messages.lock().unwrap();
+        if messages.len() == BREADCRUMBS {
+            messages.pop_front();
+        }
+        messages.push_back(message);
+    }
+}
+
+/// The Log capability API
+///
+/// This capability lets the core write messages to the shell's log.
+pub struct Log<Ev> {
+    context: CapabilityContext<LogOperation, Ev>,
+    breadcrumbs: Breadcrumbs,
+}
+
+impl<Ev> Clone for Log<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+            breadcrumbs: self.breadcrumbs.clone(),
+        }
+    }
+}
+
+impl<Ev> Capability<Ev> for Log<Ev> {
+    type Operation = LogOperation;
+    type MappedSelf<MappedEv> = Log<MappedEv>;
+
+    fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+    where
+        F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+        NewEv: 'static,
+    {
+        Log {
+            context: self.context.map_event(f),
+            breadcrumbs: self.breadcrumbs.clone(),
+        }
+    }
+}
+
+impl<Ev> Log<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<LogOperation, Ev>) -> Self {
+        Self {
+            context,
+            breadcrumbs: Breadcrumbs::default(),
+        }
+    }
+
+    /// The most recent messages written to the log, see [`Breadcrumbs`].
+    pub fn breadcrumbs(&self) -> Breadcrumbs {
+        self.breadcrumbs.clone()
+    }
+
+    /// Write `message` to the shell's log at `level`, attributed to `target`.
+    pub fn log(&self, level: LogLevel, target: impl Into<String>, message: impl Into<String>) {
+        self.context.spawn({
+            let this = self.clone();
+            let target = target.into();
+            let message = message.into();
+
+            async move { this.log_async(level, target, message).await }
+        });
+    }
+
+    /// Write `message` to the shell's log at `level`, attributed to `target`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn log_async(
+        &self,
+        level: LogLevel,
+        target: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        let operation = LogOperation {
+            level,
+            target: target.into(),
+            message: message.into(),
+        };
+
+        self.breadcrumbs.push(operation.clone());
+        self.context.notify_shell(operation).await;
+    }
+
+    /// Write `message` to the shell's log at [`LogLevel::Debug`].
+    pub fn debug(&self, target: impl Into<String>, message: impl Into<String>) {
+        self.log(LogLevel::Debug, target, message);
+    }
+
+    /// Write `message` to the shell's log at [`LogLevel::Info`].
+    pub fn info(&self, target: impl Into<String>, message: impl Into<String>) {
+        self.log(LogLevel::Info, target, message);
+    }
+
+    /// Write `message` to the shell's log at [`LogLevel::Warn`].
+    pub fn warn(&self, target: impl Into<String>, message: impl Into<String>) {
+        self.log(LogLevel::Warn, target, message);
+    }
+
+    /// Write `message` to the shell's log at [`LogLevel::Error`].
+    pub fn error(&self, target: impl Into<String>, message: impl Into<String>) {
+        self.log(LogLevel::Error, target, message);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = LogOperation {
+            level: LogLevel::Warn,
+            target: "crux_http".to_string(),
+            message: "<-- 503 GET https://example.com/".to_string(),
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"level":"warn","target":"crux_http","message":"<-- 503 GET https://example.com/"}"#
+        );
+
+        let deserialized: LogOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+    }
+
+    #[test]
+    fn test_breadcrumbs_keep_the_latest_messages() {
+        let breadcrumbs = Breadcrumbs::default();
+        for i in 0..BREADCRUMBS + 2 {
+            breadcrumbs.push(LogOperation {
+                level: LogLevel::Info,
+                target: "test".to_string(),
+                message: i.to_string(),
+            });
+        }
+
+        let recent = breadcrumbs.recent();
+        assert_eq!(recent.len(), BREADCRUMBS);
+        assert_eq!(recent[0].message, "2");
+        assert_eq!(
+            recent[BREADCRUMBS - 1].message,
+            (BREADCRUMBS + 1).to_string()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_log::Log;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        SignIn { user: String },
+        SignInFailed { reason: String },
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub user: Option<String>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::SignIn { user } => {
+                    caps.log.info("auth", format!("signed in as {user}"));
+                    model.user = Some(user);
+                    caps.render.render();
+                }
+                Event::SignInFailed { reason } => {
+                    caps.log.error("auth", format!("sign in failed: {reason}"));
+                }
+            }
+        }
+
+        fn view(&self, model: &Model) -> Option<String> {
+            model.user.clone()
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub log: Log<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_log::{LogLevel, LogOperation};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    #[test]
+    fn logs_are_sent_to_the_shell() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut update = app.update(
+            Event::SignIn {
+                user: "ferris".to_string(),
+            },
+            &mut model,
+        );
+
+        let Effect::Log(request) = update.effects_mut().next().unwrap() else {
+            panic!("expected a log effect");
+        };
+        assert_eq!(
+            request.operation,
+            LogOperation {
+                level: LogLevel::Info,
+                target: "auth".to_string(),
+                message: "signed in as ferris".to_string(),
+            }
+        );
+        assert!(update.effects().any(Effect::is_render));
+    }
+
+    #[test]
+    fn logging_sends_no_events() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::SignInFailed {
+                reason: "wrong password".to_string(),
+            },
+            &mut model,
+        );
+
+        assert_eq!(update.effects.len(), 1);
+        assert!(update.events.is_empty());
+        assert_eq!(app.view(&model), None);
+    }
+}
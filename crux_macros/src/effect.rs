@@ -21,6 +21,23 @@ pub struct EffectFieldReceiver {
     ty: Type,
     #[darling(default)]
     skip: bool,
+    #[darling(default)]
+    timeout_ms: Option<u64>,
+    #[darling(default, with = parse_max_in_flight)]
+    max_in_flight: Option<usize>,
+    #[darling(default)]
+    local: bool,
+}
+
+// a limit of 0 would never let a request through, so it's rejected here, rather than
+// panicking when the capability is created
+fn parse_max_in_flight(meta: &syn::Meta) -> darling::Result<Option<usize>> {
+    let max = usize::from_meta(meta)?;
+    if max == 0 {
+        return Err(darling::Error::custom("max_in_flight must be at least 1").with_span(meta));
+    }
+
+    Ok(Some(max))
 }
 
 struct Field {
@@ -28,6 +45,8 @@ struct Field {
     variant: Ident,
     event: Type,
     skip: bool,
+    timeout_ms: Option<u64>,
+    max_in_flight: Option<usize>,
 }
 
 impl From<&EffectFieldReceiver> for Field {
@@ -38,6 +57,8 @@ impl From<&EffectFieldReceiver> for Field {
             variant,
             event,
             skip: f.skip,
+            timeout_ms: f.timeout_ms,
+            max_in_flight: f.max_in_flight,
         }
     }
 }
@@ -71,6 +92,9 @@ impl ToTokens for EffectStructReceiver {
             .expect_or_abort("should be a struct")
             .fields;
 
+        // local capabilities are services in the core, which don't request effects
+        let (local_fields, fields): (Vec<_>, Vec<_>) = fields.into_iter().partition(|f| f.local);
+
         let fields: BTreeMap<Ident, Field> = fields
             .into_iter()
             .map(|f| (f.ident.clone().unwrap(), f.into()))
@@ -93,6 +117,13 @@ impl ToTokens for EffectStructReceiver {
         let mut match_arms = Vec::new();
         let mut filters = Vec::new();
 
+        for field in local_fields {
+            let field_name = field.ident.as_ref().unwrap();
+            with_context_fields.push(quote! {
+                #field_name: ::std::default::Default::default()
+            });
+        }
+
         for (
             field_name,
             Field {
@@ -100,6 +131,8 @@ impl ToTokens for EffectStructReceiver {
                 variant,
                 event,
                 skip,
+                timeout_ms,
+                max_in_flight,
             },
         ) in fields.iter()
         {
@@ -109,8 +142,19 @@ impl ToTokens for EffectStructReceiver {
                     #field_name: #capability::new(context.specialize(|_| unreachable!(#msg)))
                 });
             } else {
+                let mut context = quote!(context.specialize(#effect_name::#variant));
+                if let Some(millis) = timeout_ms {
+                    let millis = Literal::u64_unsuffixed(*millis);
+                    context = quote! {
+                        #context.with_timeout(::std::time::Duration::from_millis(#millis))
+                    };
+                }
+                if let Some(max) = max_in_flight {
+                    let max = Literal::usize_unsuffixed(*max);
+                    context = quote!(#context.with_max_in_flight(#max));
+                }
                 with_context_fields.push(quote! {
-                    #field_name: #capability::new(context.specialize(#effect_name::#variant))
+                    #field_name: #capability::new(#context)
                 });
 
                 variants.push(quote! {
@@ -288,6 +332,70 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn effect_local() {
+        let input = r#"
+            #[derive(Effect)]
+            pub struct Capabilities {
+                pub render: Render<Event>,
+                #[effect(local)]
+                pub search: SearchIndex,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = EffectStructReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        #[derive(Debug)]
+        pub enum Effect {
+            Render(
+                ::crux_core::Request<
+                    <Render<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                >,
+            ),
+        }
+        #[derive(::serde::Serialize, ::serde::Deserialize)]
+        #[serde(rename = "Effect")]
+        pub enum EffectFfi {
+            Render(<Render<Event> as ::crux_core::capability::Capability<Event>>::Operation),
+        }
+        impl ::crux_core::Effect for Effect {
+            type Ffi = EffectFfi;
+            fn serialize(self) -> (Self::Ffi, ::crux_core::bridge::ResolveSerialized) {
+                match self {
+                    Effect::Render(request) => request.serialize(EffectFfi::Render),
+                }
+            }
+        }
+        impl ::crux_core::WithContext<App, Effect> for Capabilities {
+            fn new_with_context(
+                context: ::crux_core::capability::ProtoContext<Effect, Event>,
+            ) -> Capabilities {
+                Capabilities {
+                    search: ::std::default::Default::default(),
+                    render: Render::new(context.specialize(Effect::Render)),
+                }
+            }
+        }
+        impl Effect {
+            pub fn is_render(&self) -> bool {
+                if let Effect::Render(_) = self { true } else { false }
+            }
+            pub fn into_render(
+                self,
+            ) -> Option<
+                crux_core::Request<
+                    <Render<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                >,
+            > {
+                if let Effect::Render(request) = self { Some(request) } else { None }
+            }
+        }
+        "###);
+    }
+
     #[test]
     fn effect_skip() {
         let input = r#"
@@ -533,6 +641,157 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn effect_timeout() {
+        let input = r#"
+            #[derive(Effect)]
+            pub struct Capabilities {
+                #[effect(timeout_ms = 5000)]
+                pub key_value: KeyValue<Event>,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = EffectStructReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        #[derive(Debug)]
+        pub enum Effect {
+            KeyValue(
+                ::crux_core::Request<
+                    <KeyValue<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                >,
+            ),
+        }
+        #[derive(::serde::Serialize, ::serde::Deserialize)]
+        #[serde(rename = "Effect")]
+        pub enum EffectFfi {
+            KeyValue(<KeyValue<Event> as ::crux_core::capability::Capability<Event>>::Operation),
+        }
+        impl ::crux_core::Effect for Effect {
+            type Ffi = EffectFfi;
+            fn serialize(self) -> (Self::Ffi, ::crux_core::bridge::ResolveSerialized) {
+                match self {
+                    Effect::KeyValue(request) => request.serialize(EffectFfi::KeyValue),
+                }
+            }
+        }
+        impl ::crux_core::WithContext<App, Effect> for Capabilities {
+            fn new_with_context(
+                context: ::crux_core::capability::ProtoContext<Effect, Event>,
+            ) -> Capabilities {
+                Capabilities {
+                    key_value: KeyValue::new(
+                        context
+                            .specialize(Effect::KeyValue)
+                            .with_timeout(::std::time::Duration::from_millis(5000)),
+                    ),
+                }
+            }
+        }
+        impl Effect {
+            pub fn is_key_value(&self) -> bool {
+                if let Effect::KeyValue(_) = self { true } else { false }
+            }
+            pub fn into_key_value(
+                self,
+            ) -> Option<
+                crux_core::Request<
+                    <KeyValue<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                >,
+            > {
+                if let Effect::KeyValue(request) = self { Some(request) } else { None }
+            }
+        }
+        "###);
+    }
+
+    #[test]
+    fn effect_max_in_flight() {
+        let input = r#"
+            #[derive(Effect)]
+            pub struct Capabilities {
+                #[effect(timeout_ms = 5000, max_in_flight = 4)]
+                pub http: Http<Event>,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = EffectStructReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        #[derive(Debug)]
+        pub enum Effect {
+            Http(
+                ::crux_core::Request<
+                    <Http<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                >,
+            ),
+        }
+        #[derive(::serde::Serialize, ::serde::Deserialize)]
+        #[serde(rename = "Effect")]
+        pub enum EffectFfi {
+            Http(<Http<Event> as ::crux_core::capability::Capability<Event>>::Operation),
+        }
+        impl ::crux_core::Effect for Effect {
+            type Ffi = EffectFfi;
+            fn serialize(self) -> (Self::Ffi, ::crux_core::bridge::ResolveSerialized) {
+                match self {
+                    Effect::Http(request) => request.serialize(EffectFfi::Http),
+                }
+            }
+        }
+        impl ::crux_core::WithContext<App, Effect> for Capabilities {
+            fn new_with_context(
+                context: ::crux_core::capability::ProtoContext<Effect, Event>,
+            ) -> Capabilities {
+                Capabilities {
+                    http: Http::new(
+                        context
+                            .specialize(Effect::Http)
+                            .with_timeout(::std::time::Duration::from_millis(5000))
+                            .with_max_in_flight(4),
+                    ),
+                }
+            }
+        }
+        impl Effect {
+            pub fn is_http(&self) -> bool {
+                if let Effect::Http(_) = self { true } else { false }
+            }
+            pub fn into_http(
+                self,
+            ) -> Option<
+                crux_core::Request<
+                    <Http<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                >,
+            > {
+                if let Effect::Http(request) = self { Some(request) } else { None }
+            }
+        }
+        "###);
+    }
+
+    #[test]
+    fn effect_max_in_flight_must_be_positive() {
+        let input = r#"
+            #[derive(Effect)]
+            pub struct Capabilities {
+                #[effect(max_in_flight = 0)]
+                pub http: Http<Event>,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let error = EffectStructReceiver::from_derive_input(&input).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "max_in_flight must be at least 1 at http/max_in_flight"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn should_panic_when_multiple_event_types() {
@@ -15,8 +15,20 @@ struct ExportStructReceiver {
 }
 
 #[derive(FromField, Debug)]
+#[darling(attributes(effect))]
 pub struct ExportFieldReceiver {
     ty: Type,
+    #[darling(default)]
+    #[allow(dead_code)] // `skip` is used by the effect derive macro only
+    skip: bool,
+    #[darling(default)]
+    #[allow(dead_code)] // `timeout_ms` is used by the effect derive macro only
+    timeout_ms: Option<u64>,
+    #[darling(default)]
+    #[allow(dead_code)] // `max_in_flight` is used by the effect derive macro only
+    max_in_flight: Option<usize>,
+    #[darling(default)]
+    local: bool,
 }
 
 impl ToTokens for ExportStructReceiver {
@@ -41,7 +53,10 @@ impl ToTokens for ExportStructReceiver {
 
         let mut output_type_exports = Vec::new();
 
-        for (capability, event) in fields.iter().map(|f| split_on_generic(&f.ty)) {
+        // local capabilities don't cross the FFI boundary
+        let fields = fields.iter().filter(|f| !f.local);
+
+        for (capability, event) in fields.map(|f| split_on_generic(&f.ty)) {
             output_type_exports.push(quote! {
                 generator.register_type::<<#capability<#event> as ::crux_core::capability::Capability<#event>>::Operation>()?;
                 generator
@@ -149,6 +164,85 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn local_capabilities_are_not_exported() {
+        let input = r#"
+            #[derive(Export)]
+            pub struct Capabilities {
+                pub render: Render<Event>,
+                #[effect(local)]
+                pub search: SearchIndex,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = ExportStructReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        impl ::crux_core::typegen::Export for Capabilities {
+            fn register_types(
+                generator: &mut ::crux_core::typegen::TypeGen,
+            ) -> ::crux_core::typegen::Result {
+                generator
+                    .register_type::<
+                        <Render<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                    >()?;
+                generator
+                    .register_type::<
+                        <<Render<
+                            Event,
+                        > as ::crux_core::capability::Capability<
+                            Event,
+                        >>::Operation as ::crux_core::capability::Operation>::Output,
+                    >()?;
+                generator.register_type::<EffectFfi>()?;
+                generator.register_type::<::crux_core::bridge::Request<EffectFfi>>()?;
+                Ok(())
+            }
+        }
+        "###);
+    }
+
+    #[test]
+    fn effect_attributes_are_ignored() {
+        let input = r#"
+            #[derive(Effect, Export)]
+            pub struct Capabilities {
+                #[effect(timeout_ms = 5000, max_in_flight = 4)]
+                pub render: Render<Event>,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = ExportStructReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        impl ::crux_core::typegen::Export for Capabilities {
+            fn register_types(
+                generator: &mut ::crux_core::typegen::TypeGen,
+            ) -> ::crux_core::typegen::Result {
+                generator
+                    .register_type::<
+                        <Render<Event> as ::crux_core::capability::Capability<Event>>::Operation,
+                    >()?;
+                generator
+                    .register_type::<
+                        <<Render<
+                            Event,
+                        > as ::crux_core::capability::Capability<
+                            Event,
+                        >>::Operation as ::crux_core::capability::Operation>::Output,
+                    >()?;
+                generator.register_type::<EffectFfi>()?;
+                generator.register_type::<::crux_core::bridge::Request<EffectFfi>>()?;
+                Ok(())
+            }
+        }
+        "###);
+    }
+
     #[test]
     fn split_event_types_preserves_path() {
         let ty = Type::from_string("crux_core::render::Render<Event>").unwrap();
@@ -1,12 +1,16 @@
 mod capability;
 mod effect;
 mod export;
+mod preview;
+mod responses;
 
 use capability::capability_impl;
 use effect::effect_impl;
 use export::export_impl;
+use preview::preview_impl;
 use proc_macro::TokenStream;
 use proc_macro_error::proc_macro_error;
+use responses::{responses_impl, Calls};
 use syn::parse_macro_input;
 
 /// Procedural macro to derive an Effect enum, with a variant for
@@ -21,6 +25,44 @@ use syn::parse_macro_input;
 /// No Effect variant will be generated for fields annotated with
 /// `#[effect(skip)]`.
 ///
+/// Fields annotated with `#[effect(local)]` are services which run entirely in the
+/// core and never request effects from the shell, e.g. a search index. They don't need
+/// to be generic over the event type, are created with `Default`, and are left out of the
+/// Effect enum and the exported types. In tests, they can be swapped with
+/// `AppTester::with_capabilities`.
+///
+/// Fields annotated with `#[effect(timeout_ms = 5000)]` give up on requests the shell
+/// doesn't resolve within the timeout, see `CapabilityContext::with_timeout`, e.g. all the
+/// requests of `crux_http` and `crux_kv`, and asking `crux_time` for the time. Only
+/// capabilities whose operation implements `TimesOut` can time out, so the attribute doesn't
+/// compile on the others:
+///
+/// ```compile_fail
+/// # use crux_core::{macros::Effect, render::Render};
+/// # #[derive(Default)]
+/// # struct MyApp;
+/// # pub enum MyEvent {None}
+/// # impl crux_core::App for MyApp {
+/// #     type Event = MyEvent;
+/// #     type Model = ();
+/// #     type ViewModel = ();
+/// #     type Capabilities = MyCapabilities;
+/// #     fn update(&self, _event: MyEvent, _model: &mut (), _caps: &MyCapabilities) {}
+/// #     fn view(&self, _model: &()) {}
+/// # }
+/// #[derive(Effect)]
+/// #[effect(app = "MyApp")]
+/// pub struct MyCapabilities {
+///     // rendering can't time out
+///     #[effect(timeout_ms = 5000)]
+///     pub render: Render<MyEvent>,
+/// }
+/// ```
+///
+/// Fields annotated with `#[effect(max_in_flight = 4)]` send at most that many requests
+/// to the shell at a time, queueing the rest, see `CapabilityContext::with_max_in_flight`.
+/// The limit must be at least 1.
+///
 /// e.g.
 /// ```rust
 /// # use crux_core::{Capability, render::Render, compose::Compose};
@@ -53,7 +95,7 @@ use syn::parse_macro_input;
 ///     #[effect(skip)]
 ///     pub compose: Compose<MyEvent>,
 /// }
-
+/// ```
 #[proc_macro_derive(Effect, attributes(effect))]
 #[proc_macro_error]
 pub fn effect(input: TokenStream) -> TokenStream {
@@ -71,3 +113,64 @@ pub fn export(input: TokenStream) -> TokenStream {
 pub fn capability(input: TokenStream) -> TokenStream {
     capability_impl(&parse_macro_input!(input)).into()
 }
+
+/// Procedural macro to derive [`Preview`](crux_core::preview::Preview) for a view model, or
+/// any type in it, building deterministic samples for UI previews from the samples of each
+/// field.
+///
+/// A field annotated with `#[preview(value = "...")]` always takes the given value, and
+/// an enum variant annotated with `#[preview(skip)]` is never sampled.
+///
+/// e.g.
+/// ```rust
+/// # use crux_core::macros::Preview;
+/// #[derive(Preview)]
+/// pub enum Screen {
+///     Loading,
+///     Basket { items: Vec<String>, total: u32 },
+///     #[preview(skip)]
+///     Crashed,
+/// }
+/// ```
+#[proc_macro_derive(Preview, attributes(preview))]
+#[proc_macro_error]
+pub fn preview(input: TokenStream) -> TokenStream {
+    preview_impl(&parse_macro_input!(input)).into()
+}
+
+/// Attribute macro to add the events for the results of capability calls to an Event enum,
+/// given the name and the result type of each call.
+///
+/// For each call, e.g. `get: Result<Vec<u8>, KeyValueError>`, it adds a `GetResponse(Vec<u8>)`
+/// variant, and a `get_response` function to pass to the capability as the callback. Errors
+/// all become a single `ResponseFailed { call, error }` event, with the error converted into
+/// a [`CapabilityError`](crux_core::error::CapabilityError), so the app can handle them in
+/// one place. Calls which can't fail, i.e. whose result type isn't a `Result`, just get the
+/// variant. Attributes on a call, e.g. `#[serde(skip)]`, are added to its variant.
+///
+/// The attribute needs to come before the derives on the enum, so that they see the
+/// added variants.
+///
+/// e.g.
+/// ```rust
+/// # use std::time::Duration;
+/// # use crux_core::capability::ShellTimeout;
+/// # use serde::{Deserialize, Serialize};
+/// #[crux_core::macros::responses(
+///     get: Result<Vec<u8>, ShellTimeout>,
+///     exists: Result<bool, ShellTimeout>,
+/// )]
+/// #[derive(Serialize, Deserialize, Debug)]
+/// pub enum Event {
+///     Get,
+/// }
+///
+/// let timeout = ShellTimeout { timeout: Duration::from_secs(1) };
+/// let event = Event::get_response(Err(timeout));
+/// assert!(matches!(event, Event::ResponseFailed { .. }));
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn responses(args: TokenStream, input: TokenStream) -> TokenStream {
+    responses_impl(parse_macro_input!(args as Calls), parse_macro_input!(input)).into()
+}
@@ -0,0 +1,215 @@
+use darling::{ast, FromDeriveInput, FromField, FromVariant, ToTokens};
+use proc_macro2::{Literal, TokenStream};
+use proc_macro_error::abort_call_site;
+use quote::quote;
+use syn::{parse_quote, Expr, Generics, Ident, Type};
+
+#[derive(FromDeriveInput, Debug)]
+#[darling(attributes(preview), supports(struct_any, enum_any))]
+struct PreviewReceiver {
+    ident: Ident,
+    generics: Generics,
+    data: ast::Data<PreviewVariantReceiver, PreviewFieldReceiver>,
+}
+
+#[derive(FromVariant, Debug)]
+#[darling(attributes(preview))]
+struct PreviewVariantReceiver {
+    ident: Ident,
+    fields: ast::Fields<PreviewFieldReceiver>,
+    #[darling(default)]
+    skip: bool,
+}
+
+#[derive(FromField, Debug)]
+#[darling(attributes(preview))]
+struct PreviewFieldReceiver {
+    ident: Option<Ident>,
+    ty: Type,
+    #[darling(default)]
+    value: Option<Expr>,
+}
+
+// the fields of one sample, each built from the same point in the cycle of samples
+fn build(path: TokenStream, fields: &ast::Fields<PreviewFieldReceiver>) -> TokenStream {
+    let values = fields.iter().enumerate().map(|(position, field)| {
+        let value = match field.value {
+            Some(ref value) => quote!(#value),
+            None => {
+                let ty = &field.ty;
+                let index = match position {
+                    0 => quote!(index),
+                    position => {
+                        let position = Literal::usize_unsuffixed(position);
+                        quote!(::crux_core::preview::field_index(index, #position))
+                    }
+                };
+                quote!(<#ty as ::crux_core::preview::Preview>::preview_at(#index))
+            }
+        };
+
+        match field.ident {
+            Some(ref ident) => quote!(#ident: #value),
+            None => value,
+        }
+    });
+
+    match fields.style {
+        ast::Style::Struct => quote!(#path { #(#values),* }),
+        ast::Style::Tuple => quote!(#path ( #(#values),* )),
+        ast::Style::Unit => path,
+    }
+}
+
+impl ToTokens for PreviewReceiver {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ident = &self.ident;
+
+        let mut generics = self.generics.clone();
+        for param in generics.type_params_mut() {
+            param
+                .bounds
+                .push(parse_quote!(::crux_core::preview::Preview));
+        }
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        let body = match self.data {
+            ast::Data::Struct(ref fields) => build(quote!(Self), fields),
+            ast::Data::Enum(ref variants) => {
+                let variants: Vec<_> = variants.iter().filter(|v| !v.skip).collect();
+                if variants.is_empty() {
+                    abort_call_site!("Preview needs at least one variant which isn't skipped");
+                }
+
+                let count = Literal::usize_unsuffixed(variants.len());
+                let arms = variants.iter().enumerate().map(|(i, variant)| {
+                    let i = Literal::usize_unsuffixed(i);
+                    let variant_ident = &variant.ident;
+                    let value = build(quote!(Self::#variant_ident), &variant.fields);
+
+                    quote!(#i => #value,)
+                });
+
+                quote! {
+                    match index % #count {
+                        #(#arms)*
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        };
+
+        tokens.extend(quote! {
+            impl #impl_generics ::crux_core::preview::Preview for #ident #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn preview_at(index: usize) -> Self {
+                    #body
+                }
+            }
+        })
+    }
+}
+
+pub(crate) fn preview_impl(input: &syn::DeriveInput) -> TokenStream {
+    let input = match PreviewReceiver::from_derive_input(input) {
+        Ok(v) => v,
+        Err(e) => {
+            return e.write_errors();
+        }
+    };
+
+    quote!(#input)
+}
+
+#[cfg(test)]
+mod tests {
+    use darling::FromDeriveInput;
+    use quote::quote;
+    use syn::parse_str;
+
+    use super::PreviewReceiver;
+
+    #[test]
+    fn structs() {
+        let input = r#"
+            #[derive(Preview)]
+            pub struct ViewModel {
+                pub title: String,
+                #[preview(value = "Screen::Basket")]
+                pub screen: Screen,
+                pub items: Vec<Item>,
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = PreviewReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        impl ::crux_core::preview::Preview for ViewModel {
+            #[allow(unused_variables)]
+            fn preview_at(index: usize) -> Self {
+                Self {
+                    title: <String as ::crux_core::preview::Preview>::preview_at(index),
+                    screen: Screen::Basket,
+                    items: <Vec<
+                        Item,
+                    > as ::crux_core::preview::Preview>::preview_at(
+                        ::crux_core::preview::field_index(index, 2),
+                    ),
+                }
+            }
+        }
+        "###);
+    }
+
+    #[test]
+    fn enums() {
+        let input = r#"
+            #[derive(Preview)]
+            pub enum Page<T> {
+                Loading,
+                Loaded(T, Option<String>),
+                #[preview(skip)]
+                Crashed { message: String },
+                Failed { message: String },
+            }
+        "#;
+        let input = parse_str(input).unwrap();
+        let input = PreviewReceiver::from_derive_input(&input).unwrap();
+
+        let actual = quote!(#input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        impl<T: ::crux_core::preview::Preview> ::crux_core::preview::Preview for Page<T> {
+            #[allow(unused_variables)]
+            fn preview_at(index: usize) -> Self {
+                match index % 3 {
+                    0 => Self::Loading,
+                    1 => {
+                        Self::Loaded(
+                            <T as ::crux_core::preview::Preview>::preview_at(index),
+                            <Option<
+                                String,
+                            > as ::crux_core::preview::Preview>::preview_at(
+                                ::crux_core::preview::field_index(index, 1),
+                            ),
+                        )
+                    }
+                    2 => {
+                        Self::Failed {
+                            message: <String as ::crux_core::preview::Preview>::preview_at(index),
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+        "###);
+    }
+
+    fn pretty_print(ts: &proc_macro2::TokenStream) -> String {
+        let file = syn::parse_file(&ts.to_string()).unwrap();
+        prettyplease::unparse(&file)
+    }
+}
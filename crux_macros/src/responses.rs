@@ -0,0 +1,233 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    Attribute, Data, DeriveInput, GenericArgument, Ident, PathArguments, Token, Type,
+};
+
+/// One capability call, e.g. `#[serde(skip)] get: Result<Vec<u8>, KeyValueError>`
+struct Call {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    ty: Type,
+}
+
+impl Parse for Call {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+
+        Ok(Self { attrs, name, ty })
+    }
+}
+
+pub(crate) struct Calls(Punctuated<Call, Token![,]>);
+
+impl Parse for Calls {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self(Punctuated::parse_terminated(input)?))
+    }
+}
+
+// `snake_case` to `PascalCase`
+fn pascal_case(name: &Ident) -> String {
+    name.to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// the `T` and `E` of a `Result<T, E>`
+fn split_result(ty: &Type) -> Option<(Type, Type)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let last = path.path.segments.last()?;
+    if last.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(ref args) = last.arguments else {
+        return None;
+    };
+
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+
+    match (types.next(), types.next(), types.next()) {
+        (Some(ok), Some(err), None) => Some((ok, err)),
+        _ => None,
+    }
+}
+
+pub(crate) fn responses_impl(calls: Calls, mut input: DeriveInput) -> TokenStream {
+    let ident = input.ident.clone();
+    let Data::Enum(ref mut data) = input.data else {
+        abort!(ident, "responses can only be added to an enum");
+    };
+
+    let mut constructors = Vec::new();
+    let mut fallible = false;
+
+    for call in calls.0 {
+        let Call { attrs, name, ty } = call;
+        let variant = format_ident!("{}Response", pascal_case(&name));
+        let constructor = format_ident!("{}_response", name);
+        let call_name = name.to_string();
+
+        let (output, body) = match split_result(&ty) {
+            Some((ok, err)) => {
+                fallible = true;
+                let body = quote! {
+                    match result {
+                        Ok(output) => Self::#variant(output),
+                        Err(error) => Self::ResponseFailed {
+                            call: #call_name.to_string(),
+                            error: <#err as Into<::crux_core::error::CapabilityError>>::into(error),
+                        },
+                    }
+                };
+
+                (ok, body)
+            }
+            None => (ty.clone(), quote!(Self::#variant(result))),
+        };
+
+        data.variants.push(parse_quote! {
+            #(#attrs)*
+            #variant(#output)
+        });
+
+        let doc = format!(
+            " The event for the result of `{call_name}`, to pass to the capability as its callback"
+        );
+        constructors.push(quote! {
+            #[doc = #doc]
+            pub fn #constructor(result: #ty) -> Self {
+                #body
+            }
+        });
+    }
+
+    if fallible {
+        data.variants.push(parse_quote! {
+            /// A capability call failed
+            ResponseFailed {
+                /// The name of the call, e.g. `"get"`
+                call: String,
+                error: ::crux_core::error::CapabilityError,
+            }
+        });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        #input
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#constructors)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_str;
+
+    use super::{responses_impl, Calls};
+
+    #[test]
+    fn responses() {
+        let calls: Calls = parse_str(
+            r#"
+            get: Result<Vec<u8>, KeyValueError>,
+            list_keys: Result<(Vec<String>, u64), KeyValueError>,
+            #[serde(skip)]
+            tick: Instant,
+            "#,
+        )
+        .unwrap();
+        let input = parse_str(
+            r#"
+            #[derive(Serialize, Deserialize, Debug)]
+            pub enum Event {
+                Get,
+            }
+            "#,
+        )
+        .unwrap();
+
+        let actual = responses_impl(calls, input);
+
+        insta::assert_snapshot!(pretty_print(&actual), @r###"
+        #[derive(Serialize, Deserialize, Debug)]
+        pub enum Event {
+            Get,
+            GetResponse(Vec<u8>),
+            ListKeysResponse((Vec<String>, u64)),
+            #[serde(skip)]
+            TickResponse(Instant),
+            /// A capability call failed
+            ResponseFailed {
+                /// The name of the call, e.g. `"get"`
+                call: String,
+                error: ::crux_core::error::CapabilityError,
+            },
+        }
+        impl Event {
+            /// The event for the result of `get`, to pass to the capability as its callback
+            pub fn get_response(result: Result<Vec<u8>, KeyValueError>) -> Self {
+                match result {
+                    Ok(output) => Self::GetResponse(output),
+                    Err(error) => {
+                        Self::ResponseFailed {
+                            call: "get".to_string(),
+                            error: <KeyValueError as Into<
+                                ::crux_core::error::CapabilityError,
+                            >>::into(error),
+                        }
+                    }
+                }
+            }
+            /// The event for the result of `list_keys`, to pass to the capability as its callback
+            pub fn list_keys_response(
+                result: Result<(Vec<String>, u64), KeyValueError>,
+            ) -> Self {
+                match result {
+                    Ok(output) => Self::ListKeysResponse(output),
+                    Err(error) => {
+                        Self::ResponseFailed {
+                            call: "list_keys".to_string(),
+                            error: <KeyValueError as Into<
+                                ::crux_core::error::CapabilityError,
+                            >>::into(error),
+                        }
+                    }
+                }
+            }
+            /// The event for the result of `tick`, to pass to the capability as its callback
+            pub fn tick_response(result: Instant) -> Self {
+                Self::TickResponse(result)
+            }
+        }
+        "###);
+    }
+
+    fn pretty_print(ts: &proc_macro2::TokenStream) -> String {
+        let file = syn::parse_file(&ts.to_string()).unwrap();
+        prettyplease::unparse(&file)
+    }
+}
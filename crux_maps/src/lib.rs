@@ -0,0 +1,447 @@
+//! Geocoding and static maps
+//!
+//! The [`Maps`] capability uses the platform's map services to geocode addresses and
+//! search queries into [`Place`]s, reverse geocode coordinates into addresses, and render
+//! static map snapshots with markers, returned as image bytes. This way address handling,
+//! like validating a delivery address, and map previews can be produced by the core, without
+//! map SDK glue in the app's business logic.
+//!
+//! [`MapSnapshot::fitting`] works out the center and zoom level needed to show a set of
+//! places, so the framing of map previews is decided in the core too.
+
+mod viewport;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use viewport::{DEFAULT_ZOOM, MAX_ZOOM};
+
+/// A point on Earth, in degrees
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Coordinate {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinate {
+    /// A coordinate, if the latitude is within ±90° and the longitude within ±180°
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either is out of range.
+    pub fn new(latitude: f64, longitude: f64) -> Result<Self, MapsError> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(MapsError::InvalidCoordinate {
+                latitude,
+                longitude,
+            });
+        }
+
+        Ok(Self {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+/// A postal address, with the components the geocoder could determine
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    /// The street address, e.g. `221B Baker Street`
+    pub street: Option<String>,
+    pub city: Option<String>,
+    /// The state, county or province
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    /// The ISO 3166 country code, e.g. `GB`
+    pub country_code: Option<String>,
+}
+
+impl Address {
+    /// The address on one line, e.g. `221B Baker Street, London, NW1 6XE, United Kingdom`
+    pub fn single_line(&self) -> String {
+        [
+            &self.street,
+            &self.city,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// A geocoding result
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Place {
+    /// The name of the place, for points of interest
+    pub name: Option<String>,
+    pub coordinate: Coordinate,
+    pub address: Address,
+}
+
+/// A search for places
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeocodeQuery {
+    /// An address, or the name of a place
+    pub query: String,
+    /// Where to prefer results near
+    pub near: Option<Coordinate>,
+    /// The most results to return
+    pub limit: u32,
+}
+
+impl GeocodeQuery {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            near: None,
+            limit: 5,
+        }
+    }
+
+    #[must_use]
+    pub fn near(mut self, coordinate: Coordinate) -> Self {
+        self.near = Some(coordinate);
+        self
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit.max(1);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MapStyle {
+    #[default]
+    Standard,
+    Satellite,
+    Hybrid,
+}
+
+/// A pin on a map snapshot
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Marker {
+    pub coordinate: Coordinate,
+    /// A short label shown on the pin, e.g. `A`
+    pub label: Option<String>,
+}
+
+impl Marker {
+    pub fn new(coordinate: Coordinate) -> Self {
+        Self {
+            coordinate,
+            label: None,
+        }
+    }
+
+    #[must_use]
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+/// A request for a static map image
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapSnapshot {
+    pub center: Coordinate,
+    /// The Web Mercator zoom level, from 0 (the whole world) to [`MAX_ZOOM`]
+    pub zoom: f64,
+    /// The width of the image, in logical pixels
+    pub width: u32,
+    /// The height of the image, in logical pixels
+    pub height: u32,
+    /// The number of physical pixels per logical pixel
+    pub scale: u32,
+    pub style: MapStyle,
+    pub markers: Vec<Marker>,
+}
+
+impl MapSnapshot {
+    /// A `width` x `height` snapshot centered on `center`, at `zoom`
+    pub fn new(center: Coordinate, zoom: f64, width: u32, height: u32) -> Self {
+        Self {
+            center,
+            zoom: zoom.clamp(0.0, MAX_ZOOM),
+            width: width.max(1),
+            height: height.max(1),
+            scale: 1,
+            style: MapStyle::default(),
+            markers: Vec::new(),
+        }
+    }
+
+    /// A `width` x `height` snapshot with a marker for each of the `markers`, centered and
+    /// zoomed to show them all, at least `padding` pixels in from the edges. A single marker
+    /// is shown at [`DEFAULT_ZOOM`]. Returns `None` if there are no markers.
+    pub fn fitting(markers: Vec<Marker>, width: u32, height: u32, padding: u32) -> Option<Self> {
+        let coordinates: Vec<_> = markers.iter().map(|marker| marker.coordinate).collect();
+        let (center, zoom) = viewport::fit(&coordinates, width, height, padding)?;
+
+        Some(Self {
+            markers,
+            ..Self::new(center, zoom, width, height)
+        })
+    }
+
+    #[must_use]
+    pub fn scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn style(mut self, style: MapStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    #[must_use]
+    pub fn marker(mut self, marker: Marker) -> Self {
+        self.markers.push(marker);
+        self
+    }
+}
+
+/// A rendered map, as a PNG image
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapImage {
+    /// The width of the image, in physical pixels
+    pub width: u32,
+    /// The height of the image, in physical pixels
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MapsOperation {
+    Geocode(GeocodeQuery),
+    ReverseGeocode(Coordinate),
+    Snapshot(MapSnapshot),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MapsResponse {
+    /// The places found, best match first
+    Places(Vec<Place>),
+    Image(MapImage),
+    /// The map service can't be reached, e.g. when offline or rate limited
+    Unavailable,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for MapsOperation {
+    type Output = MapsResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MapsError {
+    #[error("invalid coordinate {latitude}, {longitude}")]
+    InvalidCoordinate { latitude: f64, longitude: f64 },
+    #[error("the map service is not available")]
+    Unavailable,
+    #[error("map operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<MapsResponse> },
+}
+
+impl From<MapsResponse> for MapsError {
+    fn from(response: MapsResponse) -> Self {
+        match response {
+            MapsResponse::Unavailable => MapsError::Unavailable,
+            MapsResponse::Error { message } => MapsError::Shell { message },
+            response => MapsError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// The Maps capability API
+///
+/// This capability lets the app geocode addresses, reverse geocode coordinates and render
+/// static map images.
+#[derive(crux_core::macros::Capability)]
+pub struct Maps<Ev> {
+    context: CapabilityContext<MapsOperation, Ev>,
+}
+
+impl<Ev> Clone for Maps<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Maps<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<MapsOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Find the places matching the query, then send the event returned by `callback` with
+    /// them, best match first. No places are found if nothing matches.
+    pub fn geocode<F>(&self, query: GeocodeQuery, callback: F)
+    where
+        F: FnOnce(Result<Vec<Place>, MapsError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.geocode_async(query).await));
+            }
+        });
+    }
+
+    /// Find the places matching the query.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn geocode_async(&self, query: GeocodeQuery) -> Result<Vec<Place>, MapsError> {
+        self.places(MapsOperation::Geocode(query)).await
+    }
+
+    /// Find the address at the coordinate, then send the event returned by `callback` with
+    /// the closest place, or `None` if there is no address there, e.g. at sea.
+    pub fn reverse_geocode<F>(&self, coordinate: Coordinate, callback: F)
+    where
+        F: FnOnce(Result<Option<Place>, MapsError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.reverse_geocode_async(coordinate).await));
+            }
+        });
+    }
+
+    /// Find the address at the coordinate.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn reverse_geocode_async(
+        &self,
+        coordinate: Coordinate,
+    ) -> Result<Option<Place>, MapsError> {
+        let places = self
+            .places(MapsOperation::ReverseGeocode(coordinate))
+            .await?;
+
+        Ok(places.into_iter().next())
+    }
+
+    /// Render a static map, then send the event returned by `callback` with the image.
+    pub fn snapshot<F>(&self, snapshot: MapSnapshot, callback: F)
+    where
+        F: FnOnce(Result<MapImage, MapsError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.snapshot_async(snapshot).await));
+            }
+        });
+    }
+
+    /// Render a static map.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn snapshot_async(&self, snapshot: MapSnapshot) -> Result<MapImage, MapsError> {
+        match self
+            .context
+            .request_from_shell(MapsOperation::Snapshot(snapshot))
+            .await
+        {
+            MapsResponse::Image(image) => Ok(image),
+            response => Err(response.into()),
+        }
+    }
+
+    async fn places(&self, operation: MapsOperation) -> Result<Vec<Place>, MapsError> {
+        match self.context.request_from_shell(operation).await {
+            MapsResponse::Places(places) => Ok(places),
+            response => Err(response.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = MapsOperation::Geocode(
+            GeocodeQuery::new("221B Baker Street")
+                .near(Coordinate::new(51.5, -0.1).unwrap())
+                .limit(1),
+        );
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"geocode":{"query":"221B Baker Street","near":{"latitude":51.5,"longitude":-0.1},"limit":1}}"#
+        );
+
+        let deserialized: MapsOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+    }
+
+    #[test]
+    fn test_invalid_coordinates() {
+        assert!(Coordinate::new(90.0, -180.0).is_ok());
+        assert_eq!(
+            Coordinate::new(91.0, 0.0),
+            Err(MapsError::InvalidCoordinate {
+                latitude: 91.0,
+                longitude: 0.0
+            })
+        );
+        assert!(Coordinate::new(0.0, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_single_line_addresses() {
+        let address = Address {
+            street: Some("221B Baker Street".to_string()),
+            city: Some("London".to_string()),
+            region: Some(String::new()),
+            postal_code: Some("NW1 6XE".to_string()),
+            country: Some("United Kingdom".to_string()),
+            country_code: Some("GB".to_string()),
+        };
+
+        assert_eq!(
+            address.single_line(),
+            "221B Baker Street, London, NW1 6XE, United Kingdom"
+        );
+        assert_eq!(Address::default().single_line(), "");
+    }
+}
@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+
+use crate::Coordinate;
+
+/// The size of a map tile at zoom level 0, in logical pixels
+const TILE_SIZE: f64 = 256.0;
+
+/// The zoom level used when there's only one place to show
+pub const DEFAULT_ZOOM: f64 = 15.0;
+
+/// The most zoomed-in level a snapshot can be
+pub const MAX_ZOOM: f64 = 20.0;
+
+// Web Mercator projection to the unit square, with (0, 0) at the top left
+fn project(coordinate: Coordinate) -> (f64, f64) {
+    let x = (coordinate.longitude + 180.0) / 360.0;
+    let sin = coordinate.latitude.to_radians().sin();
+    let y = 0.5 - ((1.0 + sin) / (1.0 - sin)).ln() / (4.0 * PI);
+
+    (x, y.clamp(0.0, 1.0))
+}
+
+fn unproject(x: f64, y: f64) -> Coordinate {
+    let latitude = (2.0 * ((0.5 - y) * 2.0 * PI).exp().atan() - PI / 2.0).to_degrees();
+
+    Coordinate {
+        latitude,
+        longitude: x * 360.0 - 180.0,
+    }
+}
+
+/// The center and zoom level showing all the `coordinates` in a `width` x `height` map,
+/// keeping them `padding` pixels in from the edges. Returns `None` if there are no coordinates.
+pub(crate) fn fit(
+    coordinates: &[Coordinate],
+    width: u32,
+    height: u32,
+    padding: u32,
+) -> Option<(Coordinate, f64)> {
+    let (first, rest) = coordinates.split_first()?;
+
+    let (x, y) = project(*first);
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (x, x, y, y);
+    for coordinate in rest {
+        let (x, y) = project(*coordinate);
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+
+    let center = unproject((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+    let available = |size: u32| f64::from(size.saturating_sub(2 * padding).max(1));
+    let zoom_for = |span: f64, size: f64| {
+        if span > 0.0 {
+            (size / (TILE_SIZE * span)).log2()
+        } else {
+            MAX_ZOOM
+        }
+    };
+    let zoom =
+        zoom_for(max_x - min_x, available(width)).min(zoom_for(max_y - min_y, available(height)));
+
+    let zoom = if rest.is_empty() {
+        DEFAULT_ZOOM
+    } else {
+        zoom.clamp(0.0, MAX_ZOOM)
+    };
+
+    Some((center, zoom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinate(latitude: f64, longitude: f64) -> Coordinate {
+        Coordinate {
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn projection_round_trips() {
+        for (latitude, longitude) in [(0.0, 0.0), (51.5, -0.12), (-33.9, 151.2), (80.0, 179.0)] {
+            let (x, y) = project(coordinate(latitude, longitude));
+            let result = unproject(x, y);
+
+            assert!((result.latitude - latitude).abs() < 1e-9);
+            assert!((result.longitude - longitude).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn a_single_place_uses_the_default_zoom() {
+        let london = coordinate(51.5074, -0.1278);
+
+        let (center, zoom) = fit(&[london], 300, 200, 20).unwrap();
+
+        assert!((center.latitude - london.latitude).abs() < 1e-9);
+        assert!((center.longitude - london.longitude).abs() < 1e-9);
+        assert_eq!(zoom, DEFAULT_ZOOM);
+    }
+
+    #[test]
+    fn fits_all_places() {
+        // half the world wide, centered on the equator
+        let places = [coordinate(0.0, -90.0), coordinate(0.0, 90.0)];
+
+        let (center, zoom) = fit(&places, 256, 256, 0).unwrap();
+
+        assert!(center.latitude.abs() < 1e-9);
+        assert!(center.longitude.abs() < 1e-9);
+        // at zoom 1 the world is 512 pixels wide, so half of it fits in 256
+        assert!((zoom - 1.0).abs() < 1e-9);
+
+        // padding zooms out
+        let (_, padded) = fit(&places, 256, 256, 64).unwrap();
+        assert!((padded - 0.0).abs() < 1e-9);
+
+        assert_eq!(fit(&[], 256, 256, 0), None);
+    }
+}
@@ -0,0 +1,226 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_maps::{
+        Coordinate, GeocodeQuery, MapImage, MapSnapshot, Maps, MapsError, Marker, Place,
+    };
+    use serde::{Deserialize, Serialize};
+
+    pub const STORE: Coordinate = Coordinate {
+        latitude: 51.5155,
+        longitude: -0.1419,
+    };
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        DeliverTo(String),
+        UseCurrentLocation(Coordinate),
+
+        #[serde(skip)]
+        Geocoded(Result<Vec<Place>, MapsError>),
+        #[serde(skip)]
+        ReverseGeocoded(Result<Option<Place>, MapsError>),
+        #[serde(skip)]
+        Preview(Result<MapImage, MapsError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub address: Option<String>,
+        pub preview: Option<MapImage>,
+        pub error: Option<MapsError>,
+        pub not_found: bool,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub maps: Maps<Event>,
+    }
+
+    impl App {
+        fn deliver_to(place: Place, model: &mut Model, caps: &Capabilities) {
+            model.address = Some(place.address.single_line());
+
+            let markers = vec![
+                Marker::new(STORE).label("S"),
+                Marker::new(place.coordinate).label("D"),
+            ];
+            if let Some(snapshot) = MapSnapshot::fitting(markers, 320, 160, 24) {
+                caps.maps.snapshot(snapshot.scale(2), Event::Preview);
+            }
+        }
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::DeliverTo(address) => caps.maps.geocode(
+                    GeocodeQuery::new(address).near(STORE).limit(1),
+                    Event::Geocoded,
+                ),
+                Event::UseCurrentLocation(coordinate) => {
+                    caps.maps
+                        .reverse_geocode(coordinate, Event::ReverseGeocoded);
+                }
+                Event::Geocoded(Ok(places)) => match places.into_iter().next() {
+                    Some(place) => Self::deliver_to(place, model, caps),
+                    None => model.not_found = true,
+                },
+                Event::ReverseGeocoded(Ok(place)) => match place {
+                    Some(place) => Self::deliver_to(place, model, caps),
+                    None => model.not_found = true,
+                },
+                Event::Preview(Ok(image)) => model.preview = Some(image),
+                Event::Geocoded(Err(error))
+                | Event::ReverseGeocoded(Err(error))
+                | Event::Preview(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_maps::{Address, Coordinate, MapImage, MapsError, MapsOperation, MapsResponse, Place};
+
+    use crate::shared::{App, Effect, Event, Model, STORE};
+
+    fn request(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Request<MapsOperation> {
+        let mut effects = app.update(event, model).into_effects();
+        let Some(Effect::Maps(request)) = effects.next() else {
+            panic!("expected a Maps effect");
+        };
+
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<MapsOperation>,
+        response: MapsResponse,
+    ) -> Option<Request<MapsOperation>> {
+        let update = app.resolve(request, response).unwrap();
+
+        let mut effects = Vec::new();
+        for event in update.events {
+            effects.extend(app.update(event, model).into_effects());
+        }
+
+        effects.into_iter().next().map(|effect| {
+            let Effect::Maps(request) = effect;
+            request
+        })
+    }
+
+    fn place() -> Place {
+        Place {
+            name: None,
+            coordinate: Coordinate::new(51.5238, -0.1586).unwrap(),
+            address: Address {
+                street: Some("221B Baker Street".to_string()),
+                city: Some("London".to_string()),
+                postal_code: Some("NW1 6XE".to_string()),
+                ..Address::default()
+            },
+        }
+    }
+
+    #[test]
+    fn geocodes_the_address_and_previews_the_route() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut geocode = request(
+            &app,
+            &mut model,
+            Event::DeliverTo("221b baker st".to_string()),
+        );
+        let MapsOperation::Geocode(query) = &geocode.operation else {
+            panic!("expected to geocode");
+        };
+        assert_eq!(query.near, Some(STORE));
+
+        let mut snapshot = respond(
+            &app,
+            &mut model,
+            &mut geocode,
+            MapsResponse::Places(vec![place()]),
+        )
+        .unwrap();
+        assert_eq!(
+            model.address.as_deref(),
+            Some("221B Baker Street, London, NW1 6XE")
+        );
+
+        let MapsOperation::Snapshot(map) = &snapshot.operation else {
+            panic!("expected a snapshot");
+        };
+        assert_eq!(map.markers.len(), 2);
+        assert_eq!((map.width, map.height, map.scale), (320, 160, 2));
+        // the center is between the store and the delivery address
+        assert!(map.center.latitude > 51.5155 && map.center.latitude < 51.5238);
+        assert!(map.center.longitude > -0.1586 && map.center.longitude < -0.1419);
+        assert!(map.zoom > 12.0 && map.zoom < 17.0);
+
+        let image = MapImage {
+            width: 640,
+            height: 320,
+            data: vec![0x89, b'P', b'N', b'G'],
+        };
+        respond(
+            &app,
+            &mut model,
+            &mut snapshot,
+            MapsResponse::Image(image.clone()),
+        );
+        assert_eq!(model.preview, Some(image));
+    }
+
+    #[test]
+    fn reverse_geocodes_the_current_location() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let coordinate = Coordinate::new(51.5238, -0.1586).unwrap();
+        let mut reverse = request(&app, &mut model, Event::UseCurrentLocation(coordinate));
+        assert_eq!(reverse.operation, MapsOperation::ReverseGeocode(coordinate));
+
+        let snapshot = respond(
+            &app,
+            &mut model,
+            &mut reverse,
+            MapsResponse::Places(vec![place()]),
+        );
+        assert!(snapshot.is_some());
+        assert!(model.address.is_some());
+    }
+
+    #[test]
+    fn nothing_found_or_unavailable() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut geocode = request(&app, &mut model, Event::DeliverTo("nowhere".to_string()));
+        let snapshot = respond(&app, &mut model, &mut geocode, MapsResponse::Places(vec![]));
+        assert!(snapshot.is_none());
+        assert!(model.not_found);
+
+        let mut geocode = request(&app, &mut model, Event::DeliverTo("somewhere".to_string()));
+        respond(&app, &mut model, &mut geocode, MapsResponse::Unavailable);
+        assert_eq!(model.error, Some(MapsError::Unavailable));
+    }
+}
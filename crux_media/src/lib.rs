@@ -0,0 +1,337 @@
+//! Querying the media library
+//!
+//! The [`MediaLibrary`] capability lets the core page through the photos and videos in the
+//! user's media library, getting their metadata, and request downscaled thumbnails of them.
+//! Gallery-style apps can drive their selection UI from the core this way, without moving
+//! full-resolution images across the bridge: items are referred to by [`MediaHandle`], and
+//! thumbnails are limited to [`MAX_THUMBNAIL_SIZE`] pixels on either side.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The largest thumbnail width or height which can be requested
+pub const MAX_THUMBNAIL_SIZE: u32 = 1024;
+
+/// The shell's identifier for an item in the media library
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MediaHandle(pub String);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaKind {
+    Photo,
+    Video,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// The shell's position in the results of a query, to continue from with the next page
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cursor(pub String);
+
+/// A query for a page of media library items
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaQuery {
+    /// The kinds of items to include, or all if empty
+    pub kinds: Vec<MediaKind>,
+    pub sort: SortOrder,
+    pub page_size: u32,
+    /// Where to continue from, or the start if `None`
+    pub after: Option<Cursor>,
+}
+
+impl MediaQuery {
+    /// A query for the first page of all items, newest first, 50 at a time
+    pub fn new() -> Self {
+        Self {
+            kinds: Vec::new(),
+            sort: SortOrder::default(),
+            page_size: 50,
+            after: None,
+        }
+    }
+
+    #[must_use]
+    pub fn kind(mut self, kind: MediaKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    #[must_use]
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    #[must_use]
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// The same query, continuing after `cursor`
+    #[must_use]
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+}
+
+impl Default for MediaQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The metadata of an item in the media library
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaItem {
+    pub handle: MediaHandle,
+    pub kind: MediaKind,
+    /// The full-resolution width, in pixels
+    pub width: u32,
+    /// The full-resolution height, in pixels
+    pub height: u32,
+    /// When the item was created, in milliseconds since the UNIX epoch
+    pub created_at: Option<u64>,
+    /// The duration of a video, in milliseconds
+    pub duration: Option<u64>,
+    pub favorite: bool,
+}
+
+/// A page of query results
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaPage {
+    pub items: Vec<MediaItem>,
+    /// Where the next page starts, or `None` if this is the last page
+    pub next: Option<Cursor>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+/// A request for a thumbnail, fitting within `max_width` x `max_height` pixels while keeping
+/// the item's aspect ratio. For videos, the thumbnail is a representative frame.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailRequest {
+    pub handle: MediaHandle,
+    pub max_width: u32,
+    pub max_height: u32,
+    pub format: ImageFormat,
+}
+
+impl ThumbnailRequest {
+    /// A JPEG thumbnail fitting in `max_width` x `max_height`, each limited to
+    /// [`MAX_THUMBNAIL_SIZE`]
+    pub fn new(handle: MediaHandle, max_width: u32, max_height: u32) -> Self {
+        Self {
+            handle,
+            max_width: max_width.clamp(1, MAX_THUMBNAIL_SIZE),
+            max_height: max_height.clamp(1, MAX_THUMBNAIL_SIZE),
+            format: ImageFormat::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// An encoded thumbnail image
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnail {
+    pub handle: MediaHandle,
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaOperation {
+    Query(MediaQuery),
+    Thumbnail(ThumbnailRequest),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaResponse {
+    Page(MediaPage),
+    Thumbnail(Thumbnail),
+    /// The user hasn't given the app access to the media library
+    PermissionDenied,
+    /// The item no longer exists, or is no longer accessible
+    NotFound,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for MediaOperation {
+    type Output = MediaResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MediaError {
+    #[error("access to the media library was denied")]
+    PermissionDenied,
+    #[error("media item {handle:?} was not found")]
+    NotFound { handle: MediaHandle },
+    #[error("media library operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<MediaResponse> },
+}
+
+impl MediaError {
+    fn from_response(response: MediaResponse, handle: Option<MediaHandle>) -> Self {
+        match (response, handle) {
+            (MediaResponse::PermissionDenied, _) => MediaError::PermissionDenied,
+            (MediaResponse::NotFound, Some(handle)) => MediaError::NotFound { handle },
+            (MediaResponse::Error { message }, _) => MediaError::Shell { message },
+            (response, _) => MediaError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// The MediaLibrary capability API
+///
+/// This capability lets the app query the photos and videos in the user's media library,
+/// and request thumbnails of them.
+#[derive(crux_core::macros::Capability)]
+pub struct MediaLibrary<Ev> {
+    context: CapabilityContext<MediaOperation, Ev>,
+}
+
+impl<Ev> Clone for MediaLibrary<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> MediaLibrary<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<MediaOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Query a page of items, then send the event returned by `callback` with the page.
+    /// To get the next page, query again with [`MediaQuery::after`] the page's cursor.
+    pub fn query<F>(&self, query: MediaQuery, callback: F)
+    where
+        F: FnOnce(Result<MediaPage, MediaError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.query_async(query).await));
+            }
+        });
+    }
+
+    /// Query a page of items.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn query_async(&self, query: MediaQuery) -> Result<MediaPage, MediaError> {
+        match self
+            .context
+            .request_from_shell(MediaOperation::Query(query))
+            .await
+        {
+            MediaResponse::Page(page) => Ok(page),
+            response => Err(MediaError::from_response(response, None)),
+        }
+    }
+
+    /// Request a thumbnail, then send the event returned by `callback` with it.
+    pub fn thumbnail<F>(&self, request: ThumbnailRequest, callback: F)
+    where
+        F: FnOnce(Result<Thumbnail, MediaError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.thumbnail_async(request).await));
+            }
+        });
+    }
+
+    /// Request a thumbnail.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn thumbnail_async(
+        &self,
+        request: ThumbnailRequest,
+    ) -> Result<Thumbnail, MediaError> {
+        let handle = request.handle.clone();
+
+        match self
+            .context
+            .request_from_shell(MediaOperation::Thumbnail(request))
+            .await
+        {
+            MediaResponse::Thumbnail(thumbnail) => Ok(thumbnail),
+            response => Err(MediaError::from_response(response, Some(handle))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = MediaOperation::Query(
+            MediaQuery::new()
+                .kind(MediaKind::Photo)
+                .page_size(20)
+                .after(Cursor("40".to_string())),
+        );
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"query":{"kinds":["photo"],"sort":"newestFirst","pageSize":20,"after":"40"}}"#
+        );
+
+        let deserialized: MediaOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+    }
+
+    #[test]
+    fn test_thumbnails_are_limited_in_size() {
+        let request = ThumbnailRequest::new(MediaHandle("1".to_string()), 4032, 0);
+
+        assert_eq!(request.max_width, MAX_THUMBNAIL_SIZE);
+        assert_eq!(request.max_height, 1);
+    }
+}
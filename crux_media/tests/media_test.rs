@@ -0,0 +1,210 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_media::{
+        Cursor, MediaError, MediaHandle, MediaItem, MediaKind, MediaLibrary, MediaPage, MediaQuery,
+        Thumbnail, ThumbnailRequest,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        LoadMore,
+
+        #[serde(skip)]
+        Loaded(Result<MediaPage, MediaError>),
+        #[serde(skip)]
+        ThumbnailLoaded(Result<Thumbnail, MediaError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub items: Vec<MediaItem>,
+        pub next: Option<Cursor>,
+        pub done: bool,
+        pub thumbnails: Vec<MediaHandle>,
+        pub error: Option<MediaError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub media: MediaLibrary<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::LoadMore => {
+                    if model.done {
+                        return;
+                    }
+
+                    let mut query = MediaQuery::new().kind(MediaKind::Photo).page_size(2);
+                    if let Some(cursor) = model.next.clone() {
+                        query = query.after(cursor);
+                    }
+                    caps.media.query(query, Event::Loaded);
+                }
+                Event::Loaded(Ok(page)) => {
+                    for item in &page.items {
+                        caps.media.thumbnail(
+                            ThumbnailRequest::new(item.handle.clone(), 200, 200),
+                            Event::ThumbnailLoaded,
+                        );
+                    }
+
+                    model.items.extend(page.items);
+                    model.done = page.next.is_none();
+                    model.next = page.next;
+                }
+                Event::ThumbnailLoaded(Ok(thumbnail)) => model.thumbnails.push(thumbnail.handle),
+                Event::Loaded(Err(error)) | Event::ThumbnailLoaded(Err(error)) => {
+                    model.error = Some(error)
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_media::{
+        Cursor, ImageFormat, MediaError, MediaHandle, MediaItem, MediaKind, MediaOperation,
+        MediaPage, MediaResponse, Thumbnail,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn item(id: &str) -> MediaItem {
+        MediaItem {
+            handle: MediaHandle(id.to_string()),
+            kind: MediaKind::Photo,
+            width: 4032,
+            height: 3024,
+            created_at: Some(1_700_000_000_000),
+            duration: None,
+            favorite: false,
+        }
+    }
+
+    fn requests(effects: impl IntoIterator<Item = Effect>) -> Vec<Request<MediaOperation>> {
+        effects
+            .into_iter()
+            .map(|effect| {
+                let Effect::MediaLibrary(request) = effect;
+                request
+            })
+            .collect()
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<MediaOperation>,
+        response: MediaResponse,
+    ) -> Vec<Request<MediaOperation>> {
+        let update = app.resolve(request, response).unwrap();
+        let mut effects = update.effects;
+        for event in update.events {
+            effects.extend(app.update(event, model).effects);
+        }
+
+        requests(effects)
+    }
+
+    #[test]
+    fn pages_through_the_library_with_thumbnails() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut query = requests(app.update(Event::LoadMore, &mut model).into_effects()).remove(0);
+        let MediaOperation::Query(first) = &query.operation else {
+            panic!("expected a query");
+        };
+        assert_eq!(first.kinds, vec![MediaKind::Photo]);
+        assert_eq!(first.after, None);
+
+        let thumbnails = respond(
+            &app,
+            &mut model,
+            &mut query,
+            MediaResponse::Page(MediaPage {
+                items: vec![item("1"), item("2")],
+                next: Some(Cursor("2".to_string())),
+            }),
+        );
+        assert_eq!(thumbnails.len(), 2);
+
+        for mut request in thumbnails {
+            let MediaOperation::Thumbnail(thumbnail) = &request.operation else {
+                panic!("expected a thumbnail request");
+            };
+            assert_eq!((thumbnail.max_width, thumbnail.max_height), (200, 200));
+
+            let handle = thumbnail.handle.clone();
+            respond(
+                &app,
+                &mut model,
+                &mut request,
+                MediaResponse::Thumbnail(Thumbnail {
+                    handle,
+                    width: 200,
+                    height: 150,
+                    format: ImageFormat::Jpeg,
+                    data: vec![0xff, 0xd8],
+                }),
+            );
+        }
+        assert_eq!(
+            model.thumbnails,
+            vec![MediaHandle("1".to_string()), MediaHandle("2".to_string())]
+        );
+
+        // the next page continues from the cursor, and is the last one
+        let mut query = requests(app.update(Event::LoadMore, &mut model).into_effects()).remove(0);
+        let MediaOperation::Query(second) = &query.operation else {
+            panic!("expected a query");
+        };
+        assert_eq!(second.after, Some(Cursor("2".to_string())));
+
+        respond(
+            &app,
+            &mut model,
+            &mut query,
+            MediaResponse::Page(MediaPage {
+                items: vec![item("3")],
+                next: None,
+            }),
+        );
+        assert_eq!(model.items.len(), 3);
+        assert!(model.done);
+
+        let update = app.update(Event::LoadMore, &mut model);
+        assert!(update.effects.is_empty());
+    }
+
+    #[test]
+    fn permission_denied() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut query = requests(app.update(Event::LoadMore, &mut model).into_effects()).remove(0);
+        respond(
+            &app,
+            &mut model,
+            &mut query,
+            MediaResponse::PermissionDenied,
+        );
+
+        assert_eq!(model.error, Some(MediaError::PermissionDenied));
+    }
+}
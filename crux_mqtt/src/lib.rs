@@ -0,0 +1,435 @@
+//! MQTT messaging
+//!
+//! The [`Mqtt`] capability asks the shell to connect to an MQTT broker, subscribe to topics and
+//! publish messages, so that IoT companion apps can keep their messaging logic in the core.
+//! The shell owns the connection (using the platform's MQTT client library), and streams
+//! connection changes and incoming messages back to the core.
+//!
+//! Subscriptions use [`TopicFilter`]s, validated in the core. The shell may deliver messages
+//! for a subscription on a shared connection, so the core matches every incoming message
+//! against the subscription's filter, and only passes on the ones which match.
+
+mod topic;
+
+use std::fmt;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use topic::TopicFilter;
+
+/// The quality of service of a message delivery
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum QoS {
+    /// The message is delivered at most once, and may be lost
+    #[default]
+    AtMostOnce,
+    /// The message is delivered at least once, and may be duplicated
+    AtLeastOnce,
+    /// The message is delivered exactly once
+    ExactlyOnce,
+}
+
+/// How to connect to the broker
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectOptions {
+    /// The broker's URL, e.g. `mqtts://broker.example.com:8883` or `wss://broker.example.com/mqtt`
+    pub url: String,
+    pub client_id: String,
+    /// Whether to discard any session state (e.g. subscriptions) kept by the broker
+    pub clean_session: bool,
+    pub keep_alive_seconds: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// The message the broker publishes when the client disconnects unexpectedly
+    pub last_will: Option<PublishMessage>,
+}
+
+impl ConnectOptions {
+    pub fn new(url: impl Into<String>, client_id: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client_id: client_id.into(),
+            clean_session: true,
+            keep_alive_seconds: 60,
+            username: None,
+            password: None,
+            last_will: None,
+        }
+    }
+
+    /// Keep the session state on the broker, to receive messages published while disconnected
+    #[must_use]
+    pub fn persistent_session(mut self) -> Self {
+        self.clean_session = false;
+        self
+    }
+
+    #[must_use]
+    pub fn keep_alive(mut self, seconds: u16) -> Self {
+        self.keep_alive_seconds = seconds;
+        self
+    }
+
+    #[must_use]
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    #[must_use]
+    pub fn last_will(mut self, message: PublishMessage) -> Self {
+        self.last_will = Some(message);
+        self
+    }
+}
+
+// The password is left out, to avoid it ending up in logs
+impl fmt::Debug for ConnectOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectOptions")
+            .field("url", &self.url)
+            .field("client_id", &self.client_id)
+            .field("clean_session", &self.clean_session)
+            .field("keep_alive_seconds", &self.keep_alive_seconds)
+            .field("username", &self.username)
+            .field("last_will", &self.last_will)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A message to publish
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: QoS,
+    /// Whether the broker keeps the message, to deliver it to future subscribers
+    pub retain: bool,
+}
+
+impl PublishMessage {
+    /// A message with the default [`QoS::AtMostOnce`], which is not retained
+    pub fn new(topic: impl Into<String>, payload: impl Into<Vec<u8>>) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: payload.into(),
+            qos: QoS::default(),
+            retain: false,
+        }
+    }
+
+    #[must_use]
+    pub fn qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    #[must_use]
+    pub fn retain(mut self) -> Self {
+        self.retain = true;
+        self
+    }
+}
+
+/// A message received on a subscription
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: QoS,
+    /// Whether the message was retained by the broker, rather than published since subscribing
+    pub retained: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MqttOperation {
+    /// Connect to the broker, and stream the connection's lifecycle until disconnected
+    Connect(ConnectOptions),
+    /// Subscribe to the topics matching the filter, and stream the messages received
+    Subscribe {
+        filter: TopicFilter,
+        qos: QoS,
+    },
+    Unsubscribe {
+        filter: TopicFilter,
+    },
+    Publish(PublishMessage),
+    Disconnect,
+}
+
+/// A change in the state of the connection to the broker
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ConnectionEvent {
+    /// Connected, or reconnected, to the broker. `session_present` tells whether the broker
+    /// still had the session state, including the subscriptions.
+    Connected { session_present: bool },
+    /// The connection was lost, and the shell is reconnecting
+    Reconnecting { reason: String },
+    /// Disconnected, either when asked to or because the connection can't be re-established.
+    /// This is the last event for the connection.
+    Disconnected { reason: Option<String> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MqttResponse {
+    Connection(ConnectionEvent),
+    /// The subscription was acknowledged by the broker, with the QoS it granted
+    Subscribed {
+        qos: QoS,
+    },
+    Message(MqttMessage),
+    Unsubscribed,
+    Published,
+    /// The operation failed, or the subscription ended
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for MqttOperation {
+    type Output = MqttResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MqttError {
+    #[error("invalid topic filter {filter}: {message}")]
+    InvalidFilter { filter: String, message: String },
+    #[error("can't publish to {topic}, topics can't be empty or contain wildcards")]
+    InvalidTopic { topic: String },
+    #[error("MQTT operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<MqttResponse> },
+}
+
+impl From<MqttResponse> for MqttError {
+    fn from(response: MqttResponse) -> Self {
+        match response {
+            MqttResponse::Error { message } => MqttError::Shell { message },
+            response => MqttError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// The Mqtt capability API
+///
+/// This capability lets the app connect to an MQTT broker, subscribe to topics and publish
+/// messages, with the shell maintaining the connection.
+#[derive(crux_core::macros::Capability)]
+pub struct Mqtt<Ev> {
+    context: CapabilityContext<MqttOperation, Ev>,
+}
+
+impl<Ev> Clone for Mqtt<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Mqtt<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<MqttOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Connect to the broker, passing every change of the connection's state to the app,
+    /// wrapped in the event produced by the `callback`. A failure to connect is passed on as
+    /// [`ConnectionEvent::Disconnected`].
+    pub fn connect<F>(&self, options: ConnectOptions, callback: F)
+    where
+        F: Fn(ConnectionEvent) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(MqttOperation::Connect(options));
+
+                while let Some(response) = stream.next().await {
+                    let event = match response {
+                        MqttResponse::Connection(event) => event,
+                        MqttResponse::Error { message } => ConnectionEvent::Disconnected {
+                            reason: Some(message),
+                        },
+                        _ => continue,
+                    };
+                    let disconnected = matches!(event, ConnectionEvent::Disconnected { .. });
+
+                    context.update_app(callback(event));
+
+                    if disconnected {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Subscribe to the topics matching `filter`, passing every message received to the app,
+    /// wrapped in the event produced by the `callback`. If the subscription fails or is ended
+    /// by the shell, the callback is called with the error, and no more messages follow.
+    pub fn subscribe<F>(&self, filter: TopicFilter, qos: QoS, callback: F)
+    where
+        F: Fn(Result<MqttMessage, MqttError>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(MqttOperation::Subscribe {
+                    filter: filter.clone(),
+                    qos,
+                });
+
+                while let Some(response) = stream.next().await {
+                    match response {
+                        MqttResponse::Subscribed { .. } => {}
+                        MqttResponse::Message(message) => {
+                            if filter.matches(&message.topic) {
+                                context.update_app(callback(Ok(message)));
+                            }
+                        }
+                        response => {
+                            context.update_app(callback(Err(response.into())));
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Unsubscribe from `filter`, then send the event returned by `callback` with the result.
+    pub fn unsubscribe<F>(&self, filter: TopicFilter, callback: F)
+    where
+        F: FnOnce(Result<(), MqttError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.unsubscribe_async(filter).await));
+            }
+        });
+    }
+
+    /// Unsubscribe from `filter`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn unsubscribe_async(&self, filter: TopicFilter) -> Result<(), MqttError> {
+        match self
+            .context
+            .request_from_shell(MqttOperation::Unsubscribe { filter })
+            .await
+        {
+            MqttResponse::Unsubscribed => Ok(()),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Publish `message`, then send the event returned by `callback` with the result.
+    /// The result is sent once the broker acknowledged the message, for [`QoS::AtLeastOnce`]
+    /// and [`QoS::ExactlyOnce`], or once it was sent, for [`QoS::AtMostOnce`].
+    pub fn publish<F>(&self, message: PublishMessage, callback: F)
+    where
+        F: FnOnce(Result<(), MqttError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.publish_async(message).await));
+            }
+        });
+    }
+
+    /// Publish `message`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn publish_async(&self, message: PublishMessage) -> Result<(), MqttError> {
+        topic::validate_topic(&message.topic)?;
+
+        match self
+            .context
+            .request_from_shell(MqttOperation::Publish(message))
+            .await
+        {
+            MqttResponse::Published => Ok(()),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Disconnect from the broker. The connection's callback receives
+    /// [`ConnectionEvent::Disconnected`] once the shell has disconnected.
+    pub fn disconnect(&self) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context.notify_shell(MqttOperation::Disconnect).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = MqttOperation::Subscribe {
+            filter: TopicFilter::new("home/+/temperature").unwrap(),
+            qos: QoS::AtLeastOnce,
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"subscribe":{"filter":"home/+/temperature","qos":"atLeastOnce"}}"#
+        );
+
+        let deserialized: MqttOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        // filters are validated when deserialized
+        let invalid = r#"{"unsubscribe":{"filter":"home/#/temperature"}}"#;
+        assert!(serde_json::from_str::<MqttOperation>(invalid).is_err());
+
+        let event: ConnectionEvent =
+            serde_json::from_str(r#"{"connected":{"sessionPresent":true}}"#).unwrap();
+        assert_eq!(
+            event,
+            ConnectionEvent::Connected {
+                session_present: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_password_is_not_debug_printed() {
+        let options =
+            ConnectOptions::new("mqtts://broker.example.com", "app").credentials("user", "hunter2");
+
+        assert!(!format!("{options:?}").contains("hunter2"));
+    }
+}
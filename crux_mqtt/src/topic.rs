@@ -0,0 +1,154 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::MqttError;
+
+/// A topic filter to subscribe with, e.g. `home/+/temperature` or `sensors/#`
+///
+/// `+` matches exactly one topic level and `#` matches any number of levels, including none,
+/// and has to be the last level. As in MQTT, wildcards at the first level don't match topics
+/// starting with `$`, which are reserved for the broker (e.g. `$SYS/...`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TopicFilter(String);
+
+impl TopicFilter {
+    /// Parse a topic filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MqttError::InvalidFilter`] if the filter is empty, or uses wildcards other
+    /// than as whole levels, or `#` other than as the last level.
+    pub fn new(filter: impl Into<String>) -> Result<Self, MqttError> {
+        let filter = filter.into();
+        let invalid = |message: &str| MqttError::InvalidFilter {
+            filter: filter.clone(),
+            message: message.to_string(),
+        };
+
+        if filter.is_empty() {
+            return Err(invalid("topic filters can't be empty"));
+        }
+
+        let levels: Vec<&str> = filter.split('/').collect();
+        for (i, level) in levels.iter().enumerate() {
+            if level.contains('#') && (*level != "#" || i != levels.len() - 1) {
+                return Err(invalid("# has to be the whole of the last level"));
+            }
+            if level.contains('+') && *level != "+" {
+                return Err(invalid("+ has to be a whole level"));
+            }
+        }
+
+        Ok(Self(filter))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether a message published to `topic` matches the filter
+    pub fn matches(&self, topic: &str) -> bool {
+        if topic.starts_with('$') && (self.0.starts_with('+') || self.0.starts_with('#')) {
+            return false;
+        }
+
+        let mut topic_levels = topic.split('/');
+        for level in self.0.split('/') {
+            match (level, topic_levels.next()) {
+                ("#", _) => return true,
+                ("+", Some(_)) => {}
+                (level, Some(topic_level)) if level == topic_level => {}
+                _ => return false,
+            }
+        }
+
+        topic_levels.next().is_none()
+    }
+}
+
+/// Check `topic` can be published to, i.e. it is not empty and has no wildcards
+pub(crate) fn validate_topic(topic: &str) -> Result<(), MqttError> {
+    if topic.is_empty() || topic.contains(['+', '#']) {
+        return Err(MqttError::InvalidTopic {
+            topic: topic.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for TopicFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for TopicFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for TopicFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let filter = String::deserialize(deserializer)?;
+
+        TopicFilter::new(filter).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(filter: &str) -> TopicFilter {
+        TopicFilter::new(filter).unwrap()
+    }
+
+    #[test]
+    fn single_level_wildcard() {
+        let f = filter("home/+/temperature");
+
+        assert!(f.matches("home/kitchen/temperature"));
+        assert!(!f.matches("home/kitchen/humidity"));
+        assert!(!f.matches("home/temperature"));
+        assert!(!f.matches("home/kitchen/fridge/temperature"));
+
+        // empty levels are still levels
+        assert!(f.matches("home//temperature"));
+    }
+
+    #[test]
+    fn multi_level_wildcard() {
+        let f = filter("sensors/#");
+
+        assert!(f.matches("sensors"));
+        assert!(f.matches("sensors/1"));
+        assert!(f.matches("sensors/1/battery"));
+        assert!(!f.matches("actuators/1"));
+
+        assert!(filter("#").matches("anything/at/all"));
+    }
+
+    #[test]
+    fn exact_and_reserved_topics() {
+        assert!(filter("a/b").matches("a/b"));
+        assert!(!filter("a/b").matches("a/b/c"));
+        assert!(!filter("a/b/c").matches("a/b"));
+
+        assert!(!filter("#").matches("$SYS/uptime"));
+        assert!(!filter("+/uptime").matches("$SYS/uptime"));
+        assert!(filter("$SYS/#").matches("$SYS/uptime"));
+    }
+
+    #[test]
+    fn invalid_filters() {
+        for invalid in ["", "a/#/b", "a#", "a/b+", "+a/b"] {
+            assert!(TopicFilter::new(invalid).is_err(), "{invalid}");
+        }
+
+        assert!(validate_topic("a/+").is_err());
+        assert!(validate_topic("a/b").is_ok());
+    }
+}
@@ -0,0 +1,239 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_mqtt::{
+        ConnectOptions, ConnectionEvent, Mqtt, MqttError, MqttMessage, PublishMessage, QoS,
+        TopicFilter,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Connect,
+        SetTarget(String, f64),
+
+        #[serde(skip)]
+        Connection(ConnectionEvent),
+        #[serde(skip)]
+        Temperature(Result<MqttMessage, MqttError>),
+        #[serde(skip)]
+        Published(Result<(), MqttError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub connected: bool,
+        pub readings: Vec<(String, f64)>,
+        pub errors: Vec<MqttError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub mqtt: Mqtt<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Connect => {
+                    let options = ConnectOptions::new("mqtts://broker.example.com", "thermostat")
+                        .credentials("app", "secret");
+                    caps.mqtt.connect(options, Event::Connection);
+                }
+                Event::Connection(ConnectionEvent::Connected { .. }) => {
+                    model.connected = true;
+
+                    let filter = TopicFilter::new("home/+/temperature").unwrap();
+                    caps.mqtt
+                        .subscribe(filter, QoS::AtLeastOnce, Event::Temperature);
+                }
+                Event::Connection(_) => model.connected = false,
+                Event::Temperature(Ok(message)) => {
+                    let room = message.topic.split('/').nth(1).unwrap().to_string();
+                    let value = String::from_utf8(message.payload).unwrap().parse().unwrap();
+                    model.readings.push((room, value));
+                }
+                Event::SetTarget(room, target) => caps.mqtt.publish(
+                    PublishMessage::new(format!("home/{room}/target"), target.to_string())
+                        .qos(QoS::ExactlyOnce)
+                        .retain(),
+                    Event::Published,
+                ),
+                Event::Temperature(Err(error)) | Event::Published(Err(error)) => {
+                    model.errors.push(error)
+                }
+                Event::Published(Ok(())) => {}
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_mqtt::{
+        ConnectionEvent, MqttError, MqttMessage, MqttOperation, MqttResponse, QoS, TopicFilter,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn message(topic: &str, payload: &str) -> MqttResponse {
+        MqttResponse::Message(MqttMessage {
+            topic: topic.to_string(),
+            payload: payload.as_bytes().to_vec(),
+            qos: QoS::AtLeastOnce,
+            retained: false,
+        })
+    }
+
+    #[test]
+    fn connects_subscribes_and_matches_topics() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Connect, &mut model);
+        let Some(Effect::Mqtt(mut connection)) = update.into_effects().next() else {
+            panic!("expected an MQTT effect");
+        };
+        let MqttOperation::Connect(options) = &connection.operation else {
+            panic!("expected to connect");
+        };
+        assert_eq!(options.username.as_deref(), Some("app"));
+
+        let connected = MqttResponse::Connection(ConnectionEvent::Connected {
+            session_present: false,
+        });
+        let update = app.resolve(&mut connection, connected).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert!(model.connected);
+
+        // connecting subscribed
+        let update = app
+            .update(
+                Event::Connection(ConnectionEvent::Connected {
+                    session_present: false,
+                }),
+                &mut model,
+            )
+            .into_effects()
+            .next();
+        let Some(Effect::Mqtt(mut subscription)) = update else {
+            panic!("expected an MQTT effect");
+        };
+        assert_eq!(
+            subscription.operation,
+            MqttOperation::Subscribe {
+                filter: TopicFilter::new("home/+/temperature").unwrap(),
+                qos: QoS::AtLeastOnce
+            }
+        );
+
+        let mut readings = Vec::new();
+        for response in [
+            MqttResponse::Subscribed {
+                qos: QoS::AtLeastOnce,
+            },
+            message("home/kitchen/temperature", "21.5"),
+            // delivered on a shared connection, not matching the filter
+            message("home/kitchen/humidity", "40"),
+            message("home/garage/door/temperature", "12"),
+            message("home/bedroom/temperature", "19"),
+        ] {
+            let update = app.resolve(&mut subscription, response).unwrap();
+            readings.extend(update.events);
+        }
+        for event in readings {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(
+            model.readings,
+            vec![("kitchen".to_string(), 21.5), ("bedroom".to_string(), 19.0)]
+        );
+
+        // the subscription ends with an error
+        let update = app
+            .resolve(
+                &mut subscription,
+                MqttResponse::Error {
+                    message: "not authorized".to_string(),
+                },
+            )
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(
+            model.errors,
+            vec![MqttError::Shell {
+                message: "not authorized".to_string()
+            }]
+        );
+
+        // and so does the connection
+        let update = app
+            .resolve(
+                &mut connection,
+                MqttResponse::Connection(ConnectionEvent::Disconnected { reason: None }),
+            )
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert!(!model.connected);
+    }
+
+    #[test]
+    fn publishes_messages() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::SetTarget("kitchen".to_string(), 20.5), &mut model);
+        let Some(Effect::Mqtt(mut request)) = update.into_effects().next() else {
+            panic!("expected an MQTT effect");
+        };
+
+        let MqttOperation::Publish(message) = &request.operation else {
+            panic!("expected to publish");
+        };
+        assert_eq!(message.topic, "home/kitchen/target");
+        assert_eq!(message.payload, b"20.5");
+        assert_eq!(message.qos, QoS::ExactlyOnce);
+        assert!(message.retain);
+
+        let update = app.resolve(&mut request, MqttResponse::Published).unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert!(model.errors.is_empty());
+    }
+
+    #[test]
+    fn rejects_wildcard_topics_without_asking_the_shell() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::SetTarget("+".to_string(), 20.0), &mut model);
+        assert_eq!(update.effects.len(), 0);
+
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(
+            model.errors,
+            vec![MqttError::InvalidTopic {
+                topic: "home/+/target".to_string()
+            }]
+        );
+    }
+}
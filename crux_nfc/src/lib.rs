@@ -0,0 +1,277 @@
+//! Reading and writing NFC tags
+//!
+//! The [`Nfc`] capability starts NFC reader sessions, during which the NDEF messages of the
+//! tags the user taps are streamed to the core, and writes NDEF messages to tags, so that
+//! ticketing, pairing and similar flows can be written in the core.
+//!
+//! NDEF records can be built and interpreted with the helpers on [`NdefRecord`], e.g.
+//! [`NdefRecord::uri`] and [`NdefRecord::as_text`], without depending on the platform's
+//! parsing. Devices without NFC hardware, or with NFC turned off, fail with
+//! [`NfcError::Unsupported`] and [`NfcError::Disabled`] respectively.
+
+mod ndef;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use ndef::{NdefMessage, NdefRecord, Text, Tnf};
+
+/// Options for a reader session
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionOptions {
+    /// The message shown to the user while scanning, on platforms which show one
+    pub prompt: Option<String>,
+    /// Whether to end the session after the first tag is read
+    pub single_tag: bool,
+}
+
+impl SessionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    #[must_use]
+    pub fn single_tag(mut self) -> Self {
+        self.single_tag = true;
+        self
+    }
+}
+
+/// A tag read during a session
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedTag {
+    /// The tag's identifier, on platforms which expose it
+    pub id: Option<Vec<u8>>,
+    /// The NDEF message stored on the tag, `None` if the tag is empty
+    pub message: Option<NdefMessage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NfcOperation {
+    /// Start a reader session, streaming the tags read until it ends
+    StartSession(SessionOptions),
+    StopSession,
+    /// Write the message to the next tag the user taps
+    Write {
+        message: NdefMessage,
+        prompt: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NfcResponse {
+    Scanned(ScannedTag),
+    /// The session was stopped by the app, timed out, or read its single tag
+    SessionEnded,
+    Written,
+    /// The user dismissed the session
+    Cancelled,
+    /// The device has no NFC hardware
+    Unsupported,
+    /// NFC is turned off in the system settings
+    Disabled,
+    /// The tag can't be written to
+    ReadOnly,
+    /// The message doesn't fit on the tag
+    TooLarge {
+        capacity: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for NfcOperation {
+    type Output = NfcResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NfcError {
+    #[error("this device doesn't support NFC")]
+    Unsupported,
+    #[error("NFC is turned off")]
+    Disabled,
+    #[error("cancelled by the user")]
+    Cancelled,
+    #[error("the tag is read-only")]
+    ReadOnly,
+    #[error("the message doesn't fit on the tag, which holds {capacity} bytes")]
+    TooLarge { capacity: usize },
+    #[error("an NDEF message needs at least one record")]
+    EmptyMessage,
+    #[error("NFC operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<NfcResponse> },
+}
+
+impl From<NfcResponse> for NfcError {
+    fn from(response: NfcResponse) -> Self {
+        match response {
+            NfcResponse::Cancelled => NfcError::Cancelled,
+            NfcResponse::Unsupported => NfcError::Unsupported,
+            NfcResponse::Disabled => NfcError::Disabled,
+            NfcResponse::ReadOnly => NfcError::ReadOnly,
+            NfcResponse::TooLarge { capacity } => NfcError::TooLarge { capacity },
+            NfcResponse::Error { message } => NfcError::Shell { message },
+            response => NfcError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// What happened during a reader session
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionEvent {
+    Scanned(ScannedTag),
+    /// The session ended, and no more tags will be read
+    Ended,
+    /// The session could not start, or was interrupted, and no more tags will be read
+    Failed(NfcError),
+}
+
+/// The Nfc capability API
+///
+/// This capability lets the app read NDEF messages from NFC tags, and write them to tags.
+#[derive(crux_core::macros::Capability)]
+pub struct Nfc<Ev> {
+    context: CapabilityContext<NfcOperation, Ev>,
+}
+
+impl<Ev> Clone for Nfc<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Nfc<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<NfcOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Start a reader session, sending the event returned by `callback` for every tag read,
+    /// and once more when the session ends or fails.
+    pub fn start_session<F>(&self, options: SessionOptions, callback: F)
+    where
+        F: Fn(SessionEvent) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(NfcOperation::StartSession(options));
+
+                while let Some(response) = stream.next().await {
+                    let event = match response {
+                        NfcResponse::Scanned(tag) => SessionEvent::Scanned(tag),
+                        NfcResponse::SessionEnded => SessionEvent::Ended,
+                        response => SessionEvent::Failed(response.into()),
+                    };
+                    let ended = !matches!(event, SessionEvent::Scanned(_));
+
+                    context.update_app(callback(event));
+
+                    if ended {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Stop the current reader session. Its callback is called with [`SessionEvent::Ended`]
+    /// once the shell has stopped it.
+    pub fn stop_session(&self) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context.notify_shell(NfcOperation::StopSession).await;
+            }
+        });
+    }
+
+    /// Write the message to the next tag the user taps, showing the `prompt` while waiting
+    /// on platforms which show one, then send the event returned by `callback` with the result.
+    pub fn write<F>(&self, message: NdefMessage, prompt: Option<String>, callback: F)
+    where
+        F: FnOnce(Result<(), NfcError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.write_async(message, prompt).await));
+            }
+        });
+    }
+
+    /// Write the message to the next tag the user taps.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn write_async(
+        &self,
+        message: NdefMessage,
+        prompt: Option<String>,
+    ) -> Result<(), NfcError> {
+        if message.records.is_empty() {
+            return Err(NfcError::EmptyMessage);
+        }
+
+        match self
+            .context
+            .request_from_shell(NfcOperation::Write { message, prompt })
+            .await
+        {
+            NfcResponse::Written => Ok(()),
+            response => Err(response.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = NfcOperation::StartSession(SessionOptions::new().prompt("Tap your ticket"));
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"startSession":{"prompt":"Tap your ticket","singleTag":false}}"#
+        );
+
+        let deserialized: NfcOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let response = NfcResponse::Scanned(ScannedTag {
+            id: None,
+            message: Some(NdefMessage::new(vec![NdefRecord::uri("tel:123")])),
+        });
+        let serialized = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"scanned":{"id":null,"message":{"records":[{"tnf":"wellKnown","recordType":[85],"id":[],"payload":[5,49,50,51]}]}}}"#
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+
+/// The Type Name Format of a record, which says how to interpret its `record_type`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Tnf {
+    Empty,
+    /// An NFC Forum well-known type, e.g. `T` for text or `U` for a URI
+    WellKnown,
+    /// A media type, e.g. `application/json`
+    Media,
+    AbsoluteUri,
+    /// An NFC Forum external type, e.g. `example.com:ticket`
+    External,
+    Unknown,
+    Unchanged,
+}
+
+/// A record of an NDEF message
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NdefRecord {
+    pub tnf: Tnf,
+    pub record_type: Vec<u8>,
+    pub id: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+/// The contents of a text record
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Text {
+    /// The IANA language code, e.g. `en`
+    pub language: String,
+    pub text: String,
+}
+
+// The abbreviations of URI records, indexed by the first byte of the payload
+const URI_PREFIXES: [&str; 36] = [
+    "",
+    "http://www.",
+    "https://www.",
+    "http://",
+    "https://",
+    "tel:",
+    "mailto:",
+    "ftp://anonymous:anonymous@",
+    "ftp://ftp.",
+    "ftps://",
+    "sftp://",
+    "smb://",
+    "nfs://",
+    "ftp://",
+    "dav://",
+    "news:",
+    "telnet://",
+    "imap:",
+    "rtsp://",
+    "urn:",
+    "pop:",
+    "sip:",
+    "sips:",
+    "tftp:",
+    "btspp://",
+    "btl2cap://",
+    "btgoep://",
+    "tcpobex://",
+    "irdaobex://",
+    "file://",
+    "urn:epc:id:",
+    "urn:epc:tag:",
+    "urn:epc:pat:",
+    "urn:epc:raw:",
+    "urn:epc:",
+    "urn:nfc:",
+];
+
+const UTF16_FLAG: u8 = 0x80;
+const LANGUAGE_LENGTH_MASK: u8 = 0x3f;
+
+impl NdefRecord {
+    /// A well-known text record, encoded as UTF-8
+    pub fn text(text: &str, language: &str) -> Self {
+        let language = &language.as_bytes()[..language.len().min(LANGUAGE_LENGTH_MASK as usize)];
+
+        let mut payload = Vec::with_capacity(1 + language.len() + text.len());
+        payload.push(language.len() as u8);
+        payload.extend_from_slice(language);
+        payload.extend_from_slice(text.as_bytes());
+
+        Self::well_known(b"T", payload)
+    }
+
+    /// A well-known URI record, abbreviated with the longest matching prefix
+    pub fn uri(uri: &str) -> Self {
+        let (code, prefix) = URI_PREFIXES
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, prefix)| uri.starts_with(*prefix))
+            .max_by_key(|(_, prefix)| prefix.len())
+            .unwrap_or((0, &""));
+
+        let mut payload = Vec::with_capacity(1 + uri.len() - prefix.len());
+        payload.push(code as u8);
+        payload.extend_from_slice(&uri.as_bytes()[prefix.len()..]);
+
+        Self::well_known(b"U", payload)
+    }
+
+    /// A record holding `data` of the media type, e.g. `application/json`
+    pub fn mime(media_type: &str, data: Vec<u8>) -> Self {
+        Self {
+            tnf: Tnf::Media,
+            record_type: media_type.as_bytes().to_vec(),
+            id: Vec::new(),
+            payload: data,
+        }
+    }
+
+    fn well_known(record_type: &[u8], payload: Vec<u8>) -> Self {
+        Self {
+            tnf: Tnf::WellKnown,
+            record_type: record_type.to_vec(),
+            id: Vec::new(),
+            payload,
+        }
+    }
+
+    fn is_well_known(&self, record_type: &[u8]) -> bool {
+        self.tnf == Tnf::WellKnown && self.record_type == record_type
+    }
+
+    /// The contents of a text record, or `None` if this isn't a valid one
+    pub fn as_text(&self) -> Option<Text> {
+        if !self.is_well_known(b"T") {
+            return None;
+        }
+
+        let (&status, rest) = self.payload.split_first()?;
+        let language_length = (status & LANGUAGE_LENGTH_MASK) as usize;
+        if rest.len() < language_length {
+            return None;
+        }
+        let (language, text) = rest.split_at(language_length);
+
+        let text = if status & UTF16_FLAG == 0 {
+            String::from_utf8(text.to_vec()).ok()?
+        } else {
+            decode_utf16(text)?
+        };
+
+        Some(Text {
+            language: String::from_utf8(language.to_vec()).ok()?,
+            text,
+        })
+    }
+
+    /// The URI of a URI record, or `None` if this isn't a valid one
+    pub fn as_uri(&self) -> Option<String> {
+        match self.tnf {
+            Tnf::WellKnown if self.record_type == b"U" => {
+                let (&code, rest) = self.payload.split_first()?;
+                let prefix = URI_PREFIXES.get(code as usize)?;
+
+                Some(format!("{prefix}{}", std::str::from_utf8(rest).ok()?))
+            }
+            Tnf::AbsoluteUri => String::from_utf8(self.record_type.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// The media type of a media record
+    pub fn media_type(&self) -> Option<&str> {
+        match self.tnf {
+            Tnf::Media => std::str::from_utf8(&self.record_type).ok(),
+            _ => None,
+        }
+    }
+}
+
+// Big endian, unless there's a byte order mark saying otherwise
+fn decode_utf16(bytes: &[u8]) -> Option<String> {
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+
+    let (little_endian, bytes) = match bytes {
+        [0xff, 0xfe, rest @ ..] => (true, rest),
+        [0xfe, 0xff, rest @ ..] => (false, rest),
+        _ => (false, bytes),
+    };
+
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            let pair = [pair[0], pair[1]];
+            if little_endian {
+                u16::from_le_bytes(pair)
+            } else {
+                u16::from_be_bytes(pair)
+            }
+        })
+        .collect();
+
+    String::from_utf16(&units).ok()
+}
+
+/// An NDEF message, as stored on a tag
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NdefMessage {
+    pub records: Vec<NdefRecord>,
+}
+
+impl NdefMessage {
+    pub fn new(records: Vec<NdefRecord>) -> Self {
+        Self { records }
+    }
+
+    /// The URIs of the message's URI records
+    pub fn uris(&self) -> impl Iterator<Item = String> + '_ {
+        self.records.iter().filter_map(NdefRecord::as_uri)
+    }
+
+    /// The contents of the message's text records
+    pub fn texts(&self) -> impl Iterator<Item = Text> + '_ {
+        self.records.iter().filter_map(NdefRecord::as_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uris_use_the_longest_prefix() {
+        let record = NdefRecord::uri("https://www.example.com/ticket/42");
+
+        assert_eq!(record.payload[0], 0x02);
+        assert_eq!(&record.payload[1..], b"example.com/ticket/42");
+        assert_eq!(
+            record.as_uri().as_deref(),
+            Some("https://www.example.com/ticket/42")
+        );
+
+        let record = NdefRecord::uri("geo:51.5,-0.1");
+        assert_eq!(record.payload[0], 0x00);
+        assert_eq!(record.as_uri().as_deref(), Some("geo:51.5,-0.1"));
+    }
+
+    #[test]
+    fn text_records_round_trip() {
+        let record = NdefRecord::text("Grüß Gott", "de");
+
+        assert_eq!(&record.payload[..3], &[2, b'd', b'e']);
+        assert_eq!(
+            record.as_text(),
+            Some(Text {
+                language: "de".to_string(),
+                text: "Grüß Gott".to_string()
+            })
+        );
+        assert_eq!(record.as_uri(), None);
+    }
+
+    #[test]
+    fn utf16_text_records() {
+        let mut payload = vec![UTF16_FLAG | 2, b'e', b'n', 0xff, 0xfe];
+        payload.extend("hi".encode_utf16().flat_map(u16::to_le_bytes));
+        let record = NdefRecord::well_known(b"T", payload);
+
+        assert_eq!(record.as_text().unwrap().text, "hi");
+    }
+
+    #[test]
+    fn malformed_records() {
+        let truncated = NdefRecord::well_known(b"T", vec![5, b'e', b'n']);
+        assert_eq!(truncated.as_text(), None);
+
+        let unknown_prefix = NdefRecord::well_known(b"U", vec![0x40, b'x']);
+        assert_eq!(unknown_prefix.as_uri(), None);
+
+        let empty = NdefRecord::well_known(b"U", Vec::new());
+        assert_eq!(empty.as_uri(), None);
+    }
+}
@@ -0,0 +1,221 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_nfc::{NdefMessage, NdefRecord, Nfc, NfcError, SessionEvent, SessionOptions};
+    use serde::{Deserialize, Serialize};
+
+    pub const TICKETS: &str = "https://tickets.example.com/";
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        ScanTickets,
+        StopScanning,
+        IssueTicket(String),
+
+        #[serde(skip)]
+        Session(SessionEvent),
+        #[serde(skip)]
+        Issued(Result<(), NfcError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub scanning: bool,
+        pub tickets: Vec<String>,
+        pub issued: bool,
+        pub error: Option<NfcError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub nfc: Nfc<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::ScanTickets => {
+                    model.scanning = true;
+                    caps.nfc.start_session(
+                        SessionOptions::new().prompt("Hold a ticket near the top of the phone"),
+                        Event::Session,
+                    );
+                }
+                Event::StopScanning => caps.nfc.stop_session(),
+                Event::IssueTicket(id) => {
+                    let message = if id.is_empty() {
+                        NdefMessage::new(vec![])
+                    } else {
+                        NdefMessage::new(vec![NdefRecord::uri(&format!("{TICKETS}{id}"))])
+                    };
+                    caps.nfc.write(message, None, Event::Issued);
+                }
+                Event::Session(SessionEvent::Scanned(tag)) => {
+                    let tickets = tag
+                        .message
+                        .iter()
+                        .flat_map(NdefMessage::uris)
+                        .filter_map(|uri| uri.strip_prefix(TICKETS).map(ToString::to_string));
+                    model.tickets.extend(tickets);
+                }
+                Event::Session(SessionEvent::Ended) => model.scanning = false,
+                Event::Session(SessionEvent::Failed(error)) => {
+                    model.scanning = false;
+                    model.error = Some(error);
+                }
+                Event::Issued(Ok(())) => model.issued = true,
+                Event::Issued(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_nfc::{NdefMessage, NdefRecord, NfcError, NfcOperation, NfcResponse, ScannedTag};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn request(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Request<NfcOperation> {
+        let mut effects = app.update(event, model).into_effects();
+        let Some(Effect::Nfc(request)) = effects.next() else {
+            panic!("expected an NFC effect");
+        };
+
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<NfcOperation>,
+        response: NfcResponse,
+    ) {
+        let update = app.resolve(request, response).unwrap();
+        for event in update.events {
+            app.update(event, model);
+        }
+    }
+
+    fn tag(records: Vec<NdefRecord>) -> NfcResponse {
+        NfcResponse::Scanned(ScannedTag {
+            id: Some(vec![0x04, 0xa2, 0x19]),
+            message: Some(NdefMessage::new(records)),
+        })
+    }
+
+    #[test]
+    fn reads_tickets_until_the_session_ends() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut session = request(&app, &mut model, Event::ScanTickets);
+        let NfcOperation::StartSession(options) = &session.operation else {
+            panic!("expected to start a session");
+        };
+        assert!(options.prompt.is_some());
+        assert!(model.scanning);
+
+        respond(
+            &app,
+            &mut model,
+            &mut session,
+            tag(vec![
+                NdefRecord::text("Gate 4", "en"),
+                NdefRecord::uri("https://tickets.example.com/A42"),
+            ]),
+        );
+        respond(
+            &app,
+            &mut model,
+            &mut session,
+            tag(vec![NdefRecord::uri("https://example.com/not-a-ticket")]),
+        );
+        respond(
+            &app,
+            &mut model,
+            &mut session,
+            NfcResponse::Scanned(ScannedTag {
+                id: None,
+                message: None,
+            }),
+        );
+        assert_eq!(model.tickets, vec!["A42".to_string()]);
+
+        let stop = request(&app, &mut model, Event::StopScanning);
+        assert_eq!(stop.operation, NfcOperation::StopSession);
+        assert!(model.scanning);
+
+        respond(&app, &mut model, &mut session, NfcResponse::SessionEnded);
+        assert!(!model.scanning);
+        assert!(app
+            .resolve(&mut session, NfcResponse::SessionEnded)
+            .is_err());
+    }
+
+    #[test]
+    fn unsupported_hardware_fails_the_session() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut session = request(&app, &mut model, Event::ScanTickets);
+        respond(&app, &mut model, &mut session, NfcResponse::Unsupported);
+
+        assert!(!model.scanning);
+        assert_eq!(model.error, Some(NfcError::Unsupported));
+    }
+
+    #[test]
+    fn writes_a_ticket() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut write = request(&app, &mut model, Event::IssueTicket("B7".to_string()));
+        let NfcOperation::Write { message, .. } = &write.operation else {
+            panic!("expected a write");
+        };
+        assert_eq!(
+            message.uris().collect::<Vec<_>>(),
+            vec!["https://tickets.example.com/B7".to_string()]
+        );
+
+        respond(&app, &mut model, &mut write, NfcResponse::Written);
+        assert!(model.issued);
+    }
+
+    #[test]
+    fn write_errors() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut write = request(&app, &mut model, Event::IssueTicket("B7".to_string()));
+        respond(
+            &app,
+            &mut model,
+            &mut write,
+            NfcResponse::TooLarge { capacity: 48 },
+        );
+        assert_eq!(model.error, Some(NfcError::TooLarge { capacity: 48 }));
+
+        // empty messages fail without asking the shell
+        let update = app.update(Event::IssueTicket(String::new()), &mut model);
+        assert!(update.effects.is_empty());
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+        assert_eq!(model.error, Some(NfcError::EmptyMessage));
+    }
+}
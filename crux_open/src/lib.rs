@@ -0,0 +1,219 @@
+//! Opening external apps
+//!
+//! The [`Open`] capability asks the shell to hand something over to another app: compose an
+//! email, dial a number, show a place in a maps app, or open any other URL. The result tells
+//! the core whether an app was found to handle the request, so flows like "contact support"
+//! can fall back to another channel without the shell having to know about them.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+
+/// An email for the user to review and send in their mail app.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MailTo {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+impl MailTo {
+    /// An email addressed to `to`.
+    pub fn new(to: impl Into<String>) -> Self {
+        Self {
+            to: vec![to.into()],
+            ..Default::default()
+        }
+    }
+
+    /// Add a recipient to copy the email to.
+    #[must_use]
+    pub fn cc(mut self, cc: impl Into<String>) -> Self {
+        self.cc.push(cc.into());
+        self
+    }
+
+    /// Set the subject of the email.
+    #[must_use]
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Set the body of the email.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+}
+
+/// A place to show in a maps app, either by its coordinates or by a search query
+/// (e.g. an address). When both are given, the query labels the coordinates.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapsLocation {
+    pub query: Option<String>,
+    pub coordinates: Option<Coordinates>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl MapsLocation {
+    /// A place found by searching for `query`.
+    pub fn search(query: impl Into<String>) -> Self {
+        Self {
+            query: Some(query.into()),
+            coordinates: None,
+        }
+    }
+
+    /// The place at the given coordinates.
+    #[must_use]
+    pub fn at(latitude: f64, longitude: f64) -> Self {
+        Self {
+            query: None,
+            coordinates: Some(Coordinates {
+                latitude,
+                longitude,
+            }),
+        }
+    }
+}
+
+/// What to open.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenRequest {
+    /// Open the mail composer with a prefilled email
+    Mail(MailTo),
+    /// Open the dialer with the phone number, without calling it
+    Phone { number: String },
+    /// Show a location in the maps app
+    Maps(MapsLocation),
+    /// Open a URL with any scheme, e.g. a web page or another app's custom scheme
+    Url { url: String },
+}
+
+impl OpenRequest {
+    pub fn phone(number: impl Into<String>) -> Self {
+        Self::Phone {
+            number: number.into(),
+        }
+    }
+
+    pub fn url(url: impl Into<String>) -> Self {
+        Self::Url { url: url.into() }
+    }
+}
+
+impl From<MailTo> for OpenRequest {
+    fn from(mail: MailTo) -> Self {
+        Self::Mail(mail)
+    }
+}
+
+impl From<MapsLocation> for OpenRequest {
+    fn from(location: MapsLocation) -> Self {
+        Self::Maps(location)
+    }
+}
+
+/// The outcome of an [`OpenRequest`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenResult {
+    /// An app was opened to handle the request
+    Opened,
+    /// No installed app can handle the request
+    NoHandler,
+}
+
+impl Operation for OpenRequest {
+    type Output = OpenResult;
+}
+
+/// The Open capability API
+///
+/// This capability lets the core open external apps, such as mail composers, dialers and maps.
+#[derive(crux_core::macros::Capability)]
+pub struct Open<Ev> {
+    context: CapabilityContext<OpenRequest, Ev>,
+}
+
+impl<Ev> Clone for Open<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Open<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<OpenRequest, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Ask the shell to open an app for `request`, then send the event returned by `callback`
+    /// with the [`OpenResult`].
+    pub fn open<F>(&self, request: impl Into<OpenRequest>, callback: F)
+    where
+        F: FnOnce(OpenResult) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+            let request = request.into();
+
+            async move {
+                let result = this.open_async(request).await;
+
+                context.update_app(callback(result));
+            }
+        });
+    }
+
+    /// Ask the shell to open an app for `request`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn open_async(&self, request: impl Into<OpenRequest>) -> OpenResult {
+        self.context.request_from_shell(request.into()).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let request: OpenRequest = MailTo::new("support@example.com").subject("Help").into();
+
+        let serialized = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"mail":{"to":["support@example.com"],"cc":[],"subject":"Help","body":null}}"#
+        );
+
+        let deserialized: OpenRequest = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, request);
+
+        let serialized =
+            serde_json::to_string(&OpenRequest::from(MapsLocation::at(51.5, -0.1))).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"maps":{"query":null,"coordinates":{"latitude":51.5,"longitude":-0.1}}}"#
+        );
+
+        let serialized = serde_json::to_string(&OpenResult::NoHandler).unwrap();
+        assert_eq!(&serialized, r#""noHandler""#);
+    }
+}
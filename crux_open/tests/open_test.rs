@@ -0,0 +1,134 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_open::{MailTo, Open, OpenRequest, OpenResult};
+    use serde::{Deserialize, Serialize};
+
+    pub const SUPPORT_EMAIL: &str = "support@example.com";
+    pub const SUPPORT_PAGE: &str = "https://example.com/support";
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        ContactSupport,
+
+        #[serde(skip)]
+        MailOpened(OpenResult),
+        #[serde(skip)]
+        SupportPageOpened(OpenResult),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub app_version: String,
+        pub error: Option<String>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<String>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::ContactSupport => {
+                    let mail = MailTo::new(SUPPORT_EMAIL)
+                        .subject("Support request")
+                        .body(format!("App version: {}", model.app_version));
+
+                    caps.open.open(mail, Event::MailOpened);
+                }
+                // without a mail app, fall back to the support page
+                Event::MailOpened(OpenResult::NoHandler) => {
+                    caps.open
+                        .open(OpenRequest::url(SUPPORT_PAGE), Event::SupportPageOpened);
+                }
+                Event::SupportPageOpened(OpenResult::NoHandler) => {
+                    model.error = Some(format!("Please email {SUPPORT_EMAIL}"));
+                    caps.render.render();
+                }
+                Event::MailOpened(OpenResult::Opened)
+                | Event::SupportPageOpened(OpenResult::Opened) => {}
+            }
+        }
+
+        fn view(&self, model: &Model) -> Option<String> {
+            model.error.clone()
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub open: Open<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_open::{MailTo, OpenRequest, OpenResult};
+
+    use crate::shared::{App, Effect, Event, Model, SUPPORT_EMAIL, SUPPORT_PAGE};
+
+    #[test]
+    fn contact_support_opens_the_mail_composer() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model {
+            app_version: "1.2.3".to_string(),
+            ..Default::default()
+        };
+
+        let update = app.update(Event::ContactSupport, &mut model);
+        let Some(Effect::Open(mut request)) = update.into_effects().next() else {
+            panic!("expected an Open effect");
+        };
+        assert_eq!(
+            request.operation,
+            OpenRequest::Mail(MailTo {
+                to: vec![SUPPORT_EMAIL.to_string()],
+                cc: vec![],
+                subject: Some("Support request".to_string()),
+                body: Some("App version: 1.2.3".to_string()),
+            })
+        );
+
+        let update = app.resolve(&mut request, OpenResult::Opened).unwrap();
+        for event in update.events {
+            let update = app.update(event, &mut model);
+            assert!(update.effects.is_empty());
+        }
+        assert_eq!(app.view(&model), None);
+    }
+
+    #[test]
+    fn contact_support_falls_back_to_the_support_page() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::ContactSupport, &mut model);
+        let Some(Effect::Open(mut request)) = update.into_effects().next() else {
+            panic!("expected an Open effect");
+        };
+
+        let update = app.resolve(&mut request, OpenResult::NoHandler).unwrap();
+        let event = update.events.into_iter().next().unwrap();
+
+        let update = app.update(event, &mut model);
+        let Some(Effect::Open(mut request)) = update.into_effects().next() else {
+            panic!("expected an Open effect");
+        };
+        assert_eq!(request.operation, OpenRequest::url(SUPPORT_PAGE));
+
+        let update = app.resolve(&mut request, OpenResult::NoHandler).unwrap();
+        let event = update.events.into_iter().next().unwrap();
+        let _ = app.update(event, &mut model);
+
+        assert_eq!(
+            app.view(&model),
+            Some(format!("Please email {SUPPORT_EMAIL}"))
+        );
+    }
+}
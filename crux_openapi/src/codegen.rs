@@ -0,0 +1,458 @@
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::spec::{json_schema, AdditionalProperties, Operation, Parameter, Schema, Spec};
+use crate::Error;
+
+const SCHEMA_PREFIX: &str = "#/components/schemas/";
+
+pub(crate) fn generate(spec: &Spec) -> Result<TokenStream, Error> {
+    let schemas = spec
+        .components
+        .schemas
+        .iter()
+        .map(|(name, schema)| schema_item(name, schema))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut methods = Vec::new();
+    let mut encodes_paths = false;
+    for (path, item) in &spec.paths {
+        for (method, operation) in item.operations() {
+            let parameters = merge_parameters(&item.parameters, &operation.parameters);
+            encodes_paths |= parameters.iter().any(|p| p.location == "path");
+
+            methods.push(operation_method(path, method, operation, &parameters)?);
+        }
+    }
+
+    let encode_path = encodes_paths.then(|| {
+        quote! {
+            /// Percent-encode a path parameter
+            fn encode_path(segment: impl ::std::fmt::Display) -> String {
+                segment
+                    .to_string()
+                    .bytes()
+                    .map(|b| match b {
+                        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                            char::from(b).to_string()
+                        }
+                        _ => format!("%{b:02X}"),
+                    })
+                    .collect()
+            }
+        }
+    });
+
+    Ok(quote! {
+        /// The error returned by the API client's requests
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum ApiError<E> {
+            /// The request could not be sent, or the response could not be read
+            Http(::crux_http::HttpError),
+            /// The server responded with an unsuccessful status, and the error it described,
+            /// if the response body could be read as one
+            Status { code: u16, error: Option<E> },
+        }
+
+        #(#schemas)*
+
+        /// A client for the API, making requests with the Http capability
+        pub struct Client<Ev> {
+            http: ::crux_http::Http<Ev>,
+            base_url: String,
+        }
+
+        impl<Ev> Clone for Client<Ev> {
+            fn clone(&self) -> Self {
+                Self {
+                    http: self.http.clone(),
+                    base_url: self.base_url.clone(),
+                }
+            }
+        }
+
+        impl<Ev> Client<Ev>
+        where
+            Ev: 'static,
+        {
+            /// Create a client for the API served at `base_url`
+            pub fn new(http: ::crux_http::Http<Ev>, base_url: impl Into<String>) -> Self {
+                Self {
+                    http,
+                    base_url: base_url.into().trim_end_matches('/').to_string(),
+                }
+            }
+
+            #(#methods)*
+        }
+
+        #encode_path
+    })
+}
+
+/// Operation parameters override the path's parameters with the same name and location
+fn merge_parameters<'a>(path: &'a [Parameter], operation: &'a [Parameter]) -> Vec<&'a Parameter> {
+    let mut parameters: Vec<&Parameter> = path
+        .iter()
+        .filter(|p| {
+            !operation
+                .iter()
+                .any(|o| o.name == p.name && o.location == p.location)
+        })
+        .collect();
+    parameters.extend(operation);
+
+    parameters
+}
+
+fn schema_item(name: &str, schema: &Schema) -> Result<TokenStream, Error> {
+    let ident = type_ident(name);
+    let doc = doc(schema.description.as_deref());
+
+    if !schema.enumeration.is_empty() {
+        let variants = schema
+            .enumeration
+            .iter()
+            .map(|value| {
+                let value = value.as_str().ok_or_else(|| Error::Unsupported {
+                    location: name.to_string(),
+                    message: "only string enums are supported".to_string(),
+                })?;
+                let variant = type_ident(value);
+
+                Ok(quote! {
+                    #[serde(rename = #value)]
+                    #variant
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        return Ok(quote! {
+            #doc
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ::serde::Serialize, ::serde::Deserialize)]
+            pub enum #ident {
+                #(#variants),*
+            }
+        });
+    }
+
+    if schema.kind.as_deref() == Some("object") && schema.additional_properties.is_none()
+        || !schema.properties.is_empty()
+    {
+        let fields = schema
+            .properties
+            .iter()
+            .map(|(property, property_schema)| {
+                let location = format!("{name}.{property}");
+                let required = schema.required.contains(property);
+
+                field(property, property_schema, required, &location)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        return Ok(quote! {
+            #doc
+            #[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+            pub struct #ident {
+                #(#fields),*
+            }
+        });
+    }
+
+    let ty = rust_type(schema, name)?;
+    Ok(quote! {
+        #doc
+        pub type #ident = #ty;
+    })
+}
+
+fn field(
+    name: &str,
+    schema: &Schema,
+    required: bool,
+    location: &str,
+) -> Result<TokenStream, Error> {
+    let ident = value_ident(&name.to_snake_case());
+    let doc = doc(schema.description.as_deref());
+    let rename = renamed(&ident, name);
+
+    let ty = rust_type(schema, location)?;
+    let (ty, optional) = if required {
+        (ty, None)
+    } else if schema.nullable {
+        // already an Option
+        (
+            ty,
+            Some(quote!(#[serde(default, skip_serializing_if = "Option::is_none")])),
+        )
+    } else {
+        (
+            quote!(Option<#ty>),
+            Some(quote!(#[serde(default, skip_serializing_if = "Option::is_none")])),
+        )
+    };
+
+    Ok(quote! {
+        #doc
+        #rename
+        #optional
+        pub #ident: #ty
+    })
+}
+
+fn rust_type(schema: &Schema, location: &str) -> Result<TokenStream, Error> {
+    let unsupported = |message: &str| Error::Unsupported {
+        location: location.to_string(),
+        message: message.to_string(),
+    };
+
+    let ty = if let Some(reference) = &schema.reference {
+        let name = reference
+            .strip_prefix(SCHEMA_PREFIX)
+            .ok_or_else(|| unsupported("only references to component schemas are supported"))?;
+        let ident = type_ident(name);
+
+        quote!(#ident)
+    } else {
+        match (schema.kind.as_deref(), schema.format.as_deref()) {
+            (Some("string"), _) => quote!(String),
+            (Some("integer"), Some("int32")) => quote!(i32),
+            (Some("integer"), _) => quote!(i64),
+            (Some("number"), Some("float")) => quote!(f32),
+            (Some("number"), _) => quote!(f64),
+            (Some("boolean"), _) => quote!(bool),
+            (Some("array"), _) => {
+                let items = schema
+                    .items
+                    .as_ref()
+                    .ok_or_else(|| unsupported("arrays need an items schema"))?;
+                let item = rust_type(items, location)?;
+
+                quote!(Vec<#item>)
+            }
+            (Some("object") | None, _) => match &schema.additional_properties {
+                Some(AdditionalProperties::Schema(values)) if schema.properties.is_empty() => {
+                    let value = rust_type(values, location)?;
+
+                    quote!(::std::collections::HashMap<String, #value>)
+                }
+                Some(AdditionalProperties::Allowed(true)) => {
+                    return Err(unsupported("free-form objects are not supported"))
+                }
+                _ => {
+                    return Err(unsupported(
+                        "inline object schemas are not supported, use a component schema",
+                    ))
+                }
+            },
+            (Some(kind), _) => return Err(unsupported(&format!("unknown type {kind}"))),
+        }
+    };
+
+    Ok(if schema.nullable {
+        quote!(Option<#ty>)
+    } else {
+        ty
+    })
+}
+
+fn operation_method(
+    path: &str,
+    method: &str,
+    operation: &Operation,
+    parameters: &[&Parameter],
+) -> Result<TokenStream, Error> {
+    let location = format!("{} {path}", method.to_uppercase());
+    let unsupported = |message: String| Error::Unsupported {
+        location: location.clone(),
+        message,
+    };
+
+    let operation_id = operation
+        .operation_id
+        .as_deref()
+        .ok_or_else(|| unsupported("operations need an operationId".to_string()))?;
+    let name = value_ident(&operation_id.to_snake_case());
+    let doc = doc(operation
+        .summary
+        .as_deref()
+        .or(operation.description.as_deref()));
+    let http_method = Ident::new(method, Span::call_site());
+
+    let mut arguments = Vec::new();
+    let mut query_fields = Vec::new();
+    let mut query_values = Vec::new();
+    let mut headers = Vec::new();
+
+    for parameter in parameters {
+        let ident = value_ident(&parameter.name.to_snake_case());
+        let schema = parameter
+            .schema
+            .as_ref()
+            .ok_or_else(|| unsupported(format!("parameter {} needs a schema", parameter.name)))?;
+        let ty = rust_type(schema, &location)?;
+        let required = parameter.required || parameter.location == "path";
+        let ty = if required { ty } else { quote!(Option<#ty>) };
+
+        arguments.push(quote!(#ident: #ty));
+
+        let name = &parameter.name;
+        match parameter.location.as_str() {
+            "path" => {}
+            "query" => {
+                let rename = renamed(&ident, name);
+                let skip =
+                    (!required).then(|| quote!(#[serde(skip_serializing_if = "Option::is_none")]));
+                query_fields.push(quote! {
+                    #rename
+                    #skip
+                    #ident: #ty
+                });
+                query_values.push(ident);
+            }
+            "header" if required => headers.push(quote! {
+                let request = request.header(#name, #ident.to_string());
+            }),
+            "header" => headers.push(quote! {
+                let request = match #ident {
+                    Some(value) => request.header(#name, value.to_string()),
+                    None => request,
+                };
+            }),
+            other => return Err(unsupported(format!("{other} parameters are not supported"))),
+        }
+    }
+
+    let url = path_format(path, parameters).map_err(unsupported)?;
+
+    let query = (!query_fields.is_empty()).then(|| {
+        quote! {
+            #[derive(::serde::Serialize)]
+            struct Query {
+                #(#query_fields),*
+            }
+
+            let request = request
+                .query(&Query { #(#query_values),* })
+                .map_err(ApiError::Http)?;
+        }
+    });
+
+    let body = match &operation.request_body {
+        None => None,
+        Some(request_body) => {
+            let schema = json_schema(&request_body.content)
+                .ok_or_else(|| unsupported("only JSON request bodies are supported".to_string()))?;
+            let ty = rust_type(schema, &location)?;
+            arguments.push(quote!(body: &#ty));
+
+            Some(quote! {
+                let request = request.body_json(body).map_err(ApiError::Http)?;
+            })
+        }
+    };
+
+    let success = operation
+        .responses
+        .iter()
+        .find(|(status, _)| status.starts_with('2'))
+        .and_then(|(_, response)| json_schema(&response.content))
+        .map(|schema| rust_type(schema, &location))
+        .transpose()?;
+    let failure = operation
+        .responses
+        .iter()
+        .filter(|(status, _)| !status.starts_with('2'))
+        .find_map(|(_, response)| json_schema(&response.content))
+        .map(|schema| rust_type(schema, &location))
+        .transpose()?;
+
+    // the response is only mutated to read its body
+    let mutable = (success.is_some() || failure.is_some()).then(|| quote!(mut));
+    let (output, read_output) = match success {
+        Some(ty) => (
+            ty,
+            quote!(response.body_json().await.map_err(ApiError::Http)),
+        ),
+        None => (quote!(()), quote!(Ok(()))),
+    };
+    let (error, read_error) = match failure {
+        Some(ty) => (ty, quote!(response.body_json().await.ok())),
+        None => (quote!(()), quote!(None)),
+    };
+
+    Ok(quote! {
+        #doc
+        pub async fn #name(&self, #(#arguments),*) -> Result<#output, ApiError<#error>> {
+            let request = self.http.#http_method(#url);
+            #query
+            #(#headers)*
+            #body
+
+            let #mutable response = request.send_async().await.map_err(ApiError::Http)?;
+            if !response.status().is_success() {
+                let code = u16::from(response.status());
+                let error = #read_error;
+
+                return Err(ApiError::Status { code, error });
+            }
+
+            #read_output
+        }
+    })
+}
+
+/// A `format!` call building the URL of `path`, with the path parameters substituted
+fn path_format(path: &str, parameters: &[&Parameter]) -> Result<TokenStream, String> {
+    let mut template = "{}".to_string();
+    let mut values = Vec::new();
+    let mut rest = path;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed parameter in path {path}"))?
+            + start;
+        let name = &rest[start + 1..end];
+        if !parameters
+            .iter()
+            .any(|p| p.location == "path" && p.name == name)
+        {
+            return Err(format!("path parameter {name} is not declared"));
+        }
+
+        template.push_str(&rest[..start]);
+        template.push_str("{}");
+        let ident = value_ident(&name.to_snake_case());
+        values.push(quote!(encode_path(#ident)));
+
+        rest = &rest[end + 1..];
+    }
+    template.push_str(rest);
+
+    Ok(quote!(format!(#template, self.base_url #(, #values)*)))
+}
+
+/// A `#[serde(rename)]` attribute, if `ident` is not the same as the original `name`
+fn renamed(ident: &Ident, name: &str) -> Option<TokenStream> {
+    (ident.to_string().trim_start_matches("r#") != name).then(|| quote!(#[serde(rename = #name)]))
+}
+
+fn type_ident(name: &str) -> Ident {
+    value_ident(&name.to_upper_camel_case())
+}
+
+/// An identifier for `name`, which is a raw identifier if `name` is a keyword
+fn value_ident(name: &str) -> Ident {
+    syn::parse_str::<Ident>(name).unwrap_or_else(|_| Ident::new_raw(name, Span::call_site()))
+}
+
+fn doc(text: Option<&str>) -> Option<TokenStream> {
+    let lines = text?
+        .trim()
+        .lines()
+        .map(|line| format!(" {}", line.trim_end()));
+
+    Some(quote!(#(#[doc = #lines])*))
+}
@@ -0,0 +1,187 @@
+//! Typed `crux_http` API clients, generated from OpenAPI specifications
+//!
+//! The [`Generator`] reads an OpenAPI 3 specification (in JSON) and generates Rust source for
+//!
+//! * a type for every schema in `components.schemas` - structs for objects, enums for string
+//!   enumerations and aliases for everything else,
+//! * a `Client<Ev>` wrapping the [`crux_http`](https://docs.rs/crux_http) capability, with an
+//!   async method per operation, named after its `operationId`, which builds the URL, query,
+//!   headers and JSON body, sends the request and decodes the response,
+//! * an `ApiError<E>` returned by the client methods, holding either the `HttpError` or the
+//!   unsuccessful status, with the error body decoded into the operation's error schema.
+//!
+//! The client methods are meant to be used in an async context, together with
+//! [`crux_core::compose::Compose`](https://docs.rs/crux_core/latest/crux_core/compose/struct.Compose.html).
+//! The generated code uses `serde` and `crux_http`, which the app needs to depend on.
+//!
+//! Typically, the generator is used from a build script, writing the client to `OUT_DIR`:
+//!
+//! ```rust,no_run
+//! // build.rs
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//!
+//! crux_openapi::generate_file("api.json", format!("{out_dir}/api.rs")).unwrap();
+//! println!("cargo:rerun-if-changed=api.json");
+//! ```
+//!
+//! and including it in the shared crate with `include!(concat!(env!("OUT_DIR"), "/api.rs"));`
+//!
+//! Specifications using features which are not supported, like inline object schemas, cookie
+//! parameters or non-JSON bodies, are rejected with [`Error::Unsupported`], naming where in the
+//! specification the feature was used, rather than generating a partial client.
+
+mod codegen;
+mod spec;
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::spec::Spec;
+
+const HEADER: &str = "// @generated by crux_openapi from an OpenAPI specification, do not edit\n\n";
+
+/// Generates a `crux_http` client from an OpenAPI specification
+#[derive(Debug)]
+pub struct Generator {
+    spec: Spec,
+}
+
+impl Generator {
+    /// Read an OpenAPI 3 specification in JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification can't be parsed.
+    pub fn from_json(spec: &str) -> Result<Self, Error> {
+        let spec = serde_json::from_str(spec).map_err(|e| Error::Parse {
+            message: e.to_string(),
+        })?;
+
+        Ok(Self { spec })
+    }
+
+    /// Generate the formatted Rust source of the client and the schema types
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification uses features which are not supported.
+    pub fn generate(&self) -> Result<String, Error> {
+        let tokens = codegen::generate(&self.spec)?;
+        let file = syn::parse2(tokens).map_err(|e| Error::Unsupported {
+            location: "generated code".to_string(),
+            message: e.to_string(),
+        })?;
+
+        Ok(format!("{HEADER}{}", prettyplease::unparse(&file)))
+    }
+}
+
+/// Generate the client for the specification at `spec`, and write it to `out`
+///
+/// # Errors
+///
+/// Returns an error if either file can't be read or written, or the generation fails.
+pub fn generate_file(spec: impl AsRef<Path>, out: impl AsRef<Path>) -> Result<(), Error> {
+    let spec = std::fs::read_to_string(spec).map_err(|e| Error::Io {
+        message: e.to_string(),
+    })?;
+    let source = Generator::from_json(&spec)?.generate()?;
+
+    std::fs::write(out, source).map_err(|e| Error::Io {
+        message: e.to_string(),
+    })
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("failed to read or write a file: {message}")]
+    Io { message: String },
+    #[error("invalid OpenAPI specification: {message}")]
+    Parse { message: String },
+    #[error("unsupported OpenAPI feature at {location}: {message}")]
+    Unsupported { location: String, message: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(spec: serde_json::Value) -> Result<String, Error> {
+        Generator::from_json(&spec.to_string())?.generate()
+    }
+
+    #[test]
+    fn keywords_and_renames() {
+        let source = generate(serde_json::json!({
+            "components": {
+                "schemas": {
+                    "item": {
+                        "type": "object",
+                        "required": ["type"],
+                        "properties": {
+                            "type": { "type": "string" },
+                            "createdAt": { "type": "integer", "format": "int32" },
+                            "tags": {
+                                "type": "object",
+                                "additionalProperties": { "type": "string" }
+                            }
+                        }
+                    },
+                    "ids": { "type": "array", "items": { "type": "integer" } }
+                }
+            }
+        }))
+        .unwrap();
+
+        assert!(source.contains("pub struct Item {"));
+        assert!(source.contains("pub r#type: String,"));
+        assert!(source.contains("#[serde(rename = \"createdAt\")]"));
+        assert!(source.contains("pub created_at: Option<i32>,"));
+        assert!(source.contains("pub tags: Option<::std::collections::HashMap<String, String>>,"));
+        assert!(source.contains("pub type Ids = Vec<i64>;"));
+    }
+
+    #[test]
+    fn rejects_unsupported_features() {
+        let inline = generate(serde_json::json!({
+            "components": {
+                "schemas": {
+                    "outer": {
+                        "type": "object",
+                        "properties": {
+                            "inner": { "type": "object", "properties": {} }
+                        }
+                    }
+                }
+            }
+        }));
+        assert!(matches!(
+            inline,
+            Err(Error::Unsupported { location, .. }) if location == "outer.inner"
+        ));
+
+        let cookie = generate(serde_json::json!({
+            "paths": {
+                "/me": {
+                    "get": {
+                        "operationId": "me",
+                        "parameters": [
+                            { "name": "session", "in": "cookie", "schema": { "type": "string" } }
+                        ],
+                        "responses": {}
+                    }
+                }
+            }
+        }));
+        assert!(matches!(
+            cookie,
+            Err(Error::Unsupported { location, .. }) if location == "GET /me"
+        ));
+
+        let anonymous = generate(serde_json::json!({
+            "paths": { "/me": { "get": { "responses": {} } } }
+        }));
+        assert!(matches!(anonymous, Err(Error::Unsupported { .. })));
+    }
+}
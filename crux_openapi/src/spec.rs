@@ -0,0 +1,121 @@
+//! The subset of the OpenAPI 3 specification the generator understands
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Spec {
+    #[serde(default)]
+    pub paths: BTreeMap<String, PathItem>,
+    #[serde(default)]
+    pub components: Components,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct Components {
+    #[serde(default)]
+    pub schemas: BTreeMap<String, Schema>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PathItem {
+    pub get: Option<Operation>,
+    pub put: Option<Operation>,
+    pub post: Option<Operation>,
+    pub delete: Option<Operation>,
+    pub patch: Option<Operation>,
+    /// Parameters shared by all the operations on the path
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+}
+
+impl PathItem {
+    pub fn operations(&self) -> impl Iterator<Item = (&'static str, &Operation)> {
+        [
+            ("get", &self.get),
+            ("put", &self.put),
+            ("post", &self.post),
+            ("delete", &self.delete),
+            ("patch", &self.patch),
+        ]
+        .into_iter()
+        .filter_map(|(method, operation)| Some((method, operation.as_ref()?)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Operation {
+    pub operation_id: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+    pub request_body: Option<RequestBody>,
+    #[serde(default)]
+    pub responses: BTreeMap<String, Response>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Parameter {
+    pub name: String,
+    #[serde(rename = "in")]
+    pub location: String,
+    #[serde(default)]
+    pub required: bool,
+    pub schema: Option<Schema>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RequestBody {
+    #[serde(default)]
+    pub content: BTreeMap<String, MediaType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Response {
+    #[serde(default)]
+    pub content: BTreeMap<String, MediaType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MediaType {
+    pub schema: Option<Schema>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Schema {
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub format: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, Schema>,
+    #[serde(default)]
+    pub required: Vec<String>,
+    pub items: Option<Box<Schema>>,
+    #[serde(rename = "enum", default)]
+    pub enumeration: Vec<serde_json::Value>,
+    pub additional_properties: Option<AdditionalProperties>,
+    #[serde(default)]
+    pub nullable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum AdditionalProperties {
+    Allowed(bool),
+    Schema(Box<Schema>),
+}
+
+/// The JSON content of a request or response, if any
+pub(crate) fn json_schema(content: &BTreeMap<String, MediaType>) -> Option<&Schema> {
+    content
+        .iter()
+        .find(|(media_type, _)| media_type.starts_with("application/json"))
+        .and_then(|(_, media_type)| media_type.schema.as_ref())
+}
@@ -0,0 +1,157 @@
+// @generated by crux_openapi from an OpenAPI specification, do not edit
+
+/// The error returned by the API client's requests
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError<E> {
+    /// The request could not be sent, or the response could not be read
+    Http(::crux_http::HttpError),
+    /// The server responded with an unsuccessful status, and the error it described,
+    /// if the response body could be read as one
+    Status { code: u16, error: Option<E> },
+}
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct Error {
+    pub code: i32,
+    pub message: String,
+}
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct NewPet {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+/// A pet in the store
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct Pet {
+    #[serde(rename = "birthDate")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub birth_date: Option<String>,
+    pub id: i64,
+    pub name: String,
+    pub status: PetStatus,
+    /// A free-form label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    ::serde::Serialize,
+    ::serde::Deserialize
+)]
+pub enum PetStatus {
+    #[serde(rename = "available")]
+    Available,
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "sold")]
+    Sold,
+}
+/// A client for the API, making requests with the Http capability
+pub struct Client<Ev> {
+    http: ::crux_http::Http<Ev>,
+    base_url: String,
+}
+impl<Ev> Clone for Client<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            http: self.http.clone(),
+            base_url: self.base_url.clone(),
+        }
+    }
+}
+impl<Ev> Client<Ev>
+where
+    Ev: 'static,
+{
+    /// Create a client for the API served at `base_url`
+    pub fn new(http: ::crux_http::Http<Ev>, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+    /// List the pets in the store
+    pub async fn list_pets(
+        &self,
+        limit: Option<i32>,
+        status: Option<PetStatus>,
+    ) -> Result<Vec<Pet>, ApiError<Error>> {
+        let request = self.http.get(format!("{}/pets", self.base_url));
+        #[derive(::serde::Serialize)]
+        struct Query {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            limit: Option<i32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            status: Option<PetStatus>,
+        }
+        let request = request.query(&Query { limit, status }).map_err(ApiError::Http)?;
+        let mut response = request.send_async().await.map_err(ApiError::Http)?;
+        if !response.status().is_success() {
+            let code = u16::from(response.status());
+            let error = response.body_json().await.ok();
+            return Err(ApiError::Status { code, error });
+        }
+        response.body_json().await.map_err(ApiError::Http)
+    }
+    /// Add a pet to the store
+    pub async fn create_pet(
+        &self,
+        x_request_id: String,
+        body: &NewPet,
+    ) -> Result<Pet, ApiError<Error>> {
+        let request = self.http.post(format!("{}/pets", self.base_url));
+        let request = request.header("X-Request-Id", x_request_id.to_string());
+        let request = request.body_json(body).map_err(ApiError::Http)?;
+        let mut response = request.send_async().await.map_err(ApiError::Http)?;
+        if !response.status().is_success() {
+            let code = u16::from(response.status());
+            let error = response.body_json().await.ok();
+            return Err(ApiError::Status { code, error });
+        }
+        response.body_json().await.map_err(ApiError::Http)
+    }
+    /// Show a pet
+    pub async fn show_pet_by_id(&self, pet_id: String) -> Result<Pet, ApiError<Error>> {
+        let request = self
+            .http
+            .get(format!("{}/pets/{}", self.base_url, encode_path(pet_id)));
+        let mut response = request.send_async().await.map_err(ApiError::Http)?;
+        if !response.status().is_success() {
+            let code = u16::from(response.status());
+            let error = response.body_json().await.ok();
+            return Err(ApiError::Status { code, error });
+        }
+        response.body_json().await.map_err(ApiError::Http)
+    }
+    /// Remove a pet from the store
+    pub async fn delete_pet(&self, pet_id: String) -> Result<(), ApiError<()>> {
+        let request = self
+            .http
+            .delete(format!("{}/pets/{}", self.base_url, encode_path(pet_id)));
+        let response = request.send_async().await.map_err(ApiError::Http)?;
+        if !response.status().is_success() {
+            let code = u16::from(response.status());
+            let error = None;
+            return Err(ApiError::Status { code, error });
+        }
+        Ok(())
+    }
+}
+/// Percent-encode a path parameter
+fn encode_path(segment: impl ::std::fmt::Display) -> String {
+    segment
+        .to_string()
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,235 @@
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[path = "generated/petstore.rs"]
+mod petstore;
+
+mod shared {
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_http::Http;
+    use serde::{Deserialize, Serialize};
+
+    use crate::petstore::{ApiError, Client, Error, NewPet, Pet, PetStatus};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        ListAvailable,
+        Show(String),
+        Create(String),
+
+        #[serde(skip)]
+        Listed(Result<Vec<Pet>, ApiError<Error>>),
+        #[serde(skip)]
+        Shown(Result<Pet, ApiError<Error>>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub pets: Vec<Pet>,
+        pub error: Option<ApiError<Error>>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            let client = Client::new(caps.http.clone(), "https://petstore.example.com/v1/");
+
+            match event {
+                Event::ListAvailable => caps.compose.spawn(|ctx| async move {
+                    let pets = client.list_pets(None, Some(PetStatus::Available)).await;
+                    ctx.update_app(Event::Listed(pets));
+                }),
+                Event::Show(id) => caps.compose.spawn(|ctx| async move {
+                    ctx.update_app(Event::Shown(client.show_pet_by_id(id).await));
+                }),
+                Event::Create(name) => caps.compose.spawn(|ctx| async move {
+                    let pet = NewPet { name, tag: None };
+                    let created = client.create_pet("request-1".to_string(), &pet).await;
+                    ctx.update_app(Event::Shown(created));
+                }),
+                Event::Listed(Ok(pets)) => model.pets = pets,
+                Event::Shown(Ok(pet)) => model.pets.push(pet),
+                Event::Listed(Err(error)) | Event::Shown(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpRequest, HttpResponse, HttpResult};
+    use serde_json::json;
+
+    use crate::petstore::{ApiError, Error, Pet, PetStatus};
+    use crate::shared::{App, Effect, Event, Model};
+
+    /// Sends the event and answers its only HTTP request with `response`
+    fn run(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+        response: HttpResponse,
+    ) -> HttpRequest {
+        let update = app.update(event, model);
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("expected an HTTP request");
+        };
+
+        let update = app.resolve(&mut request, HttpResult::Ok(response)).unwrap();
+        for event in update.events {
+            app.update(event, model);
+        }
+
+        request.operation
+    }
+
+    fn pet(id: i64, name: &str) -> Pet {
+        Pet {
+            id,
+            name: name.to_string(),
+            status: PetStatus::Available,
+            tag: None,
+            birth_date: None,
+        }
+    }
+
+    #[test]
+    fn generated_client_is_up_to_date() {
+        let spec = include_str!("petstore.json");
+        let generated = crux_openapi::Generator::from_json(spec)
+            .unwrap()
+            .generate()
+            .unwrap();
+
+        assert_eq!(generated, include_str!("generated/petstore.rs"));
+    }
+
+    #[test]
+    fn query_parameters_and_typed_response() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let request = run(
+            &app,
+            &mut model,
+            Event::ListAvailable,
+            HttpResponse::ok()
+                .json(json!([
+                    {"id": 1, "name": "Rex", "status": "available", "birthDate": null},
+                    {"id": 2, "name": "Tom", "status": "available"}
+                ]))
+                .build(),
+        );
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(
+            request.url,
+            "https://petstore.example.com/v1/pets?status=available"
+        );
+        assert_eq!(model.pets, vec![pet(1, "Rex"), pet(2, "Tom")]);
+    }
+
+    #[test]
+    fn path_parameters_are_encoded() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let request = run(
+            &app,
+            &mut model,
+            Event::Show("a/b c".to_string()),
+            HttpResponse::ok()
+                .json(json!({"id": 3, "name": "Bob", "status": "available"}))
+                .build(),
+        );
+
+        assert_eq!(
+            request.url,
+            "https://petstore.example.com/v1/pets/a%2Fb%20c"
+        );
+        assert_eq!(model.pets, vec![pet(3, "Bob")]);
+    }
+
+    #[test]
+    fn headers_and_json_body() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let request = run(
+            &app,
+            &mut model,
+            Event::Create("Rex".to_string()),
+            HttpResponse::status(201)
+                .json(json!({"id": 1, "name": "Rex", "status": "available"}))
+                .build(),
+        );
+
+        assert_eq!(request.method, "POST");
+        assert!(request
+            .headers
+            .iter()
+            .any(|h| h.name.eq_ignore_ascii_case("x-request-id") && h.value == "request-1"));
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&request.body).unwrap(),
+            json!({"name": "Rex"})
+        );
+        assert_eq!(model.pets, vec![pet(1, "Rex")]);
+    }
+
+    #[test]
+    fn unsuccessful_status_decodes_the_error() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        run(
+            &app,
+            &mut model,
+            Event::Show("42".to_string()),
+            HttpResponse::status(404)
+                .json(json!({"code": 404, "message": "no such pet"}))
+                .build(),
+        );
+
+        assert_eq!(
+            model.error,
+            Some(ApiError::Status {
+                code: 404,
+                error: Some(Error {
+                    code: 404,
+                    message: "no such pet".to_string()
+                })
+            })
+        );
+
+        // an error body which doesn't match the schema is left out
+        run(
+            &app,
+            &mut model,
+            Event::Show("42".to_string()),
+            HttpResponse::status(500).body("oops").build(),
+        );
+
+        assert_eq!(
+            model.error,
+            Some(ApiError::Status {
+                code: 500,
+                error: None
+            })
+        );
+    }
+}
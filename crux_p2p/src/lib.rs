@@ -0,0 +1,493 @@
+//! Peer-to-peer local networking
+//!
+//! The [`P2p`] capability lets the core find other devices on the local network and exchange
+//! messages with them, for local multiplayer or device pairing. The shell advertises and browses
+//! for services with mDNS (Bonjour on Apple platforms, NSD on Android), and opens the TCP or
+//! UDP sockets. The core drives everything else: which peers to connect to, what to send, and
+//! how to react to connections opening and closing.
+//!
+//! Connections are identified by a [`ConnectionId`] chosen by the shell, both for the
+//! connections the core opens with [`P2p::connect`] and the ones accepted while advertising.
+//! [`Peers`] keeps track of the peers currently discovered, from the [`DiscoveryEvent`]s.
+
+mod peers;
+
+use std::collections::BTreeMap;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use peers::Peers;
+
+/// A DNS-SD service type, e.g. `_chess._tcp`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ServiceType(String);
+
+impl ServiceType {
+    /// Parse a service type.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::InvalidServiceType`] unless `service_type` is an underscore prefixed
+    /// name of at most 15 characters, followed by `._tcp` or `._udp`.
+    pub fn new(service_type: impl Into<String>) -> Result<Self, P2pError> {
+        let service_type = service_type.into();
+
+        let name = service_type
+            .strip_suffix("._tcp")
+            .or_else(|| service_type.strip_suffix("._udp"))
+            .and_then(|name| name.strip_prefix('_'));
+        let valid = match name {
+            Some(name) => {
+                (1..=15).contains(&name.len())
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    && !name.starts_with('-')
+                    && !name.ends_with('-')
+            }
+            None => false,
+        };
+
+        if valid {
+            Ok(Self(service_type))
+        } else {
+            Err(P2pError::InvalidServiceType { service_type })
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for ServiceType {
+    type Error = P2pError;
+
+    fn try_from(service_type: String) -> Result<Self, Self::Error> {
+        Self::new(service_type)
+    }
+}
+
+impl From<ServiceType> for String {
+    fn from(service_type: ServiceType) -> Self {
+        service_type.0
+    }
+}
+
+/// A service to advertise on the local network
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Advertisement {
+    pub service_type: ServiceType,
+    /// The instance name shown to other devices, e.g. "Alice's iPad"
+    pub name: String,
+    /// The port to listen on, or any free port if `None`
+    pub port: Option<u16>,
+    /// Key-value metadata published in the TXT record
+    pub txt: BTreeMap<String, String>,
+}
+
+impl Advertisement {
+    pub fn new(service_type: ServiceType, name: impl Into<String>) -> Self {
+        Self {
+            service_type,
+            name: name.into(),
+            port: None,
+            txt: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    #[must_use]
+    pub fn txt(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.txt.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// The shell's identifier for a discovered peer
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PeerId(pub String);
+
+/// The shell's identifier for an open connection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ConnectionId(pub u64);
+
+/// A peer discovered on the local network
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Peer {
+    pub id: PeerId,
+    pub name: String,
+    pub service_type: ServiceType,
+    pub txt: BTreeMap<String, String>,
+}
+
+/// Where to connect to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Endpoint {
+    /// A discovered peer, resolved to an address by the shell
+    Peer {
+        id: PeerId,
+    },
+    Address {
+        host: String,
+        port: u16,
+    },
+}
+
+impl From<&Peer> for Endpoint {
+    fn from(peer: &Peer) -> Self {
+        Self::Peer {
+            id: peer.id.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Transport {
+    /// A reliable, ordered stream. Each message sent is delivered as one message, with the
+    /// shell taking care of the framing.
+    #[default]
+    Tcp,
+    /// Unreliable datagrams, for latency sensitive messages such as game state
+    Udp,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum P2pOperation {
+    /// Advertise the service and accept connections to it, until stopped
+    Advertise(Advertisement),
+    StopAdvertising {
+        service_type: ServiceType,
+    },
+    /// Browse for peers advertising the service type, until stopped
+    Browse {
+        service_type: ServiceType,
+    },
+    StopBrowsing {
+        service_type: ServiceType,
+    },
+    /// Open a connection, and stream its events until it's closed
+    Connect {
+        endpoint: Endpoint,
+        transport: Transport,
+    },
+    Send {
+        connection: ConnectionId,
+        data: Vec<u8>,
+    },
+    Close {
+        connection: ConnectionId,
+    },
+}
+
+/// An event of a connection, opened by either side
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionEvent {
+    Opened {
+        connection: ConnectionId,
+        /// The peer, if it was discovered
+        peer: Option<PeerId>,
+    },
+    Received {
+        connection: ConnectionId,
+        data: Vec<u8>,
+    },
+    /// The connection was closed, by either side, or couldn't be opened. This is the last
+    /// event for the connection.
+    Closed {
+        connection: Option<ConnectionId>,
+        reason: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AdvertiseEvent {
+    /// The service is advertised, with the name and port it got. The name may differ from the
+    /// requested one, if another device on the network already uses it.
+    Advertising { name: String, port: u16 },
+    /// An event of a connection accepted from a peer
+    Connection(ConnectionEvent),
+    /// The service is no longer advertised. This is the last event of the advertisement.
+    Stopped { reason: Option<String> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscoveryEvent {
+    Found(Peer),
+    Lost {
+        id: PeerId,
+    },
+    /// Browsing stopped. This is the last event of the discovery.
+    Stopped {
+        reason: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum P2pResponse {
+    Advertise(AdvertiseEvent),
+    Discovery(DiscoveryEvent),
+    Connection(ConnectionEvent),
+    Sent,
+    Error { message: String },
+}
+
+impl Operation for P2pOperation {
+    type Output = P2pResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum P2pError {
+    #[error("invalid service type {service_type}, expected e.g. _name._tcp")]
+    InvalidServiceType { service_type: String },
+    #[error("local network operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<P2pResponse> },
+}
+
+/// The P2p capability API
+///
+/// This capability lets the app discover peers on the local network, and open connections
+/// to exchange messages with them.
+#[derive(crux_core::macros::Capability)]
+pub struct P2p<Ev> {
+    context: CapabilityContext<P2pOperation, Ev>,
+}
+
+impl<Ev> Clone for P2p<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> P2p<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<P2pOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Advertise a service, and accept connections from peers. The advertisement's progress
+    /// and the events of the accepted connections are passed to the app wrapped in the event
+    /// produced by the `callback`, until [`AdvertiseEvent::Stopped`].
+    pub fn advertise<F>(&self, advertisement: Advertisement, callback: F)
+    where
+        F: Fn(AdvertiseEvent) -> Ev + Send + Sync + 'static,
+    {
+        self.stream(P2pOperation::Advertise(advertisement), move |response| {
+            let event = match response {
+                P2pResponse::Advertise(event) => event,
+                P2pResponse::Connection(event) => AdvertiseEvent::Connection(event),
+                P2pResponse::Error { message } => AdvertiseEvent::Stopped {
+                    reason: Some(message),
+                },
+                _ => return None,
+            };
+            let last = matches!(event, AdvertiseEvent::Stopped { .. });
+
+            Some((callback(event), last))
+        });
+    }
+
+    /// Stop advertising the service type. The shell closes the connections accepted while
+    /// advertising, before ending the advertisement with [`AdvertiseEvent::Stopped`].
+    pub fn stop_advertising(&self, service_type: ServiceType) {
+        self.notify(P2pOperation::StopAdvertising { service_type });
+    }
+
+    /// Browse for peers advertising `service_type`, passing the peers found and lost to the
+    /// app wrapped in the event produced by the `callback`, until [`DiscoveryEvent::Stopped`].
+    pub fn browse<F>(&self, service_type: ServiceType, callback: F)
+    where
+        F: Fn(DiscoveryEvent) -> Ev + Send + Sync + 'static,
+    {
+        self.stream(P2pOperation::Browse { service_type }, move |response| {
+            let event = match response {
+                P2pResponse::Discovery(event) => event,
+                P2pResponse::Error { message } => DiscoveryEvent::Stopped {
+                    reason: Some(message),
+                },
+                _ => return None,
+            };
+            let last = matches!(event, DiscoveryEvent::Stopped { .. });
+
+            Some((callback(event), last))
+        });
+    }
+
+    pub fn stop_browsing(&self, service_type: ServiceType) {
+        self.notify(P2pOperation::StopBrowsing { service_type });
+    }
+
+    /// Open a connection to `endpoint`, passing its events to the app wrapped in the event
+    /// produced by the `callback`, until [`ConnectionEvent::Closed`]. A connection which can't
+    /// be opened is closed without being opened.
+    pub fn connect<F>(&self, endpoint: impl Into<Endpoint>, transport: Transport, callback: F)
+    where
+        F: Fn(ConnectionEvent) -> Ev + Send + Sync + 'static,
+    {
+        let operation = P2pOperation::Connect {
+            endpoint: endpoint.into(),
+            transport,
+        };
+
+        self.stream(operation, move |response| {
+            let event = match response {
+                P2pResponse::Connection(event) => event,
+                P2pResponse::Error { message } => ConnectionEvent::Closed {
+                    connection: None,
+                    reason: Some(message),
+                },
+                _ => return None,
+            };
+            let last = matches!(event, ConnectionEvent::Closed { .. });
+
+            Some((callback(event), last))
+        });
+    }
+
+    /// Send `data` on the connection, then send the event returned by `callback` with the
+    /// result.
+    pub fn send<F>(&self, connection: ConnectionId, data: Vec<u8>, callback: F)
+    where
+        F: FnOnce(Result<(), P2pError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.send_async(connection, data).await));
+            }
+        });
+    }
+
+    /// Send `data` on the connection.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn send_async(
+        &self,
+        connection: ConnectionId,
+        data: Vec<u8>,
+    ) -> Result<(), P2pError> {
+        match self
+            .context
+            .request_from_shell(P2pOperation::Send { connection, data })
+            .await
+        {
+            P2pResponse::Sent => Ok(()),
+            P2pResponse::Error { message } => Err(P2pError::Shell { message }),
+            response => Err(P2pError::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Close the connection. Its events end with [`ConnectionEvent::Closed`].
+    pub fn close(&self, connection: ConnectionId) {
+        self.notify(P2pOperation::Close { connection });
+    }
+
+    fn notify(&self, operation: P2pOperation) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context.notify_shell(operation).await;
+            }
+        });
+    }
+
+    /// Stream the responses to `operation`, passing the events made from them to the app,
+    /// until the last one
+    fn stream<F>(&self, operation: P2pOperation, make_event: F)
+    where
+        F: Fn(P2pResponse) -> Option<(Ev, bool)> + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(operation);
+
+                while let Some(response) = stream.next().await {
+                    let Some((event, last)) = make_event(response) else {
+                        continue;
+                    };
+
+                    context.update_app(event);
+
+                    if last {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_service_types() {
+        for valid in ["_chess._tcp", "_x._udp", "_my-game._tcp"] {
+            assert!(ServiceType::new(valid).is_ok(), "{valid}");
+        }
+
+        for invalid in [
+            "chess._tcp",
+            "_chess",
+            "_._tcp",
+            "_chess._sctp",
+            "_-chess._tcp",
+        ] {
+            assert!(ServiceType::new(invalid).is_err(), "{invalid}");
+        }
+
+        let too_long = format!("_{}._tcp", "a".repeat(16));
+        assert!(ServiceType::new(too_long).is_err());
+    }
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = P2pOperation::Advertise(
+            Advertisement::new(ServiceType::new("_chess._tcp").unwrap(), "Alice").txt("v", "2"),
+        );
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"advertise":{"serviceType":"_chess._tcp","name":"Alice","port":null,"txt":{"v":"2"}}}"#
+        );
+
+        let deserialized: P2pOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let valid = r#"{"browse":{"serviceType":"_chess._tcp"}}"#;
+        assert!(serde_json::from_str::<P2pOperation>(valid).is_ok());
+
+        let invalid = r#"{"browse":{"serviceType":"chess"}}"#;
+        assert!(serde_json::from_str::<P2pOperation>(invalid).is_err());
+    }
+}
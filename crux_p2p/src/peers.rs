@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+
+use crate::{DiscoveryEvent, Peer, PeerId};
+
+/// The peers currently discovered, kept up to date with the [`DiscoveryEvent`]s of browsing
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Peers {
+    peers: BTreeMap<PeerId, Peer>,
+}
+
+impl Peers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the peers with a discovery event. When browsing stops, all the peers are
+    /// forgotten, as their comings and goings are no longer known.
+    pub fn update(&mut self, event: &DiscoveryEvent) {
+        match event {
+            DiscoveryEvent::Found(peer) => {
+                self.peers.insert(peer.id.clone(), peer.clone());
+            }
+            DiscoveryEvent::Lost { id } => {
+                self.peers.remove(id);
+            }
+            DiscoveryEvent::Stopped { .. } => self.peers.clear(),
+        }
+    }
+
+    pub fn get(&self, id: &PeerId) -> Option<&Peer> {
+        self.peers.get(id)
+    }
+
+    /// The peers, ordered by their id
+    pub fn iter(&self) -> impl Iterator<Item = &Peer> {
+        self.peers.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
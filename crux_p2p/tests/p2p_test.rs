@@ -0,0 +1,303 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_p2p::{
+        AdvertiseEvent, Advertisement, ConnectionEvent, ConnectionId, DiscoveryEvent, P2p, PeerId,
+        Peers, ServiceType, Transport,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Host,
+        Join,
+        Pair(PeerId),
+        Leave,
+
+        #[serde(skip)]
+        Advertised(AdvertiseEvent),
+        #[serde(skip)]
+        Discovered(DiscoveryEvent),
+        #[serde(skip)]
+        Connection(ConnectionEvent),
+        #[serde(skip)]
+        Sent(Result<(), crux_p2p::P2pError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub advertised_as: Option<String>,
+        pub peers: Peers,
+        pub connection: Option<ConnectionId>,
+        pub received: Vec<String>,
+        pub closed: Option<Option<String>>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub p2p: P2p<Event>,
+    }
+
+    fn service_type() -> ServiceType {
+        ServiceType::new("_chess._tcp").unwrap()
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Host => caps.p2p.advertise(
+                    Advertisement::new(service_type(), "Alice").txt("version", "2"),
+                    Event::Advertised,
+                ),
+                Event::Join => caps.p2p.browse(service_type(), Event::Discovered),
+                Event::Pair(id) => {
+                    if let Some(peer) = model.peers.get(&id) {
+                        caps.p2p.connect(peer, Transport::Tcp, Event::Connection);
+                    }
+                }
+                Event::Leave => {
+                    if let Some(connection) = model.connection {
+                        caps.p2p.close(connection);
+                    }
+                }
+                Event::Advertised(AdvertiseEvent::Advertising { name, .. }) => {
+                    model.advertised_as = Some(name);
+                }
+                Event::Advertised(AdvertiseEvent::Connection(event)) | Event::Connection(event) => {
+                    match event {
+                        ConnectionEvent::Opened { connection, .. } => {
+                            model.connection = Some(connection);
+                            caps.p2p.send(connection, b"hello".to_vec(), Event::Sent);
+                        }
+                        ConnectionEvent::Received { data, .. } => {
+                            model.received.push(String::from_utf8(data).unwrap());
+                        }
+                        ConnectionEvent::Closed { reason, .. } => {
+                            model.connection = None;
+                            model.closed = Some(reason);
+                        }
+                    }
+                }
+                Event::Advertised(AdvertiseEvent::Stopped { .. }) => model.advertised_as = None,
+                Event::Discovered(event) => model.peers.update(&event),
+                Event::Sent(result) => result.unwrap(),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crux_core::{testing::AppTester, Request};
+    use crux_p2p::{
+        AdvertiseEvent, ConnectionEvent, ConnectionId, DiscoveryEvent, Endpoint, P2pOperation,
+        P2pResponse, Peer, PeerId, ServiceType, Transport,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn only_request(effects: impl Iterator<Item = Effect>) -> Request<P2pOperation> {
+        let mut effects: Vec<_> = effects.collect();
+        assert_eq!(effects.len(), 1);
+
+        let Effect::P2p(request) = effects.remove(0);
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<P2pOperation>,
+        response: P2pResponse,
+    ) -> Vec<Effect> {
+        let update = app.resolve(request, response).unwrap();
+        let mut effects = update.effects;
+
+        for event in update.events {
+            effects.extend(app.update(event, model).effects);
+        }
+
+        effects
+    }
+
+    fn peer(id: &str, name: &str) -> Peer {
+        Peer {
+            id: PeerId(id.to_string()),
+            name: name.to_string(),
+            service_type: ServiceType::new("_chess._tcp").unwrap(),
+            txt: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn discovers_and_connects_to_a_peer() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut browse = only_request(app.update(Event::Join, &mut model).into_effects());
+        assert_eq!(
+            browse.operation,
+            P2pOperation::Browse {
+                service_type: ServiceType::new("_chess._tcp").unwrap()
+            }
+        );
+
+        for event in [
+            DiscoveryEvent::Found(peer("1", "Alice")),
+            DiscoveryEvent::Found(peer("2", "Bob")),
+            DiscoveryEvent::Lost {
+                id: PeerId("2".to_string()),
+            },
+        ] {
+            respond(&app, &mut model, &mut browse, P2pResponse::Discovery(event));
+        }
+        let names: Vec<_> = model.peers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Alice"]);
+
+        let mut connection = only_request(
+            app.update(Event::Pair(PeerId("1".to_string())), &mut model)
+                .into_effects(),
+        );
+        assert_eq!(
+            connection.operation,
+            P2pOperation::Connect {
+                endpoint: Endpoint::Peer {
+                    id: PeerId("1".to_string())
+                },
+                transport: Transport::Tcp
+            }
+        );
+
+        // once opened, the app says hello
+        let effects = respond(
+            &app,
+            &mut model,
+            &mut connection,
+            P2pResponse::Connection(ConnectionEvent::Opened {
+                connection: ConnectionId(7),
+                peer: Some(PeerId("1".to_string())),
+            }),
+        );
+        let mut send = only_request(effects.into_iter());
+        assert_eq!(
+            send.operation,
+            P2pOperation::Send {
+                connection: ConnectionId(7),
+                data: b"hello".to_vec()
+            }
+        );
+        respond(&app, &mut model, &mut send, P2pResponse::Sent);
+
+        respond(
+            &app,
+            &mut model,
+            &mut connection,
+            P2pResponse::Connection(ConnectionEvent::Received {
+                connection: ConnectionId(7),
+                data: b"hi".to_vec(),
+            }),
+        );
+        assert_eq!(model.received, vec!["hi"]);
+
+        let close = only_request(app.update(Event::Leave, &mut model).into_effects());
+        assert_eq!(
+            close.operation,
+            P2pOperation::Close {
+                connection: ConnectionId(7)
+            }
+        );
+
+        respond(
+            &app,
+            &mut model,
+            &mut connection,
+            P2pResponse::Connection(ConnectionEvent::Closed {
+                connection: Some(ConnectionId(7)),
+                reason: None,
+            }),
+        );
+        assert_eq!(model.connection, None);
+        assert_eq!(model.closed, Some(None));
+    }
+
+    #[test]
+    fn advertises_and_accepts_connections() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut advertise = only_request(app.update(Event::Host, &mut model).into_effects());
+        let P2pOperation::Advertise(advertisement) = &advertise.operation else {
+            panic!("expected to advertise");
+        };
+        assert_eq!(advertisement.txt["version"], "2");
+
+        // the name was taken
+        respond(
+            &app,
+            &mut model,
+            &mut advertise,
+            P2pResponse::Advertise(AdvertiseEvent::Advertising {
+                name: "Alice (2)".to_string(),
+                port: 49152,
+            }),
+        );
+        assert_eq!(model.advertised_as.as_deref(), Some("Alice (2)"));
+
+        let effects = respond(
+            &app,
+            &mut model,
+            &mut advertise,
+            P2pResponse::Connection(ConnectionEvent::Opened {
+                connection: ConnectionId(1),
+                peer: None,
+            }),
+        );
+        assert_eq!(model.connection, Some(ConnectionId(1)));
+        assert_eq!(effects.len(), 1);
+
+        // an error stops the advertisement
+        respond(
+            &app,
+            &mut model,
+            &mut advertise,
+            P2pResponse::Error {
+                message: "local network access denied".to_string(),
+            },
+        );
+        assert_eq!(model.advertised_as, None);
+    }
+
+    #[test]
+    fn failing_to_connect_closes_the_connection() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        model
+            .peers
+            .update(&DiscoveryEvent::Found(peer("1", "Alice")));
+
+        let mut connection = only_request(
+            app.update(Event::Pair(PeerId("1".to_string())), &mut model)
+                .into_effects(),
+        );
+        respond(
+            &app,
+            &mut model,
+            &mut connection,
+            P2pResponse::Error {
+                message: "peer unreachable".to_string(),
+            },
+        );
+
+        assert_eq!(model.closed, Some(Some("peer unreachable".to_string())));
+    }
+}
@@ -0,0 +1,411 @@
+//! Taking payments with the platform's payment sheet
+//!
+//! The [`Payments`] capability presents the platform's payment sheet (Apple Pay or Google Pay)
+//! for a [`PaymentRequest`] assembled by the core. When the user authorizes the payment, the
+//! shell returns the tokenized payment data, which the core sends to its backend to charge,
+//! before telling the shell whether the payment succeeded, so the sheet can show the outcome.
+//!
+//! The whole flow, including the confirmation with the backend over `crux_http`, is available
+//! as [`Payments::pay_async`]. The merchant details needed by the platform (merchant
+//! identifiers, payment gateway configuration) stay in the shell, and the core refers to them
+//! by name.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use crux_http::{Http, HttpError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+/// An amount shown on the payment sheet
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineItem {
+    pub label: String,
+    /// The amount in the currency's minor unit, e.g. cents
+    pub amount: u64,
+    /// Whether the amount isn't final yet, e.g. an estimated tip
+    pub pending: bool,
+}
+
+/// A request for a payment
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequest {
+    /// The name of the merchant configuration in the shell, e.g. which Apple Pay merchant
+    /// identifier or Google Pay gateway to use
+    pub merchant: String,
+    /// The merchant's ISO 3166 country code, e.g. `GB`
+    pub country_code: String,
+    /// The ISO 4217 currency code, e.g. `GBP`
+    pub currency_code: String,
+    pub items: Vec<LineItem>,
+    /// The sum of the items, labelled with the merchant's name
+    pub total: LineItem,
+}
+
+impl PaymentRequest {
+    /// A request with no items yet, totalling zero. The total is shown labelled
+    /// with `merchant_name`.
+    pub fn new(
+        merchant: impl Into<String>,
+        country_code: impl Into<String>,
+        currency_code: impl Into<String>,
+        merchant_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            merchant: merchant.into(),
+            country_code: country_code.into(),
+            currency_code: currency_code.into(),
+            items: Vec::new(),
+            total: LineItem {
+                label: merchant_name.into(),
+                amount: 0,
+                pending: false,
+            },
+        }
+    }
+
+    /// Add an item, and its amount to the total
+    #[must_use]
+    pub fn item(self, label: impl Into<String>, amount: u64) -> Self {
+        self.line_item(label.into(), amount, false)
+    }
+
+    /// Add an item whose amount isn't final yet, making the total pending too
+    #[must_use]
+    pub fn pending_item(self, label: impl Into<String>, amount: u64) -> Self {
+        self.line_item(label.into(), amount, true)
+    }
+
+    fn line_item(mut self, label: String, amount: u64, pending: bool) -> Self {
+        self.total.amount = self.total.amount.saturating_add(amount);
+        self.total.pending |= pending;
+        self.items.push(LineItem {
+            label,
+            amount,
+            pending,
+        });
+        self
+    }
+
+    fn validate(&self) -> Result<(), PaymentError> {
+        let is_code = |code: &str, len: usize| {
+            code.len() == len && code.chars().all(|c| c.is_ascii_uppercase())
+        };
+        let invalid = |message: &str| {
+            Err(PaymentError::InvalidRequest {
+                message: message.to_string(),
+            })
+        };
+
+        if !is_code(&self.country_code, 2) {
+            return invalid("the country code must be two upper case letters");
+        }
+        if !is_code(&self.currency_code, 3) {
+            return invalid("the currency code must be three upper case letters");
+        }
+        if self.total.amount == 0 {
+            return invalid("the total must be more than zero");
+        }
+
+        Ok(())
+    }
+}
+
+/// The payment authorized by the user
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentToken {
+    /// The encrypted payment data, as provided by the platform, for the backend to pass
+    /// on to its payment processor
+    pub data: String,
+    /// The card network, e.g. `visa`, if known
+    pub network: Option<String>,
+    /// A description of the card for display, e.g. `Visa 1234`
+    pub description: Option<String>,
+}
+
+/// Whether the backend charged the payment, for the payment sheet to show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaymentStatus {
+    Success,
+    Failure,
+}
+
+/// What's sent to the backend to charge an authorized payment
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Confirmation {
+    pub token: PaymentToken,
+    pub amount: u64,
+    pub currency_code: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaymentOperation {
+    /// Whether the user can pay with the merchant configuration
+    CanMakePayments { merchant: String },
+    /// Present the payment sheet
+    Present(PaymentRequest),
+    /// Dismiss the payment sheet, showing whether the payment succeeded
+    Complete(PaymentStatus),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaymentResponse {
+    Available(bool),
+    Authorized(PaymentToken),
+    /// The user dismissed the payment sheet
+    Cancelled,
+    /// Payments aren't supported on the device, or with the merchant configuration
+    Unavailable,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for PaymentOperation {
+    type Output = PaymentResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PaymentError {
+    #[error("invalid payment request: {message}")]
+    InvalidRequest { message: String },
+    #[error("payments are not available")]
+    Unavailable,
+    #[error("the payment was cancelled")]
+    Cancelled,
+    #[error("the payment was declined, with status {status}")]
+    Declined { status: u16 },
+    #[error("confirming the payment failed: {error}")]
+    Http { error: HttpError },
+    #[error("payment sheet failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<PaymentResponse> },
+}
+
+impl From<PaymentResponse> for PaymentError {
+    fn from(response: PaymentResponse) -> Self {
+        match response {
+            PaymentResponse::Cancelled => PaymentError::Cancelled,
+            PaymentResponse::Unavailable => PaymentError::Unavailable,
+            PaymentResponse::Error { message } => PaymentError::Shell { message },
+            response => PaymentError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// The Payments capability API
+///
+/// This capability lets the app take payments with the platform's payment sheet.
+#[derive(crux_core::macros::Capability)]
+pub struct Payments<Ev> {
+    context: CapabilityContext<PaymentOperation, Ev>,
+}
+
+impl<Ev> Clone for Payments<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Payments<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<PaymentOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Check whether the user can pay with the `merchant` configuration, e.g. to decide
+    /// whether to show the payment button, then send the event returned by `callback`.
+    pub fn can_make_payments<F>(&self, merchant: impl Into<String>, callback: F)
+    where
+        F: FnOnce(bool) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+            let merchant = merchant.into();
+
+            async move {
+                context.update_app(callback(this.can_make_payments_async(merchant).await));
+            }
+        });
+    }
+
+    /// Check whether the user can pay with the `merchant` configuration.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn can_make_payments_async(&self, merchant: impl Into<String>) -> bool {
+        let response = self
+            .context
+            .request_from_shell(PaymentOperation::CanMakePayments {
+                merchant: merchant.into(),
+            })
+            .await;
+
+        matches!(response, PaymentResponse::Available(true))
+    }
+
+    /// Present the payment sheet, then send the event returned by `callback` with the
+    /// payment authorized by the user. The sheet stays up until [`Self::complete`] is called.
+    pub fn present<F>(&self, request: PaymentRequest, callback: F)
+    where
+        F: FnOnce(Result<PaymentToken, PaymentError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.present_async(request).await));
+            }
+        });
+    }
+
+    /// Present the payment sheet, returning the payment authorized by the user.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request isn't valid, in which case no sheet is presented,
+    /// if payments aren't available, or if the user cancels.
+    pub async fn present_async(
+        &self,
+        request: PaymentRequest,
+    ) -> Result<PaymentToken, PaymentError> {
+        request.validate()?;
+
+        match self
+            .context
+            .request_from_shell(PaymentOperation::Present(request))
+            .await
+        {
+            PaymentResponse::Authorized(token) => Ok(token),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Dismiss the payment sheet, showing whether the payment succeeded
+    pub fn complete(&self, status: PaymentStatus) {
+        self.context.spawn({
+            let this = self.clone();
+
+            async move {
+                this.complete_async(status).await;
+            }
+        });
+    }
+
+    /// Dismiss the payment sheet, showing whether the payment succeeded.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn complete_async(&self, status: PaymentStatus) {
+        self.context
+            .notify_shell(PaymentOperation::Complete(status))
+            .await;
+    }
+
+    /// Take a payment: present the payment sheet, POST the authorized payment as a
+    /// [`Confirmation`] to the backend at `confirm_url`, then complete the sheet with the
+    /// outcome, returning the backend's JSON response, e.g. an order receipt.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payment isn't authorized, or the backend doesn't accept it.
+    /// Once the backend has accepted the payment, the sheet shows success even if its
+    /// response can't be deserialized, in which case an [`PaymentError::Http`] is returned.
+    pub async fn pay_async<T, HttpEv>(
+        &self,
+        request: PaymentRequest,
+        http: &Http<HttpEv>,
+        confirm_url: &str,
+    ) -> Result<T, PaymentError>
+    where
+        T: DeserializeOwned,
+        HttpEv: 'static,
+    {
+        let confirmation = Confirmation {
+            amount: request.total.amount,
+            currency_code: request.currency_code.clone(),
+            token: self.present_async(request).await?,
+        };
+
+        let sent = match http.post(confirm_url).body_json(&confirmation) {
+            Ok(request) => request.send_async().await,
+            Err(error) => Err(error),
+        };
+        let mut response = match sent {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                self.complete_async(PaymentStatus::Failure).await;
+                return Err(PaymentError::Declined {
+                    status: response.status().into(),
+                });
+            }
+            Err(error) => {
+                self.complete_async(PaymentStatus::Failure).await;
+                return Err(PaymentError::Http { error });
+            }
+        };
+
+        self.complete_async(PaymentStatus::Success).await;
+
+        response
+            .body_json()
+            .await
+            .map_err(|error| PaymentError::Http { error })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let request = PaymentRequest::new("merchant.com.example", "GB", "GBP", "Example Coffee")
+            .item("Flat white", 350);
+
+        let serialized =
+            serde_json::to_string(&PaymentOperation::Present(request.clone())).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"present":{"merchant":"merchant.com.example","countryCode":"GB","currencyCode":"GBP","items":[{"label":"Flat white","amount":350,"pending":false}],"total":{"label":"Example Coffee","amount":350,"pending":false}}}"#
+        );
+
+        let deserialized: PaymentOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, PaymentOperation::Present(request));
+    }
+
+    #[test]
+    fn test_totals_and_validation() {
+        let request = PaymentRequest::new("merchant", "GB", "GBP", "Cafe")
+            .item("Flat white", 350)
+            .pending_item("Tip", 50);
+
+        assert_eq!(request.total.amount, 400);
+        assert!(request.total.pending);
+        assert_eq!(request.validate(), Ok(()));
+
+        let invalid = [
+            PaymentRequest::new("merchant", "gb", "GBP", "Cafe").item("Tea", 1),
+            PaymentRequest::new("merchant", "GB", "POUNDS", "Cafe").item("Tea", 1),
+            PaymentRequest::new("merchant", "GB", "GBP", "Cafe"),
+        ];
+        for request in invalid {
+            assert!(matches!(
+                request.validate(),
+                Err(PaymentError::InvalidRequest { .. })
+            ));
+        }
+    }
+}
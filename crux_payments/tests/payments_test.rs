@@ -0,0 +1,204 @@
+mod shared {
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_http::Http;
+    use crux_payments::{PaymentError, PaymentRequest, Payments};
+    use serde::{Deserialize, Serialize};
+
+    pub const CONFIRM_URL: &str = "https://api.example.com/orders";
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Receipt {
+        pub order: String,
+    }
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Checkout,
+
+        #[serde(skip)]
+        Paid(Result<Receipt, PaymentError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub receipt: Option<Receipt>,
+        pub error: Option<PaymentError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub payments: Payments<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Checkout => {
+                    let request =
+                        PaymentRequest::new("merchant.com.example", "GB", "GBP", "Example Coffee")
+                            .item("Flat white", 350)
+                            .item("Croissant", 275);
+                    let http = caps.http.clone();
+                    let payments = caps.payments.clone();
+
+                    caps.compose.spawn(|ctx| async move {
+                        let result = payments.pay_async(request, &http, CONFIRM_URL).await;
+                        ctx.update_app(Event::Paid(result));
+                    });
+                }
+                Event::Paid(Ok(receipt)) => model.receipt = Some(receipt),
+                Event::Paid(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::{AppTester, Update};
+    use crux_http::protocol::{HttpResponse, HttpResult};
+    use crux_payments::{
+        Confirmation, PaymentError, PaymentOperation, PaymentResponse, PaymentStatus, PaymentToken,
+    };
+    use serde_json::json;
+
+    use crate::shared::{App, Effect, Event, Model, Receipt, CONFIRM_URL};
+
+    fn token() -> PaymentToken {
+        PaymentToken {
+            data: "eyJwYXltZW50RGF0YSI6e319".to_string(),
+            network: Some("visa".to_string()),
+            description: Some("Visa 1234".to_string()),
+        }
+    }
+
+    /// Present the sheet and authorize the payment, returning the update with the backend request
+    fn authorize(app: &AppTester<App, Effect>, model: &mut Model) -> Update<Effect, Event> {
+        let update = app.update(Event::Checkout, model);
+        let Some(Effect::Payments(mut request)) = update.into_effects().next() else {
+            panic!("expected a Payments effect");
+        };
+        let PaymentOperation::Present(payment) = &request.operation else {
+            panic!("expected to present the payment sheet");
+        };
+        assert_eq!(payment.total.amount, 625);
+
+        app.resolve(&mut request, PaymentResponse::Authorized(token()))
+            .unwrap()
+    }
+
+    fn confirm(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        update: Update<Effect, Event>,
+        response: HttpResponse,
+    ) -> Vec<PaymentOperation> {
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("expected an Http effect");
+        };
+        assert_eq!(request.operation.url, CONFIRM_URL);
+        assert_eq!(request.operation.method, "POST");
+
+        let confirmation: Confirmation = serde_json::from_slice(&request.operation.body).unwrap();
+        assert_eq!(
+            confirmation,
+            Confirmation {
+                token: token(),
+                amount: 625,
+                currency_code: "GBP".to_string(),
+            }
+        );
+
+        let update = app.resolve(&mut request, HttpResult::Ok(response)).unwrap();
+        for event in update.events {
+            app.update(event, model);
+        }
+
+        update
+            .effects
+            .into_iter()
+            .map(|effect| {
+                let Effect::Payments(request) = effect else {
+                    panic!("expected a Payments effect");
+                };
+                request.operation
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pays_and_confirms_with_the_backend() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = authorize(&app, &mut model);
+        let completed = confirm(
+            &app,
+            &mut model,
+            update,
+            HttpResponse::ok()
+                .json(json!({ "order": "A-1042" }))
+                .build(),
+        );
+
+        assert_eq!(
+            completed,
+            vec![PaymentOperation::Complete(PaymentStatus::Success)]
+        );
+        assert_eq!(
+            model.receipt,
+            Some(Receipt {
+                order: "A-1042".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn declined_by_the_backend() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = authorize(&app, &mut model);
+        let completed = confirm(&app, &mut model, update, HttpResponse::status(402).build());
+
+        assert_eq!(
+            completed,
+            vec![PaymentOperation::Complete(PaymentStatus::Failure)]
+        );
+        assert_eq!(model.receipt, None);
+        assert_eq!(model.error, Some(PaymentError::Declined { status: 402 }));
+    }
+
+    #[test]
+    fn cancelled_payments_are_not_confirmed() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Checkout, &mut model);
+        let Some(Effect::Payments(mut request)) = update.into_effects().next() else {
+            panic!("expected a Payments effect");
+        };
+
+        let update = app
+            .resolve(&mut request, PaymentResponse::Cancelled)
+            .unwrap();
+        assert!(update.effects.is_empty());
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(model.error, Some(PaymentError::Cancelled));
+    }
+}
@@ -0,0 +1,565 @@
+//! Extending an app with plugins, sandboxed in the core
+//!
+//! Plugins let third parties, or users, extend an app without shipping a new core. A plugin is
+//! data: a manifest, usually downloaded, listing the events it handles and the commands it
+//! sends back to the app for each of them, with [`crux_rules`] expressions deciding when a
+//! handler runs and computing the arguments of its commands.
+//!
+//! The app stays in control of what plugins can do:
+//!
+//! * plugins only see the events the app chooses to [`dispatch`](Plugins::dispatch) to them,
+//!   of a type the app defines for plugins, and a view of its state made for them, rather than
+//!   the app's own events and model
+//! * plugins can only send the commands of another type the app defines, which it handles in
+//!   its `update` like any other input. Anything else a plugin asks for is an error.
+//! * plugins can't perform I/O, keep state or call into the app, and each expression is limited
+//!   in the work it can do (see [`crux_rules::Limits`]), so dispatching is deterministic and
+//!   can't hang the core
+//!
+//! ```
+//! use crux_plugins::Plugins;
+//! use serde::{Deserialize, Serialize};
+//!
+//! // what plugins see
+//! #[derive(Serialize)]
+//! #[serde(rename_all = "camelCase")]
+//! enum PluginEvent {
+//!     ItemAdded { name: String, price: u32 },
+//!     CheckoutOpened,
+//! }
+//!
+//! // what plugins can do
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! #[serde(rename_all = "camelCase")]
+//! enum PluginCommand {
+//!     ShowToast { message: String },
+//!     HighlightRewards,
+//! }
+//!
+//! let mut plugins = Plugins::<PluginCommand>::new();
+//! plugins.load(serde_json::from_str(r#"{
+//!     "name": "loyalty",
+//!     "handlers": [{
+//!         "on": "itemAdded",
+//!         "when": "event.price >= 100 && state.member",
+//!         "send": [
+//!             { "command": "showToast", "args": { "message": "'Double points on ' + event.name" } },
+//!             { "command": "highlightRewards" }
+//!         ]
+//!     }]
+//! }"#)?);
+//!
+//! let event = PluginEvent::ItemAdded { name: "Kettle".to_string(), price: 120 };
+//! let dispatch = plugins.dispatch(&event, &serde_json::json!({ "member": true }));
+//!
+//! assert_eq!(
+//!     dispatch.commands,
+//!     vec![
+//!         PluginCommand::ShowToast { message: "Double points on Kettle".to_string() },
+//!         PluginCommand::HighlightRewards,
+//!     ]
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use crux_rules::{Rule, RuleError, Value};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PluginError {
+    #[error("plugin {plugin} is already loaded")]
+    AlreadyLoaded { plugin: String },
+    #[error("plugin {plugin} failed: {error}")]
+    Rule { plugin: String, error: RuleError },
+    #[error("plugin {plugin} sent a command which isn't allowed: {message}")]
+    Command { plugin: String, message: String },
+    #[error("the event or state couldn't be serialized: {0}")]
+    Input(String),
+}
+
+/// A command a handler sends, with expressions computing its arguments
+#[derive(Clone, Debug, PartialEq)]
+struct SendCommand {
+    command: String,
+    args: BTreeMap<String, Rule>,
+}
+
+/// What a plugin does when it's sent an event
+#[derive(Clone, Debug, PartialEq)]
+struct Handler {
+    on: String,
+    when: Option<Rule>,
+    send: Vec<SendCommand>,
+}
+
+/// A plugin, parsed from its manifest.
+///
+/// Manifests are JSON objects with the plugin's `name`, and its `handlers`. Each handler has
+/// the name of the event it handles (`on`), an optional condition (`when`), and the commands
+/// it sends (`send`), each with a `command` name and `args` computed by expressions. The
+/// expressions read the event's fields as `event` and the state the app shares with plugins as
+/// `state`. Deserializing fails if any of the expressions is invalid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plugin {
+    name: String,
+    handlers: Vec<Handler>,
+}
+
+impl Plugin {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names of the events the plugin handles
+    pub fn events(&self) -> impl Iterator<Item = &str> {
+        self.handlers.iter().map(|handler| handler.on.as_str())
+    }
+
+    fn handle(&self, event: &str, input: &Value) -> Result<Vec<Value>, PluginError> {
+        let rule_error = |error| PluginError::Rule {
+            plugin: self.name.clone(),
+            error,
+        };
+
+        let mut commands = Vec::new();
+        for handler in self.handlers.iter().filter(|handler| handler.on == event) {
+            if let Some(when) = &handler.when {
+                match when.evaluate_value(input).map_err(rule_error)? {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => continue,
+                    _ => {
+                        return Err(rule_error(RuleError::Type(format!(
+                            "the condition for {event} isn't a boolean"
+                        ))))
+                    }
+                }
+            }
+
+            for send in &handler.send {
+                // the shape serde gives unit and struct variants of an enum
+                let command = if send.args.is_empty() {
+                    Value::String(send.command.clone())
+                } else {
+                    let args = send
+                        .args
+                        .iter()
+                        .map(|(name, arg)| Ok((name.clone(), arg.evaluate_value(input)?)))
+                        .collect::<Result<_, RuleError>>()
+                        .map_err(rule_error)?;
+
+                    Value::Object(
+                        [(send.command.clone(), Value::Object(args))]
+                            .into_iter()
+                            .collect(),
+                    )
+                };
+                commands.push(command);
+            }
+        }
+
+        Ok(commands)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PluginRepr {
+    name: String,
+    handlers: Vec<HandlerRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HandlerRepr {
+    on: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    when: Option<String>,
+    #[serde(default)]
+    send: Vec<SendRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SendRepr {
+    command: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    args: BTreeMap<String, String>,
+}
+
+impl TryFrom<PluginRepr> for Plugin {
+    type Error = RuleError;
+
+    fn try_from(repr: PluginRepr) -> Result<Self, RuleError> {
+        let handlers = repr
+            .handlers
+            .into_iter()
+            .map(|handler| {
+                let rule = |name: String, expression| Rule::new(name, expression);
+
+                let when = handler
+                    .when
+                    .map(|when| rule(format!("{}.{}.when", repr.name, handler.on), when))
+                    .transpose()?;
+                let send = handler
+                    .send
+                    .into_iter()
+                    .map(|send| {
+                        let args = send
+                            .args
+                            .into_iter()
+                            .map(|(arg, expression)| {
+                                let name = format!("{}.{}.{}", repr.name, send.command, arg);
+                                Ok((arg, rule(name, expression)?))
+                            })
+                            .collect::<Result<_, RuleError>>()?;
+
+                        Ok(SendCommand {
+                            command: send.command,
+                            args,
+                        })
+                    })
+                    .collect::<Result<_, RuleError>>()?;
+
+                Ok(Handler {
+                    on: handler.on,
+                    when,
+                    send,
+                })
+            })
+            .collect::<Result<_, RuleError>>()?;
+
+        Ok(Self {
+            name: repr.name,
+            handlers,
+        })
+    }
+}
+
+impl Serialize for Plugin {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let handlers = self
+            .handlers
+            .iter()
+            .map(|handler| HandlerRepr {
+                on: handler.on.clone(),
+                when: handler
+                    .when
+                    .as_ref()
+                    .map(|when| when.expression().to_string()),
+                send: handler
+                    .send
+                    .iter()
+                    .map(|send| SendRepr {
+                        command: send.command.clone(),
+                        args: send
+                            .args
+                            .iter()
+                            .map(|(name, arg)| (name.clone(), arg.expression().to_string()))
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        PluginRepr {
+            name: self.name.clone(),
+            handlers,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Plugin {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PluginRepr::deserialize(deserializer)?;
+
+        Self::try_from(repr).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The commands the plugins sent for an event, and the errors of any plugins which failed.
+/// A failing plugin doesn't stop the others.
+#[derive(Debug, PartialEq)]
+pub struct Dispatch<C> {
+    /// The commands, in the order the plugins were loaded
+    pub commands: Vec<C>,
+    pub errors: Vec<PluginError>,
+}
+
+impl<C> Default for Dispatch<C> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// The plugins loaded into the app, which can send it commands of type `C`.
+#[derive(Debug)]
+pub struct Plugins<C> {
+    plugins: Vec<Plugin>,
+    commands: PhantomData<fn() -> C>,
+}
+
+impl<C> Default for Plugins<C> {
+    fn default() -> Self {
+        Self {
+            plugins: Vec::new(),
+            commands: PhantomData,
+        }
+    }
+}
+
+impl<C> Plugins<C>
+where
+    C: DeserializeOwned,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the `plugin`, after the ones already loaded. Plugins are identified by name, and
+    /// loading a plugin with the same name as one already loaded fails.
+    pub fn load(&mut self, plugin: Plugin) -> Result<(), PluginError> {
+        if self.get(&plugin.name).is_some() {
+            return Err(PluginError::AlreadyLoaded {
+                plugin: plugin.name,
+            });
+        }
+
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
+    /// Unload the plugin called `name`, returning it if it was loaded.
+    pub fn unload(&mut self, name: &str) -> Option<Plugin> {
+        let index = self.plugins.iter().position(|plugin| plugin.name == name)?;
+
+        Some(self.plugins.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Plugin> {
+        self.plugins.iter().find(|plugin| plugin.name == name)
+    }
+
+    pub fn plugins(&self) -> impl Iterator<Item = &Plugin> {
+        self.plugins.iter()
+    }
+
+    /// Send the `event` to the plugins which handle it, with the `state` the app shares with
+    /// them, and collect the commands they send back.
+    ///
+    /// The event is identified by its name as serde serializes an enum variant, e.g.
+    /// `"checkoutOpened"` or `{ "itemAdded": { ... } }`, and its fields are the `event` in the
+    /// plugins' expressions. Commands which don't deserialize as a `C` aren't allowed, and are
+    /// reported as errors.
+    pub fn dispatch<E, S>(&self, event: &E, state: &S) -> Dispatch<C>
+    where
+        E: Serialize,
+        S: Serialize,
+    {
+        let mut dispatch = Dispatch::default();
+
+        let input = serde_json::to_value(event).and_then(|event| {
+            let (name, fields) = match event {
+                Value::String(name) => (name, Value::Null),
+                Value::Object(map) if map.len() == 1 => map.into_iter().next().unwrap(),
+                other => (String::new(), other),
+            };
+            let state = serde_json::to_value(state)?;
+
+            Ok((
+                name,
+                Value::Object(
+                    [("event".to_string(), fields), ("state".to_string(), state)]
+                        .into_iter()
+                        .collect(),
+                ),
+            ))
+        });
+        let (name, input) = match input {
+            Ok(input) => input,
+            Err(e) => {
+                dispatch.errors.push(PluginError::Input(e.to_string()));
+                return dispatch;
+            }
+        };
+
+        for plugin in &self.plugins {
+            let commands = plugin.handle(&name, &input).and_then(|commands| {
+                commands
+                    .into_iter()
+                    .map(|command| {
+                        serde_json::from_value(command).map_err(|e| PluginError::Command {
+                            plugin: plugin.name.clone(),
+                            message: e.to_string(),
+                        })
+                    })
+                    .collect::<Result<Vec<C>, _>>()
+            });
+
+            // a plugin's commands are sent all together or not at all
+            match commands {
+                Ok(commands) => dispatch.commands.extend(commands),
+                Err(error) => dispatch.errors.push(error),
+            }
+        }
+
+        dispatch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+    enum Event {
+        Opened,
+        Scanned { code: String, count: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+    enum Command {
+        Vibrate,
+        Show { message: String },
+    }
+
+    fn plugin(manifest: Value) -> Plugin {
+        serde_json::from_value(manifest).unwrap()
+    }
+
+    fn scanned(code: &str, count: u32) -> Event {
+        Event::Scanned {
+            code: code.to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn handlers_run_for_their_events_when_their_condition_holds() {
+        let mut plugins = Plugins::<Command>::new();
+        plugins
+            .load(plugin(json!({
+                "name": "scanner",
+                "handlers": [
+                    { "on": "opened", "send": [{ "command": "vibrate" }] },
+                    {
+                        "on": "scanned",
+                        "when": "event.code.startsWith(state.prefix)",
+                        "send": [{
+                            "command": "show",
+                            "args": { "message": "event.code + ' x' + (event.count > 1 ? 'many' : 'one')" }
+                        }]
+                    },
+                ]
+            })))
+            .unwrap();
+        let state = json!({ "prefix": "978" });
+
+        assert_eq!(
+            plugins.dispatch(&Event::Opened, &state).commands,
+            vec![Command::Vibrate]
+        );
+        assert_eq!(
+            plugins.dispatch(&scanned("9780", 2), &state),
+            Dispatch {
+                commands: vec![Command::Show {
+                    message: "9780 xmany".to_string()
+                }],
+                errors: vec![],
+            }
+        );
+        assert_eq!(
+            plugins.dispatch(&scanned("5000", 1), &state),
+            Dispatch::default()
+        );
+    }
+
+    #[test]
+    fn a_failing_plugin_doesnt_stop_the_others() {
+        let mut plugins = Plugins::<Command>::new();
+        for manifest in [
+            json!({
+                "name": "greedy",
+                "handlers": [{ "on": "opened", "send": [
+                    { "command": "vibrate" },
+                    { "command": "openUrl", "args": { "url": "'https://example.com'" } },
+                ]}]
+            }),
+            json!({
+                "name": "broken",
+                "handlers": [{ "on": "opened", "when": "state.missing.size() > 0" }]
+            }),
+            json!({
+                "name": "polite",
+                "handlers": [{ "on": "opened", "send": [{ "command": "vibrate" }] }]
+            }),
+        ] {
+            plugins.load(plugin(manifest)).unwrap();
+        }
+
+        let dispatch = plugins.dispatch(&Event::Opened, &json!({}));
+        assert_eq!(dispatch.commands, vec![Command::Vibrate]);
+
+        let failed: Vec<_> = dispatch
+            .errors
+            .iter()
+            .map(|error| match error {
+                PluginError::Command { plugin, .. } | PluginError::Rule { plugin, .. } => {
+                    plugin.as_str()
+                }
+                _ => "",
+            })
+            .collect();
+        assert_eq!(failed, vec!["greedy", "broken"]);
+    }
+
+    #[test]
+    fn plugins_are_loaded_once_and_can_be_unloaded() {
+        let mut plugins = Plugins::<Command>::new();
+        let manifest = json!({ "name": "a", "handlers": [] });
+
+        plugins.load(plugin(manifest.clone())).unwrap();
+        assert_eq!(
+            plugins.load(plugin(manifest.clone())),
+            Err(PluginError::AlreadyLoaded {
+                plugin: "a".to_string()
+            })
+        );
+
+        assert!(plugins.unload("a").is_some());
+        assert!(plugins.load(plugin(manifest)).is_ok());
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        let result = serde_json::from_value::<Plugin>(json!({
+            "name": "typo",
+            "handlers": [{ "on": "opened", "when": "state.count >" }]
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let manifest = json!({
+            "name": "scanner",
+            "handlers": [{
+                "on": "scanned",
+                "when": "event.count > 1",
+                "send": [{ "command": "show", "args": { "message": "event.code" } }]
+            }]
+        });
+
+        let serialized = serde_json::to_value(plugin(manifest.clone())).unwrap();
+        assert_eq!(serialized, manifest);
+    }
+}
@@ -0,0 +1,270 @@
+//! Marking the app's screens and events for session replay tools
+//!
+//! The [`SessionReplay`] capability sends structured breadcrumbs, the screens the user saw and
+//! the events they caused, to the shell while a session is being recorded, for the shell to pass
+//! on to its session replay SDK. Describing the session in the core rather than recording the
+//! pixels keeps the replays the same on every platform, and keeps out anything the core didn't
+//! choose to send.
+//!
+//! Recording is off until [`SessionReplay::start`] is called, and breadcrumbs marked before then,
+//! or after [`SessionReplay::stop`], are dropped. Property values are masked unless their key has
+//! been allowed with [`SessionReplay::set_allowed_properties`], and the parts of the screen to
+//! hide from any recording the shell does itself are sent as [`Redaction`]s, using the
+//! [`Sensitive`] view models from `crux_screenshot`.
+//!
+//! Marking is fire and forget: the shell doesn't respond, and the app isn't sent an event.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+use crux_core::capability::{Capability, CapabilityContext, Operation};
+pub use crux_screenshot::{Redaction, Region, Sensitive};
+use serde::{Deserialize, Serialize};
+
+/// The value sent in place of a property which hasn't been allowed
+pub const MASKED: &str = "***";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BreadcrumbKind {
+    /// The user was shown a screen
+    Screen,
+    /// The user did something, e.g. tapped a button
+    Event,
+}
+
+/// A mark in the session, in the order the core made it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Breadcrumb {
+    /// Counts up from zero in each recording
+    pub seq: u64,
+    pub kind: BreadcrumbKind,
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ReplayOperation {
+    /// Start recording a session, hiding the `redact`ed parts of the screen
+    Start {
+        redact: Vec<Redaction>,
+    },
+    /// Stop recording the session
+    Stop,
+    /// Hide these parts of the screen from now on, instead of the previous ones
+    Redact(Vec<Redaction>),
+    Breadcrumb(Breadcrumb),
+}
+
+impl Operation for ReplayOperation {
+    type Output = ();
+}
+
+#[derive(Default)]
+struct Recording {
+    recording: bool,
+    next_seq: u64,
+    allowed: BTreeSet<String>,
+}
+
+/// The SessionReplay capability API
+///
+/// This capability lets the core mark the screens and events of a recorded session for the
+/// shell's session replay tooling.
+pub struct SessionReplay<Ev> {
+    context: CapabilityContext<ReplayOperation, Ev>,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl<Ev> Clone for SessionReplay<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+            recording: self.recording.clone(),
+        }
+    }
+}
+
+impl<Ev> Capability<Ev> for SessionReplay<Ev> {
+    type Operation = ReplayOperation;
+    type MappedSelf<MappedEv> = SessionReplay<MappedEv>;
+
+    fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+    where
+        F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+        NewEv: 'static,
+    {
+        SessionReplay {
+            context: self.context.map_event(f),
+            recording: self.recording.clone(),
+        }
+    }
+}
+
+impl<Ev> SessionReplay<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<ReplayOperation, Ev>) -> Self {
+        Self {
+            context,
+            recording: Arc::default(),
+        }
+    }
+
+    /// Start recording a session, hiding the sensitive parts of the `view` from the start.
+    /// Does nothing if a session is already being recorded.
+    pub fn start(&self, view: &impl Sensitive) {
+        {
+            let mut recording = self.recording.lock().unwrap();
+            if recording.recording {
+                return;
+            }
+            recording.recording = true;
+            recording.next_seq = 0;
+        }
+
+        self.notify(ReplayOperation::Start {
+            redact: view.redactions(),
+        });
+    }
+
+    /// Stop recording the session. Does nothing if no session is being recorded.
+    pub fn stop(&self) {
+        if !std::mem::take(&mut self.recording.lock().unwrap().recording) {
+            return;
+        }
+
+        self.notify(ReplayOperation::Stop);
+    }
+
+    /// Whether a session is being recorded
+    pub fn is_recording(&self) -> bool {
+        self.recording.lock().unwrap().recording
+    }
+
+    /// Send the values of the properties with these `keys` as they are, masking all others.
+    /// The keys are shared by all the clones of the capability.
+    pub fn set_allowed_properties<I, K>(&self, keys: I)
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.recording.lock().unwrap().allowed = keys.into_iter().map(Into::into).collect();
+    }
+
+    /// Hide the sensitive parts of the `view` from now on, e.g. when the user moves to a new
+    /// screen.
+    pub fn redact(&self, view: &impl Sensitive) {
+        if !self.is_recording() {
+            return;
+        }
+
+        self.notify(ReplayOperation::Redact(view.redactions()));
+    }
+
+    /// Mark that the user was shown the screen called `name`.
+    pub fn screen(&self, name: impl Into<String>) {
+        self.mark(BreadcrumbKind::Screen, name.into(), BTreeMap::new());
+    }
+
+    /// Mark that the user caused the event called `name`, described by the `properties`.
+    /// Values of properties which haven't been allowed are sent as [`MASKED`].
+    pub fn event<I, K, V>(&self, name: impl Into<String>, properties: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let properties = properties
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+
+        self.mark(BreadcrumbKind::Event, name.into(), properties);
+    }
+
+    fn mark(&self, kind: BreadcrumbKind, name: String, properties: BTreeMap<String, String>) {
+        let breadcrumb = {
+            let mut recording = self.recording.lock().unwrap();
+            if !recording.recording {
+                return;
+            }
+
+            let properties = properties
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = if recording.allowed.contains(&key) {
+                        value
+                    } else {
+                        MASKED.to_string()
+                    };
+
+                    (key, value)
+                })
+                .collect();
+
+            let seq = recording.next_seq;
+            recording.next_seq += 1;
+
+            Breadcrumb {
+                seq,
+                kind,
+                name,
+                properties,
+            }
+        };
+
+        self.notify(ReplayOperation::Breadcrumb(breadcrumb));
+    }
+
+    fn notify(&self, operation: ReplayOperation) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move { context.notify_shell(operation).await }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = ReplayOperation::Breadcrumb(Breadcrumb {
+            seq: 3,
+            kind: BreadcrumbKind::Event,
+            name: "checkout".to_string(),
+            properties: [("items".to_string(), "2".to_string())].into(),
+        });
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"breadcrumb":{"seq":3,"kind":"event","name":"checkout","properties":{"items":"2"}}}"#
+        );
+
+        let deserialized: ReplayOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let operation = ReplayOperation::Start {
+            redact: vec![Redaction::Element("card-number".to_string())],
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"start":{"redact":[{"element":"card-number"}]}}"#
+        );
+
+        let deserialized: ReplayOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+    }
+}
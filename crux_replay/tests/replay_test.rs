@@ -0,0 +1,166 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_replay::{Redaction, Sensitive, SessionReplay};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        StartRecording,
+        StopRecording,
+        OpenCheckout,
+        Pay { amount: String, card: String },
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub screen: Screen,
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum Screen {
+        #[default]
+        Basket,
+        Checkout,
+    }
+
+    impl Sensitive for Screen {
+        fn redactions(&self) -> Vec<Redaction> {
+            match self {
+                Screen::Basket => vec![],
+                Screen::Checkout => vec![Redaction::Element("card-number".to_string())],
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub replay: SessionReplay<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::StartRecording => {
+                    caps.replay.set_allowed_properties(["amount"]);
+                    caps.replay.start(&model.screen);
+                }
+                Event::StopRecording => caps.replay.stop(),
+                Event::OpenCheckout => {
+                    model.screen = Screen::Checkout;
+                    caps.replay.redact(&model.screen);
+                    caps.replay.screen("checkout");
+                }
+                Event::Pay { amount, card } => {
+                    caps.replay
+                        .event("pay", [("amount", amount), ("card", card)]);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_replay::{Breadcrumb, BreadcrumbKind, Redaction, ReplayOperation, MASKED};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn marks(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Vec<ReplayOperation> {
+        app.update(event, model)
+            .into_effects()
+            .filter_map(Effect::into_replay)
+            .map(|request| request.operation)
+            .collect()
+    }
+
+    fn pay() -> Event {
+        Event::Pay {
+            amount: "12.50".to_string(),
+            card: "4111 1111 1111 1111".to_string(),
+        }
+    }
+
+    #[test]
+    fn nothing_is_sent_until_recording_starts() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        assert!(marks(&app, &mut model, Event::OpenCheckout).is_empty());
+        assert!(marks(&app, &mut model, pay()).is_empty());
+        assert!(marks(&app, &mut model, Event::StopRecording).is_empty());
+    }
+
+    #[test]
+    fn a_recording_is_described_by_breadcrumbs() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        assert_eq!(
+            marks(&app, &mut model, Event::StartRecording),
+            vec![ReplayOperation::Start { redact: vec![] }]
+        );
+        // starting again does nothing
+        assert!(marks(&app, &mut model, Event::StartRecording).is_empty());
+
+        assert_eq!(
+            marks(&app, &mut model, Event::OpenCheckout),
+            vec![
+                ReplayOperation::Redact(vec![Redaction::Element("card-number".to_string())]),
+                ReplayOperation::Breadcrumb(Breadcrumb {
+                    seq: 0,
+                    kind: BreadcrumbKind::Screen,
+                    name: "checkout".to_string(),
+                    properties: Default::default(),
+                }),
+            ]
+        );
+
+        // the card number isn't an allowed property
+        assert_eq!(
+            marks(&app, &mut model, pay()),
+            vec![ReplayOperation::Breadcrumb(Breadcrumb {
+                seq: 1,
+                kind: BreadcrumbKind::Event,
+                name: "pay".to_string(),
+                properties: [
+                    ("amount".to_string(), "12.50".to_string()),
+                    ("card".to_string(), MASKED.to_string()),
+                ]
+                .into(),
+            })]
+        );
+
+        assert_eq!(
+            marks(&app, &mut model, Event::StopRecording),
+            vec![ReplayOperation::Stop]
+        );
+        assert!(marks(&app, &mut model, pay()).is_empty());
+
+        // a new recording starts counting again, hiding the current screen straight away
+        assert_eq!(
+            marks(&app, &mut model, Event::StartRecording),
+            vec![ReplayOperation::Start {
+                redact: vec![Redaction::Element("card-number".to_string())]
+            }]
+        );
+        let marked = marks(&app, &mut model, pay());
+        let [ReplayOperation::Breadcrumb(breadcrumb)] = marked.as_slice() else {
+            panic!("expected a breadcrumb");
+        };
+        assert_eq!(breadcrumb.seq, 0);
+    }
+}
@@ -0,0 +1,314 @@
+//! Evaluating an [`Expr`] against a model, within a budget of steps
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::{
+    parse::{BinaryOp, Expr, Method, UnaryOp},
+    RuleError,
+};
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "map",
+    }
+}
+
+fn type_error(operation: &str, values: &[&Value]) -> RuleError {
+    let types: Vec<_> = values.iter().map(|value| type_name(value)).collect();
+    RuleError::Type(format!("can't {operation} {}", types.join(" and ")))
+}
+
+/// Equality, with numbers compared by value, so that `1 == 1.0`
+fn equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => match (left.as_i64(), right.as_i64()) {
+            (Some(left), Some(right)) => left == right,
+            _ => left.as_f64() == right.as_f64(),
+        },
+        (Value::Array(left), Value::Array(right)) => {
+            left.len() == right.len() && left.iter().zip(right).all(|(l, r)| equal(l, r))
+        }
+        (Value::Object(left), Value::Object(right)) => {
+            left.len() == right.len()
+                && left
+                    .iter()
+                    .all(|(key, l)| right.get(key).map_or(false, |r| equal(l, r)))
+        }
+        _ => left == right,
+    }
+}
+
+fn compare(op: BinaryOp, left: &Value, right: &Value) -> Result<bool, RuleError> {
+    let ordering = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => match (l.as_i64(), r.as_i64()) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            _ => l
+                .as_f64()
+                .zip(r.as_f64())
+                .and_then(|(l, r)| l.partial_cmp(&r)),
+        },
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => return Err(type_error("compare", &[left, right])),
+    };
+    let Some(ordering) = ordering else {
+        return Ok(false);
+    };
+
+    Ok(match op {
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Le => ordering.is_le(),
+        BinaryOp::Gt => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    })
+}
+
+fn arithmetic(op: BinaryOp, left: &Value, right: &Value) -> Result<Value, RuleError> {
+    let (Value::Number(l), Value::Number(r)) = (left, right) else {
+        return Err(type_error("do arithmetic on", &[left, right]));
+    };
+
+    if let (Some(l), Some(r)) = (l.as_i64(), r.as_i64()) {
+        if r == 0 && matches!(op, BinaryOp::Div | BinaryOp::Rem) {
+            return Err(RuleError::DivisionByZero);
+        }
+
+        let result = match op {
+            BinaryOp::Add => Some(l.checked_add(r)),
+            BinaryOp::Sub => Some(l.checked_sub(r)),
+            BinaryOp::Mul => Some(l.checked_mul(r)),
+            BinaryOp::Rem => Some(l.checked_rem(r)),
+            // division which isn't exact gives a decimal
+            _ if l.checked_rem(r) == Some(0) => Some(l.checked_div(r)),
+            _ => None,
+        };
+        if let Some(result) = result {
+            return result.map(Value::from).ok_or(RuleError::Overflow);
+        }
+    }
+
+    let (l, r) = (
+        l.as_f64().unwrap_or_default(),
+        r.as_f64().unwrap_or_default(),
+    );
+    if r == 0.0 && matches!(op, BinaryOp::Div | BinaryOp::Rem) {
+        return Err(RuleError::DivisionByZero);
+    }
+    let result = match op {
+        BinaryOp::Add => l + r,
+        BinaryOp::Sub => l - r,
+        BinaryOp::Mul => l * r,
+        BinaryOp::Div => l / r,
+        _ => l % r,
+    };
+
+    serde_json::Number::from_f64(result)
+        .map(Value::Number)
+        .ok_or(RuleError::Overflow)
+}
+
+pub(crate) struct Evaluator<'a> {
+    model: &'a Value,
+    steps_left: u64,
+}
+
+impl<'a> Evaluator<'a> {
+    pub(crate) fn new(model: &'a Value, max_steps: u64) -> Self {
+        Self {
+            model,
+            steps_left: max_steps,
+        }
+    }
+
+    fn step(&mut self, cost: usize) -> Result<(), RuleError> {
+        let cost = u64::try_from(cost).unwrap_or(u64::MAX);
+        self.steps_left = self
+            .steps_left
+            .checked_sub(cost)
+            .ok_or(RuleError::BudgetExceeded)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn eval(&mut self, expr: &Expr) -> Result<Cow<'a, Value>, RuleError> {
+        self.step(1)?;
+
+        match expr {
+            Expr::Literal(value) => Ok(Cow::Owned(value.clone())),
+            Expr::List(items) => items
+                .iter()
+                .map(|item| self.eval(item).map(Cow::into_owned))
+                .collect::<Result<_, _>>()
+                .map(|items| Cow::Owned(Value::Array(items))),
+            Expr::Ident(name) => field(Cow::Borrowed(self.model), name),
+            Expr::Field(target, name) => {
+                let target = self.eval(target)?;
+                field(target, name)
+            }
+            Expr::Index(target, index) => {
+                let target = self.eval(target)?;
+                let index = self.eval(index)?;
+                element(target, &index)
+            }
+            Expr::Call(target, method, args) => {
+                let target = self.eval(target)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(&target, *method, &args).map(Cow::Owned)
+            }
+            Expr::Unary(op, operand) => {
+                let operand = self.eval(operand)?;
+                let result = match (op, operand.as_ref()) {
+                    (UnaryOp::Not, Value::Bool(value)) => Value::Bool(!value),
+                    (UnaryOp::Neg, Value::Number(n)) => match n.as_i64() {
+                        Some(n) => Value::from(n.checked_neg().ok_or(RuleError::Overflow)?),
+                        None => Value::from(-n.as_f64().unwrap_or_default()),
+                    },
+                    (UnaryOp::Not, value) => return Err(type_error("negate", &[value])),
+                    (UnaryOp::Neg, value) => return Err(type_error("negate", &[value])),
+                };
+                Ok(Cow::Owned(result))
+            }
+            Expr::Binary(op @ (BinaryOp::And | BinaryOp::Or), left, right) => {
+                let short_circuit = *op == BinaryOp::Or;
+                if self.condition(left, "combine")? == short_circuit {
+                    return Ok(Cow::Owned(Value::Bool(short_circuit)));
+                }
+
+                self.condition(right, "combine")
+                    .map(|value| Cow::Owned(Value::Bool(value)))
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                self.binary(*op, &left, &right).map(Cow::Owned)
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.condition(condition, "choose with")? {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+        }
+    }
+
+    fn condition(&mut self, expr: &Expr, operation: &str) -> Result<bool, RuleError> {
+        match self.eval(expr)?.as_ref() {
+            Value::Bool(value) => Ok(*value),
+            value => Err(type_error(operation, &[value])),
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: &Value, right: &Value) -> Result<Value, RuleError> {
+        let result = match (op, left, right) {
+            (BinaryOp::Eq, _, _) => Value::Bool(equal(left, right)),
+            (BinaryOp::Ne, _, _) => Value::Bool(!equal(left, right)),
+            (BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, _, _) => {
+                Value::Bool(compare(op, left, right)?)
+            }
+            (BinaryOp::In, _, _) => Value::Bool(self.contains(right, left)?),
+            (BinaryOp::Add, Value::String(l), Value::String(r)) => {
+                self.step(l.len() + r.len())?;
+                Value::String(format!("{l}{r}"))
+            }
+            (BinaryOp::Add, Value::Array(l), Value::Array(r)) => {
+                self.step(l.len() + r.len())?;
+                Value::Array(l.iter().chain(r).cloned().collect())
+            }
+            _ => arithmetic(op, left, right)?,
+        };
+
+        Ok(result)
+    }
+
+    fn contains(&mut self, haystack: &Value, needle: &Value) -> Result<bool, RuleError> {
+        match (haystack, needle) {
+            (Value::Array(items), _) => {
+                self.step(items.len())?;
+                Ok(items.iter().any(|item| equal(item, needle)))
+            }
+            (Value::String(text), Value::String(part)) => {
+                self.step(text.len())?;
+                Ok(text.contains(part.as_str()))
+            }
+            (Value::Object(map), Value::String(key)) => Ok(map.contains_key(key)),
+            _ => Err(type_error("look in", &[haystack, needle])),
+        }
+    }
+
+    fn call(
+        &mut self,
+        target: &Value,
+        method: Method,
+        args: &[Cow<Value>],
+    ) -> Result<Value, RuleError> {
+        let result = match (method, target, args) {
+            (Method::Size, Value::String(text), []) => Value::from(text.chars().count()),
+            (Method::Size, Value::Array(items), []) => Value::from(items.len()),
+            (Method::Size, Value::Object(map), []) => Value::from(map.len()),
+            (Method::Contains, _, [needle]) => Value::Bool(self.contains(target, needle)?),
+            (Method::StartsWith, Value::String(text), [arg]) => match arg.as_ref() {
+                Value::String(prefix) => Value::Bool(text.starts_with(prefix.as_str())),
+                value => return Err(type_error("call startsWith with", &[value])),
+            },
+            (Method::EndsWith, Value::String(text), [arg]) => match arg.as_ref() {
+                Value::String(suffix) => Value::Bool(text.ends_with(suffix.as_str())),
+                value => return Err(type_error("call endsWith with", &[value])),
+            },
+            _ => return Err(type_error(&format!("call {method:?} on"), &[target])),
+        };
+
+        Ok(result)
+    }
+}
+
+/// A field of a map, `null` for a field which is missing or a field of `null`, so that
+/// optional parts of the model can be checked with `== null`
+fn field<'a>(target: Cow<'a, Value>, name: &str) -> Result<Cow<'a, Value>, RuleError> {
+    match target {
+        Cow::Borrowed(Value::Object(map)) => {
+            Ok(map.get(name).map_or(Cow::Owned(Value::Null), Cow::Borrowed))
+        }
+        Cow::Owned(Value::Object(mut map)) => {
+            Ok(Cow::Owned(map.remove(name).unwrap_or(Value::Null)))
+        }
+        Cow::Borrowed(Value::Null) | Cow::Owned(Value::Null) => Ok(Cow::Owned(Value::Null)),
+        target => Err(type_error(&format!("read `{name}` from"), &[&target])),
+    }
+}
+
+fn element<'a>(target: Cow<'a, Value>, index: &Value) -> Result<Cow<'a, Value>, RuleError> {
+    match (target, index) {
+        (Cow::Borrowed(Value::Array(items)), Value::Number(n)) => {
+            let i = n.as_u64().ok_or(RuleError::IndexOutOfRange)?;
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i))
+                .map(Cow::Borrowed)
+                .ok_or(RuleError::IndexOutOfRange)
+        }
+        (Cow::Owned(Value::Array(items)), Value::Number(n)) => {
+            let i = n.as_u64().ok_or(RuleError::IndexOutOfRange)?;
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| items.into_iter().nth(i))
+                .map(Cow::Owned)
+                .ok_or(RuleError::IndexOutOfRange)
+        }
+        (
+            target @ (Cow::Borrowed(Value::Object(_) | Value::Null)
+            | Cow::Owned(Value::Object(_) | Value::Null)),
+            Value::String(key),
+        ) => field(target, key),
+        (target, index) => Err(type_error("index", &[&target, index])),
+    }
+}
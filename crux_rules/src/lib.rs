@@ -0,0 +1,416 @@
+//! Business rules delivered by a server and evaluated in the core
+//!
+//! Merchandising and eligibility rules, like who gets free shipping or which banner to show,
+//! often change faster than apps are released. This crate evaluates rules written in a small
+//! expression language against the app's model, so that they can be delivered as data, e.g.
+//! with the rest of the app's configuration, instead of being compiled into the core.
+//!
+//! Evaluating a rule is safe whatever the rule says: expressions can only read the model and
+//! compute a value, with no way to perform I/O or call into the app, and they're limited in
+//! length and nesting when parsed, and in the number of steps taken when evaluated (see
+//! [`Limits`]), so that a rule can't hang the core. Rules are deterministic: the same model
+//! always gives the same result.
+//!
+//! ```
+//! use crux_rules::{RuleSet, Value};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Model {
+//!     country: String,
+//!     basket: Vec<Item>,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Item {
+//!     price: u32,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let rules: RuleSet = serde_json::from_str(r#"[
+//!     { "name": "freeShipping", "expression": "country in ['GB', 'IE'] && basket.size() >= 2" },
+//!     { "name": "banner", "expression": "'sale' in basket[0].tags ? 'summer-sale' : null" }
+//! ]"#)?;
+//!
+//! let model = Model {
+//!     country: "GB".to_string(),
+//!     basket: vec![
+//!         Item { price: 1200, tags: vec!["sale".to_string()] },
+//!         Item { price: 800, tags: vec![] },
+//!     ],
+//! };
+//!
+//! assert!(rules.matches("freeShipping", &model));
+//! assert_eq!(rules.evaluate("banner", &model)?, Value::from("summer-sale"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## The expression language
+//!
+//! The language is a small subset of the [Common Expression Language](https://cel.dev):
+//!
+//! * literals: numbers (`10`, `2.5`), strings (`"a"` or `'a'`), `true`, `false`, `null` and
+//!   lists (`[1, 2]`)
+//! * the fields of the model as it's serialized, e.g. `user.address.city`, `items[0]` or
+//!   `prices['GBP']`. Missing fields, and fields of `null`, are `null`
+//! * `+`, `-`, `*`, `/` and `%` on numbers, `+` on strings and lists
+//! * `==`, `!=`, `<`, `<=`, `>`, `>=`, and `in` for items of lists, substrings and keys of maps
+//! * `!`, `&&` and `||` on booleans, and `condition ? a : b`
+//! * `.size()`, `.contains(x)`, `.startsWith(s)` and `.endsWith(s)`
+
+mod eval;
+mod parse;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use serde_json::Value;
+
+use eval::Evaluator;
+use parse::Expr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RuleError {
+    #[error("invalid expression at {position}: {message}")]
+    Parse { position: usize, message: String },
+    #[error("expression is too long or too deeply nested")]
+    TooComplex,
+    #[error("type error: {0}")]
+    Type(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("index out of range")]
+    IndexOutOfRange,
+    #[error("evaluation took too many steps")]
+    BudgetExceeded,
+    #[error("no rule called {0}")]
+    UnknownRule(String),
+    #[error("the model couldn't be serialized: {0}")]
+    Model(String),
+}
+
+/// The most work a rule can do, so that a rule delivered by a server can't hang the core.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The longest expression, in characters
+    pub max_length: usize,
+    /// The deepest nesting of an expression, e.g. `(1 + (2 * 3))` is nested three deep
+    pub max_depth: usize,
+    /// The most steps an evaluation can take. Each part of the expression is a step, and
+    /// working through a string or a list, e.g. for `in`, takes a step for each item.
+    pub max_steps: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_length: 4_096,
+            max_depth: 64,
+            max_steps: 10_000,
+        }
+    }
+}
+
+/// A named expression, parsed and ready to evaluate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    name: String,
+    source: String,
+    expr: Expr,
+    limits: Limits,
+}
+
+impl Rule {
+    /// Parse the `expression` of the rule called `name`, within the default [`Limits`].
+    pub fn new(name: impl Into<String>, expression: impl Into<String>) -> Result<Self, RuleError> {
+        Self::with_limits(name, expression, Limits::default())
+    }
+
+    /// Parse the `expression` of the rule called `name`, within the `limits`.
+    pub fn with_limits(
+        name: impl Into<String>,
+        expression: impl Into<String>,
+        limits: Limits,
+    ) -> Result<Self, RuleError> {
+        let source = expression.into();
+        let expr = parse::parse(&source, &limits)?;
+
+        Ok(Self {
+            name: name.into(),
+            source,
+            expr,
+            limits,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the rule against the `model`, as it's serialized.
+    pub fn evaluate<M: Serialize>(&self, model: &M) -> Result<Value, RuleError> {
+        self.evaluate_value(&to_value(model)?)
+    }
+
+    /// Whether the rule is true for the `model`. Rules which don't evaluate to a boolean
+    /// are a [`RuleError::Type`].
+    pub fn is_true<M: Serialize>(&self, model: &M) -> Result<bool, RuleError> {
+        match self.evaluate(model)? {
+            Value::Bool(value) => Ok(value),
+            _ => Err(RuleError::Type(format!(
+                "rule {} isn't a condition",
+                self.name
+            ))),
+        }
+    }
+
+    /// Evaluate the rule against a model which has already been serialized, e.g. to evaluate
+    /// many rules against the same model.
+    pub fn evaluate_value(&self, model: &Value) -> Result<Value, RuleError> {
+        Evaluator::new(model, self.limits.max_steps)
+            .eval(&self.expr)
+            .map(std::borrow::Cow::into_owned)
+    }
+}
+
+impl Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr {
+            name: self.name.clone(),
+            expression: self.source.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        Self::new(repr.name, repr.expression).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Repr {
+    name: String,
+    expression: String,
+}
+
+/// Rules looked up by name, which deserialize from a list of `{ "name", "expression" }`
+/// objects, failing if any of the expressions is invalid.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn new(rules: impl IntoIterator<Item = Rule>) -> Self {
+        Self {
+            rules: rules.into_iter().collect(),
+        }
+    }
+
+    /// The rule called `name`. If there's more than one, the last one wins, so a rule can be
+    /// overridden by adding another with the same name.
+    pub fn get(&self, name: &str) -> Option<&Rule> {
+        self.rules.iter().rev().find(|rule| rule.name == name)
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
+    /// Evaluate the rule called `name` against the `model`.
+    pub fn evaluate<M: Serialize>(&self, name: &str, model: &M) -> Result<Value, RuleError> {
+        self.get(name)
+            .ok_or_else(|| RuleError::UnknownRule(name.to_string()))?
+            .evaluate(model)
+    }
+
+    /// Whether the rule called `name` is true for the `model`. Rules which are missing, fail
+    /// or aren't a condition are treated as false, so an unexpected rule turns a feature off.
+    pub fn matches<M: Serialize>(&self, name: &str, model: &M) -> bool {
+        self.get(name)
+            .map_or(false, |rule| rule.is_true(model).unwrap_or(false))
+    }
+
+    /// The names of the rules which are true for the `model`, in order, serializing the
+    /// model once.
+    pub fn matching<M: Serialize>(&self, model: &M) -> Result<Vec<&str>, RuleError> {
+        let model = to_value(model)?;
+
+        Ok(self
+            .rules
+            .iter()
+            // skipping the rules overridden by a later one with the same name
+            .filter(|rule| {
+                self.get(&rule.name)
+                    .map_or(false, |last| std::ptr::eq(last, *rule))
+            })
+            .filter(|rule| rule.evaluate_value(&model) == Ok(Value::Bool(true)))
+            .map(Rule::name)
+            .collect())
+    }
+}
+
+fn to_value<M: Serialize>(model: &M) -> Result<Value, RuleError> {
+    serde_json::to_value(model).map_err(|e| RuleError::Model(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn eval(expression: &str, model: &Value) -> Result<Value, RuleError> {
+        Rule::new("test", expression)?.evaluate(model)
+    }
+
+    #[test]
+    fn evaluates_against_the_model() {
+        let model = json!({
+            "user": { "age": 34, "tier": "gold", "tags": ["beta"] },
+            "basket": [{ "price": 12.5 }, { "price": 7.5 }],
+            "prices": { "GBP": 10 },
+        });
+
+        let cases = [
+            ("user.age >= 18 && user.tier == 'gold'", json!(true)),
+            ("basket[0].price + basket[1].price", json!(20.0)),
+            ("basket.size() * 2 + 1", json!(5)),
+            ("7 / 2", json!(3.5)),
+            ("6 / 2 == 3", json!(true)),
+            ("1 == 1.0", json!(true)),
+            ("'beta' in user.tags", json!(true)),
+            ("'GBP' in prices && prices['GBP'] > 5", json!(true)),
+            (
+                "user.tier.startsWith('go') ? 'vip' : 'regular'",
+                json!("vip"),
+            ),
+            ("user.address.city == null", json!(true)),
+            ("[1, 2] + [3]", json!([1, 2, 3])),
+            ("'a' + \"b\" < 'b'", json!(true)),
+            ("-user.age % 5", json!(-4)),
+        ];
+
+        for (expression, expected) in cases {
+            assert_eq!(eval(expression, &model), Ok(expected), "{expression}");
+        }
+    }
+
+    #[test]
+    fn conditions_short_circuit() {
+        let model = json!({ "user": null });
+
+        assert_eq!(
+            eval("user != null && user.age > 18", &model),
+            Ok(json!(false))
+        );
+        assert_eq!(eval("true || 1 / 0", &model), Ok(json!(true)));
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let model = json!({ "name": "Ada", "items": [1] });
+
+        assert_eq!(
+            eval("name > 1", &model),
+            Err(RuleError::Type(
+                "can't compare string and number".to_string()
+            ))
+        );
+        assert_eq!(eval("1 / 0", &model), Err(RuleError::DivisionByZero));
+        assert_eq!(
+            eval("9223372036854775807 + 1", &model),
+            Err(RuleError::Overflow)
+        );
+        assert_eq!(eval("items[1]", &model), Err(RuleError::IndexOutOfRange));
+        assert_eq!(
+            eval("name.age", &model),
+            Err(RuleError::Type("can't read `age` from string".to_string()))
+        );
+        assert_eq!(
+            eval("name && true", &model),
+            Err(RuleError::Type("can't combine string".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluation_is_limited_in_steps() {
+        let model = json!({ "ids": (0..20_000).collect::<Vec<_>>() });
+
+        assert_eq!(eval("-1 in ids", &model), Err(RuleError::BudgetExceeded));
+
+        let rule = Rule::with_limits(
+            "test",
+            "1 + 1 + 1",
+            Limits {
+                max_steps: 4,
+                ..Limits::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(rule.evaluate(&model), Err(RuleError::BudgetExceeded));
+    }
+
+    #[test]
+    fn rule_sets_deserialize_and_match() {
+        let rules: RuleSet = serde_json::from_value(json!([
+            { "name": "adult", "expression": "age >= 18" },
+            { "name": "discount", "expression": "age < 25 ? 10 : 0" },
+            { "name": "senior", "expression": "age >= 18" },
+            { "name": "broken", "expression": "age.size()" },
+            { "name": "senior", "expression": "age >= 65" },
+        ]))
+        .unwrap();
+        let model = json!({ "age": 62 });
+
+        assert_eq!(rules.matching(&model), Ok(vec!["adult"]));
+        assert!(!rules.matches("senior", &model));
+        assert!(rules.matches("adult", &model));
+        assert!(!rules.matches("broken", &model));
+        assert!(!rules.matches("discount", &model));
+        assert!(!rules.matches("missing", &model));
+        assert_eq!(rules.evaluate("discount", &model), Ok(json!(0)));
+        assert_eq!(
+            rules.evaluate("missing", &model),
+            Err(RuleError::UnknownRule("missing".to_string()))
+        );
+
+        let invalid = serde_json::from_value::<RuleSet>(json!([
+            { "name": "adult", "expression": "age >=" },
+        ]));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let rule = Rule::new("adult", "age >= 18").unwrap();
+
+        let serialized = serde_json::to_string(&RuleSet::new([rule])).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"[{"name":"adult","expression":"age >= 18"}]"#
+        );
+
+        let error = RuleError::Parse {
+            position: 3,
+            message: "expected a value".to_string(),
+        };
+        let serialized = serde_json::to_string(&error).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"parse":{"position":3,"message":"expected a value"}}"#
+        );
+    }
+}
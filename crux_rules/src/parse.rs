@@ -0,0 +1,607 @@
+//! Parsing rule expressions into an [`Expr`] tree
+
+use serde_json::{Number, Value};
+
+use crate::{Limits, RuleError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// The functions which can be called on a value, e.g. `name.startsWith("A")`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Method {
+    Size,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl Method {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "size" => Some(Self::Size),
+            "contains" => Some(Self::Contains),
+            "startsWith" => Some(Self::StartsWith),
+            "endsWith" => Some(Self::EndsWith),
+            _ => None,
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Self::Size => 0,
+            Self::Contains | Self::StartsWith | Self::EndsWith => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expr {
+    Literal(Value),
+    List(Vec<Expr>),
+    /// A field of the model
+    Ident(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Call(Box<Expr>, Method, Vec<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// The depth of the tree, worked out without recursion so that it's safe to call on any tree
+    fn depth(&self) -> usize {
+        let mut deepest = 0;
+        let mut stack = vec![(self, 1)];
+        while let Some((expr, depth)) = stack.pop() {
+            deepest = deepest.max(depth);
+            let children: Vec<&Expr> = match expr {
+                Expr::Literal(_) | Expr::Ident(_) => vec![],
+                Expr::List(items) => items.iter().collect(),
+                Expr::Field(target, _) | Expr::Unary(_, target) => vec![target],
+                Expr::Index(target, index) => vec![target, index],
+                Expr::Call(target, _, args) => std::iter::once(&**target).chain(args).collect(),
+                Expr::Binary(_, left, right) => vec![left, right],
+                Expr::Conditional(condition, then, otherwise) => {
+                    vec![condition, then, otherwise]
+                }
+            };
+            stack.extend(children.into_iter().map(|child| (child, depth + 1)));
+        }
+
+        deepest
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(Number),
+    Str(String),
+    Ident(String),
+    True,
+    False,
+    Null,
+    In,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Comma,
+    Question,
+    Colon,
+    Bang,
+    EqEq,
+    BangEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    AndAnd,
+    OrOr,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+}
+
+fn error(position: usize, message: impl Into<String>) -> RuleError {
+    RuleError::Parse {
+        position,
+        message: message.into(),
+    }
+}
+
+/// Split the source into tokens, each with its position in characters.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, RuleError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('0'..='9', _) => {
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let is_float = chars.get(i) == Some(&'.')
+                    && chars.get(i + 1).map_or(false, char::is_ascii_digit);
+                if is_float {
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+
+                let text: String = chars[start..i].iter().collect();
+                let number = if is_float {
+                    text.parse::<f64>().ok().and_then(Number::from_f64)
+                } else {
+                    text.parse::<i64>().ok().map(Number::from)
+                };
+                tokens.push((
+                    start,
+                    Token::Number(number.ok_or_else(|| error(start, "number out of range"))?),
+                ));
+                continue;
+            }
+            ('"' | '\'', _) => {
+                let quote = c;
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error(start, "unterminated string")),
+                        Some(&c) if c == quote => break,
+                        Some('\\') => {
+                            let escaped = match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some(&c @ ('\\' | '"' | '\'')) => c,
+                                _ => return Err(error(i, "invalid escape")),
+                            };
+                            text.push(escaped);
+                            i += 2;
+                        }
+                        Some(&c) => {
+                            text.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                tokens.push((start, Token::Str(text)));
+                continue;
+            }
+            (c, _) if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+
+                let word: String = chars[start..i].iter().collect();
+                let token = match word.as_str() {
+                    "true" => Token::True,
+                    "false" => Token::False,
+                    "null" => Token::Null,
+                    "in" => Token::In,
+                    _ => Token::Ident(word),
+                };
+                tokens.push((start, token));
+                continue;
+            }
+            ('=', Some('=')) => Token::EqEq,
+            ('!', Some('=')) => Token::BangEq,
+            ('<', Some('=')) => Token::Le,
+            ('>', Some('=')) => Token::Ge,
+            ('&', Some('&')) => Token::AndAnd,
+            ('|', Some('|')) => Token::OrOr,
+            ('(', _) => Token::LParen,
+            (')', _) => Token::RParen,
+            ('[', _) => Token::LBracket,
+            (']', _) => Token::RBracket,
+            ('.', _) => Token::Dot,
+            (',', _) => Token::Comma,
+            ('?', _) => Token::Question,
+            (':', _) => Token::Colon,
+            ('!', _) => Token::Bang,
+            ('<', _) => Token::Lt,
+            ('>', _) => Token::Gt,
+            ('+', _) => Token::Plus,
+            ('-', _) => Token::Minus,
+            ('*', _) => Token::Star,
+            ('/', _) => Token::Slash,
+            ('%', _) => Token::Percent,
+            (c, _) => return Err(error(start, format!("unexpected character `{c}`"))),
+        };
+
+        i += match token {
+            Token::EqEq | Token::BangEq | Token::Le | Token::Ge | Token::AndAnd | Token::OrOr => 2,
+            _ => 1,
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+/// Parse the `source` of an expression, checking it's within the `limits`.
+pub(crate) fn parse(source: &str, limits: &Limits) -> Result<Expr, RuleError> {
+    if source.chars().count() > limits.max_length {
+        return Err(RuleError::TooComplex);
+    }
+
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        end: source.chars().count(),
+        depth: 0,
+        max_depth: limits.max_depth,
+    };
+
+    let expr = parser.expression()?;
+    if let Some((position, _)) = parser.tokens.get(parser.position) {
+        return Err(error(*position, "expected the end of the expression"));
+    }
+    if expr.depth() > limits.max_depth {
+        return Err(RuleError::TooComplex);
+    }
+
+    Ok(expr)
+}
+
+/// A recursive descent parser, from the lowest precedence to the highest
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// The length of the source, where errors at the end are reported
+    end: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, what: &str) -> Result<(), RuleError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(error(self.offset(), format!("expected {what}")))
+        }
+    }
+
+    /// Guard against input nested deeply enough to overflow the parser's stack
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, RuleError>,
+    ) -> Result<T, RuleError> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return Err(RuleError::TooComplex);
+        }
+        let result = parse(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn expression(&mut self) -> Result<Expr, RuleError> {
+        self.nested(|parser| {
+            let condition = parser.or()?;
+            if !parser.eat(&Token::Question) {
+                return Ok(condition);
+            }
+
+            let then = parser.expression()?;
+            parser.expect(&Token::Colon, "`:`")?;
+            let otherwise = parser.expression()?;
+
+            Ok(Expr::Conditional(
+                Box::new(condition),
+                Box::new(then),
+                Box::new(otherwise),
+            ))
+        })
+    }
+
+    fn binary(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Expr, RuleError>,
+        operator: fn(&Token) -> Option<BinaryOp>,
+    ) -> Result<Expr, RuleError> {
+        let mut left = operand(self)?;
+        while let Some(op) = self.peek().and_then(operator) {
+            self.position += 1;
+            let right = operand(self)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, RuleError> {
+        self.binary(Self::and, |token| {
+            (token == &Token::OrOr).then_some(BinaryOp::Or)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, RuleError> {
+        self.binary(Self::comparison, |token| {
+            (token == &Token::AndAnd).then_some(BinaryOp::And)
+        })
+    }
+
+    fn comparison(&mut self) -> Result<Expr, RuleError> {
+        let left = self.sum()?;
+        let op = match self.peek() {
+            Some(Token::EqEq) => BinaryOp::Eq,
+            Some(Token::BangEq) => BinaryOp::Ne,
+            Some(Token::Lt) => BinaryOp::Lt,
+            Some(Token::Le) => BinaryOp::Le,
+            Some(Token::Gt) => BinaryOp::Gt,
+            Some(Token::Ge) => BinaryOp::Ge,
+            Some(Token::In) => BinaryOp::In,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        let right = self.sum()?;
+
+        Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, RuleError> {
+        self.binary(Self::product, |token| match token {
+            Token::Plus => Some(BinaryOp::Add),
+            Token::Minus => Some(BinaryOp::Sub),
+            _ => None,
+        })
+    }
+
+    fn product(&mut self) -> Result<Expr, RuleError> {
+        self.binary(Self::unary, |token| match token {
+            Token::Star => Some(BinaryOp::Mul),
+            Token::Slash => Some(BinaryOp::Div),
+            Token::Percent => Some(BinaryOp::Rem),
+            _ => None,
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, RuleError> {
+        let op = if self.eat(&Token::Bang) {
+            UnaryOp::Not
+        } else if self.eat(&Token::Minus) {
+            UnaryOp::Neg
+        } else {
+            return self.postfix();
+        };
+
+        let operand = self.nested(Self::unary)?;
+        Ok(Expr::Unary(op, Box::new(operand)))
+    }
+
+    fn postfix(&mut self) -> Result<Expr, RuleError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat(&Token::Dot) {
+                let offset = self.offset();
+                let Some(Token::Ident(name)) = self.peek().cloned() else {
+                    return Err(error(offset, "expected a field name"));
+                };
+                self.position += 1;
+
+                if !self.eat(&Token::LParen) {
+                    expr = Expr::Field(Box::new(expr), name);
+                    continue;
+                }
+
+                let method = Method::from_name(&name)
+                    .ok_or_else(|| error(offset, format!("unknown function `{name}`")))?;
+                let args = self.list(&Token::RParen, "`)`")?;
+                if args.len() != method.arity() {
+                    return Err(error(
+                        offset,
+                        format!("`{name}` takes {} argument(s)", method.arity()),
+                    ));
+                }
+                expr = Expr::Call(Box::new(expr), method, args);
+            } else if self.eat(&Token::LBracket) {
+                let index = self.expression()?;
+                self.expect(&Token::RBracket, "`]`")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Comma separated expressions, up to the `close`ing token
+    fn list(&mut self, close: &Token, what: &str) -> Result<Vec<Expr>, RuleError> {
+        let mut items = Vec::new();
+        if self.eat(close) {
+            return Ok(items);
+        }
+
+        loop {
+            items.push(self.expression()?);
+            if self.eat(close) {
+                return Ok(items);
+            }
+            self.expect(&Token::Comma, &format!("`,` or {what}"))?;
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, RuleError> {
+        let offset = self.offset();
+        let Some(token) = self.peek().cloned() else {
+            return Err(error(offset, "unexpected end of the expression"));
+        };
+        self.position += 1;
+
+        let expr = match token {
+            Token::Number(number) => Expr::Literal(Value::Number(number)),
+            Token::Str(text) => Expr::Literal(Value::String(text)),
+            Token::True => Expr::Literal(Value::Bool(true)),
+            Token::False => Expr::Literal(Value::Bool(false)),
+            Token::Null => Expr::Literal(Value::Null),
+            Token::Ident(name) => {
+                if self.peek() == Some(&Token::LParen) {
+                    return Err(error(offset, format!("unknown function `{name}`")));
+                }
+                Expr::Ident(name)
+            }
+            Token::LParen => {
+                let expr = self.expression()?;
+                self.expect(&Token::RParen, "`)`")?;
+                expr
+            }
+            Token::LBracket => {
+                Expr::List(self.nested(|parser| parser.list(&Token::RBracket, "`]`"))?)
+            }
+            _ => return Err(error(offset, "expected a value")),
+        };
+
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Expr, RuleError> {
+        super::parse(source, &Limits::default())
+    }
+
+    fn ident(name: &str) -> Box<Expr> {
+        Box::new(Expr::Ident(name.to_string()))
+    }
+
+    fn int(n: i64) -> Box<Expr> {
+        Box::new(Expr::Literal(Value::from(n)))
+    }
+
+    #[test]
+    fn operators_bind_by_precedence() {
+        assert_eq!(
+            parse("a + 2 * 3 > 10 && !b").unwrap(),
+            Expr::Binary(
+                BinaryOp::And,
+                Box::new(Expr::Binary(
+                    BinaryOp::Gt,
+                    Box::new(Expr::Binary(
+                        BinaryOp::Add,
+                        ident("a"),
+                        Box::new(Expr::Binary(BinaryOp::Mul, int(2), int(3))),
+                    )),
+                    int(10),
+                )),
+                Box::new(Expr::Unary(UnaryOp::Not, ident("b"))),
+            )
+        );
+    }
+
+    #[test]
+    fn fields_indexes_and_methods_chain() {
+        assert_eq!(
+            parse("user.tags[0].startsWith('vip')").unwrap(),
+            Expr::Call(
+                Box::new(Expr::Index(
+                    Box::new(Expr::Field(ident("user"), "tags".to_string())),
+                    int(0),
+                )),
+                Method::StartsWith,
+                vec![Expr::Literal(Value::from("vip"))],
+            )
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let at = |source| match parse(source) {
+            Err(RuleError::Parse { position, message }) => (position, message),
+            other => panic!("expected a parse error, got {other:?}"),
+        };
+
+        assert_eq!(
+            at("a >"),
+            (3, "unexpected end of the expression".to_string())
+        );
+        assert_eq!(at("a # b"), (2, "unexpected character `#`".to_string()));
+        assert_eq!(
+            at("name.upper()"),
+            (5, "unknown function `upper`".to_string())
+        );
+        assert_eq!(at("now()"), (0, "unknown function `now`".to_string()));
+        assert_eq!(
+            at("s.size(1)"),
+            (2, "`size` takes 0 argument(s)".to_string())
+        );
+        assert_eq!(at("'open"), (0, "unterminated string".to_string()));
+        assert_eq!(
+            at("a b"),
+            (2, "expected the end of the expression".to_string())
+        );
+    }
+
+    #[test]
+    fn deeply_nested_expressions_are_rejected() {
+        let limits = Limits::default();
+        let nested = format!("{}1{}", "(".repeat(1_000), ")".repeat(1_000));
+        assert_eq!(parse(&nested), Err(RuleError::TooComplex));
+
+        let chained = vec!["1"; limits.max_depth + 1].join(" + ");
+        assert_eq!(parse(&chained), Err(RuleError::TooComplex));
+
+        let long = "a".repeat(limits.max_length + 1);
+        assert_eq!(parse(&long), Err(RuleError::TooComplex));
+    }
+}
@@ -0,0 +1,305 @@
+//! Controlling the screen
+//!
+//! The [`Screen`] capability lets the core keep the screen awake, override its brightness,
+//! query and lock the orientation of the app, and read the display's metrics, so that
+//! decisions like "keep the screen awake while a recipe is shown" or "lock to landscape
+//! during video playback" can be made in the core, rather than in each shell.
+//!
+//! Keeping the screen awake and overriding the brightness only apply while the app is in the
+//! foreground, and are undone by the shell when it leaves it.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Orientation {
+    Portrait,
+    PortraitUpsideDown,
+    LandscapeLeft,
+    LandscapeRight,
+}
+
+impl Orientation {
+    pub fn is_portrait(self) -> bool {
+        matches!(
+            self,
+            Orientation::Portrait | Orientation::PortraitUpsideDown
+        )
+    }
+
+    pub fn is_landscape(self) -> bool {
+        !self.is_portrait()
+    }
+}
+
+/// The orientations the app can be shown in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrientationLock {
+    /// Follow the device, in any orientation the app supports
+    #[default]
+    Unlocked,
+    /// Either portrait orientation
+    Portrait,
+    /// Either landscape orientation
+    Landscape,
+    /// The orientation the app is currently shown in
+    Current,
+}
+
+/// Distances from the edges of the display, in logical pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Insets {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+/// The metrics of the display the app is shown on
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayMetrics {
+    /// The width available to the app, in logical pixels
+    pub width: f64,
+    /// The height available to the app, in logical pixels
+    pub height: f64,
+    /// The number of physical pixels per logical pixel
+    pub scale: f64,
+    /// The areas of the display obscured by notches, rounded corners and system bars
+    pub safe_area: Insets,
+    /// The display's refresh rate, in Hz, if known
+    pub refresh_rate: Option<f64>,
+    pub orientation: Orientation,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenOperation {
+    /// Prevent the screen from dimming and locking, or allow it again
+    KeepAwake(bool),
+    /// Override the screen's brightness for the app, from 0.0 to 1.0,
+    /// or restore the system brightness when `None`
+    SetBrightness(Option<f64>),
+    Orientation,
+    LockOrientation(OrientationLock),
+    Metrics,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenResponse {
+    Orientation(Orientation),
+    Metrics(DisplayMetrics),
+    /// The orientation lock was applied
+    Locked,
+    /// The platform doesn't support the operation, e.g. locking the orientation of an app
+    /// shown in split view
+    Unsupported,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for ScreenOperation {
+    type Output = ScreenResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScreenError {
+    #[error("not supported on this platform")]
+    Unsupported,
+    #[error("screen operation failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<ScreenResponse> },
+}
+
+impl From<ScreenResponse> for ScreenError {
+    fn from(response: ScreenResponse) -> Self {
+        match response {
+            ScreenResponse::Unsupported => ScreenError::Unsupported,
+            ScreenResponse::Error { message } => ScreenError::Shell { message },
+            response => ScreenError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// The Screen capability API
+///
+/// This capability lets the app keep the screen awake, set its brightness and orientation,
+/// and read the display's metrics.
+#[derive(crux_core::macros::Capability)]
+pub struct Screen<Ev> {
+    context: CapabilityContext<ScreenOperation, Ev>,
+}
+
+impl<Ev> Clone for Screen<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Screen<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<ScreenOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Keep the screen awake while `enabled`, e.g. while a recipe or a boarding pass is shown
+    pub fn keep_awake(&self, enabled: bool) {
+        self.notify(ScreenOperation::KeepAwake(enabled));
+    }
+
+    /// Override the screen's brightness for the app, clamped to between 0.0 and 1.0,
+    /// e.g. to make a barcode easier to scan
+    pub fn set_brightness(&self, level: f64) {
+        self.notify(ScreenOperation::SetBrightness(Some(level.clamp(0.0, 1.0))));
+    }
+
+    /// Stop overriding the screen's brightness
+    pub fn restore_brightness(&self) {
+        self.notify(ScreenOperation::SetBrightness(None));
+    }
+
+    /// Get the orientation the app is shown in, then send the event returned by `callback`
+    /// with it.
+    pub fn orientation<F>(&self, callback: F)
+    where
+        F: FnOnce(Result<Orientation, ScreenError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.orientation_async().await));
+            }
+        });
+    }
+
+    /// Get the orientation the app is shown in.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn orientation_async(&self) -> Result<Orientation, ScreenError> {
+        match self
+            .context
+            .request_from_shell(ScreenOperation::Orientation)
+            .await
+        {
+            ScreenResponse::Orientation(orientation) => Ok(orientation),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Lock the orientation of the app, or unlock it with [`OrientationLock::Unlocked`],
+    /// then send the event returned by `callback` with the result.
+    pub fn lock_orientation<F>(&self, lock: OrientationLock, callback: F)
+    where
+        F: FnOnce(Result<(), ScreenError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.lock_orientation_async(lock).await));
+            }
+        });
+    }
+
+    /// Lock the orientation of the app, or unlock it with [`OrientationLock::Unlocked`].
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn lock_orientation_async(&self, lock: OrientationLock) -> Result<(), ScreenError> {
+        match self
+            .context
+            .request_from_shell(ScreenOperation::LockOrientation(lock))
+            .await
+        {
+            ScreenResponse::Locked => Ok(()),
+            response => Err(response.into()),
+        }
+    }
+
+    /// Get the metrics of the display, then send the event returned by `callback` with them.
+    pub fn metrics<F>(&self, callback: F)
+    where
+        F: FnOnce(Result<DisplayMetrics, ScreenError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.metrics_async().await));
+            }
+        });
+    }
+
+    /// Get the metrics of the display.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn metrics_async(&self) -> Result<DisplayMetrics, ScreenError> {
+        match self
+            .context
+            .request_from_shell(ScreenOperation::Metrics)
+            .await
+        {
+            ScreenResponse::Metrics(metrics) => Ok(metrics),
+            response => Err(response.into()),
+        }
+    }
+
+    fn notify(&self, operation: ScreenOperation) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context.notify_shell(operation).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let serialized = serde_json::to_string(&ScreenOperation::KeepAwake(true)).unwrap();
+        assert_eq!(&serialized, r#"{"keepAwake":true}"#);
+
+        let operation = ScreenOperation::LockOrientation(OrientationLock::Landscape);
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(&serialized, r#"{"lockOrientation":"landscape"}"#);
+
+        let deserialized: ScreenOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let response = ScreenResponse::Metrics(DisplayMetrics {
+            width: 390.0,
+            height: 844.0,
+            scale: 3.0,
+            safe_area: Insets {
+                top: 47.0,
+                bottom: 34.0,
+                ..Insets::default()
+            },
+            refresh_rate: Some(60.0),
+            orientation: Orientation::Portrait,
+        });
+        let serialized = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"metrics":{"width":390.0,"height":844.0,"scale":3.0,"safeArea":{"top":47.0,"right":0.0,"bottom":34.0,"left":0.0},"refreshRate":60.0,"orientation":"portrait"}}"#
+        );
+    }
+}
@@ -0,0 +1,161 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_screen::{DisplayMetrics, OrientationLock, Screen, ScreenError};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Start,
+        ShowRecipe,
+        CloseRecipe,
+
+        #[serde(skip)]
+        GotMetrics(Result<DisplayMetrics, ScreenError>),
+        #[serde(skip)]
+        Locked(Result<(), ScreenError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub two_columns: bool,
+        pub showing_recipe: bool,
+        pub locked: bool,
+        pub error: Option<ScreenError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub screen: Screen<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Start => caps.screen.metrics(Event::GotMetrics),
+                Event::GotMetrics(Ok(metrics)) => model.two_columns = metrics.width >= 600.0,
+                Event::ShowRecipe => {
+                    model.showing_recipe = true;
+                    caps.screen.keep_awake(true);
+                    caps.screen
+                        .lock_orientation(OrientationLock::Current, Event::Locked);
+                }
+                Event::CloseRecipe => {
+                    model.showing_recipe = false;
+                    caps.screen.keep_awake(false);
+                    caps.screen
+                        .lock_orientation(OrientationLock::Unlocked, Event::Locked);
+                }
+                Event::Locked(Ok(())) => model.locked = model.showing_recipe,
+                Event::GotMetrics(Err(error)) | Event::Locked(Err(error)) => {
+                    model.error = Some(error);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_screen::{
+        DisplayMetrics, Insets, Orientation, OrientationLock, ScreenError, ScreenOperation,
+        ScreenResponse,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn requests(effects: impl IntoIterator<Item = Effect>) -> Vec<Request<ScreenOperation>> {
+        effects
+            .into_iter()
+            .map(|effect| {
+                let Effect::Screen(request) = effect;
+                request
+            })
+            .collect()
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<ScreenOperation>,
+        response: ScreenResponse,
+    ) {
+        let update = app.resolve(request, response).unwrap();
+        for event in update.events {
+            app.update(event, model);
+        }
+    }
+
+    #[test]
+    fn layout_follows_the_display_metrics() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut request = requests(app.update(Event::Start, &mut model).into_effects()).remove(0);
+        assert_eq!(request.operation, ScreenOperation::Metrics);
+
+        respond(
+            &app,
+            &mut model,
+            &mut request,
+            ScreenResponse::Metrics(DisplayMetrics {
+                width: 820.0,
+                height: 1180.0,
+                scale: 2.0,
+                safe_area: Insets::default(),
+                refresh_rate: Some(120.0),
+                orientation: Orientation::Portrait,
+            }),
+        );
+
+        assert!(model.two_columns);
+    }
+
+    #[test]
+    fn keeps_awake_and_locked_while_showing_a_recipe() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut show = requests(app.update(Event::ShowRecipe, &mut model).into_effects());
+        assert_eq!(show.len(), 2);
+        assert_eq!(show[0].operation, ScreenOperation::KeepAwake(true));
+        assert_eq!(
+            show[1].operation,
+            ScreenOperation::LockOrientation(OrientationLock::Current)
+        );
+
+        respond(&app, &mut model, &mut show[1], ScreenResponse::Locked);
+        assert!(model.locked);
+
+        let mut close = requests(app.update(Event::CloseRecipe, &mut model).into_effects());
+        assert_eq!(close[0].operation, ScreenOperation::KeepAwake(false));
+        assert_eq!(
+            close[1].operation,
+            ScreenOperation::LockOrientation(OrientationLock::Unlocked)
+        );
+
+        respond(&app, &mut model, &mut close[1], ScreenResponse::Locked);
+        assert!(!model.locked);
+    }
+
+    #[test]
+    fn locking_can_be_unsupported() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut show = requests(app.update(Event::ShowRecipe, &mut model).into_effects());
+        respond(&app, &mut model, &mut show[1], ScreenResponse::Unsupported);
+
+        assert!(!model.locked);
+        assert_eq!(model.error, Some(ScreenError::Unsupported));
+    }
+}
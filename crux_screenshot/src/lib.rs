@@ -0,0 +1,305 @@
+//! Capturing the screen for feedback reports
+//!
+//! The [`Screenshot`] capability asks the shell to capture what's currently on screen, with
+//! sensitive parts blacked out, so that a "send feedback" flow can be written in the core.
+//! [`Screenshot::report`] pairs the image with a snapshot of the core's state, serialized when the
+//! capture is requested, so that the two describe the same moment.
+//!
+//! Which parts of the screen to redact is decided by the core: view models flag their sensitive
+//! elements by implementing [`Sensitive`], and [`CaptureOptions::redact_view`] turns those flags
+//! into [`Redaction`]s for the shell to apply before the image leaves it.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A rectangle on screen, in logical points from the top left
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A part of the screen to black out in the capture
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Redaction {
+    /// The element with this identifier, e.g. its accessibility or test identifier,
+    /// wherever the shell laid it out
+    Element(String),
+    Region(Region),
+}
+
+/// Implemented by view models which show sensitive information, like card numbers or messages,
+/// which must not appear in screenshots
+pub trait Sensitive {
+    /// The parts of the screen showing sensitive information
+    fn redactions(&self) -> Vec<Redaction>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    /// JPEG, with a quality from 0 to 100
+    Jpeg(u8),
+}
+
+/// How to capture the screen
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureOptions {
+    pub redact: Vec<Redaction>,
+    pub format: ImageFormat,
+    /// The scale of the image relative to the screen's logical points, `None` for the screen's own
+    pub scale: Option<f64>,
+}
+
+impl CaptureOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact the element with the `id`
+    #[must_use]
+    pub fn redact_element(mut self, id: impl Into<String>) -> Self {
+        self.redact.push(Redaction::Element(id.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn redact_region(mut self, region: Region) -> Self {
+        self.redact.push(Redaction::Region(region));
+        self
+    }
+
+    /// Redact everything the `view` flags as sensitive
+    #[must_use]
+    pub fn redact_view(mut self, view: &impl Sensitive) -> Self {
+        self.redact.extend(view.redactions());
+        self
+    }
+
+    /// Capture a JPEG with the `quality`, from 0 to 100, instead of a PNG
+    #[must_use]
+    pub fn jpeg(mut self, quality: u8) -> Self {
+        self.format = ImageFormat::Jpeg(quality.min(100));
+        self
+    }
+
+    /// Capture at the `scale` relative to logical points, e.g. 1.0 for a smaller image
+    #[must_use]
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+}
+
+/// A captured image
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    pub format: ImageFormat,
+    /// The width in pixels
+    pub width: u32,
+    /// The height in pixels
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// A screenshot together with the core's state when it was taken
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub image: Image,
+    pub state: serde_json::Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenshotOperation {
+    Capture(CaptureOptions),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenshotResponse {
+    Captured(Image),
+    /// The screen is marked as secure by the platform, and can't be captured
+    Protected,
+    /// The shell can't capture the screen
+    Unavailable,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for ScreenshotOperation {
+    type Output = ScreenshotResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScreenshotError {
+    #[error("the screen is protected from capture")]
+    Protected,
+    #[error("screen capture is not available")]
+    Unavailable,
+    #[error("failed to snapshot the state: {message}")]
+    State { message: String },
+    #[error("screen capture failed: {message}")]
+    Shell { message: String },
+}
+
+impl ScreenshotResponse {
+    fn into_result(self) -> Result<Image, ScreenshotError> {
+        match self {
+            ScreenshotResponse::Captured(image) => Ok(image),
+            ScreenshotResponse::Protected => Err(ScreenshotError::Protected),
+            ScreenshotResponse::Unavailable => Err(ScreenshotError::Unavailable),
+            ScreenshotResponse::Error { message } => Err(ScreenshotError::Shell { message }),
+        }
+    }
+}
+
+/// The Screenshot capability API
+///
+/// This capability lets the app capture the screen, with sensitive parts redacted.
+#[derive(crux_core::macros::Capability)]
+pub struct Screenshot<Ev> {
+    context: CapabilityContext<ScreenshotOperation, Ev>,
+}
+
+impl<Ev> Clone for Screenshot<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Screenshot<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<ScreenshotOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Capture the screen, then send the event returned by `callback` with the image.
+    pub fn capture<F>(&self, options: CaptureOptions, callback: F)
+    where
+        F: FnOnce(Result<Image, ScreenshotError>) -> Ev + Send + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.capture_async(options).await));
+            }
+        });
+    }
+
+    /// Capture the screen.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn capture_async(&self, options: CaptureOptions) -> Result<Image, ScreenshotError> {
+        self.context
+            .request_from_shell(ScreenshotOperation::Capture(options))
+            .await
+            .into_result()
+    }
+
+    /// Capture the screen, and snapshot the `state` (e.g. the model, or the parts of it
+    /// useful for diagnosing problems), then send the event returned by `callback` with both.
+    ///
+    /// The state is serialized straight away, so it matches what's on screen.
+    pub fn report<S, F>(&self, options: CaptureOptions, state: &S, callback: F)
+    where
+        S: Serialize,
+        F: FnOnce(Result<Report, ScreenshotError>) -> Ev + Send + 'static,
+    {
+        let state = snapshot(state);
+
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let report = match state {
+                    Ok(state) => this
+                        .capture_async(options)
+                        .await
+                        .map(|image| Report { image, state }),
+                    Err(e) => Err(e),
+                };
+
+                context.update_app(callback(report));
+            }
+        });
+    }
+
+    /// Capture the screen, and snapshot the `state`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// The state is serialized before the capture is requested.
+    pub async fn report_async<S: Serialize>(
+        &self,
+        options: CaptureOptions,
+        state: &S,
+    ) -> Result<Report, ScreenshotError> {
+        let state = snapshot(state)?;
+        let image = self.capture_async(options).await?;
+
+        Ok(Report { image, state })
+    }
+}
+
+fn snapshot<S: Serialize>(state: &S) -> Result<serde_json::Value, ScreenshotError> {
+    serde_json::to_value(state).map_err(|e| ScreenshotError::State {
+        message: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = ScreenshotOperation::Capture(
+            CaptureOptions::new()
+                .redact_element("card-number")
+                .redact_region(Region {
+                    x: 0.0,
+                    y: 40.0,
+                    width: 320.0,
+                    height: 20.0,
+                })
+                .jpeg(120),
+        );
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"capture":{"redact":[{"element":"card-number"},{"region":{"x":0.0,"y":40.0,"width":320.0,"height":20.0}}],"format":{"jpeg":100},"scale":null}}"#
+        );
+
+        let deserialized: ScreenshotOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let response = ScreenshotResponse::Captured(Image {
+            format: ImageFormat::Png,
+            width: 1,
+            height: 1,
+            data: vec![0x89],
+        });
+        let serialized = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"captured":{"format":"png","width":1,"height":1,"data":[137]}}"#
+        );
+    }
+}
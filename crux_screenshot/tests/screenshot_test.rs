@@ -0,0 +1,163 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_screenshot::{
+        CaptureOptions, Redaction, Report, Screenshot, ScreenshotError, Sensitive,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        AddToBasket(String),
+        SendFeedback,
+
+        #[serde(skip)]
+        Captured(Result<Report, ScreenshotError>),
+    }
+
+    #[derive(Default, Serialize)]
+    pub struct Model {
+        pub basket: Vec<String>,
+        #[serde(skip)]
+        pub card_number: Option<String>,
+        #[serde(skip)]
+        pub report: Option<Result<Report, ScreenshotError>>,
+    }
+
+    #[derive(Serialize)]
+    pub struct ViewModel {
+        pub basket: Vec<String>,
+        pub card_number: Option<String>,
+    }
+
+    impl Sensitive for ViewModel {
+        fn redactions(&self) -> Vec<Redaction> {
+            self.card_number
+                .iter()
+                .map(|_| Redaction::Element("card-number".to_string()))
+                .collect()
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub screenshot: Screenshot<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::AddToBasket(item) => model.basket.push(item),
+                Event::SendFeedback => {
+                    let options = CaptureOptions::new()
+                        .redact_view(&self.view(model))
+                        .scale(1.0);
+                    caps.screenshot.report(options, model, Event::Captured);
+                }
+                Event::Captured(report) => model.report = Some(report),
+            }
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            ViewModel {
+                basket: model.basket.clone(),
+                card_number: model.card_number.clone(),
+            }
+        }
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_screenshot::{
+        Image, ImageFormat, Redaction, ScreenshotError, ScreenshotOperation, ScreenshotResponse,
+    };
+    use serde_json::json;
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn image() -> Image {
+        Image {
+            format: ImageFormat::Png,
+            width: 390,
+            height: 844,
+            data: vec![0x89, b'P', b'N', b'G'],
+        }
+    }
+
+    #[test]
+    fn reports_the_redacted_screen_with_the_state() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model {
+            basket: vec!["Flat white".to_string()],
+            card_number: Some("4111 1111 1111 1111".to_string()),
+            ..Model::default()
+        };
+
+        let update = app.update(Event::SendFeedback, &mut model);
+        let Some(Effect::Screenshot(mut request)) = update.into_effects().next() else {
+            panic!("expected a Screenshot effect");
+        };
+        let ScreenshotOperation::Capture(options) = &request.operation;
+        assert_eq!(
+            options.redact,
+            vec![Redaction::Element("card-number".to_string())]
+        );
+        assert_eq!(options.scale, Some(1.0));
+
+        // the state is snapshotted when the capture is requested
+        app.update(Event::AddToBasket("Croissant".to_string()), &mut model);
+
+        let update = app
+            .resolve(&mut request, ScreenshotResponse::Captured(image()))
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        let report = model.report.unwrap().unwrap();
+        assert_eq!(report.image, image());
+        assert_eq!(report.state, json!({ "basket": ["Flat white"] }));
+    }
+
+    #[test]
+    fn nothing_to_redact() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::SendFeedback, &mut model);
+        let Some(Effect::Screenshot(request)) = update.into_effects().next() else {
+            panic!("expected a Screenshot effect");
+        };
+        let ScreenshotOperation::Capture(options) = &request.operation;
+
+        assert!(options.redact.is_empty());
+    }
+
+    #[test]
+    fn protected_screens_are_not_captured() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::SendFeedback, &mut model);
+        let Some(Effect::Screenshot(mut request)) = update.into_effects().next() else {
+            panic!("expected a Screenshot effect");
+        };
+
+        let update = app
+            .resolve(&mut request, ScreenshotResponse::Protected)
+            .unwrap();
+        for event in update.events {
+            app.update(event, &mut model);
+        }
+
+        assert_eq!(model.report, Some(Err(ScreenshotError::Protected)));
+    }
+}
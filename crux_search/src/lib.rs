@@ -0,0 +1,471 @@
+//! Full-text search for Crux apps
+//!
+//! [`SearchIndex`] is an inverted index kept in the app's model. Documents are added with an id
+//! and their text, which is split into lowercase terms. Queries match whole terms, prefixes of
+//! terms (to search as the user types) and terms within a number of typos, and results are ranked
+//! with BM25.
+//!
+//! ```rust
+//! use crux_search::{Query, SearchIndex};
+//!
+//! let mut index = SearchIndex::new();
+//! index.add("1", "Buy oat milk and bread");
+//! index.add("2", "Book a table for Friday");
+//! index.add("3", "Bread recipes");
+//!
+//! let hits = index.search(&Query::new("bread"));
+//! assert_eq!(hits.len(), 2);
+//!
+//! // prefix of the last term
+//! let hits = index.search(&Query::new("boo").prefix());
+//! assert_eq!(hits[0].id, "2");
+//!
+//! // one typo
+//! let hits = index.search(&Query::new("frieday").fuzzy(1));
+//! assert_eq!(hits[0].id, "2");
+//! ```
+//!
+//! The index can be persisted with the key-value store, see [`SearchIndex::save_async`]
+//! and [`SearchIndex::load_async`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+};
+
+use crux_kv::{error::KeyValueError, KeyValue};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const FORMAT_VERSION: u32 = 1;
+
+// BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// score multipliers for terms which only match the query term approximately
+const PREFIX_WEIGHT: f64 = 0.8;
+const FUZZY_WEIGHT: f64 = 0.5;
+
+/// Error type for search index persistence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchError {
+    #[error("storage error: {error}")]
+    Storage { error: KeyValueError },
+    #[error("stored index could not be read: {message}")]
+    Corrupt { message: String },
+}
+
+/// Split `text` into lowercase terms, at any character which isn't alphanumeric.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// A search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<String>,
+    prefix: bool,
+    max_edits: usize,
+    match_all: bool,
+    limit: usize,
+}
+
+impl Query {
+    /// A query for the terms in `text`. By default, documents must contain all of the terms,
+    /// matched exactly, and up to 20 results are returned.
+    pub fn new(text: &str) -> Self {
+        Self {
+            terms: tokenize(text).collect(),
+            prefix: false,
+            max_edits: 0,
+            match_all: true,
+            limit: 20,
+        }
+    }
+
+    /// Also match terms starting with the last term of the query, e.g. to search as the user types
+    pub fn prefix(mut self) -> Self {
+        self.prefix = true;
+        self
+    }
+
+    /// Also match terms within `max_edits` insertions, deletions or substitutions of a query term.
+    /// Terms shorter than four characters are always matched exactly.
+    pub fn fuzzy(mut self, max_edits: usize) -> Self {
+        self.max_edits = max_edits;
+        self
+    }
+
+    /// Return documents containing any of the terms, rather than all of them
+    pub fn match_any(mut self) -> Self {
+        self.match_all = false;
+        self
+    }
+
+    /// Return at most `limit` results
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// A search result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub score: f64,
+}
+
+/// An inverted index over documents identified by string ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchIndex {
+    /// term -> document id -> term frequency
+    postings: BTreeMap<String, BTreeMap<String, u32>>,
+    /// document id -> terms in the document, with repetitions
+    documents: BTreeMap<String, Vec<String>>,
+    total_length: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    version: u32,
+    index: SearchIndex,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of documents in the index
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.documents.contains_key(id)
+    }
+
+    /// Add a document to the index, replacing any previous document with the same id.
+    pub fn add(&mut self, id: impl Into<String>, text: &str) {
+        let id = id.into();
+        self.remove(&id);
+
+        let terms: Vec<String> = tokenize(text).collect();
+        for term in &terms {
+            *self
+                .postings
+                .entry(term.clone())
+                .or_default()
+                .entry(id.clone())
+                .or_default() += 1;
+        }
+
+        self.total_length += terms.len() as u64;
+        self.documents.insert(id, terms);
+    }
+
+    /// Remove a document from the index. Returns whether the document was in the index.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(terms) = self.documents.remove(id) else {
+            return false;
+        };
+
+        for term in &terms {
+            if let Some(posting) = self.postings.get_mut(term) {
+                posting.remove(id);
+                if posting.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= terms.len() as u64;
+
+        true
+    }
+
+    /// Search the index, returning the matching documents, most relevant first.
+    pub fn search(&self, query: &Query) -> Vec<SearchHit> {
+        if query.terms.is_empty() || self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let document_count = self.documents.len() as f64;
+        let average_length = self.total_length as f64 / document_count;
+
+        let mut scores: HashMap<&str, f64> = HashMap::new();
+        let mut matched_terms: HashMap<&str, usize> = HashMap::new();
+
+        for (i, query_term) in query.terms.iter().enumerate() {
+            let is_last = i == query.terms.len() - 1;
+            let mut term_scores: HashMap<&str, f64> = HashMap::new();
+
+            for (term, weight) in self.expand(query_term, query.prefix && is_last, query.max_edits)
+            {
+                let posting = &self.postings[term];
+                let idf = (1.0
+                    + (document_count - posting.len() as f64 + 0.5) / (posting.len() as f64 + 0.5))
+                    .ln();
+
+                for (id, frequency) in posting {
+                    let length = self.documents[id].len() as f64;
+                    let frequency = f64::from(*frequency);
+                    let score = idf * frequency * (K1 + 1.0)
+                        / (frequency + K1 * (1.0 - B + B * length / average_length));
+
+                    // a document matching a query term several ways counts its best match
+                    let best = term_scores.entry(id.as_str()).or_default();
+                    *best = best.max(score * weight);
+                }
+            }
+
+            for (id, score) in term_scores {
+                *scores.entry(id).or_default() += score;
+                *matched_terms.entry(id).or_default() += 1;
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .filter(|(id, _)| !query.match_all || matched_terms[id] == query.terms.len())
+            .map(|(id, score)| SearchHit {
+                id: id.to_string(),
+                score,
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        hits.truncate(query.limit);
+
+        hits
+    }
+
+    /// The terms in the index matching `query_term`, with the weight of the match
+    fn expand<'a>(
+        &'a self,
+        query_term: &'a str,
+        prefix: bool,
+        max_edits: usize,
+    ) -> impl Iterator<Item = (&'a str, f64)> + 'a {
+        let query_length = query_term.chars().count();
+        let max_edits = if query_length < 4 { 0 } else { max_edits };
+
+        let matches = move |term: &str| {
+            if term == query_term {
+                return Some(1.0);
+            }
+            if prefix && term.starts_with(query_term) {
+                return Some(PREFIX_WEIGHT);
+            }
+            if max_edits > 0 {
+                let length = term.chars().count();
+                if length.abs_diff(query_length) <= max_edits {
+                    let distance = edit_distance(query_term, term);
+                    if distance <= max_edits {
+                        return Some(FUZZY_WEIGHT / distance as f64);
+                    }
+                }
+            }
+            None
+        };
+
+        // without fuzzy matching, only terms starting with the query term can match
+        let candidates: Box<dyn Iterator<Item = &String>> = if max_edits == 0 {
+            Box::new(
+                self.postings
+                    .range::<str, _>((Bound::Included(query_term), Bound::Unbounded))
+                    .map(|(term, _)| term)
+                    .take_while(move |term| term.starts_with(query_term)),
+            )
+        } else {
+            Box::new(self.postings.keys())
+        };
+
+        candidates.filter_map(move |term| matches(term).map(|weight| (term.as_str(), weight)))
+    }
+
+    /// Serialize the index to be persisted
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(&Stored {
+            version: FORMAT_VERSION,
+            index: self.clone(),
+        })
+        .expect("index serializes to JSON")
+    }
+
+    /// Restore an index serialized with [`SearchIndex::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SearchError> {
+        let stored: Stored = serde_json::from_slice(bytes).map_err(|e| SearchError::Corrupt {
+            message: e.to_string(),
+        })?;
+
+        if stored.version != FORMAT_VERSION {
+            return Err(SearchError::Corrupt {
+                message: format!("unknown index format version {}", stored.version),
+            });
+        }
+
+        Ok(stored.index)
+    }
+
+    /// Persist the index in the key-value store under `key`.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn save_async<Ev>(
+        &self,
+        key_value: &KeyValue<Ev>,
+        key: String,
+    ) -> Result<(), SearchError>
+    where
+        Ev: 'static,
+    {
+        key_value
+            .set_async(key, self.to_bytes())
+            .await
+            .map(|_| ())
+            .map_err(|error| SearchError::Storage { error })
+    }
+
+    /// Load an index persisted with [`SearchIndex::save_async`]. If there is no index stored
+    /// under `key`, an empty index is returned.
+    /// This is used together with [`crux_core::compose::Compose`].
+    pub async fn load_async<Ev>(key_value: &KeyValue<Ev>, key: String) -> Result<Self, SearchError>
+    where
+        Ev: 'static,
+    {
+        let bytes = key_value
+            .get_async(key)
+            .await
+            .map_err(|error| SearchError::Storage { error })?;
+
+        if bytes.is_empty() {
+            return Ok(Self::new());
+        }
+
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Levenshtein distance between two strings, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.add("apples", "Apples and pears");
+        index.add("pie", "Apple pie with cinnamon");
+        index.add("crumble", "Rhubarb crumble");
+        index
+    }
+
+    fn ids(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.id.as_str()).collect()
+    }
+
+    #[test]
+    fn tokenizes_unicode_text() {
+        let terms: Vec<_> = tokenize("Crème brûlée, 2 portions!").collect();
+
+        assert_eq!(terms, vec!["crème", "brûlée", "2", "portions"]);
+    }
+
+    #[test]
+    fn ranks_by_relevance() {
+        let hits = index().search(&Query::new("apples"));
+        assert_eq!(ids(&hits), vec!["apples"]);
+
+        let hits = index().search(&Query::new("apple").prefix());
+        assert_eq!(ids(&hits), vec!["pie", "apples"]);
+    }
+
+    #[test]
+    fn matches_all_terms_by_default() {
+        let index = index();
+
+        assert_eq!(
+            ids(&index.search(&Query::new("apple crumble"))),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            ids(&index.search(&Query::new("apple crumble").match_any())),
+            vec!["crumble", "pie"]
+        );
+    }
+
+    #[test]
+    fn fuzzy_matches_within_edit_distance() {
+        let index = index();
+
+        assert_eq!(
+            ids(&index.search(&Query::new("rubarb").fuzzy(1))),
+            vec!["crumble"]
+        );
+        assert!(index.search(&Query::new("rbarb").fuzzy(1)).is_empty());
+        assert_eq!(
+            ids(&index.search(&Query::new("rbarb").fuzzy(2))),
+            vec!["crumble"]
+        );
+
+        // short terms are only matched exactly
+        assert!(index.search(&Query::new("pi").fuzzy(1)).is_empty());
+    }
+
+    #[test]
+    fn updates_and_removals() {
+        let mut index = index();
+
+        index.add("pie", "Cherry pie");
+        assert_eq!(ids(&index.search(&Query::new("cherry"))), vec!["pie"]);
+        assert!(index.search(&Query::new("cinnamon")).is_empty());
+
+        assert!(index.remove("pie"));
+        assert!(!index.remove("pie"));
+        assert!(index.search(&Query::new("cherry")).is_empty());
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let index = index();
+        let restored = SearchIndex::from_bytes(&index.to_bytes()).unwrap();
+
+        assert_eq!(restored, index);
+        assert!(matches!(
+            SearchIndex::from_bytes(b"{}"),
+            Err(SearchError::Corrupt { .. })
+        ));
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("flaw", "flaw"), 0);
+    }
+}
@@ -0,0 +1,176 @@
+mod shared {
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_kv::KeyValue;
+    use crux_search::{Query, SearchError, SearchIndex};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum Event {
+        Add(String, String),
+        Save,
+        Load,
+
+        #[serde(skip)]
+        Saved(Result<(), SearchError>),
+        #[serde(skip)]
+        Loaded(Result<SearchIndex, SearchError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub index: SearchIndex,
+        pub error: Option<SearchError>,
+    }
+
+    #[derive(Serialize, Deserialize, Default)]
+    pub struct ViewModel {
+        pub results: Vec<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub key_value: KeyValue<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Add(id, text) => model.index.add(id, &text),
+                Event::Save => {
+                    let index = model.index.clone();
+                    let key_value = caps.key_value.clone();
+
+                    caps.compose.spawn(|ctx| async move {
+                        let result = index.save_async(&key_value, "index".to_string()).await;
+                        ctx.update_app(Event::Saved(result));
+                    });
+                }
+                Event::Load => {
+                    let key_value = caps.key_value.clone();
+
+                    caps.compose.spawn(|ctx| async move {
+                        let result = SearchIndex::load_async(&key_value, "index".to_string()).await;
+                        ctx.update_app(Event::Loaded(result));
+                    });
+                }
+                Event::Saved(result) => model.error = result.err(),
+                Event::Loaded(Ok(index)) => model.index = index,
+                Event::Loaded(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, model: &Model) -> ViewModel {
+            ViewModel {
+                results: model
+                    .index
+                    .search(&Query::new("ind").prefix())
+                    .into_iter()
+                    .map(|hit| hit.id)
+                    .collect(),
+            }
+        }
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_kv::{KeyValueOperation, KeyValueResponse, KeyValueResult};
+    use crux_search::SearchError;
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    #[test]
+    fn saves_and_loads_the_index() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let _ = app.update(
+            Event::Add("1".to_string(), "An inverted index".to_string()),
+            &mut model,
+        );
+
+        let mut update = app.update(Event::Save, &mut model);
+        let Effect::KeyValue(mut request) = update.effects.remove(0);
+        let KeyValueOperation::Set { key, value, .. } = request.operation.clone() else {
+            panic!("expected a set operation");
+        };
+        assert_eq!(key, "index");
+
+        let update = app
+            .resolve(
+                &mut request,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Set {
+                        previous: vec![],
+                        version: 1,
+                    },
+                },
+            )
+            .unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+        assert_eq!(model.error, None);
+
+        let mut restored = Model::default();
+        let mut update = app.update(Event::Load, &mut restored);
+        let Effect::KeyValue(mut request) = update.effects.remove(0);
+        assert_eq!(
+            request.operation,
+            KeyValueOperation::Get {
+                key: "index".to_string()
+            }
+        );
+
+        let update = app
+            .resolve(
+                &mut request,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Get { value, version: 1 },
+                },
+            )
+            .unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut restored);
+        }
+
+        assert_eq!(restored.index, model.index);
+        assert_eq!(app.view(&restored).results, vec!["1"]);
+    }
+
+    #[test]
+    fn loading_a_corrupt_index_fails() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut update = app.update(Event::Load, &mut model);
+        let Effect::KeyValue(mut request) = update.effects.remove(0);
+
+        let update = app
+            .resolve(
+                &mut request,
+                KeyValueResult::Ok {
+                    response: KeyValueResponse::Get {
+                        value: b"not an index".to_vec(),
+                        version: 1,
+                    },
+                },
+            )
+            .unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+
+        assert!(matches!(model.error, Some(SearchError::Corrupt { .. })));
+    }
+}
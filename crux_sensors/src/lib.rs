@@ -0,0 +1,274 @@
+//! Motion sensor streams
+//!
+//! The [`Sensors`] capability subscribes to the device's motion sensors (accelerometer,
+//! gyroscope, magnetometer and compass heading) at a requested rate, so that sensor fusion,
+//! step counting, gesture detection and the like can be written in the core.
+//!
+//! Readings can be batched by the shell, to limit how often the core is woken up: each batch
+//! holds up to [`SensorOptions::batch_size`] readings, and is delivered at the latest
+//! [`SensorOptions::max_latency_ms`] after its first reading. Subscriptions are cancelled with
+//! [`Sensors::unsubscribe`], after which no more readings are passed to the app, even if the
+//! shell already sent them.
+
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SensorKind {
+    /// Acceleration, including gravity, in m/s² along each axis
+    Accelerometer,
+    /// Rotation rate, in rad/s around each axis
+    Gyroscope,
+    /// Magnetic field strength, in µT along each axis
+    Magnetometer,
+    /// The compass heading
+    Heading,
+}
+
+/// How to sample a sensor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorOptions {
+    pub sensor: SensorKind,
+    /// The requested sampling rate, in readings per second. The shell uses the closest
+    /// rate the sensor supports.
+    pub rate_hz: u32,
+    /// The most readings delivered together
+    pub batch_size: u32,
+    /// The longest time a reading is held back for batching, in milliseconds
+    pub max_latency_ms: u32,
+}
+
+impl SensorOptions {
+    /// Sample `sensor` at `rate_hz`, delivering every reading as soon as it's available
+    pub fn new(sensor: SensorKind, rate_hz: u32) -> Self {
+        Self {
+            sensor,
+            rate_hz: rate_hz.max(1),
+            batch_size: 1,
+            max_latency_ms: 0,
+        }
+    }
+
+    /// Deliver up to `batch_size` readings together, holding readings back for at most
+    /// `max_latency_ms`
+    #[must_use]
+    pub fn batched(mut self, batch_size: u32, max_latency_ms: u32) -> Self {
+        self.batch_size = batch_size.max(1);
+        self.max_latency_ms = max_latency_ms;
+        self
+    }
+}
+
+/// The core's identifier for a sensor subscription
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SubscriptionId(pub u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HeadingAccuracy {
+    High,
+    Medium,
+    Low,
+    /// The compass needs calibrating
+    Unreliable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SensorValue {
+    /// A reading along the device's x, y and z axes
+    Vector { x: f64, y: f64, z: f64 },
+    /// A compass heading, in degrees clockwise from magnetic north, and from true north
+    /// when the location is known
+    Heading {
+        magnetic: f64,
+        true_north: Option<f64>,
+        accuracy: HeadingAccuracy,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorReading {
+    /// When the reading was taken, in nanoseconds on the shell's monotonic clock
+    pub timestamp: u64,
+    pub value: SensorValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SensorOperation {
+    /// Sample a sensor, streaming batches of readings until unsubscribed
+    Subscribe {
+        id: SubscriptionId,
+        options: SensorOptions,
+    },
+    Unsubscribe {
+        id: SubscriptionId,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SensorResponse {
+    Readings(Vec<SensorReading>),
+    /// The device doesn't have the sensor
+    Unavailable,
+    /// The user hasn't given the app access to motion data
+    PermissionDenied,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for SensorOperation {
+    type Output = SensorResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SensorError {
+    #[error("the {sensor:?} sensor is not available")]
+    Unavailable { sensor: SensorKind },
+    #[error("access to motion data was denied")]
+    PermissionDenied,
+    #[error("sensor failed: {message}")]
+    Shell { message: String },
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// Shared by all the instances of the capability, including the ones mapped for composed apps
+static CANCELLED: Mutex<BTreeSet<SubscriptionId>> = Mutex::new(BTreeSet::new());
+
+/// The Sensors capability API
+///
+/// This capability lets the app subscribe to streams of motion sensor readings.
+#[derive(crux_core::macros::Capability)]
+pub struct Sensors<Ev> {
+    context: CapabilityContext<SensorOperation, Ev>,
+}
+
+impl<Ev> Clone for Sensors<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Sensors<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<SensorOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Subscribe to a sensor, passing every batch of readings to the app, wrapped in the event
+    /// produced by the `callback`. If the sensor can't be sampled, the callback is called with
+    /// the error, and no more readings follow.
+    ///
+    /// Returns the id to [unsubscribe](Self::unsubscribe) with.
+    pub fn subscribe<F>(&self, options: SensorOptions, callback: F) -> SubscriptionId
+    where
+        F: Fn(Result<Vec<SensorReading>, SensorError>) -> Ev + Send + Sync + 'static,
+    {
+        let id = SubscriptionId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream =
+                    context.stream_from_shell(SensorOperation::Subscribe { id, options });
+
+                while let Some(response) = stream.next().await {
+                    if CANCELLED.lock().unwrap().remove(&id) {
+                        break;
+                    }
+
+                    let readings = match response {
+                        SensorResponse::Readings(readings) => Ok(readings),
+                        SensorResponse::Unavailable => Err(SensorError::Unavailable {
+                            sensor: options.sensor,
+                        }),
+                        SensorResponse::PermissionDenied => Err(SensorError::PermissionDenied),
+                        SensorResponse::Error { message } => Err(SensorError::Shell { message }),
+                    };
+                    let failed = readings.is_err();
+
+                    context.update_app(callback(readings));
+
+                    if failed {
+                        break;
+                    }
+                }
+            }
+        });
+
+        id
+    }
+
+    /// Cancel the subscription. No more of its readings are passed to the app.
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        CANCELLED.lock().unwrap().insert(id);
+
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context
+                    .notify_shell(SensorOperation::Unsubscribe { id })
+                    .await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = SensorOperation::Subscribe {
+            id: SubscriptionId(1),
+            options: SensorOptions::new(SensorKind::Accelerometer, 50).batched(10, 200),
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"subscribe":{"id":1,"options":{"sensor":"accelerometer","rateHz":50,"batchSize":10,"maxLatencyMs":200}}}"#
+        );
+
+        let deserialized: SensorOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let reading = SensorReading {
+            timestamp: 1,
+            value: SensorValue::Heading {
+                magnetic: 90.0,
+                true_north: None,
+                accuracy: HeadingAccuracy::High,
+            },
+        };
+        let serialized = serde_json::to_string(&reading).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"timestamp":1,"value":{"heading":{"magnetic":90.0,"trueNorth":null,"accuracy":"high"}}}"#
+        );
+    }
+}
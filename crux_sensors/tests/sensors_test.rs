@@ -0,0 +1,184 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_sensors::{
+        SensorError, SensorKind, SensorOptions, SensorReading, SensorValue, Sensors, SubscriptionId,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        StartWorkout,
+        StopWorkout,
+
+        #[serde(skip)]
+        Motion(Result<Vec<SensorReading>, SensorError>),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub subscription: Option<SubscriptionId>,
+        /// the peak acceleration seen, in m/s²
+        pub peak: f64,
+        pub readings: usize,
+        pub error: Option<SensorError>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub sensors: Sensors<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::StartWorkout => {
+                    let options =
+                        SensorOptions::new(SensorKind::Accelerometer, 50).batched(25, 500);
+                    model.subscription = Some(caps.sensors.subscribe(options, Event::Motion));
+                }
+                Event::StopWorkout => {
+                    if let Some(id) = model.subscription.take() {
+                        caps.sensors.unsubscribe(id);
+                    }
+                }
+                Event::Motion(Ok(readings)) => {
+                    for reading in readings {
+                        if let SensorValue::Vector { x, y, z } = reading.value {
+                            model.peak = model.peak.max((x * x + y * y + z * z).sqrt());
+                        }
+                        model.readings += 1;
+                    }
+                }
+                Event::Motion(Err(error)) => {
+                    model.subscription = None;
+                    model.error = Some(error);
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_sensors::{
+        SensorError, SensorKind, SensorOperation, SensorReading, SensorResponse, SensorValue,
+    };
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn request(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Request<SensorOperation> {
+        let Some(Effect::Sensors(request)) = app.update(event, model).into_effects().next() else {
+            panic!("expected a sensors effect");
+        };
+
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<SensorOperation>,
+        response: SensorResponse,
+    ) {
+        let update = app.resolve(request, response).unwrap();
+        for event in update.events {
+            app.update(event, model);
+        }
+    }
+
+    fn batch(values: &[(f64, f64, f64)]) -> SensorResponse {
+        SensorResponse::Readings(
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &(x, y, z))| SensorReading {
+                    timestamp: i as u64 * 20_000_000,
+                    value: SensorValue::Vector { x, y, z },
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn streams_batches_until_unsubscribed() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut subscription = request(&app, &mut model, Event::StartWorkout);
+        let SensorOperation::Subscribe { id, options } = subscription.operation else {
+            panic!("expected to subscribe");
+        };
+        assert_eq!(options.sensor, SensorKind::Accelerometer);
+        assert_eq!((options.rate_hz, options.batch_size), (50, 25));
+        assert_eq!(model.subscription, Some(id));
+
+        respond(
+            &app,
+            &mut model,
+            &mut subscription,
+            batch(&[(0.0, 0.0, 9.8), (3.0, 4.0, 0.0)]),
+        );
+        respond(
+            &app,
+            &mut model,
+            &mut subscription,
+            batch(&[(0.0, 0.0, 12.0)]),
+        );
+        assert_eq!(model.readings, 3);
+        assert_eq!(model.peak, 12.0);
+
+        let unsubscribe = request(&app, &mut model, Event::StopWorkout);
+        assert_eq!(unsubscribe.operation, SensorOperation::Unsubscribe { id });
+
+        // a batch already on its way is dropped
+        respond(
+            &app,
+            &mut model,
+            &mut subscription,
+            batch(&[(0.0, 0.0, 30.0)]),
+        );
+        assert_eq!(model.readings, 3);
+        assert_eq!(model.peak, 12.0);
+    }
+
+    #[test]
+    fn unavailable_sensor_ends_the_subscription() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut subscription = request(&app, &mut model, Event::StartWorkout);
+        respond(
+            &app,
+            &mut model,
+            &mut subscription,
+            SensorResponse::Unavailable,
+        );
+
+        assert_eq!(
+            model.error,
+            Some(SensorError::Unavailable {
+                sensor: SensorKind::Accelerometer
+            })
+        );
+
+        // the core is no longer listening
+        assert!(app
+            .resolve(&mut subscription, batch(&[(1.0, 0.0, 0.0)]))
+            .is_err());
+        assert_eq!(model.readings, 0);
+    }
+}
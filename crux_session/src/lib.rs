@@ -0,0 +1,433 @@
+//! Authentication sessions
+//!
+//! A [`Session`] holds the tokens of the signed in user and tracks whether the user is
+//! [anonymous, authenticated, or their session has expired](SessionState). It
+//!
+//! * persists the tokens with `crux_kv`, so the session survives the app being stopped. The
+//!   shell should back the key-value store used for the session with the platform's secure
+//!   storage (e.g. the iOS Keychain, or the Android Keystore)
+//! * refreshes the access token before it expires, using `crux_time` to wait and `crux_http`
+//!   to call the refresh endpoint, see [`Session::keep_fresh`]
+//! * authorizes HTTP requests with the access token, see [`Session::authorize`]
+//! * publishes [`SessionEvent`]s on the [`Bus`], so that composed apps can react to the user
+//!   logging in or out
+//!
+//! Like [`crux_core::compose::Compose`] tasks, its methods are async and take the capabilities
+//! they use as arguments.
+//!
+//! # Protocol
+//!
+//! The refresh endpoint is sent a `POST` request with a JSON body `{"refreshToken": "..."}`, and
+//! is expected to respond with new [`Tokens`], as JSON. A `4xx` response means the refresh token
+//! is no longer valid, and the session expires.
+
+use async_trait::async_trait;
+use crux_core::{bus::Bus, bus::Topic, shared::Shared};
+use crux_http::{
+    client::Client,
+    middleware::{Middleware, Next},
+    Http, HttpError, Request, ResponseAsync,
+};
+use crux_kv::{error::KeyValueError, KeyValue};
+use crux_time::{Duration, Time, TimeResponse};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// How long before the access token expires it is refreshed, by default
+const DEFAULT_REFRESH_MARGIN_SECONDS: u64 = 60;
+
+/// How long to wait before retrying a refresh which failed for a reason other than the
+/// refresh token being rejected (e.g. the device being offline)
+const RETRY_SECONDS: u64 = 30;
+
+/// Error type for sessions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionError {
+    #[error("http error: {error}")]
+    Http { error: HttpError },
+    #[error("storage error: {error}")]
+    Storage { error: KeyValueError },
+    #[error("stored session could not be read: {message}")]
+    Corrupt { message: String },
+    #[error("the session has no refresh token")]
+    NoRefreshToken,
+}
+
+/// The tokens issued to the user when signing in
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tokens {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// When the access token expires, in seconds since the UNIX epoch
+    pub expires_at: u64,
+}
+
+// keep the tokens out of logs
+impl std::fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tokens")
+            .field("expires_at", &self.expires_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The state of a [`Session`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionState {
+    /// The user is not signed in
+    Anonymous,
+    /// The user is signed in, and the access token is valid
+    Authenticated,
+    /// The user was signed in, but the session could not be refreshed, and they need to
+    /// sign in again
+    Expired,
+}
+
+/// Published on the [`Bus`] when the state of the session changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionEvent {
+    LoggedIn,
+    LoggedOut,
+    Expired,
+}
+
+impl Topic for SessionEvent {}
+
+#[derive(Debug, Default, PartialEq)]
+struct Inner {
+    tokens: Option<Tokens>,
+    expired: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RefreshRequest<'a> {
+    refresh_token: &'a str,
+}
+
+/// The session of the signed in user. Clones of a session share its state.
+#[derive(Debug, Clone)]
+pub struct Session {
+    refresh_url: String,
+    storage_key: String,
+    refresh_margin: u64,
+    inner: Shared<Inner>,
+}
+
+impl Session {
+    /// Create an anonymous session, which refreshes its tokens at `refresh_url` and stores them
+    /// with the key `storage_key`.
+    pub fn new(refresh_url: impl Into<String>, storage_key: impl Into<String>) -> Self {
+        Self {
+            refresh_url: refresh_url.into(),
+            storage_key: storage_key.into(),
+            refresh_margin: DEFAULT_REFRESH_MARGIN_SECONDS,
+            inner: Shared::default(),
+        }
+    }
+
+    /// Refresh the access token `seconds` before it expires (a minute by default).
+    #[must_use]
+    pub fn with_refresh_margin(mut self, seconds: u64) -> Self {
+        self.refresh_margin = seconds;
+        self
+    }
+
+    /// The state of the session at `now`, in seconds since the UNIX epoch.
+    pub fn state(&self, now: u64) -> SessionState {
+        self.inner.with(|inner| match &inner.tokens {
+            _ if inner.expired => SessionState::Expired,
+            None => SessionState::Anonymous,
+            Some(tokens) if now >= tokens.expires_at => SessionState::Expired,
+            Some(_) => SessionState::Authenticated,
+        })
+    }
+
+    /// The current access token, if the user is signed in
+    pub fn access_token(&self) -> Option<String> {
+        self.inner
+            .with(|inner| inner.tokens.as_ref().map(|t| t.access_token.clone()))
+    }
+
+    /// Middleware adding the access token to requests as a bearer token, for use with
+    /// [`RequestBuilder::middleware`](crux_http::RequestBuilder::middleware). Requests are
+    /// sent without authorization while the user is not signed in.
+    pub fn authorize(&self) -> Authorize {
+        Authorize {
+            session: self.clone(),
+        }
+    }
+
+    /// Load the tokens stored by a previous run of the app.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tokens can't be read from storage.
+    pub async fn restore<Ev>(&self, key_value: &KeyValue<Ev>) -> Result<SessionState, SessionError>
+    where
+        Ev: 'static,
+    {
+        let bytes = key_value
+            .get_async(self.storage_key.clone())
+            .await
+            .map_err(|error| SessionError::Storage { error })?;
+
+        if bytes.is_empty() {
+            return Ok(SessionState::Anonymous);
+        }
+
+        let tokens: Tokens = serde_json::from_slice(&bytes).map_err(|e| SessionError::Corrupt {
+            message: e.to_string(),
+        })?;
+        self.inner.set(Inner {
+            tokens: Some(tokens),
+            expired: false,
+        });
+
+        Ok(SessionState::Authenticated)
+    }
+
+    /// Start a session with the `tokens` issued when the user signed in, and publish
+    /// [`SessionEvent::LoggedIn`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tokens can't be stored.
+    pub async fn login<Ev>(
+        &self,
+        tokens: Tokens,
+        key_value: &KeyValue<Ev>,
+        bus: &Bus<Ev>,
+    ) -> Result<(), SessionError>
+    where
+        Ev: 'static,
+    {
+        self.store(tokens, key_value).await?;
+        bus.publish(SessionEvent::LoggedIn);
+
+        Ok(())
+    }
+
+    /// End the session, removing the stored tokens, and publish [`SessionEvent::LoggedOut`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored tokens can't be removed. The session ends regardless.
+    pub async fn logout<Ev>(
+        &self,
+        key_value: &KeyValue<Ev>,
+        bus: &Bus<Ev>,
+    ) -> Result<(), SessionError>
+    where
+        Ev: 'static,
+    {
+        self.inner.set(Inner::default());
+        bus.publish(SessionEvent::LoggedOut);
+
+        self.forget(key_value).await
+    }
+
+    /// Exchange the refresh token for new tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session has no refresh token, or the refresh fails.
+    pub async fn refresh<Ev>(
+        &self,
+        http: &Http<Ev>,
+        key_value: &KeyValue<Ev>,
+    ) -> Result<(), SessionError>
+    where
+        Ev: 'static,
+    {
+        let error = |error| SessionError::Http { error };
+
+        let refresh_token = self
+            .inner
+            .with(|inner| inner.tokens.as_ref()?.refresh_token.clone())
+            .ok_or(SessionError::NoRefreshToken)?;
+
+        let mut response = http
+            .post(&self.refresh_url)
+            .body_json(&RefreshRequest {
+                refresh_token: &refresh_token,
+            })
+            .map_err(error)?
+            .send_async()
+            .await
+            .map_err(error)?;
+
+        if !response.status().is_success() {
+            return Err(error(HttpError::Http {
+                code: response.status(),
+                message: "refreshing the session failed".to_string(),
+                body: response.body_bytes().await.ok(),
+            }));
+        }
+
+        let tokens = response.body_json().await.map_err(error)?;
+
+        self.store(tokens, key_value).await
+    }
+
+    /// Refresh the access token shortly before it expires, for as long as the user is signed
+    /// in. If the refresh token is rejected, the session expires and [`SessionEvent::Expired`]
+    /// is published. Other failures are retried after a while.
+    ///
+    /// This finishes when the session ends, so it should run in its own task, started when
+    /// the user signs in or the session is restored.
+    pub async fn keep_fresh<Ev>(
+        &self,
+        http: &Http<Ev>,
+        key_value: &KeyValue<Ev>,
+        time: &Time<Ev>,
+        bus: &Bus<Ev>,
+    ) where
+        Ev: 'static,
+    {
+        loop {
+            let Some(expires_at) = self.inner.with(|inner| {
+                let tokens = inner.tokens.as_ref().filter(|_| !inner.expired)?;
+                Some(tokens.expires_at)
+            }) else {
+                return;
+            };
+
+            let TimeResponse::Now(now) = time.now_async().await else {
+                panic!("Time::now_async should respond with Now");
+            };
+
+            let refresh_at = expires_at.saturating_sub(self.refresh_margin);
+            if now.seconds < refresh_at {
+                // the session may have changed while waiting, so check again afterwards
+                wait(time, refresh_at - now.seconds).await;
+                continue;
+            }
+
+            match self.refresh(http, key_value).await {
+                Ok(()) => {}
+                Err(SessionError::Http {
+                    error: HttpError::Http { code, .. },
+                }) if code.is_client_error() => {
+                    return self.expire(key_value, bus).await;
+                }
+                Err(SessionError::NoRefreshToken) => return self.expire(key_value, bus).await,
+                Err(_) => wait(time, RETRY_SECONDS).await,
+            }
+        }
+    }
+
+    async fn expire<Ev>(&self, key_value: &KeyValue<Ev>, bus: &Bus<Ev>)
+    where
+        Ev: 'static,
+    {
+        self.inner.set(Inner {
+            tokens: None,
+            expired: true,
+        });
+        bus.publish(SessionEvent::Expired);
+
+        // the tokens are useless now, failing to remove them does no harm
+        let _ = self.forget(key_value).await;
+    }
+
+    async fn store<Ev>(&self, tokens: Tokens, key_value: &KeyValue<Ev>) -> Result<(), SessionError>
+    where
+        Ev: 'static,
+    {
+        let bytes = serde_json::to_vec(&tokens).map_err(|e| SessionError::Corrupt {
+            message: e.to_string(),
+        })?;
+        self.inner.set(Inner {
+            tokens: Some(tokens),
+            expired: false,
+        });
+
+        key_value
+            .set_async(self.storage_key.clone(), bytes)
+            .await
+            .map(|_| ())
+            .map_err(|error| SessionError::Storage { error })
+    }
+
+    async fn forget<Ev>(&self, key_value: &KeyValue<Ev>) -> Result<(), SessionError>
+    where
+        Ev: 'static,
+    {
+        key_value
+            .delete_async(self.storage_key.clone())
+            .await
+            .map(|_| ())
+            .map_err(|error| SessionError::Storage { error })
+    }
+}
+
+async fn wait<Ev>(time: &Time<Ev>, seconds: u64)
+where
+    Ev: 'static,
+{
+    let duration = Duration::from_secs(seconds).unwrap_or(Duration::new(u64::MAX));
+
+    time.notify_after_async(duration).await;
+}
+
+/// Middleware authorizing requests with the access token of a [`Session`],
+/// see [`Session::authorize`].
+#[derive(Debug)]
+pub struct Authorize {
+    session: Session,
+}
+
+#[async_trait]
+impl Middleware for Authorize {
+    async fn handle(
+        &self,
+        mut req: Request,
+        client: Client,
+        next: Next<'_>,
+    ) -> crux_http::Result<ResponseAsync> {
+        if let Some(token) = self.session.access_token() {
+            req.insert_header("Authorization", format!("Bearer {token}"));
+        }
+
+        next.run(req, client).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(expires_at: u64) -> Tokens {
+        Tokens {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn state_follows_token_expiry() {
+        let session = Session::new("https://auth.example.com/refresh", "session");
+        assert_eq!(session.state(100), SessionState::Anonymous);
+
+        session.inner.set(Inner {
+            tokens: Some(tokens(200)),
+            expired: false,
+        });
+        assert_eq!(session.state(100), SessionState::Authenticated);
+        assert_eq!(session.state(200), SessionState::Expired);
+    }
+
+    #[test]
+    fn tokens_are_not_logged() {
+        let debug = format!("{:?}", tokens(200));
+
+        assert_eq!(debug, "Tokens { expires_at: 200, .. }");
+    }
+}
@@ -0,0 +1,297 @@
+mod shared {
+    use crux_core::{bus::Bus, compose::Compose, macros::Effect};
+    use crux_http::Http;
+    use crux_kv::KeyValue;
+    use crux_session::{Session, SessionError, SessionEvent, SessionState, Tokens};
+    use crux_time::Time;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Start,
+        LogIn(Tokens),
+        LogOut,
+        FetchProfile,
+
+        #[serde(skip)]
+        Done(Result<(), SessionError>),
+        #[serde(skip)]
+        Session(SessionEvent),
+        #[serde(skip)]
+        Profile(crux_http::Result<crux_http::Response<Vec<u8>>>),
+    }
+
+    pub struct Model {
+        pub session: Session,
+        pub events: Vec<SessionEvent>,
+        pub error: Option<SessionError>,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            Self {
+                session: Session::new("https://auth.example.com/refresh", "session"),
+                events: Vec::new(),
+                error: None,
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub key_value: KeyValue<Event>,
+        pub time: Time<Event>,
+        #[effect(skip)]
+        pub bus: Bus<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            let session = model.session.clone();
+            let (http, key_value, time, bus) = (
+                caps.http.clone(),
+                caps.key_value.clone(),
+                caps.time.clone(),
+                caps.bus.clone(),
+            );
+
+            match event {
+                Event::Start => {
+                    caps.bus.subscribe(Event::Session);
+                    caps.compose.spawn(|ctx| async move {
+                        let restored = session.restore(&key_value).await;
+                        ctx.update_app(Event::Done(restored.clone().map(|_| ())));
+
+                        if restored == Ok(SessionState::Authenticated) {
+                            session.keep_fresh(&http, &key_value, &time, &bus).await;
+                        }
+                    });
+                }
+                Event::LogIn(tokens) => caps.compose.spawn(|ctx| async move {
+                    let logged_in = session.login(tokens, &key_value, &bus).await;
+                    ctx.update_app(Event::Done(logged_in.clone()));
+
+                    if logged_in.is_ok() {
+                        session.keep_fresh(&http, &key_value, &time, &bus).await;
+                    }
+                }),
+                Event::LogOut => caps.compose.spawn(|ctx| async move {
+                    ctx.update_app(Event::Done(session.logout(&key_value, &bus).await));
+                }),
+                Event::FetchProfile => caps
+                    .http
+                    .get("https://api.example.com/profile")
+                    .middleware(session.authorize())
+                    .send(Event::Profile),
+                Event::Done(result) => model.error = result.err(),
+                Event::Session(event) => model.events.push(event),
+                Event::Profile(response) => {
+                    model.error = response.err().map(|error| SessionError::Http { error })
+                }
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpRequest, HttpResponse, HttpResult};
+    use crux_kv::{KeyValueOperation, KeyValueResponse, KeyValueResult};
+    use crux_session::{SessionEvent, SessionState, Tokens};
+    use crux_time::{Instant, TimeRequest, TimeResponse};
+    use serde_json::json;
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    /// The shell: an in-memory key-value store, a clock and an HTTP server
+    struct Shell<'a> {
+        store: BTreeMap<String, Vec<u8>>,
+        now: u64,
+        server: &'a mut dyn FnMut(&HttpRequest) -> HttpResponse,
+        requests: Vec<HttpRequest>,
+    }
+
+    impl Shell<'_> {
+        /// Runs the app until it settles, or it waits for longer than `max_wait` seconds.
+        /// Time passes instantly.
+        fn run(
+            &mut self,
+            app: &AppTester<App, Effect>,
+            model: &mut Model,
+            event: Event,
+            max_wait: u64,
+        ) {
+            let mut events = vec![event];
+            let deadline = self.now + max_wait;
+
+            while let Some(event) = events.pop() {
+                let update = app.update(event, model);
+                let mut effects = update.effects;
+                events.extend(update.events);
+
+                while let Some(effect) = effects.pop() {
+                    let update = match effect {
+                        Effect::KeyValue(mut request) => {
+                            let response = match request.operation.clone() {
+                                KeyValueOperation::Get { key } => KeyValueResponse::Get {
+                                    value: self.store.get(&key).cloned().unwrap_or_default(),
+                                    version: 1,
+                                },
+                                KeyValueOperation::Set { key, value, .. } => {
+                                    KeyValueResponse::Set {
+                                        previous: self.store.insert(key, value).unwrap_or_default(),
+                                        version: 1,
+                                    }
+                                }
+                                KeyValueOperation::Delete { key } => KeyValueResponse::Delete {
+                                    previous: self.store.remove(&key).unwrap_or_default(),
+                                },
+                                operation => panic!("unexpected operation {operation:?}"),
+                            };
+                            app.resolve(&mut request, KeyValueResult::Ok { response })
+                        }
+                        Effect::Http(mut request) => {
+                            let response = (self.server)(&request.operation);
+                            self.requests.push(request.operation.clone());
+                            app.resolve(&mut request, HttpResult::Ok(response))
+                        }
+                        Effect::Time(mut request) => match request.operation.clone() {
+                            TimeRequest::Now => {
+                                let now = Instant::new(self.now, 0).unwrap();
+                                app.resolve(&mut request, TimeResponse::Now(now))
+                            }
+                            TimeRequest::NotifyAfter(duration) => {
+                                let nanos =
+                                    serde_json::to_value(duration).unwrap()["nanos"].as_u64();
+                                let seconds = nanos.unwrap() / 1_000_000_000;
+                                if self.now + seconds > deadline {
+                                    continue;
+                                }
+                                self.now += seconds;
+                                app.resolve(&mut request, TimeResponse::DurationElapsed)
+                            }
+                            operation => panic!("unexpected operation {operation:?}"),
+                        },
+                    }
+                    .unwrap();
+
+                    effects.extend(update.effects);
+                    events.extend(update.events);
+                }
+            }
+        }
+    }
+
+    fn tokens(access_token: &str, expires_at: u64) -> Tokens {
+        Tokens {
+            access_token: access_token.to_string(),
+            refresh_token: Some(format!("refresh-{access_token}")),
+            expires_at,
+        }
+    }
+
+    fn header<'a>(request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+
+    #[test]
+    fn refreshes_before_expiry_and_authorizes_requests() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut server = |request: &HttpRequest| match request.url.as_str() {
+            "https://auth.example.com/refresh" => {
+                assert_eq!(
+                    serde_json::from_slice::<serde_json::Value>(&request.body).unwrap(),
+                    json!({"refreshToken": "refresh-first"})
+                );
+                HttpResponse::ok()
+                    .json(json!({
+                        "accessToken": "second",
+                        "refreshToken": "refresh-second",
+                        "expiresAt": 10_000
+                    }))
+                    .build()
+            }
+            _ => HttpResponse::ok().build(),
+        };
+        let mut shell = Shell {
+            store: BTreeMap::new(),
+            now: 1000,
+            server: &mut server,
+            requests: Vec::new(),
+        };
+
+        shell.run(&app, &mut model, Event::Start, 0);
+        assert_eq!(model.session.state(shell.now), SessionState::Anonymous);
+
+        shell.run(&app, &mut model, Event::LogIn(tokens("first", 2000)), 5000);
+        assert_eq!(model.events, vec![SessionEvent::LoggedIn]);
+
+        // refreshed a minute before the expiry
+        assert_eq!(shell.requests.len(), 1);
+        assert_eq!(shell.now, 1940);
+        assert_eq!(model.session.access_token().as_deref(), Some("second"));
+        assert_eq!(model.session.state(shell.now), SessionState::Authenticated);
+        let stored: Tokens = serde_json::from_slice(&shell.store["session"]).unwrap();
+        assert_eq!(stored.access_token, "second");
+
+        shell.run(&app, &mut model, Event::FetchProfile, 0);
+        assert_eq!(
+            header(&shell.requests[1], "Authorization"),
+            Some("Bearer second")
+        );
+
+        shell.run(&app, &mut model, Event::LogOut, 0);
+        assert_eq!(
+            model.events,
+            vec![SessionEvent::LoggedIn, SessionEvent::LoggedOut]
+        );
+        assert!(shell.store.is_empty());
+
+        shell.run(&app, &mut model, Event::FetchProfile, 0);
+        assert_eq!(header(&shell.requests[2], "Authorization"), None);
+    }
+
+    #[test]
+    fn expires_when_the_refresh_token_is_rejected() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut server = |_: &HttpRequest| HttpResponse::status(401).build();
+        let mut shell = Shell {
+            store: BTreeMap::from([(
+                "session".to_string(),
+                serde_json::to_vec(&tokens("stored", 1030)).unwrap(),
+            )]),
+            now: 1000,
+            server: &mut server,
+            requests: Vec::new(),
+        };
+
+        // the stored session is restored, and refreshed straight away
+        shell.run(&app, &mut model, Event::Start, 0);
+
+        assert_eq!(shell.requests.len(), 1);
+        assert_eq!(model.events, vec![SessionEvent::Expired]);
+        assert_eq!(model.session.state(shell.now), SessionState::Expired);
+        assert!(shell.store.is_empty());
+    }
+}
@@ -0,0 +1,510 @@
+//! Delta synchronization of collections with a server
+//!
+//! A [`SyncClient`] keeps a local copy of a server-side collection of records up to date, and
+//! sends local changes to the server, even when they're made offline. It uses `crux_http` to talk
+//! to the server and `crux_kv` to persist its state, so it has to be used in an async context,
+//! together with [`crux_core::compose::Compose`].
+//!
+//! For each collection, the client persists
+//!
+//! * a cursor, returned by the server, marking the last change the app has seen
+//! * an outbox of local changes which haven't been acknowledged by the server yet, each with an
+//!   idempotency key, so that the server can recognise a change it has already applied when it
+//!   is sent again after a network failure
+//!
+//! A typical sync first [pushes](SyncClient::push) the outbox, then [pulls](SyncClient::pull)
+//! changes since the cursor, applies them to the model, and finally
+//! [commits](SyncClient::commit) the new cursor.
+//!
+//! # Protocol
+//!
+//! Changes are exchanged as JSON, see [`Change`]. The server is expected to serve
+//!
+//! * `GET {base_url}/{collection}/changes?since={cursor}` - a [`ChangeSet`] of changes after the
+//!   cursor (or all records, if the `since` parameter is missing), in order. If `hasMore` is
+//!   true, the client will fetch the next page with the returned cursor.
+//! * `POST {base_url}/{collection}/mutations` - apply the [`Change`] in the request body. The
+//!   request has an `Idempotency-Key` header. If the change conflicts with the server's version
+//!   of the record, the server responds with `409 Conflict` and its current version of the record
+//!   as a [`Change`], which is given to the client's [resolver](SyncClient::with_resolver).
+
+use std::sync::Arc;
+
+use crux_http::{http::StatusCode, Http, HttpError};
+use crux_kv::{error::KeyValueError, KeyValue};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+/// How many times a conflicting change is retried with the resolver's result before giving up
+const MAX_CONFLICT_RETRIES: usize = 3;
+
+/// Error type for synchronization
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncError {
+    #[error("http error: {error}")]
+    Http { error: HttpError },
+    #[error("storage error: {error}")]
+    Storage { error: KeyValueError },
+    #[error("stored sync state could not be read: {message}")]
+    Corrupt { message: String },
+    #[error("change to {id} still conflicts after {attempts} attempts")]
+    Conflict { id: String, attempts: usize },
+}
+
+/// A change to a record in a collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Change<T> {
+    /// The record was created or updated
+    Upsert { id: String, record: T },
+    /// The record was deleted
+    Delete { id: String },
+}
+
+impl<T> Change<T> {
+    /// The id of the changed record
+    pub fn id(&self) -> &str {
+        match self {
+            Change::Upsert { id, .. } | Change::Delete { id } => id,
+        }
+    }
+}
+
+/// A page of changes, as returned by the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSet<T> {
+    pub changes: Vec<Change<T>>,
+    pub cursor: String,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// How to resolve a local change conflicting with the server's version of the record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution<T> {
+    /// Drop the local change and take the server's version of the record
+    KeepServer,
+    /// Send this change instead, e.g. the local and server versions merged
+    Overwrite(Change<T>),
+}
+
+/// The result of [`SyncClient::push`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pushed<T> {
+    /// The number of local changes accepted by the server
+    pub accepted: usize,
+    /// Server versions of records which replaced local changes when resolving conflicts.
+    /// These should be applied to the model.
+    pub server_wins: Vec<Change<T>>,
+}
+
+/// The result of [`SyncClient::pull`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pulled<T> {
+    /// Changes since the last committed cursor, in order
+    pub changes: Vec<Change<T>>,
+    /// The cursor to [commit](SyncClient::commit) once the changes have been applied
+    pub cursor: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Pending<T> {
+    idempotency_key: String,
+    change: Change<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Outbox<T> {
+    next_sequence: u64,
+    pending: Vec<Pending<T>>,
+}
+
+impl<T> Default for Outbox<T> {
+    fn default() -> Self {
+        Self {
+            next_sequence: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<T> Outbox<T> {
+    fn push(&mut self, client_id: &str, collection: &str, change: Change<T>) {
+        let pending = self.pending_change(client_id, collection, change);
+        self.pending.push(pending);
+    }
+
+    /// Assign the change a new idempotency key
+    fn pending_change(
+        &mut self,
+        client_id: &str,
+        collection: &str,
+        change: Change<T>,
+    ) -> Pending<T> {
+        let idempotency_key = format!("{client_id}:{collection}:{}", self.next_sequence);
+        self.next_sequence += 1;
+
+        Pending {
+            idempotency_key,
+            change,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Since<'a> {
+    since: &'a str,
+}
+
+type Resolver<T> = Arc<dyn Fn(&Change<T>, &Change<T>) -> Resolution<T> + Send + Sync>;
+
+/// Synchronizes a collection of records of type `T` with a server.
+pub struct SyncClient<T> {
+    base_url: String,
+    collection: String,
+    client_id: String,
+    resolver: Resolver<T>,
+}
+
+impl<T> Clone for SyncClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            base_url: self.base_url.clone(),
+            collection: self.collection.clone(),
+            client_id: self.client_id.clone(),
+            resolver: self.resolver.clone(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for SyncClient<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncClient")
+            .field("base_url", &self.base_url)
+            .field("collection", &self.collection)
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SyncClient<T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    /// Create a client for `collection` on the server at `base_url`.
+    ///
+    /// The `client_id` must be unique to this installation of the app (e.g. generated once and
+    /// stored), it makes idempotency keys unique across clients.
+    ///
+    /// Conflicts are resolved in favour of the server, see [`SyncClient::with_resolver`].
+    pub fn new(
+        base_url: impl Into<String>,
+        collection: impl Into<String>,
+        client_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            collection: collection.into(),
+            client_id: client_id.into(),
+            resolver: Arc::new(|_, _| Resolution::KeepServer),
+        }
+    }
+
+    /// Resolve conflicts with `resolver`, which is called with the local change and the
+    /// server's version of the record.
+    pub fn with_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&Change<T>, &Change<T>) -> Resolution<T> + Send + Sync + 'static,
+    {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Add a local change to the outbox, to be sent with the next [`SyncClient::push`].
+    pub async fn queue<Ev>(
+        &self,
+        key_value: &KeyValue<Ev>,
+        change: Change<T>,
+    ) -> Result<(), SyncError>
+    where
+        Ev: 'static,
+    {
+        let mut outbox = self.load_outbox(key_value).await?;
+        outbox.push(&self.client_id, &self.collection, change);
+
+        self.save_outbox(key_value, &outbox).await
+    }
+
+    /// The local changes in the outbox, oldest first.
+    pub async fn pending<Ev>(&self, key_value: &KeyValue<Ev>) -> Result<Vec<Change<T>>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let outbox = self.load_outbox(key_value).await?;
+
+        Ok(outbox.pending.into_iter().map(|p| p.change).collect())
+    }
+
+    /// Send the changes in the outbox to the server, in order. Each change is removed from the
+    /// outbox once the server has accepted it, so if pushing fails, it can be retried later.
+    pub async fn push<Ev>(
+        &self,
+        http: &Http<Ev>,
+        key_value: &KeyValue<Ev>,
+    ) -> Result<Pushed<T>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let mut outbox = self.load_outbox(key_value).await?;
+        let mut pushed = Pushed {
+            accepted: 0,
+            server_wins: Vec::new(),
+        };
+        let mut attempts = 0;
+
+        while let Some(pending) = outbox.pending.first().cloned() {
+            match self.send(http, &pending).await? {
+                None => {
+                    outbox.pending.remove(0);
+                    pushed.accepted += 1;
+                    attempts = 0;
+                }
+                Some(server) => {
+                    attempts += 1;
+                    if attempts > MAX_CONFLICT_RETRIES {
+                        return Err(SyncError::Conflict {
+                            id: pending.change.id().to_string(),
+                            attempts: MAX_CONFLICT_RETRIES,
+                        });
+                    }
+
+                    outbox.pending.remove(0);
+                    match (self.resolver)(&pending.change, &server) {
+                        Resolution::KeepServer => {
+                            pushed.server_wins.push(server);
+                            attempts = 0;
+                        }
+                        Resolution::Overwrite(change) => {
+                            // a new change, so it needs a new idempotency key
+                            let retry =
+                                outbox.pending_change(&self.client_id, &self.collection, change);
+                            outbox.pending.insert(0, retry);
+                        }
+                    }
+                }
+            }
+
+            self.save_outbox(key_value, &outbox).await?;
+        }
+
+        Ok(pushed)
+    }
+
+    /// Fetch the changes since the last committed cursor from the server. The cursor is not
+    /// advanced until the changes are applied and the returned cursor is
+    /// [committed](SyncClient::commit), so changes are never lost if the app stops in between.
+    pub async fn pull<Ev>(
+        &self,
+        http: &Http<Ev>,
+        key_value: &KeyValue<Ev>,
+    ) -> Result<Pulled<T>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let mut cursor = self.cursor(key_value).await?;
+        let mut changes = Vec::new();
+
+        loop {
+            let page = self.fetch(http, cursor.as_deref()).await?;
+            changes.extend(page.changes);
+            cursor = Some(page.cursor);
+
+            if !page.has_more {
+                break;
+            }
+        }
+
+        Ok(Pulled {
+            changes,
+            cursor: cursor.unwrap_or_default(),
+        })
+    }
+
+    /// Persist `cursor`, returned by [`SyncClient::pull`], once its changes have been applied.
+    pub async fn commit<Ev>(
+        &self,
+        key_value: &KeyValue<Ev>,
+        cursor: String,
+    ) -> Result<(), SyncError>
+    where
+        Ev: 'static,
+    {
+        key_value
+            .set_async(self.key("cursor"), cursor.into_bytes())
+            .await
+            .map(|_| ())
+            .map_err(|error| SyncError::Storage { error })
+    }
+
+    /// The last committed cursor, if any
+    pub async fn cursor<Ev>(&self, key_value: &KeyValue<Ev>) -> Result<Option<String>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let bytes = key_value
+            .get_async(self.key("cursor"))
+            .await
+            .map_err(|error| SyncError::Storage { error })?;
+
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        String::from_utf8(bytes)
+            .map(Some)
+            .map_err(|e| SyncError::Corrupt {
+                message: e.to_string(),
+            })
+    }
+
+    /// Send a change to the server. Returns the server's version of the record on a conflict.
+    async fn send<Ev>(
+        &self,
+        http: &Http<Ev>,
+        pending: &Pending<T>,
+    ) -> Result<Option<Change<T>>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let error = |error| SyncError::Http { error };
+
+        let mut response = http
+            .post(format!("{}/{}/mutations", self.base_url, self.collection))
+            .header("Idempotency-Key", pending.idempotency_key.as_str())
+            .body_json(&pending.change)
+            .map_err(error)?
+            .send_async()
+            .await
+            .map_err(error)?;
+
+        match response.status() {
+            status if status.is_success() => Ok(None),
+            StatusCode::Conflict => response.body_json().await.map(Some).map_err(error),
+            code => Err(error(HttpError::Http {
+                code,
+                message: format!("pushing a change to {} failed", self.collection),
+                body: response.body_bytes().await.ok(),
+            })),
+        }
+    }
+
+    async fn fetch<Ev>(
+        &self,
+        http: &Http<Ev>,
+        since: Option<&str>,
+    ) -> Result<ChangeSet<T>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let error = |error| SyncError::Http { error };
+
+        let mut request = http.get(format!("{}/{}/changes", self.base_url, self.collection));
+        if let Some(since) = since {
+            request = request.query(&Since { since }).map_err(error)?;
+        }
+
+        let mut response = request.send_async().await.map_err(error)?;
+
+        if !response.status().is_success() {
+            return Err(error(HttpError::Http {
+                code: response.status(),
+                message: format!("fetching changes to {} failed", self.collection),
+                body: response.body_bytes().await.ok(),
+            }));
+        }
+
+        response.body_json().await.map_err(error)
+    }
+
+    async fn load_outbox<Ev>(&self, key_value: &KeyValue<Ev>) -> Result<Outbox<T>, SyncError>
+    where
+        Ev: 'static,
+    {
+        let bytes = key_value
+            .get_async(self.key("outbox"))
+            .await
+            .map_err(|error| SyncError::Storage { error })?;
+
+        if bytes.is_empty() {
+            return Ok(Outbox::default());
+        }
+
+        serde_json::from_slice(&bytes).map_err(|e| SyncError::Corrupt {
+            message: e.to_string(),
+        })
+    }
+
+    async fn save_outbox<Ev>(
+        &self,
+        key_value: &KeyValue<Ev>,
+        outbox: &Outbox<T>,
+    ) -> Result<(), SyncError>
+    where
+        Ev: 'static,
+    {
+        let bytes = serde_json::to_vec(outbox).map_err(|e| SyncError::Corrupt {
+            message: e.to_string(),
+        })?;
+
+        key_value
+            .set_async(self.key("outbox"), bytes)
+            .await
+            .map(|_| ())
+            .map_err(|error| SyncError::Storage { error })
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("sync/{}/{name}", self.collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_serialization() {
+        let change = Change::Upsert {
+            id: "1".to_string(),
+            record: serde_json::json!({"title": "Milk"}),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            serde_json::json!({"type": "upsert", "id": "1", "record": {"title": "Milk"}})
+        );
+        assert_eq!(
+            serde_json::from_str::<Change<serde_json::Value>>(r#"{"type":"delete","id":"2"}"#)
+                .unwrap(),
+            Change::Delete {
+                id: "2".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn idempotency_keys_are_unique_per_client_and_collection() {
+        let mut outbox = Outbox::<()>::default();
+        outbox.push("phone", "todos", Change::Delete { id: "1".into() });
+        outbox.push("phone", "todos", Change::Delete { id: "1".into() });
+
+        let keys: Vec<_> = outbox
+            .pending
+            .iter()
+            .map(|p| p.idempotency_key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["phone:todos:0", "phone:todos:1"]);
+    }
+}
@@ -0,0 +1,364 @@
+mod shared {
+    use std::collections::BTreeMap;
+
+    use crux_core::{compose::Compose, macros::Effect};
+    use crux_http::Http;
+    use crux_kv::KeyValue;
+    use crux_sync::{Change, Pulled, Pushed, Resolution, SyncClient, SyncError};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum Event {
+        Edit(String, String),
+        Sync,
+        Commit(String),
+
+        #[serde(skip)]
+        Queued(Result<(), SyncError>),
+        #[serde(skip)]
+        Synced(Result<(Pushed<String>, Pulled<String>), SyncError>),
+        #[serde(skip)]
+        Committed(Result<(), SyncError>),
+    }
+
+    pub struct Model {
+        pub client: SyncClient<String>,
+        pub todos: BTreeMap<String, String>,
+        pub error: Option<SyncError>,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            Self {
+                // concatenate conflicting titles
+                client: SyncClient::new("https://sync.example.com/", "todos", "phone")
+                    .with_resolver(|local, server| match (local, server) {
+                        (
+                            Change::Upsert { id, record: mine },
+                            Change::Upsert { record: theirs, .. },
+                        ) => Resolution::Overwrite(Change::Upsert {
+                            id: id.clone(),
+                            record: format!("{theirs} / {mine}"),
+                        }),
+                        _ => Resolution::KeepServer,
+                    }),
+                todos: BTreeMap::new(),
+                error: None,
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub key_value: KeyValue<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    fn apply(todos: &mut BTreeMap<String, String>, changes: Vec<Change<String>>) {
+        for change in changes {
+            match change {
+                Change::Upsert { id, record } => {
+                    todos.insert(id, record);
+                }
+                Change::Delete { id } => {
+                    todos.remove(&id);
+                }
+            }
+        }
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            let client = model.client.clone();
+            let http = caps.http.clone();
+            let key_value = caps.key_value.clone();
+
+            match event {
+                Event::Edit(id, title) => {
+                    model.todos.insert(id.clone(), title.clone());
+
+                    caps.compose.spawn(|ctx| async move {
+                        let change = Change::Upsert { id, record: title };
+                        ctx.update_app(Event::Queued(client.queue(&key_value, change).await));
+                    });
+                }
+                Event::Sync => caps.compose.spawn(|ctx| async move {
+                    let result = async {
+                        let pushed = client.push(&http, &key_value).await?;
+                        let pulled = client.pull(&http, &key_value).await?;
+                        Ok((pushed, pulled))
+                    };
+
+                    ctx.update_app(Event::Synced(result.await));
+                }),
+                Event::Commit(cursor) => caps.compose.spawn(|ctx| async move {
+                    ctx.update_app(Event::Committed(client.commit(&key_value, cursor).await));
+                }),
+                Event::Synced(Ok((pushed, pulled))) => {
+                    apply(&mut model.todos, pushed.server_wins);
+                    apply(&mut model.todos, pulled.changes);
+
+                    self.update(Event::Commit(pulled.cursor), model, caps);
+                }
+                Event::Queued(result) | Event::Committed(result) => model.error = result.err(),
+                Event::Synced(Err(error)) => model.error = Some(error),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpRequest, HttpResponse, HttpResult};
+    use crux_kv::{KeyValueOperation, KeyValueResponse, KeyValueResult};
+    use crux_sync::SyncError;
+    use serde_json::json;
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    /// Runs the app until it settles, handling key-value operations with an in-memory store and
+    /// HTTP requests with `server`. Returns the HTTP requests made.
+    fn run(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        store: &mut BTreeMap<String, Vec<u8>>,
+        event: Event,
+        server: &mut dyn FnMut(&HttpRequest) -> HttpResponse,
+    ) -> Vec<HttpRequest> {
+        let mut requests = Vec::new();
+        let mut events = vec![event];
+
+        while let Some(event) = events.pop() {
+            let mut effects = app.update(event, model).effects;
+
+            while let Some(effect) = effects.pop() {
+                let update = match effect {
+                    Effect::KeyValue(mut request) => {
+                        let response = match request.operation.clone() {
+                            KeyValueOperation::Get { key } => KeyValueResponse::Get {
+                                value: store.get(&key).cloned().unwrap_or_default(),
+                                version: 1,
+                            },
+                            KeyValueOperation::Set { key, value, .. } => KeyValueResponse::Set {
+                                previous: store.insert(key, value).unwrap_or_default(),
+                                version: 1,
+                            },
+                            operation => panic!("unexpected operation {operation:?}"),
+                        };
+                        app.resolve(&mut request, KeyValueResult::Ok { response })
+                    }
+                    Effect::Http(mut request) => {
+                        let response = server(&request.operation);
+                        requests.push(request.operation.clone());
+                        app.resolve(&mut request, HttpResult::Ok(response))
+                    }
+                }
+                .unwrap();
+
+                effects.extend(update.effects);
+                events.extend(update.events);
+            }
+        }
+
+        requests
+    }
+
+    fn header<'a>(request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+
+    fn body(request: &HttpRequest) -> serde_json::Value {
+        serde_json::from_slice(&request.body).unwrap()
+    }
+
+    #[test]
+    fn pushes_outbox_then_pulls_pages() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut store = BTreeMap::new();
+
+        let mut offline = |_: &HttpRequest| panic!("no requests while editing");
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Edit("1".into(), "Milk".into()),
+            &mut offline,
+        );
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Edit("2".into(), "Eggs".into()),
+            &mut offline,
+        );
+
+        let requests = run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Sync,
+            &mut |request| match (request.method.as_str(), request.url.as_str()) {
+                ("POST", "https://sync.example.com/todos/mutations") => HttpResponse::ok().build(),
+                ("GET", "https://sync.example.com/todos/changes") => HttpResponse::ok()
+                    .json(json!({
+                        "changes": [{"type": "upsert", "id": "3", "record": "Bread"}],
+                        "cursor": "c1",
+                        "hasMore": true
+                    }))
+                    .build(),
+                ("GET", "https://sync.example.com/todos/changes?since=c1") => HttpResponse::ok()
+                    .json(json!({
+                        "changes": [{"type": "delete", "id": "1"}],
+                        "cursor": "c2"
+                    }))
+                    .build(),
+                _ => panic!("unexpected request {request:?}"),
+            },
+        );
+
+        assert_eq!(requests.len(), 4);
+        assert_eq!(
+            header(&requests[0], "Idempotency-Key"),
+            Some("phone:todos:0")
+        );
+        assert_eq!(
+            body(&requests[0]),
+            json!({"type": "upsert", "id": "1", "record": "Milk"})
+        );
+        assert_eq!(
+            header(&requests[1], "Idempotency-Key"),
+            Some("phone:todos:1")
+        );
+
+        assert_eq!(model.error, None);
+        assert_eq!(
+            model.todos.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("2".to_string(), "Eggs".to_string()),
+                ("3".to_string(), "Bread".to_string())
+            ]
+        );
+        assert_eq!(store["sync/todos/cursor"], b"c2");
+
+        // the next sync starts from the committed cursor
+        let mut model = Model::default();
+        let requests = run(&app, &mut model, &mut store, Event::Sync, &mut |_| {
+            HttpResponse::ok()
+                .json(json!({"changes": [], "cursor": "c2"}))
+                .build()
+        });
+        assert_eq!(
+            requests.iter().map(|r| r.url.as_str()).collect::<Vec<_>>(),
+            vec!["https://sync.example.com/todos/changes?since=c2"]
+        );
+    }
+
+    #[test]
+    fn failed_push_keeps_the_outbox() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut store = BTreeMap::new();
+
+        let mut offline = |_: &HttpRequest| panic!("no requests while editing");
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Edit("1".into(), "Milk".into()),
+            &mut offline,
+        );
+
+        run(&app, &mut model, &mut store, Event::Sync, &mut |_| {
+            HttpResponse::status(503).build()
+        });
+        assert!(matches!(model.error, Some(SyncError::Http { .. })));
+
+        // retried with the same idempotency key
+        let requests = run(&app, &mut model, &mut store, Event::Sync, &mut |request| {
+            if request.method == "POST" {
+                HttpResponse::ok().build()
+            } else {
+                HttpResponse::ok()
+                    .json(json!({"changes": [], "cursor": "c1"}))
+                    .build()
+            }
+        });
+        assert_eq!(
+            header(&requests[0], "Idempotency-Key"),
+            Some("phone:todos:0")
+        );
+        assert_eq!(requests.len(), 2);
+    }
+
+    #[test]
+    fn resolves_conflicts() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+        let mut store = BTreeMap::new();
+
+        let mut offline = |_: &HttpRequest| panic!("no requests while editing");
+        run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Edit("1".into(), "Milk".into()),
+            &mut offline,
+        );
+
+        let mut conflicted = false;
+        let requests = run(
+            &app,
+            &mut model,
+            &mut store,
+            Event::Sync,
+            &mut |request| match request.method.as_str() {
+                "POST" if !conflicted => {
+                    conflicted = true;
+                    HttpResponse::status(409)
+                        .json(json!({"type": "upsert", "id": "1", "record": "Oat milk"}))
+                        .build()
+                }
+                "POST" => HttpResponse::ok().build(),
+                _ => HttpResponse::ok()
+                    .json(json!({
+                        "changes": [{"type": "upsert", "id": "1", "record": "Oat milk / Milk"}],
+                        "cursor": "c1"
+                    }))
+                    .build(),
+            },
+        );
+
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            body(&requests[1]),
+            json!({"type": "upsert", "id": "1", "record": "Oat milk / Milk"})
+        );
+        // the merged change is a new change, with a new key
+        assert_eq!(
+            header(&requests[1], "Idempotency-Key"),
+            Some("phone:todos:1")
+        );
+
+        assert_eq!(model.error, None);
+        assert_eq!(model.todos["1"], "Oat milk / Milk");
+    }
+}
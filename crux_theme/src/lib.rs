@@ -0,0 +1,155 @@
+//! Theming for Crux apps
+//!
+//! The [`Theme`] type holds design tokens (colors, spacing and typography) keyed by semantic names,
+//! which the core can include in its view model, so that all shells render with the same design.
+//! The [`Appearance`] capability lets the app find out the user's appearance preference from
+//! the shell (light or dark, standard or high contrast) and follow its changes, so that the
+//! core can pick the right theme with [`Themes::select`].
+
+pub mod theme;
+
+pub use theme::{Color, FontWeight, TextStyle, Theme, Themes};
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AppearanceRequest {
+    /// Get the current appearance preference
+    Get,
+    /// Get the current appearance preference, and then every time it changes
+    Watch,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Contrast {
+    #[default]
+    Standard,
+    High,
+}
+
+/// The user's appearance preference, as reported by the shell
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppearancePreference {
+    pub color_scheme: ColorScheme,
+    pub contrast: Contrast,
+}
+
+impl AppearancePreference {
+    pub const LIGHT: AppearancePreference = AppearancePreference {
+        color_scheme: ColorScheme::Light,
+        contrast: Contrast::Standard,
+    };
+    pub const DARK: AppearancePreference = AppearancePreference {
+        color_scheme: ColorScheme::Dark,
+        contrast: Contrast::Standard,
+    };
+}
+
+impl Operation for AppearanceRequest {
+    type Output = AppearancePreference;
+}
+
+/// The Appearance capability API
+///
+/// This capability lets the app ask the shell for the user's appearance preference, and
+/// to be notified when it changes.
+#[derive(crux_core::macros::Capability)]
+pub struct Appearance<Ev> {
+    context: CapabilityContext<AppearanceRequest, Ev>,
+}
+
+impl<Ev> Clone for Appearance<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Appearance<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<AppearanceRequest, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Request the current appearance preference, which will be passed to the app
+    /// wrapped in the event produced by the `callback`.
+    pub fn get<F>(&self, callback: F)
+    where
+        F: FnOnce(AppearancePreference) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.get_async().await));
+            }
+        });
+    }
+
+    /// Request the current appearance preference.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn get_async(&self) -> AppearancePreference {
+        self.context
+            .request_from_shell(AppearanceRequest::Get)
+            .await
+    }
+
+    /// Ask to receive the current appearance preference, and then the new preference every
+    /// time the user changes it. Each preference is passed to the app wrapped in the event
+    /// produced by the `callback`.
+    pub fn watch<F>(&self, callback: F)
+    where
+        F: Fn(AppearancePreference) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(AppearanceRequest::Watch);
+
+                while let Some(preference) = stream.next().await {
+                    context.update_app(callback(preference));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let serialized = serde_json::to_string(&AppearanceRequest::Watch).unwrap();
+        assert_eq!(&serialized, r#""watch""#);
+
+        let preference = AppearancePreference {
+            color_scheme: ColorScheme::Dark,
+            contrast: Contrast::High,
+        };
+
+        let serialized = serde_json::to_string(&preference).unwrap();
+        assert_eq!(&serialized, r#"{"colorScheme":"dark","contrast":"high"}"#);
+
+        let deserialized: AppearancePreference = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(preference, deserialized);
+    }
+}
@@ -0,0 +1,201 @@
+//! Design tokens for the view model.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::AppearancePreference;
+
+/// An sRGB color with an alpha channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Color {
+    /// An opaque color
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha: 255,
+        }
+    }
+
+    /// Parse a color from a `#rrggbb` or `#rrggbbaa` hex string. The leading `#` is optional.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+
+        match hex.len() {
+            6 => Some(Self::rgb(channel(0)?, channel(2)?, channel(4)?)),
+            8 => Some(Self {
+                red: channel(0)?,
+                green: channel(2)?,
+                blue: channel(4)?,
+                alpha: channel(6)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Font weight, on the usual 100 (thin) to 900 (black) scale
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const REGULAR: FontWeight = FontWeight(400);
+    pub const MEDIUM: FontWeight = FontWeight(500);
+    pub const BOLD: FontWeight = FontWeight(700);
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        Self::REGULAR
+    }
+}
+
+/// A text style. Sizes are in points (iOS), scale-independent pixels (Android) or CSS pixels (Web),
+/// and shells are expected to scale them with the user's preferred text size.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStyle {
+    /// The font family, empty for the platform's default
+    pub family: String,
+    pub size: f64,
+    pub weight: FontWeight,
+    /// Line height as a multiple of the size
+    pub line_height: f64,
+}
+
+/// A set of design tokens, keyed by semantic names (e.g. `"background"`, `"accent"`, `"body"`)
+/// rather than by their values, so that shells style their UI by meaning and the values
+/// are controlled by the core.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    /// The appearance this theme was designed for
+    pub appearance: AppearancePreference,
+    pub colors: BTreeMap<String, Color>,
+    /// Spacing and sizing tokens, in the same units as [`TextStyle::size`]
+    pub spacing: BTreeMap<String, f64>,
+    pub typography: BTreeMap<String, TextStyle>,
+}
+
+impl Theme {
+    /// An empty theme for the given appearance
+    pub fn new(appearance: AppearancePreference) -> Self {
+        Self {
+            appearance,
+            ..Default::default()
+        }
+    }
+
+    pub fn color(mut self, name: impl Into<String>, color: Color) -> Self {
+        self.colors.insert(name.into(), color);
+        self
+    }
+
+    pub fn spacing(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.spacing.insert(name.into(), value);
+        self
+    }
+
+    pub fn text_style(mut self, name: impl Into<String>, style: TextStyle) -> Self {
+        self.typography.insert(name.into(), style);
+        self
+    }
+}
+
+/// The themes of an app, one for each appearance it supports. Keep a `Themes` in the model,
+/// and use [`Themes::select`] in `view` to pick the theme for the shell's current appearance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Themes {
+    themes: Vec<Theme>,
+}
+
+impl Themes {
+    /// Create a set of themes, with `default` used for appearances without a better match.
+    pub fn new(default: Theme) -> Self {
+        Self {
+            themes: vec![default],
+        }
+    }
+
+    /// Add a theme, replacing an existing theme designed for the same appearance.
+    pub fn with(mut self, theme: Theme) -> Self {
+        match self
+            .themes
+            .iter_mut()
+            .find(|t| t.appearance == theme.appearance)
+        {
+            Some(existing) => *existing = theme,
+            None => self.themes.push(theme),
+        }
+        self
+    }
+
+    /// The theme for `preference`. An exact match is preferred, then a theme with the same
+    /// color scheme, then a theme with the same contrast, and finally the default theme.
+    pub fn select(&self, preference: &AppearancePreference) -> &Theme {
+        let scheme = |t: &&Theme| t.appearance.color_scheme == preference.color_scheme;
+        let contrast = |t: &&Theme| t.appearance.contrast == preference.contrast;
+
+        self.themes
+            .iter()
+            .find(|t| scheme(t) && contrast(t))
+            .or_else(|| self.themes.iter().find(scheme))
+            .or_else(|| self.themes.iter().find(contrast))
+            .unwrap_or(&self.themes[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorScheme, Contrast};
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(Color::from_hex("#ff8000"), Some(Color::rgb(255, 128, 0)));
+        assert_eq!(
+            Color::from_hex("00000080"),
+            Some(Color {
+                red: 0,
+                green: 0,
+                blue: 0,
+                alpha: 128
+            })
+        );
+        assert_eq!(Color::from_hex("#fff"), None);
+        assert_eq!(Color::from_hex("#gg0000"), None);
+    }
+
+    #[test]
+    fn selects_closest_theme() {
+        let light =
+            Theme::new(AppearancePreference::LIGHT).color("background", Color::rgb(255, 255, 255));
+        let dark = Theme::new(AppearancePreference::DARK).color("background", Color::rgb(0, 0, 0));
+        let themes = Themes::new(light.clone()).with(dark.clone());
+
+        assert_eq!(themes.select(&AppearancePreference::DARK), &dark);
+
+        let dark_high_contrast = AppearancePreference {
+            color_scheme: ColorScheme::Dark,
+            contrast: Contrast::High,
+        };
+        assert_eq!(themes.select(&dark_high_contrast), &dark);
+
+        let high_contrast_dark = Theme::new(dark_high_contrast);
+        let themes = themes.with(high_contrast_dark.clone());
+        assert_eq!(themes.select(&dark_high_contrast), &high_contrast_dark);
+        assert_eq!(themes.select(&AppearancePreference::LIGHT), &light);
+    }
+}
@@ -0,0 +1,102 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_theme::{Appearance, AppearancePreference, Color, Theme, Themes};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        WatchAppearance,
+        AppearanceChanged(AppearancePreference),
+    }
+
+    pub struct Model {
+        pub appearance: AppearancePreference,
+        pub themes: Themes,
+    }
+
+    impl Default for Model {
+        fn default() -> Self {
+            let light = Theme::new(AppearancePreference::LIGHT)
+                .color("background", Color::rgb(255, 255, 255))
+                .spacing("gutter", 16.0);
+            let dark = Theme::new(AppearancePreference::DARK)
+                .color("background", Color::rgb(0, 0, 0))
+                .spacing("gutter", 16.0);
+
+            Self {
+                appearance: AppearancePreference::default(),
+                themes: Themes::new(light).with(dark),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ViewModel {
+        pub theme: Theme,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ViewModel;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::WatchAppearance => caps.appearance.watch(Event::AppearanceChanged),
+                Event::AppearanceChanged(appearance) => {
+                    model.appearance = appearance;
+                    caps.render.render()
+                }
+            }
+        }
+
+        fn view(&self, model: &Self::Model) -> Self::ViewModel {
+            ViewModel {
+                theme: model.themes.select(&model.appearance).clone(),
+            }
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub appearance: Appearance<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_theme::{AppearancePreference, AppearanceRequest, Color};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    #[test]
+    fn follows_appearance_changes() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::WatchAppearance, &mut model);
+        let Some(Effect::Appearance(mut request)) = update.into_effects().next() else {
+            panic!("Expected Appearance effect");
+        };
+        assert_eq!(request.operation, AppearanceRequest::Watch);
+
+        for (preference, background) in [
+            (AppearancePreference::DARK, Color::rgb(0, 0, 0)),
+            (AppearancePreference::LIGHT, Color::rgb(255, 255, 255)),
+        ] {
+            let update = app.resolve(&mut request, preference).unwrap();
+            for event in update.events {
+                app.update(event, &mut model);
+            }
+
+            let view = app.view(&model);
+            assert_eq!(view.theme.colors["background"], background);
+        }
+    }
+}
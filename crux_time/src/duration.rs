@@ -43,6 +43,12 @@ impl Duration {
     }
 }
 
+impl From<Duration> for std::time::Duration {
+    fn from(value: Duration) -> Self {
+        std::time::Duration::from_nanos(value.nanos)
+    }
+}
+
 #[cfg(feature = "chrono")]
 impl TryFrom<chrono::TimeDelta> for Duration {
     type Error = TimeError;
@@ -1,3 +1,4 @@
+use crux_core::error::CapabilityError;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -13,4 +14,12 @@ pub enum TimeError {
     InvalidDuration,
     #[error("invalid Instant")]
     InvalidInstant,
+    #[error("invalid recurrence rule: {0}")]
+    InvalidRecurrence(String),
+}
+
+impl From<TimeError> for CapabilityError {
+    fn from(e: TimeError) -> Self {
+        Self::InvalidData(e.to_string())
+    }
 }
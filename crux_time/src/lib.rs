@@ -3,18 +3,27 @@
 //! Current time (on a wall clock) is considered a side-effect (although if we were to get pedantic, it's
 //! more of a side-cause) by Crux, and has to be obtained externally. This capability provides a simple
 //! interface to do so.
+//!
+//! Besides the raw timer operations, [`Time`] has helpers for the usual scheduling of
+//! reminders: after a number of seconds, at the next occurrence of a time of day, or on a
+//! day of the week, both on the user's local wall clock. For anything more involved, such as
+//! "the last Friday of every month", [`Recurrence`] follows iCalendar recurrence rules.
 
 pub mod duration;
 pub mod error;
 pub mod instant;
+pub mod recurrence;
+mod schedule;
 
 pub use duration::Duration;
 pub use error::TimeError;
 pub use instant::Instant;
+pub use recurrence::{LocalDateTime, Recurrence, RecurrenceRule};
+pub use schedule::{TimeOfDay, Weekday};
 
 use serde::{Deserialize, Serialize};
 
-use crux_core::capability::{CapabilityContext, Operation};
+use crux_core::capability::{CapabilityContext, Operation, ShellTimeout, TimesOut};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -22,6 +31,8 @@ pub enum TimeRequest {
     Now,
     NotifyAt(Instant),
     NotifyAfter(Duration),
+    /// The current offset of the local time zone from UTC
+    UtcOffset,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,12 +41,51 @@ pub enum TimeResponse {
     Now(Instant),
     InstantArrived,
     DurationElapsed,
+    /// The offset of the local time zone from UTC, in seconds ahead of UTC
+    /// (e.g. 3600 for UTC+1)
+    UtcOffset(i32),
+    /// The shell didn't tell the time or the offset within the capability's timeout (only
+    /// produced in the core, see [`Time::now_async`])
+    #[serde(skip)]
+    TimedOut,
 }
 
 impl Operation for TimeRequest {
     type Output = TimeResponse;
 }
 
+impl TimesOut for TimeRequest {
+    fn timed_out(_timeout: ShellTimeout) -> TimeResponse {
+        TimeResponse::TimedOut
+    }
+}
+
+/// Lets [`AppTester::with_virtual_clock`](crux_core::testing::AppTester::with_virtual_clock)
+/// resolve time requests, so that tests can [`advance_time`](crux_core::testing::AppTester::advance_time).
+impl crux_core::testing::Timer for TimeRequest {
+    fn schedule(&self, now: std::time::Duration) -> Option<(std::time::Duration, TimeResponse)> {
+        Some(match self {
+            TimeRequest::Now => {
+                let now_instant = Instant {
+                    seconds: now.as_secs(),
+                    nanos: now.subsec_nanos(),
+                };
+                (now, TimeResponse::Now(now_instant))
+            }
+            TimeRequest::NotifyAt(instant) => (
+                std::time::Duration::new(instant.seconds, instant.nanos),
+                TimeResponse::InstantArrived,
+            ),
+            TimeRequest::NotifyAfter(duration) => (
+                now + std::time::Duration::from(*duration),
+                TimeResponse::DurationElapsed,
+            ),
+            // tests run in UTC
+            TimeRequest::UtcOffset => (now, TimeResponse::UtcOffset(0)),
+        })
+    }
+}
+
 /// The Time capability API
 ///
 /// This capability provides access to the current time and allows the app to ask for
@@ -79,6 +129,11 @@ where
 
     /// Request current time, which will be passed to the app as a [`TimeResponse`] containing an [`Instant`]
     /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// If the capability was constructed with a timeout (see
+    /// [`CapabilityContext::with_timeout`]) and the shell doesn't respond in time, the response
+    /// is [`TimeResponse::TimedOut`]. The same goes for [`utc_offset_async`](Self::utc_offset_async).
+    /// Notifications aren't limited by the timeout, as they're expected to take a while.
     pub async fn now_async(&self) -> TimeResponse {
         self.context.request_from_shell(TimeRequest::Now).await
     }
@@ -102,6 +157,7 @@ where
     /// This is an async call to use with [`crux_core::compose::Compose`].
     pub async fn notify_at_async(&self, instant: Instant) -> TimeResponse {
         self.context
+            .without_timeout()
             .request_from_shell(TimeRequest::NotifyAt(instant))
             .await
     }
@@ -125,9 +181,187 @@ where
     /// This is an async call to use with [`crux_core::compose::Compose`].
     pub async fn notify_after_async(&self, duration: Duration) -> TimeResponse {
         self.context
+            .without_timeout()
             .request_from_shell(TimeRequest::NotifyAfter(duration))
             .await
     }
+
+    /// Ask to receive a notification when `seconds` have elapsed.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `seconds` is too long to be represented as a [`Duration`].
+    pub fn notify_after_secs<F>(&self, seconds: u64, callback: F)
+    where
+        F: FnOnce(TimeResponse) -> Ev + Send + Sync + 'static,
+    {
+        let duration = Duration::from_secs(seconds).expect("seconds should fit in a Duration");
+
+        self.notify_after(duration, callback);
+    }
+
+    /// Request the offset of the local time zone from UTC, which will be passed to the app
+    /// as a [`TimeResponse::UtcOffset`] wrapped in the event produced by the `callback`.
+    pub fn utc_offset<F>(&self, callback: F)
+    where
+        F: FnOnce(TimeResponse) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                context.update_app(callback(this.utc_offset_async().await));
+            }
+        });
+    }
+
+    /// Request the offset of the local time zone from UTC.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn utc_offset_async(&self) -> TimeResponse {
+        self.context
+            .request_from_shell(TimeRequest::UtcOffset)
+            .await
+    }
+
+    /// Ask to receive a notification the next time the local wall clock shows `time_of_day`
+    /// (`"HH:MM"` on a 24-hour clock), e.g. `"03:00"` for the coming night.
+    ///
+    /// The instant is worked out from the current time and UTC offset, so the notification
+    /// arrives an hour early or late if the clocks change in between.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `time_of_day` is malformed.
+    pub fn notify_at_next<F>(&self, time_of_day: &str, callback: F)
+    where
+        F: FnOnce(TimeResponse) -> Ev + Send + Sync + 'static,
+    {
+        let time_of_day = parse_time_of_day(time_of_day);
+
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this.notify_at_local(None, time_of_day).await;
+                context.update_app(callback(response));
+            }
+        });
+    }
+
+    /// Ask to receive a notification the next time the local wall clock shows `time_of_day`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `time_of_day` is malformed.
+    pub async fn notify_at_next_async(&self, time_of_day: &str) -> TimeResponse {
+        let time_of_day = parse_time_of_day(time_of_day);
+
+        self.notify_at_local(None, time_of_day).await
+    }
+
+    /// Ask to receive a notification the next time it's `time_of_day` (`"HH:MM"` on a
+    /// 24-hour clock) on the `weekday` on the local wall clock, e.g. Monday at `"09:00"`.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `time_of_day` is malformed.
+    pub fn notify_on<F>(&self, weekday: Weekday, time_of_day: &str, callback: F)
+    where
+        F: FnOnce(TimeResponse) -> Ev + Send + Sync + 'static,
+    {
+        let time_of_day = parse_time_of_day(time_of_day);
+
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+
+            async move {
+                let response = this.notify_at_local(Some(weekday), time_of_day).await;
+                context.update_app(callback(response));
+            }
+        });
+    }
+
+    /// Ask to receive a notification the next time it's `time_of_day` on the `weekday`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `time_of_day` is malformed.
+    pub async fn notify_on_async(&self, weekday: Weekday, time_of_day: &str) -> TimeResponse {
+        let time_of_day = parse_time_of_day(time_of_day);
+
+        self.notify_at_local(Some(weekday), time_of_day).await
+    }
+
+    /// Ask to receive a notification at the next occurrence of the `recurrence` on the local
+    /// wall clock. The `callback` is called with the instant of the occurrence once it has
+    /// arrived, or with `None` straight away if the recurrence has ended.
+    ///
+    /// As with [`notify_at_next`](Self::notify_at_next), the instant is worked out from the
+    /// current UTC offset. To keep a reminder going, ask again from the event.
+    pub fn notify_at_next_occurrence<F>(&self, recurrence: &Recurrence, callback: F)
+    where
+        F: FnOnce(Option<Instant>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            let this = self.clone();
+            let recurrence = recurrence.clone();
+
+            async move {
+                let occurrence = this.notify_at_next_occurrence_async(&recurrence).await;
+                context.update_app(callback(occurrence));
+            }
+        });
+    }
+
+    /// Ask to receive a notification at the next occurrence of the `recurrence`, returning
+    /// its instant once it has arrived, or `None` if the recurrence has ended.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn notify_at_next_occurrence_async(
+        &self,
+        recurrence: &Recurrence,
+    ) -> Option<Instant> {
+        let TimeResponse::Now(now) = self.now_async().await else {
+            panic!("Time::now_async should respond with Now");
+        };
+        let TimeResponse::UtcOffset(utc_offset) = self.utc_offset_async().await else {
+            panic!("Time::utc_offset_async should respond with UtcOffset");
+        };
+
+        let instant = recurrence.next_instant(now, utc_offset)?;
+        self.notify_at_async(instant).await;
+
+        Some(instant)
+    }
+
+    async fn notify_at_local(
+        &self,
+        weekday: Option<Weekday>,
+        time_of_day: TimeOfDay,
+    ) -> TimeResponse {
+        let TimeResponse::Now(now) = self.now_async().await else {
+            panic!("Time::now_async should respond with Now");
+        };
+        let TimeResponse::UtcOffset(utc_offset) = self.utc_offset_async().await else {
+            panic!("Time::utc_offset_async should respond with UtcOffset");
+        };
+
+        let instant = schedule::next_occurrence(now, utc_offset, weekday, time_of_day)
+            .expect("the next occurrence should be a valid Instant");
+
+        self.notify_at_async(instant).await
+    }
+}
+
+fn parse_time_of_day(time_of_day: &str) -> TimeOfDay {
+    time_of_day
+        .parse()
+        .unwrap_or_else(|_| panic!("time of day should be HH:MM, got {time_of_day:?}"))
 }
 
 #[cfg(test)]
@@ -186,5 +420,13 @@ mod test {
 
         let deserialized: TimeResponse = serde_json::from_str(&serialized).unwrap();
         assert_eq!(now, deserialized);
+
+        let offset = TimeResponse::UtcOffset(-18_000);
+
+        let serialized = serde_json::to_string(&offset).unwrap();
+        assert_eq!(&serialized, r#"{"utcOffset":-18000}"#);
+
+        let deserialized: TimeResponse = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(offset, deserialized);
     }
 }
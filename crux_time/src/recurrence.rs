@@ -0,0 +1,1121 @@
+//! Recurrence rules, as in the `RRULE` property of iCalendar (RFC 5545), see [`Recurrence`]
+
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{error::TimeResult, Instant, TimeError, Weekday};
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Periods in a row without occurrences after which the rule is considered exhausted, e.g.
+/// for `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`
+const MAX_EMPTY_PERIODS: u32 = 10_000;
+
+/// A date and time on a local wall clock, without a time zone ("floating" in RFC 5545)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalDateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl LocalDateTime {
+    /// Errors with [`TimeError::InvalidTime`] unless the date exists in the (proleptic)
+    /// Gregorian calendar, between the years 1 and 9999, and the time is on a 24-hour clock.
+    pub fn new(
+        year: i32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> TimeResult<Self> {
+        let valid = (1..=9999).contains(&year)
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        if !valid {
+            return Err(TimeError::InvalidTime);
+        }
+
+        Ok(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// The time on a wall clock `utc_offset` seconds ahead of UTC at the `instant`
+    pub fn from_instant(instant: Instant, utc_offset: i32) -> TimeResult<Self> {
+        let seconds = i64::try_from(instant.seconds).map_err(|_| TimeError::InvalidInstant)?;
+        let local = seconds + i64::from(utc_offset);
+
+        let date = Date::from_days(local.div_euclid(SECS_PER_DAY));
+        let second_of_day = local.rem_euclid(SECS_PER_DAY);
+
+        #[allow(clippy::cast_possible_truncation)]
+        Self::new(
+            date.year,
+            date.month,
+            date.day,
+            (second_of_day / 3600) as u8,
+            (second_of_day / 60 % 60) as u8,
+            (second_of_day % 60) as u8,
+        )
+    }
+
+    /// The instant at which a wall clock `utc_offset` seconds ahead of UTC shows this time
+    pub fn to_instant(&self, utc_offset: i32) -> TimeResult<Instant> {
+        let seconds = self.date().days() * SECS_PER_DAY
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second)
+            - i64::from(utc_offset);
+
+        let seconds = u64::try_from(seconds).map_err(|_| TimeError::InvalidInstant)?;
+        Instant::new(seconds, 0)
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        self.date().weekday()
+    }
+
+    fn date(&self) -> Date {
+        Date {
+            year: self.year,
+            month: self.month,
+            day: self.day,
+        }
+    }
+}
+
+/// Parses the iCalendar forms `"20240229T090000"` and `"20240229"` (for midnight). A
+/// trailing `Z` for UTC is accepted, but the time is taken as a local time.
+impl FromStr for LocalDateTime {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> TimeResult<Self> {
+        let s = s.strip_suffix('Z').unwrap_or(s);
+        let (date, time) = s.split_once('T').unwrap_or((s, "000000"));
+        if date.len() != 8
+            || time.len() != 6
+            || !(date.chars().chain(time.chars())).all(|c| c.is_ascii_digit())
+        {
+            return Err(TimeError::InvalidTime);
+        }
+
+        let year = date[..4].parse().map_err(|_| TimeError::InvalidTime)?;
+        let number = |s: &str| s.parse().map_err(|_| TimeError::InvalidTime);
+        Self::new(
+            year,
+            number(&date[4..6])?,
+            number(&date[6..])?,
+            number(&time[..2])?,
+            number(&time[2..4])?,
+            number(&time[4..])?,
+        )
+    }
+}
+
+impl fmt::Display for LocalDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}{:02}{:02}T{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// How often a [`RecurrenceRule`] repeats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A day of the week in a `BYDAY` list, e.g. `MO` for every Monday, or `-1FR` for the last
+/// Friday of the month (or year)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NthWeekday {
+    /// Which of the weekdays in the month or year, counting from the end if negative
+    pub nth: Option<i8>,
+    pub weekday: Weekday,
+}
+
+/// The rule of a recurrence, e.g. `FREQ=MONTHLY;BYDAY=-1FR` for the last Friday of every
+/// month, parsed from and formatted as an iCalendar `RRULE`.
+///
+/// The `DAILY`, `WEEKLY`, `MONTHLY` and `YEARLY` frequencies are supported, with the
+/// `INTERVAL`, `COUNT`, `UNTIL`, `BYMONTH`, `BYMONTHDAY`, `BYDAY`, `BYHOUR`, `BYMINUTE`,
+/// `BYSETPOS` and `WKST` parts. Rules with other parts fail to parse rather than recurring
+/// differently than in other calendar apps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    /// The number of periods between occurrences, e.g. 2 for every other week
+    pub interval: u32,
+    /// The total number of occurrences
+    pub count: Option<u32>,
+    /// The last time an occurrence can be at
+    pub until: Option<LocalDateTime>,
+    pub by_month: Vec<u8>,
+    /// Days of the month, counting from the end if negative
+    pub by_month_day: Vec<i8>,
+    pub by_day: Vec<NthWeekday>,
+    pub by_hour: Vec<u8>,
+    pub by_minute: Vec<u8>,
+    /// Which of the occurrences in each period to keep, counting from the end if negative
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Weekday,
+}
+
+impl RecurrenceRule {
+    pub fn new(frequency: Frequency) -> Self {
+        Self {
+            frequency,
+            interval: 1,
+            count: None,
+            until: None,
+            by_month: Vec::new(),
+            by_month_day: Vec::new(),
+            by_day: Vec::new(),
+            by_hour: Vec::new(),
+            by_minute: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Monday,
+        }
+    }
+}
+
+fn invalid(message: impl fmt::Display) -> TimeError {
+    TimeError::InvalidRecurrence(message.to_string())
+}
+
+fn parse_list<T: FromStr>(
+    name: &str,
+    value: &str,
+    valid: impl Fn(&T) -> bool,
+) -> TimeResult<Vec<T>> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim_start_matches('+')
+                .parse()
+                .ok()
+                .filter(|item| valid(item))
+                .ok_or_else(|| invalid(format!("invalid {name} value {item:?}")))
+        })
+        .collect()
+}
+
+fn parse_weekday(code: &str) -> TimeResult<Weekday> {
+    Ok(match code {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return Err(invalid(format!("invalid weekday {code:?}"))),
+    })
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Monday => "MO",
+        Weekday::Tuesday => "TU",
+        Weekday::Wednesday => "WE",
+        Weekday::Thursday => "TH",
+        Weekday::Friday => "FR",
+        Weekday::Saturday => "SA",
+        Weekday::Sunday => "SU",
+    }
+}
+
+/// Parses an `RRULE` value, with or without the `RRULE:` name, erroring with
+/// [`TimeError::InvalidRecurrence`]
+impl FromStr for RecurrenceRule {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> TimeResult<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+
+        let mut frequency = None;
+        let mut rule = RecurrenceRule::new(Frequency::Daily);
+
+        for part in s.split(';') {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected NAME=VALUE, got {part:?}")))?;
+
+            match name {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(format!("unsupported frequency {value:?}"))),
+                    });
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| invalid(format!("invalid INTERVAL {value:?}")))?;
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid(format!("invalid COUNT {value:?}")))?,
+                    );
+                }
+                "UNTIL" => {
+                    rule.until = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid(format!("invalid UNTIL {value:?}")))?,
+                    );
+                }
+                "BYMONTH" => rule.by_month = parse_list(name, value, |m| (1..=12).contains(m))?,
+                "BYMONTHDAY" => {
+                    rule.by_month_day =
+                        parse_list(name, value, |d: &i8| *d != 0 && (-31..=31).contains(d))?;
+                }
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|item| {
+                            let (nth, code) = item.split_at(item.len().saturating_sub(2));
+                            let nth = match nth.trim_start_matches('+') {
+                                "" => None,
+                                nth => Some(
+                                    nth.parse()
+                                        .ok()
+                                        .filter(|n: &i8| *n != 0 && (-53..=53).contains(n))
+                                        .ok_or_else(|| {
+                                            invalid(format!("invalid BYDAY {item:?}"))
+                                        })?,
+                                ),
+                            };
+
+                            Ok(NthWeekday {
+                                nth,
+                                weekday: parse_weekday(code)?,
+                            })
+                        })
+                        .collect::<TimeResult<_>>()?;
+                }
+                "BYHOUR" => rule.by_hour = parse_list(name, value, |h| *h < 24)?,
+                "BYMINUTE" => rule.by_minute = parse_list(name, value, |m| *m < 60)?,
+                "BYSETPOS" => {
+                    rule.by_set_pos =
+                        parse_list(name, value, |p: &i32| *p != 0 && (-366..=366).contains(p))?;
+                }
+                "WKST" => rule.week_start = parse_weekday(value)?,
+                "BYSECOND" | "BYWEEKNO" | "BYYEARDAY" => {
+                    return Err(invalid(format!("{name} is not supported")));
+                }
+                _ => return Err(invalid(format!("unknown rule part {name:?}"))),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| invalid("FREQ is required"))?;
+
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err(invalid("COUNT and UNTIL can't both be set"));
+        }
+        let ordinals = rule.by_day.iter().any(|day| day.nth.is_some());
+        if ordinals && matches!(rule.frequency, Frequency::Daily | Frequency::Weekly) {
+            return Err(invalid(
+                "BYDAY can only have ordinals in MONTHLY and YEARLY rules",
+            ));
+        }
+        if !rule.by_month_day.is_empty() && rule.frequency == Frequency::Weekly {
+            return Err(invalid("BYMONTHDAY can't be used in WEEKLY rules"));
+        }
+
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list<T: fmt::Display>(items: &[T]) -> String {
+            items
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        }
+
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={frequency}")?;
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={until}")?;
+        }
+        if !self.by_month.is_empty() {
+            write!(f, ";BYMONTH={}", list(&self.by_month))?;
+        }
+        if !self.by_month_day.is_empty() {
+            write!(f, ";BYMONTHDAY={}", list(&self.by_month_day))?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|day| match day.nth {
+                    Some(nth) => format!("{nth}{}", weekday_code(day.weekday)),
+                    None => weekday_code(day.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_hour.is_empty() {
+            write!(f, ";BYHOUR={}", list(&self.by_hour))?;
+        }
+        if !self.by_minute.is_empty() {
+            write!(f, ";BYMINUTE={}", list(&self.by_minute))?;
+        }
+        if !self.by_set_pos.is_empty() {
+            write!(f, ";BYSETPOS={}", list(&self.by_set_pos))?;
+        }
+        if self.week_start != Weekday::Monday {
+            write!(f, ";WKST={}", weekday_code(self.week_start))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Serialized as the `RRULE` value, wrapped in a newtype named `RecurrenceRule`. Because it's
+/// validated when deserialized, register a sample with the type generation.
+impl Serialize for RecurrenceRule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("RecurrenceRule", &self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for RecurrenceRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "RecurrenceRule")]
+        struct Repr(String);
+
+        let Repr(rule) = Repr::deserialize(deserializer)?;
+
+        rule.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A series of occurrences on the local wall clock, from a `start` following a `rule`.
+///
+/// Recurrences work out occurrences the same way in every shell, and with
+/// [`Time::notify_at_next_occurrence`](crate::Time::notify_at_next_occurrence) schedule
+/// reminders for them.
+///
+/// ```
+/// use crux_time::recurrence::{LocalDateTime, Recurrence};
+///
+/// // the last Friday of every month, at 17:00
+/// let payday: Recurrence = "DTSTART:20240101T170000\nRRULE:FREQ=MONTHLY;BYDAY=-1FR"
+///     .parse()
+///     .unwrap();
+///
+/// let after: LocalDateTime = "20240301".parse().unwrap();
+/// let next: Vec<String> = payday
+///     .next_n(after, 3)
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect();
+/// assert_eq!(next, ["20240329T170000", "20240426T170000", "20240531T170000"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub start: LocalDateTime,
+    pub rule: RecurrenceRule,
+}
+
+impl Recurrence {
+    pub fn new(start: LocalDateTime, rule: RecurrenceRule) -> Self {
+        Self { start, rule }
+    }
+
+    /// All the occurrences in order, which may go on forever
+    pub fn occurrences(&self) -> Occurrences<'_> {
+        Occurrences {
+            recurrence: self,
+            period: 0,
+            pending: VecDeque::new(),
+            emitted: 0,
+            empty_periods: 0,
+            done: false,
+        }
+    }
+
+    /// The first occurrence after `after`
+    pub fn next_after(&self, after: LocalDateTime) -> Option<LocalDateTime> {
+        self.occurrences().find(|occurrence| *occurrence > after)
+    }
+
+    /// The next `n` occurrences after `after`, or fewer if the recurrence ends
+    pub fn next_n(&self, after: LocalDateTime, n: usize) -> Vec<LocalDateTime> {
+        self.occurrences()
+            .skip_while(|occurrence| *occurrence <= after)
+            .take(n)
+            .collect()
+    }
+
+    /// The occurrences from `start` up to, but not including, `end`
+    pub fn between(&self, start: LocalDateTime, end: LocalDateTime) -> Vec<LocalDateTime> {
+        self.occurrences()
+            .skip_while(|occurrence| *occurrence < start)
+            .take_while(|occurrence| *occurrence < end)
+            .collect()
+    }
+
+    /// The instant of the first occurrence after `now` on a wall clock `utc_offset` seconds
+    /// ahead of UTC
+    pub fn next_instant(&self, now: Instant, utc_offset: i32) -> Option<Instant> {
+        let now = LocalDateTime::from_instant(now, utc_offset).ok()?;
+
+        self.next_after(now)?.to_instant(utc_offset).ok()
+    }
+
+    /// The occurrences in the `period`th period of the rule, sorted
+    fn period(&self, period: u32) -> Vec<LocalDateTime> {
+        let rule = &self.rule;
+        let start = self.start.date();
+        let step = i64::from(period) * i64::from(rule.interval.max(1));
+
+        let dates: Vec<Date> = match rule.frequency {
+            Frequency::Daily => {
+                let date = Date::from_days(start.days() + step);
+                vec![date]
+                    .into_iter()
+                    .filter(|date| self.in_month(date) && self.on_month_day(date))
+                    .filter(|date| self.on_weekday(date, None))
+                    .collect()
+            }
+            Frequency::Weekly => {
+                let offset = (start.weekday().index() - rule.week_start.index()).rem_euclid(7);
+                let week = start.days() - offset + 7 * step;
+
+                (week..week + 7)
+                    .map(Date::from_days)
+                    .filter(|date| {
+                        if rule.by_day.is_empty() {
+                            date.weekday() == start.weekday()
+                        } else {
+                            self.on_weekday(date, None)
+                        }
+                    })
+                    .filter(|date| self.in_month(date))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let month = i64::from(start.year) * 12 + i64::from(start.month) - 1 + step;
+                let Some((year, month)) = year_month(month) else {
+                    return Vec::new();
+                };
+
+                if self.in_month(&Date::new(year, month, 1)) {
+                    self.days_in_month(year, month)
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Yearly => {
+                let Some(year) = i32::try_from(i64::from(start.year) + step).ok() else {
+                    return Vec::new();
+                };
+
+                if rule.by_month.is_empty()
+                    && rule.by_month_day.is_empty()
+                    && !rule.by_day.is_empty()
+                {
+                    // weekdays of the year, e.g. the 20th Monday
+                    let first = Date::new(year, 1, 1).days();
+                    let last = Date::new(year, 12, 31).days();
+
+                    (first..=last)
+                        .map(Date::from_days)
+                        .filter(|date| self.on_weekday(date, Some((first, last))))
+                        .collect()
+                } else {
+                    let months = if !rule.by_month.is_empty() {
+                        rule.by_month.clone()
+                    } else if rule.by_month_day.is_empty() {
+                        vec![start.month]
+                    } else {
+                        (1..=12).collect()
+                    };
+
+                    months
+                        .into_iter()
+                        .filter(|month| (1..=12).contains(month))
+                        .flat_map(|month| self.days_in_month(year, month))
+                        .collect()
+                }
+            }
+        };
+
+        let hours = if rule.by_hour.is_empty() {
+            vec![self.start.hour]
+        } else {
+            rule.by_hour.clone()
+        };
+        let minutes = if rule.by_minute.is_empty() {
+            vec![self.start.minute]
+        } else {
+            rule.by_minute.clone()
+        };
+
+        let mut occurrences = Vec::new();
+        for date in &dates {
+            for hour in &hours {
+                for minute in &minutes {
+                    let time = LocalDateTime::new(
+                        date.year,
+                        date.month,
+                        date.day,
+                        *hour,
+                        *minute,
+                        self.start.second,
+                    );
+                    occurrences.extend(time.ok());
+                }
+            }
+        }
+        occurrences.sort();
+        occurrences.dedup();
+
+        if !rule.by_set_pos.is_empty() {
+            let count = occurrences.len() as i64;
+            let mut selected: Vec<LocalDateTime> = rule
+                .by_set_pos
+                .iter()
+                .filter_map(|&position| {
+                    let index = if position > 0 {
+                        i64::from(position) - 1
+                    } else {
+                        count + i64::from(position)
+                    };
+                    usize::try_from(index)
+                        .ok()
+                        .and_then(|index| occurrences.get(index).copied())
+                })
+                .collect();
+            selected.sort();
+            selected.dedup();
+            occurrences = selected;
+        }
+
+        occurrences
+    }
+
+    /// The dates in the month which the rule's `BYMONTHDAY` and `BYDAY` select, or the day of
+    /// the start if it has neither
+    fn days_in_month(&self, year: i32, month: u8) -> Vec<Date> {
+        let rule = &self.rule;
+        let first = Date::new(year, month, 1).days();
+        let last = first + i64::from(days_in_month(year, month)) - 1;
+
+        (first..=last)
+            .map(Date::from_days)
+            .filter(|date| {
+                if rule.by_month_day.is_empty() && rule.by_day.is_empty() {
+                    date.day == self.start.day
+                } else {
+                    self.on_month_day(date) && self.on_weekday(date, Some((first, last)))
+                }
+            })
+            .collect()
+    }
+
+    fn in_month(&self, date: &Date) -> bool {
+        self.rule.by_month.is_empty() || self.rule.by_month.contains(&date.month)
+    }
+
+    fn on_month_day(&self, date: &Date) -> bool {
+        let length = i16::from(days_in_month(date.year, date.month));
+
+        self.rule.by_month_day.is_empty()
+            || self.rule.by_month_day.iter().any(|&day| {
+                let day = i16::from(day);
+                let day = if day < 0 { length + day + 1 } else { day };
+                day == i16::from(date.day)
+            })
+    }
+
+    /// Whether the date is on one of the `BYDAY` weekdays, counting the nth weekdays in the
+    /// `scope` of days from the first to the last
+    fn on_weekday(&self, date: &Date, scope: Option<(i64, i64)>) -> bool {
+        let days = date.days();
+
+        self.rule.by_day.is_empty()
+            || self.rule.by_day.iter().any(|by_day| {
+                if by_day.weekday != date.weekday() {
+                    return false;
+                }
+
+                match (by_day.nth, scope) {
+                    (Some(nth), Some((first, _))) if nth > 0 => {
+                        (days - first) / 7 + 1 == i64::from(nth)
+                    }
+                    (Some(nth), Some((_, last))) => (last - days) / 7 + 1 == -i64::from(nth),
+                    _ => true,
+                }
+            })
+    }
+}
+
+/// Parses an iCalendar `DTSTART` and `RRULE`, on separate lines, e.g.
+/// `"DTSTART:20240101T090000\nRRULE:FREQ=WEEKLY;BYDAY=MO"`. Parameters of `DTSTART` such as
+/// `TZID` are ignored, the start is always on the local wall clock.
+impl FromStr for Recurrence {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> TimeResult<Self> {
+        let mut start = None;
+        let mut rule = None;
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("expected NAME:VALUE, got {line:?}")))?;
+            let name = name.split(';').next().unwrap_or_default();
+
+            match name {
+                "DTSTART" => {
+                    start = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid(format!("invalid DTSTART {value:?}")))?,
+                    );
+                }
+                "RRULE" => rule = Some(value.parse()?),
+                _ => return Err(invalid(format!("unsupported property {name:?}"))),
+            }
+        }
+
+        Ok(Self {
+            start: start.ok_or_else(|| invalid("DTSTART is required"))?,
+            rule: rule.ok_or_else(|| invalid("RRULE is required"))?,
+        })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DTSTART:{}\nRRULE:{}", self.start, self.rule)
+    }
+}
+
+/// The occurrences of a [`Recurrence`], see [`Recurrence::occurrences`]
+#[derive(Debug)]
+pub struct Occurrences<'a> {
+    recurrence: &'a Recurrence,
+    period: u32,
+    pending: VecDeque<LocalDateTime>,
+    emitted: u32,
+    empty_periods: u32,
+    done: bool,
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = LocalDateTime;
+
+    fn next(&mut self) -> Option<LocalDateTime> {
+        let rule = &self.recurrence.rule;
+
+        while !self.done && self.pending.is_empty() {
+            let occurrences: Vec<LocalDateTime> = self
+                .recurrence
+                .period(self.period)
+                .into_iter()
+                .filter(|occurrence| *occurrence >= self.recurrence.start)
+                .collect();
+
+            if occurrences.is_empty() {
+                self.empty_periods += 1;
+            } else {
+                self.empty_periods = 0;
+            }
+
+            self.pending.extend(occurrences);
+            self.period = self.period.saturating_add(1);
+            self.done = self.empty_periods > MAX_EMPTY_PERIODS || self.period == u32::MAX;
+        }
+
+        let next = self.pending.pop_front()?;
+        if rule.until.map_or(false, |until| next > until)
+            || rule.count.map_or(false, |count| self.emitted >= count)
+        {
+            self.done = true;
+            self.pending.clear();
+            return None;
+        }
+
+        self.emitted += 1;
+        Some(next)
+    }
+}
+
+/// A date in the proleptic Gregorian calendar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    fn new(year: i32, month: u8, day: u8) -> Self {
+        Self { year, month, day }
+    }
+
+    /// Days since the Unix epoch, using Howard Hinnant's `days_from_civil`
+    fn days(&self) -> i64 {
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The date `days` after the Unix epoch, using Howard Hinnant's `civil_from_days`
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Self::new(year as i32, month as u8, day as u8)
+    }
+
+    fn weekday(&self) -> Weekday {
+        // the epoch was on a Thursday
+        Weekday::from_index((self.days() + 3).rem_euclid(7))
+    }
+}
+
+/// The year and month of a count of months since the year 0
+fn year_month(months: i64) -> Option<(i32, u8)> {
+    let year = i32::try_from(months.div_euclid(12)).ok()?;
+    let month = u8::try_from(months.rem_euclid(12) + 1).ok()?;
+
+    Some((year, month))
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(s: &str) -> LocalDateTime {
+        s.parse().unwrap()
+    }
+
+    fn occurrences(recurrence: &str, n: usize) -> Vec<String> {
+        let recurrence: Recurrence = recurrence.parse().unwrap();
+
+        recurrence
+            .occurrences()
+            .take(n)
+            .map(|occurrence| occurrence.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn converts_dates_and_instants() {
+        // Thursday 2024-02-29 14:30:00 UTC
+        let instant = Instant::new(1_709_217_000, 0).unwrap();
+
+        let local = LocalDateTime::from_instant(instant, 11 * 3600).unwrap();
+        assert_eq!(local, at("20240301T013000"));
+        assert_eq!(local.weekday(), Weekday::Friday);
+        assert_eq!(local.to_instant(11 * 3600), Ok(instant));
+
+        for invalid in [
+            "20230229",
+            "20241301",
+            "2024-02-29",
+            "20240229T240000",
+            "2024022",
+        ] {
+            assert_eq!(
+                invalid.parse::<LocalDateTime>(),
+                Err(TimeError::InvalidTime),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_rules() {
+        let rule: RecurrenceRule = "RRULE:FREQ=MONTHLY;INTERVAL=2;BYDAY=+1MO,-1FR;COUNT=10"
+            .parse()
+            .unwrap();
+
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(
+            rule.by_day,
+            [
+                NthWeekday {
+                    nth: Some(1),
+                    weekday: Weekday::Monday
+                },
+                NthWeekday {
+                    nth: Some(-1),
+                    weekday: Weekday::Friday
+                }
+            ]
+        );
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1MO,-1FR"
+        );
+
+        let json = serde_json::to_string(&rule).unwrap();
+        assert_eq!(json, r#""FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1MO,-1FR""#);
+        assert_eq!(serde_json::from_str::<RecurrenceRule>(&json).unwrap(), rule);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for invalid in [
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=2;UNTIL=20240101",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+            "FREQ=MONTHLY;BYDAY=XX",
+            "FREQ=YEARLY;BYMONTH=13",
+            "FREQ=YEARLY;BYWEEKNO=20",
+            "FREQ=DAILY;FOO=BAR",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<RecurrenceRule>(),
+                    Err(TimeError::InvalidRecurrence(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn repeats_daily_and_weekly() {
+        assert_eq!(
+            occurrences("DTSTART:20240229T090000\nRRULE:FREQ=DAILY;INTERVAL=2", 3),
+            ["20240229T090000", "20240302T090000", "20240304T090000"]
+        );
+        // weekdays only, at two times of day
+        assert_eq!(
+            occurrences(
+                "DTSTART:20240301T080000\nRRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;BYHOUR=8,20",
+                4
+            ),
+            [
+                "20240301T080000",
+                "20240301T200000",
+                "20240304T080000",
+                "20240304T200000"
+            ]
+        );
+        // every other week on Tuesday and Thursday, starting on a Thursday
+        assert_eq!(
+            occurrences(
+                "DTSTART:20240229T100000\nRRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;COUNT=4",
+                10
+            ),
+            [
+                "20240229T100000",
+                "20240312T100000",
+                "20240314T100000",
+                "20240326T100000"
+            ]
+        );
+    }
+
+    #[test]
+    fn week_start_changes_biweekly_rules() {
+        // the examples from RFC 5545 section 3.8.5.3
+        assert_eq!(
+            occurrences(
+                "DTSTART:19970805T090000\nRRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO",
+                10
+            ),
+            [
+                "19970805T090000",
+                "19970810T090000",
+                "19970819T090000",
+                "19970824T090000"
+            ]
+        );
+        assert_eq!(
+            occurrences(
+                "DTSTART:19970805T090000\nRRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU",
+                10
+            ),
+            [
+                "19970805T090000",
+                "19970817T090000",
+                "19970819T090000",
+                "19970831T090000"
+            ]
+        );
+    }
+
+    #[test]
+    fn repeats_monthly() {
+        // the 31st, skipping shorter months
+        assert_eq!(
+            occurrences("DTSTART:20240131\nRRULE:FREQ=MONTHLY", 3),
+            ["20240131T000000", "20240331T000000", "20240531T000000"]
+        );
+        // the last day of the month
+        assert_eq!(
+            occurrences("DTSTART:20240131\nRRULE:FREQ=MONTHLY;BYMONTHDAY=-1", 3),
+            ["20240131T000000", "20240229T000000", "20240331T000000"]
+        );
+        // the last working day of the month
+        assert_eq!(
+            occurrences(
+                "DTSTART:20240101T170000\nRRULE:FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                3
+            ),
+            ["20240131T170000", "20240229T170000", "20240329T170000"]
+        );
+        // Friday the 13th
+        assert_eq!(
+            occurrences(
+                "DTSTART:20240101\nRRULE:FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13",
+                2
+            ),
+            ["20240913T000000", "20241213T000000"]
+        );
+    }
+
+    #[test]
+    fn repeats_yearly() {
+        // birthdays on the 29th of February
+        assert_eq!(
+            occurrences("DTSTART:20200229T120000\nRRULE:FREQ=YEARLY", 3),
+            ["20200229T120000", "20240229T120000", "20280229T120000"]
+        );
+        // US Thanksgiving
+        assert_eq!(
+            occurrences(
+                "DTSTART:20240101\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+                2
+            ),
+            ["20241128T000000", "20251127T000000"]
+        );
+        // the 20th Monday of the year, from RFC 5545
+        assert_eq!(
+            occurrences("DTSTART:19970519T090000\nRRULE:FREQ=YEARLY;BYDAY=20MO", 3),
+            ["19970519T090000", "19980518T090000", "19990517T090000"]
+        );
+    }
+
+    #[test]
+    fn ends_at_until_or_never() {
+        let until = occurrences(
+            "DTSTART:20240101\nRRULE:FREQ=DAILY;UNTIL=20240103T000000Z",
+            10,
+        );
+        assert_eq!(
+            until,
+            ["20240101T000000", "20240102T000000", "20240103T000000"]
+        );
+
+        // the 30th of February never comes
+        assert!(occurrences(
+            "DTSTART:20240101\nRRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30",
+            1
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn queries_occurrences() {
+        let recurrence: Recurrence = "DTSTART:20240101T093000\nRRULE:FREQ=WEEKLY;BYDAY=MO"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            recurrence.next_after(at("20240108T093000")),
+            Some(at("20240115T093000"))
+        );
+        assert_eq!(
+            recurrence.between(at("20240201"), at("20240301")),
+            [
+                at("20240205T093000"),
+                at("20240212T093000"),
+                at("20240219T093000"),
+                at("20240226T093000")
+            ]
+        );
+
+        // Thursday 2024-02-29 14:30:00 UTC, Monday 09:30 in UTC-5 is 14:30 UTC
+        let now = Instant::new(1_709_217_000, 0).unwrap();
+        let next = recurrence.next_instant(now, -5 * 3600).unwrap();
+        assert_eq!(next.seconds, 1_709_217_000 + 4 * 86_400);
+    }
+}
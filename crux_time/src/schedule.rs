@@ -0,0 +1,200 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::TimeResult, Instant, TimeError};
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// A day of the week
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    // days from Monday
+    pub(crate) fn index(self) -> i64 {
+        match self {
+            Weekday::Monday => 0,
+            Weekday::Tuesday => 1,
+            Weekday::Wednesday => 2,
+            Weekday::Thursday => 3,
+            Weekday::Friday => 4,
+            Weekday::Saturday => 5,
+            Weekday::Sunday => 6,
+        }
+    }
+
+    pub(crate) fn from_index(index: i64) -> Self {
+        match index.rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+/// A time on the local wall clock, e.g. `"09:30"`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    /// Errors with [`TimeError::InvalidTime`] unless `hour` is below 24 and `minute` below 60.
+    pub fn new(hour: u8, minute: u8) -> TimeResult<Self> {
+        if hour >= 24 || minute >= 60 {
+            return Err(TimeError::InvalidTime);
+        }
+
+        Ok(Self { hour, minute })
+    }
+
+    fn seconds(self) -> i64 {
+        i64::from(self.hour) * 3600 + i64::from(self.minute) * 60
+    }
+}
+
+/// Parses `"HH:MM"` on a 24-hour clock, erroring with [`TimeError::InvalidTime`]
+impl FromStr for TimeOfDay {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> TimeResult<Self> {
+        let (hour, minute) = s.split_once(':').ok_or(TimeError::InvalidTime)?;
+        if hour.is_empty() || hour.len() > 2 || minute.len() != 2 {
+            return Err(TimeError::InvalidTime);
+        }
+
+        let hour = hour.parse().map_err(|_| TimeError::InvalidTime)?;
+        let minute = minute.parse().map_err(|_| TimeError::InvalidTime)?;
+
+        Self::new(hour, minute)
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// The first instant after `now` at which the local wall clock, `utc_offset` seconds ahead
+/// of UTC, shows `time` on the `weekday`, or on any day if there's no `weekday`.
+pub(crate) fn next_occurrence(
+    now: Instant,
+    utc_offset: i32,
+    weekday: Option<Weekday>,
+    time: TimeOfDay,
+) -> TimeResult<Instant> {
+    let now = i64::try_from(now.seconds).map_err(|_| TimeError::InvalidInstant)?;
+    let local = now + i64::from(utc_offset);
+
+    let today = local.div_euclid(SECS_PER_DAY);
+    let second_of_day = local.rem_euclid(SECS_PER_DAY);
+
+    let mut days = match weekday {
+        // the epoch was on a Thursday
+        Some(weekday) => (weekday.index() - (today + 3)).rem_euclid(7),
+        None => 0,
+    };
+    if days == 0 && time.seconds() <= second_of_day {
+        days = if weekday.is_some() { 7 } else { 1 };
+    }
+
+    let next = (today + days) * SECS_PER_DAY + time.seconds() - i64::from(utc_offset);
+    let next = u64::try_from(next).map_err(|_| TimeError::InvalidInstant)?;
+
+    Instant::new(next, 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Thursday 2024-02-29 14:30:00 UTC
+    const NOW: u64 = 1_709_217_000;
+
+    fn at(seconds: u64) -> Instant {
+        Instant::new(seconds, 0).unwrap()
+    }
+
+    fn time(s: &str) -> TimeOfDay {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_times_of_day() {
+        assert_eq!(time("03:00"), TimeOfDay::new(3, 0).unwrap());
+        assert_eq!(time("9:05"), TimeOfDay::new(9, 5).unwrap());
+        assert_eq!(time("23:59").to_string(), "23:59");
+
+        for invalid in ["24:00", "12:60", "12", "12:5", ":30", "ab:cd", "-1:00"] {
+            assert_eq!(
+                invalid.parse::<TimeOfDay>(),
+                Err(TimeError::InvalidTime),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn next_time_of_day_is_today_or_tomorrow() {
+        let later_today = next_occurrence(at(NOW), 0, None, time("18:00")).unwrap();
+        assert_eq!(later_today, at(NOW + 3 * 3600 + 1800));
+
+        let tomorrow = next_occurrence(at(NOW), 0, None, time("03:00")).unwrap();
+        assert_eq!(tomorrow, at(NOW + 12 * 3600 + 1800));
+
+        // exactly now is the next day
+        let now = next_occurrence(at(NOW), 0, None, time("14:30")).unwrap();
+        assert_eq!(now, at(NOW + 24 * 3600));
+    }
+
+    #[test]
+    fn next_time_of_day_is_in_local_time() {
+        // 23:30 in UTC+9 is 14:30 UTC
+        let tokyo = next_occurrence(at(NOW), 9 * 3600, None, time("23:30")).unwrap();
+        assert_eq!(tokyo, at(NOW + 24 * 3600));
+
+        // it's already Friday 01:30 in Sydney (UTC+11), so 03:00 is in 90 minutes
+        let sydney = next_occurrence(at(NOW), 11 * 3600, None, time("03:00")).unwrap();
+        assert_eq!(sydney, at(NOW + 5400));
+
+        // and 09:30 Thursday in New York (UTC-5)
+        let new_york = next_occurrence(at(NOW), -5 * 3600, None, time("10:00")).unwrap();
+        assert_eq!(new_york, at(NOW + 1800));
+    }
+
+    #[test]
+    fn next_weekday() {
+        let monday = next_occurrence(at(NOW), 0, Some(Weekday::Monday), time("09:00")).unwrap();
+        // Thursday 14:30 to Monday 09:00
+        assert_eq!(monday, at(NOW + 3 * 24 * 3600 + 18 * 3600 + 1800));
+
+        let later_today =
+            next_occurrence(at(NOW), 0, Some(Weekday::Thursday), time("15:00")).unwrap();
+        assert_eq!(later_today, at(NOW + 1800));
+
+        let next_week =
+            next_occurrence(at(NOW), 0, Some(Weekday::Thursday), time("09:00")).unwrap();
+        assert_eq!(next_week, at(NOW + 7 * 24 * 3600 - 5 * 3600 - 1800));
+
+        // already Friday in Sydney
+        let sydney =
+            next_occurrence(at(NOW), 11 * 3600, Some(Weekday::Friday), time("09:00")).unwrap();
+        assert_eq!(sydney, at(NOW + 7 * 3600 + 1800));
+    }
+}
@@ -0,0 +1,89 @@
+mod app {
+    use crux_core::macros::Effect;
+    use crux_time::{Duration, Time, TimeResponse};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        Now,
+        Wait,
+        Responded(TimeResponse),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub responses: Vec<TimeResponse>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        // times out on the next check
+        #[effect(timeout_ms = 0)]
+        pub time: Time<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Vec<TimeResponse>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Now => caps.time.now(Event::Responded),
+                Event::Wait => caps
+                    .time
+                    .notify_after(Duration::from_secs(60).unwrap(), Event::Responded),
+                Event::Responded(response) => model.responses.push(response),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Vec<TimeResponse> {
+            model.responses.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::Core;
+    use crux_time::TimeResponse;
+
+    use crate::app::{App, Effect, Event};
+
+    #[test]
+    fn asking_for_the_time_times_out() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Now);
+        let Some(Effect::Time(mut request)) = effects.pop() else {
+            panic!("expected a Time effect");
+        };
+
+        assert!(core.check_timeouts().is_empty());
+        assert_eq!(core.view(), vec![TimeResponse::TimedOut]);
+
+        // resolving after the timeout has no effect
+        assert!(core
+            .resolve(&mut request, TimeResponse::InstantArrived)
+            .is_empty());
+        assert_eq!(core.view(), vec![TimeResponse::TimedOut]);
+    }
+
+    #[test]
+    fn notifications_are_not_limited_by_the_timeout() {
+        let core: Core<Effect, App> = Core::default();
+
+        let mut effects = core.process_event(Event::Wait);
+        let Some(Effect::Time(mut request)) = effects.pop() else {
+            panic!("expected a Time effect");
+        };
+
+        assert!(core.check_timeouts().is_empty());
+        assert!(core.view().is_empty());
+
+        core.resolve(&mut request, TimeResponse::DurationElapsed);
+        assert_eq!(core.view(), vec![TimeResponse::DurationElapsed]);
+    }
+}
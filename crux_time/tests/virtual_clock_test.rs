@@ -0,0 +1,227 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_time::{Duration, Instant, Recurrence, Time, TimeResponse, Weekday};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Debug)]
+    pub enum Event {
+        Search(String),
+        SearchDue(usize),
+        Remind(Instant),
+        Reminded,
+        RemindTonight,
+        WeeklyReview,
+        WeeklyReviewDue,
+        Payday,
+        PaydayDue(Option<Instant>),
+        CheckTime,
+        TimeChecked(TimeResponse),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub query: String,
+        pub pending: usize,
+        pub searches: Vec<String>,
+        pub reminders: usize,
+        pub reviews: usize,
+        pub paydays: Vec<Instant>,
+        pub paydays_ended: bool,
+        pub checked: Option<Instant>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Search(query) => {
+                    model.query = query;
+                    model.pending += 1;
+
+                    let pending = model.pending;
+                    caps.time.notify_after(
+                        Duration::from_millis(300).expect("valid duration"),
+                        move |_| Event::SearchDue(pending),
+                    );
+                }
+                Event::SearchDue(pending) => {
+                    if pending == model.pending {
+                        model.searches.push(model.query.clone());
+                        caps.render.render();
+                    }
+                }
+                Event::Remind(instant) => caps.time.notify_at(instant, |_| Event::Reminded),
+                Event::Reminded => {
+                    model.reminders += 1;
+                    caps.time.now(Event::TimeChecked);
+                }
+                Event::RemindTonight => caps.time.notify_at_next("03:00", |_| Event::Reminded),
+                // a recurring reminder, scheduled again every time it's due
+                Event::WeeklyReview => {
+                    caps.time
+                        .notify_on(Weekday::Monday, "09:00", |_| Event::WeeklyReviewDue);
+                }
+                Event::WeeklyReviewDue => {
+                    model.reviews += 1;
+                    self.update(Event::WeeklyReview, model, caps);
+                }
+                // the last Friday of the first three months of 2024, at 17:00
+                Event::Payday => {
+                    let payday: Recurrence =
+                        "DTSTART:20240101T170000\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3"
+                            .parse()
+                            .expect("valid recurrence");
+                    caps.time
+                        .notify_at_next_occurrence(&payday, Event::PaydayDue);
+                }
+                Event::PaydayDue(Some(instant)) => {
+                    model.paydays.push(instant);
+                    self.update(Event::Payday, model, caps);
+                }
+                Event::PaydayDue(None) => model.paydays_ended = true,
+                Event::CheckTime => caps.time.now(Event::TimeChecked),
+                Event::TimeChecked(TimeResponse::Now(instant)) => model.checked = Some(instant),
+                Event::TimeChecked(_) => panic!("Unexpected time response"),
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub time: Time<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use std::time::Duration;
+
+    use crux_core::{assert_effect, testing::AppTester};
+    use crux_time::{Instant, TimeRequest};
+
+    use crate::shared::{App, Effect, Event, Model};
+
+    fn app() -> AppTester<App, Effect> {
+        AppTester::default().with_virtual_clock::<TimeRequest>(Duration::from_secs(1_000))
+    }
+
+    #[test]
+    fn debounced_search_fires_once_the_typing_stops() {
+        let app = app();
+        let mut model = Model::default();
+
+        let update = app.update(Event::Search("cr".to_string()), &mut model);
+        assert!(update.effects.is_empty());
+
+        app.advance_time(Duration::from_millis(200), &mut model);
+        app.update(Event::Search("crux".to_string()), &mut model);
+
+        let update = app.advance_time(Duration::from_millis(200), &mut model);
+        assert!(update.effects.is_empty());
+        assert!(model.searches.is_empty());
+
+        let update = app.advance_time(Duration::from_millis(100), &mut model);
+        assert_effect!(update, Effect::Render(_));
+        assert_eq!(model.searches, vec!["crux"]);
+        assert_eq!(app.now(), Some(Duration::from_millis(1_000_500)));
+    }
+
+    #[test]
+    fn timers_fire_in_the_order_of_their_deadlines() {
+        let app = app();
+        let mut model = Model::default();
+
+        let at = |seconds| Instant::new(seconds, 0).unwrap();
+        app.update(Event::Remind(at(1_060)), &mut model);
+        app.update(Event::Remind(at(1_030)), &mut model);
+
+        app.advance_time(Duration::from_secs(45), &mut model);
+        assert_eq!(model.reminders, 1);
+        // the time was checked at the reminder, not at the end of the advance
+        assert_eq!(model.checked, Some(at(1_030)));
+
+        app.advance_time(Duration::from_secs(15), &mut model);
+        assert_eq!(model.reminders, 2);
+        assert_eq!(model.checked, Some(at(1_060)));
+    }
+
+    // Thursday 2024-02-29 14:30:00 UTC
+    const THURSDAY: u64 = 1_709_217_000;
+    const HOUR: u64 = 3600;
+    const DAY: u64 = 24 * HOUR;
+
+    #[test]
+    fn reminders_are_scheduled_on_the_wall_clock() {
+        let app = AppTester::<App, Effect>::default()
+            .with_virtual_clock::<TimeRequest>(Duration::from_secs(THURSDAY));
+        let mut model = Model::default();
+
+        app.update(Event::RemindTonight, &mut model);
+
+        app.advance_time(Duration::from_secs(12 * HOUR), &mut model);
+        assert_eq!(model.reminders, 0);
+
+        app.advance_time(Duration::from_secs(HOUR), &mut model);
+        assert_eq!(model.reminders, 1);
+        // Friday 03:00
+        let friday = Instant::new(THURSDAY + 12 * HOUR + 1800, 0).unwrap();
+        assert_eq!(model.checked, Some(friday));
+    }
+
+    #[test]
+    fn recurring_reminders_fire_every_week() {
+        let app = AppTester::<App, Effect>::default()
+            .with_virtual_clock::<TimeRequest>(Duration::from_secs(THURSDAY));
+        let mut model = Model::default();
+
+        app.update(Event::WeeklyReview, &mut model);
+
+        // Monday 09:00 is 3 days and 18.5 hours away
+        app.advance_time(Duration::from_secs(3 * DAY + 18 * HOUR), &mut model);
+        assert_eq!(model.reviews, 0);
+        app.advance_time(Duration::from_secs(HOUR), &mut model);
+        assert_eq!(model.reviews, 1);
+
+        app.advance_time(Duration::from_secs(7 * DAY), &mut model);
+        assert_eq!(model.reviews, 2);
+    }
+
+    #[test]
+    fn recurrences_fire_until_they_end() {
+        let app = AppTester::<App, Effect>::default()
+            .with_virtual_clock::<TimeRequest>(Duration::from_secs(THURSDAY));
+        let mut model = Model::default();
+
+        app.update(Event::Payday, &mut model);
+
+        // Friday 29 March at 17:00 is the last payday, the earlier ones have passed
+        let march = THURSDAY + 29 * DAY + 2 * HOUR + 1800;
+        app.advance_time(Duration::from_secs(march - THURSDAY - 1), &mut model);
+        assert!(model.paydays.is_empty());
+
+        app.advance_time(Duration::from_secs(1), &mut model);
+        assert_eq!(model.paydays, vec![Instant::new(march, 0).unwrap()]);
+        assert!(model.paydays_ended);
+    }
+
+    #[test]
+    fn the_current_time_resolves_without_time_passing() {
+        let app = app();
+        let mut model = Model::default();
+
+        app.update(Event::CheckTime, &mut model);
+        assert_eq!(model.checked, None);
+
+        app.advance_time(Duration::ZERO, &mut model);
+        assert_eq!(model.checked, Some(Instant::new(1_000, 0).unwrap()));
+    }
+}
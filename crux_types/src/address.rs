@@ -0,0 +1,415 @@
+//! Postal addresses, see [`PostalAddress`]
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::TypeError;
+
+/// How postal codes are written in a country
+#[derive(Clone, Copy)]
+enum PostalCode {
+    /// A number of digits, split at `split` by the separator if it's set, e.g. `123 45`
+    Digits(usize, Option<(usize, char)>),
+    /// `12345` or `12345-6789`
+    UnitedStates,
+    /// `A1A 1A1`
+    Canada,
+    /// `SW1A 1AA`
+    UnitedKingdom,
+    /// `1234 AB`
+    Netherlands,
+}
+
+fn postal_code_format(country: &str) -> Option<PostalCode> {
+    Some(match country {
+        "US" => PostalCode::UnitedStates,
+        "CA" => PostalCode::Canada,
+        "GB" => PostalCode::UnitedKingdom,
+        "NL" => PostalCode::Netherlands,
+        "DE" | "FR" | "ES" | "IT" | "FI" | "MX" => PostalCode::Digits(5, None),
+        "AT" | "AU" | "BE" | "CH" | "DK" | "NO" | "NZ" | "ZA" => PostalCode::Digits(4, None),
+        "SE" => PostalCode::Digits(5, Some((3, ' '))),
+        "PL" => PostalCode::Digits(5, Some((2, '-'))),
+        "PT" => PostalCode::Digits(7, Some((4, '-'))),
+        "JP" => PostalCode::Digits(7, Some((3, '-'))),
+        "BR" => PostalCode::Digits(8, Some((5, '-'))),
+        "IN" => PostalCode::Digits(6, None),
+        _ => return None,
+    })
+}
+
+/// The postal code written the way the country does, if it's valid there
+fn normalize_postal_code(format: PostalCode, code: &str) -> Option<String> {
+    let compact: String = code
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-'))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    let letters = |s: &str| s.chars().all(|c| c.is_ascii_uppercase());
+
+    match format {
+        PostalCode::Digits(length, split) => {
+            if compact.len() != length || !digits(&compact) {
+                return None;
+            }
+
+            Some(match split {
+                Some((at, separator)) => format!("{}{separator}{}", &compact[..at], &compact[at..]),
+                None => compact,
+            })
+        }
+        PostalCode::UnitedStates => match compact.len() {
+            5 if digits(&compact) => Some(compact),
+            9 if digits(&compact) => Some(format!("{}-{}", &compact[..5], &compact[5..])),
+            _ => None,
+        },
+        PostalCode::Canada => {
+            let valid = compact.len() == 6
+                && compact.chars().enumerate().all(|(index, c)| {
+                    if index % 2 == 0 {
+                        c.is_ascii_uppercase()
+                    } else {
+                        c.is_ascii_digit()
+                    }
+                });
+
+            valid.then(|| format!("{} {}", &compact[..3], &compact[3..]))
+        }
+        PostalCode::UnitedKingdom => {
+            if !(5..=7).contains(&compact.len()) || !compact.is_ascii() {
+                return None;
+            }
+
+            // the inward code is a digit and two letters, the outward code starts with a letter
+            let (outward, inward) = compact.split_at(compact.len() - 3);
+            let valid = digits(&inward[..1])
+                && letters(&inward[1..])
+                && outward.starts_with(|c: char| c.is_ascii_uppercase())
+                && outward.chars().all(|c| c.is_ascii_alphanumeric());
+
+            valid.then(|| format!("{outward} {inward}"))
+        }
+        PostalCode::Netherlands => {
+            let valid = compact.len() == 6
+                && compact.is_ascii()
+                && digits(&compact[..4])
+                && letters(&compact[4..])
+                && !compact.starts_with('0');
+
+            valid.then(|| format!("{} {}", &compact[..4], &compact[4..]))
+        }
+    }
+}
+
+/// Trim and collapse whitespace
+fn clean(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A postal address, normalized so it can be compared and shown the same way in every shell.
+///
+/// Whitespace is trimmed and collapsed, the country and, in the United States and Canada, the
+/// region are upper-cased, and postal codes are checked and written the way the country does
+/// (e.g. `sw1a1aa` as `SW1A 1AA`) for the countries which have a known format. Postal codes are
+/// required there, and the region (state or province) in the United States, Canada and
+/// Australia.
+///
+/// Serialized as a struct of its fields, which is validated when deserialized.
+///
+/// ```
+/// use crux_types::PostalAddress;
+///
+/// let address = PostalAddress::new(
+///     "us",
+///     &["1600  Amphitheatre Pkwy"],
+///     "Mountain View",
+///     Some("ca"),
+///     Some("940431351"),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(address.postal_code(), Some("94043-1351"));
+/// assert_eq!(
+///     address.format_lines(),
+///     ["1600 Amphitheatre Pkwy", "Mountain View, CA 94043-1351"]
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostalAddress {
+    country: String,
+    street: Vec<String>,
+    locality: String,
+    region: Option<String>,
+    postal_code: Option<String>,
+}
+
+impl PostalAddress {
+    /// An address in the `country` (an ISO 3166 code such as `"GB"`), with up to three lines
+    /// of `street` address, in the `locality` (city or town).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TypeError::InvalidAddress`] if a required part is missing, or the postal code
+    /// isn't valid in the country.
+    pub fn new(
+        country: &str,
+        street: &[&str],
+        locality: &str,
+        region: Option<&str>,
+        postal_code: Option<&str>,
+    ) -> Result<Self, TypeError> {
+        let invalid = |message: &str| TypeError::InvalidAddress(message.to_string());
+
+        let country = country.trim().to_ascii_uppercase();
+        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(invalid("the country should be a two letter ISO 3166 code"));
+        }
+
+        let street: Vec<String> = street
+            .iter()
+            .map(|line| clean(line))
+            .filter(|line| !line.is_empty())
+            .collect();
+        if street.is_empty() || street.len() > 3 {
+            return Err(invalid("the street address should have one to three lines"));
+        }
+
+        let locality = clean(locality);
+        if locality.is_empty() {
+            return Err(invalid("the locality is missing"));
+        }
+
+        let region = region.map(clean).filter(|region| !region.is_empty());
+        let region = match country.as_str() {
+            "US" | "CA" => region.map(|region| region.to_ascii_uppercase()),
+            _ => region,
+        };
+        if region.is_none() && matches!(country.as_str(), "US" | "CA" | "AU") {
+            return Err(invalid("the region is missing"));
+        }
+
+        let postal_code = postal_code.map(clean).filter(|code| !code.is_empty());
+        let postal_code = match (postal_code_format(&country), postal_code) {
+            (Some(format), Some(code)) => Some(
+                normalize_postal_code(format, &code)
+                    .ok_or_else(|| invalid("the postal code isn't valid in the country"))?,
+            ),
+            (Some(_), None) => return Err(invalid("the postal code is missing")),
+            (None, code) => code.map(|code| code.to_uppercase()),
+        };
+
+        Ok(Self {
+            country,
+            street,
+            locality,
+            region,
+            postal_code,
+        })
+    }
+
+    /// The ISO 3166 country code, e.g. `GB`
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    pub fn street(&self) -> &[String] {
+        &self.street
+    }
+
+    pub fn locality(&self) -> &str {
+        &self.locality
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn postal_code(&self) -> Option<&str> {
+        self.postal_code.as_deref()
+    }
+
+    /// The address as it's written on an envelope in its country, without the country itself:
+    /// the street, then the locality with the region and postal code, e.g.
+    /// `Mountain View, CA 94043` in the United States, `75008 Paris` in most of Europe, or
+    /// the postal code on a line of its own in the United Kingdom.
+    pub fn format_lines(&self) -> Vec<String> {
+        let mut lines = self.street.clone();
+        let region = self.region.as_deref();
+        let postal_code = self.postal_code.as_deref();
+
+        let join = |parts: &[Option<&str>]| {
+            parts
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        match self.country.as_str() {
+            "US" | "CA" => lines.push(format!(
+                "{}, {}",
+                self.locality,
+                join(&[region, postal_code])
+            )),
+            "AU" => lines.push(join(&[Some(&self.locality), region, postal_code])),
+            "GB" | "IE" => {
+                lines.push(self.locality.clone());
+                lines.extend(region.map(str::to_string));
+                lines.extend(postal_code.map(str::to_string));
+            }
+            _ => {
+                lines.push(join(&[postal_code, Some(&self.locality)]));
+                lines.extend(region.map(str::to_string));
+            }
+        }
+
+        lines
+    }
+}
+
+impl<'de> Deserialize<'de> for PostalAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "PostalAddress", rename_all = "camelCase")]
+        struct Repr {
+            country: String,
+            street: Vec<String>,
+            locality: String,
+            region: Option<String>,
+            postal_code: Option<String>,
+        }
+
+        let repr = Repr::deserialize(deserializer)?;
+        let street: Vec<&str> = repr.street.iter().map(String::as_str).collect();
+
+        PostalAddress::new(
+            &repr.country,
+            &street,
+            &repr.locality,
+            repr.region.as_deref(),
+            repr.postal_code.as_deref(),
+        )
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn postal_code(country: &str, code: &str) -> Option<String> {
+        normalize_postal_code(postal_code_format(country).unwrap(), code)
+    }
+
+    #[test]
+    fn normalizes_postal_codes() {
+        assert_eq!(postal_code("GB", "sw1a1aa").as_deref(), Some("SW1A 1AA"));
+        assert_eq!(postal_code("GB", "M1 1AE").as_deref(), Some("M1 1AE"));
+        assert_eq!(postal_code("CA", "k1a0b1").as_deref(), Some("K1A 0B1"));
+        assert_eq!(postal_code("NL", "1012ab").as_deref(), Some("1012 AB"));
+        assert_eq!(postal_code("SE", "11455").as_deref(), Some("114 55"));
+        assert_eq!(postal_code("JP", "100-0001").as_deref(), Some("100-0001"));
+        assert_eq!(
+            postal_code("US", "12345 6789").as_deref(),
+            Some("12345-6789")
+        );
+
+        for (country, code) in [
+            ("GB", "SW1A"),
+            ("GB", "1W1A 1AA"),
+            ("CA", "K1A 0BB"),
+            ("NL", "0123 AB"),
+            ("DE", "1234"),
+            ("US", "123456"),
+            ("FR", "75OO8"),
+        ] {
+            assert_eq!(postal_code(country, code), None, "{country} {code}");
+        }
+    }
+
+    #[test]
+    fn validates_addresses() {
+        let address = PostalAddress::new(
+            " gb ",
+            &["10  Downing Street", ""],
+            "London",
+            None,
+            Some("sw1a2aa"),
+        )
+        .unwrap();
+        assert_eq!(address.country(), "GB");
+        assert_eq!(
+            address.format_lines(),
+            ["10 Downing Street", "London", "SW1A 2AA"]
+        );
+
+        let error = |message: &str| Err(TypeError::InvalidAddress(message.to_string()));
+        assert_eq!(
+            PostalAddress::new("GBR", &["1 Street"], "London", None, Some("SW1A 2AA")),
+            error("the country should be a two letter ISO 3166 code")
+        );
+        assert_eq!(
+            PostalAddress::new("DE", &[], "Berlin", None, Some("10117")),
+            error("the street address should have one to three lines")
+        );
+        assert_eq!(
+            PostalAddress::new("DE", &["Pariser Platz 1"], "Berlin", None, None),
+            error("the postal code is missing")
+        );
+        assert_eq!(
+            PostalAddress::new("US", &["1 Main St"], "Springfield", None, Some("12345")),
+            error("the region is missing")
+        );
+        assert_eq!(
+            PostalAddress::new("FR", &["1 rue de Rivoli"], "Paris", None, Some("7500")),
+            error("the postal code isn't valid in the country")
+        );
+    }
+
+    #[test]
+    fn formats_addresses_by_country() {
+        let address = PostalAddress::new(
+            "FR",
+            &["55 rue du Faubourg Saint-Honoré"],
+            "Paris",
+            None,
+            Some("75008"),
+        )
+        .unwrap();
+        assert_eq!(
+            address.format_lines(),
+            ["55 rue du Faubourg Saint-Honoré", "75008 Paris"]
+        );
+
+        // countries without a known postal code format keep theirs as it is
+        let address =
+            PostalAddress::new("AR", &["Balcarce 50"], "Buenos Aires", None, Some("c1064"))
+                .unwrap();
+        assert_eq!(address.postal_code(), Some("C1064"));
+        assert_eq!(
+            address.format_lines(),
+            ["Balcarce 50", "C1064 Buenos Aires"]
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_struct() {
+        let address =
+            PostalAddress::new("NL", &["Dam 1"], "Amsterdam", None, Some("1012JS")).unwrap();
+
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(
+            json,
+            r#"{"country":"NL","street":["Dam 1"],"locality":"Amsterdam","region":null,"postalCode":"1012 JS"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<PostalAddress>(&json).unwrap(),
+            address
+        );
+
+        let invalid = json.replace("1012 JS", "1012");
+        assert!(serde_json::from_str::<PostalAddress>(&invalid).is_err());
+    }
+}
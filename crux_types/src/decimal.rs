@@ -0,0 +1,552 @@
+//! Exact decimal numbers, see [`Decimal`]
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{NumberFormat, TypeError};
+
+/// How to round a number which can't be represented exactly with the available decimals
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// To the nearest number, and ties to the even one ("banker's rounding")
+    #[default]
+    HalfEven,
+    /// To the nearest number, and ties away from zero
+    HalfUp,
+    /// Towards zero, i.e. truncating
+    TowardZero,
+    /// Away from zero, i.e. up for positive numbers and down for negative ones
+    AwayFromZero,
+    /// Towards negative infinity
+    Floor,
+    /// Towards positive infinity
+    Ceiling,
+}
+
+impl Rounding {
+    /// Divide `numerator` by `denominator`, rounding the quotient
+    fn divide(self, numerator: i128, denominator: i128) -> Option<i128> {
+        let quotient = numerator.checked_div(denominator)?;
+        let remainder = numerator % denominator;
+        if remainder == 0 {
+            return Some(quotient);
+        }
+
+        let positive = (numerator < 0) == (denominator < 0);
+        let remainder = remainder.unsigned_abs();
+        let rest = denominator.unsigned_abs() - remainder;
+
+        let away_from_zero = match self {
+            Rounding::HalfEven => remainder > rest || (remainder == rest && quotient % 2 != 0),
+            Rounding::HalfUp => remainder >= rest,
+            Rounding::TowardZero => false,
+            Rounding::AwayFromZero => true,
+            Rounding::Floor => !positive,
+            Rounding::Ceiling => positive,
+        };
+
+        if !away_from_zero {
+            Some(quotient)
+        } else if positive {
+            quotient.checked_add(1)
+        } else {
+            quotient.checked_sub(1)
+        }
+    }
+}
+
+fn pow10(exponent: u32) -> Option<i128> {
+    10_i128.checked_pow(exponent)
+}
+
+/// An exact decimal number, `units` × 10<sup>-`scale`</sup>, with up to
+/// [`MAX_SCALE`](Decimal::MAX_SCALE) decimals.
+///
+/// Addition, subtraction and multiplication are exact, division rounds to the requested number
+/// of decimals. The operators panic on overflow, like integer operators do, the `checked_`
+/// methods return `None` instead.
+///
+/// Decimals remember their number of decimals, so `1.50` is displayed as `1.50`, but compares
+/// equal to `1.5`. They're serialized as a string, e.g. `"1.50"`, so they keep their precision
+/// in every shell.
+///
+/// ```
+/// use crux_types::{Decimal, Rounding};
+///
+/// let price: Decimal = "19.99".parse().unwrap();
+/// let total = price * Decimal::from(3);
+/// assert_eq!(total.to_string(), "59.97");
+///
+/// let share = total.checked_div(Decimal::from(7), 2, Rounding::HalfEven).unwrap();
+/// assert_eq!(share.to_string(), "8.57");
+///
+/// // no floating point surprises
+/// let sum = "0.1".parse::<Decimal>().unwrap() + "0.2".parse().unwrap();
+/// assert_eq!(sum, "0.3".parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Decimal {
+    units: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// The largest number of decimals a `Decimal` can have
+    pub const MAX_SCALE: u32 = 18;
+
+    pub const ZERO: Decimal = Decimal { units: 0, scale: 0 };
+    pub const ONE: Decimal = Decimal { units: 1, scale: 0 };
+
+    /// The number `units` × 10<sup>-`scale`</sup>, e.g. `Decimal::new(1999, 2)` is 19.99.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is larger than [`MAX_SCALE`](Decimal::MAX_SCALE).
+    pub fn new(units: i128, scale: u32) -> Self {
+        assert!(
+            scale <= Self::MAX_SCALE,
+            "a decimal can have at most {} decimals",
+            Self::MAX_SCALE
+        );
+
+        Self { units, scale }
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    /// The number of decimals
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.units == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.units < 0
+    }
+
+    /// The absolute value, see [`checked_abs`](Decimal::checked_abs).
+    ///
+    /// # Panics
+    ///
+    /// Panics on overflow, i.e. for the smallest representable number.
+    #[must_use]
+    pub fn abs(&self) -> Self {
+        self.checked_abs().expect("decimal overflow")
+    }
+
+    /// The absolute value. Returns `None` on overflow.
+    pub fn checked_abs(&self) -> Option<Self> {
+        Some(Self {
+            units: self.units.checked_abs()?,
+            scale: self.scale,
+        })
+    }
+
+    /// The negated number. Returns `None` on overflow.
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self {
+            units: self.units.checked_neg()?,
+            scale: self.scale,
+        })
+    }
+
+    /// This number with exactly `scale` decimals, rounded with `rounding` if it has more,
+    /// padded with zeros if it has fewer. Returns `None` on overflow.
+    pub fn checked_round(&self, scale: u32, rounding: Rounding) -> Option<Self> {
+        if scale > Self::MAX_SCALE {
+            return None;
+        }
+
+        let units = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.units,
+            Ordering::Greater => self.units.checked_mul(pow10(scale - self.scale)?)?,
+            Ordering::Less => rounding.divide(self.units, pow10(self.scale - scale)?)?,
+        };
+
+        Some(Self { units, scale })
+    }
+
+    /// This number with exactly `scale` decimals, see [`checked_round`](Decimal::checked_round).
+    ///
+    /// # Panics
+    ///
+    /// Panics on overflow, or if `scale` is larger than [`MAX_SCALE`](Decimal::MAX_SCALE).
+    #[must_use]
+    pub fn round(&self, scale: u32, rounding: Rounding) -> Self {
+        self.checked_round(scale, rounding)
+            .expect("decimal overflow")
+    }
+
+    /// The number without trailing zeros in its decimals, e.g. `1.5` for `1.500`
+    #[must_use]
+    pub fn normalize(&self) -> Self {
+        let mut normalized = *self;
+        while normalized.scale > 0 && normalized.units % 10 == 0 {
+            normalized.units /= 10;
+            normalized.scale -= 1;
+        }
+
+        normalized
+    }
+
+    pub fn checked_add(&self, other: Decimal) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescaled(scale)?, other.rescaled(scale)?);
+
+        Some(Self {
+            units: a.checked_add(b)?,
+            scale,
+        })
+    }
+
+    pub fn checked_sub(&self, other: Decimal) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescaled(scale)?, other.rescaled(scale)?);
+
+        Some(Self {
+            units: a.checked_sub(b)?,
+            scale,
+        })
+    }
+
+    /// Multiply exactly, unless the product has more than [`MAX_SCALE`](Decimal::MAX_SCALE)
+    /// decimals, in which case it's rounded half to even.
+    pub fn checked_mul(&self, other: Decimal) -> Option<Self> {
+        let product = Self {
+            units: self.units.checked_mul(other.units)?,
+            scale: self.scale + other.scale,
+        };
+
+        if product.scale > Self::MAX_SCALE {
+            let units = Rounding::HalfEven
+                .divide(product.units, pow10(product.scale - Self::MAX_SCALE)?)?;
+            return Some(Self::new(units, Self::MAX_SCALE));
+        }
+
+        Some(product)
+    }
+
+    /// Divide by `other`, rounding the quotient to `scale` decimals. Returns `None` when dividing
+    /// by zero or on overflow.
+    pub fn checked_div(&self, other: Decimal, scale: u32, rounding: Rounding) -> Option<Self> {
+        if other.is_zero() || scale > Self::MAX_SCALE {
+            return None;
+        }
+
+        // units / 10^scale = (self.units / 10^self.scale) / (other.units / 10^other.scale)
+        let exponent = i64::from(scale) + i64::from(other.scale) - i64::from(self.scale);
+        let exponent_abs = u32::try_from(exponent.unsigned_abs()).ok()?;
+        let (numerator, denominator) = if exponent >= 0 {
+            (self.units.checked_mul(pow10(exponent_abs)?)?, other.units)
+        } else {
+            (self.units, other.units.checked_mul(pow10(exponent_abs)?)?)
+        };
+
+        Some(Self {
+            units: rounding.divide(numerator, denominator)?,
+            scale,
+        })
+    }
+
+    /// Format the number with the separators of the `locale`, e.g. `1.234,5` in German.
+    pub fn format(&self, locale: &str) -> String {
+        NumberFormat::for_locale(locale).format_decimal(self)
+    }
+
+    fn rescaled(&self, scale: u32) -> Option<i128> {
+        self.units.checked_mul(pow10(scale - self.scale)?)
+    }
+
+    /// The integer part and the fractional part with `MAX_SCALE` decimals, which are the same
+    /// for equal numbers with different scales
+    fn parts(&self) -> (i128, i128) {
+        let divisor = pow10(self.scale).expect("scale is at most MAX_SCALE");
+        let fraction = (self.units % divisor) * pow10(Self::MAX_SCALE - self.scale).unwrap_or(1);
+
+        (self.units / divisor, fraction)
+    }
+
+    /// The digits of the absolute value, with the integer and fractional parts separate
+    pub(crate) fn digits(&self) -> (String, String) {
+        let digits = self.units.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+
+        (integer.to_string(), fraction.to_string())
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts().cmp(&other.parts())
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        self.checked_neg().expect("decimal overflow")
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: Decimal) -> Decimal {
+        self.checked_add(other).expect("decimal overflow")
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: Decimal) -> Decimal {
+        self.checked_sub(other).expect("decimal overflow")
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: Decimal) -> Decimal {
+        self.checked_mul(other).expect("decimal overflow")
+    }
+}
+
+macro_rules! integer_decimals {
+    ($($integer:ty),*) => {
+        $(
+            impl From<$integer> for Decimal {
+                fn from(value: $integer) -> Self {
+                    Self::new(i128::from(value), 0)
+                }
+            }
+        )*
+    };
+}
+
+integer_decimals!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (integer, fraction) = self.digits();
+        let sign = if self.is_negative() { "-" } else { "" };
+
+        if fraction.is_empty() {
+            write!(f, "{sign}{integer}")
+        } else {
+            write!(f, "{sign}{integer}.{fraction}")
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = TypeError;
+
+    /// Parse a number like `-1234.50`. Exponents and group separators are not supported.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || TypeError::InvalidDecimal(value.to_string());
+
+        let (negative, unsigned) = match value.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+        let all_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+            return Err(invalid());
+        }
+        if unsigned.ends_with('.') {
+            return Err(invalid());
+        }
+
+        let scale = u32::try_from(fraction.len()).map_err(|_| invalid())?;
+        if scale > Self::MAX_SCALE {
+            return Err(invalid());
+        }
+
+        let units: i128 = format!("{integer}{fraction}")
+            .parse()
+            .map_err(|_| invalid())?;
+
+        Ok(Self {
+            units: if negative { -units } else { units },
+            scale,
+        })
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("Decimal", &self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Decimal")]
+        struct Repr(String);
+
+        let Repr(decimal) = Repr::deserialize(deserializer)?;
+
+        decimal.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        for value in ["0", "1.50", "-0.05", "1234567.890", "0.000000000000000001"] {
+            assert_eq!(d(value).to_string(), value);
+        }
+        assert_eq!(d("+7").to_string(), "7");
+
+        for value in [
+            "",
+            "-",
+            "1.",
+            ".5",
+            "1,5",
+            "1e3",
+            "0.0000000000000000001",
+            "--1",
+        ] {
+            assert!(value.parse::<Decimal>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn compares_by_value() {
+        assert_eq!(d("1.50"), d("1.5"));
+        assert!(d("-1.5") < d("-1.2"));
+        assert!(d("-0.5") < d("0.3"));
+        assert!(d("10") > d("9.999"));
+        assert_eq!(d("1.50").normalize().to_string(), "1.5");
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(d("2.0"));
+        assert!(set.contains(&d("2")));
+    }
+
+    #[test]
+    fn adds_and_multiplies_exactly() {
+        assert_eq!((d("0.1") + d("0.2")).to_string(), "0.3");
+        assert_eq!((d("5") - d("0.25")).to_string(), "4.75");
+        assert_eq!((d("1.10") * d("3")).to_string(), "3.30");
+        assert_eq!((d("-1.5") * d("0.5")).to_string(), "-0.75");
+        assert_eq!(Decimal::new(i128::MAX, 0).checked_add(Decimal::ONE), None);
+    }
+
+    #[test]
+    fn overflows_at_the_smallest_number() {
+        let min = Decimal::new(i128::MIN, 2);
+
+        assert_eq!(min.checked_sub(Decimal::ONE), None);
+        assert_eq!(
+            Decimal::ZERO.checked_sub(Decimal::new(-1, 0)),
+            Some(Decimal::ONE)
+        );
+        assert_eq!(
+            min.checked_sub(Decimal::new(-1, 2)).unwrap().units(),
+            i128::MIN + 1
+        );
+        assert_eq!(min.checked_abs(), None);
+        assert_eq!(min.checked_neg(), None);
+        assert_eq!(d("-1.5").checked_abs(), Some(d("1.5")));
+        assert_eq!(d("1.5").checked_neg(), Some(d("-1.5")));
+    }
+
+    #[test]
+    fn divides_with_rounding() {
+        let third = d("1").checked_div(d("3"), 4, Rounding::HalfEven).unwrap();
+        assert_eq!(third.to_string(), "0.3333");
+
+        let value = d("10.5")
+            .checked_div(d("0.25"), 0, Rounding::HalfEven)
+            .unwrap();
+        assert_eq!(value.to_string(), "42");
+
+        assert_eq!(
+            d("1").checked_div(Decimal::ZERO, 2, Rounding::HalfEven),
+            None
+        );
+    }
+
+    #[test]
+    fn rounds() {
+        let cases = [
+            (Rounding::HalfEven, ["2", "2", "-2", "3", "-1"]),
+            (Rounding::HalfUp, ["3", "2", "-3", "3", "-1"]),
+            (Rounding::TowardZero, ["2", "1", "-2", "2", "-1"]),
+            (Rounding::AwayFromZero, ["3", "2", "-3", "3", "-2"]),
+            (Rounding::Floor, ["2", "1", "-3", "2", "-2"]),
+            (Rounding::Ceiling, ["3", "2", "-2", "3", "-1"]),
+        ];
+
+        for (rounding, expected) in cases {
+            let rounded: Vec<_> = ["2.5", "1.5", "-2.5", "2.6", "-1.4"]
+                .into_iter()
+                .map(|value| d(value).round(0, rounding).to_string())
+                .collect();
+
+            assert_eq!(rounded, expected, "{rounding:?}");
+        }
+
+        assert_eq!(d("1.5").round(3, Rounding::HalfEven).to_string(), "1.500");
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        let json = serde_json::to_string(&d("-12.30")).unwrap();
+        assert_eq!(json, r#""-12.30""#);
+
+        let decimal: Decimal = serde_json::from_str(&json).unwrap();
+        assert_eq!(decimal.to_string(), "-12.30");
+
+        assert!(serde_json::from_str::<Decimal>(r#""twelve""#).is_err());
+    }
+}
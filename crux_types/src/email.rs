@@ -0,0 +1,182 @@
+//! Email addresses, see [`EmailAddress`]
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::TypeError;
+
+/// A syntactically valid email address, e.g. `ada@example.com`.
+///
+/// Addresses are checked against the common subset of RFC 5322 which mail providers accept:
+/// a local part of letters, digits, dots (not at the start or end, nor two in a row) and
+/// ``!#$%&'*+/=?^_`{|}~-``, an `@`, and a domain name with at least two labels and an
+/// alphabetic top level domain. Quoted local parts, comments and IP address literals are
+/// rejected. Surrounding whitespace is trimmed and the domain is lower-cased, the local part
+/// keeps its case. Serialized as the address.
+///
+/// ```
+/// use crux_types::EmailAddress;
+///
+/// let email: EmailAddress = " Ada.Lovelace+crux@Example.COM ".parse().unwrap();
+/// assert_eq!(email.to_string(), "Ada.Lovelace+crux@example.com");
+/// assert_eq!(email.domain(), "example.com");
+///
+/// assert!("ada@localhost".parse::<EmailAddress>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EmailAddress {
+    address: String,
+    at: usize,
+}
+
+impl EmailAddress {
+    pub fn local_part(&self) -> &str {
+        &self.address[..self.at]
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.address[self.at + 1..]
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.address
+    }
+}
+
+fn is_valid_local_part(local: &str) -> bool {
+    const SPECIALS: &str = "!#$%&'*+/=?^_`{|}~-";
+
+    !local.is_empty()
+        && local.len() <= 64
+        && local.split('.').all(|atom| {
+            !atom.is_empty()
+                && atom
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || SPECIALS.contains(c))
+        })
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    let top_level = labels.last().copied().unwrap_or_default();
+
+    domain.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && top_level.len() >= 2
+        && top_level.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Parses and normalizes an address, erroring with [`TypeError::InvalidEmail`]
+impl FromStr for EmailAddress {
+    type Err = TypeError;
+
+    fn from_str(s: &str) -> Result<Self, TypeError> {
+        let error = || TypeError::InvalidEmail(s.to_string());
+
+        let trimmed = s.trim();
+        let (local, domain) = trimmed.rsplit_once('@').ok_or_else(error)?;
+        let domain = domain.to_ascii_lowercase();
+
+        if !is_valid_local_part(local) || !is_valid_domain(&domain) || trimmed.len() > 254 {
+            return Err(error());
+        }
+
+        Ok(Self {
+            address: format!("{local}@{domain}"),
+            at: local.len(),
+        })
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address)
+    }
+}
+
+impl Serialize for EmailAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("EmailAddress", &self.address)
+    }
+}
+
+impl<'de> Deserialize<'de> for EmailAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "EmailAddress")]
+        struct Repr(String);
+
+        let Repr(address) = Repr::deserialize(deserializer)?;
+
+        address.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_addresses() {
+        for address in [
+            "ada@example.com",
+            "first.last@sub.example.co.uk",
+            "o'brien+tag@example.ie",
+            "x@a-b.io",
+            "{weird}=!#@example.org",
+        ] {
+            let email: EmailAddress = address.parse().unwrap();
+            assert_eq!(email.as_str(), address);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        let long_local = format!("{}@example.com", "a".repeat(65));
+
+        for address in [
+            "",
+            "ada",
+            "@example.com",
+            "ada@",
+            "ada@example",
+            "ada@example.c",
+            "ada@example.123",
+            ".ada@example.com",
+            "ada.@example.com",
+            "a..da@example.com",
+            "a da@example.com",
+            "\"ada\"@example.com",
+            "ada@-example.com",
+            "ada@example-.com",
+            "ada@exa_mple.com",
+            "ada@[192.168.0.1]",
+            "ada@@example.com",
+            long_local.as_str(),
+        ] {
+            assert_eq!(
+                address.parse::<EmailAddress>(),
+                Err(TypeError::InvalidEmail(address.to_string())),
+                "{address}"
+            );
+        }
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        let email: EmailAddress = "ada@EXAMPLE.com".parse().unwrap();
+        assert_eq!(email.local_part(), "ada");
+
+        let json = serde_json::to_string(&email).unwrap();
+        assert_eq!(json, r#""ada@example.com""#);
+        assert_eq!(serde_json::from_str::<EmailAddress>(&json).unwrap(), email);
+        assert!(serde_json::from_str::<EmailAddress>(r#""ada""#).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Locale-aware formatting of numbers and amounts of money, see [`NumberFormat`]
+
+use crate::{Decimal, Money, Rounding};
+
+/// Where the currency symbol goes, relative to the amount
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolPosition {
+    Before,
+    After,
+}
+
+/// The conventions for writing numbers and amounts of money in a locale.
+///
+/// [`NumberFormat::for_locale`] knows the conventions of a set of common locales, apps can
+/// construct others directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal_separator: &'static str,
+    pub group_separator: &'static str,
+    /// The smallest number of digits in front of the first group separator, e.g. 2 in Spanish,
+    /// which writes `1234` but `12.345`
+    pub min_grouping: usize,
+    pub symbol_position: SymbolPosition,
+    /// The space between the currency symbol and the amount, if any
+    pub symbol_spacing: &'static str,
+}
+
+const NBSP: &str = "\u{a0}";
+const NARROW_NBSP: &str = "\u{202f}";
+
+impl NumberFormat {
+    pub const ENGLISH: NumberFormat = NumberFormat {
+        decimal_separator: ".",
+        group_separator: ",",
+        min_grouping: 1,
+        symbol_position: SymbolPosition::Before,
+        symbol_spacing: "",
+    };
+
+    /// The conventions of the `locale`, e.g. `de-CH`, or its language if the region isn't
+    /// known, or English if the language isn't known either.
+    pub fn for_locale(locale: &str) -> NumberFormat {
+        let locale = locale.replace('_', "-");
+        let language = locale.split('-').next().unwrap_or_default();
+
+        Self::known(&locale)
+            .or_else(|| Self::known(language))
+            .unwrap_or(Self::ENGLISH)
+    }
+
+    fn known(locale: &str) -> Option<NumberFormat> {
+        let (decimal_separator, group_separator, min_grouping, symbol_position, symbol_spacing) =
+            match locale {
+                "en" | "ja" | "zh" | "ko" => return Some(Self::ENGLISH),
+                "de" | "it" | "da" => (",", ".", 1, SymbolPosition::After, NBSP),
+                "de-CH" => (".", "’", 1, SymbolPosition::Before, NBSP),
+                "fr" => (",", NARROW_NBSP, 1, SymbolPosition::After, NBSP),
+                "es" => (",", ".", 2, SymbolPosition::After, NBSP),
+                "nl" | "pt" => (",", ".", 1, SymbolPosition::Before, NBSP),
+                "pt-PT" => (",", NBSP, 2, SymbolPosition::After, NBSP),
+                "sv" | "nb" => (",", NBSP, 1, SymbolPosition::After, NBSP),
+                "pl" => (",", NBSP, 2, SymbolPosition::After, NBSP),
+                _ => return None,
+            };
+
+        Some(NumberFormat {
+            decimal_separator,
+            group_separator,
+            min_grouping,
+            symbol_position,
+            symbol_spacing,
+        })
+    }
+
+    /// Format the number with all its decimals, e.g. `-1,234.50`
+    pub fn format_decimal(&self, value: &Decimal) -> String {
+        let sign = if value.is_negative() { "-" } else { "" };
+
+        format!("{sign}{}", self.digits(value))
+    }
+
+    /// Format the amount rounded to the minor units of its currency, with the currency's
+    /// symbol, e.g. `-$1,234.50` or `-1.234,50 €`
+    pub fn format_money(&self, money: &Money) -> String {
+        let amount = money
+            .amount
+            .round(money.currency.minor_units(), Rounding::HalfEven);
+        let digits = self.digits(&amount);
+        let symbol = money.currency.symbol();
+        let sign = if amount.is_negative() { "-" } else { "" };
+        let spacing = self.symbol_spacing;
+
+        match self.symbol_position {
+            SymbolPosition::Before if spacing.is_empty() => format!("{sign}{symbol}{digits}"),
+            SymbolPosition::Before => format!("{symbol}{spacing}{sign}{digits}"),
+            SymbolPosition::After => format!("{sign}{digits}{spacing}{symbol}"),
+        }
+    }
+
+    /// The absolute value with separators
+    fn digits(&self, value: &Decimal) -> String {
+        let (integer, fraction) = value.digits();
+
+        let mut grouped = String::new();
+        if integer.len() >= 3 + self.min_grouping {
+            for (index, digit) in integer.chars().enumerate() {
+                if index > 0 && (integer.len() - index) % 3 == 0 {
+                    grouped.push_str(self.group_separator);
+                }
+                grouped.push(digit);
+            }
+        } else {
+            grouped = integer;
+        }
+
+        if !fraction.is_empty() {
+            grouped.push_str(self.decimal_separator);
+            grouped.push_str(&fraction);
+        }
+
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Currency;
+
+    fn d(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn formats_decimals() {
+        let value = d("-1234567.891");
+
+        assert_eq!(value.format("en-US"), "-1,234,567.891");
+        assert_eq!(value.format("de_DE"), "-1.234.567,891");
+        assert_eq!(value.format("fr"), "-1\u{202f}234\u{202f}567,891");
+        assert_eq!(d("123").format("de"), "123");
+        assert_eq!(d("1234").format("es"), "1234");
+        assert_eq!(d("12345").format("es"), "12.345");
+        assert_eq!(d("1234.5").format("xx"), "1,234.5");
+    }
+
+    #[test]
+    fn formats_money() {
+        let money = Money::new(d("-1234.5"), Currency::EUR);
+
+        assert_eq!(money.format("en"), "-€1,234.50");
+        assert_eq!(money.format("de-AT"), "-1.234,50\u{a0}€");
+        assert_eq!(money.format("nl"), "€\u{a0}-1.234,50");
+        assert_eq!(
+            Money::new(d("1234.5"), Currency::CHF).format("de-CH"),
+            "CHF\u{a0}1’234.50"
+        );
+        assert_eq!(
+            Money::new(d("1500.4"), Currency::JPY).format("ja"),
+            "¥1,500"
+        );
+    }
+}
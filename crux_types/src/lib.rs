@@ -0,0 +1,75 @@
+//! Value types for Crux view models
+//!
+//! Financial cores shouldn't push floating point numbers into their view models: `0.1 + 0.2`
+//! isn't `0.3`, and every shell would format them differently. This crate provides
+//!
+//! * [`Decimal`], an exact decimal number, with [rounding](Rounding) only where it's asked for
+//! * [`Money`], an amount in a [`Currency`], which keeps to the currency's minor units and
+//!   refuses to mix currencies
+//! * [`NumberFormat`], which formats both following the conventions of a locale, so the core can
+//!   put ready-to-show text in the view model
+//! * [`RichText`], formatted text such as chat messages or articles, which the core can parse
+//!   from Markdown
+//! * [`PhoneNumber`], [`EmailAddress`] and [`PostalAddress`], contact details validated and
+//!   normalized in the core, so every shell shows and submits them the same way
+//! * [`Measurement`], a length, [mass](Mass) or [temperature](Temperature) in a unit, which
+//!   converts exactly where it can and formats in the [measurement system](MeasurementSystem) of
+//!   a locale
+//!
+//! # Type generation
+//!
+//! `Decimal` and `Currency` are serialized as strings, wrapped in newtypes of the same name, so
+//! the generated types are `Decimal` and `Currency` holding a string, and `Money` is a struct of
+//! the two. `PhoneNumber` and `EmailAddress` are strings in the same way, and `PostalAddress` is
+//! a struct of its parts. Because they validate their strings when deserialized, register a sample of each
+//! before the app, so the type generation has valid values to trace:
+//!
+//! ```rust,ignore
+//! gen.register_samples(vec![Money::from_minor(100, Currency::EUR)])?;
+//! gen.register_samples(vec![Decimal::ONE])?;
+//! gen.register_samples(vec![Currency::EUR])?;
+//! gen.register_samples(vec![PhoneNumber::from_str("+442079460958")?])?;
+//! gen.register_samples(vec![EmailAddress::from_str("ada@example.com")?])?;
+//! gen.register_samples(vec![Measurement::new(Decimal::ONE, Length::Metre)])?;
+//! gen.register_app::<App>()?;
+//! ```
+
+mod address;
+mod decimal;
+mod email;
+mod format;
+mod measurement;
+mod money;
+mod phone;
+pub mod rich_text;
+
+pub use address::PostalAddress;
+pub use decimal::{Decimal, Rounding};
+pub use email::EmailAddress;
+pub use format::{NumberFormat, SymbolPosition};
+pub use measurement::{Length, Mass, Measurement, MeasurementSystem, Temperature, Unit};
+pub use money::{Currency, Money};
+pub use phone::PhoneNumber;
+pub use rich_text::RichText;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum TypeError {
+    #[error("invalid decimal: {0}")]
+    InvalidDecimal(String),
+    #[error("invalid currency code: {0}")]
+    InvalidCurrency(String),
+    #[error("expected an amount in {expected}, found {found}")]
+    CurrencyMismatch { expected: Currency, found: Currency },
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("invalid phone number: {0}")]
+    InvalidPhoneNumber(String),
+    #[error("invalid email address: {0}")]
+    InvalidEmail(String),
+    #[error("invalid postal address: {0}")]
+    InvalidAddress(String),
+}
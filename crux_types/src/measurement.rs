@@ -0,0 +1,567 @@
+//! Physical quantities in units of length, mass and temperature, see [`Measurement`]
+
+use std::{cmp::Ordering, fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, DeserializeOwned, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{Decimal, NumberFormat, Rounding};
+
+/// The decimals kept when a conversion can't be exact, e.g. from metres to feet
+const CONVERSION_SCALE: u32 = 12;
+
+const NBSP: &str = "\u{a0}";
+
+/// Whether a locale measures in metric or imperial units
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MeasurementSystem {
+    Metric,
+    /// The US customary units: inches, feet and miles, ounces and pounds, and degrees
+    /// Fahrenheit
+    Imperial,
+}
+
+impl MeasurementSystem {
+    /// The system used in the region of the `locale`, e.g. imperial for `en-US` and metric for
+    /// `en-GB`. Locales without a region, such as `en`, are metric.
+    pub fn for_locale(locale: &str) -> MeasurementSystem {
+        let region = locale
+            .split(['-', '_'])
+            .skip(1)
+            .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()));
+
+        match region.map(str::to_ascii_uppercase).as_deref() {
+            Some("US" | "LR" | "MM") => MeasurementSystem::Imperial,
+            _ => MeasurementSystem::Metric,
+        }
+    }
+}
+
+/// A unit of a kind of quantity, e.g. [`Length`]
+///
+/// Each kind of quantity has a base unit, e.g. metres, which values are converted through.
+pub trait Unit: Copy + PartialEq + fmt::Debug + Serialize + DeserializeOwned {
+    /// The name measurements in these units are serialized with, e.g. `"LengthMeasurement"`
+    const MEASUREMENT_NAME: &'static str;
+
+    /// The unit's symbol, e.g. `km`
+    fn symbol(&self) -> &'static str;
+
+    /// The value in this unit converted to the base unit
+    fn to_base(&self, value: Decimal) -> Decimal;
+
+    /// The value in the base unit converted to this unit
+    fn in_unit(&self, value: Decimal) -> Decimal;
+
+    /// The unit a value (in the base unit) reads best in, in the measurement system
+    fn preferred(system: MeasurementSystem, value: Decimal) -> Self;
+}
+
+/// Convert with a factor from the unit to the base unit
+fn scale_to_base(value: Decimal, factor: Decimal) -> Decimal {
+    value * factor
+}
+
+fn scale_from_base(value: Decimal, factor: Decimal) -> Decimal {
+    value
+        .checked_div(factor, CONVERSION_SCALE, Rounding::HalfEven)
+        .expect("decimal overflow")
+        .normalize()
+}
+
+/// Units of length, with the metre as the base unit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Length {
+    Millimetre,
+    Centimetre,
+    Metre,
+    Kilometre,
+    Inch,
+    Foot,
+    Yard,
+    Mile,
+}
+
+impl Length {
+    /// The length of the unit in metres, which is exact for the international yard and its
+    /// multiples
+    fn metres(self) -> Decimal {
+        match self {
+            Length::Millimetre => Decimal::new(1, 3),
+            Length::Centimetre => Decimal::new(1, 2),
+            Length::Metre => Decimal::ONE,
+            Length::Kilometre => Decimal::from(1000),
+            Length::Inch => Decimal::new(254, 4),
+            Length::Foot => Decimal::new(3048, 4),
+            Length::Yard => Decimal::new(9144, 4),
+            Length::Mile => Decimal::new(1_609_344, 3),
+        }
+    }
+}
+
+impl Unit for Length {
+    const MEASUREMENT_NAME: &'static str = "LengthMeasurement";
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Length::Millimetre => "mm",
+            Length::Centimetre => "cm",
+            Length::Metre => "m",
+            Length::Kilometre => "km",
+            Length::Inch => "in",
+            Length::Foot => "ft",
+            Length::Yard => "yd",
+            Length::Mile => "mi",
+        }
+    }
+
+    fn to_base(&self, value: Decimal) -> Decimal {
+        scale_to_base(value, self.metres())
+    }
+
+    fn in_unit(&self, value: Decimal) -> Decimal {
+        scale_from_base(value, self.metres())
+    }
+
+    /// Millimetres, centimetres, metres or kilometres, or inches, feet or miles: the largest
+    /// unit the value is at least one of
+    fn preferred(system: MeasurementSystem, value: Decimal) -> Self {
+        let units: &[Length] = match system {
+            MeasurementSystem::Metric => &[Length::Kilometre, Length::Metre, Length::Centimetre],
+            MeasurementSystem::Imperial => &[Length::Mile, Length::Foot],
+        };
+        let smallest = match system {
+            MeasurementSystem::Metric => Length::Millimetre,
+            MeasurementSystem::Imperial => Length::Inch,
+        };
+
+        let value = value.abs();
+        units
+            .iter()
+            .copied()
+            .find(|unit| value >= unit.metres())
+            .unwrap_or(smallest)
+    }
+}
+
+/// Units of mass, with the kilogram as the base unit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Mass {
+    Gram,
+    Kilogram,
+    Ounce,
+    Pound,
+    Stone,
+}
+
+impl Mass {
+    /// The mass of the unit in kilograms, which is exact for the international pound and its
+    /// multiples
+    fn kilograms(self) -> Decimal {
+        match self {
+            Mass::Gram => Decimal::new(1, 3),
+            Mass::Kilogram => Decimal::ONE,
+            Mass::Ounce => Decimal::new(28_349_523_125, 12),
+            Mass::Pound => Decimal::new(45_359_237, 8),
+            Mass::Stone => Decimal::new(635_029_318, 8),
+        }
+    }
+}
+
+impl Unit for Mass {
+    const MEASUREMENT_NAME: &'static str = "MassMeasurement";
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Mass::Gram => "g",
+            Mass::Kilogram => "kg",
+            Mass::Ounce => "oz",
+            Mass::Pound => "lb",
+            Mass::Stone => "st",
+        }
+    }
+
+    fn to_base(&self, value: Decimal) -> Decimal {
+        scale_to_base(value, self.kilograms())
+    }
+
+    fn in_unit(&self, value: Decimal) -> Decimal {
+        scale_from_base(value, self.kilograms())
+    }
+
+    /// Grams or kilograms, or ounces or pounds
+    fn preferred(system: MeasurementSystem, value: Decimal) -> Self {
+        let (large, small) = match system {
+            MeasurementSystem::Metric => (Mass::Kilogram, Mass::Gram),
+            MeasurementSystem::Imperial => (Mass::Pound, Mass::Ounce),
+        };
+
+        if value.abs() >= large.kilograms() {
+            large
+        } else {
+            small
+        }
+    }
+}
+
+/// Units of temperature, with the kelvin as the base unit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Temperature {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl Unit for Temperature {
+    const MEASUREMENT_NAME: &'static str = "TemperatureMeasurement";
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Temperature::Celsius => "°C",
+            Temperature::Fahrenheit => "°F",
+            Temperature::Kelvin => "K",
+        }
+    }
+
+    fn to_base(&self, value: Decimal) -> Decimal {
+        match self {
+            Temperature::Celsius => value + Decimal::new(27315, 2),
+            // K = (°F + 459.67) × 5/9
+            Temperature::Fahrenheit => ((value + Decimal::new(45967, 2)) * Decimal::from(5))
+                .checked_div(Decimal::from(9), CONVERSION_SCALE, Rounding::HalfEven)
+                .expect("decimal overflow")
+                .normalize(),
+            Temperature::Kelvin => value,
+        }
+    }
+
+    fn in_unit(&self, value: Decimal) -> Decimal {
+        match self {
+            Temperature::Celsius => value - Decimal::new(27315, 2),
+            Temperature::Fahrenheit => value * Decimal::new(18, 1) - Decimal::new(45967, 2),
+            Temperature::Kelvin => value,
+        }
+    }
+
+    /// Degrees Celsius, or degrees Fahrenheit
+    fn preferred(system: MeasurementSystem, _value: Decimal) -> Self {
+        match system {
+            MeasurementSystem::Metric => Temperature::Celsius,
+            MeasurementSystem::Imperial => Temperature::Fahrenheit,
+        }
+    }
+}
+
+/// A quantity in a unit, e.g. 5.2 kilometres, kept as an exact [`Decimal`].
+///
+/// Cores keep measurements in whichever unit they were taken in, and convert them for display in
+/// `view()`, in the units of the user's locale, instead of leaving each shell to convert them
+/// (and round them) differently. Conversions between metric and imperial units of length and
+/// mass are exact one way, and kept to 12 decimals the other way.
+///
+/// Measurements compare equal, and can be added and subtracted, across units of the same kind.
+///
+/// ```
+/// use crux_types::{Length, Measurement, Temperature};
+///
+/// let run = Measurement::new("5.2".parse().unwrap(), Length::Kilometre);
+/// assert_eq!(run.format("en-GB", 1), "5.2\u{a0}km");
+/// assert_eq!(run.format("en-US", 1), "3.2\u{a0}mi");
+/// assert_eq!(run.format("de-DE", 1), "5,2\u{a0}km");
+///
+/// let step = Measurement::new(30.into(), Length::Inch);
+/// assert_eq!(step.to(Length::Centimetre).value.to_string(), "76.2");
+///
+/// let fever = Measurement::new(38.into(), Temperature::Celsius);
+/// assert_eq!(fever.format("en-US", 1), "100.4\u{a0}°F");
+/// ```
+///
+/// Measurements are serialized as a struct of the value and the unit, named after the kind of
+/// unit (e.g. `LengthMeasurement`) so each kind gets its own generated type. Register a sample of
+/// each kind the app uses with the type generation, like for [`Decimal`].
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement<U> {
+    pub value: Decimal,
+    pub unit: U,
+}
+
+impl<U: Unit> Measurement<U> {
+    pub fn new(value: Decimal, unit: U) -> Self {
+        Self { value, unit }
+    }
+
+    /// The measurement converted to the `unit`
+    #[must_use]
+    pub fn to(&self, unit: U) -> Self {
+        if unit == self.unit {
+            return *self;
+        }
+
+        Self {
+            value: unit.in_unit(self.base_value()),
+            unit,
+        }
+    }
+
+    /// The measurement converted to the unit it reads best in, in the measurement `system`,
+    /// e.g. 800 m rather than 0.8 km
+    #[must_use]
+    pub fn to_system(&self, system: MeasurementSystem) -> Self {
+        self.to(U::preferred(system, self.base_value()))
+    }
+
+    /// Format the measurement for the `locale`, in its measurement system, rounded to at most
+    /// `decimals` decimals, e.g. `3.2 mi` for 5.2 km in `en-US`.
+    pub fn format(&self, locale: &str, decimals: u32) -> String {
+        self.to_system(MeasurementSystem::for_locale(locale))
+            .format_in_unit(locale, decimals)
+    }
+
+    /// Format the measurement in its own unit with the number conventions of the `locale`,
+    /// rounded to at most `decimals` decimals, e.g. `5,2 km` in German.
+    pub fn format_in_unit(&self, locale: &str, decimals: u32) -> String {
+        let value = self.value.round(decimals, Rounding::HalfEven).normalize();
+        let number = NumberFormat::for_locale(locale).format_decimal(&value);
+
+        format!("{number}{NBSP}{}", self.unit.symbol())
+    }
+
+    fn base_value(&self) -> Decimal {
+        self.unit.to_base(self.value)
+    }
+}
+
+impl<U: Unit> PartialEq for Measurement<U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base_value() == other.base_value()
+    }
+}
+
+impl<U: Unit> PartialOrd for Measurement<U> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.base_value().cmp(&other.base_value()))
+    }
+}
+
+/// Adds in the unit of the left hand side
+impl<U: Unit> std::ops::Add for Measurement<U> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.value + rhs.to(self.unit).value, self.unit)
+    }
+}
+
+/// Subtracts in the unit of the left hand side
+impl<U: Unit> std::ops::Sub for Measurement<U> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.value - rhs.to(self.unit).value, self.unit)
+    }
+}
+
+/// Formats the value and the unit's symbol, e.g. `5.2 km`
+impl<U: Unit> fmt::Display for Measurement<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.unit.symbol())
+    }
+}
+
+const FIELDS: &[&str] = &["value", "unit"];
+
+impl<U: Unit> Serialize for Measurement<U> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct(U::MEASUREMENT_NAME, 2)?;
+        state.serialize_field("value", &self.value)?;
+        state.serialize_field("unit", &self.unit)?;
+        state.end()
+    }
+}
+
+impl<'de, U: Unit> Deserialize<'de> for Measurement<U> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MeasurementVisitor<U>(PhantomData<U>);
+
+        impl<'de, U: Unit> Visitor<'de> for MeasurementVisitor<U> {
+            type Value = Measurement<U>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(U::MEASUREMENT_NAME)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let value = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let unit = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+                Ok(Measurement { value, unit })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let (mut value, mut unit) = (None, None);
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "value" => value = Some(map.next_value()?),
+                        "unit" => unit = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(Measurement {
+                    value: value.ok_or_else(|| de::Error::missing_field("value"))?,
+                    unit: unit.ok_or_else(|| de::Error::missing_field("unit"))?,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct(
+            U::MEASUREMENT_NAME,
+            FIELDS,
+            MeasurementVisitor(PhantomData),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn measurement_systems_follow_the_region() {
+        for locale in ["en-US", "en_US", "es-US", "en-LR", "my-MM", "zh-Hant-us"] {
+            assert_eq!(
+                MeasurementSystem::for_locale(locale),
+                MeasurementSystem::Imperial,
+                "{locale}"
+            );
+        }
+        for locale in ["en", "en-GB", "fr-CA", "es-419", "de"] {
+            assert_eq!(
+                MeasurementSystem::for_locale(locale),
+                MeasurementSystem::Metric,
+                "{locale}"
+            );
+        }
+    }
+
+    #[test]
+    fn converts_exactly_where_it_can() {
+        let mile = Measurement::new(Decimal::ONE, Length::Mile);
+        assert_eq!(mile.to(Length::Kilometre).value, d("1.609344"));
+        assert_eq!(mile.to(Length::Foot).value, d("5280"));
+
+        let kilometre = Measurement::new(Decimal::ONE, Length::Kilometre);
+        assert_eq!(kilometre.to(Length::Mile).value, d("0.621371192237"));
+
+        let stone = Measurement::new(Decimal::ONE, Mass::Stone);
+        assert_eq!(stone.to(Mass::Pound).value, d("14"));
+        assert_eq!(stone.to(Mass::Ounce).value, d("224"));
+        assert_eq!(stone.to(Mass::Kilogram).value, d("6.35029318"));
+
+        let boiling = Measurement::new(Decimal::from(100), Temperature::Celsius);
+        assert_eq!(boiling.to(Temperature::Fahrenheit).value, d("212"));
+        assert_eq!(boiling.to(Temperature::Kelvin).value, d("373.15"));
+
+        let body = Measurement::new(d("98.6"), Temperature::Fahrenheit);
+        assert_eq!(body.to(Temperature::Celsius).value, d("37"));
+    }
+
+    #[test]
+    fn compares_and_adds_across_units() {
+        let metres = Measurement::new(Decimal::from(1609), Length::Metre);
+        let mile = Measurement::new(Decimal::ONE, Length::Mile);
+        assert!(metres < mile);
+        assert_eq!(
+            Measurement::new(Decimal::from(12), Length::Inch),
+            Measurement::new(Decimal::ONE, Length::Foot)
+        );
+
+        let total = Measurement::new(Decimal::ONE, Mass::Kilogram)
+            + Measurement::new(Decimal::from(250), Mass::Gram);
+        assert_eq!(total.unit, Mass::Kilogram);
+        assert_eq!(total.value, d("1.25"));
+        assert_eq!(total.to_string(), "1.25 kg");
+
+        let difference = Measurement::new(Decimal::from(20), Temperature::Celsius)
+            - Measurement::new(Decimal::from(293), Temperature::Kelvin);
+        assert_eq!(difference.value, d("0.15"));
+    }
+
+    fn preferred<U: Unit>(value: &str, unit: U, system: MeasurementSystem) -> U {
+        Measurement::new(d(value), unit).to_system(system).unit
+    }
+
+    #[test]
+    fn picks_units_which_read_well() {
+        use MeasurementSystem::{Imperial, Metric};
+
+        assert_eq!(preferred("800", Length::Metre, Metric), Length::Metre);
+        assert_eq!(preferred("1.2", Length::Mile, Metric), Length::Kilometre);
+        assert_eq!(preferred("0.5", Length::Metre, Metric), Length::Centimetre);
+        assert_eq!(
+            preferred("3", Length::Millimetre, Metric),
+            Length::Millimetre
+        );
+        assert_eq!(
+            preferred("-5", Length::Kilometre, Metric),
+            Length::Kilometre
+        );
+        assert_eq!(preferred("800", Length::Metre, Imperial), Length::Foot);
+        assert_eq!(preferred("2", Length::Kilometre, Imperial), Length::Mile);
+        assert_eq!(preferred("10", Length::Centimetre, Imperial), Length::Inch);
+
+        assert_eq!(preferred("0.3", Mass::Kilogram, Metric), Mass::Gram);
+        assert_eq!(preferred("70", Mass::Kilogram, Imperial), Mass::Pound);
+        assert_eq!(preferred("0.3", Mass::Kilogram, Imperial), Mass::Ounce);
+
+        assert_eq!(
+            preferred("300", Temperature::Kelvin, Metric),
+            Temperature::Celsius
+        );
+    }
+
+    #[test]
+    fn formats_for_the_locale() {
+        let weight = Measurement::new(d("72.5"), Mass::Kilogram);
+        assert_eq!(weight.format("en-GB", 1), "72.5\u{a0}kg");
+        assert_eq!(weight.format("fr-FR", 1), "72,5\u{a0}kg");
+        assert_eq!(weight.format("en-US", 1), "159.8\u{a0}lb");
+        assert_eq!(weight.format("en-US", 0), "160\u{a0}lb");
+        assert_eq!(weight.format_in_unit("en-US", 2), "72.5\u{a0}kg");
+
+        let marathon = Measurement::new(d("42195"), Length::Metre);
+        assert_eq!(marathon.format("en", 2), "42.2\u{a0}km");
+        assert_eq!(marathon.format_in_unit("en", 0), "42,195\u{a0}m");
+
+        let cold = Measurement::new(d("-40"), Temperature::Fahrenheit);
+        assert_eq!(cold.format("de", 0), "-40\u{a0}°C");
+    }
+
+    #[test]
+    fn serializes_as_a_struct_per_kind() {
+        let height = Measurement::new(d("1.80"), Length::Metre);
+
+        let json = serde_json::to_string(&height).unwrap();
+        assert_eq!(json, r#"{"value":"1.80","unit":"Metre"}"#);
+
+        let deserialized: Measurement<Length> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.value.to_string(), "1.80");
+        assert_eq!(deserialized.unit, Length::Metre);
+
+        assert!(serde_json::from_str::<Measurement<Mass>>(&json).is_err());
+        assert!(serde_json::from_str::<Measurement<Length>>(r#"{"value":"1.80"}"#).is_err());
+    }
+}
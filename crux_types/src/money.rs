@@ -0,0 +1,341 @@
+//! Amounts of money in a currency, see [`Money`]
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Decimal, NumberFormat, Rounding, TypeError};
+
+/// An ISO 4217 currency, e.g. `EUR`. Serialized as its code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const AUD: Currency = Currency(*b"AUD");
+    pub const BRL: Currency = Currency(*b"BRL");
+    pub const CAD: Currency = Currency(*b"CAD");
+    pub const CHF: Currency = Currency(*b"CHF");
+    pub const CNY: Currency = Currency(*b"CNY");
+    pub const EUR: Currency = Currency(*b"EUR");
+    pub const GBP: Currency = Currency(*b"GBP");
+    pub const INR: Currency = Currency(*b"INR");
+    pub const JPY: Currency = Currency(*b"JPY");
+    pub const SEK: Currency = Currency(*b"SEK");
+    pub const USD: Currency = Currency(*b"USD");
+
+    /// The currency with the three letter `code`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TypeError::InvalidCurrency`] if the code isn't three upper case letters.
+    pub fn new(code: &str) -> Result<Self, TypeError> {
+        let bytes: [u8; 3] = code
+            .as_bytes()
+            .try_into()
+            .map_err(|_| TypeError::InvalidCurrency(code.to_string()))?;
+
+        if !bytes.iter().all(u8::is_ascii_uppercase) {
+            return Err(TypeError::InvalidCurrency(code.to_string()));
+        }
+
+        Ok(Self(bytes))
+    }
+
+    pub fn code(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("currency codes are ASCII")
+    }
+
+    /// The number of decimals of the currency's minor unit, e.g. 2 for cents
+    pub fn minor_units(&self) -> u32 {
+        match self.code() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+            | "UGX" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            _ => 2,
+        }
+    }
+
+    /// The symbol amounts are written with, or the code if the currency has no common symbol
+    pub fn symbol(&self) -> &str {
+        match self.code() {
+            "USD" | "AUD" | "CAD" | "NZD" | "MXN" => "$",
+            "EUR" => "€",
+            "GBP" => "£",
+            "JPY" | "CNY" => "¥",
+            "INR" => "₹",
+            "KRW" => "₩",
+            "BRL" => "R$",
+            "SEK" | "NOK" | "DKK" => "kr",
+            "PLN" => "zł",
+            code => code,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("Currency", self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Currency")]
+        struct Repr(String);
+
+        let Repr(code) = Repr::deserialize(deserializer)?;
+
+        Currency::new(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+/// An amount of money in a currency.
+///
+/// Amounts in different currencies can't be added or subtracted, and results which can't be
+/// paid exactly are rounded to the currency's minor units, so that the numbers the shells
+/// show always add up.
+///
+/// ```
+/// use crux_types::{Currency, Money, Rounding};
+///
+/// let bill = Money::from_minor(10000, Currency::EUR);
+/// let tip = bill.times("0.125".parse().unwrap(), Rounding::HalfEven);
+/// let total = bill.checked_add(&tip).unwrap();
+/// assert_eq!(total.format("de"), "112,50\u{a0}€");
+///
+/// let shares = total.allocate(&[1, 1, 1]);
+/// assert_eq!(shares[0].to_string(), "37.50 EUR");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(Decimal::ZERO, currency)
+    }
+
+    /// The amount of `minor` units of the currency, e.g. cents
+    pub fn from_minor(minor: i64, currency: Currency) -> Self {
+        Self::new(
+            Decimal::new(i128::from(minor), currency.minor_units()),
+            currency,
+        )
+    }
+
+    /// The amount in minor units of the currency, e.g. cents, rounded with `rounding`
+    pub fn to_minor(&self, rounding: Rounding) -> i128 {
+        self.round(rounding).amount.units()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_negative()
+    }
+
+    /// The amount rounded to the minor units of the currency
+    #[must_use]
+    pub fn round(&self, rounding: Rounding) -> Self {
+        Self::new(
+            self.amount.round(self.currency.minor_units(), rounding),
+            self.currency,
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`TypeError::CurrencyMismatch`] if `other` is in a different currency, or
+    /// [`TypeError::Overflow`].
+    pub fn checked_add(&self, other: &Money) -> Result<Self, TypeError> {
+        self.same_currency(other)?;
+
+        let amount = self
+            .amount
+            .checked_add(other.amount)
+            .ok_or(TypeError::Overflow)?;
+
+        Ok(Self::new(amount, self.currency))
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`TypeError::CurrencyMismatch`] if `other` is in a different currency, or
+    /// [`TypeError::Overflow`].
+    pub fn checked_sub(&self, other: &Money) -> Result<Self, TypeError> {
+        self.checked_add(&-*other)
+    }
+
+    /// The amount multiplied by `factor`, e.g. a tax rate, rounded to the minor units of the
+    /// currency.
+    #[must_use]
+    pub fn times(&self, factor: Decimal, rounding: Rounding) -> Self {
+        Self::new(self.amount * factor, self.currency).round(rounding)
+    }
+
+    /// Split the amount, rounded half to even to the minor units of the currency, into parts
+    /// proportional to the `ratios`, without losing or making up any minor units: the
+    /// remainder is spread over the first parts with a non-zero ratio, one unit each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratios add up to zero.
+    pub fn allocate(&self, ratios: &[u32]) -> Vec<Money> {
+        let total: i128 = ratios.iter().copied().map(i128::from).sum();
+        assert!(total > 0, "the ratios must not add up to zero");
+
+        let minor_units = self.currency.minor_units();
+        let amount = self.to_minor(Rounding::HalfEven);
+
+        let mut parts: Vec<i128> = ratios
+            .iter()
+            .map(|&ratio| amount * i128::from(ratio) / total)
+            .collect();
+
+        let mut remainder = amount - parts.iter().sum::<i128>();
+        let weighted = parts.iter_mut().zip(ratios).filter(|(_, &ratio)| ratio > 0);
+        for (part, _) in weighted {
+            if remainder == 0 {
+                break;
+            }
+            *part += remainder.signum();
+            remainder -= remainder.signum();
+        }
+
+        parts
+            .into_iter()
+            .map(|part| Self::new(Decimal::new(part, minor_units), self.currency))
+            .collect()
+    }
+
+    /// Format the amount with the currency symbol, following the conventions of the `locale`,
+    /// see [`NumberFormat::format_money`].
+    pub fn format(&self, locale: &str) -> String {
+        NumberFormat::for_locale(locale).format_money(self)
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), TypeError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(TypeError::CurrencyMismatch {
+                expected: self.currency,
+                found: other.currency,
+            })
+        }
+    }
+}
+
+impl std::ops::Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Self::new(-self.amount, self.currency)
+    }
+}
+
+/// Formats as the amount and the currency code, e.g. `12.50 EUR`
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur(amount: &str) -> Money {
+        Money::new(amount.parse().unwrap(), Currency::EUR)
+    }
+
+    #[test]
+    fn validates_currency_codes() {
+        assert_eq!(Currency::new("EUR").unwrap(), Currency::EUR);
+        for code in ["eur", "EURO", "E1R", ""] {
+            assert_eq!(
+                Currency::new(code),
+                Err(TypeError::InvalidCurrency(code.to_string()))
+            );
+        }
+
+        assert_eq!(Currency::JPY.minor_units(), 0);
+        assert_eq!(Currency::new("KWD").unwrap().minor_units(), 3);
+        assert_eq!(Currency::new("XYZ").unwrap().symbol(), "XYZ");
+    }
+
+    #[test]
+    fn refuses_to_mix_currencies() {
+        let dollars = Money::from_minor(100, Currency::USD);
+
+        assert_eq!(
+            eur("1").checked_add(&dollars),
+            Err(TypeError::CurrencyMismatch {
+                expected: Currency::EUR,
+                found: Currency::USD
+            })
+        );
+        assert_eq!(eur("1.25").checked_sub(&eur("0.50")), Ok(eur("0.75")));
+    }
+
+    #[test]
+    fn rounds_to_minor_units() {
+        assert_eq!(
+            eur("10").times("0.075".parse().unwrap(), Rounding::HalfEven),
+            eur("0.75")
+        );
+        assert_eq!(
+            eur("0.125").round(Rounding::HalfEven).to_string(),
+            "0.12 EUR"
+        );
+        assert_eq!(eur("0.125").round(Rounding::HalfUp).to_string(), "0.13 EUR");
+        assert_eq!(eur("19.99").to_minor(Rounding::HalfEven), 1999);
+        assert_eq!(Money::from_minor(1999, Currency::EUR), eur("19.99"));
+    }
+
+    #[test]
+    fn allocates_without_losing_cents() {
+        let shares: Vec<_> = eur("100")
+            .allocate(&[1, 1, 1])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(shares, ["33.34 EUR", "33.33 EUR", "33.33 EUR"]);
+
+        let shares: Vec<_> = eur("-0.05")
+            .allocate(&[3, 7])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(shares, ["-0.02 EUR", "-0.03 EUR"]);
+
+        let shares = eur("0.05").allocate(&[0, 1, 1]);
+        assert_eq!(shares, [eur("0"), eur("0.03"), eur("0.02")]);
+    }
+
+    #[test]
+    fn serializes_with_string_fields() {
+        let json = serde_json::to_string(&eur("12.30")).unwrap();
+        assert_eq!(json, r#"{"amount":"12.30","currency":"EUR"}"#);
+
+        let money: Money = serde_json::from_str(&json).unwrap();
+        assert_eq!(money, eur("12.30"));
+
+        assert!(serde_json::from_str::<Money>(r#"{"amount":"1","currency":"euro"}"#).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! Phone numbers in E.164 format, see [`PhoneNumber`]
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::TypeError;
+
+/// The two digit country calling codes. All other codes, except 1 and 7, have three digits.
+const TWO_DIGIT_CODES: [u16; 44] = [
+    20, 27, 30, 31, 32, 33, 34, 36, 39, 40, 41, 43, 44, 45, 46, 47, 48, 49, 51, 52, 53, 54, 55, 56,
+    57, 58, 60, 61, 62, 63, 64, 65, 66, 81, 82, 84, 86, 90, 91, 92, 93, 94, 95, 98,
+];
+
+/// The calling code and trunk prefix (dialled before national numbers) of common regions
+const REGIONS: [(&str, u16, Option<&str>); 26] = [
+    ("AT", 43, Some("0")),
+    ("AU", 61, Some("0")),
+    ("BE", 32, Some("0")),
+    ("BR", 55, Some("0")),
+    ("CA", 1, Some("1")),
+    ("CH", 41, Some("0")),
+    ("CN", 86, Some("0")),
+    ("DE", 49, Some("0")),
+    ("DK", 45, None),
+    ("ES", 34, None),
+    ("FR", 33, Some("0")),
+    ("GB", 44, Some("0")),
+    ("IE", 353, Some("0")),
+    ("IN", 91, Some("0")),
+    ("IT", 39, None),
+    ("JP", 81, Some("0")),
+    ("KR", 82, Some("0")),
+    ("MX", 52, None),
+    ("NL", 31, Some("0")),
+    ("NO", 47, None),
+    ("NZ", 64, Some("0")),
+    ("PL", 48, None),
+    ("PT", 351, None),
+    ("SE", 46, Some("0")),
+    ("US", 1, Some("1")),
+    ("ZA", 27, Some("0")),
+];
+
+/// A phone number in the international E.164 format, e.g. `+442079460958`.
+///
+/// Numbers are parsed from what users type, with spaces, dashes, dots and brackets, in
+/// international format (`+44 20 7946 0958` or `0044 20 7946 0958`), or in national format
+/// for a default region (`020 7946 0958` in `GB`). Serialized as the E.164 string.
+///
+/// Only the structure is checked: the length, the country calling code and, in the North
+/// American Numbering Plan, the area code. Whether the number is in service is for the shell
+/// or a server to find out.
+///
+/// ```
+/// use crux_types::PhoneNumber;
+///
+/// let number = PhoneNumber::parse("(415) 555-0123", Some("US")).unwrap();
+/// assert_eq!(number.to_string(), "+14155550123");
+/// assert_eq!(number.format_international(), "+1 415 555 0123");
+///
+/// let number: PhoneNumber = "+44 20 7946 0958".parse().unwrap();
+/// assert_eq!(number.country_code(), 44);
+/// assert_eq!(number.national_number(), "2079460958");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PhoneNumber {
+    country_code: u16,
+    national_number: String,
+}
+
+impl PhoneNumber {
+    /// Parse a number in international format, or in national format if a `default_region`
+    /// (an ISO 3166 country code, e.g. `"GB"`) is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TypeError::InvalidPhoneNumber`] if the input isn't a phone number, or is in
+    /// national format for an unknown region.
+    pub fn parse(input: &str, default_region: Option<&str>) -> Result<Self, TypeError> {
+        let error = || TypeError::InvalidPhoneNumber(input.to_string());
+
+        let trimmed = input.trim();
+        let (international, rest) = match trimmed.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+
+        // the trunk prefix is sometimes written in brackets, e.g. +44 (0)20 7946 0958
+        let rest = rest.replace("(0)", "");
+
+        let mut digits = String::new();
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                ' ' | '-' | '.' | '(' | ')' | '/' | '\u{a0}' => {}
+                _ => return Err(error()),
+            }
+        }
+
+        if international {
+            return Self::from_international(&digits).ok_or_else(error);
+        }
+        if let Some(digits) = digits.strip_prefix("00") {
+            return Self::from_international(digits).ok_or_else(error);
+        }
+
+        let (country_code, trunk_prefix) = default_region
+            .and_then(|region| {
+                REGIONS
+                    .iter()
+                    .find(|(code, ..)| code.eq_ignore_ascii_case(region))
+            })
+            .map(|(_, country_code, trunk_prefix)| (*country_code, *trunk_prefix))
+            .ok_or_else(error)?;
+
+        let national_number = trunk_prefix
+            .and_then(|prefix| digits.strip_prefix(prefix))
+            .filter(|national| country_code != 1 || national.len() == 10)
+            .unwrap_or(&digits);
+
+        Self::new(country_code, national_number).ok_or_else(error)
+    }
+
+    fn from_international(digits: &str) -> Option<Self> {
+        let length = match digits.get(..1)? {
+            "1" | "7" => 1,
+            _ => {
+                let two: u16 = digits.get(..2)?.parse().ok()?;
+                if TWO_DIGIT_CODES.contains(&two) {
+                    2
+                } else {
+                    3
+                }
+            }
+        };
+
+        Self::new(digits.get(..length)?.parse().ok()?, digits.get(length..)?)
+    }
+
+    fn new(country_code: u16, national_number: &str) -> Option<Self> {
+        let total = country_code.to_string().len() + national_number.len();
+        // only Italian numbers start with a zero after the country code
+        let valid = (8..=15).contains(&total)
+            && national_number.chars().all(|c| c.is_ascii_digit())
+            && (country_code == 39 || !national_number.starts_with('0'));
+        if !valid {
+            return None;
+        }
+
+        // in the North American Numbering Plan, area codes and exchanges don't start with 0 or 1
+        if country_code == 1 {
+            let bytes = national_number.as_bytes();
+            if bytes.len() != 10 || bytes[0] < b'2' || bytes[3] < b'2' {
+                return None;
+            }
+        }
+
+        Some(Self {
+            country_code,
+            national_number: national_number.to_string(),
+        })
+    }
+
+    /// The country calling code, e.g. 44 for the United Kingdom
+    pub fn country_code(&self) -> u16 {
+        self.country_code
+    }
+
+    /// The number without the country calling code or trunk prefix
+    pub fn national_number(&self) -> &str {
+        &self.national_number
+    }
+
+    /// The number in E.164 format, e.g. `+442079460958`
+    pub fn e164(&self) -> String {
+        self.to_string()
+    }
+
+    /// The number in international format, with the national number split into groups for
+    /// readability: `+1 415 555 0123` in North America, and groups of three digits elsewhere,
+    /// ending with a group of four rather than a single digit, e.g. `+44 207 946 0958`. The
+    /// groups don't follow each country's conventions, so this is meant for display only.
+    pub fn format_international(&self) -> String {
+        let national = &self.national_number;
+        let groups: Vec<&str> = if self.country_code == 1 {
+            vec![&national[..3], &national[3..6], &national[6..]]
+        } else {
+            let mut groups = Vec::new();
+            let mut rest = national.as_str();
+            while rest.len() > 3 && rest.len() != 4 {
+                let (group, remaining) = rest.split_at(3);
+                groups.push(group);
+                rest = remaining;
+            }
+            groups.push(rest);
+            groups
+        };
+
+        format!("+{} {}", self.country_code, groups.join(" "))
+    }
+}
+
+/// Parses numbers in international format, see [`PhoneNumber::parse`]
+impl FromStr for PhoneNumber {
+    type Err = TypeError;
+
+    fn from_str(s: &str) -> Result<Self, TypeError> {
+        Self::parse(s, None)
+    }
+}
+
+/// Formats as E.164, e.g. `+442079460958`
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{}{}", self.country_code, self.national_number)
+    }
+}
+
+impl Serialize for PhoneNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("PhoneNumber", &self.e164())
+    }
+}
+
+impl<'de> Deserialize<'de> for PhoneNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "PhoneNumber")]
+        struct Repr(String);
+
+        let Repr(number) = Repr::deserialize(deserializer)?;
+
+        number.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn e164(input: &str, region: Option<&str>) -> Result<String, TypeError> {
+        PhoneNumber::parse(input, region).map(|number| number.e164())
+    }
+
+    #[test]
+    fn parses_international_numbers() {
+        for input in [
+            "+44 20 7946 0958",
+            "+44 (0)20 7946 0958",
+            "0044 20-7946-0958",
+        ] {
+            assert_eq!(
+                e164(input, None),
+                Ok("+442079460958".to_string()),
+                "{input}"
+            );
+        }
+
+        let number = PhoneNumber::parse("+353 1 234 5678", None).unwrap();
+        assert_eq!(number.country_code(), 353);
+        assert_eq!(number.national_number(), "12345678");
+
+        let number = PhoneNumber::parse("+7 912 345 67 89", None).unwrap();
+        assert_eq!(number.country_code(), 7);
+    }
+
+    #[test]
+    fn parses_national_numbers_in_a_region() {
+        assert_eq!(
+            e164("020 7946 0958", Some("GB")),
+            Ok("+442079460958".to_string())
+        );
+        assert_eq!(
+            e164("030 123456", Some("de")),
+            Ok("+4930123456".to_string())
+        );
+        assert_eq!(
+            e164("1-415-555-0123", Some("US")),
+            Ok("+14155550123".to_string())
+        );
+        assert_eq!(
+            e164("415.555.0123", Some("CA")),
+            Ok("+14155550123".to_string())
+        );
+        // Italian numbers keep their leading zero
+        assert_eq!(
+            e164("06 1234 5678", Some("IT")),
+            Ok("+390612345678".to_string())
+        );
+
+        assert_eq!(
+            e164("020 7946 0958", None),
+            Err(TypeError::InvalidPhoneNumber("020 7946 0958".to_string()))
+        );
+        assert!(e164("020 7946 0958", Some("XX")).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for input in [
+            "",
+            "+",
+            "+44 20 7946 095a",
+            "+1 555 0123",
+            "+1 015 555 0123",
+            "+1 415 155 0123",
+            "+44 1234",
+            "+44 1234 5678 9012 3456",
+            "tel:+442079460958",
+        ] {
+            assert!(PhoneNumber::parse(input, None).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn formats_numbers() {
+        let number: PhoneNumber = "+33 1 23 45 67 89".parse().unwrap();
+        assert_eq!(number.to_string(), "+33123456789");
+        assert_eq!(number.format_international(), "+33 123 456 789");
+
+        let json = serde_json::to_string(&number).unwrap();
+        assert_eq!(json, r#""+33123456789""#);
+        assert_eq!(serde_json::from_str::<PhoneNumber>(&json).unwrap(), number);
+        assert!(serde_json::from_str::<PhoneNumber>(r#""123""#).is_err());
+    }
+}
@@ -0,0 +1,848 @@
+//! A Markdown parser producing [`Block`]s, see [`RichText::from_markdown`](super::RichText::from_markdown)
+
+use super::{Block, Inline, Style};
+
+pub(super) fn parse(markdown: &str) -> Vec<Block> {
+    let lines: Vec<&str> = markdown.lines().collect();
+
+    parse_blocks(&lines)
+}
+
+/// The number of leading spaces, with tabs counted as 4
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Remove up to `width` columns of indentation
+fn dedent(line: &str, width: usize) -> &str {
+    let mut removed = 0;
+    for (index, c) in line.char_indices() {
+        if removed >= width || !(c == ' ' || c == '\t') {
+            return &line[index..];
+        }
+        removed += if c == '\t' { 4 } else { 1 };
+    }
+
+    ""
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// The fence character and length of a code fence opening on the line
+fn fence(line: &str) -> Option<(char, usize)> {
+    if indentation(line) > 3 {
+        return None;
+    }
+
+    let trimmed = line.trim_start();
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|x| *x == c).count();
+
+    (length >= 3).then_some((c, length))
+}
+
+fn is_rule(line: &str) -> bool {
+    if indentation(line) > 3 {
+        return false;
+    }
+
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['-', '*', '_'].contains(&marks[0]) && marks.iter().all(|c| *c == marks[0])
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    if indentation(line) > 3 {
+        return None;
+    }
+
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    // an optional closing sequence of #s
+    let text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    let text = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        text
+    };
+
+    Some((u8::try_from(level).unwrap_or(6), text))
+}
+
+fn quote(line: &str) -> Option<&str> {
+    if indentation(line) > 3 {
+        return None;
+    }
+
+    let rest = line.trim_start().strip_prefix('>')?;
+    Some(rest.strip_prefix(' ').unwrap_or(rest))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Bullet(char),
+    Ordered { start: u32, delimiter: char },
+}
+
+/// A list item opening on the line: its marker, the indentation of its content and the content
+fn list_item(line: &str) -> Option<(Marker, usize, &str)> {
+    let indent = indentation(line);
+    if indent > 3 {
+        return None;
+    }
+
+    let trimmed = line.trim_start();
+    let (marker, width) = match trimmed.chars().next()? {
+        c @ ('-' | '*' | '+') => (Marker::Bullet(c), 1),
+        _ => {
+            let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+            let delimiter = trimmed[digits..].chars().next()?;
+            if !(1..=9).contains(&digits) || !(delimiter == '.' || delimiter == ')') {
+                return None;
+            }
+
+            let start = trimmed[..digits].parse().ok()?;
+            (Marker::Ordered { start, delimiter }, digits + 1)
+        }
+    };
+
+    let rest = &trimmed[width..];
+    if rest.trim().is_empty() {
+        return Some((marker, indent + width + 1, ""));
+    }
+
+    let spaces = indentation(rest);
+    if spaces == 0 {
+        return None;
+    }
+
+    // content indented by 5 or more starts with an indented code block, which isn't
+    // supported, so treat it as indented by one
+    let spaces = if spaces > 4 { 1 } else { spaces };
+
+    Some((marker, indent + width + spaces, dedent(rest, spaces)))
+}
+
+fn same_list(a: Marker, b: Marker) -> bool {
+    match (a, b) {
+        (Marker::Bullet(a), Marker::Bullet(b)) => a == b,
+        (Marker::Ordered { delimiter: a, .. }, Marker::Ordered { delimiter: b, .. }) => a == b,
+        _ => false,
+    }
+}
+
+/// Whether the line starts a block other than a paragraph
+fn starts_block(line: &str) -> bool {
+    fence(line).is_some()
+        || is_rule(line)
+        || heading(line).is_some()
+        || quote(line).is_some()
+        || list_item(line).is_some()
+}
+
+fn parse_blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut index = 0;
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph {
+                inlines: parse_inlines(paragraph),
+            });
+            paragraph.clear();
+        }
+    };
+
+    while index < lines.len() {
+        let line = lines[index];
+
+        if is_blank(line) {
+            flush(&mut paragraph, &mut blocks);
+            index += 1;
+        } else if let Some((c, length)) = fence(line) {
+            flush(&mut paragraph, &mut blocks);
+
+            let indent = indentation(line);
+            let info = line.trim_start().trim_start_matches(c).trim();
+            let language = info.split_whitespace().next().map(str::to_string);
+
+            let mut code = Vec::new();
+            index += 1;
+            while index < lines.len() {
+                let closing = fence(lines[index]).filter(|(x, l)| *x == c && *l >= length);
+                let is_closing = closing.is_some() && lines[index].trim().chars().all(|x| x == c);
+                index += 1;
+                if is_closing {
+                    break;
+                }
+                code.push(dedent(lines[index - 1], indent));
+            }
+
+            blocks.push(Block::Code {
+                language,
+                code: code.join("\n"),
+            });
+        } else if is_rule(line) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Rule);
+            index += 1;
+        } else if let Some((level, text)) = heading(line) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading {
+                level,
+                inlines: parse_inlines(&[text]),
+            });
+            index += 1;
+        } else if quote(line).is_some() {
+            flush(&mut paragraph, &mut blocks);
+
+            let mut quoted = Vec::new();
+            while let Some(rest) = lines.get(index).and_then(|line| quote(line)) {
+                quoted.push(rest);
+                index += 1;
+            }
+
+            blocks.push(Block::Quote {
+                blocks: parse_blocks(&quoted),
+            });
+        } else if let Some((marker, ..)) = list_item(line) {
+            flush(&mut paragraph, &mut blocks);
+
+            let (list, next) = parse_list(lines, index, marker);
+            blocks.push(list);
+            index = next;
+        } else {
+            paragraph.push(line.trim_start());
+            index += 1;
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+
+    blocks
+}
+
+/// Parse the list starting at `lines[start]`, returning it and the index of the line after it
+fn parse_list(lines: &[&str], start: usize, first: Marker) -> (Block, usize) {
+    let mut items: Vec<Vec<Block>> = Vec::new();
+    let mut index = start;
+
+    while let Some((marker, content_indent, content)) =
+        lines.get(index).and_then(|line| list_item(line))
+    {
+        if !same_list(first, marker) {
+            break;
+        }
+
+        let mut item = vec![content];
+        index += 1;
+
+        while index < lines.len() {
+            let line = lines[index];
+
+            if is_blank(line) {
+                // the item continues if the blank lines are followed by indented content
+                match lines[index..].iter().position(|line| !is_blank(line)) {
+                    Some(offset) if indentation(lines[index + offset]) >= content_indent => {
+                        item.extend(std::iter::repeat("").take(offset));
+                        index += offset;
+                    }
+                    _ => break,
+                }
+            } else if indentation(line) >= content_indent {
+                item.push(dedent(line, content_indent));
+                index += 1;
+            } else if !starts_block(line) && item.last().map_or(false, |last| !is_blank(last)) {
+                // a lazy continuation of the item's paragraph
+                item.push(line.trim_start());
+                index += 1;
+            } else {
+                break;
+            }
+        }
+
+        items.push(parse_blocks(&item));
+
+        // the list ends at a blank line which isn't followed by another item
+        let next = lines[index..].iter().position(|line| !is_blank(line));
+        match next.map(|offset| index + offset) {
+            Some(next) if list_item(lines[next]).is_some() => index = next,
+            _ => break,
+        }
+    }
+
+    let start = match first {
+        Marker::Bullet(_) => None,
+        Marker::Ordered { start, .. } => Some(start),
+    };
+
+    (Block::List { start, items }, index)
+}
+
+/// Parse the inline content of a paragraph's lines
+fn parse_inlines(lines: &[&str]) -> Vec<Inline> {
+    let mut text = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let last = index + 1 == lines.len();
+
+        if last {
+            text.push_str(line.trim_end());
+        } else if line.ends_with("  ") {
+            text.push_str(line.trim_end());
+            text.push('\n');
+        } else if let Some(line) = line.strip_suffix('\\') {
+            // a hard line break, unless the backslash is escaped itself
+            text.push_str(line);
+            text.push_str(if line.ends_with('\\') { "\\ " } else { "\n" });
+        } else {
+            text.push_str(line.trim_end());
+            text.push(' ');
+        }
+    }
+
+    let mut parser = InlineParser {
+        chars: text.chars().collect(),
+        inlines: Vec::new(),
+    };
+    parser.parse(0, parser.chars.len(), Style::default(), None);
+
+    parser.inlines
+}
+
+struct InlineParser {
+    chars: Vec<char>,
+    inlines: Vec<Inline>,
+}
+
+impl InlineParser {
+    fn push_text(&mut self, text: &str, style: Style, link: Option<&str>) {
+        if text.is_empty() {
+            return;
+        }
+
+        if let Some(Inline::Text {
+            text: last,
+            style: last_style,
+            link: last_link,
+        }) = self.inlines.last_mut()
+        {
+            if *last_style == style && last_link.as_deref() == link {
+                last.push_str(text);
+                return;
+            }
+        }
+
+        self.inlines.push(Inline::Text {
+            text: text.to_string(),
+            style,
+            link: link.map(str::to_string),
+        });
+    }
+
+    fn run_length(&self, at: usize, end: usize, c: char) -> usize {
+        self.chars[at..end].iter().take_while(|x| **x == c).count()
+    }
+
+    fn parse(&mut self, start: usize, end: usize, style: Style, link: Option<&str>) {
+        let mut text = String::new();
+        let mut i = start;
+
+        while i < end {
+            let c = self.chars[i];
+
+            match c {
+                '\\' if i + 1 < end && self.chars[i + 1].is_ascii_punctuation() => {
+                    text.push(self.chars[i + 1]);
+                    i += 2;
+                }
+                '\n' => {
+                    self.push_text(&std::mem::take(&mut text), style, link);
+                    self.inlines.push(Inline::LineBreak);
+                    i += 1;
+                }
+                '`' => {
+                    let length = self.run_length(i, end, '`');
+                    match self.find_code_span_end(i + length, end, length) {
+                        Some(close) => {
+                            self.push_text(&std::mem::take(&mut text), style, link);
+
+                            let code: String = self.chars[i + length..close].iter().collect();
+                            let stripped = code
+                                .strip_prefix(' ')
+                                .and_then(|code| code.strip_suffix(' '))
+                                .filter(|code| !code.trim().is_empty());
+                            let code_style = Style {
+                                code: true,
+                                ..style
+                            };
+                            self.push_text(stripped.unwrap_or(&code), code_style, link);
+
+                            i = close + length;
+                        }
+                        None => {
+                            text.extend(&self.chars[i..i + length]);
+                            i += length;
+                        }
+                    }
+                }
+                '!' if self.chars.get(i + 1) == Some(&'[') && i + 1 < end => {
+                    match self.link(i + 1, end) {
+                        Some((text_end, url, after)) => {
+                            self.push_text(&std::mem::take(&mut text), style, link);
+
+                            let alt = self.chars[i + 2..text_end]
+                                .iter()
+                                .filter(|c| !matches!(c, '*' | '_' | '`'))
+                                .collect();
+                            self.inlines.push(Inline::Image { url, alt });
+                            i = after;
+                        }
+                        None => {
+                            text.push('!');
+                            i += 1;
+                        }
+                    }
+                }
+                '[' => match self.link(i, end) {
+                    Some((text_end, url, after)) => {
+                        self.push_text(&std::mem::take(&mut text), style, link);
+                        self.parse(i + 1, text_end, style, Some(&url));
+                        i = after;
+                    }
+                    None => {
+                        text.push('[');
+                        i += 1;
+                    }
+                },
+                '<' => match self.autolink(i, end) {
+                    Some((url, after)) => {
+                        self.push_text(&std::mem::take(&mut text), style, link);
+                        self.push_text(&url, style, Some(link.unwrap_or(&url)));
+                        i = after;
+                    }
+                    None => {
+                        text.push('<');
+                        i += 1;
+                    }
+                },
+                '*' | '_' | '~' => {
+                    let length = self.run_length(i, end, c);
+                    match self.emphasis(i, end, c, length, style) {
+                        Some((inner_style, width, close)) => {
+                            self.push_text(&std::mem::take(&mut text), style, link);
+                            self.parse(i + width, close, inner_style, link);
+                            i = close + width;
+                        }
+                        None => {
+                            text.extend(&self.chars[i..i + length]);
+                            i += length;
+                        }
+                    }
+                }
+                c => {
+                    text.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        self.push_text(&text, style, link);
+    }
+
+    /// The start of the run of exactly `length` backticks closing a code span
+    fn find_code_span_end(&self, from: usize, end: usize, length: usize) -> Option<usize> {
+        let mut i = from;
+        while i < end {
+            if self.chars[i] == '`' {
+                let run = self.run_length(i, end, '`');
+                if run == length {
+                    return Some(i);
+                }
+                i += run;
+            } else {
+                i += 1;
+            }
+        }
+
+        None
+    }
+
+    /// A link `[text](url)` starting at `open`: the end of the text, the url and the index
+    /// after the link
+    fn link(&self, open: usize, end: usize) -> Option<(usize, String, usize)> {
+        let mut depth = 0;
+        let mut i = open;
+        let text_end = loop {
+            match self.chars.get(i).filter(|_| i < end)? {
+                '\\' => i += 1,
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break i;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        };
+
+        if self.chars.get(text_end + 1) != Some(&'(') {
+            return None;
+        }
+
+        let destination_start = text_end + 2;
+        let destination_end = (destination_start..end).find(|&i| self.chars[i] == ')')?;
+        let destination: String = self.chars[destination_start..destination_end]
+            .iter()
+            .collect();
+
+        // drop the optional title
+        let url = destination.split_whitespace().next().unwrap_or_default();
+        let url = url
+            .strip_prefix('<')
+            .and_then(|url| url.strip_suffix('>'))
+            .unwrap_or(url);
+
+        Some((text_end, url.to_string(), destination_end + 1))
+    }
+
+    /// An autolink `<https://...>` starting at `open`: the url and the index after the link
+    fn autolink(&self, open: usize, end: usize) -> Option<(String, usize)> {
+        let close = (open + 1..end).find(|&i| self.chars[i] == '>')?;
+        let url: String = self.chars[open + 1..close].iter().collect();
+
+        let is_url = ["http://", "https://", "mailto:"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme))
+            && !url.contains(char::is_whitespace);
+
+        is_url.then_some((url, close + 1))
+    }
+
+    /// Emphasis opening with the run of `length` `c`s at `open`: the style inside it, the width
+    /// of its delimiters and the start of the closing delimiter
+    fn emphasis(
+        &self,
+        open: usize,
+        end: usize,
+        c: char,
+        length: usize,
+        style: Style,
+    ) -> Option<(Style, usize, usize)> {
+        let (inner_style, width) = match (c, length) {
+            ('~', 1 | 2) => (
+                Style {
+                    strikethrough: true,
+                    ..style
+                },
+                length,
+            ),
+            ('~', _) => return None,
+            (_, 1) => (
+                Style {
+                    italic: true,
+                    ..style
+                },
+                1,
+            ),
+            (_, 2) => (
+                Style {
+                    bold: true,
+                    ..style
+                },
+                2,
+            ),
+            _ => (
+                Style {
+                    bold: true,
+                    italic: true,
+                    ..style
+                },
+                3,
+            ),
+        };
+
+        let alphanumeric = |i: usize| self.chars.get(i).map_or(false, |c| c.is_alphanumeric());
+        let whitespace = |i: usize| self.chars.get(i).map_or(true, |c| c.is_whitespace());
+
+        // an opening delimiter is followed by text, and `_` can't open inside a word
+        if whitespace(open + length) || (c == '_' && open > 0 && alphanumeric(open - 1)) {
+            return None;
+        }
+
+        // prefer a closing run of the same length, e.g. skip `**` when closing `*`
+        let closing_runs: Vec<(usize, usize)> = self
+            .runs(open + length, end, c)
+            .into_iter()
+            .filter(|&(at, run)| !whitespace(at - 1) && !(c == '_' && alphanumeric(at + run)))
+            .collect();
+        let close = closing_runs
+            .iter()
+            .find(|(_, run)| *run == length)
+            .or_else(|| {
+                closing_runs
+                    .iter()
+                    .find(|(_, run)| *run > length && c != '~')
+            })
+            .map(|&(at, run)| at + run - width)?;
+
+        Some((inner_style, width, close))
+    }
+
+    /// The runs of `c` between `from` and `end`, outside of code spans
+    fn runs(&self, from: usize, end: usize, c: char) -> Vec<(usize, usize)> {
+        let mut runs = Vec::new();
+        let mut i = from;
+
+        while i < end {
+            if self.chars[i] == '`' {
+                let length = self.run_length(i, end, '`');
+                i = self
+                    .find_code_span_end(i + length, end, length)
+                    .map_or(i + length, |close| close + length);
+            } else if self.chars[i] == '\\' {
+                i += 2;
+            } else if self.chars[i] == c {
+                let length = self.run_length(i, end, c);
+                runs.push((i, length));
+                i += length;
+            } else {
+                i += 1;
+            }
+        }
+
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Inline {
+        Inline::text(text, Style::default())
+    }
+
+    fn paragraph(inlines: Vec<Inline>) -> Block {
+        Block::Paragraph { inlines }
+    }
+
+    fn inlines(markdown: &str) -> Vec<Inline> {
+        match parse(markdown).as_slice() {
+            [Block::Paragraph { inlines }] => inlines.clone(),
+            blocks => panic!("expected one paragraph, got {blocks:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_emphasis() {
+        let bold_italic = Style {
+            bold: true,
+            italic: true,
+            ..Style::default()
+        };
+
+        assert_eq!(
+            inlines("*a* **b** ***c*** ~~d~~"),
+            vec![
+                Inline::text("a", Style::ITALIC),
+                text(" "),
+                Inline::text("b", Style::BOLD),
+                text(" "),
+                Inline::text("c", bold_italic),
+                text(" "),
+                Inline::text(
+                    "d",
+                    Style {
+                        strikethrough: true,
+                        ..Style::default()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            inlines("**bold *both* bold**"),
+            vec![
+                Inline::text("bold ", Style::BOLD),
+                Inline::text("both", bold_italic),
+                Inline::text(" bold", Style::BOLD),
+            ]
+        );
+        assert_eq!(
+            inlines("*italic **both***"),
+            vec![
+                Inline::text("italic ", Style::ITALIC),
+                Inline::text("both", bold_italic),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unmatched_delimiters_as_text() {
+        assert_eq!(inlines("2 * 3 * 4"), vec![text("2 * 3 * 4")]);
+        assert_eq!(inlines("snake_case_name"), vec![text("snake_case_name")]);
+        assert_eq!(inlines("**open"), vec![text("**open")]);
+        assert_eq!(inlines(r"\*not italic\*"), vec![text("*not italic*")]);
+    }
+
+    #[test]
+    fn parses_code_spans() {
+        assert_eq!(
+            inlines("run `cargo *test*` now"),
+            vec![
+                text("run "),
+                Inline::text("cargo *test*", Style::CODE),
+                text(" now")
+            ]
+        );
+        assert_eq!(
+            inlines("``a ` b``"),
+            vec![Inline::text("a ` b", Style::CODE)]
+        );
+        assert_eq!(inlines("`unclosed"), vec![text("`unclosed")]);
+    }
+
+    #[test]
+    fn parses_links_and_images() {
+        assert_eq!(
+            inlines(r#"see [the **docs**](https://example.com "Docs") or <https://crux.dev>"#),
+            vec![
+                text("see "),
+                Inline::link("the ", Style::default(), "https://example.com"),
+                Inline::link("docs", Style::BOLD, "https://example.com"),
+                text(" or "),
+                Inline::link("https://crux.dev", Style::default(), "https://crux.dev"),
+            ]
+        );
+        assert_eq!(
+            inlines("![a *cat*](cat.png) [not a link] <not a link>"),
+            vec![
+                Inline::Image {
+                    url: "cat.png".to_string(),
+                    alt: "a cat".to_string()
+                },
+                text(" [not a link] <not a link>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_line_breaks() {
+        assert_eq!(
+            inlines("soft\nbreak  \nhard\\\nbreak"),
+            vec![
+                text("soft break"),
+                Inline::LineBreak,
+                text("hard"),
+                Inline::LineBreak,
+                text("break"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let markdown = "\
+# Heading #
+
+First paragraph
+continues.
+
+---
+
+> quoted
+> **text**
+
+```rust ignore
+fn main() {}
+```
+";
+
+        assert_eq!(
+            parse(markdown),
+            vec![
+                Block::Heading {
+                    level: 1,
+                    inlines: vec![text("Heading")]
+                },
+                paragraph(vec![text("First paragraph continues.")]),
+                Block::Rule,
+                Block::Quote {
+                    blocks: vec![paragraph(vec![
+                        text("quoted "),
+                        Inline::text("text", Style::BOLD)
+                    ])]
+                },
+                Block::Code {
+                    language: Some("rust".to_string()),
+                    code: "fn main() {}".to_string()
+                },
+            ]
+        );
+
+        assert_eq!(parse("#hashtag"), vec![paragraph(vec![text("#hashtag")])]);
+        assert_eq!(
+            parse("~~~\nunclosed\n\ncode"),
+            vec![Block::Code {
+                language: None,
+                code: "unclosed\n\ncode".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_lists() {
+        let markdown = "\
+1. one
+2. two
+   - nested
+     lazy
+   - nested again
+
+   more of two
+3. three
+
+- other list
+";
+
+        assert_eq!(
+            parse(markdown),
+            vec![
+                Block::List {
+                    start: Some(1),
+                    items: vec![
+                        vec![paragraph(vec![text("one")])],
+                        vec![
+                            paragraph(vec![text("two")]),
+                            Block::List {
+                                start: None,
+                                items: vec![
+                                    vec![paragraph(vec![text("nested lazy")])],
+                                    vec![paragraph(vec![text("nested again")])],
+                                ]
+                            },
+                            paragraph(vec![text("more of two")]),
+                        ],
+                        vec![paragraph(vec![text("three")])],
+                    ]
+                },
+                Block::List {
+                    start: None,
+                    items: vec![vec![paragraph(vec![text("other list")])]]
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Rich text for view models, see [`RichText`]
+//!
+//! Chat messages, articles and other formatted content can be prepared by the core as a
+//! [`RichText`]: a tree of [blocks](Block) (paragraphs, headings, lists, ...) holding runs of
+//! [inline](Inline) content with a [style](Style). Each shell maps it onto its native text
+//! system (e.g. `AttributedString` on iOS, `AnnotatedString` on Android), so the content looks
+//! the same everywhere without each shell parsing Markdown itself.
+//!
+//! As with other enums nested in the view model, register [`Block`] and [`Inline`] with
+//! `TypeGen::register_type` so that all their variants are generated.
+//!
+//! ```
+//! use crux_types::rich_text::{Block, Inline, RichText, Style};
+//!
+//! let text = RichText::from_markdown("Hello **world**");
+//!
+//! assert_eq!(
+//!     text.blocks,
+//!     vec![Block::Paragraph {
+//!         inlines: vec![
+//!             Inline::text("Hello ", Style::default()),
+//!             Inline::text("world", Style::BOLD),
+//!         ]
+//!     }]
+//! );
+//! assert_eq!(text.to_plain_text(), "Hello world");
+//! ```
+
+mod markdown;
+
+use serde::{Deserialize, Serialize};
+
+/// A document of formatted text
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RichText {
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Block {
+    Paragraph {
+        inlines: Vec<Inline>,
+    },
+    /// A heading, with a `level` from 1 (the most important) to 6
+    Heading {
+        level: u8,
+        inlines: Vec<Inline>,
+    },
+    Quote {
+        blocks: Vec<Block>,
+    },
+    /// A list, numbered from `start` if it's an ordered list. Each item is a list of blocks.
+    List {
+        start: Option<u32>,
+        items: Vec<Vec<Block>>,
+    },
+    /// Preformatted code, with the language it's written in, if known
+    Code {
+        language: Option<String>,
+        code: String,
+    },
+    /// A thematic break between sections
+    Rule,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Inline {
+    /// A run of text in one style, which is part of a link to `link` if set
+    Text {
+        text: String,
+        style: Style,
+        link: Option<String>,
+    },
+    Image {
+        url: String,
+        alt: String,
+    },
+    LineBreak,
+}
+
+impl Inline {
+    pub fn text(text: impl Into<String>, style: Style) -> Self {
+        Inline::Text {
+            text: text.into(),
+            style,
+            link: None,
+        }
+    }
+
+    pub fn link(text: impl Into<String>, style: Style, url: impl Into<String>) -> Self {
+        Inline::Text {
+            text: text.into(),
+            style,
+            link: Some(url.into()),
+        }
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub strikethrough: bool,
+    /// Inline code, usually shown in a monospaced font
+    pub code: bool,
+}
+
+impl Style {
+    pub const BOLD: Style = Style {
+        bold: true,
+        italic: false,
+        strikethrough: false,
+        code: false,
+    };
+    pub const ITALIC: Style = Style {
+        bold: false,
+        italic: true,
+        strikethrough: false,
+        code: false,
+    };
+    pub const CODE: Style = Style {
+        bold: false,
+        italic: false,
+        strikethrough: false,
+        code: true,
+    };
+}
+
+impl RichText {
+    /// A single paragraph of unstyled text
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            blocks: vec![Block::Paragraph {
+                inlines: vec![Inline::text(text, Style::default())],
+            }],
+        }
+    }
+
+    /// Parse [CommonMark](https://commonmark.org) Markdown, with strikethrough (`~~`) from
+    /// GitHub Flavored Markdown.
+    ///
+    /// Headings, paragraphs, block quotes, nested lists, fenced code blocks, thematic breaks,
+    /// emphasis, code spans, links, autolinks, images and hard line breaks are supported.
+    /// Setext headings, indented code blocks, reference links and HTML are not, and show up
+    /// as text.
+    pub fn from_markdown(markdown: &str) -> Self {
+        Self {
+            blocks: markdown::parse(markdown),
+        }
+    }
+
+    /// The text without formatting, e.g. for notifications or accessibility labels. Blocks
+    /// are separated by blank lines, and images are replaced by their alt text.
+    pub fn to_plain_text(&self) -> String {
+        blocks_to_plain_text(&self.blocks)
+    }
+}
+
+fn blocks_to_plain_text(blocks: &[Block]) -> String {
+    let texts: Vec<String> = blocks
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph { inlines } | Block::Heading { inlines, .. } => {
+                inlines_to_plain_text(inlines)
+            }
+            Block::Quote { blocks } => blocks_to_plain_text(blocks),
+            Block::List { start, items } => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let marker = match start {
+                        Some(start) => format!("{}.", u64::from(*start) + index as u64),
+                        None => "-".to_string(),
+                    };
+                    format!("{marker} {}", blocks_to_plain_text(item))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Code { code, .. } => code.clone(),
+            Block::Rule => "---".to_string(),
+        })
+        .collect();
+
+    texts.join("\n\n")
+}
+
+fn inlines_to_plain_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text { text, .. } => text.as_str(),
+            Inline::Image { alt, .. } => alt.as_str(),
+            Inline::LineBreak => "\n",
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_plain_text() {
+        let text = RichText::from_markdown(
+            "# Title\n\nSome *text*  \nand ![a cat](cat.png)\n\n3. one\n4. two\n\n> quoted",
+        );
+
+        assert_eq!(
+            text.to_plain_text(),
+            "Title\n\nSome text\nand a cat\n\n3. one\n4. two\n\nquoted"
+        );
+    }
+
+    #[test]
+    fn serializes_variants_in_camel_case() {
+        let text = RichText {
+            blocks: vec![Block::Rule, RichText::plain("hi").blocks[0].clone()],
+        };
+
+        let json = serde_json::to_string(&text).unwrap();
+        assert_eq!(
+            json,
+            r#"{"blocks":["rule",{"paragraph":{"inlines":[{"text":{"text":"hi","style":{"bold":false,"italic":false,"strikethrough":false,"code":false},"link":null}}]}}]}"#
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! App update and version checks
+//!
+//! An [`UpdateChecker`] fetches a [`VersionManifest`] with `crux_http` and compares it with
+//! the installed version of the app, following semver precedence, to tell whether an update
+//! is [available or required](UpdateStatus). It has to be used in an async context, together
+//! with [`crux_core::compose::Compose`].
+//!
+//! The installed version is given by the app (typically the shell passes it to the core at
+//! start up). [`UpdateChecker::watch`] repeats the check periodically using `crux_time`, and
+//! [`UpdateStatus::store_listing`] is a request for the `crux_open` capability to send the user
+//! to the store listing.
+//!
+//! # Protocol
+//!
+//! The manifest is served as JSON, for example
+//!
+//! ```json
+//! {
+//!   "latest": "2.4.0",
+//!   "minimum": "2.0.0",
+//!   "storeUrl": "https://apps.apple.com/app/id000000000",
+//!   "releaseNotes": "Faster sync"
+//! }
+//! ```
+//!
+//! `minimum` and `releaseNotes` are optional. Installed versions lower than `minimum` are no
+//! longer supported, and the app should ask the user to update before continuing.
+
+use crux_http::{Http, HttpError};
+use crux_open::OpenRequest;
+use crux_time::{Duration, Time};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+mod version;
+
+pub use version::{Identifier, Version};
+
+/// How often [`UpdateChecker::watch`] checks for updates by default: once a day
+const DEFAULT_INTERVAL_SECONDS: u64 = 24 * 60 * 60;
+
+/// Error type for update checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateError {
+    #[error("http error: {error}")]
+    Http { error: HttpError },
+    #[error("invalid version {version}: {message}")]
+    InvalidVersion { version: String, message: String },
+}
+
+/// The versions of the app available in the store, as served by the manifest URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionManifest {
+    /// The latest released version
+    pub latest: Version,
+    /// The lowest version which is still supported
+    #[serde(default)]
+    pub minimum: Option<Version>,
+    /// The app's listing in the store
+    pub store_url: String,
+    #[serde(default)]
+    pub release_notes: Option<String>,
+}
+
+/// Whether the installed version of the app should be updated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum UpdateStatus {
+    /// The installed version is the latest
+    UpToDate,
+    /// A newer version is available, the user can update when they like
+    Available {
+        latest: Version,
+        store_url: String,
+        release_notes: Option<String>,
+    },
+    /// The installed version is no longer supported, the user needs to update
+    Required {
+        latest: Version,
+        store_url: String,
+        release_notes: Option<String>,
+    },
+}
+
+impl UpdateStatus {
+    /// Whether the app should stop the user until they update
+    pub fn is_required(&self) -> bool {
+        matches!(self, UpdateStatus::Required { .. })
+    }
+
+    /// A request to open the app's store listing, for use with [`crux_open::Open`],
+    /// unless the app is up to date.
+    pub fn store_listing(&self) -> Option<OpenRequest> {
+        match self {
+            UpdateStatus::UpToDate => None,
+            UpdateStatus::Available { store_url, .. }
+            | UpdateStatus::Required { store_url, .. } => {
+                Some(OpenRequest::url(store_url.as_str()))
+            }
+        }
+    }
+}
+
+/// Checks for updates of the installed version of the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateChecker {
+    manifest_url: String,
+    installed: Version,
+    interval: Duration,
+}
+
+impl UpdateChecker {
+    /// Create a checker comparing the `installed` version of the app with the manifest
+    /// served at `manifest_url`. The manifest is checked daily, see [`UpdateChecker::with_interval`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `installed` is not a valid semver version.
+    pub fn new(manifest_url: impl Into<String>, installed: &str) -> Result<Self, UpdateError> {
+        let installed = Version::parse(installed)?;
+        let interval = Duration::from_secs(DEFAULT_INTERVAL_SECONDS)
+            .expect("the default interval is a valid Duration");
+
+        Ok(Self {
+            manifest_url: manifest_url.into(),
+            installed,
+            interval,
+        })
+    }
+
+    /// Check for updates every `interval` in [`UpdateChecker::watch`].
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The installed version of the app
+    pub fn installed(&self) -> &Version {
+        &self.installed
+    }
+
+    /// Compare the installed version with `manifest`.
+    pub fn status(&self, manifest: VersionManifest) -> UpdateStatus {
+        let VersionManifest {
+            latest,
+            minimum,
+            store_url,
+            release_notes,
+        } = manifest;
+
+        if minimum.map_or(false, |minimum| self.installed < minimum) {
+            UpdateStatus::Required {
+                latest,
+                store_url,
+                release_notes,
+            }
+        } else if self.installed < latest {
+            UpdateStatus::Available {
+                latest,
+                store_url,
+                release_notes,
+            }
+        } else {
+            UpdateStatus::UpToDate
+        }
+    }
+
+    /// Fetch the manifest and compare it with the installed version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest can't be fetched or parsed.
+    pub async fn check<Ev>(&self, http: &Http<Ev>) -> Result<UpdateStatus, UpdateError>
+    where
+        Ev: 'static,
+    {
+        let error = |error| UpdateError::Http { error };
+
+        let mut response = http
+            .get(&self.manifest_url)
+            .send_async()
+            .await
+            .map_err(error)?;
+
+        if !response.status().is_success() {
+            return Err(error(HttpError::Http {
+                code: response.status(),
+                message: "fetching the version manifest failed".to_string(),
+                body: response.body_bytes().await.ok(),
+            }));
+        }
+
+        let manifest = response.body_json().await.map_err(error)?;
+
+        Ok(self.status(manifest))
+    }
+
+    /// Check for updates now and then at every interval, calling `on_status` with the result
+    /// of each check. This never finishes, so it should run in its own task.
+    pub async fn watch<Ev, TimeEv, F>(&self, http: &Http<Ev>, time: &Time<TimeEv>, mut on_status: F)
+    where
+        Ev: 'static,
+        TimeEv: 'static,
+        F: FnMut(Result<UpdateStatus, UpdateError>),
+    {
+        loop {
+            on_status(self.check(http).await);
+
+            time.notify_after_async(self.interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(latest: &str, minimum: Option<&str>) -> VersionManifest {
+        VersionManifest {
+            latest: latest.parse().unwrap(),
+            minimum: minimum.map(|m| m.parse().unwrap()),
+            store_url: "https://store.example.com/app".to_string(),
+            release_notes: None,
+        }
+    }
+
+    #[test]
+    fn compares_versions_by_semver_precedence() {
+        let checker = UpdateChecker::new("https://example.com/version.json", "1.10.0").unwrap();
+
+        assert_eq!(
+            checker.status(manifest("1.9.0", None)),
+            UpdateStatus::UpToDate
+        );
+        assert_eq!(
+            checker.status(manifest("1.10.0", None)),
+            UpdateStatus::UpToDate
+        );
+        assert!(matches!(
+            checker.status(manifest("1.10.1", Some("1.0.0"))),
+            UpdateStatus::Available { .. }
+        ));
+        assert!(checker
+            .status(manifest("2.0.0", Some("1.10.1")))
+            .is_required());
+
+        // a release candidate comes before the release
+        let checker = UpdateChecker::new("https://example.com/version.json", "2.0.0-rc.1").unwrap();
+        assert!(matches!(
+            checker.status(manifest("2.0.0", None)),
+            UpdateStatus::Available { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_installed_version() {
+        assert!(matches!(
+            UpdateChecker::new("https://example.com/version.json", "1.2"),
+            Err(UpdateError::InvalidVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn manifest_serialization() {
+        let manifest: VersionManifest = serde_json::from_str(
+            r#"{"latest":"2.4.0","minimum":"2.0.0","storeUrl":"https://store.example.com/app"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.latest, Version::new(2, 4, 0));
+        assert_eq!(manifest.minimum, Some(Version::new(2, 0, 0)));
+        assert_eq!(manifest.release_notes, None);
+
+        let status = UpdateStatus::Available {
+            latest: manifest.latest,
+            store_url: manifest.store_url,
+            release_notes: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&status).unwrap(),
+            r#"{"available":{"latest":"2.4.0","storeUrl":"https://store.example.com/app","releaseNotes":null}}"#
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::UpdateError;
+
+/// A semantic version, e.g. `1.4.0` or `2.0.0-rc.1`, ordered by semver precedence.
+/// Build metadata (`+...`) is accepted but ignored, as precedence doesn't depend on it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// The dot separated pre-release identifiers, empty for a release
+    pub pre: Vec<Identifier>,
+}
+
+/// A pre-release identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+        }
+    }
+
+    /// Parse a version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `version` is not a valid semantic version.
+    pub fn parse(version: &str) -> Result<Self, UpdateError> {
+        let invalid = |message: &str| UpdateError::InvalidVersion {
+            version: version.to_string(),
+            message: message.to_string(),
+        };
+
+        let without_build = version.split('+').next().unwrap_or_default();
+        let (core, pre) = match without_build.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (without_build, None),
+        };
+
+        let numbers = core
+            .split('.')
+            .map(parse_number)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid("expected numeric major, minor and patch versions"))?;
+        let [major, minor, patch] = numbers[..] else {
+            return Err(invalid("expected major, minor and patch versions"));
+        };
+
+        let pre = match pre {
+            None => Vec::new(),
+            Some(pre) => pre
+                .split('.')
+                .map(Identifier::parse)
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid("invalid pre-release identifier"))?,
+        };
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+fn parse_number(number: &str) -> Option<u64> {
+    // no leading zeros
+    if number.is_empty() || (number.len() > 1 && number.starts_with('0')) {
+        return None;
+    }
+
+    number.parse().ok()
+}
+
+impl Identifier {
+    fn parse(identifier: &str) -> Option<Self> {
+        if identifier.is_empty()
+            || !identifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return None;
+        }
+
+        if identifier.chars().all(|c| c.is_ascii_digit()) {
+            parse_number(identifier).map(Identifier::Numeric)
+        } else {
+            Some(Identifier::AlphaNumeric(identifier.to_string()))
+        }
+    }
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                // a pre-release comes before the release
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(number) => write!(f, "{number}"),
+            Identifier::AlphaNumeric(identifier) => f.write_str(identifier),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        for (i, identifier) in self.pre.iter().enumerate() {
+            let separator = if i == 0 { '-' } else { '.' };
+            write!(f, "{separator}{identifier}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = UpdateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+
+        Version::parse(&version).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    #[test]
+    fn precedence() {
+        // from the example in the semver spec
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+            "1.10.0",
+            "2.0.0",
+        ];
+
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(v("1.0.0+build.5"), v("1.0.0"));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(v("2.0.0-rc.1+build").to_string(), "2.0.0-rc.1");
+    }
+
+    #[test]
+    fn invalid_versions() {
+        for version in ["1.2", "1.2.3.4", "01.2.3", "1.2.x", "1.2.3-", "1.2.3-a..b"] {
+            assert!(version.parse::<Version>().is_err(), "{version}");
+        }
+    }
+}
@@ -0,0 +1,211 @@
+mod shared {
+    use crux_core::{compose::Compose, macros::Effect, render::Render};
+    use crux_http::Http;
+    use crux_open::{Open, OpenResult};
+    use crux_time::{Duration, Time};
+    use crux_update::{UpdateChecker, UpdateError, UpdateStatus};
+    use serde::{Deserialize, Serialize};
+
+    pub const MANIFEST_URL: &str = "https://example.com/version.json";
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Start {
+            version: String,
+        },
+        OpenStore,
+
+        #[serde(skip)]
+        Checked(Result<UpdateStatus, UpdateError>),
+        #[serde(skip)]
+        StoreOpened(OpenResult),
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub status: Option<UpdateStatus>,
+        pub error: Option<UpdateError>,
+        pub store_opened: Option<OpenResult>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub http: Http<Event>,
+        pub time: Time<Event>,
+        pub open: Open<Event>,
+        pub render: Render<Event>,
+        #[effect(skip)]
+        pub compose: Compose<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = Option<UpdateStatus>;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Start { version } => {
+                    let checker = match UpdateChecker::new(MANIFEST_URL, &version) {
+                        Ok(checker) => checker.with_interval(Duration::from_secs(3600).unwrap()),
+                        Err(error) => {
+                            model.error = Some(error);
+                            return;
+                        }
+                    };
+                    let http = caps.http.clone();
+                    let time = caps.time.clone();
+
+                    caps.compose.spawn(|ctx| async move {
+                        checker
+                            .watch(&http, &time, |result| {
+                                ctx.update_app(Event::Checked(result))
+                            })
+                            .await;
+                    });
+                }
+                Event::OpenStore => {
+                    if let Some(request) =
+                        model.status.as_ref().and_then(UpdateStatus::store_listing)
+                    {
+                        caps.open.open(request, Event::StoreOpened);
+                    }
+                }
+                Event::Checked(Ok(status)) => {
+                    model.status = Some(status);
+                    caps.render.render();
+                }
+                Event::Checked(Err(error)) => model.error = Some(error),
+                Event::StoreOpened(result) => model.store_opened = Some(result),
+            }
+        }
+
+        fn view(&self, model: &Model) -> Option<UpdateStatus> {
+            model.status.clone()
+        }
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_http::protocol::{HttpResponse, HttpResult};
+    use crux_open::{OpenRequest, OpenResult};
+    use crux_time::{TimeRequest, TimeResponse};
+    use crux_update::{UpdateError, UpdateStatus, Version};
+    use serde_json::json;
+
+    use crate::shared::{App, Effect, Event, Model, MANIFEST_URL};
+
+    fn manifest(latest: &str, minimum: &str) -> HttpResponse {
+        HttpResponse::ok()
+            .json(json!({
+                "latest": latest,
+                "minimum": minimum,
+                "storeUrl": "https://store.example.com/app"
+            }))
+            .build()
+    }
+
+    #[test]
+    fn checks_periodically_and_opens_the_store() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::Start {
+                version: "1.4.0".to_string(),
+            },
+            &mut model,
+        );
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("expected an Http effect");
+        };
+        assert_eq!(request.operation.url, MANIFEST_URL);
+
+        let update = app
+            .resolve(&mut request, HttpResult::Ok(manifest("1.5.0", "1.0.0")))
+            .unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+        assert_eq!(
+            app.view(&model),
+            Some(UpdateStatus::Available {
+                latest: Version::new(1, 5, 0),
+                store_url: "https://store.example.com/app".to_string(),
+                release_notes: None,
+            })
+        );
+
+        // the next check waits for the interval
+        let Some(Effect::Time(mut request)) = update.effects.into_iter().next() else {
+            panic!("expected a Time effect");
+        };
+        assert_eq!(
+            request.operation,
+            TimeRequest::NotifyAfter(crux_time::Duration::from_secs(3600).unwrap())
+        );
+
+        let update = app
+            .resolve(&mut request, TimeResponse::DurationElapsed)
+            .unwrap();
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("expected an Http effect");
+        };
+        let update = app
+            .resolve(&mut request, HttpResult::Ok(manifest("2.0.0", "1.5.0")))
+            .unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+        assert!(model.status.as_ref().unwrap().is_required());
+
+        let update = app.update(Event::OpenStore, &mut model);
+        let Some(Effect::Open(mut request)) = update.into_effects().next() else {
+            panic!("expected an Open effect");
+        };
+        assert_eq!(
+            request.operation,
+            OpenRequest::url("https://store.example.com/app")
+        );
+
+        let update = app.resolve(&mut request, OpenResult::Opened).unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+        assert_eq!(model.store_opened, Some(OpenResult::Opened));
+    }
+
+    #[test]
+    fn failed_checks_are_reported() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(
+            Event::Start {
+                version: "1.4.0".to_string(),
+            },
+            &mut model,
+        );
+        let Some(Effect::Http(mut request)) = update.into_effects().next() else {
+            panic!("expected an Http effect");
+        };
+
+        let update = app
+            .resolve(
+                &mut request,
+                HttpResult::Ok(HttpResponse::status(404).build()),
+            )
+            .unwrap();
+        for event in update.events {
+            let _ = app.update(event, &mut model);
+        }
+
+        assert!(matches!(model.error, Some(UpdateError::Http { .. })));
+        assert_eq!(model.status, None);
+    }
+}
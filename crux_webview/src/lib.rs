@@ -0,0 +1,385 @@
+//! Hosting web content in the shell
+//!
+//! The [`WebView`] capability asks the shell to show a WebView with a page the core chose,
+//! either loaded from a URL or rendered from HTML the core produced, and bridges messages between
+//! the page and the core: messages the page posts (with `postMessage`) are streamed to the core,
+//! and the core can post messages back to the page. This lets hybrid screens, like a 3-D Secure
+//! payment challenge or an embedded editor, be orchestrated from the core.
+//!
+//! Messages are strings, which are usually JSON, see [`WebMessage::json`] and
+//! [`WebView::post_json`]. Messages from origins the [`WebPage`] doesn't allow are dropped in
+//! the core, and navigations to URLs the page [intercepts](WebPage::intercept) are reported to the
+//! core instead of being loaded, which is how a redirect back to the app is usually detected.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+/// What to show in the WebView
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum WebContent {
+    Url(String),
+    /// HTML produced by the core, with relative URLs resolved against the `base_url`
+    Html {
+        html: String,
+        base_url: Option<String>,
+    },
+}
+
+/// A page to open in a WebView
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebPage {
+    pub content: WebContent,
+    /// The origins messages are accepted from. Empty means messages are accepted from any origin.
+    pub allowed_origins: Vec<String>,
+    /// URL prefixes which are not loaded when the page navigates to them,
+    /// but reported to the core with [`WebViewEvent::Intercepted`]
+    pub intercept: Vec<String>,
+}
+
+impl WebPage {
+    /// A page loaded from the `url`, accepting messages from the URL's origin
+    pub fn url(url: impl Into<String>) -> Self {
+        let url = url.into();
+        let allowed_origins = origin(&url).map(str::to_string).into_iter().collect();
+
+        Self {
+            content: WebContent::Url(url),
+            allowed_origins,
+            intercept: Vec::new(),
+        }
+    }
+
+    /// A page rendered from the `html`, accepting messages from any origin
+    pub fn html(html: impl Into<String>) -> Self {
+        Self {
+            content: WebContent::Html {
+                html: html.into(),
+                base_url: None,
+            },
+            allowed_origins: Vec::new(),
+            intercept: Vec::new(),
+        }
+    }
+
+    /// Resolve relative URLs in the page's HTML against the `base_url`.
+    /// Has no effect on pages loaded from a URL.
+    #[must_use]
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        if let WebContent::Html { base_url: base, .. } = &mut self.content {
+            *base = Some(base_url.into());
+        }
+        self
+    }
+
+    /// Also accept messages from the `origin`, e.g. `https://example.com`
+    #[must_use]
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.allowed_origins.push(origin.into());
+        self
+    }
+
+    /// Report navigations to URLs starting with the `prefix` instead of loading them
+    #[must_use]
+    pub fn intercept(mut self, prefix: impl Into<String>) -> Self {
+        self.intercept.push(prefix.into());
+        self
+    }
+
+    fn allows(&self, message: &WebMessage) -> bool {
+        self.allowed_origins.is_empty()
+            || message
+                .origin
+                .as_ref()
+                .map_or(false, |origin| self.allowed_origins.contains(origin))
+    }
+}
+
+/// The origin of the `url`, i.e. its scheme, host and port, if it has one
+fn origin(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once("://")?;
+    if scheme.is_empty() {
+        return None;
+    }
+
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    if end == 0 {
+        return None;
+    }
+
+    Some(&url[..scheme.len() + 3 + end])
+}
+
+/// A message posted by the page
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebMessage {
+    /// The origin of the frame which posted the message, if known
+    pub origin: Option<String>,
+    pub data: String,
+}
+
+impl WebMessage {
+    /// Parse the message data as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, WebViewError> {
+        serde_json::from_str(&self.data).map_err(|e| WebViewError::Json {
+            message: e.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebViewOperation {
+    /// Open the page in a WebView, streaming its events until it closes
+    Open {
+        id: String,
+        page: WebPage,
+    },
+    /// Post a message to the page in the WebView
+    PostMessage {
+        id: String,
+        data: String,
+    },
+    Close {
+        id: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebViewResponse {
+    /// The page, or a page it navigated to, finished loading
+    Loaded {
+        url: String,
+    },
+    Message(WebMessage),
+    /// The page navigated to an intercepted URL, which was not loaded
+    Intercepted {
+        url: String,
+    },
+    /// The WebView was closed by the user or the core
+    Closed,
+    /// The shell can't show WebViews
+    Unavailable,
+    Error {
+        message: String,
+    },
+}
+
+impl Operation for WebViewOperation {
+    type Output = WebViewResponse;
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WebViewError {
+    #[error("WebViews are not available")]
+    Unavailable,
+    #[error("invalid JSON message: {message}")]
+    Json { message: String },
+    #[error("WebView failed: {message}")]
+    Shell { message: String },
+    #[error("unexpected response from the shell: {response:?}")]
+    UnexpectedResponse { response: Box<WebViewResponse> },
+}
+
+impl From<WebViewResponse> for WebViewError {
+    fn from(response: WebViewResponse) -> Self {
+        match response {
+            WebViewResponse::Unavailable => WebViewError::Unavailable,
+            WebViewResponse::Error { message } => WebViewError::Shell { message },
+            response => WebViewError::UnexpectedResponse {
+                response: Box::new(response),
+            },
+        }
+    }
+}
+
+/// What happened in an open WebView
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WebViewEvent {
+    Loaded {
+        url: String,
+    },
+    /// The page posted a message, from an allowed origin
+    Message(WebMessage),
+    /// The page navigated to an intercepted URL, which was not loaded
+    Intercepted {
+        url: String,
+    },
+    /// The WebView closed, and there will be no more events
+    Closed,
+    /// The WebView could not be opened, or failed, and there will be no more events
+    Failed(WebViewError),
+}
+
+/// The WebView capability API
+///
+/// This capability lets the app show web content in the shell, and exchange messages with it.
+#[derive(crux_core::macros::Capability)]
+pub struct WebView<Ev> {
+    context: CapabilityContext<WebViewOperation, Ev>,
+}
+
+impl<Ev> Clone for WebView<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> WebView<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<WebViewOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Open the page in a WebView identified by `id`, sending the event returned by `callback`
+    /// for everything that happens in it, until it closes or fails.
+    pub fn open<F>(&self, id: impl Into<String>, page: WebPage, callback: F)
+    where
+        F: Fn(WebViewEvent) -> Ev + Send + Sync + 'static,
+    {
+        let id = id.into();
+
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                let mut stream = context.stream_from_shell(WebViewOperation::Open {
+                    id,
+                    page: page.clone(),
+                });
+
+                while let Some(response) = stream.next().await {
+                    let event = match response {
+                        WebViewResponse::Loaded { url } => WebViewEvent::Loaded { url },
+                        WebViewResponse::Message(message) if page.allows(&message) => {
+                            WebViewEvent::Message(message)
+                        }
+                        WebViewResponse::Message(_) => continue,
+                        WebViewResponse::Intercepted { url } => WebViewEvent::Intercepted { url },
+                        WebViewResponse::Closed => WebViewEvent::Closed,
+                        response => WebViewEvent::Failed(response.into()),
+                    };
+                    let ended = matches!(event, WebViewEvent::Closed | WebViewEvent::Failed(_));
+
+                    context.update_app(callback(event));
+
+                    if ended {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Post the `data` to the page in the WebView identified by `id`
+    pub fn post_message(&self, id: impl Into<String>, data: impl Into<String>) {
+        self.notify(WebViewOperation::PostMessage {
+            id: id.into(),
+            data: data.into(),
+        });
+    }
+
+    /// Post the `message`, serialized as JSON, to the page in the WebView identified by `id`
+    pub fn post_json<T: Serialize>(
+        &self,
+        id: impl Into<String>,
+        message: &T,
+    ) -> Result<(), WebViewError> {
+        let data = serde_json::to_string(message).map_err(|e| WebViewError::Json {
+            message: e.to_string(),
+        })?;
+        self.post_message(id, data);
+
+        Ok(())
+    }
+
+    /// Close the WebView identified by `id`. Its callback is called with
+    /// [`WebViewEvent::Closed`] once the shell has closed it.
+    pub fn close(&self, id: impl Into<String>) {
+        self.notify(WebViewOperation::Close { id: id.into() });
+    }
+
+    fn notify(&self, operation: WebViewOperation) {
+        self.context.spawn({
+            let context = self.context.clone();
+
+            async move {
+                context.notify_shell(operation).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let operation = WebViewOperation::Open {
+            id: "editor".to_string(),
+            page: WebPage::html("<p>hi</p>").base_url("https://example.com/"),
+        };
+
+        let serialized = serde_json::to_string(&operation).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"open":{"id":"editor","page":{"content":{"html":{"html":"<p>hi</p>","baseUrl":"https://example.com/"}},"allowedOrigins":[],"intercept":[]}}}"#
+        );
+
+        let deserialized: WebViewOperation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, operation);
+
+        let response = WebViewResponse::Message(WebMessage {
+            origin: Some("https://example.com".to_string()),
+            data: r#"{"saved":true}"#.to_string(),
+        });
+        let serialized = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"message":{"origin":"https://example.com","data":"{\"saved\":true}"}}"#
+        );
+    }
+
+    #[test]
+    fn test_origins() {
+        assert_eq!(
+            origin("https://acs.bank.com:8443/challenge?id=1"),
+            Some("https://acs.bank.com:8443")
+        );
+        assert_eq!(origin("https://example.com"), Some("https://example.com"));
+        assert_eq!(
+            origin("https://example.com#top"),
+            Some("https://example.com")
+        );
+        assert_eq!(origin("about:blank"), None);
+        assert_eq!(origin("file:///index.html"), None);
+
+        let page = WebPage::url("https://acs.bank.com/challenge").allow_origin("https://cdn.com");
+        assert_eq!(
+            page.allowed_origins,
+            vec![
+                "https://acs.bank.com".to_string(),
+                "https://cdn.com".to_string()
+            ]
+        );
+
+        let message = |origin: Option<&str>| WebMessage {
+            origin: origin.map(str::to_string),
+            data: String::new(),
+        };
+        assert!(page.allows(&message(Some("https://cdn.com"))));
+        assert!(!page.allows(&message(Some("https://evil.com"))));
+        assert!(!page.allows(&message(None)));
+        assert!(WebPage::html("").allows(&message(None)));
+    }
+}
@@ -0,0 +1,270 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_webview::{WebPage, WebView, WebViewEvent};
+    use serde::{Deserialize, Serialize};
+
+    pub const RETURN_URL: &str = "coffee://3ds/complete";
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum EditorMessage {
+        Load { text: String },
+        Saved { text: String },
+    }
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Challenge(String),
+        Edit,
+
+        #[serde(skip)]
+        ChallengeUpdated(WebViewEvent),
+        #[serde(skip)]
+        EditorUpdated(WebViewEvent),
+    }
+
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub enum Challenge {
+        #[default]
+        None,
+        Showing,
+        Passed,
+        Failed,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub challenge: Challenge,
+        pub document: String,
+        pub editor_error: Option<String>,
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub web_view: WebView<Event>,
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = ();
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::Challenge(url) => {
+                    model.challenge = Challenge::Showing;
+                    caps.web_view.open(
+                        "3ds",
+                        WebPage::url(url).intercept(RETURN_URL),
+                        Event::ChallengeUpdated,
+                    );
+                }
+                Event::ChallengeUpdated(WebViewEvent::Intercepted { url }) => {
+                    model.challenge = if url.ends_with("?status=passed") {
+                        Challenge::Passed
+                    } else {
+                        Challenge::Failed
+                    };
+                    caps.web_view.close("3ds");
+                }
+                Event::ChallengeUpdated(WebViewEvent::Closed | WebViewEvent::Failed(_)) => {
+                    if model.challenge == Challenge::Showing {
+                        model.challenge = Challenge::Failed;
+                    }
+                }
+                Event::ChallengeUpdated(_) => {}
+                Event::Edit => caps.web_view.open(
+                    "editor",
+                    WebPage::html("<div id=editor></div>").base_url("https://cdn.example.com/"),
+                    Event::EditorUpdated,
+                ),
+                Event::EditorUpdated(WebViewEvent::Loaded { .. }) => {
+                    let message = EditorMessage::Load {
+                        text: model.document.clone(),
+                    };
+                    caps.web_view.post_json("editor", &message).unwrap();
+                }
+                Event::EditorUpdated(WebViewEvent::Message(message)) => match message.json() {
+                    Ok(EditorMessage::Saved { text }) => model.document = text,
+                    Ok(EditorMessage::Load { .. }) => {}
+                    Err(error) => model.editor_error = Some(error.to_string()),
+                },
+                Event::EditorUpdated(_) => {}
+            }
+        }
+
+        fn view(&self, _model: &Model) {}
+    }
+}
+
+mod tests {
+    use crux_core::{testing::AppTester, Request};
+    use crux_webview::{WebMessage, WebViewOperation, WebViewResponse};
+
+    use crate::shared::{App, Challenge, EditorMessage, Effect, Event, Model, RETURN_URL};
+
+    fn open(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        event: Event,
+    ) -> Request<WebViewOperation> {
+        let Some(Effect::WebView(request)) = app.update(event, model).into_effects().next() else {
+            panic!("expected a WebView effect");
+        };
+
+        request
+    }
+
+    fn respond(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        request: &mut Request<WebViewOperation>,
+        response: WebViewResponse,
+    ) -> Vec<WebViewOperation> {
+        let update = app.resolve(request, response).unwrap();
+
+        let mut operations = Vec::new();
+        for event in update.events {
+            for effect in app.update(event, model).into_effects() {
+                let Effect::WebView(request) = effect;
+                operations.push(request.operation);
+            }
+        }
+
+        operations
+    }
+
+    fn message(origin: &str, data: &str) -> WebViewResponse {
+        WebViewResponse::Message(WebMessage {
+            origin: Some(origin.to_string()),
+            data: data.to_string(),
+        })
+    }
+
+    #[test]
+    fn completes_a_payment_challenge() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut request = open(
+            &app,
+            &mut model,
+            Event::Challenge("https://acs.bank.com/challenge?id=42".to_string()),
+        );
+        let WebViewOperation::Open { id, page } = &request.operation else {
+            panic!("expected to open a WebView");
+        };
+        assert_eq!(id, "3ds");
+        assert_eq!(page.intercept, vec![RETURN_URL.to_string()]);
+        assert_eq!(model.challenge, Challenge::Showing);
+
+        let loaded = WebViewResponse::Loaded {
+            url: "https://acs.bank.com/challenge?id=42".to_string(),
+        };
+        assert!(respond(&app, &mut model, &mut request, loaded).is_empty());
+
+        let operations = respond(
+            &app,
+            &mut model,
+            &mut request,
+            WebViewResponse::Intercepted {
+                url: format!("{RETURN_URL}?status=passed"),
+            },
+        );
+        assert_eq!(
+            operations,
+            vec![WebViewOperation::Close {
+                id: "3ds".to_string()
+            }]
+        );
+        assert_eq!(model.challenge, Challenge::Passed);
+
+        respond(&app, &mut model, &mut request, WebViewResponse::Closed);
+        assert_eq!(model.challenge, Challenge::Passed);
+
+        // the stream has ended
+        assert!(app.resolve(&mut request, WebViewResponse::Closed).is_err());
+    }
+
+    #[test]
+    fn a_dismissed_challenge_fails() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut request = open(
+            &app,
+            &mut model,
+            Event::Challenge("https://acs.bank.com/challenge".to_string()),
+        );
+        respond(&app, &mut model, &mut request, WebViewResponse::Closed);
+
+        assert_eq!(model.challenge, Challenge::Failed);
+    }
+
+    #[test]
+    fn exchanges_messages_with_an_editor() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model {
+            document: "Hello".to_string(),
+            ..Model::default()
+        };
+
+        let mut request = open(&app, &mut model, Event::Edit);
+
+        let operations = respond(
+            &app,
+            &mut model,
+            &mut request,
+            WebViewResponse::Loaded {
+                url: "https://cdn.example.com/".to_string(),
+            },
+        );
+        let [WebViewOperation::PostMessage { id, data }] = &operations[..] else {
+            panic!("expected to post a message");
+        };
+        assert_eq!(id, "editor");
+        assert_eq!(
+            serde_json::from_str::<EditorMessage>(data).unwrap(),
+            EditorMessage::Load {
+                text: "Hello".to_string()
+            }
+        );
+
+        respond(
+            &app,
+            &mut model,
+            &mut request,
+            message("null", r#"{"saved":{"text":"Hello, world"}}"#),
+        );
+        assert_eq!(model.document, "Hello, world");
+
+        respond(&app, &mut model, &mut request, message("null", "not json"));
+        assert!(model.editor_error.is_some());
+    }
+
+    #[test]
+    fn drops_messages_from_other_origins() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let mut request = open(
+            &app,
+            &mut model,
+            Event::Challenge("https://acs.bank.com/challenge".to_string()),
+        );
+
+        let update = app
+            .resolve(&mut request, message("https://evil.com", "{}"))
+            .unwrap();
+        assert!(update.events.is_empty());
+
+        let update = app
+            .resolve(&mut request, message("https://acs.bank.com", "{}"))
+            .unwrap();
+        assert_eq!(update.events.len(), 1);
+    }
+}
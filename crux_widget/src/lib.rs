@@ -0,0 +1,209 @@
+//! Data for home screen widgets and watch complications
+//!
+//! Widgets and complications are rendered by separate extensions, which usually can't run the
+//! core. The [`Widget`] capability lets the core publish a small serialized snapshot of the data
+//! each kind of widget shows, whenever that data changes, so the widget content is derived
+//! by the same logic as the rest of the app. Each snapshot carries a [`Refresh`] hint, which the
+//! shell passes on to the system to schedule the next time the widget should be reloaded.
+
+use crux_core::capability::{CapabilityContext, Operation};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+/// When the system should next refresh a widget. This is only a hint, the system
+/// may refresh widgets more or less often depending on its budget.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Refresh {
+    /// Only refresh when a new snapshot is published
+    #[default]
+    Never,
+    /// Refresh after the given number of seconds
+    After { seconds: u64 },
+    /// Refresh at the given time, in seconds since the UNIX epoch
+    At { epoch_seconds: u64 },
+}
+
+/// A snapshot of the data shown by one kind of widget.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WidgetSnapshot {
+    /// The kind of widget the data is for, as registered by the shell's widget extension
+    pub kind: String,
+    /// The data, serialized as JSON
+    pub data: Vec<u8>,
+    pub refresh: Refresh,
+}
+
+impl WidgetSnapshot {
+    /// Create a snapshot of `data` for widgets of `kind`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` can't be serialized as JSON.
+    pub fn new<T>(kind: impl Into<String>, data: &T, refresh: Refresh) -> Result<Self, WidgetError>
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_vec(data).map_err(|e| WidgetError::Serialization {
+            message: e.to_string(),
+        })?;
+
+        Ok(Self {
+            kind: kind.into(),
+            data,
+            refresh,
+        })
+    }
+
+    /// Decode the snapshot's data, mainly useful in tests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data can't be deserialized as `T`.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, WidgetError> {
+        serde_json::from_slice(&self.data).map_err(|e| WidgetError::Serialization {
+            message: e.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WidgetOperation {
+    /// Store the snapshot for the widget extension and ask the system to reload the widgets
+    Publish(WidgetSnapshot),
+    /// Remove the stored snapshot of a kind of widget, e.g. when the user logs out
+    Clear { kind: String },
+}
+
+impl Operation for WidgetOperation {
+    type Output = ();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WidgetError {
+    #[error("widget data could not be serialized: {message}")]
+    Serialization { message: String },
+}
+
+/// The Widget capability API
+///
+/// This capability lets the core publish data snapshots for home screen widgets
+/// and watch complications.
+#[derive(crux_core::macros::Capability)]
+pub struct Widget<Ev> {
+    context: CapabilityContext<WidgetOperation, Ev>,
+}
+
+impl<Ev> Clone for Widget<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Widget<Ev>
+where
+    Ev: 'static,
+{
+    pub fn new(context: CapabilityContext<WidgetOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Publish `data` for widgets of `kind`, replacing the previous snapshot. The shell will
+    /// ask the system to reload the widgets, and schedule the next refresh according to `refresh`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` can't be serialized, in which case nothing is published.
+    pub fn publish<T>(
+        &self,
+        kind: impl Into<String>,
+        data: &T,
+        refresh: Refresh,
+    ) -> Result<(), WidgetError>
+    where
+        T: Serialize,
+    {
+        let snapshot = WidgetSnapshot::new(kind, data, refresh)?;
+
+        self.context.spawn({
+            let this = self.clone();
+
+            async move {
+                this.publish_snapshot_async(snapshot).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Publish a snapshot, replacing the previous snapshot of the same kind.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn publish_snapshot_async(&self, snapshot: WidgetSnapshot) {
+        self.context
+            .notify_shell(WidgetOperation::Publish(snapshot))
+            .await;
+    }
+
+    /// Remove the snapshot for widgets of `kind`.
+    pub fn clear(&self, kind: impl Into<String>) {
+        self.context.spawn({
+            let this = self.clone();
+            let kind = kind.into();
+
+            async move {
+                this.clear_async(kind).await;
+            }
+        });
+    }
+
+    /// Remove the snapshot for widgets of `kind`.
+    /// This is an async call to use with [`crux_core::compose::Compose`].
+    pub async fn clear_async(&self, kind: impl Into<String>) {
+        self.context
+            .notify_shell(WidgetOperation::Clear { kind: kind.into() })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_serializing_the_types_as_json() {
+        let snapshot =
+            WidgetSnapshot::new("balance", &42, Refresh::After { seconds: 900 }).unwrap();
+
+        let serialized = serde_json::to_string(&WidgetOperation::Publish(snapshot)).unwrap();
+        assert_eq!(
+            &serialized,
+            r#"{"publish":{"kind":"balance","data":[52,50],"refresh":{"after":{"seconds":900}}}}"#
+        );
+
+        let deserialized: WidgetOperation = serde_json::from_str(&serialized).unwrap();
+        let WidgetOperation::Publish(snapshot) = deserialized else {
+            panic!("expected a publish operation");
+        };
+        assert_eq!(snapshot.decode::<u32>().unwrap(), 42);
+
+        let serialized = serde_json::to_string(&Refresh::At { epoch_seconds: 60 }).unwrap();
+        assert_eq!(&serialized, r#"{"at":{"epochSeconds":60}}"#);
+    }
+
+    #[test]
+    fn unserializable_data_is_an_error() {
+        let data = HashMap::from([((1, 2), "not a string key")]);
+
+        assert!(matches!(
+            WidgetSnapshot::new("map", &data, Refresh::Never),
+            Err(WidgetError::Serialization { .. })
+        ));
+    }
+}
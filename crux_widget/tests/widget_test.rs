@@ -0,0 +1,141 @@
+mod shared {
+    use crux_core::macros::Effect;
+    use crux_core::render::Render;
+    use crux_widget::{Refresh, Widget};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default)]
+    pub struct App;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        AddTask(String),
+        CompleteTask(usize),
+        LogOut,
+    }
+
+    #[derive(Default)]
+    pub struct Model {
+        pub tasks: Vec<String>,
+    }
+
+    /// The data shown by the "next task" widget
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    pub struct NextTask {
+        pub title: Option<String>,
+        pub remaining: usize,
+    }
+
+    // the same logic drives the app's view and the widget
+    fn next_task(model: &Model) -> NextTask {
+        NextTask {
+            title: model.tasks.first().cloned(),
+            remaining: model.tasks.len(),
+        }
+    }
+
+    impl crux_core::App for App {
+        type Event = Event;
+        type Model = Model;
+        type ViewModel = NextTask;
+        type Capabilities = Capabilities;
+
+        fn update(&self, event: Event, model: &mut Model, caps: &Capabilities) {
+            match event {
+                Event::AddTask(task) => model.tasks.push(task),
+                Event::CompleteTask(index) => {
+                    model.tasks.remove(index);
+                }
+                Event::LogOut => {
+                    model.tasks.clear();
+                    caps.widget.clear("next_task");
+                    caps.render.render();
+                    return;
+                }
+            }
+
+            caps.widget
+                .publish("next_task", &next_task(model), Refresh::Never)
+                .expect("NextTask serializes");
+            caps.render.render();
+        }
+
+        fn view(&self, model: &Model) -> NextTask {
+            next_task(model)
+        }
+    }
+
+    #[derive(Effect)]
+    pub struct Capabilities {
+        pub widget: Widget<Event>,
+        pub render: Render<Event>,
+    }
+}
+
+mod tests {
+    use crux_core::testing::AppTester;
+    use crux_widget::{Refresh, WidgetOperation, WidgetSnapshot};
+
+    use crate::shared::{App, Effect, Event, Model, NextTask};
+
+    fn widget_operations(effects: impl Iterator<Item = Effect>) -> Vec<WidgetOperation> {
+        effects
+            .filter_map(|effect| match effect {
+                Effect::Widget(request) => Some(request.operation),
+                Effect::Render(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn publishes_a_snapshot_when_the_data_changes() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::AddTask("water plants".to_string()), &mut model);
+        let _ = app.update(Event::AddTask("buy milk".to_string()), &mut model);
+        let operations = widget_operations(update.into_effects());
+
+        let [WidgetOperation::Publish(snapshot)] = &operations[..] else {
+            panic!("expected a single publish operation, got {operations:?}");
+        };
+        assert_eq!(snapshot.kind, "next_task");
+        assert_eq!(snapshot.refresh, Refresh::Never);
+        assert_eq!(
+            snapshot.decode::<NextTask>().unwrap(),
+            NextTask {
+                title: Some("water plants".to_string()),
+                remaining: 1
+            }
+        );
+
+        let update = app.update(Event::CompleteTask(0), &mut model);
+        let operations = widget_operations(update.into_effects());
+
+        let expected = WidgetSnapshot::new(
+            "next_task",
+            &NextTask {
+                title: Some("buy milk".to_string()),
+                remaining: 1,
+            },
+            Refresh::Never,
+        )
+        .unwrap();
+        assert_eq!(operations, vec![WidgetOperation::Publish(expected)]);
+    }
+
+    #[test]
+    fn clears_the_snapshot_on_log_out() {
+        let app = AppTester::<App, _>::default();
+        let mut model = Model::default();
+
+        let update = app.update(Event::LogOut, &mut model);
+
+        assert_eq!(
+            widget_operations(update.into_effects()),
+            vec![WidgetOperation::Clear {
+                kind: "next_task".to_string()
+            }]
+        );
+    }
+}
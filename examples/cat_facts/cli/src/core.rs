@@ -67,7 +67,7 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Arc<Sender<Effect>>) ->
                     async move {
                         let response = match read_state(&key).await {
                             Ok(value) => KeyValueResult::Ok {
-                                response: KeyValueResponse::Get { value },
+                                response: KeyValueResponse::Get { value, version: 1 },
                             },
                             Err(err) => KeyValueResult::Err {
                                 error: KeyValueError::Io {
@@ -84,7 +84,9 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Arc<Sender<Effect>>) ->
                 });
             }
 
-            KeyValueOperation::Set { ref key, ref value } => {
+            KeyValueOperation::Set {
+                ref key, ref value, ..
+            } => {
                 spawn({
                     let core = core.clone();
                     let tx = tx.clone();
@@ -94,7 +96,10 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Arc<Sender<Effect>>) ->
                     async move {
                         let response = match write_state(&key, &value).await {
                             Ok(()) => KeyValueResult::Ok {
-                                response: KeyValueResponse::Set { previous: vec![] },
+                                response: KeyValueResponse::Set {
+                                    previous: vec![],
+                                    version: 1,
+                                },
                             },
                             Err(err) => KeyValueResult::Err {
                                 error: KeyValueError::Io {
@@ -116,6 +121,8 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Arc<Sender<Effect>>) ->
                 prefix: _,
                 cursor: _,
             } => unimplemented!("list_keys"),
+            KeyValueOperation::Batch { operations: _ } => unimplemented!("batch"),
+            KeyValueOperation::Stats { prefix: _ } => unimplemented!("stats"),
         },
 
         Effect::Platform(mut request) => {
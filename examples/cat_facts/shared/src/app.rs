@@ -1,10 +1,16 @@
 pub mod platform;
 
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub use crux_core::App;
-use crux_core::{render::Render, Capability};
+use crux_core::{
+    migrations::{Migrated, Migrations},
+    render::Render,
+    Capability,
+};
 use crux_http::Http;
 use crux_kv::{error::KeyValueError, KeyValue};
 use crux_platform::Platform;
@@ -29,7 +35,7 @@ impl CatFact {
     }
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
 pub struct Model {
     cat_fact: Option<CatFact>,
     cat_image: Option<CatImage>,
@@ -71,7 +77,7 @@ pub enum Event {
     #[serde(skip)]
     Platform(platform::Event),
     #[serde(skip)]
-    SetState(Result<Vec<u8>, KeyValueError>), // receive the data to restore state with
+    SetState(Result<Option<Migrated<Model>>, KeyValueError>), // receive the state to restore
     #[serde(skip)]
     CurrentTime(TimeResponse),
     #[serde(skip)]
@@ -80,6 +86,12 @@ pub enum Event {
     SetImage(crux_http::Result<crux_http::Response<CatImage>>),
 }
 
+// the model is persisted in a version envelope, so that it can change shape in later versions
+fn migrations() -> Migrations<Model> {
+    // version 0 is the model persisted before versioning, which has the same shape
+    Migrations::new(1).register(0, |model: Model| model)
+}
+
 #[derive(Default)]
 pub struct CatFacts {
     platform: platform::App,
@@ -122,7 +134,7 @@ impl App for CatFacts {
             Event::Clear => {
                 model.cat_fact = None;
                 model.cat_image = None;
-                let bytes = serde_json::to_vec(&model).unwrap();
+                let bytes = migrations().persist(model).unwrap();
 
                 caps.key_value.set(KEY.to_string(), bytes, |_| Event::None);
                 caps.render.render();
@@ -152,7 +164,7 @@ impl App for CatFacts {
             Event::SetFact(Ok(mut response)) => {
                 model.cat_fact = Some(response.take_body().unwrap());
 
-                let bytes = serde_json::to_vec(&model).unwrap();
+                let bytes = migrations().persist(model).unwrap();
                 caps.key_value.set(KEY.to_string(), bytes, |_| Event::None);
 
                 caps.time.now(Event::CurrentTime);
@@ -160,7 +172,7 @@ impl App for CatFacts {
             Event::SetImage(Ok(mut response)) => {
                 model.cat_image = Some(response.take_body().unwrap());
 
-                let bytes = serde_json::to_vec(&model).unwrap();
+                let bytes = migrations().persist(model).unwrap();
                 caps.key_value.set(KEY.to_string(), bytes, |_| Event::None);
 
                 caps.render.render();
@@ -171,21 +183,21 @@ impl App for CatFacts {
             Event::CurrentTime(TimeResponse::Now(instant)) => {
                 let time: DateTime<Utc> = instant.try_into().unwrap();
                 model.time = Some(time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
-                let bytes = serde_json::to_vec(&model).unwrap();
+                let bytes = migrations().persist(model).unwrap();
                 caps.key_value.set(KEY.to_string(), bytes, |_| Event::None);
 
                 caps.render.render();
             }
             Event::CurrentTime(_) => panic!("Unexpected time response"),
             Event::Restore => {
-                caps.key_value.get(KEY.to_string(), Event::SetState);
+                caps.key_value
+                    .restore(KEY.to_string(), Arc::new(migrations()), Event::SetState);
             }
-            Event::SetState(Ok(value)) => {
-                if let Ok(m) = serde_json::from_slice::<Model>(&value) {
-                    *model = m;
-                    caps.render.render();
-                };
+            Event::SetState(Ok(Some(restored))) => {
+                *model = restored.model;
+                caps.render.render();
             }
+            Event::SetState(Ok(None)) => {}
             Event::SetState(Err(_)) => {
                 // handle error
             }
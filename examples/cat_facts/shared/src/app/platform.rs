@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Default)]
 pub struct App {}
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct Model {
     pub platform: String,
 }
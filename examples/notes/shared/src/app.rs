@@ -569,7 +569,10 @@ mod save_load_tests {
 
         // Read was successful
         let response = KeyValueResult::Ok {
-            response: KeyValueResponse::Get { value: note.save() },
+            response: KeyValueResponse::Get {
+                value: note.save(),
+                version: 1,
+            },
         };
         let update = app.resolve(&mut request, response).unwrap();
         assert_eq!(update.events.len(), 1);
@@ -609,7 +612,10 @@ mod save_load_tests {
             .resolve(
                 &mut request,
                 KeyValueResult::Ok {
-                    response: KeyValueResponse::Get { value: vec![] },
+                    response: KeyValueResponse::Get {
+                        value: vec![],
+                        version: 0,
+                    },
                 },
             )
             .unwrap();
@@ -622,7 +628,7 @@ mod save_load_tests {
                 .find_map(Effect::into_key_value)
                 .unwrap();
 
-            assert_let!(KeyValueOperation::Set { key, value: _ }, &save.operation);
+            assert_let!(KeyValueOperation::Set { key, .. }, &save.operation);
             assert_eq!(key, "note");
         }
     }
@@ -748,7 +754,7 @@ mod save_load_tests {
             .unwrap();
 
         assert_let!(
-            KeyValueOperation::Set { key, value },
+            KeyValueOperation::Set { key, value, .. },
             &write_request.operation
         );
 
@@ -164,7 +164,11 @@ export class Core {
             this.respond(
               uuid,
               new KeyValueResultVariantOk(
-                new KeyValueResponseVariantGet(bytes || []),
+                // versions aren't tracked, the app only overwrites
+                new KeyValueResponseVariantGet(
+                  bytes || [],
+                  BigInt(bytes == null ? 0 : 1),
+                ),
               ),
             );
 
@@ -181,7 +185,9 @@ export class Core {
 
             this.respond(
               uuid,
-              new KeyValueResultVariantOk(new KeyValueResponseVariantSet([])),
+              new KeyValueResultVariantOk(
+                new KeyValueResponseVariantSet([], BigInt(1)),
+              ),
             );
 
             break;